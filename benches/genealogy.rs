@@ -0,0 +1,84 @@
+//! Parse and query benchmarks over synthetic maps, see `genealogy::bench`.
+//!
+//! Run with `cargo bench --features bench`.
+
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput};
+use genealogy::{
+    bench::{elf, gnu_mapfile, SUBSECTION_SIZE, TEXT_OFFSET, TEXT_VADDR},
+    Genealogy,
+};
+
+const SIZES: [u64; 3] = [1_000, 100_000, 5_000_000];
+
+fn parse(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parse");
+    group.sample_size(10);
+    for intervals in SIZES {
+        let mapfile = gnu_mapfile(intervals);
+        let binary = elf(intervals);
+        group.throughput(Throughput::Bytes(mapfile.len() as u64));
+        group.bench_with_input(
+            BenchmarkId::from_parameter(intervals),
+            &(mapfile, binary),
+            |b, (mapfile, binary)| b.iter(|| Genealogy::new(mapfile, binary).unwrap()),
+        );
+    }
+    group.finish();
+}
+
+fn query(c: &mut Criterion) {
+    let mut group = c.benchmark_group("query");
+    for intervals in SIZES {
+        let genealogy = Genealogy::new(&gnu_mapfile(intervals), &elf(intervals)).unwrap();
+        let span = intervals * SUBSECTION_SIZE;
+        // A cheap pseudo-random walk, so that lookups do not hit the same path of the tree
+        let mut state = 0x9e37_79b9_7f4a_7c15u64;
+        let mut next_offset = move || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state % span
+        };
+
+        group.bench_function(BenchmarkId::new("point", intervals), |b| {
+            b.iter_batched(
+                &mut next_offset,
+                |offset| genealogy.query_point(TEXT_OFFSET + offset).count(),
+                BatchSize::SmallInput,
+            )
+        });
+        group.bench_function(BenchmarkId::new("vaddr_point", intervals), |b| {
+            b.iter_batched(
+                &mut next_offset,
+                |offset| genealogy.query_vaddr_point(TEXT_VADDR + offset).count(),
+                BatchSize::SmallInput,
+            )
+        });
+        group.bench_function(BenchmarkId::new("range_4k", intervals), |b| {
+            b.iter_batched(
+                &mut next_offset,
+                |offset| {
+                    let start = TEXT_OFFSET + offset;
+                    genealogy.query(start..start + 0x1000).count()
+                },
+                BatchSize::SmallInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+fn report(c: &mut Criterion) {
+    let mut group = c.benchmark_group("report");
+    group.sample_size(10);
+    for intervals in SIZES {
+        let genealogy = Genealogy::new(&gnu_mapfile(intervals), &elf(intervals)).unwrap();
+        group.bench_function(BenchmarkId::from_parameter(intervals), |b| {
+            b.iter(|| genealogy.report())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, parse, query, report);
+criterion_main!(benches);
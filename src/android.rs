@@ -0,0 +1,402 @@
+//! Android apps: the native libraries of APKs and Android App Bundles, `lib/<abi>/*.so` in APKs
+//! and `<module>/lib/<abi>/*.so` in bundles, paired with the maps of their links to tell what
+//! every object adds to the native code of the app.
+
+use std::{borrow::Cow, collections::HashMap, fmt::Display, ops::Range};
+
+use crate::{
+    format::{human_size, Align, Cell, Style, Table},
+    Genealogy, GenealogyError,
+};
+
+/// The ABIs of the NDK, old ones included, as named in `lib/<abi>/`.
+pub const ABIS: [&str; 8] = [
+    "arm64-v8a",
+    "armeabi-v7a",
+    "armeabi",
+    "x86_64",
+    "x86",
+    "riscv64",
+    "mips64",
+    "mips",
+];
+
+const LOCAL_HEADER: u32 = 0x0403_4b50;
+const CENTRAL_HEADER: u32 = 0x0201_4b50;
+const END_OF_CENTRAL_DIRECTORY: u32 = 0x0605_4b50;
+
+const STORED: u16 = 0;
+const DEFLATED: u16 = 8;
+
+/// A file of a ZIP archive, APKs and bundles being ones.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ZipEntry {
+    pub name: String,
+    /// The compression method, 0 when stored and 8 when deflated.
+    pub method: u16,
+    /// The size of the file once extracted.
+    pub size: u64,
+    /// The offsets of its (compressed) data in the archive.
+    pub data: Range<u64>,
+}
+
+impl ZipEntry {
+    /// The bytes the file takes in the archive, without its headers.
+    pub fn stored_size(&self) -> u64 {
+        self.data.end - self.data.start
+    }
+}
+
+/// The files of the ZIP archive `archive`, in central directory order. ZIP64 archives are not
+/// supported.
+pub fn zip_entries(archive: &[u8]) -> Result<Vec<ZipEntry>, GenealogyError> {
+    let u16_at = |offset: usize| -> Result<u16, GenealogyError> {
+        archive
+            .get(offset..offset + 2)
+            .map(|bytes| u16::from_le_bytes(bytes.try_into().expect("2 bytes")))
+            .ok_or(GenealogyError::WrongArchiveFormat)
+    };
+    let u32_at = |offset: usize| -> Result<u32, GenealogyError> {
+        archive
+            .get(offset..offset + 4)
+            .map(|bytes| u32::from_le_bytes(bytes.try_into().expect("4 bytes")))
+            .ok_or(GenealogyError::WrongArchiveFormat)
+    };
+
+    // The end of central directory record is followed by a comment of up to 64K
+    let lowest = archive.len().saturating_sub(22 + 0xffff);
+    let end = (lowest..archive.len().saturating_sub(21))
+        .rev()
+        .find(|&offset| u32_at(offset).is_ok_and(|magic| magic == END_OF_CENTRAL_DIRECTORY))
+        .ok_or(GenealogyError::WrongArchiveFormat)?;
+    let count = u16_at(end + 10)?;
+    let mut offset = u32_at(end + 16)? as usize;
+
+    let mut entries = vec![];
+    for _ in 0..count {
+        if u32_at(offset)? != CENTRAL_HEADER {
+            return Err(GenealogyError::WrongArchiveFormat);
+        }
+        let method = u16_at(offset + 10)?;
+        let compressed_size = u32_at(offset + 20)?;
+        let size = u32_at(offset + 24)?;
+        let name_len = u16_at(offset + 28)? as usize;
+        let extra_len = u16_at(offset + 30)? as usize;
+        let comment_len = u16_at(offset + 32)? as usize;
+        let local = u32_at(offset + 42)? as usize;
+        if [compressed_size, size, local as u32].contains(&u32::MAX) {
+            return Err(GenealogyError::WrongArchiveFormat);
+        }
+        let name = archive
+            .get(offset + 46..offset + 46 + name_len)
+            .ok_or(GenealogyError::WrongArchiveFormat)?;
+
+        // The extra field of the local header may differ, e.g. with the alignment `zipalign`
+        // pads it with
+        if u32_at(local)? != LOCAL_HEADER {
+            return Err(GenealogyError::WrongArchiveFormat);
+        }
+        let start =
+            (local + 30 + u16_at(local + 26)? as usize + u16_at(local + 28)? as usize) as u64;
+        let data = start..start + u64::from(compressed_size);
+        if data.end > archive.len() as u64 {
+            return Err(GenealogyError::WrongArchiveFormat);
+        }
+        entries.push(ZipEntry {
+            name: String::from_utf8_lossy(name).into_owned(),
+            method,
+            size: u64::from(size),
+            data,
+        });
+        offset += 46 + name_len + extra_len + comment_len;
+    }
+    Ok(entries)
+}
+
+/// The contents of `entry`, borrowed from `archive` when stored. Deflated entries need the
+/// `compression` feature.
+pub fn extract<'a>(archive: &'a [u8], entry: &ZipEntry) -> Result<Cow<'a, [u8]>, GenealogyError> {
+    let data = archive
+        .get(entry.data.start as usize..entry.data.end as usize)
+        .ok_or(GenealogyError::WrongArchiveFormat)?;
+    match entry.method {
+        STORED => Ok(Cow::Borrowed(data)),
+        #[cfg(feature = "compression")]
+        DEFLATED => {
+            use std::io::Read;
+
+            let mut contents = Vec::with_capacity(entry.size as usize);
+            flate2::read::DeflateDecoder::new(data)
+                .read_to_end(&mut contents)
+                .map_err(|_| GenealogyError::WrongArchiveFormat)?;
+            Ok(Cow::Owned(contents))
+        }
+        #[cfg(not(feature = "compression"))]
+        DEFLATED => Err(GenealogyError::CompressedArchiveEntry(entry.name.clone())),
+        _ => Err(GenealogyError::WrongArchiveFormat),
+    }
+}
+
+/// A shared library of an APK or of a bundle.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NativeLibrary {
+    /// The module of the bundle holding it, e.g. `base`, `None` in APKs.
+    pub module: Option<String>,
+    pub abi: String,
+    /// The file name, e.g. `libfoo.so`.
+    pub name: String,
+    pub entry: ZipEntry,
+}
+
+impl NativeLibrary {
+    /// Whether `path` names the map of this library: `libfoo.so.map` or `libfoo.map`, in a
+    /// directory named after the ABI when any of its directories is.
+    pub fn matches_map(&self, path: &str) -> bool {
+        let mut components: Vec<&str> = path.split(['/', '\\']).collect();
+        let file_name = components.pop().unwrap_or_default();
+        let Some(stem) = file_name.strip_suffix(".map") else {
+            return false;
+        };
+        let abi_matches = match components.iter().rev().find(|dir| ABIS.contains(dir)) {
+            Some(abi) => *abi == self.abi,
+            None => true,
+        };
+        abi_matches && (stem == self.name || self.name.strip_suffix(".so") == Some(stem))
+    }
+}
+
+/// The native libraries of the APK or bundle `archive`, in archive order.
+pub fn native_libraries(archive: &[u8]) -> Result<Vec<NativeLibrary>, GenealogyError> {
+    let mut libraries = vec![];
+    for entry in zip_entries(archive)? {
+        let parts: Vec<&str> = entry.name.split('/').collect();
+        let (module, abi, name) = match parts.as_slice() {
+            ["lib", abi, name] => (None, *abi, *name),
+            [module, "lib", abi, name] => (Some(module.to_string()), *abi, *name),
+            _ => continue,
+        };
+        if name.ends_with(".so") {
+            libraries.push(NativeLibrary {
+                module,
+                abi: abi.to_string(),
+                name: name.to_string(),
+                entry,
+            });
+        }
+    }
+    Ok(libraries)
+}
+
+/// The native code of an app per library and object, see [`app_report`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AppReport {
+    /// Biggest libraries first.
+    pub libraries: Vec<LibraryEntry>,
+    /// The objects of the libraries with a map, biggest first.
+    pub rows: Vec<AppRow>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LibraryEntry {
+    /// The path in the archive, e.g. `lib/arm64-v8a/libfoo.so`.
+    pub library: String,
+    pub abi: String,
+    /// The size of the library once extracted.
+    pub size: u64,
+    /// The bytes it takes in the archive, less than `size` when deflated.
+    pub stored: u64,
+    /// The bytes attributed to objects by its map, `None` without one.
+    pub attributed: Option<u64>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AppRow {
+    /// The path of the library in the archive.
+    pub library: String,
+    pub filename: String,
+    /// The bytes of the object in the library file.
+    pub size: u64,
+}
+
+/// Attributes the bytes of every library of an app, with the genealogy built from its map when
+/// there is one. Only the bytes in the library files count, not `.bss`.
+pub fn app_report(libraries: &[(&NativeLibrary, Option<&Genealogy>)]) -> AppReport {
+    let mut entries = vec![];
+    let mut rows = vec![];
+    for &(library, genealogy) in libraries {
+        let attributed = genealogy.map(|genealogy| {
+            let mut files: HashMap<&str, u64> = HashMap::new();
+            for hit in genealogy.iter() {
+                *files.entry(hit.filename).or_default() += hit.range.end - hit.range.start;
+            }
+            let attributed = files.values().sum();
+            rows.extend(files.into_iter().map(|(filename, size)| AppRow {
+                library: library.entry.name.clone(),
+                filename: filename.to_string(),
+                size,
+            }));
+            attributed
+        });
+        entries.push(LibraryEntry {
+            library: library.entry.name.clone(),
+            abi: library.abi.clone(),
+            size: library.entry.size,
+            stored: library.entry.stored_size(),
+            attributed,
+        });
+    }
+
+    entries.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.library.cmp(&b.library)));
+    rows.retain(|row| row.size > 0);
+    rows.sort_by(|a, b| {
+        b.size
+            .cmp(&a.size)
+            .then_with(|| a.library.cmp(&b.library))
+            .then_with(|| a.filename.cmp(&b.filename))
+    });
+    AppReport {
+        libraries: entries,
+        rows,
+    }
+}
+
+impl AppReport {
+    /// The table rendered by `Display`, one row per object of every library.
+    pub fn to_table(&self) -> Table {
+        let mut table = Table::new(&[Align::Right, Align::Left, Align::Left])
+            .with_header(["size", "library", "file"]);
+        for row in &self.rows {
+            table.push_row([
+                human_size(row.size),
+                row.library.clone(),
+                row.filename.clone(),
+            ]);
+        }
+        let total: u64 = self.rows.iter().map(|row| row.size).sum();
+        let mapped = self
+            .libraries
+            .iter()
+            .filter(|library| library.attributed.is_some())
+            .count();
+        table.push_row([
+            Cell::styled(human_size(total), Some(Style::Bold)),
+            format!("{mapped} libraries").into(),
+            format!("total in {} files", self.rows.len()).into(),
+        ]);
+        table
+    }
+
+    /// The table of the libraries, mapped or not.
+    pub fn libraries_table(&self) -> Table {
+        let mut table = Table::new(&[
+            Align::Right,
+            Align::Right,
+            Align::Right,
+            Align::Left,
+            Align::Left,
+        ])
+        .with_header(["size", "stored", "attributed", "abi", "library"]);
+        for library in &self.libraries {
+            table.push_row([
+                human_size(library.size),
+                human_size(library.stored),
+                library.attributed.map_or("-".to_string(), human_size),
+                library.abi.clone(),
+                library.library.clone(),
+            ]);
+        }
+        let size: u64 = self.libraries.iter().map(|library| library.size).sum();
+        let stored: u64 = self.libraries.iter().map(|library| library.stored).sum();
+        let attributed: u64 = self
+            .libraries
+            .iter()
+            .filter_map(|library| library.attributed)
+            .sum();
+        table.push_row([
+            Cell::styled(human_size(size), Some(Style::Bold)),
+            Cell::styled(human_size(stored), Some(Style::Bold)),
+            Cell::styled(human_size(attributed), Some(Style::Bold)),
+            Cell::from(""),
+            format!("total in {} libraries", self.libraries.len()).into(),
+        ]);
+        table
+    }
+}
+
+impl Display for AppReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_table())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Genealogy, GenealogyError};
+
+    use super::{app_report, extract, native_libraries, zip_entries};
+
+    #[test]
+    fn test_android_app() {
+        let apk = std::fs::read("tests/android/app.apk").unwrap();
+        let libraries = native_libraries(&apk).unwrap();
+        let names: Vec<(Option<&str>, &str, &str)> = libraries
+            .iter()
+            .map(|library| {
+                (
+                    library.module.as_deref(),
+                    library.abi.as_str(),
+                    library.name.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            names,
+            [
+                (None, "x86_64", "libcounter.so"),
+                (None, "x86_64", "libparser.so")
+            ]
+        );
+        assert!(libraries[0].matches_map("build/libcounter.so.map"));
+        assert!(libraries[0].matches_map("x86_64/libcounter.map"));
+        assert!(!libraries[0].matches_map("arm64-v8a/libcounter.so.map"));
+        assert!(!libraries[0].matches_map("libcounter.so"));
+        assert!(matches!(
+            zip_entries(b"PK\x03\x04 not an archive"),
+            Err(GenealogyError::WrongArchiveFormat)
+        ));
+
+        // The libraries are stored uncompressed, and mapped as is
+        let mapfile = std::fs::read_to_string("tests/android/libcounter.so.map").unwrap();
+        let binary = extract(&apk, &libraries[0].entry).unwrap();
+        assert_eq!(binary.len() as u64, libraries[0].entry.stored_size());
+        let genealogy = Genealogy::new(&mapfile, &binary).unwrap();
+        let report = app_report(&[(&libraries[0], Some(&genealogy)), (&libraries[1], None)]);
+        let counter = report
+            .rows
+            .iter()
+            .find(|row| row.filename == "counter.o")
+            .unwrap();
+        assert_eq!(counter.library, "lib/x86_64/libcounter.so");
+        assert!(report.rows.iter().any(|row| row.filename == "util.o"));
+        assert_eq!(report.libraries[0].library, "lib/x86_64/libcounter.so");
+        assert_eq!(
+            report.libraries[0].attributed,
+            Some(report.rows.iter().map(|row| row.size).sum())
+        );
+        assert_eq!(report.libraries[1].attributed, None);
+
+        // Bundles deflate them, under a directory per module
+        let aab = std::fs::read("tests/android/app.aab").unwrap();
+        let bundled = native_libraries(&aab).unwrap();
+        assert_eq!(bundled[1].module.as_deref(), Some("search"));
+        assert!(bundled[0].entry.stored_size() < bundled[0].entry.size);
+        let extracted = extract(&aab, &bundled[0].entry);
+        #[cfg(feature = "compression")]
+        assert_eq!(extracted.unwrap(), binary);
+        #[cfg(not(feature = "compression"))]
+        assert!(matches!(
+            extracted,
+            Err(GenealogyError::CompressedArchiveEntry(_))
+        ));
+    }
+}
@@ -0,0 +1,306 @@
+//! Why the linker pulled every archive member in, from the table GNU ld writes at the top of its
+//! maps, `Archive member included to satisfy reference by file (symbol)`: the file referencing
+//! a symbol the member defines, itself an object of the command line or a member pulled in
+//! before, so that the chain of references leading to a large member can be cut.
+
+use std::{collections::HashMap, fmt::Display};
+
+use crate::{
+    format::{human_size, Align, Table},
+    origin::{file_name, Origin},
+    Genealogy,
+};
+
+/// The titles of the table of GNU maps listing the archive members included in the link, as GNU
+/// ld and gold write them.
+const ARCHIVE_MEMBERS_TITLES: [&str; 2] = [
+    "Archive member included to satisfy reference by file (symbol)",
+    "Archive member included because of file (symbol)",
+];
+
+/// An archive member of the link, and the reference it satisfies.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ArchiveMember {
+    /// The archive as given to the linker, e.g. `/usr/lib/libc.a`.
+    pub archive: String,
+    pub member: String,
+    /// The file referencing a symbol of the member, as the map names it. `None` for the members
+    /// of `--whole-archive` archives.
+    pub referenced_by: Option<String>,
+    /// The symbol referenced, as the map names it, demangled for Rust.
+    pub symbol: Option<String>,
+    /// The 1-based line of the map the member is listed at.
+    pub line: usize,
+}
+
+impl ArchiveMember {
+    /// `libfoo.a(bar.o)`, the origin of the subsections of the member.
+    pub fn origin(&self) -> String {
+        format!("{}({})", self.archive, self.member)
+    }
+}
+
+/// The archive members of a GNU map, see [`ArchiveMembers::from_mapfile`].
+#[derive(Clone, Debug, Default)]
+pub struct ArchiveMembers {
+    members: Vec<ArchiveMember>,
+    /// Indices of `members` by origin, and by archive file name and member for origins rewritten
+    /// by the [`GenealogyBuilder`](crate::GenealogyBuilder), e.g. with another sysroot.
+    by_origin: HashMap<String, usize>,
+    by_file_name: HashMap<(String, String), usize>,
+}
+
+impl ArchiveMembers {
+    /// Parses the archive member table of a GNU map, empty for other formats. Members whose
+    /// name is too long for the first column have their reference on the next line.
+    pub fn from_mapfile(mapfile: &str) -> Self {
+        let mut members = Self::default();
+        let Some(offset) = ARCHIVE_MEMBERS_TITLES
+            .iter()
+            .find_map(|title| mapfile.find(title))
+        else {
+            return members;
+        };
+        let first_line = mapfile[..offset].matches('\n').count() + 1;
+        let mut pending: Option<(usize, &str)> = None;
+        let lines = mapfile[offset..]
+            .lines()
+            .enumerate()
+            .skip(1)
+            .skip_while(|(_, line)| line.trim().is_empty())
+            .take_while(|(_, line)| !line.trim().is_empty());
+        for (i, line) in lines {
+            let line_number = first_line + i;
+            let (member, reference) = match pending.take() {
+                Some(member) if line.starts_with(char::is_whitespace) => (member, line.trim()),
+                _ => match line.split_once(char::is_whitespace) {
+                    Some((member, reference)) => ((line_number, member), reference.trim()),
+                    None => {
+                        pending = Some((line_number, line));
+                        continue;
+                    }
+                },
+            };
+            let (line_number, member) = member;
+            let Origin {
+                archive: Some(archive),
+                object,
+            } = Origin::parse(member)
+            else {
+                continue;
+            };
+            // `main.o (helper)`, or `(--whole-archive)`
+            let (referenced_by, symbol) = match reference.split_once(" (") {
+                Some((file, symbol)) => (
+                    Some(file.to_string()),
+                    symbol.strip_suffix(')').map(str::to_string),
+                ),
+                None => (None, None),
+            };
+            members.push(ArchiveMember {
+                archive: archive.to_string(),
+                member: object.to_string(),
+                referenced_by,
+                symbol,
+                line: line_number,
+            });
+        }
+        members
+    }
+
+    fn push(&mut self, member: ArchiveMember) {
+        let index = self.members.len();
+        self.by_origin.entry(member.origin()).or_insert(index);
+        self.by_file_name
+            .entry((
+                file_name(&member.archive).to_string(),
+                member.member.clone(),
+            ))
+            .or_insert(index);
+        self.members.push(member);
+    }
+
+    /// The members in the order the linker pulled them in.
+    pub fn iter(&self) -> impl Iterator<Item = &ArchiveMember> {
+        self.members.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.members.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.members.is_empty()
+    }
+
+    /// The member an origin such as `libfoo.a(bar.o)` stands for, matched as written or by the
+    /// file name of its archive.
+    pub fn get(&self, origin: &str) -> Option<&ArchiveMember> {
+        let index = self.by_origin.get(origin).or_else(|| {
+            let Origin {
+                archive: Some(archive),
+                object,
+            } = Origin::parse(origin)
+            else {
+                return None;
+            };
+            self.by_file_name
+                .get(&(file_name(archive).to_string(), object.to_string()))
+        })?;
+        Some(&self.members[*index])
+    }
+
+    /// Why `origin` is in the link: its member, the member referencing it, and so on up to the
+    /// object of the command line or the `--whole-archive` archive at the root of the chain.
+    /// Empty when `origin` is not an archive member of the table.
+    pub fn pulled_in_because(&self, origin: &str) -> Vec<&ArchiveMember> {
+        let mut chain: Vec<&ArchiveMember> = vec![];
+        let mut next = self.get(origin);
+        while let Some(member) = next {
+            // The table lists every member once, but guard against hand-edited maps
+            if chain.contains(&member) {
+                break;
+            }
+            chain.push(member);
+            next = member
+                .referenced_by
+                .as_deref()
+                .and_then(|file| self.get(file));
+        }
+        chain
+    }
+}
+
+/// The chain of references pulling a file in, see [`Genealogy::inclusion_report`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InclusionReport {
+    pub origin: String,
+    /// The file first, then the member referencing it and so on.
+    pub steps: Vec<InclusionStep>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InclusionStep {
+    pub member: ArchiveMember,
+    /// Loaded bytes of the member in the genealogy.
+    pub size: u64,
+}
+
+impl Genealogy {
+    /// Why the archive member `origin` is in the binary, with the loaded size of every member of
+    /// the chain of [`ArchiveMembers::pulled_in_because`]: cutting a reference drops the members
+    /// after it, unless another reference pulls them in.
+    pub fn inclusion_report(&self, members: &ArchiveMembers, origin: &str) -> InclusionReport {
+        let mut sizes: HashMap<&str, u64> = HashMap::new();
+        for subsection in self
+            .sections()
+            .iter()
+            .filter(|section| section.loaded)
+            .flat_map(|section| &section.subsections)
+        {
+            *sizes.entry(&subsection.filename).or_default() += subsection.size;
+        }
+        let steps = members
+            .pulled_in_because(origin)
+            .into_iter()
+            .map(|member| InclusionStep {
+                size: sizes
+                    .iter()
+                    .filter(|(filename, _)| members.get(filename) == Some(member))
+                    .map(|(_, size)| size)
+                    .sum(),
+                member: member.clone(),
+            })
+            .collect();
+        InclusionReport {
+            origin: origin.to_string(),
+            steps,
+        }
+    }
+}
+
+impl InclusionReport {
+    /// The table rendered by `Display`.
+    pub fn to_table(&self) -> Table {
+        let mut table = Table::new(&[Align::Right, Align::Left, Align::Left, Align::Left])
+            .with_header(["size", "member", "referenced by", "symbol"]);
+        for step in &self.steps {
+            table.push_row([
+                human_size(step.size),
+                step.member.origin(),
+                step.member
+                    .referenced_by
+                    .clone()
+                    .unwrap_or_else(|| "--whole-archive".to_string()),
+                step.member.symbol.clone().unwrap_or_default(),
+            ]);
+        }
+        table
+    }
+}
+
+impl Display for InclusionReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_table())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Genealogy;
+
+    use super::ArchiveMembers;
+
+    #[test]
+    fn test_archive_members() {
+        let mapfile = std::fs::read_to_string("tests/archives/out.map").unwrap();
+        let members = ArchiveMembers::from_mapfile(&mapfile);
+        let table: Vec<(String, Option<&str>, Option<&str>, usize)> = members
+            .iter()
+            .map(|member| {
+                (
+                    member.origin(),
+                    member.referenced_by.as_deref(),
+                    member.symbol.as_deref(),
+                    member.line,
+                )
+            })
+            .collect();
+        let helper = "libhelper_with_a_long_name_for_wrapping.a(helper.o)";
+        assert_eq!(
+            table,
+            [
+                (helper.to_string(), Some("main.o"), Some("helper"), 3),
+                ("libd.a(deep.o)".to_string(), Some(helper), Some("deep"), 5),
+                ("libx.a(extra.o)".to_string(), None, None, 6),
+            ]
+        );
+
+        // deep.o is there because main.o calls into helper.o, which calls into it
+        let chain: Vec<String> = members
+            .pulled_in_because("/opt/sdk/lib/libd.a(deep.o)")
+            .iter()
+            .map(|member| member.origin())
+            .collect();
+        assert_eq!(chain, ["libd.a(deep.o)", helper]);
+        assert!(members.pulled_in_because("main.o").is_empty());
+
+        let binary = std::fs::read("tests/archives/app").unwrap();
+        let genealogy = Genealogy::new(&mapfile, &binary).unwrap();
+        let report = genealogy.inclusion_report(&members, "libd.a(deep.o)");
+        let sizes: Vec<u64> = report.steps.iter().map(|step| step.size).collect();
+        assert_eq!(sizes, [0xb + 0x20, 0xb + 0x20]);
+
+        assert!(ArchiveMembers::from_mapfile("").is_empty());
+        let gold = std::fs::read_to_string("tests/gold/out.map").unwrap();
+        let members = ArchiveMembers::from_mapfile(&gold);
+        assert_eq!(
+            members
+                .get("libhelper.a(helper.o)")
+                .unwrap()
+                .symbol
+                .as_deref(),
+            Some("helper_with_a_really_long_function_name_to_wrap")
+        );
+    }
+}
@@ -0,0 +1,114 @@
+//! Synthetic inputs for the benchmarks, enabled by the `bench` feature.
+//!
+//! The map and the ELF file are generated together: one `.text` output section holding
+//! `intervals` subsections of [`SUBSECTION_SIZE`] bytes, each from its own object file. The
+//! bytes of the `.text` section of the ELF file lie past its end, so that the binary stays tiny
+//! whatever the number of intervals.
+
+use std::fmt::Write;
+
+/// Virtual address of the synthetic `.text` section.
+pub const TEXT_VADDR: u64 = 0x40_0000;
+/// File offset of the synthetic `.text` section.
+pub const TEXT_OFFSET: u64 = 0x1000;
+pub const SUBSECTION_SIZE: u64 = 0x10;
+
+/// A GNU ld map with `intervals` subsections in `.text`.
+pub fn gnu_mapfile(intervals: u64) -> String {
+    let mut map = String::with_capacity(intervals as usize * 64);
+    writeln!(
+        map,
+        ".text           0x{TEXT_VADDR:016x}     0x{:x}",
+        intervals * SUBSECTION_SIZE
+    )
+    .expect("writing to a String");
+    for i in 0..intervals {
+        writeln!(
+            map,
+            " .text          0x{:016x}       0x{SUBSECTION_SIZE:x} obj/{}/file{i}.o",
+            TEXT_VADDR + i * SUBSECTION_SIZE,
+            i % 64
+        )
+        .expect("writing to a String");
+    }
+    map
+}
+
+/// A little-endian ELF64 executable with a `.text` section matching [`gnu_mapfile`].
+pub fn elf(intervals: u64) -> Vec<u8> {
+    const HEADER_SIZE: u64 = 64;
+    const SECTION_HEADER_SIZE: u16 = 64;
+    let shstrtab = b"\0.text\0.shstrtab\0";
+    let shstrtab_offset = HEADER_SIZE;
+    let section_headers_offset = (shstrtab_offset + shstrtab.len() as u64).next_multiple_of(8);
+
+    let mut elf = vec![];
+    // e_ident: magic, ELFCLASS64, ELFDATA2LSB, EV_CURRENT, System V ABI
+    elf.extend_from_slice(&[0x7f, b'E', b'L', b'F', 2, 1, 1, 0]);
+    elf.extend_from_slice(&[0; 8]);
+    elf.extend_from_slice(&2u16.to_le_bytes()); // ET_EXEC
+    elf.extend_from_slice(&0x3eu16.to_le_bytes()); // EM_X86_64
+    elf.extend_from_slice(&1u32.to_le_bytes()); // e_version
+    elf.extend_from_slice(&TEXT_VADDR.to_le_bytes()); // e_entry
+    elf.extend_from_slice(&0u64.to_le_bytes()); // e_phoff
+    elf.extend_from_slice(&section_headers_offset.to_le_bytes()); // e_shoff
+    elf.extend_from_slice(&0u32.to_le_bytes()); // e_flags
+    elf.extend_from_slice(&(HEADER_SIZE as u16).to_le_bytes()); // e_ehsize
+    elf.extend_from_slice(&56u16.to_le_bytes()); // e_phentsize
+    elf.extend_from_slice(&0u16.to_le_bytes()); // e_phnum
+    elf.extend_from_slice(&SECTION_HEADER_SIZE.to_le_bytes()); // e_shentsize
+    elf.extend_from_slice(&3u16.to_le_bytes()); // e_shnum
+    elf.extend_from_slice(&2u16.to_le_bytes()); // e_shstrndx
+
+    elf.extend_from_slice(shstrtab);
+    elf.resize(section_headers_offset as usize, 0);
+
+    let mut section_header =
+        |name: u32, kind: u32, flags: u64, addr: u64, offset: u64, size: u64, align: u64| {
+            elf.extend_from_slice(&name.to_le_bytes());
+            elf.extend_from_slice(&kind.to_le_bytes());
+            elf.extend_from_slice(&flags.to_le_bytes());
+            elf.extend_from_slice(&addr.to_le_bytes());
+            elf.extend_from_slice(&offset.to_le_bytes());
+            elf.extend_from_slice(&size.to_le_bytes());
+            elf.extend_from_slice(&0u32.to_le_bytes()); // sh_link
+            elf.extend_from_slice(&0u32.to_le_bytes()); // sh_info
+            elf.extend_from_slice(&align.to_le_bytes());
+            elf.extend_from_slice(&0u64.to_le_bytes()); // sh_entsize
+        };
+    section_header(0, 0, 0, 0, 0, 0, 0);
+    // SHT_PROGBITS, SHF_ALLOC | SHF_EXECINSTR
+    section_header(
+        1,
+        1,
+        0x6,
+        TEXT_VADDR,
+        TEXT_OFFSET,
+        intervals * SUBSECTION_SIZE,
+        16,
+    );
+    // SHT_STRTAB
+    section_header(7, 3, 0, 0, shstrtab_offset, shstrtab.len() as u64, 1);
+    elf
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Genealogy;
+
+    use super::{elf, gnu_mapfile, TEXT_OFFSET, TEXT_VADDR};
+
+    #[test]
+    fn test_synthetic_inputs() {
+        let genealogy = Genealogy::new(&gnu_mapfile(1000), &elf(1000)).unwrap();
+        assert!(genealogy.warnings().is_empty());
+        assert_eq!(genealogy.iter().count(), 1000);
+        let hit = genealogy
+            .query_vaddr_point(TEXT_VADDR + 0x25)
+            .next()
+            .unwrap();
+        assert_eq!(hit.filename, "obj/2/file2.o");
+        let hit = genealogy.query_point(TEXT_OFFSET + 0x25).next().unwrap();
+        assert_eq!(hit.filename, "obj/2/file2.o");
+    }
+}
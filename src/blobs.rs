@@ -0,0 +1,262 @@
+//! Embedded blobs: archives, images, firmware or models linked in as data, found by their magic
+//! bytes or by their entropy, with the file that brought them in.
+
+use std::{fmt::Display, ops::Range};
+
+use crate::{
+    format::{hex, human_size, Align, Table},
+    Genealogy,
+};
+
+/// Blobs smaller than this are not reported by default.
+pub const DEFAULT_MIN_BLOB_SIZE: u64 = 64 << 10;
+
+/// The size of the windows entropy is measured over.
+const WINDOW: usize = 4096;
+
+/// Bits per byte above which a window is taken for compressed or encrypted data. Machine code
+/// and tables stay well below it, compressed data is close to 8.
+const HIGH_ENTROPY: f64 = 7.5;
+
+/// Magic bytes, at the given offset of the blob.
+const MAGICS: &[(&str, usize, &[u8])] = &[
+    ("zip", 0, b"PK\x03\x04"),
+    ("gzip", 0, b"\x1f\x8b\x08"),
+    ("zstd", 0, b"\x28\xb5\x2f\xfd"),
+    ("xz", 0, b"\xfd7zXZ\x00"),
+    ("bzip2", 0, b"BZh"),
+    ("7z", 0, b"7z\xbc\xaf\x27\x1c"),
+    ("lz4", 0, b"\x04\x22\x4d\x18"),
+    ("png", 0, b"\x89PNG\r\n\x1a\n"),
+    ("jpeg", 0, b"\xff\xd8\xff"),
+    ("gif", 0, b"GIF8"),
+    ("riff", 0, b"RIFF"),
+    ("ogg", 0, b"OggS"),
+    ("pdf", 0, b"%PDF-"),
+    ("sqlite", 0, b"SQLite format 3\x00"),
+    ("elf", 0, b"\x7fELF"),
+    ("u-boot image", 0, b"\x27\x05\x19\x56"),
+    ("device tree", 0, b"\xd0\x0d\xfe\xed"),
+    ("gguf", 0, b"GGUF"),
+    ("tflite", 4, b"TFL3"),
+];
+
+/// A blob of an input section, see [`Genealogy::blob_report`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Blob {
+    pub filename: String,
+    /// The input section holding the blob.
+    pub section: String,
+    pub file_offset: u64,
+    pub size: u64,
+    /// The format of the blob when it starts with known magic bytes.
+    pub kind: Option<&'static str>,
+    /// Shannon entropy of the blob, in bits per byte.
+    pub entropy: f64,
+}
+
+impl Blob {
+    /// The format of the blob, or `high entropy` for data without magic bytes.
+    pub fn kind_name(&self) -> &'static str {
+        self.kind.unwrap_or("high entropy")
+    }
+}
+
+/// The blobs of an image, biggest first.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BlobReport {
+    pub blobs: Vec<Blob>,
+    pub min_size: u64,
+}
+
+impl Genealogy {
+    /// Finds the blobs of at least `min_size` bytes in the input sections of `binary`, the
+    /// binary the genealogy was built from: input sections starting with the magic bytes of a
+    /// known format, and runs of high entropy windows within input sections.
+    pub fn blob_report(&self, binary: &[u8], min_size: u64) -> BlobReport {
+        let mut blobs = vec![];
+        for section in self.sections().iter().filter(|section| section.loaded) {
+            for subsection in &section.subsections {
+                let Some(bytes) = subsection.start_file_offset.and_then(|start| {
+                    let start = usize::try_from(start).ok()?;
+                    binary.get(start..start.checked_add(usize::try_from(subsection.size).ok()?)?)
+                }) else {
+                    continue;
+                };
+                for (range, kind) in find_blobs(bytes, min_size) {
+                    blobs.push(Blob {
+                        filename: subsection.filename.clone(),
+                        section: subsection.name.clone(),
+                        file_offset: subsection.start_file_offset.unwrap_or_default()
+                            + range.start as u64,
+                        size: range.len() as u64,
+                        kind,
+                        entropy: entropy(&bytes[range]),
+                    });
+                }
+            }
+        }
+        blobs.sort_by(|a, b| {
+            b.size
+                .cmp(&a.size)
+                .then_with(|| a.file_offset.cmp(&b.file_offset))
+        });
+        BlobReport { blobs, min_size }
+    }
+}
+
+/// The blobs of at least `min_size` bytes in the bytes of an input section.
+fn find_blobs(bytes: &[u8], min_size: u64) -> Vec<(Range<usize>, Option<&'static str>)> {
+    let min_size = usize::try_from(min_size).unwrap_or(usize::MAX).max(1);
+    if bytes.len() < min_size {
+        return vec![];
+    }
+    // An embedded file is usually an input section of its own, e.g. from `incbin` or
+    // `include_bytes!`, and may be uncompressed
+    if let Some(kind) = magic(bytes) {
+        return vec![(0..bytes.len(), Some(kind))];
+    }
+
+    let mut blobs = vec![];
+    let mut run: Option<Range<usize>> = None;
+    for (i, window) in bytes.chunks(WINDOW).enumerate() {
+        let start = i * WINDOW;
+        // The entropy of a short last window is bounded by its length, it extends a run
+        let high = if window.len() < WINDOW {
+            run.is_some()
+        } else {
+            entropy(window) >= HIGH_ENTROPY
+        };
+        match (&mut run, high) {
+            (Some(run), true) => run.end = start + window.len(),
+            (None, true) => run = Some(start..start + window.len()),
+            (Some(_), false) => blobs.extend(run.take()),
+            (None, false) => {}
+        }
+    }
+    blobs.extend(run);
+    blobs
+        .into_iter()
+        .filter(|range| range.len() >= min_size)
+        .map(|range| {
+            // Runs start at a window, their header may be anywhere in the previous one
+            let header = range.start.saturating_sub(WINDOW)..range.start + WINDOW.min(range.len());
+            let found = (header.start..header.end)
+                .find_map(|start| magic(&bytes[start..]).map(|kind| (start, kind)));
+            match found {
+                Some((start, kind)) => (start..range.end, Some(kind)),
+                None => (range, None),
+            }
+        })
+        .collect()
+}
+
+/// The format whose magic bytes `bytes` starts with.
+fn magic(bytes: &[u8]) -> Option<&'static str> {
+    MAGICS
+        .iter()
+        .find(|(_, offset, magic)| {
+            bytes
+                .get(*offset..)
+                .is_some_and(|bytes| bytes.starts_with(magic))
+        })
+        .map(|(kind, _, _)| *kind)
+}
+
+/// Shannon entropy of `bytes`, in bits per byte.
+fn entropy(bytes: &[u8]) -> f64 {
+    let mut counts = [0u64; 256];
+    for &byte in bytes {
+        counts[usize::from(byte)] += 1;
+    }
+    let len = bytes.len() as f64;
+    counts
+        .iter()
+        .filter(|&&count| count > 0)
+        .map(|&count| {
+            let p = count as f64 / len;
+            -p * p.log2()
+        })
+        .sum()
+}
+
+impl BlobReport {
+    /// The table rendered by `Display`.
+    pub fn to_table(&self) -> Table {
+        let mut table = Table::new(&[
+            Align::Right,
+            Align::Left,
+            Align::Right,
+            Align::Right,
+            Align::Left,
+            Align::Left,
+        ])
+        .with_header(["size", "kind", "entropy", "offset", "section", "file"]);
+        for blob in &self.blobs {
+            table.push_row([
+                human_size(blob.size),
+                blob.kind_name().to_string(),
+                format!("{:.2}", blob.entropy),
+                hex(blob.file_offset),
+                blob.section.clone(),
+                blob.filename.clone(),
+            ]);
+        }
+        table
+    }
+}
+
+impl Display for BlobReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_table())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Genealogy;
+
+    use super::{find_blobs, WINDOW};
+
+    #[test]
+    fn test_blob_report() {
+        // xorshift bytes, which look compressed, between zeros
+        let mut state = 0x2545_f491_4f6c_dd1du64;
+        let mut random = |n: usize| -> Vec<u8> {
+            (0..n)
+                .map(|_| {
+                    state ^= state << 13;
+                    state ^= state >> 7;
+                    state ^= state << 17;
+                    state as u8
+                })
+                .collect()
+        };
+        let mut bytes = vec![0; 3 * WINDOW];
+        bytes.extend(random(8 * WINDOW));
+        bytes.extend(vec![0; 2 * WINDOW]);
+        assert_eq!(
+            find_blobs(&bytes, 4 * WINDOW as u64),
+            [(3 * WINDOW..11 * WINDOW, None)]
+        );
+        assert!(find_blobs(&bytes, 9 * WINDOW as u64).is_empty());
+
+        // A zip payload inside a section, and an uncompressed one filling its own
+        let mut zip = vec![0; 3 * WINDOW + 100];
+        zip.extend(b"PK\x03\x04");
+        zip.extend(random(8 * WINDOW));
+        assert_eq!(
+            find_blobs(&zip, 4 * WINDOW as u64),
+            [(3 * WINDOW + 100..zip.len(), Some("zip"))]
+        );
+        let mut elf = b"\x7fELF".to_vec();
+        elf.extend(vec![0; 2 * WINDOW]);
+        assert_eq!(find_blobs(&elf, 1024), [(0..elf.len(), Some("elf"))]);
+
+        // Code and tables are not blobs
+        let mapfile = std::fs::read_to_string("tests/cargo/out.map").unwrap();
+        let binary = std::fs::read("tests/cargo/genealogy").unwrap();
+        let genealogy = Genealogy::new(&mapfile, &binary).unwrap();
+        assert!(genealogy.blob_report(&binary, 16 << 10).blobs.is_empty());
+    }
+}
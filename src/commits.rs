@@ -0,0 +1,279 @@
+//! Attribution of the size changes between two revisions to the commits in between: the objects
+//! whose size changed are joined with the commits that touched their source, read from
+//! `git log --name-only`.
+
+use std::fmt::Display;
+
+use crate::{
+    compile_commands::{normalize, CompileCommands},
+    diff::{DiffEntry, ReportDiff},
+    format::{human_delta, human_size, Align, Cell, Style, Table},
+    origin::{file_name, Origin},
+};
+
+/// The arguments of `git log` printing what [`parse_git_log`] reads, before the revision range.
+pub const GIT_LOG_ARGUMENTS: [&str; 3] = ["log", "--name-only", "--format=%x00%H%x09%s"];
+
+/// A commit and the files it touched, relative to the root of the repository.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Commit {
+    pub id: String,
+    /// The first line of the message.
+    pub summary: String,
+    pub files: Vec<String>,
+}
+
+impl Commit {
+    /// The first 7 characters of the ID, as shown by `git log --oneline` in small repositories.
+    pub fn short_id(&self) -> &str {
+        self.id.get(..7).unwrap_or(&self.id)
+    }
+}
+
+/// Parses the output of `git log` with [`GIT_LOG_ARGUMENTS`], newest commit first.
+pub fn parse_git_log(log: &str) -> Vec<Commit> {
+    log.split('\0')
+        .filter(|record| !record.trim().is_empty())
+        .filter_map(|record| {
+            let mut lines = record.lines();
+            let (id, summary) = lines.next()?.split_once('\t')?;
+            Some(Commit {
+                id: id.to_string(),
+                summary: summary.to_string(),
+                files: lines
+                    .map(str::trim)
+                    .filter(|line| !line.is_empty())
+                    .map(str::to_string)
+                    .collect(),
+            })
+        })
+        .collect()
+}
+
+/// The size changes of a diff per commit, see [`ReportDiff::by_commit`].
+#[derive(Clone, Debug)]
+pub struct CommitReport {
+    /// Biggest absolute change first.
+    pub entries: Vec<CommitChange>,
+    /// The changes of objects whose source no commit of the range touched, e.g. of compiler or
+    /// library upgrades, or of headers.
+    pub unattributed: Vec<DiffEntry>,
+    pub old_total: u64,
+    pub new_total: u64,
+}
+
+/// The size change of an object, and the commits that touched its source.
+#[derive(Clone, Debug)]
+pub struct CommitChange {
+    /// Newest first. Changes of a source in several commits are not split between them.
+    pub commits: Vec<Commit>,
+    pub change: DiffEntry,
+}
+
+impl ReportDiff {
+    /// Joins the changed objects with the `commits` touching their source, the one `sources`
+    /// gives, relative to `repository`, or else a source with the same name as the object
+    /// without its extension, e.g. `src/parser.c` for `parser.o` or `parser.c.o`.
+    pub fn by_commit(
+        &self,
+        commits: &[Commit],
+        sources: Option<&CompileCommands>,
+        repository: &str,
+    ) -> CommitReport {
+        let repository = normalize(repository);
+        let mut entries = vec![];
+        let mut unattributed = vec![];
+        for entry in &self.entries {
+            let source = sources
+                .and_then(|sources| sources.source_for_object(&entry.filename))
+                .and_then(|source| source.strip_prefix(repository.as_str()))
+                .map(|relative| relative.trim_start_matches('/'));
+            let touching: Vec<Commit> = commits
+                .iter()
+                .filter(|commit| {
+                    commit.files.iter().any(|file| match source {
+                        Some(source) => normalize(file) == source,
+                        None => is_source_of(file, &entry.filename),
+                    })
+                })
+                .cloned()
+                .collect();
+            if touching.is_empty() {
+                unattributed.push(entry.clone());
+            } else {
+                entries.push(CommitChange {
+                    commits: touching,
+                    change: entry.clone(),
+                });
+            }
+        }
+        CommitReport {
+            entries,
+            unattributed,
+            old_total: self.old_total,
+            new_total: self.new_total,
+        }
+    }
+}
+
+/// Whether `path` is named like the source of the object of `origin`.
+fn is_source_of(path: &str, origin: &str) -> bool {
+    let object = file_name(Origin::parse(origin).object);
+    let stem = object
+        .strip_suffix(".o")
+        .or_else(|| object.strip_suffix(".obj"))
+        .unwrap_or(object);
+    let source = file_name(path);
+    // CMake keeps the extension of the source, `parser.c.o`
+    source == stem
+        || source
+            .rsplit_once('.')
+            .is_some_and(|(source_stem, _)| source_stem == stem)
+}
+
+impl CommitReport {
+    /// Sum of the changes not attributed to a commit.
+    pub fn unattributed_delta(&self) -> i64 {
+        self.unattributed.iter().map(DiffEntry::delta).sum()
+    }
+
+    /// A table of the changes, for renderers of tables; `Display` writes sentences instead.
+    pub fn to_table(&self) -> Table {
+        let mut table = Table::new(&[Align::Right, Align::Left, Align::Left, Align::Left])
+            .with_header(["delta", "commits", "file", "summary"]);
+        for entry in &self.entries {
+            let delta = entry.change.delta();
+            table.push_row([
+                Cell::styled(human_delta(delta), Style::for_delta(delta)),
+                entry
+                    .commits
+                    .iter()
+                    .map(Commit::short_id)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+                    .into(),
+                entry.change.filename.as_str().into(),
+                match entry.commits.as_slice() {
+                    [commit] => commit.summary.as_str().into(),
+                    _ => "".into(),
+                },
+            ]);
+        }
+        if !self.unattributed.is_empty() {
+            let delta = self.unattributed_delta();
+            table.push_row([
+                Cell::styled(human_delta(delta), Style::for_delta(delta)),
+                "-".into(),
+                format!("{} files", self.unattributed.len()).into(),
+                "not touched by a commit".into(),
+            ]);
+        }
+        table
+    }
+}
+
+impl Display for CommitChange {
+    /// E.g. `commit 1a2b3c4 added 40.00 KiB via parser.o (Add the expression parser)`.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let ids: Vec<&str> = self.commits.iter().map(Commit::short_id).collect();
+        let delta = self.change.delta();
+        write!(
+            f,
+            "{} {} {} {} via {}",
+            if ids.len() == 1 { "commit" } else { "commits" },
+            ids.join(", "),
+            if delta < 0 { "removed" } else { "added" },
+            human_size(delta.unsigned_abs()),
+            file_name(&self.change.filename)
+        )?;
+        if let [commit] = self.commits.as_slice() {
+            write!(f, " ({})", commit.summary)?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for CommitReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for entry in &self.entries {
+            writeln!(f, "{entry}")?;
+        }
+        let delta = self.unattributed_delta();
+        if !self.unattributed.is_empty() {
+            writeln!(
+                f,
+                "{}{} in {} files not touched by a commit",
+                if delta < 0 { "-" } else { "+" },
+                human_size(delta.unsigned_abs()),
+                self.unattributed.len()
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{compile_commands::CompileCommands, Report, ReportEntry};
+
+    use super::parse_git_log;
+
+    fn report(entries: &[(&str, u64)]) -> Report {
+        Report {
+            entries: entries
+                .iter()
+                .map(|&(filename, size)| ReportEntry {
+                    filename: filename.to_string(),
+                    size,
+                    source: None,
+                })
+                .collect(),
+            total: entries.iter().map(|&(_, size)| size).sum(),
+        }
+    }
+
+    #[test]
+    fn test_by_commit() {
+        let commits = parse_git_log(
+            "\0c0ffee1234567890\tAdd the expression parser\n\nsrc/parser.c\nsrc/parser.h\n\
+             \0deadbeef00000000\tSpeed up the lexer\n\nsrc/lexer.c\nsrc/parser.c\n\
+             \0abad1dea00000000\tUpdate the README\n\nREADME.md\n",
+        );
+        assert_eq!(commits.len(), 3);
+        assert_eq!(commits[0].short_id(), "c0ffee1");
+        assert_eq!(commits[2].files, ["README.md"]);
+
+        let old = report(&[
+            ("build/parser.c.o", 1000),
+            ("build/lexer.o", 500),
+            ("libm.a(sin.o)", 80),
+        ]);
+        let new = report(&[
+            ("build/parser.c.o", 41960),
+            ("build/lexer.o", 400),
+            ("libm.a(sin.o)", 96),
+        ]);
+        let diff = old.diff(&new);
+        let report = diff.by_commit(&commits, None, "/repo");
+        let lines: Vec<String> = report.entries.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            lines,
+            [
+                "commits c0ffee1, deadbee added 40.00 KiB via parser.c.o",
+                "commit deadbee removed 100 B via lexer.o (Speed up the lexer)",
+            ]
+        );
+        assert_eq!(report.unattributed_delta(), 16);
+
+        // Sources from a compilation database, relative to the repository
+        let sources = CompileCommands::parse(
+            r#"[{"directory": "/repo/build", "file": "../src/lexer.c",
+                 "arguments": ["cc", "-c", "-o", "lexer.o", "../src/lexer.c"]}]"#,
+        )
+        .unwrap();
+        let report = diff.by_commit(&commits[..1], Some(&sources), "/repo");
+        assert_eq!(report.entries.len(), 1);
+        assert_eq!(report.entries[0].change.filename, "build/parser.c.o");
+        assert_eq!(report.unattributed.len(), 2);
+    }
+}
@@ -0,0 +1,167 @@
+//! Object to source file mapping from a compilation database (`compile_commands.json`, as
+//! written by CMake, Meson or Bear), which works without debug information.
+
+use std::collections::HashMap;
+
+use crate::{
+    origin::{Origin, PathSeparators},
+    output::Value,
+    GenealogyError, Report,
+};
+
+/// The source file every object of a compilation database was compiled from.
+#[derive(Clone, Debug, Default)]
+pub struct CompileCommands {
+    /// Object paths as written in the database, and their absolute form.
+    sources: HashMap<String, String>,
+}
+
+impl CompileCommands {
+    /// Parses a `compile_commands.json`. Objects are read from the `output` field when present,
+    /// from the `-o` argument of `arguments` or `command` otherwise.
+    pub fn parse(json: &str) -> Result<Self, GenealogyError> {
+        let database = Value::from_json(json).ok_or(GenealogyError::WrongCompileCommandsFormat)?;
+        let commands = database
+            .as_array()
+            .ok_or(GenealogyError::WrongCompileCommandsFormat)?;
+
+        let mut sources = HashMap::new();
+        for command in commands {
+            let field = |name| command.get(name).and_then(Value::as_str);
+            let (Some(directory), Some(file)) = (field("directory"), field("file")) else {
+                return Err(GenealogyError::WrongCompileCommandsFormat);
+            };
+            let arguments: Vec<&str> = match command.get("arguments").and_then(Value::as_array) {
+                Some(arguments) => arguments.iter().filter_map(Value::as_str).collect(),
+                None => field("command")
+                    .unwrap_or_default()
+                    .split_whitespace()
+                    .collect(),
+            };
+            let output = field("output").or_else(|| {
+                arguments
+                    .iter()
+                    .position(|&argument| argument == "-o")
+                    .and_then(|i| arguments.get(i + 1).copied())
+            });
+            let Some(output) = output else {
+                continue;
+            };
+
+            let source = absolute(directory, file);
+            sources.insert(absolute(directory, output), source.clone());
+            sources.insert(normalize(output), source);
+        }
+        Ok(Self { sources })
+    }
+
+    /// The source `origin` was compiled from. Archive members are looked up by object name, and
+    /// origins relative to another directory than the database by path suffix.
+    pub fn source_for_object(&self, origin: &str) -> Option<&str> {
+        lookup_object(&self.sources, origin).map(String::as_str)
+    }
+}
+
+impl Report {
+    /// Fills in the [`source`](crate::ReportEntry::source) of the entries whose object is in
+    /// `sources`.
+    pub fn annotate_sources(&mut self, sources: &CompileCommands) {
+        for entry in &mut self.entries {
+            entry.source = sources
+                .source_for_object(&entry.filename)
+                .map(str::to_string);
+        }
+    }
+}
+
+/// `path` with `/` separators and without `.` and `..` components.
+pub(crate) fn normalize(path: &str) -> String {
+    PathSeparators::Slash.normalize(path).into_owned()
+}
+
+fn absolute(directory: &str, path: &str) -> String {
+    if path.starts_with(['/', '\\']) || path.get(1..2) == Some(":") {
+        normalize(path)
+    } else {
+        normalize(&format!("{directory}/{path}"))
+    }
+}
+
+/// The value of the object of `origin` in `objects`, keyed by normalized object paths: the one
+/// of the same path, or else of the longest path that is a suffix of the other, the first in
+/// path order on ties.
+pub(crate) fn lookup_object<'a, V>(objects: &'a HashMap<String, V>, origin: &str) -> Option<&'a V> {
+    let object = normalize(Origin::parse(origin).object);
+    if let Some(value) = objects.get(&object) {
+        return Some(value);
+    }
+    objects
+        .iter()
+        .filter(|(path, _)| is_path_suffix(path, &object) || is_path_suffix(&object, path))
+        .max_by(|(a, _), (b, _)| a.len().cmp(&b.len()).then_with(|| b.cmp(a)))
+        .map(|(_, value)| value)
+}
+
+/// Whether `suffix` is made of the last components of `path`.
+fn is_path_suffix(path: &str, suffix: &str) -> bool {
+    path.strip_suffix(suffix)
+        .is_some_and(|rest| rest.is_empty() || rest.ends_with('/'))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Report, ReportEntry};
+
+    use super::CompileCommands;
+
+    #[test]
+    fn test_compile_commands() {
+        let commands = CompileCommands::parse(
+            r#"[
+                {"directory": "/repo/build", "file": "../src/motor/control.c",
+                 "arguments": ["cc", "-c", "-o", "CMakeFiles/fw.dir/src/motor/control.c.o",
+                               "../src/motor/control.c"]},
+                {"directory": "/repo/build", "file": "/repo/src/net/tcp.c",
+                 "command": "cc -c -o net/tcp.o /repo/src/net/tcp.c"},
+                {"directory": "/repo/build", "file": "/repo/src/main.c"}
+            ]"#,
+        )
+        .unwrap();
+        assert_eq!(
+            commands.source_for_object("/repo/build/CMakeFiles/fw.dir/src/motor/control.c.o"),
+            Some("/repo/src/motor/control.c")
+        );
+        assert_eq!(
+            commands.source_for_object("CMakeFiles/fw.dir/src/motor/control.c.o"),
+            Some("/repo/src/motor/control.c")
+        );
+        assert_eq!(
+            commands.source_for_object("libnet.a(net/tcp.o)"),
+            Some("/repo/src/net/tcp.c")
+        );
+        assert_eq!(commands.source_for_object("main.o"), None);
+        assert!(CompileCommands::parse("{}").is_err());
+        assert!(CompileCommands::parse(r#"[{"file": "a.c"}]"#).is_err());
+
+        let entry = |filename: &str| ReportEntry {
+            filename: filename.to_string(),
+            size: 10,
+            source: None,
+        };
+        let mut report = Report {
+            entries: vec![entry("net/tcp.o"), entry("main.o")],
+            total: 20,
+        };
+        assert!(!report.to_table().to_string().contains("source"));
+        report.annotate_sources(&commands);
+        assert_eq!(
+            report.entries[0].source.as_deref(),
+            Some("/repo/src/net/tcp.c")
+        );
+        assert_eq!(report.entries[1].source, None);
+        assert!(report
+            .to_table()
+            .to_string()
+            .contains("/repo/src/net/tcp.c"));
+    }
+}
@@ -0,0 +1,176 @@
+//! Compressed size attribution: download size limits (OTA updates, app stores) apply to the
+//! compressed binary, in which some files weigh much less than others.
+
+use std::{collections::HashMap, fmt::Display, io::Write, ops::RangeInclusive, str::FromStr};
+
+use flate2::write::GzEncoder;
+
+use crate::{Genealogy, GenealogyError, Report};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Codec {
+    Gzip,
+    Zstd,
+}
+
+impl Codec {
+    /// The levels the codec accepts.
+    pub fn levels(self) -> RangeInclusive<i32> {
+        match self {
+            Codec::Gzip => 0..=9,
+            Codec::Zstd => zstd::compression_level_range(),
+        }
+    }
+
+    /// The level used by the codec's command line tool.
+    pub fn default_level(self) -> i32 {
+        match self {
+            Codec::Gzip => 6,
+            Codec::Zstd => zstd::DEFAULT_COMPRESSION_LEVEL,
+        }
+    }
+}
+
+impl FromStr for Codec {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "gzip" => Ok(Codec::Gzip),
+            "zstd" => Ok(Codec::Zstd),
+            _ => Err(()),
+        }
+    }
+}
+
+impl Display for Codec {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Codec::Gzip => "gzip",
+            Codec::Zstd => "zstd",
+        })
+    }
+}
+
+/// A codec and a level it accepts.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Compression {
+    codec: Codec,
+    level: i32,
+}
+
+impl Compression {
+    /// `None` when `level` is not in [`Codec::levels`].
+    pub fn new(codec: Codec, level: i32) -> Option<Self> {
+        codec
+            .levels()
+            .contains(&level)
+            .then_some(Self { codec, level })
+    }
+
+    pub fn codec(&self) -> Codec {
+        self.codec
+    }
+
+    pub fn level(&self) -> i32 {
+        self.level
+    }
+
+    /// The compressed size of `data`.
+    pub fn compressed_size(&self, data: &[u8]) -> u64 {
+        match self.codec {
+            Codec::Gzip => {
+                let mut encoder =
+                    GzEncoder::new(vec![], flate2::Compression::new(self.level as u32));
+                encoder
+                    .write_all(data)
+                    .and_then(|()| encoder.finish())
+                    .expect("writing to a vector does not fail")
+                    .len() as u64
+            }
+            Codec::Zstd => zstd::bulk::compress(data, self.level)
+                .expect("the level is in the supported range")
+                .len() as u64,
+        }
+    }
+}
+
+impl Default for Compression {
+    fn default() -> Self {
+        Self {
+            codec: Codec::Zstd,
+            level: Codec::Zstd.default_level(),
+        }
+    }
+}
+
+impl Genealogy {
+    /// Estimates the compressed size of what each file contributes to `binary`, the binary the
+    /// genealogy was built from.
+    ///
+    /// The ranges of every file are compressed together, in file offset order. Redundancy
+    /// between files is not taken into account, so the total is usually a bit more than the
+    /// compressed size of the whole binary, see [`Compression::compressed_size`] for the latter.
+    pub fn compressed_report(
+        &self,
+        binary: &[u8],
+        compression: Compression,
+    ) -> Result<Report, GenealogyError> {
+        let mut contents: Vec<(&str, Vec<u8>)> = vec![];
+        let mut files = HashMap::new();
+        for hit in self.iter() {
+            let bytes = usize::try_from(hit.range.start)
+                .ok()
+                .zip(usize::try_from(hit.range.end).ok())
+                .and_then(|(start, end)| binary.get(start..end))
+                .ok_or_else(|| {
+                    GenealogyError::AddressOutOfRange(format!(
+                        "0x{:08x}..0x{:08x} of {} is outside of the binary",
+                        hit.range.start, hit.range.end, hit.filename
+                    ))
+                })?;
+            let index = *files.entry(hit.filename).or_insert_with(|| {
+                contents.push((hit.filename, vec![]));
+                contents.len() - 1
+            });
+            contents[index].1.extend_from_slice(bytes);
+        }
+
+        Ok(Report::from_sizes(contents.iter().map(
+            |(filename, bytes)| (*filename, compression.compressed_size(bytes)),
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Genealogy;
+
+    use super::{Codec, Compression};
+
+    #[test]
+    fn test_compressed_report() {
+        assert!(Compression::new(Codec::Gzip, 10).is_none());
+        assert_eq!("zstd".parse(), Ok(Codec::Zstd));
+
+        let mapfile = std::fs::read_to_string("tests/gcc/out.map").unwrap();
+        let binary = std::fs::read("tests/gcc/a.out").unwrap();
+        let genealogy = Genealogy::new(&mapfile, &binary).unwrap();
+        let raw = genealogy.report();
+
+        for codec in [Codec::Gzip, Codec::Zstd] {
+            let compression = Compression::new(codec, codec.default_level()).unwrap();
+            let report = genealogy.compressed_report(&binary, compression).unwrap();
+            assert_eq!(report.entries.len(), raw.entries.len());
+            assert!(report.total > 0);
+
+            // Zeroes compress well
+            let zeroes = compression.compressed_size(&[0; 4096]);
+            assert!(zeroes < 100, "{codec}: {zeroes}");
+        }
+
+        assert!(genealogy
+            .compressed_report(&binary[..0x1000], Compression::default())
+            .is_err());
+    }
+}
@@ -0,0 +1,220 @@
+//! Attribution of the static constructors registered in `.init_array`, `.preinit_array` and
+//! `.ctors` to the object files they come from.
+
+use std::{collections::HashMap, fmt::Display};
+
+use goblin::{elf::Elf, Object};
+
+use crate::{
+    format::{hex, Align, Cell, Style, Table},
+    Genealogy, GenealogyError,
+};
+
+const CONSTRUCTOR_SECTIONS: [&str; 3] = [".preinit_array", ".init_array", ".ctors"];
+
+/// One function pointer of a constructor table.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StaticConstructor {
+    pub section: String,
+    /// Address of the table slot holding the pointer.
+    pub slot_vaddr: u64,
+    /// Address of the constructor function.
+    pub target_vaddr: u64,
+    /// The file that registered the slot, or else the one defining the function.
+    pub filename: Option<String>,
+}
+
+/// Static constructors and their count per registering file, see
+/// [`Genealogy::static_constructors`].
+#[derive(Clone, Debug)]
+pub struct ConstructorReport {
+    /// In table order.
+    pub constructors: Vec<StaticConstructor>,
+    /// Files registering the most constructors first.
+    pub entries: Vec<ConstructorEntry>,
+}
+
+#[derive(Clone, Debug)]
+pub struct ConstructorEntry {
+    pub filename: String,
+    pub count: u64,
+}
+
+impl Genealogy {
+    /// Reads the constructor tables of the ELF `binary` the genealogy was built from, and
+    /// attributes every entry.
+    ///
+    /// Position independent binaries usually hold the pointers in `R_*_RELATIVE` relocations
+    /// rather than in the tables themselves, the relocation addend is used when present.
+    pub fn static_constructors(&self, binary: &[u8]) -> Result<ConstructorReport, GenealogyError> {
+        let Object::Elf(elf) = Object::parse(binary).map_err(GenealogyError::BinaryParse)? else {
+            return Err(GenealogyError::UnsupportedBinaryFormat);
+        };
+
+        let constructors: Vec<StaticConstructor> = read_constructors(&elf, binary)
+            .into_iter()
+            .map(|(section, slot_vaddr, target_vaddr)| {
+                let filename = self
+                    .query_vaddr_point(slot_vaddr)
+                    .chain(self.query_vaddr_point(target_vaddr))
+                    .next()
+                    .map(|hit| hit.filename.to_string());
+                StaticConstructor {
+                    section,
+                    slot_vaddr,
+                    target_vaddr,
+                    filename,
+                }
+            })
+            .collect();
+
+        let mut counts: HashMap<&str, u64> = HashMap::new();
+        for constructor in &constructors {
+            let filename = constructor.filename.as_deref().unwrap_or("<unattributed>");
+            *counts.entry(filename).or_default() += 1;
+        }
+        let mut entries: Vec<ConstructorEntry> = counts
+            .into_iter()
+            .map(|(filename, count)| ConstructorEntry {
+                filename: filename.to_string(),
+                count,
+            })
+            .collect();
+        entries.sort_by(|a, b| {
+            b.count
+                .cmp(&a.count)
+                .then_with(|| a.filename.cmp(&b.filename))
+        });
+
+        Ok(ConstructorReport {
+            constructors,
+            entries,
+        })
+    }
+}
+
+/// `(section, slot vaddr, target vaddr)` of every constructor pointer, skipping the null and
+/// `-1` markers delimiting `.ctors`.
+fn read_constructors(elf: &Elf, binary: &[u8]) -> Vec<(String, u64, u64)> {
+    let (pointer_size, terminator) = if elf.is_64 {
+        (8, u64::MAX)
+    } else {
+        (4, u64::from(u32::MAX))
+    };
+    let relocated: HashMap<u64, u64> = elf
+        .dynrelas
+        .iter()
+        .filter(|reloc| reloc.r_sym == 0)
+        .filter_map(|reloc| Some((reloc.r_offset, reloc.r_addend? as u64)))
+        .collect();
+
+    let mut constructors = vec![];
+    for shdr in &elf.section_headers {
+        let Some(name) = elf.shdr_strtab.get_at(shdr.sh_name) else {
+            continue;
+        };
+        if !CONSTRUCTOR_SECTIONS.contains(&name) {
+            continue;
+        }
+        let Some(data) = usize::try_from(shdr.sh_offset)
+            .ok()
+            .zip(usize::try_from(shdr.sh_size).ok())
+            .and_then(|(start, size)| binary.get(start..start.checked_add(size)?))
+        else {
+            continue;
+        };
+
+        for (i, bytes) in data.chunks_exact(pointer_size).enumerate() {
+            let Some(slot_vaddr) = shdr.sh_addr.checked_add((i * pointer_size) as u64) else {
+                break;
+            };
+            let in_place = read_pointer(bytes, elf.little_endian);
+            let target_vaddr = relocated.get(&slot_vaddr).copied().unwrap_or(in_place);
+            if target_vaddr != 0 && target_vaddr != terminator {
+                constructors.push((name.to_string(), slot_vaddr, target_vaddr));
+            }
+        }
+    }
+    constructors
+}
+
+/// Reads a pointer of up to 8 bytes.
+pub(crate) fn read_pointer(bytes: &[u8], little_endian: bool) -> u64 {
+    let mut buffer = [0; 8];
+    if little_endian {
+        buffer[..bytes.len()].copy_from_slice(bytes);
+        u64::from_le_bytes(buffer)
+    } else {
+        buffer[8 - bytes.len()..].copy_from_slice(bytes);
+        u64::from_be_bytes(buffer)
+    }
+}
+
+impl ConstructorReport {
+    /// The table rendered by `Display`.
+    pub fn to_table(&self) -> Table {
+        let mut table =
+            Table::new(&[Align::Right, Align::Left]).with_header(["constructors", "file"]);
+        for entry in &self.entries {
+            table.push_row([entry.count.to_string(), entry.filename.clone()]);
+        }
+        table.push_row([
+            Cell::styled(self.constructors.len().to_string(), Some(Style::Bold)),
+            format!("total in {} files", self.entries.len()).into(),
+        ]);
+        table
+    }
+}
+
+impl Display for ConstructorReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_table())
+    }
+}
+
+impl Display for StaticConstructor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {} -> {} {}",
+            self.section,
+            hex(self.slot_vaddr),
+            hex(self.target_vaddr),
+            self.filename.as_deref().unwrap_or("<unattributed>")
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Genealogy;
+
+    use super::read_pointer;
+
+    #[test]
+    fn test_static_constructors() {
+        assert_eq!(read_pointer(&[0x40, 0x11, 0, 0], true), 0x1140);
+        assert_eq!(read_pointer(&[0, 0, 0x11, 0x40], false), 0x1140);
+
+        // GNU ld writes the pointers in place, lld leaves them to relocations
+        for (binary, map) in [
+            ("tests/gcc/a.out", "tests/gcc/out.map"),
+            ("tests/clang/a.out", "tests/clang/output.map"),
+        ] {
+            let binary = std::fs::read(binary).unwrap();
+            let mapfile = std::fs::read_to_string(map).unwrap();
+            let genealogy = Genealogy::new(&mapfile, &binary).unwrap();
+            let report = genealogy.static_constructors(&binary).unwrap();
+
+            assert_eq!(report.constructors.len(), 1);
+            let constructor = &report.constructors[0];
+            assert_eq!(constructor.section, ".init_array");
+            assert_ne!(constructor.target_vaddr, 0);
+            assert!(constructor
+                .filename
+                .as_deref()
+                .is_some_and(|filename| filename.contains("crtbegin")));
+            assert_eq!(report.entries[0].count, 1);
+        }
+    }
+}
@@ -0,0 +1,203 @@
+//! What dead stripping (`--gc-sections` for GNU ld and lld, `/OPT:REF` for MSVC) removes: the
+//! input sections of the same project linked with and without it, compared per file, to weigh
+//! the option before enabling it.
+
+use std::{collections::BTreeMap, fmt::Display};
+
+use crate::{
+    format::{human_size, percentage, Align, Cell, Style, Table},
+    index::SubSection,
+    Genealogy,
+};
+
+/// Bytes removed by dead stripping per file, see [`Genealogy::dead_strip`].
+#[derive(Clone, Debug)]
+pub struct DeadStripReport {
+    /// Only files losing bytes, most stripped first.
+    pub entries: Vec<DeadStripEntry>,
+    /// Loaded bytes without dead stripping.
+    pub size: u64,
+    pub stripped: u64,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DeadStripEntry {
+    pub filename: String,
+    /// Loaded bytes of the file without dead stripping.
+    pub size: u64,
+    pub stripped: u64,
+    /// The input sections losing bytes, most stripped first.
+    pub sections: Vec<StrippedSection>,
+}
+
+impl DeadStripEntry {
+    /// Whether nothing of the file is left, e.g. an unused object pulled in by a wildcard.
+    pub fn is_removed(&self) -> bool {
+        self.stripped == self.size
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StrippedSection {
+    /// The input section, e.g. `.text.unused_function`.
+    pub name: String,
+    pub stripped: u64,
+}
+
+impl Genealogy {
+    /// Compares this genealogy, of a build without dead stripping, with the `stripped` build of
+    /// the same project. Input sections are matched by file and name, the bytes an input
+    /// section loses are stripped: all of them when it was removed, or a part of merged sections
+    /// such as `.eh_frame`.
+    pub fn dead_strip(&self, stripped: &Genealogy) -> DeadStripReport {
+        // Per file, per input section: the sizes of both builds
+        let mut sizes: BTreeMap<&str, BTreeMap<&str, (u64, u64)>> = BTreeMap::new();
+        let builds = loaded_subsections(self)
+            .map(|subsection| (subsection, false))
+            .chain(loaded_subsections(stripped).map(|subsection| (subsection, true)));
+        for (subsection, is_stripped) in builds {
+            let (size, kept) = sizes
+                .entry(&subsection.filename)
+                .or_default()
+                .entry(&subsection.name)
+                .or_default();
+            *if is_stripped { kept } else { size } += subsection.size;
+        }
+
+        let mut entries: Vec<DeadStripEntry> = sizes
+            .into_iter()
+            .filter_map(|(filename, sections)| {
+                let size = sections.values().map(|(size, _)| size).sum();
+                let mut sections: Vec<StrippedSection> = sections
+                    .into_iter()
+                    .filter(|(_, (size, kept))| size > kept)
+                    .map(|(name, (size, kept))| StrippedSection {
+                        name: name.to_string(),
+                        stripped: size - kept,
+                    })
+                    .collect();
+                // Stable sort, ties stay ordered by name
+                sections.sort_by_key(|section| std::cmp::Reverse(section.stripped));
+                (!sections.is_empty()).then(|| DeadStripEntry {
+                    filename: filename.to_string(),
+                    size,
+                    stripped: sections.iter().map(|section| section.stripped).sum(),
+                    sections,
+                })
+            })
+            .collect();
+        entries.sort_by_key(|entry| std::cmp::Reverse(entry.stripped));
+
+        DeadStripReport {
+            size: loaded_subsections(self)
+                .map(|subsection| subsection.size)
+                .sum(),
+            stripped: entries.iter().map(|entry| entry.stripped).sum(),
+            entries,
+        }
+    }
+}
+
+fn loaded_subsections(genealogy: &Genealogy) -> impl Iterator<Item = &SubSection> {
+    genealogy
+        .sections()
+        .iter()
+        .filter(|section| section.loaded)
+        .flat_map(|section| &section.subsections)
+}
+
+impl DeadStripReport {
+    /// The table rendered by `Display`, files removed entirely in bold.
+    pub fn to_table(&self) -> Table {
+        let mut table = Table::new(&[
+            Align::Right,
+            Align::Right,
+            Align::Right,
+            Align::Right,
+            Align::Left,
+            Align::Left,
+        ])
+        .with_header(["size", "stripped", "share", "sections", "biggest", "file"]);
+        for entry in &self.entries {
+            let style = entry.is_removed().then_some(Style::Bold);
+            table.push_row(
+                [
+                    human_size(entry.size),
+                    human_size(entry.stripped),
+                    percentage(entry.stripped, entry.size),
+                    entry.sections.len().to_string(),
+                    entry.sections[0].name.clone(),
+                    entry.filename.clone(),
+                ]
+                .map(|text| Cell::styled(text, style)),
+            );
+        }
+        table.push_row([
+            Cell::styled(human_size(self.size), Some(Style::Bold)),
+            Cell::styled(human_size(self.stripped), Some(Style::Bold)),
+            percentage(self.stripped, self.size).into(),
+            "".into(),
+            "".into(),
+            Cell::styled(
+                format!("total, {} files stripped", self.entries.len()),
+                Some(Style::Bold),
+            ),
+        ]);
+        table
+    }
+}
+
+impl Display for DeadStripReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_table())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Genealogy;
+
+    #[test]
+    fn test_dead_strip() {
+        let genealogy = |name: &str| {
+            let mapfile = std::fs::read_to_string(format!("tests/gc/{name}.map")).unwrap();
+            let binary = std::fs::read(format!("tests/gc/{name}")).unwrap();
+            Genealogy::new(&mapfile, &binary).unwrap()
+        };
+        let (full, collected) = (genealogy("full"), genealogy("gc"));
+
+        // `unused` and the buffer only it reads are stripped, with their unwind info
+        let report = full.dead_strip(&collected);
+        let lib = report
+            .entries
+            .iter()
+            .find(|entry| entry.filename == "lib.o")
+            .unwrap();
+        let sections: Vec<_> = lib
+            .sections
+            .iter()
+            .map(|section| (section.name.as_str(), section.stripped))
+            .collect();
+        assert_eq!(
+            sections,
+            [
+                (".data.unused_buffer", 0x100),
+                (".eh_frame", 0x14),
+                (".text.unused", 0x14)
+            ]
+        );
+        assert!(!lib.is_removed());
+        assert!(report
+            .entries
+            .iter()
+            .all(|entry| entry.filename != "keep.o"));
+        assert_eq!(
+            report.stripped,
+            report.entries.iter().map(|entry| entry.stripped).sum()
+        );
+
+        let report = full.dead_strip(&full);
+        assert!(report.entries.is_empty());
+        assert_eq!(report.stripped, 0);
+    }
+}
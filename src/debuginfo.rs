@@ -0,0 +1,369 @@
+//! Attribution of the debug sections, which often outweigh the code in debug builds: the map
+//! gives their input sections like any other, and the DWARF unit headers of `.debug_info` split
+//! it per compilation unit when the map does not.
+
+use crate::{Genealogy, Report};
+
+/// The prefixes of the names of DWARF sections, compressed (`.zdebug_info`) or not.
+pub const DEBUG_PREFIXES: [&str; 2] = [".debug_", ".zdebug_"];
+
+const DW_AT_NAME: u64 = 0x03;
+const DW_AT_LOW_PC: u64 = 0x11;
+
+const DW_FORM_ADDR: u64 = 0x01;
+const DW_FORM_STRING: u64 = 0x08;
+const DW_FORM_STRP: u64 = 0x0e;
+const DW_FORM_INDIRECT: u64 = 0x16;
+const DW_FORM_IMPLICIT_CONST: u64 = 0x21;
+const DW_FORM_LINE_STRP: u64 = 0x1f;
+
+/// Whether `name` is a DWARF section, e.g. `.debug_info` or `.debug_str`.
+pub fn is_debug_section(name: &str) -> bool {
+    DEBUG_PREFIXES.iter().any(|prefix| name.starts_with(prefix))
+}
+
+impl Genealogy {
+    /// Sums the debug section bytes each file contributes to `binary`, the file the genealogy
+    /// was built for.
+    ///
+    /// Input sections are taken from the map. A `.debug_info` the map gives no input sections
+    /// of is split into its compilation units, each attributed to the file owning its lowest
+    /// address (`DW_AT_low_pc`), or else named after its source (`DW_AT_name`). The other
+    /// sections without input sections, and compressed ones, are left unattributed.
+    pub fn debug_report(&self, binary: &[u8]) -> Report {
+        let mut contributions: Vec<(&str, u64)> = vec![];
+        for section in self.sections() {
+            if !is_debug_section(&section.name) {
+                continue;
+            }
+            let attributed: Vec<(&str, u64)> = section
+                .subsections
+                .iter()
+                .filter(|subsection| subsection.start_file_offset.is_some())
+                .map(|subsection| (subsection.filename.as_str(), subsection.size))
+                .collect();
+            if attributed.is_empty() && section.name == ".debug_info" {
+                contributions.extend(self.debug_info_units(binary));
+            }
+            contributions.extend(attributed);
+        }
+        Report::from_sizes(contributions)
+    }
+
+    /// The size and origin of the compilation units of `.debug_info`, none if a unit header
+    /// cannot be read, e.g. in compressed sections.
+    fn debug_info_units<'a>(&'a self, binary: &'a [u8]) -> Vec<(&'a str, u64)> {
+        let section = |name: &str| {
+            self.sections()
+                .iter()
+                .find(|section| section.name == name)
+                .and_then(|section| {
+                    let start = usize::try_from(section.start_file_offset?).ok()?;
+                    let end = start.checked_add(usize::try_from(section.size).ok()?)?;
+                    binary.get(start..end)
+                })
+                .unwrap_or_default()
+        };
+        let sections = DwarfSections {
+            info: section(".debug_info"),
+            abbrev: section(".debug_abbrev"),
+            strings: section(".debug_str"),
+            line_strings: section(".debug_line_str"),
+            // EI_DATA of ELF files, PE files are little-endian
+            big_endian: binary.starts_with(b"\x7fELF") && binary.get(5) == Some(&2),
+        };
+        let Some(units) = sections.units() else {
+            return vec![];
+        };
+        units
+            .into_iter()
+            .filter_map(|unit| {
+                let owner = unit
+                    .low_pc
+                    .filter(|&low_pc| low_pc != 0)
+                    .and_then(|low_pc| self.query_vaddr_point(low_pc).next())
+                    .map(|hit| hit.filename);
+                Some((owner.or(unit.name)?, unit.size))
+            })
+            .collect()
+    }
+}
+
+/// The DWARF sections the unit headers and the first entry of every unit are read from.
+struct DwarfSections<'a> {
+    info: &'a [u8],
+    abbrev: &'a [u8],
+    strings: &'a [u8],
+    line_strings: &'a [u8],
+    big_endian: bool,
+}
+
+/// A unit of `.debug_info`, with the attributes of its first entry.
+struct Unit<'a> {
+    /// Including its header.
+    size: u64,
+    name: Option<&'a str>,
+    low_pc: Option<u64>,
+}
+
+impl<'a> DwarfSections<'a> {
+    /// The units of `.debug_info`, `None` if they do not cover it exactly.
+    fn units(&self) -> Option<Vec<Unit<'a>>> {
+        let mut units = vec![];
+        let mut reader = Reader {
+            bytes: self.info,
+            position: 0,
+            big_endian: self.big_endian,
+        };
+        while reader.position < self.info.len() {
+            let start = reader.position;
+            let (length, offset_size) = match reader.uint(4)? {
+                0xffff_ffff => (reader.uint(8)?, 8),
+                length => (length, 4),
+            };
+            let end = reader.position.checked_add(usize::try_from(length).ok()?)?;
+            if end > self.info.len() {
+                return None;
+            }
+            let (name, low_pc) = self
+                .first_entry(&mut reader, offset_size)
+                .unwrap_or_default();
+            units.push(Unit {
+                size: (end - start) as u64,
+                name,
+                low_pc,
+            });
+            reader.position = end;
+        }
+        Some(units)
+    }
+
+    /// The name and lowest address of the entry following the unit header at `reader`.
+    fn first_entry(
+        &self,
+        reader: &mut Reader<'a>,
+        offset_size: usize,
+    ) -> Option<(Option<&'a str>, Option<u64>)> {
+        let version = reader.uint(2)?;
+        let (abbrev_offset, address_size) = match version {
+            2..=4 => {
+                let abbrev_offset = reader.uint(offset_size)?;
+                (abbrev_offset, reader.uint(1)? as usize)
+            }
+            5 => {
+                let unit_type = reader.uint(1)?;
+                let address_size = reader.uint(1)? as usize;
+                let abbrev_offset = reader.uint(offset_size)?;
+                match unit_type {
+                    // Skeleton and split compilation units have an ID
+                    4 | 5 => reader.skip(8)?,
+                    // Type units have a signature and a type offset
+                    2 | 6 => reader.skip(8 + offset_size)?,
+                    _ => {}
+                }
+                (abbrev_offset, address_size)
+            }
+            _ => return None,
+        };
+        let code = reader.uleb()?;
+        let attributes = self.abbreviation(usize::try_from(abbrev_offset).ok()?, code)?;
+
+        let mut name = None;
+        let mut low_pc = None;
+        for (attribute, mut form) in attributes {
+            if form == DW_FORM_INDIRECT {
+                form = reader.uleb()?;
+            }
+            match (attribute, form) {
+                (DW_AT_NAME, DW_FORM_STRING) => name = Some(reader.cstr()?),
+                (DW_AT_NAME, DW_FORM_STRP | DW_FORM_LINE_STRP) => {
+                    let strings = if form == DW_FORM_STRP {
+                        self.strings
+                    } else {
+                        self.line_strings
+                    };
+                    let offset = usize::try_from(reader.uint(offset_size)?).ok()?;
+                    name = Reader {
+                        bytes: strings,
+                        position: offset,
+                        big_endian: self.big_endian,
+                    }
+                    .cstr();
+                }
+                (DW_AT_LOW_PC, DW_FORM_ADDR) => low_pc = Some(reader.uint(address_size)?),
+                _ => reader.skip_form(form, address_size, offset_size, version)?,
+            }
+        }
+        Some((name, low_pc))
+    }
+
+    /// The attributes and forms of the abbreviation `code` of the table at `offset`.
+    fn abbreviation(&self, offset: usize, code: u64) -> Option<Vec<(u64, u64)>> {
+        let mut reader = Reader {
+            bytes: self.abbrev,
+            position: offset,
+            big_endian: self.big_endian,
+        };
+        loop {
+            let found = match reader.uleb()? {
+                0 => return None,
+                found => found,
+            };
+            let _tag = reader.uleb()?;
+            let _children = reader.uint(1)?;
+            let mut attributes = vec![];
+            loop {
+                let attribute = reader.uleb()?;
+                let form = reader.uleb()?;
+                if (attribute, form) == (0, 0) {
+                    break;
+                }
+                if form == DW_FORM_IMPLICIT_CONST {
+                    // The value is in the abbreviation, not in the entry
+                    reader.skip_sleb()?;
+                }
+                attributes.push((attribute, form));
+            }
+            if found == code {
+                return Some(attributes);
+            }
+        }
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+    big_endian: bool,
+}
+
+impl<'a> Reader<'a> {
+    fn skip(&mut self, len: usize) -> Option<()> {
+        let end = self.position.checked_add(len)?;
+        (end <= self.bytes.len()).then(|| self.position = end)
+    }
+
+    /// An unsigned integer of `size` bytes, at most 8.
+    fn uint(&mut self, size: usize) -> Option<u64> {
+        let bytes = self
+            .bytes
+            .get(self.position..self.position.checked_add(size)?)?;
+        self.position += size;
+        let fold = |value: u64, &byte: &u8| value << 8 | u64::from(byte);
+        Some(if self.big_endian {
+            bytes.iter().fold(0, fold)
+        } else {
+            bytes.iter().rev().fold(0, fold)
+        })
+    }
+
+    fn uleb(&mut self) -> Option<u64> {
+        let mut value = 0;
+        for shift in (0..64).step_by(7) {
+            let byte = self.uint(1)?;
+            value |= (byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Some(value);
+            }
+        }
+        None
+    }
+
+    /// Skips a signed LEB128, encoded like an unsigned one.
+    fn skip_sleb(&mut self) -> Option<()> {
+        self.uleb().map(|_| ())
+    }
+
+    fn cstr(&mut self) -> Option<&'a str> {
+        let rest = self.bytes.get(self.position..)?;
+        let len = rest.iter().position(|&byte| byte == 0)?;
+        self.position += len + 1;
+        std::str::from_utf8(&rest[..len]).ok()
+    }
+
+    /// Skips the value of an attribute of `form`, `None` for unknown forms.
+    fn skip_form(
+        &mut self,
+        form: u64,
+        address_size: usize,
+        offset_size: usize,
+        version: u64,
+    ) -> Option<()> {
+        match form {
+            0x01 => self.skip(address_size),
+            // block2, block4
+            0x03 => {
+                let len = self.uint(2)?;
+                self.skip(usize::try_from(len).ok()?)
+            }
+            0x04 => {
+                let len = self.uint(4)?;
+                self.skip(usize::try_from(len).ok()?)
+            }
+            // data2, data4, data8
+            0x05 => self.skip(2),
+            0x06 => self.skip(4),
+            0x07 => self.skip(8),
+            0x08 => self.cstr().map(|_| ()),
+            // block, exprloc
+            0x09 | 0x18 => {
+                let len = self.uleb()?;
+                self.skip(usize::try_from(len).ok()?)
+            }
+            0x0a => {
+                let len = self.uint(1)?;
+                self.skip(usize::try_from(len).ok()?)
+            }
+            // data1, flag, ref1, strx1, addrx1
+            0x0b | 0x0c | 0x11 | 0x25 | 0x29 => self.skip(1),
+            // sdata, udata, ref_udata, strx, addrx, loclistx, rnglistx
+            0x0d | 0x0f | 0x15 | 0x1a | 0x1b | 0x22 | 0x23 => self.uleb().map(|_| ()),
+            // strp, sec_offset, strp_sup, line_strp
+            0x0e | 0x17 | 0x1d | 0x1f => self.skip(offset_size),
+            // ref_addr, an address before DWARF 3
+            0x10 => self.skip(if version == 2 {
+                address_size
+            } else {
+                offset_size
+            }),
+            // ref2, strx2, addrx2
+            0x12 | 0x26 | 0x2a => self.skip(2),
+            // strx3, addrx3
+            0x27 | 0x2b => self.skip(3),
+            // ref4, ref_sup4, strx4, addrx4
+            0x13 | 0x1c | 0x28 | 0x2c => self.skip(4),
+            // ref8, ref_sig8, ref_sup8
+            0x14 | 0x20 | 0x24 => self.skip(8),
+            0x1e => self.skip(16),
+            // flag_present, implicit_const
+            0x19 | 0x21 => Some(()),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Genealogy;
+
+    use super::is_debug_section;
+
+    #[test]
+    fn test_debug_report() {
+        assert!(is_debug_section(".debug_info"));
+        assert!(is_debug_section(".zdebug_line"));
+        assert!(!is_debug_section(".text"));
+
+        let mapfile = std::fs::read_to_string("tests/gcc/out.map").unwrap();
+        let binary = std::fs::read("tests/gcc/a.out").unwrap();
+        let genealogy = Genealogy::new(&mapfile, &binary).unwrap();
+        let report = genealogy.debug_report(&binary);
+        let files: Vec<&str> = report.entries.iter().map(|e| e.filename.as_str()).collect();
+        assert_eq!(files[..2], ["simple.o", "simple2.o"]);
+
+        // The units of .debug_info are attributed to the files of the map entries
+        let mut units = genealogy.debug_info_units(&binary);
+        units.sort();
+        assert_eq!(units, [("simple.o", 0x26c), ("simple2.o", 0x60)]);
+    }
+}
@@ -0,0 +1,339 @@
+//! Files every object was compiled from, headers included, as recorded by the build system in
+//! Make depfiles (`-MD`) or Ninja's `.ninja_deps`. Joined with a report, this gives the size of
+//! the code including a header, e.g. to measure the footprint of a header-only library.
+
+use std::{collections::HashMap, fmt::Display};
+
+use crate::{
+    compile_commands::{lookup_object, normalize},
+    format::{human_size, percentage, Align, Cell, Style, Table},
+    GenealogyError, Report,
+};
+
+/// The first bytes of a `.ninja_deps` file, followed by its version.
+const NINJA_DEPS_SIGNATURE: &[u8] = b"# ninjadeps\n";
+
+/// Flag of the size of the records listing the inputs of an output, other records name paths.
+const NINJA_DEPS_RECORD: u32 = 0x8000_0000;
+
+/// The inputs of object files.
+#[derive(Clone, Debug, Default)]
+pub struct Dependencies {
+    /// Normalized object paths, as written by the build system.
+    inputs: HashMap<String, Vec<String>>,
+}
+
+impl Dependencies {
+    /// Parses a Make depfile, as written by `gcc -MD` or `clang -MD`. Rules without
+    /// prerequisites, such as the phony targets of `-MP`, are ignored.
+    pub fn parse_depfile(text: &str) -> Self {
+        let mut dependencies = Self::default();
+        let mut targets = vec![];
+        let mut prerequisites = vec![];
+        let mut in_prerequisites = false;
+        for (word, end_of_rule) in depfile_words(text) {
+            if !in_prerequisites && (word == ":" || word.ends_with(':')) {
+                let target = word.trim_end_matches(':');
+                if !target.is_empty() {
+                    targets.push(target.to_string());
+                }
+                in_prerequisites = true;
+            } else if in_prerequisites {
+                prerequisites.push(word);
+            } else {
+                targets.push(word);
+            }
+            if end_of_rule {
+                if !prerequisites.is_empty() {
+                    for target in &targets {
+                        dependencies.add(target, prerequisites.iter().map(String::as_str));
+                    }
+                }
+                targets.clear();
+                prerequisites.clear();
+                in_prerequisites = false;
+            }
+        }
+        dependencies
+    }
+
+    /// Parses a `.ninja_deps` log, of version 3 or 4. Outputs recorded several times keep their
+    /// last inputs, as Ninja does.
+    pub fn parse_ninja_deps(bytes: &[u8]) -> Result<Self, GenealogyError> {
+        let invalid = || GenealogyError::WrongNinjaDepsFormat;
+        let rest = bytes
+            .strip_prefix(NINJA_DEPS_SIGNATURE)
+            .ok_or_else(invalid)?;
+        let (version, mut rest) = split_u32(rest).ok_or_else(invalid)?;
+        // The modification time of the output, 4 bytes before version 4 and 8 since
+        let mtime_len = match version {
+            3 => 4,
+            4 => 8,
+            _ => return Err(invalid()),
+        };
+
+        let mut paths: Vec<String> = vec![];
+        let mut inputs: HashMap<usize, Vec<usize>> = HashMap::new();
+        while !rest.is_empty() {
+            let (size, body) = split_u32(rest).ok_or_else(invalid)?;
+            let len = (size & !NINJA_DEPS_RECORD) as usize;
+            if !len.is_multiple_of(4) {
+                return Err(invalid());
+            }
+            let record = body.get(..len).ok_or_else(invalid)?;
+            rest = &body[len..];
+
+            if size & NINJA_DEPS_RECORD == 0 {
+                // A path NUL-padded to 4 bytes, then a checksum of its id
+                let path = record.get(..len.saturating_sub(4)).ok_or_else(invalid)?;
+                let end = path.iter().rposition(|&b| b != 0).map_or(0, |i| i + 1);
+                let path = std::str::from_utf8(&path[..end]).map_err(|_| invalid())?;
+                paths.push(path.to_string());
+            } else {
+                let (output, ids) = split_u32(record).ok_or_else(invalid)?;
+                let ids = ids.get(mtime_len..).ok_or_else(invalid)?;
+                inputs.insert(
+                    output as usize,
+                    ids.chunks_exact(4)
+                        .map(|id| u32::from_le_bytes(id.try_into().expect("4 bytes")) as usize)
+                        .collect(),
+                );
+            }
+        }
+
+        let mut dependencies = Self::default();
+        for (output, ids) in inputs {
+            let output = paths.get(output).ok_or_else(invalid)?;
+            let ids = ids
+                .into_iter()
+                .map(|id| paths.get(id).map(String::as_str).ok_or_else(invalid))
+                .collect::<Result<Vec<_>, _>>()?;
+            dependencies.add(output, ids);
+        }
+        Ok(dependencies)
+    }
+
+    /// Adds the dependencies of `other`, replacing the inputs of the objects of both.
+    pub fn extend(&mut self, other: Self) {
+        self.inputs.extend(other.inputs);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inputs.is_empty()
+    }
+
+    /// The inputs of `origin`, its source file included. Archive members are looked up by object
+    /// name, and origins relative to another directory than the build's by path suffix.
+    pub fn inputs_of(&self, origin: &str) -> Option<&[String]> {
+        lookup_object(&self.inputs, origin).map(Vec::as_slice)
+    }
+
+    fn add<'a>(&mut self, object: &str, inputs: impl IntoIterator<Item = &'a str>) {
+        let mut inputs: Vec<String> = inputs.into_iter().map(normalize).collect();
+        inputs.sort();
+        inputs.dedup();
+        self.inputs.insert(normalize(object), inputs);
+    }
+}
+
+fn split_u32(bytes: &[u8]) -> Option<(u32, &[u8])> {
+    let (value, rest) = bytes.split_first_chunk::<4>()?;
+    Some((u32::from_le_bytes(*value), rest))
+}
+
+/// The words of a depfile, unescaped, and whether they end a rule. Backslash-newlines continue
+/// a rule, `\ ` and `\#` escape a space and a hash, `$$` a dollar sign.
+fn depfile_words(text: &str) -> Vec<(String, bool)> {
+    let mut words = vec![];
+    let mut word = String::new();
+    let mut chars = text.chars().peekable();
+    let mut push = |word: &mut String, end_of_rule: bool| {
+        if !word.is_empty() {
+            words.push((std::mem::take(word), end_of_rule));
+        } else if end_of_rule {
+            if let Some(last) = words.last_mut() {
+                last.1 = true;
+            }
+        }
+    };
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.peek() {
+                Some('\n') => {
+                    chars.next();
+                    push(&mut word, false);
+                }
+                Some('\r') => {
+                    chars.next();
+                    chars.next_if_eq(&'\n');
+                    push(&mut word, false);
+                }
+                Some(&escaped @ (' ' | '#')) => {
+                    chars.next();
+                    word.push(escaped);
+                }
+                _ => word.push(c),
+            },
+            '$' if chars.next_if_eq(&'$').is_some() => word.push('$'),
+            '\n' => push(&mut word, true),
+            ' ' | '\t' | '\r' => push(&mut word, false),
+            _ => word.push(c),
+        }
+    }
+    push(&mut word, true);
+    words
+}
+
+/// Size per input file, see [`Report::by_header`].
+#[derive(Clone, Debug)]
+pub struct HeaderReport {
+    /// Biggest first.
+    pub entries: Vec<HeaderEntry>,
+    /// The total of the report, not of the entries: objects count towards all of their inputs.
+    pub total: u64,
+}
+
+#[derive(Clone, Debug)]
+pub struct HeaderEntry {
+    pub header: String,
+    /// The size of the objects depending on the header.
+    pub size: u64,
+    pub files: u64,
+}
+
+impl Report {
+    /// Sums the entries of the report per file they depend on. Every input counts, the source
+    /// files compiled included, and report entries without known inputs are left out.
+    pub fn by_header(&self, dependencies: &Dependencies) -> HeaderReport {
+        let mut headers: HashMap<&str, (u64, u64)> = HashMap::new();
+        for entry in &self.entries {
+            for header in dependencies.inputs_of(&entry.filename).unwrap_or_default() {
+                let (size, files) = headers.entry(header).or_default();
+                *size += entry.size;
+                *files += 1;
+            }
+        }
+
+        let mut entries: Vec<HeaderEntry> = headers
+            .into_iter()
+            .map(|(header, (size, files))| HeaderEntry {
+                header: header.to_string(),
+                size,
+                files,
+            })
+            .collect();
+        entries.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.header.cmp(&b.header)));
+
+        HeaderReport {
+            entries,
+            total: self.total,
+        }
+    }
+}
+
+impl HeaderReport {
+    /// The table rendered by `Display`.
+    pub fn to_table(&self) -> Table {
+        let mut table = Table::new(&[Align::Right, Align::Right, Align::Right, Align::Left])
+            .with_header(["size", "share", "files", "header"]);
+        for entry in &self.entries {
+            table.push_row([
+                human_size(entry.size),
+                percentage(entry.size, self.total),
+                entry.files.to_string(),
+                entry.header.clone(),
+            ]);
+        }
+        table.push_row([
+            Cell::styled(human_size(self.total), Some(Style::Bold)),
+            percentage(self.total, self.total).into(),
+            String::new().into(),
+            "total".into(),
+        ]);
+        table
+    }
+}
+
+impl Display for HeaderReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_table())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Report, ReportEntry};
+
+    use super::Dependencies;
+
+    #[test]
+    fn test_by_header() {
+        let depfile = Dependencies::parse_depfile(
+            "CMakeFiles/fw.dir/src/motor.c.o: ../src/motor.c ../include/fmt.h \\\n \
+             ../include/my\\ config.h\n\n../include/fmt.h:\n",
+        );
+        assert_eq!(
+            depfile
+                .inputs_of("CMakeFiles/fw.dir/src/motor.c.o")
+                .unwrap(),
+            [
+                "../include/fmt.h",
+                "../include/my config.h",
+                "../src/motor.c"
+            ]
+        );
+        assert_eq!(depfile.inputs_of("../include/fmt.h"), None);
+
+        let mut log = b"# ninjadeps\n".to_vec();
+        log.extend(4u32.to_le_bytes());
+        for (id, path) in ["net/tcp.o", "src/tcp.c", "include/fmt.h"]
+            .iter()
+            .enumerate()
+        {
+            let mut record = path.as_bytes().to_vec();
+            record.resize(record.len().div_ceil(4) * 4, 0);
+            record.extend((!(id as u32)).to_le_bytes());
+            log.extend((record.len() as u32).to_le_bytes());
+            log.extend(record);
+        }
+        // tcp.o, its mtime, tcp.c and fmt.h
+        log.extend((0x8000_0000u32 | 20).to_le_bytes());
+        for word in [0u32, 0x1234, 0, 1, 2] {
+            log.extend(word.to_le_bytes());
+        }
+        let mut dependencies = Dependencies::parse_ninja_deps(&log).unwrap();
+        assert!(Dependencies::parse_ninja_deps(&log[..log.len() - 2]).is_err());
+        assert!(Dependencies::parse_ninja_deps(b"# ninjadeps\n\x02\0\0\0").is_err());
+        dependencies.extend(depfile);
+
+        let entry = |filename: &str, size| ReportEntry {
+            filename: filename.to_string(),
+            size,
+            source: None,
+        };
+        let report = Report {
+            entries: vec![
+                entry("/build/CMakeFiles/fw.dir/src/motor.c.o", 300),
+                entry("libnet.a(net/tcp.o)", 200),
+                entry("crtbeginS.o", 10),
+            ],
+            total: 510,
+        };
+        let headers: Vec<_> = report
+            .by_header(&dependencies)
+            .entries
+            .into_iter()
+            .map(|e| (e.header, e.size, e.files))
+            .collect();
+        assert_eq!(
+            headers,
+            [
+                ("../include/fmt.h".to_string(), 300, 1),
+                ("../include/my config.h".to_string(), 300, 1),
+                ("../src/motor.c".to_string(), 300, 1),
+                ("include/fmt.h".to_string(), 200, 1),
+                ("src/tcp.c".to_string(), 200, 1),
+            ]
+        );
+    }
+}
@@ -0,0 +1,144 @@
+//! Comparison of two [`Report`]s, typically of the same program built from two revisions.
+
+use std::{collections::BTreeMap, fmt::Display};
+
+use crate::{
+    format::{human_delta, human_size, Align, Cell, Style, Table},
+    Report,
+};
+
+/// Per-file size changes between two reports, see [`Report::diff`].
+#[derive(Clone, Debug)]
+pub struct ReportDiff {
+    /// Only files whose size changed, biggest absolute change first.
+    pub entries: Vec<DiffEntry>,
+    pub old_total: u64,
+    pub new_total: u64,
+}
+
+#[derive(Clone, Debug)]
+pub struct DiffEntry {
+    pub filename: String,
+    /// 0 if the file is new.
+    pub old_size: u64,
+    /// 0 if the file disappeared.
+    pub new_size: u64,
+}
+
+impl DiffEntry {
+    pub fn delta(&self) -> i64 {
+        delta(self.old_size, self.new_size)
+    }
+}
+
+impl ReportDiff {
+    pub fn delta(&self) -> i64 {
+        delta(self.old_total, self.new_total)
+    }
+}
+
+/// `new - old`, saturating instead of wrapping for sizes that do not fit in an `i64`.
+fn delta(old: u64, new: u64) -> i64 {
+    if new >= old {
+        i64::try_from(new - old).unwrap_or(i64::MAX)
+    } else {
+        i64::try_from(old - new).map_or(i64::MIN, |shrink| -shrink)
+    }
+}
+
+impl Report {
+    /// Compares this (old) report with a `new` one.
+    pub fn diff(&self, new: &Report) -> ReportDiff {
+        let mut sizes: BTreeMap<&str, (u64, u64)> = BTreeMap::new();
+        for entry in &self.entries {
+            sizes.entry(&entry.filename).or_default().0 += entry.size;
+        }
+        for entry in &new.entries {
+            sizes.entry(&entry.filename).or_default().1 += entry.size;
+        }
+
+        let mut entries: Vec<DiffEntry> = sizes
+            .into_iter()
+            .filter(|(_, (old_size, new_size))| old_size != new_size)
+            .map(|(filename, (old_size, new_size))| DiffEntry {
+                filename: filename.to_string(),
+                old_size,
+                new_size,
+            })
+            .collect();
+        // Stable sort, ties stay ordered by file name
+        entries.sort_by_key(|e| std::cmp::Reverse(e.delta().unsigned_abs()));
+
+        ReportDiff {
+            entries,
+            old_total: self.total,
+            new_total: new.total,
+        }
+    }
+}
+
+impl ReportDiff {
+    /// The table rendered by `Display`, with deltas styled by [`Style::for_delta`].
+    pub fn to_table(&self) -> Table {
+        let mut table = Table::new(&[Align::Right, Align::Right, Align::Right, Align::Left])
+            .with_header(["old", "new", "delta", "file"]);
+        for entry in &self.entries {
+            table.push_row([
+                human_size(entry.old_size).into(),
+                human_size(entry.new_size).into(),
+                Cell::styled(human_delta(entry.delta()), Style::for_delta(entry.delta())),
+                entry.filename.as_str().into(),
+            ]);
+        }
+        table.push_row([
+            human_size(self.old_total).into(),
+            human_size(self.new_total).into(),
+            Cell::styled(human_delta(self.delta()), Style::for_delta(self.delta())),
+            format!("total, {} files changed", self.entries.len()).into(),
+        ]);
+        table
+    }
+}
+
+impl Display for ReportDiff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_table())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Report, ReportEntry};
+
+    fn report(entries: &[(&str, u64)]) -> Report {
+        Report {
+            entries: entries
+                .iter()
+                .map(|&(filename, size)| ReportEntry {
+                    filename: filename.to_string(),
+                    size,
+                    source: None,
+                })
+                .collect(),
+            total: entries.iter().map(|&(_, size)| size).sum(),
+        }
+    }
+
+    #[test]
+    fn test_diff() {
+        let old = report(&[("a.o", 10), ("b.o", 5), ("c.o", 7)]);
+        let new = report(&[("a.o", 12), ("c.o", 7), ("d.o", 100)]);
+        let diff = old.diff(&new);
+
+        assert_eq!(diff.delta(), 97);
+        let changes: Vec<_> = diff
+            .entries
+            .iter()
+            .map(|e| (e.filename.as_str(), e.delta()))
+            .collect();
+        assert_eq!(changes, [("d.o", 100), ("b.o", -5), ("a.o", 2)]);
+
+        assert_eq!(super::delta(0, u64::MAX), i64::MAX);
+        assert_eq!(super::delta(u64::MAX, 0), i64::MIN);
+    }
+}
@@ -0,0 +1,106 @@
+//! Disassembly listings annotated with provenance: the output of `objdump -d` or
+//! `llvm-objdump -d`, with a banner comment wherever the file owning the instructions changes.
+
+use std::fmt::Write;
+
+use crate::Genealogy;
+
+impl Genealogy {
+    /// Annotates `disassembly`, a listing of `objdump -d` or `llvm-objdump -d` of the binary, with
+    /// a `;; <file>` line before every run of function labels and instructions whose virtual
+    /// address belongs to the same file, or `;; unattributed`. Every section of the listing
+    /// starts with a banner, the other lines are kept as is.
+    pub fn annotate_disassembly(&self, disassembly: &str) -> String {
+        let mut annotated = String::with_capacity(disassembly.len());
+        // The banner of the current run, `None` before the first one of a section
+        let mut current: Option<String> = None;
+        for line in disassembly.lines() {
+            if line.starts_with("Disassembly of section ") {
+                current = None;
+            } else if let Some(vaddr) = line_address(line) {
+                let banner = match self.query_vaddr_point(vaddr).next() {
+                    Some(hit) if hit.folded_with.is_empty() => hit.filename.to_string(),
+                    Some(hit) => format!(
+                        "{} (folded with {})",
+                        hit.filename,
+                        hit.folded_with.join(", ")
+                    ),
+                    None => "unattributed".to_string(),
+                };
+                if current.as_ref() != Some(&banner) {
+                    writeln!(annotated, ";; {banner}").expect("writing to a String");
+                    current = Some(banner);
+                }
+            }
+            annotated.push_str(line);
+            annotated.push('\n');
+        }
+        annotated
+    }
+}
+
+/// The address of a function label (`0000000000001149 <f>:`) or of an instruction
+/// (`    1149: f3 0f 1e fa  endbr64`, tab separated) of a listing.
+fn line_address(line: &str) -> Option<u64> {
+    let line = line.trim_start();
+    let end = line
+        .find(|c: char| !c.is_ascii_hexdigit())
+        .filter(|&end| end > 0)?;
+    let rest = &line[end..];
+    let is_label = rest.starts_with(" <") && rest.trim_end().ends_with(">:");
+    (rest.starts_with(':') || is_label)
+        .then(|| u64::from_str_radix(&line[..end], 16).ok())
+        .flatten()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Genealogy;
+
+    #[test]
+    fn test_annotate_disassembly() {
+        let mapfile = std::fs::read_to_string("tests/gcc/out.map").unwrap();
+        let binary = std::fs::read("tests/gcc/a.out").unwrap();
+        let genealogy = Genealogy::new(&mapfile, &binary).unwrap();
+        // objdump -d, abridged
+        let disassembly = "
+tests/gcc/a.out:     file format elf64-x86-64
+
+
+Disassembly of section .text:
+
+0000000000001149 <f>:
+    1149:	f3 0f 1e fa          	endbr64
+    114d:	8d 04 3f             	lea    (%rdi,%rdi,1),%eax
+    1150:	c3                   	ret
+
+0000000000001151 <main>:
+    1151:	f3 0f 1e fa          	endbr64
+
+0000000000001198 <f2>:
+    1198: f3 0f 1e fa                  	endbr64
+    119c: 8d 04 7f                     	leal	(%rdi,%rdi,2), %eax
+    119f: c3                           	retq
+
+Disassembly of section .fini:
+
+00000000000011a0 <_fini>:
+    11a0:	f3 0f 1e fa          	endbr64
+";
+        let annotated = genealogy.annotate_disassembly(disassembly);
+        let banners: Vec<&str> = annotated
+            .lines()
+            .filter(|line| line.starts_with(";;"))
+            .collect();
+        assert_eq!(banners[..2], [";; simple.o", ";; simple2.o"]);
+        assert!(banners[2].ends_with("/crti.o"));
+        assert!(annotated.contains(";; simple.o\n0000000000001149 <f>:\n"));
+        assert_eq!(
+            annotated
+                .lines()
+                .filter(|line| !line.starts_with(";;"))
+                .count(),
+            disassembly.lines().count()
+        );
+    }
+}
@@ -0,0 +1,287 @@
+//! Why a file offset is attributed to a file: the map line a subsection was parsed from, the
+//! section of the binary that placed it, and the arithmetic in between, for users who suspect a
+//! wrong attribution.
+
+use std::fmt::Display;
+
+use crate::{
+    format::{hex, human_size},
+    parse::{mapfile_format, MapfileFormat},
+    Addressing, Genealogy, Section, SubSection,
+};
+
+/// How the addresses of the map were turned into addresses of the binary.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Placement {
+    /// The map gives virtual addresses, shifted by `vaddr_bias`, and the ELF section headers
+    /// give the file offset of every section.
+    Elf { vaddr_bias: i64 },
+    /// The map gives offsets in numbered sections, which the PE section headers place.
+    Pe { image_base: u64 },
+    /// The map gives virtual addresses, and the Mach-O section headers give the file offset of
+    /// every section.
+    MachO,
+    /// The map gives offsets in the code section and addresses in linear memory, which the
+    /// sections and data segments of the module place.
+    Wasm,
+}
+
+/// The derivations of the subsections containing a file offset, see [`Genealogy::explain`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Explanation<'a> {
+    pub point: u64,
+    pub placement: Placement,
+    /// Several when subsections overlap, none when the offset is not attributed.
+    pub derivations: Vec<Derivation<'a>>,
+}
+
+/// How a subsection containing the offset got its file offsets.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Derivation<'a> {
+    pub section: &'a Section,
+    /// The number of the section in MSVC maps, its index otherwise.
+    pub section_index: usize,
+    pub subsection: &'a SubSection,
+    /// The 1-based number and text of the map line of the subsection, `None` when it was not
+    /// parsed from a line, e.g. for PE resources.
+    pub map_line: Option<(usize, &'a str)>,
+    /// The address the map gives for the subsection, a section offset in MSVC maps.
+    pub map_vaddr: u64,
+}
+
+impl Genealogy {
+    /// Explains the attribution of the file offset `point`: for every subsection containing it,
+    /// the line of `mapfile` it comes from, the section of the binary giving its file offset and
+    /// the arithmetic applied. `mapfile` is the map the genealogy was built from, whose lines
+    /// the subsections point at.
+    pub fn explain<'a>(&'a self, mapfile: &'a str, point: u64) -> Explanation<'a> {
+        let placement = match self.addressing {
+            Addressing::Elf { .. } => Placement::Elf {
+                vaddr_bias: self.vaddr_bias,
+            },
+            Addressing::Pe { image_base } => Placement::Pe { image_base },
+            Addressing::MachO => Placement::MachO,
+            Addressing::Wasm => Placement::Wasm,
+        };
+        let mut derivations = vec![];
+        for (section_index, section) in self.sections().iter().enumerate() {
+            for subsection in &section.subsections {
+                let contains = subsection.start_file_offset.is_some_and(|start| {
+                    (start..start.saturating_add(subsection.size)).contains(&point)
+                });
+                if !contains {
+                    continue;
+                }
+                let map_vaddr = match placement {
+                    Placement::Elf { vaddr_bias } => subsection
+                        .start_vaddr
+                        .wrapping_add_signed(vaddr_bias.wrapping_neg()),
+                    Placement::Pe { .. } => subsection.start_vaddr - section.start_vaddr,
+                    Placement::MachO | Placement::Wasm => subsection.start_vaddr,
+                };
+                derivations.push(Derivation {
+                    section,
+                    section_index,
+                    subsection,
+                    map_line: subsection
+                        .line
+                        .and_then(|line| Some((line, line_text(mapfile, line)?))),
+                    map_vaddr,
+                });
+            }
+        }
+        Explanation {
+            point,
+            placement,
+            derivations,
+        }
+    }
+}
+
+/// The text of the 1-based `line` of `mapfile`, with the next line for GNU input sections and
+/// SEGGER entries whose name did not fit its column, and for the sections and files of Renesas
+/// maps, whose range is always on the next line.
+fn line_text(mapfile: &str, line: usize) -> Option<&str> {
+    let start: usize = mapfile
+        .split_inclusive('\n')
+        .take(line.saturating_sub(1))
+        .map(str::len)
+        .sum();
+    let mut lines = mapfile[start..].split_inclusive('\n');
+    let mut end = start + lines.next()?.len();
+    let wrapped = match mapfile_format(mapfile) {
+        MapfileFormat::Gnu | MapfileFormat::Gold | MapfileFormat::Microchip => {
+            !mapfile[start..end].contains("0x")
+        }
+        MapfileFormat::Segger => mapfile[start..end].split_whitespace().count() == 2,
+        MapfileFormat::Renesas => mapfile[start..end].split_whitespace().count() == 1,
+        _ => false,
+    };
+    if wrapped {
+        end += lines.next().map_or(0, str::len);
+    }
+    Some(mapfile[start..end].trim_end())
+}
+
+impl Derivation<'_> {
+    /// The steps from the map line to the offset, one sentence each.
+    pub fn steps(&self, placement: Placement, point: u64) -> Vec<String> {
+        let section = self.section;
+        let subsection = self.subsection;
+        let file_offset = subsection.start_file_offset.unwrap_or_default();
+        let section_file_offset = section.start_file_offset.unwrap_or_default();
+        let into = point - file_offset;
+        let mut steps = vec![match self.map_line {
+            Some((number, text)) => format!("map line {number}: {}", text.trim()),
+            None => "not parsed from a line of the map".to_string(),
+        }];
+        match placement {
+            Placement::Elf { .. } | Placement::MachO | Placement::Wasm => {
+                let vaddr_bias = match placement {
+                    Placement::Elf { vaddr_bias } => vaddr_bias,
+                    _ => 0,
+                };
+                if vaddr_bias == 0 {
+                    steps.push(format!(
+                        "the map places it at {}, in {} at {}",
+                        hex(self.map_vaddr),
+                        section.name,
+                        hex(section.start_vaddr)
+                    ));
+                } else {
+                    steps.push(format!(
+                        "the map places it at {}, biased by {}{:#x} to {}, in {} at {}",
+                        hex(self.map_vaddr),
+                        if vaddr_bias < 0 { "-" } else { "" },
+                        vaddr_bias.unsigned_abs(),
+                        hex(subsection.start_vaddr),
+                        section.name,
+                        hex(section.start_vaddr)
+                    ));
+                }
+                steps.push(format!(
+                    "the binary has {} at file offset {}",
+                    section.name,
+                    hex(section_file_offset)
+                ));
+                steps.push(format!(
+                    "file offset {} + ({} - {}) = {}",
+                    hex(section_file_offset),
+                    hex(subsection.start_vaddr),
+                    hex(section.start_vaddr),
+                    hex(file_offset)
+                ));
+            }
+            Placement::Pe { image_base } => {
+                let rva = section.start_vaddr - image_base;
+                steps.push(format!(
+                    "the map places it at offset {} of section {} ({})",
+                    hex(self.map_vaddr),
+                    self.section_index,
+                    section.name
+                ));
+                steps.push(format!(
+                    "the binary has {} at RVA {}, its raw data at file offset {}",
+                    section.name,
+                    hex(rva),
+                    hex(section_file_offset)
+                ));
+                steps.push(format!(
+                    "file offset {} + {} = {}",
+                    hex(section_file_offset),
+                    hex(self.map_vaddr),
+                    hex(file_offset)
+                ));
+                steps.push(format!(
+                    "vaddr {} + {} + {} = {}",
+                    hex(image_base),
+                    hex(rva),
+                    hex(self.map_vaddr),
+                    hex(subsection.start_vaddr)
+                ));
+            }
+        }
+        steps.push(format!(
+            "{} is {into} bytes in, at vaddr {} + {into} = {}",
+            hex(point),
+            hex(subsection.start_vaddr),
+            hex(subsection.start_vaddr.wrapping_add(into))
+        ));
+        steps
+    }
+}
+
+impl Display for Explanation<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.derivations.is_empty() {
+            return writeln!(f, "no subsection contains file offset {}", hex(self.point));
+        }
+        for derivation in &self.derivations {
+            let subsection = derivation.subsection;
+            let start = subsection.start_file_offset.unwrap_or_default();
+            write!(
+                f,
+                "{} of {}, file offsets {}..{} ({})",
+                if subsection.name.is_empty() {
+                    "contribution"
+                } else {
+                    &subsection.name
+                },
+                subsection.filename,
+                hex(start),
+                hex(start + subsection.size),
+                human_size(subsection.size)
+            )?;
+            if !subsection.folded_with.is_empty() {
+                write!(f, ", folded with {}", subsection.folded_with.join(", "))?;
+            }
+            writeln!(f)?;
+            for step in derivation.steps(self.placement, self.point) {
+                writeln!(f, "  {step}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Genealogy;
+
+    #[test]
+    fn test_explain() {
+        let mapfile = std::fs::read_to_string("tests/gcc/out.map").unwrap();
+        let binary = std::fs::read("tests/gcc/a.out").unwrap();
+        let genealogy = Genealogy::new(&mapfile, &binary).unwrap();
+        let explanation = genealogy.explain(&mapfile, 0x1150);
+        assert_eq!(explanation.derivations.len(), 1);
+        let derivation = &explanation.derivations[0];
+        assert_eq!(derivation.subsection.filename, "simple.o");
+        assert_eq!(derivation.map_line.map(|(number, _)| number), Some(190));
+        let steps = derivation.steps(explanation.placement, explanation.point);
+        assert_eq!(
+            steps,
+            [
+                "map line 190: .text          0x0000000000001149       0x4f simple.o",
+                "the map places it at 0x00001149, in .text at 0x00001060",
+                "the binary has .text at file offset 0x00001060",
+                "file offset 0x00001060 + (0x00001149 - 0x00001060) = 0x00001149",
+                "0x00001150 is 7 bytes in, at vaddr 0x00001149 + 7 = 0x00001150",
+            ]
+        );
+
+        // Windows, from a run of static symbols
+        let mapfile = std::fs::read_to_string("tests/cargo-windows/out.map").unwrap();
+        let binary = std::fs::read("tests/cargo-windows/genealogy.exe").unwrap();
+        let genealogy = Genealogy::new(&mapfile, &binary).unwrap();
+        let hit = genealogy.iter().nth(10).unwrap();
+        let explanation = genealogy.explain(&mapfile, hit.range.start);
+        assert!(explanation.derivations[0].map_line.is_some());
+        assert!(explanation.to_string().contains("of section 1 (.text)"));
+
+        assert_eq!(
+            genealogy.explain(&mapfile, 0).to_string(),
+            "no subsection contains file offset 0x00000000\n"
+        );
+    }
+}
@@ -0,0 +1,197 @@
+//! The export surface of a shared library: the symbols of its dynamic symbol table, attributed
+//! to the object files defining them, to find the files to build with `-fvisibility=hidden` or
+//! to cover with a version script.
+
+use std::{collections::HashMap, fmt::Display};
+
+use goblin::{
+    elf::sym::{STB_GLOBAL, STB_GNU_UNIQUE, STB_WEAK, STT_FILE, STT_SECTION, STV_HIDDEN},
+    Object,
+};
+
+use crate::{
+    format::{hex, human_size, Align, Cell, Style, Table},
+    symbols::demangle,
+    Genealogy, GenealogyError,
+};
+
+/// A symbol exported by the binary, see [`Genealogy::export_report`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ExportedSymbol {
+    /// The name as found in `.dynsym`, without its version.
+    pub name: String,
+    pub demangled: Option<String>,
+    pub vaddr: u64,
+    pub size: u64,
+    pub weak: bool,
+    /// The object file defining the symbol, `None` if the map does not cover its address.
+    pub filename: Option<String>,
+}
+
+/// The dynamic symbols of the binary and their count and size per file, see
+/// [`Genealogy::export_report`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ExportReport {
+    /// In address order.
+    pub symbols: Vec<ExportedSymbol>,
+    /// Files with the most exports first.
+    pub entries: Vec<ExportEntry>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ExportEntry {
+    pub filename: String,
+    pub exports: u64,
+    /// Bytes of the exported symbols.
+    pub size: u64,
+}
+
+impl Genealogy {
+    /// Lists the symbols the ELF `binary` the genealogy was built from exports: the defined
+    /// global, weak and unique symbols of `.dynsym` that are not hidden, attributed to the file
+    /// their address falls in. Symbols the map does not cover go to `<unattributed>`.
+    pub fn export_report(&self, binary: &[u8]) -> Result<ExportReport, GenealogyError> {
+        let Object::Elf(elf) = Object::parse(binary).map_err(GenealogyError::BinaryParse)? else {
+            return Err(GenealogyError::UnsupportedBinaryFormat);
+        };
+
+        let mut symbols = vec![];
+        for sym in elf.dynsyms.iter() {
+            if sym.st_shndx == 0
+                || !matches!(sym.st_bind(), STB_GLOBAL | STB_WEAK | STB_GNU_UNIQUE)
+                || matches!(sym.st_type(), STT_SECTION | STT_FILE)
+                || sym.st_visibility() == STV_HIDDEN
+            {
+                continue;
+            }
+            let Some(name) = elf
+                .dynstrtab
+                .get_at(sym.st_name)
+                .filter(|name| !name.is_empty())
+            else {
+                continue;
+            };
+            symbols.push(ExportedSymbol {
+                name: name.to_string(),
+                demangled: demangle(name),
+                vaddr: sym.st_value,
+                size: sym.st_size,
+                weak: sym.st_bind() == STB_WEAK,
+                filename: self
+                    .query_vaddr_point(sym.st_value)
+                    .next()
+                    .map(|hit| hit.filename.to_string()),
+            });
+        }
+        symbols.sort_by(|a, b| a.vaddr.cmp(&b.vaddr).then_with(|| a.name.cmp(&b.name)));
+
+        let mut files: HashMap<&str, (u64, u64)> = HashMap::new();
+        for symbol in &symbols {
+            let entry = files
+                .entry(symbol.filename.as_deref().unwrap_or("<unattributed>"))
+                .or_default();
+            entry.0 += 1;
+            entry.1 += symbol.size;
+        }
+        let mut entries: Vec<ExportEntry> = files
+            .into_iter()
+            .map(|(filename, (exports, size))| ExportEntry {
+                filename: filename.to_string(),
+                exports,
+                size,
+            })
+            .collect();
+        entries.sort_by(|a, b| {
+            b.exports
+                .cmp(&a.exports)
+                .then_with(|| b.size.cmp(&a.size))
+                .then_with(|| a.filename.cmp(&b.filename))
+        });
+
+        Ok(ExportReport { symbols, entries })
+    }
+}
+
+impl ExportReport {
+    /// The bytes of all exported symbols.
+    pub fn total(&self) -> u64 {
+        self.symbols.iter().map(|symbol| symbol.size).sum()
+    }
+
+    /// The table rendered by `Display`.
+    pub fn to_table(&self) -> Table {
+        let mut table = Table::new(&[Align::Right, Align::Right, Align::Left])
+            .with_header(["exports", "size", "file"]);
+        for entry in &self.entries {
+            table.push_row([
+                entry.exports.to_string(),
+                human_size(entry.size),
+                entry.filename.clone(),
+            ]);
+        }
+        table.push_row([
+            Cell::styled(self.symbols.len().to_string(), Some(Style::Bold)),
+            Cell::styled(human_size(self.total()), Some(Style::Bold)),
+            format!("total in {} files", self.entries.len()).into(),
+        ]);
+        table
+    }
+}
+
+impl Display for ExportReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_table())
+    }
+}
+
+impl Display for ExportedSymbol {
+    /// E.g. `0x00001000 api_open api.o`.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {} {}",
+            hex(self.vaddr),
+            self.demangled.as_deref().unwrap_or(&self.name),
+            self.filename.as_deref().unwrap_or("<unattributed>")
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Genealogy, GenealogyError};
+
+    #[test]
+    fn test_export_report() {
+        let mapfile = std::fs::read_to_string("tests/exports/out.map").unwrap();
+        let binary = std::fs::read("tests/exports/libapi.so").unwrap();
+        let genealogy = Genealogy::new(&mapfile, &binary).unwrap();
+        let report = genealogy.export_report(&binary).unwrap();
+
+        // Hidden and static symbols stay out, as do those of `util.o` but the one it marked
+        let entries: Vec<_> = report
+            .entries
+            .iter()
+            .map(|entry| (entry.filename.as_str(), entry.exports, entry.size))
+            .collect();
+        assert_eq!(
+            entries,
+            [
+                ("api.o", 3, 12 + 4 + 4),
+                ("impl.o", 2, 14 + 14),
+                ("util.o", 1, 5)
+            ]
+        );
+        assert_eq!(
+            report.symbols[0].to_string(),
+            "0x00001000 api_open api.o".to_string()
+        );
+        assert_eq!(report.total(), 53);
+
+        let binary = std::fs::read("tests/macho/app").unwrap();
+        assert!(matches!(
+            genealogy.export_report(&binary),
+            Err(GenealogyError::UnsupportedBinaryFormat)
+        ));
+    }
+}
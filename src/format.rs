@@ -0,0 +1,255 @@
+//! Formatting helpers shared by the `Display` impls and the command line tool, so that sizes and
+//! tables are rendered the same way everywhere.
+
+use std::fmt::Display;
+
+const UNITS: [&str; 5] = ["KiB", "MiB", "GiB", "TiB", "PiB"];
+
+/// Formats a byte count with binary units, e.g. `1.24 MiB`.
+pub fn human_size(size: u64) -> String {
+    if size < 1024 {
+        return format!("{size} B");
+    }
+    let mut value = size as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{value:.2} {}", UNITS[unit])
+}
+
+/// Formats a signed size change, e.g. `+1.24 MiB` or `-12 B`.
+pub fn human_delta(delta: i64) -> String {
+    match delta {
+        0 => "0 B".to_string(),
+        d if d > 0 => format!("+{}", human_size(d.unsigned_abs())),
+        d => format!("-{}", human_size(d.unsigned_abs())),
+    }
+}
+
+/// Formats `part` as a percentage of `total` with two decimals, e.g. `43.71%`.
+///
+/// An empty total yields `0.00%` rather than `NaN%`.
+pub fn percentage(part: u64, total: u64) -> String {
+    let value = if total == 0 {
+        0.0
+    } else {
+        part as f64 * 100.0 / total as f64
+    };
+    format!("{value:.2}%")
+}
+
+/// Formats an address or offset as zero-padded hexadecimal, e.g. `0x00001149`.
+pub fn hex(value: u64) -> String {
+    format!("0x{value:08x}")
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Align {
+    Left,
+    Right,
+}
+
+/// Terminal styles a [`Cell`] can be rendered with when color is enabled on its [`Table`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Style {
+    Bold,
+    Dim,
+    Red,
+    Green,
+}
+
+impl Style {
+    fn ansi_code(self) -> &'static str {
+        match self {
+            Style::Bold => "1",
+            Style::Dim => "2",
+            Style::Red => "31",
+            Style::Green => "32",
+        }
+    }
+
+    /// The style for a size change: growth is red, shrinkage green.
+    pub fn for_delta(delta: i64) -> Option<Style> {
+        match delta {
+            d if d > 0 => Some(Style::Red),
+            d if d < 0 => Some(Style::Green),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Cell {
+    pub text: String,
+    pub style: Option<Style>,
+}
+
+impl Cell {
+    pub fn styled(text: impl Into<String>, style: Option<Style>) -> Self {
+        Self {
+            text: text.into(),
+            style,
+        }
+    }
+}
+
+impl From<String> for Cell {
+    fn from(text: String) -> Self {
+        Self { text, style: None }
+    }
+}
+
+impl From<&str> for Cell {
+    fn from(text: &str) -> Self {
+        Self::from(text.to_string())
+    }
+}
+
+/// A table whose columns are padded to the width of their widest cell.
+///
+/// The last column is never padded, so long trailing cells (typically file names) do not drag
+/// whitespace along. Styles are only emitted as ANSI escapes once color is enabled with
+/// [`Table::set_color`], and never count towards column widths.
+#[derive(Clone, Debug)]
+pub struct Table {
+    alignments: Vec<Align>,
+    header: Option<Vec<Cell>>,
+    rows: Vec<Vec<Cell>>,
+    color: bool,
+}
+
+impl Table {
+    pub fn new(alignments: &[Align]) -> Self {
+        Self {
+            alignments: alignments.to_vec(),
+            header: None,
+            rows: vec![],
+            color: false,
+        }
+    }
+
+    /// Adds a header row, rendered in bold when color is enabled.
+    pub fn with_header<I, S>(mut self, titles: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.header = Some(
+            titles
+                .into_iter()
+                .map(|title| Cell::styled(title, Some(Style::Bold)))
+                .collect(),
+        );
+        self
+    }
+
+    pub fn set_color(&mut self, color: bool) {
+        self.color = color;
+    }
+
+    pub fn push_row<I, C>(&mut self, cells: I)
+    where
+        I: IntoIterator<Item = C>,
+        C: Into<Cell>,
+    {
+        self.rows.push(cells.into_iter().map(Into::into).collect());
+    }
+
+    /// Whether the table has no rows, not counting the header.
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    pub fn alignments(&self) -> &[Align] {
+        &self.alignments
+    }
+
+    pub fn header(&self) -> Option<&[Cell]> {
+        self.header.as_deref()
+    }
+
+    /// The rows, without the header.
+    pub fn rows(&self) -> &[Vec<Cell>] {
+        &self.rows
+    }
+
+    fn all_rows(&self) -> impl Iterator<Item = &Vec<Cell>> {
+        self.header.iter().chain(&self.rows)
+    }
+
+    fn widths(&self) -> Vec<usize> {
+        let mut widths = vec![0; self.alignments.len()];
+        for row in self.all_rows() {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = (*width).max(cell.text.chars().count());
+            }
+        }
+        widths
+    }
+}
+
+impl Display for Table {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let widths = self.widths();
+        for (row_nb, row) in self.all_rows().enumerate() {
+            if row_nb > 0 {
+                writeln!(f)?;
+            }
+            for (column, cell) in row.iter().enumerate() {
+                if column > 0 {
+                    write!(f, " ")?;
+                }
+                let width = widths.get(column).copied().unwrap_or(0);
+                let is_last = column + 1 == row.len();
+                let text = &cell.text;
+                let padded = match self.alignments.get(column).copied().unwrap_or(Align::Left) {
+                    Align::Right => format!("{text:>width$}"),
+                    Align::Left if is_last => text.clone(),
+                    Align::Left => format!("{text:<width$}"),
+                };
+                match cell.style.filter(|_| self.color) {
+                    Some(style) => write!(f, "\x1b[{}m{padded}\x1b[0m", style.ansi_code())?,
+                    None => write!(f, "{padded}")?,
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{human_delta, human_size, percentage, Align, Cell, Style, Table};
+
+    #[test]
+    fn test_human_size() {
+        assert_eq!(human_size(0), "0 B");
+        assert_eq!(human_size(1023), "1023 B");
+        assert_eq!(human_size(1024), "1.00 KiB");
+        assert_eq!(human_size(1_300_234), "1.24 MiB");
+        assert_eq!(human_delta(-12), "-12 B");
+        assert_eq!(human_delta(2048), "+2.00 KiB");
+    }
+
+    #[test]
+    fn test_table() {
+        assert_eq!(percentage(1, 3), "33.33%");
+        assert_eq!(percentage(1, 0), "0.00%");
+
+        let mut table = Table::new(&[Align::Right, Align::Left]);
+        table.push_row(["1 B", "a.o"]);
+        table.push_row(["1.00 KiB", "b.o"]);
+        assert_eq!(table.to_string(), "     1 B a.o\n1.00 KiB b.o");
+
+        let mut table = Table::new(&[Align::Right, Align::Left]).with_header(["size", "file"]);
+        table.push_row([Cell::styled("+1 B", Style::for_delta(1)), "a.o".into()]);
+        assert_eq!(table.to_string(), "size file\n+1 B a.o");
+        table.set_color(true);
+        assert_eq!(
+            table.to_string(),
+            "\x1b[1msize\x1b[0m \x1b[1mfile\x1b[0m\n\x1b[31m+1 B\x1b[0m a.o"
+        );
+    }
+}
@@ -0,0 +1,281 @@
+//! The files causing dynamic-linking overhead: every GOT and PLT slot of a binary, attributed
+//! to the object files referencing the symbol it is relocated to.
+
+use std::{collections::HashMap, fmt::Display};
+
+use goblin::{elf::reloc::Reloc, Object};
+
+use crate::{
+    format::{hex, human_size, Align, Cell, Style, Table},
+    parse::{gnu_cross_references, normalize_line_endings},
+    Genealogy, GenealogyError,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SlotKind {
+    /// A `.got` slot the dynamic linker fills at load time, e.g. by `R_X86_64_GLOB_DAT`.
+    Got,
+    /// A `.got.plt` slot bound through the PLT, by a `R_*_JUMP_SLOT` relocation.
+    Plt,
+}
+
+impl SlotKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SlotKind::Got => "got",
+            SlotKind::Plt => "plt",
+        }
+    }
+}
+
+/// One relocated slot, see [`Genealogy::got_report`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GotSlot {
+    pub vaddr: u64,
+    pub kind: SlotKind,
+    /// The symbol the slot is relocated to.
+    pub symbol: String,
+    /// The bytes of the slot and of its relocation entry.
+    pub size: u64,
+    /// The object files referencing the symbol, empty if the map does not tell.
+    pub files: Vec<String>,
+}
+
+/// Slots and their size per referencing file, see [`Genealogy::got_report`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GotReport {
+    /// In address order.
+    pub slots: Vec<GotSlot>,
+    /// Files with the largest slots first.
+    pub entries: Vec<GotEntry>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GotEntry {
+    pub filename: String,
+    pub slots: u64,
+    pub size: u64,
+}
+
+impl Genealogy {
+    /// Lists the GOT and PLT slots of the ELF `binary` the genealogy was built from, and
+    /// attributes every slot to the files referencing its symbol in the cross reference table
+    /// of `mapfile`, written by GNU ld with `--cref`.
+    ///
+    /// Shared libraries defining the symbols are left out. A slot shared by several files
+    /// counts fully for each of them; without a cross reference table, or for symbols it does
+    /// not list, slots are unattributed. Relative relocations refer to no symbol and are not
+    /// slots. The PLT stubs themselves are attributed by the map, to the linker.
+    pub fn got_report(&self, binary: &[u8], mapfile: &str) -> Result<GotReport, GenealogyError> {
+        let Object::Elf(elf) = Object::parse(binary).map_err(GenealogyError::BinaryParse)? else {
+            return Err(GenealogyError::UnsupportedBinaryFormat);
+        };
+
+        let pointer_size = if elf.is_64 { 8 } else { 4 };
+        let reloc_size = |reloc: &Reloc| match (elf.is_64, reloc.r_addend.is_some()) {
+            (true, true) => 24,
+            (true, false) => 16,
+            (false, true) => 12,
+            (false, false) => 8,
+        };
+        let in_got = |vaddr: u64| {
+            elf.section_headers.iter().any(|shdr| {
+                (shdr.sh_addr..shdr.sh_addr.saturating_add(shdr.sh_size)).contains(&vaddr)
+                    && elf
+                        .shdr_strtab
+                        .get_at(shdr.sh_name)
+                        .is_some_and(|name| name.starts_with(".got"))
+            })
+        };
+        let mapfile = normalize_line_endings(mapfile);
+        let cross_references = gnu_cross_references(&mapfile);
+
+        let relocs = elf
+            .pltrelocs
+            .iter()
+            .map(|reloc| (SlotKind::Plt, reloc))
+            .chain(
+                elf.dynrelas
+                    .iter()
+                    .chain(elf.dynrels.iter())
+                    .filter(|reloc| in_got(reloc.r_offset))
+                    .map(|reloc| (SlotKind::Got, reloc)),
+            );
+        let mut slots = vec![];
+        for (kind, reloc) in relocs {
+            if reloc.r_sym == 0 {
+                continue;
+            }
+            let Some(symbol) = elf
+                .dynsyms
+                .get(reloc.r_sym)
+                .and_then(|sym| elf.dynstrtab.get_at(sym.st_name))
+                .filter(|name| !name.is_empty())
+            else {
+                continue;
+            };
+            let mut files = vec![];
+            if let Some(cross_reference) = cross_references.get(symbol) {
+                for &file in
+                    std::iter::once(&cross_reference.definition).chain(&cross_reference.references)
+                {
+                    if !is_shared_library(file) && !files.iter().any(|f| f == file) {
+                        files.push(file.to_string());
+                    }
+                }
+            }
+            slots.push(GotSlot {
+                vaddr: reloc.r_offset,
+                kind,
+                symbol: symbol.to_string(),
+                size: pointer_size + reloc_size(&reloc),
+                files,
+            });
+        }
+        slots.sort_by_key(|slot| slot.vaddr);
+
+        let mut files: HashMap<&str, (u64, u64)> = HashMap::new();
+        for slot in &slots {
+            let filenames = match slot.files.is_empty() {
+                true => vec!["<unattributed>"],
+                false => slot.files.iter().map(String::as_str).collect(),
+            };
+            for filename in filenames {
+                let entry = files.entry(filename).or_default();
+                entry.0 += 1;
+                entry.1 += slot.size;
+            }
+        }
+        let mut entries: Vec<GotEntry> = files
+            .into_iter()
+            .map(|(filename, (slots, size))| GotEntry {
+                filename: filename.to_string(),
+                slots,
+                size,
+            })
+            .collect();
+        entries.sort_by(|a, b| {
+            b.size
+                .cmp(&a.size)
+                .then_with(|| a.filename.cmp(&b.filename))
+        });
+
+        Ok(GotReport { slots, entries })
+    }
+}
+
+/// Whether `file`, as named in a map, is a shared library, e.g. `/lib/x86_64-linux-gnu/libc.so.6`.
+fn is_shared_library(file: &str) -> bool {
+    let name = file.rsplit(['/', '\\']).next().unwrap_or(file);
+    name.ends_with(".so") || name.contains(".so.")
+}
+
+impl GotReport {
+    /// The bytes of all slots and their relocations.
+    pub fn total(&self) -> u64 {
+        self.slots.iter().map(|slot| slot.size).sum()
+    }
+
+    /// The table rendered by `Display`.
+    pub fn to_table(&self) -> Table {
+        let mut table = Table::new(&[Align::Right, Align::Right, Align::Left])
+            .with_header(["slots", "size", "file"]);
+        for entry in &self.entries {
+            table.push_row([
+                entry.slots.to_string(),
+                human_size(entry.size),
+                entry.filename.clone(),
+            ]);
+        }
+        table.push_row([
+            Cell::styled(self.slots.len().to_string(), Some(Style::Bold)),
+            Cell::styled(human_size(self.total()), Some(Style::Bold)),
+            format!("total in {} files", self.entries.len()).into(),
+        ]);
+        table
+    }
+}
+
+impl Display for GotReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_table())
+    }
+}
+
+impl Display for GotSlot {
+    /// E.g. `plt 0x3fd0 __printf_chk simple.o`.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let files = match self.files.is_empty() {
+            true => "<unattributed>".to_string(),
+            false => self.files.join(", "),
+        };
+        write!(
+            f,
+            "{} {} {} {files}",
+            self.kind.as_str(),
+            hex(self.vaddr),
+            self.symbol
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Genealogy;
+
+    use super::{is_shared_library, SlotKind};
+
+    #[test]
+    fn test_got_report() {
+        assert!(is_shared_library("/lib/x86_64-linux-gnu/libc.so.6"));
+        assert!(!is_shared_library("libsolver.a(sort.o)"));
+
+        // As written by `-Wl,--cref`, abridged
+        let mapfile = std::fs::read_to_string("tests/gcc/out.map").unwrap()
+            + "
+Cross Reference Table
+
+Symbol                                            File
+_ITM_deregisterTMCloneTable                       /usr/lib/gcc/x86_64-linux-gnu/11/crtbeginS.o
+_ITM_registerTMCloneTable                         /usr/lib/gcc/x86_64-linux-gnu/11/crtbeginS.o
+__cxa_finalize@@GLIBC_2.2.5                       /lib/x86_64-linux-gnu/libc.so.6
+__cxa_finalize                                    /lib/x86_64-linux-gnu/libc.so.6
+                                                  /usr/lib/gcc/x86_64-linux-gnu/11/crtbeginS.o
+__printf_chk                                      /lib/x86_64-linux-gnu/libc.so.6
+                                                  simple.o
+main                                              simple.o
+                                                  /usr/lib/gcc/x86_64-linux-gnu/11/../../../x86_64-linux-gnu/Scrt1.o
+";
+        let binary = std::fs::read("tests/gcc/a.out").unwrap();
+        let genealogy = Genealogy::new(&mapfile, &binary).unwrap();
+        let report = genealogy.got_report(&binary, &mapfile).unwrap();
+
+        let printf = report
+            .slots
+            .iter()
+            .find(|slot| slot.symbol == "__printf_chk")
+            .unwrap();
+        assert_eq!(printf.kind, SlotKind::Plt);
+        assert_eq!(printf.vaddr, 0x3fd0);
+        assert_eq!(printf.size, 8 + 24);
+        assert_eq!(printf.files, ["simple.o"]);
+        let finalize = report
+            .slots
+            .iter()
+            .find(|slot| slot.symbol == "__cxa_finalize")
+            .unwrap();
+        assert_eq!(finalize.kind, SlotKind::Got);
+        assert_eq!(
+            finalize.files,
+            ["/usr/lib/gcc/x86_64-linux-gnu/11/crtbeginS.o"]
+        );
+
+        // `__libc_start_main` and `__gmon_start__` are not in the table
+        assert_eq!(report.slots.len(), 6);
+        assert!(report
+            .entries
+            .iter()
+            .any(|entry| entry.filename == "<unattributed>" && entry.slots == 2));
+        assert_eq!(report.total(), 6 * 32);
+    }
+}
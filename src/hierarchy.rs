@@ -0,0 +1,394 @@
+//! The address space of a binary as one tree, for visualization tools outside this crate, e.g.
+//! treemaps and sunburst charts: the binary, its segments, their sections and the contributions
+//! of the input files to every section.
+//!
+//! The JSON document of [`Hierarchy::to_json`] is versioned by [`HIERARCHY_VERSION`]; fields are
+//! only added within a version. Addresses and sizes are integers, absent file offsets `null`:
+//!
+//! ```text
+//! {
+//!   "version": 1,
+//!   "binary": {"format": "elf", "size": 18800},
+//!   "segments": [
+//!     {"name": "LOAD1", "vaddr": 4096, "size": 429, "file_offset": 4096, "file_size": 429,
+//!      "permissions": "r-x",
+//!      "sections": [
+//!        {"name": ".text", "vaddr": 4192, "size": 320, "file_offset": 4192,
+//!         "attributed": 310, "contributions": [
+//!           {"file": "simple.o", "name": ".text", "vaddr": 4425, "size": 79,
+//!            "file_offset": 4425, "folded_with": []}]}]}],
+//!   "sections": [{"name": ".comment", ...}]
+//! }
+//! ```
+//!
+//! `format` is one of `elf`, `pe`, `mach-o` and `wasm`. Segments are the `PT_LOAD` program
+//! headers of ELF files, named `LOAD<index>`, and the segments of Mach-O images; PE and
+//! WebAssembly files have none. Sections in no segment, e.g. debug sections, are listed in the
+//! top-level `sections`, as are all sections of a binary without segments. Sections and
+//! contributions are in address order, `attributed` being the bytes of the section its
+//! contributions cover.
+
+use std::fmt::Display;
+
+use goblin::{
+    elf::program_header::{PF_R, PF_W, PF_X, PT_LOAD},
+    mach::{
+        constants::{
+            cputype::get_arch_name_from_types, VM_PROT_EXECUTE, VM_PROT_READ, VM_PROT_WRITE,
+        },
+        Mach, MachO, SingleArch,
+    },
+    Object,
+};
+
+use crate::{
+    format::{hex, human_size, Align, Cell, Style, Table},
+    output::ToValue,
+    Genealogy, GenealogyError, Section,
+};
+
+/// The version of the document written by [`Hierarchy::to_json`].
+pub const HIERARCHY_VERSION: u64 = 1;
+
+/// The tree of a binary, see [`Genealogy::hierarchy`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Hierarchy {
+    /// `elf`, `pe`, `mach-o` or `wasm`.
+    pub format: &'static str,
+    /// Bytes of the binary file.
+    pub size: u64,
+    /// In address order.
+    pub segments: Vec<Segment>,
+    /// The sections in no segment.
+    pub sections: Vec<HierarchySection>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Segment {
+    pub name: String,
+    pub vaddr: u64,
+    /// Bytes in memory.
+    pub size: u64,
+    pub file_offset: u64,
+    /// Bytes in the file, less than `size` when the segment ends with zero-filled memory.
+    pub file_size: u64,
+    /// E.g. `r-x`.
+    pub permissions: String,
+    pub sections: Vec<HierarchySection>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HierarchySection {
+    pub name: String,
+    pub vaddr: u64,
+    pub size: u64,
+    pub file_offset: Option<u64>,
+    pub contributions: Vec<Contribution>,
+}
+
+impl HierarchySection {
+    /// Bytes of the section its contributions cover.
+    pub fn attributed(&self) -> u64 {
+        self.contributions
+            .iter()
+            .map(|contribution| contribution.size)
+            .sum()
+    }
+}
+
+/// The bytes of an input section of a file, a leaf of the tree.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Contribution {
+    pub filename: String,
+    /// The input section, e.g. `.text.main`.
+    pub name: String,
+    pub vaddr: u64,
+    pub size: u64,
+    pub file_offset: Option<u64>,
+    /// Files of the identical sections the linker folded into this one.
+    pub folded_with: Vec<String>,
+}
+
+fn permissions(read: bool, write: bool, execute: bool) -> String {
+    [(read, 'r'), (write, 'w'), (execute, 'x')]
+        .iter()
+        .map(|&(allowed, flag)| if allowed { flag } else { '-' })
+        .collect()
+}
+
+fn elf_segments(elf: &goblin::elf::Elf) -> Vec<Segment> {
+    elf.program_headers
+        .iter()
+        .filter(|phdr| phdr.p_type == PT_LOAD)
+        .enumerate()
+        .map(|(i, phdr)| Segment {
+            name: format!("LOAD{i}"),
+            vaddr: phdr.p_vaddr,
+            size: phdr.p_memsz,
+            file_offset: phdr.p_offset,
+            file_size: phdr.p_filesz,
+            permissions: permissions(
+                phdr.p_flags & PF_R != 0,
+                phdr.p_flags & PF_W != 0,
+                phdr.p_flags & PF_X != 0,
+            ),
+            sections: vec![],
+        })
+        .collect()
+}
+
+/// The segments of `macho`, its file offsets shifted by the offset of its slice, and without
+/// `__PAGEZERO` and `__LINKEDIT`, which hold no sections.
+fn macho_segments(macho: &MachO, slice_offset: u64) -> Vec<Segment> {
+    macho
+        .segments
+        .iter()
+        .filter(|segment| segment.nsects > 0)
+        .map(|segment| Segment {
+            name: segment.name().unwrap_or_default().to_string(),
+            vaddr: segment.vmaddr,
+            size: segment.vmsize,
+            file_offset: segment.fileoff + slice_offset,
+            file_size: segment.filesize,
+            permissions: permissions(
+                segment.initprot & VM_PROT_READ != 0,
+                segment.initprot & VM_PROT_WRITE != 0,
+                segment.initprot & VM_PROT_EXECUTE != 0,
+            ),
+            sections: vec![],
+        })
+        .collect()
+}
+
+fn hierarchy_section(section: &Section) -> HierarchySection {
+    let mut contributions: Vec<Contribution> = section
+        .subsections
+        .iter()
+        .map(|subsection| Contribution {
+            filename: subsection.filename.clone(),
+            name: subsection.name.clone(),
+            vaddr: subsection.start_vaddr,
+            size: subsection.size,
+            file_offset: subsection.start_file_offset,
+            folded_with: subsection.folded_with.clone(),
+        })
+        .collect();
+    contributions.sort_by_key(|contribution| contribution.vaddr);
+    HierarchySection {
+        name: section.name.clone(),
+        vaddr: section.start_vaddr,
+        size: section.size,
+        file_offset: section.start_file_offset,
+        contributions,
+    }
+}
+
+impl Genealogy {
+    /// The tree of the `binary` the genealogy was built from. Loaded sections go to the segment
+    /// their address falls in, and for Mach-O images to the segment they are named after.
+    pub fn hierarchy(&self, binary: &[u8]) -> Result<Hierarchy, GenealogyError> {
+        let (format, segments) = if binary.starts_with(b"\0asm") {
+            ("wasm", vec![])
+        } else {
+            match Object::parse(binary).map_err(GenealogyError::BinaryParse)? {
+                Object::Elf(elf) => ("elf", elf_segments(&elf)),
+                Object::PE(_) => ("pe", vec![]),
+                Object::Mach(Mach::Binary(macho)) => ("mach-o", macho_segments(&macho, 0)),
+                Object::Mach(Mach::Fat(fat)) => {
+                    // The slice the sections were placed in
+                    let offset = self
+                        .sections()
+                        .iter()
+                        .find_map(|section| section.start_file_offset)
+                        .unwrap_or_default();
+                    let arches = fat.arches().map_err(GenealogyError::BinaryParse)?;
+                    let index = arches
+                        .iter()
+                        .position(|arch| {
+                            (u64::from(arch.offset)..u64::from(arch.offset) + u64::from(arch.size))
+                                .contains(&offset)
+                        })
+                        .ok_or_else(|| {
+                            let arch = arches.first().and_then(|arch| {
+                                get_arch_name_from_types(arch.cputype, arch.cpusubtype)
+                            });
+                            GenealogyError::NoArchSlice(arch.unwrap_or("single").into())
+                        })?;
+                    match fat.get(index).map_err(GenealogyError::BinaryParse)? {
+                        SingleArch::MachO(macho) => (
+                            "mach-o",
+                            macho_segments(&macho, u64::from(arches[index].offset)),
+                        ),
+                        SingleArch::Archive(_) => {
+                            return Err(GenealogyError::UnsupportedBinaryFormat)
+                        }
+                    }
+                }
+                _ => return Err(GenealogyError::UnsupportedBinaryFormat),
+            }
+        };
+
+        let mut hierarchy = Hierarchy {
+            format,
+            size: binary.len() as u64,
+            segments,
+            sections: vec![],
+        };
+        let mut sections: Vec<&Section> = self.sections().iter().collect();
+        sections.sort_by(|a, b| {
+            a.start_vaddr
+                .cmp(&b.start_vaddr)
+                .then_with(|| a.name.cmp(&b.name))
+        });
+        for section in sections {
+            let segment = hierarchy.segments.iter_mut().find(|segment| {
+                let end = segment.vaddr.saturating_add(segment.size);
+                section.loaded
+                    && (segment.vaddr..end).contains(&section.start_vaddr)
+                    && match format {
+                        "mach-o" => section.name.split(',').next() == Some(&segment.name),
+                        _ => true,
+                    }
+            });
+            match segment {
+                Some(segment) => segment.sections.push(hierarchy_section(section)),
+                None => hierarchy.sections.push(hierarchy_section(section)),
+            }
+        }
+        hierarchy.segments.sort_by_key(|segment| segment.vaddr);
+        Ok(hierarchy)
+    }
+}
+
+impl Hierarchy {
+    /// The document described in the [module documentation](self).
+    pub fn to_json(&self) -> String {
+        let mut json = self.to_value().to_json();
+        json.push('\n');
+        json
+    }
+
+    /// The table rendered by `Display`: every segment then its sections, and the sections in no
+    /// segment last.
+    pub fn to_table(&self) -> Table {
+        let mut table = Table::new(&[
+            Align::Left,
+            Align::Right,
+            Align::Right,
+            Align::Right,
+            Align::Left,
+        ])
+        .with_header(["name", "vaddr", "size", "attributed", "permissions"]);
+        let push_section = |table: &mut Table, indent: &str, section: &HierarchySection| {
+            table.push_row([
+                format!("{indent}{}", section.name),
+                hex(section.vaddr),
+                human_size(section.size),
+                human_size(section.attributed()),
+                String::new(),
+            ]);
+        };
+        for segment in &self.segments {
+            table.push_row([
+                Cell::styled(segment.name.clone(), Some(Style::Bold)),
+                hex(segment.vaddr).into(),
+                human_size(segment.size).into(),
+                "".into(),
+                segment.permissions.as_str().into(),
+            ]);
+            for section in &segment.sections {
+                push_section(&mut table, "  ", section);
+            }
+        }
+        if !self.sections.is_empty() {
+            table.push_row([
+                Cell::styled("<no segment>", Some(Style::Bold)),
+                "".into(),
+                "".into(),
+                "".into(),
+                "".into(),
+            ]);
+            for section in &self.sections {
+                push_section(&mut table, "  ", section);
+            }
+        }
+        table
+    }
+}
+
+impl Display for Hierarchy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_table())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{output::Value, Genealogy};
+
+    #[test]
+    fn test_hierarchy() {
+        let mapfile = std::fs::read_to_string("tests/gcc/out.map").unwrap();
+        let binary = std::fs::read("tests/gcc/a.out").unwrap();
+        let genealogy = Genealogy::new(&mapfile, &binary).unwrap();
+        let hierarchy = genealogy.hierarchy(&binary).unwrap();
+
+        let segments: Vec<(&str, &str, Vec<&str>)> = hierarchy
+            .segments
+            .iter()
+            .map(|segment| {
+                (
+                    segment.name.as_str(),
+                    segment.permissions.as_str(),
+                    segment
+                        .sections
+                        .iter()
+                        .map(|section| section.name.as_str())
+                        .collect(),
+                )
+            })
+            .collect();
+        assert_eq!(segments.len(), 4);
+        assert_eq!(
+            segments[1],
+            (
+                "LOAD1",
+                "r-x",
+                vec![".init", ".plt", ".plt.got", ".plt.sec", ".text", ".fini"]
+            )
+        );
+        // `.bss` is in the zero-filled end of the data segment, debug sections in none
+        assert_eq!(segments[3].2.last(), Some(&".bss"));
+        let unloaded: Vec<&str> = hierarchy
+            .sections
+            .iter()
+            .map(|section| section.name.as_str())
+            .collect();
+        assert_eq!(unloaded[..2], [".comment", ".debug_abbrev"]);
+        let text = &hierarchy.segments[1].sections[4];
+        assert_eq!((text.size, text.attributed()), (320, 310));
+        assert!(text
+            .contributions
+            .iter()
+            .any(|contribution| contribution.filename == "simple.o"));
+
+        let json = Value::from_json(&hierarchy.to_json()).unwrap();
+        assert_eq!(json.get("version").and_then(Value::as_u64), Some(1));
+
+        let mapfile = std::fs::read_to_string("tests/macho/app.map").unwrap();
+        let binary = std::fs::read("tests/macho/app.universal").unwrap();
+        let genealogy = Genealogy::new(&mapfile, &binary).unwrap();
+        let hierarchy = genealogy.hierarchy(&binary).unwrap();
+        assert_eq!(hierarchy.format, "mach-o");
+        let names: Vec<&str> = hierarchy
+            .segments
+            .iter()
+            .map(|segment| segment.name.as_str())
+            .collect();
+        assert_eq!(names, ["__TEXT", "__DATA"]);
+        assert_eq!(
+            hierarchy.segments[0].sections[0].name,
+            "__TEXT,__text".to_string()
+        );
+    }
+}
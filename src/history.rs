@@ -0,0 +1,261 @@
+//! A size history of the builds of a project, kept in a JSON Lines file the builds are appended
+//! to with `genealogy history --record`, one per line with the size of every component, and the
+//! trend of a component over the last builds. Nothing is recorded unless asked for.
+
+use std::fmt::Display;
+
+use crate::{
+    format::{human_delta, human_size, Align, Cell, Style, Table},
+    origin::Granularities,
+    output::{ToValue, Value},
+    GenealogyError, Report,
+};
+
+/// The version of the build records written by [`Build::to_json`].
+pub const HISTORY_VERSION: u64 = 1;
+
+/// The sizes of a build, per component, see [`Granularities::component`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Build {
+    /// Seconds since the Unix epoch.
+    pub timestamp: u64,
+    /// What the build was made from, e.g. a commit or a CI job number.
+    pub label: Option<String>,
+    pub total: u64,
+    /// By name.
+    pub components: Vec<(String, u64)>,
+}
+
+impl Build {
+    /// Sums the files of `report` per component.
+    pub fn from_report(report: &Report, timestamp: u64, label: Option<String>) -> Self {
+        let mut components: Vec<(String, u64)> = vec![];
+        for entry in &report.entries {
+            let component = Granularities::of(&entry.filename).component;
+            match components.binary_search_by(|(name, _)| name.as_str().cmp(component)) {
+                Ok(i) => components[i].1 += entry.size,
+                Err(i) => components.insert(i, (component.to_string(), entry.size)),
+            }
+        }
+        Self {
+            timestamp,
+            label,
+            total: report.total,
+            components,
+        }
+    }
+
+    /// The size of `component`, 0 when the build has none.
+    pub fn size_of(&self, component: &str) -> u64 {
+        self.components
+            .iter()
+            .find(|(name, _)| name == component)
+            .map_or(0, |&(_, size)| size)
+    }
+
+    /// Parses a build written by [`Build::to_json`].
+    pub fn from_json(json: &str) -> Result<Self, GenealogyError> {
+        let invalid = || GenealogyError::WrongHistoryFormat;
+        let build = Value::from_json(json).ok_or_else(invalid)?;
+        if build.get("version").and_then(Value::as_u64) != Some(HISTORY_VERSION) {
+            return Err(invalid());
+        }
+        let number = |key| build.get(key).and_then(Value::as_u64).ok_or_else(invalid);
+        let label = match build.get("label") {
+            None | Some(Value::Null) => None,
+            Some(label) => Some(label.as_str().ok_or_else(invalid)?.to_string()),
+        };
+        let Some(Value::Object(components)) = build.get("components") else {
+            return Err(invalid());
+        };
+        let components = components
+            .iter()
+            .map(|(name, size)| Ok((name.clone(), size.as_u64().ok_or_else(invalid)?)))
+            .collect::<Result<_, GenealogyError>>()?;
+        Ok(Self {
+            timestamp: number("timestamp")?,
+            label,
+            total: number("total")?,
+            components,
+        })
+    }
+
+    /// The line of the build in a history file, with its line break.
+    pub fn to_json(&self) -> String {
+        self.to_value().to_json() + "\n"
+    }
+}
+
+/// The builds of a history file, in the order they were recorded.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct History {
+    pub builds: Vec<Build>,
+}
+
+impl History {
+    /// Parses a history file, one [`Build`] per line. Blank lines are skipped.
+    pub fn parse(text: &str) -> Result<Self, GenealogyError> {
+        let builds = text
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(Build::from_json)
+            .collect::<Result<_, _>>()?;
+        Ok(Self { builds })
+    }
+
+    /// The size of `component`, or the total without one, in the last `last` builds, all of
+    /// them without a count. The change of the first one is from the build before it.
+    pub fn trend(&self, component: Option<&str>, last: Option<usize>) -> TrendReport {
+        let size = |build: &Build| component.map_or(build.total, |name| build.size_of(name));
+        let first = last.map_or(0, |last| self.builds.len().saturating_sub(last));
+        let mut previous = first.checked_sub(1).map(|i| size(&self.builds[i]));
+        let entries = self.builds[first..]
+            .iter()
+            .map(|build| {
+                let size = size(build);
+                let delta = previous.map_or(0, |previous| size as i64 - previous as i64);
+                previous = Some(size);
+                TrendEntry {
+                    timestamp: build.timestamp,
+                    label: build.label.clone(),
+                    size,
+                    delta,
+                }
+            })
+            .collect();
+        TrendReport {
+            component: component.map(str::to_string),
+            entries,
+        }
+    }
+}
+
+/// The sizes of a component across builds, see [`History::trend`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TrendReport {
+    /// `None` for the total of the builds.
+    pub component: Option<String>,
+    /// Oldest first.
+    pub entries: Vec<TrendEntry>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TrendEntry {
+    pub timestamp: u64,
+    pub label: Option<String>,
+    pub size: u64,
+    /// The change from the build before.
+    pub delta: i64,
+}
+
+impl TrendReport {
+    /// The change from the size before the first entry to the last one.
+    pub fn delta(&self) -> i64 {
+        self.entries.iter().map(|entry| entry.delta).sum()
+    }
+
+    /// The table rendered by `Display`.
+    pub fn to_table(&self) -> Table {
+        let mut table = Table::new(&[Align::Left, Align::Left, Align::Right, Align::Right])
+            .with_header(["recorded", "build", "size", "delta"]);
+        for entry in &self.entries {
+            table.push_row([
+                utc(entry.timestamp).into(),
+                entry.label.as_deref().unwrap_or("-").into(),
+                human_size(entry.size).into(),
+                Cell::styled(human_delta(entry.delta), Style::for_delta(entry.delta)),
+            ]);
+        }
+        table.push_row([
+            Cell::styled(
+                self.component.as_deref().unwrap_or("total"),
+                Some(Style::Bold),
+            ),
+            format!("{} builds", self.entries.len()).into(),
+            "".into(),
+            Cell::styled(human_delta(self.delta()), Style::for_delta(self.delta())),
+        ]);
+        table
+    }
+}
+
+impl Display for TrendReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_table())
+    }
+}
+
+/// `2026-10-14 09:30`, the UTC date and time of seconds since the Unix epoch.
+fn utc(timestamp: u64) -> String {
+    let (days, seconds) = (timestamp / 86_400, timestamp % 86_400);
+    // The civil date of a day count, shifted to start the years in March
+    let days = days + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days % 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month + 2) / 5 + 1;
+    let month = if month < 10 { month + 3 } else { month - 9 };
+    let year = era * 400 + year_of_era + u64::from(month <= 2);
+    format!(
+        "{year:04}-{month:02}-{day:02} {:02}:{:02}",
+        seconds / 3_600,
+        seconds % 3_600 / 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Genealogy, GenealogyError};
+
+    use super::{utc, Build, History};
+
+    #[test]
+    fn test_history() {
+        let mapfile = std::fs::read_to_string("tests/archives/out.map").unwrap();
+        let binary = std::fs::read("tests/archives/app").unwrap();
+        let report = Genealogy::new(&mapfile, &binary).unwrap().report();
+        let build = Build::from_report(&report, 1_760_000_000, Some("abc1234".to_string()));
+        assert_eq!(
+            build.components.iter().map(|(_, size)| size).sum::<u64>(),
+            report.total
+        );
+        // The members of an archive are a single component
+        assert_eq!(build.size_of("libd.a"), 0xb + 0x20);
+
+        let json = build.to_json();
+        assert_eq!(json.lines().count(), 1);
+        assert_eq!(Build::from_json(&json).unwrap(), build);
+        assert!(matches!(
+            Build::from_json(&json.replace("\"version\":1", "\"version\":2")),
+            Err(GenealogyError::WrongHistoryFormat)
+        ));
+
+        // The build grows, then the archive is dropped
+        let mut grown = build.clone();
+        grown.timestamp += 3_600;
+        grown.components.iter_mut().for_each(|(_, size)| *size += 8);
+        let mut dropped = grown.clone();
+        dropped.timestamp += 3_600;
+        dropped.label = None;
+        dropped.components.retain(|(name, _)| name != "libd.a");
+        let text = [build, grown, dropped]
+            .map(|build| build.to_json())
+            .join("\n");
+        let history = History::parse(&text).unwrap();
+        assert_eq!(history.builds.len(), 3);
+
+        let trend = history.trend(Some("libd.a"), Some(2));
+        let sizes: Vec<(u64, i64)> = trend.entries.iter().map(|e| (e.size, e.delta)).collect();
+        assert_eq!(sizes, [(0x2b + 8, 8), (0, -0x33)]);
+        assert_eq!(trend.delta(), -0x2b);
+        assert_eq!(history.trend(None, None).entries[0].delta, 0);
+        assert!(History::parse("{}").is_err());
+
+        assert_eq!(utc(0), "1970-01-01 00:00");
+        assert_eq!(utc(1_760_000_000), "2025-10-09 08:53");
+        assert_eq!(utc(951_782_400), "2000-02-29 00:00");
+    }
+}
@@ -0,0 +1,253 @@
+//! Verification of hot/cold splitting: functions found hot by PGO or a profiler should end up in
+//! `.text.hot` input sections, or at the front of `.text` when laid out by an ordering file.
+
+use std::{collections::HashMap, fmt::Display, ops::Range};
+
+use crate::{
+    format::{hex, human_size, Align, Table},
+    index::Section,
+    Genealogy, GenealogyError,
+};
+
+/// The prefix of the input sections of hot functions, as emitted by GCC and Clang.
+const HOT_SECTION: &str = ".text.hot";
+
+/// Where the hot functions were placed, see [`Genealogy::hot_placement`].
+#[derive(Clone, Debug)]
+pub struct HotColdReport {
+    /// The hot functions found in the binary, in address order.
+    pub functions: Vec<HotFunction>,
+    /// The hot functions that are not in the symbol index, maybe inlined or renamed.
+    pub missing: Vec<String>,
+    /// Per file, most misplaced bytes first.
+    pub entries: Vec<HotColdEntry>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HotFunction {
+    pub name: String,
+    pub vaddr: u64,
+    pub size: u64,
+    pub filename: Option<String>,
+    /// The input section holding the function.
+    pub subsection: Option<String>,
+    /// Whether the function is in the hot region of its output section.
+    pub placed: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HotColdEntry {
+    pub filename: String,
+    pub functions: u64,
+    pub misplaced: u64,
+    pub misplaced_size: u64,
+}
+
+impl Genealogy {
+    /// Checks the placement of the `hot` functions, looked up by mangled or demangled name.
+    ///
+    /// The hot region of an output section spans its `.text.hot` input sections when it has
+    /// some. Otherwise it is at the front of the section and as big as the hot functions placed
+    /// in it, which is where `--symbol-ordering-file` and `-order_file` put them.
+    pub fn hot_placement<'a>(
+        &self,
+        hot: impl IntoIterator<Item = &'a str>,
+    ) -> Result<HotColdReport, GenealogyError> {
+        let mut functions = vec![];
+        let mut missing = vec![];
+        for name in hot {
+            let hits = self.lookup_symbol(name)?;
+            let Some(hit) = hits.iter().find(|hit| hit.symbol.size > 0).or(hits.first()) else {
+                missing.push(name.to_string());
+                continue;
+            };
+            let symbol = hit.symbol;
+            let subsection = self.section_of(symbol.vaddr).and_then(|section| {
+                section
+                    .subsections
+                    .iter()
+                    .find(|subsection| {
+                        subsection.size > 0
+                            && (subsection.start_vaddr..subsection.start_vaddr + subsection.size)
+                                .contains(&symbol.vaddr)
+                    })
+                    .map(|subsection| subsection.name.clone())
+            });
+            functions.push(HotFunction {
+                name: name.to_string(),
+                vaddr: symbol.vaddr,
+                size: symbol.size,
+                filename: hit.filename.map(str::to_string),
+                subsection,
+                placed: false,
+            });
+        }
+        functions.sort_by(|a, b| a.vaddr.cmp(&b.vaddr).then_with(|| a.name.cmp(&b.name)));
+        functions.dedup_by(|a, b| a.vaddr == b.vaddr);
+
+        let mut hot_sizes: HashMap<&str, u64> = HashMap::new();
+        for function in &functions {
+            if let Some(section) = self.section_of(function.vaddr) {
+                *hot_sizes.entry(section.name.as_str()).or_default() += function.size;
+            }
+        }
+        let regions: HashMap<&str, Range<u64>> = self
+            .sections()
+            .iter()
+            .filter_map(|section| {
+                let hot_size = *hot_sizes.get(section.name.as_str())?;
+                Some((section.name.as_str(), hot_region(section, hot_size)))
+            })
+            .collect();
+        for function in &mut functions {
+            function.placed = self
+                .section_of(function.vaddr)
+                .and_then(|section| regions.get(section.name.as_str()))
+                .is_some_and(|region| {
+                    region.start <= function.vaddr
+                        && function.vaddr.saturating_add(function.size) <= region.end
+                });
+        }
+
+        let mut entries: HashMap<&str, HotColdEntry> = HashMap::new();
+        for function in &functions {
+            let filename = function.filename.as_deref().unwrap_or("<unknown>");
+            let entry = entries.entry(filename).or_insert_with(|| HotColdEntry {
+                filename: filename.to_string(),
+                functions: 0,
+                misplaced: 0,
+                misplaced_size: 0,
+            });
+            entry.functions += 1;
+            if !function.placed {
+                entry.misplaced += 1;
+                entry.misplaced_size += function.size;
+            }
+        }
+        let mut entries: Vec<HotColdEntry> = entries.into_values().collect();
+        entries.sort_by(|a, b| {
+            b.misplaced_size
+                .cmp(&a.misplaced_size)
+                .then_with(|| b.misplaced.cmp(&a.misplaced))
+                .then_with(|| a.filename.cmp(&b.filename))
+        });
+
+        Ok(HotColdReport {
+            functions,
+            missing,
+            entries,
+        })
+    }
+
+    fn section_of(&self, vaddr: u64) -> Option<&Section> {
+        self.sections().iter().find(|section| {
+            section.loaded
+                && (section.start_vaddr..section.start_vaddr + section.size).contains(&vaddr)
+        })
+    }
+}
+
+fn hot_region(section: &Section, hot_size: u64) -> Range<u64> {
+    let hot = section.subsections.iter().filter(|subsection| {
+        subsection.size > 0
+            && subsection
+                .name
+                .strip_prefix(HOT_SECTION)
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with('.'))
+    });
+    hot.fold(None, |region: Option<Range<u64>>, subsection| {
+        let end = subsection.start_vaddr + subsection.size;
+        Some(match region {
+            Some(region) => region.start.min(subsection.start_vaddr)..region.end.max(end),
+            None => subsection.start_vaddr..end,
+        })
+    })
+    .unwrap_or(section.start_vaddr..section.start_vaddr.saturating_add(hot_size))
+}
+
+/// Parses a list of functions, one name per line, as in a symbol ordering file. Blank lines and
+/// lines starting with `#` are ignored.
+pub fn parse_function_list(text: &str) -> Vec<&str> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .collect()
+}
+
+impl HotColdReport {
+    /// The hot functions outside of the hot region, in address order.
+    pub fn misplaced(&self) -> impl Iterator<Item = &HotFunction> {
+        self.functions.iter().filter(|function| !function.placed)
+    }
+
+    /// The per file table rendered by `Display`.
+    pub fn to_table(&self) -> Table {
+        let mut table = Table::new(&[Align::Right, Align::Right, Align::Right, Align::Left])
+            .with_header(["misplaced", "hot", "size", "file"]);
+        for entry in &self.entries {
+            table.push_row([
+                entry.misplaced.to_string(),
+                entry.functions.to_string(),
+                human_size(entry.misplaced_size),
+                entry.filename.clone(),
+            ]);
+        }
+        table
+    }
+
+    /// The table of the [`misplaced`](Self::misplaced) functions.
+    pub fn misplaced_table(&self) -> Table {
+        let mut table = Table::new(&[Align::Left, Align::Right, Align::Left, Align::Left])
+            .with_header(["vaddr", "size", "section", "function"]);
+        for function in self.misplaced() {
+            table.push_row([
+                hex(function.vaddr),
+                human_size(function.size),
+                function.subsection.clone().unwrap_or_default(),
+                function.name.clone(),
+            ]);
+        }
+        table
+    }
+}
+
+impl Display for HotColdReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_table())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Genealogy;
+
+    use super::parse_function_list;
+
+    #[test]
+    fn test_hot_placement() {
+        let mapfile = std::fs::read_to_string("tests/gcc/out.map").unwrap();
+        let binary = std::fs::read("tests/gcc/a.out").unwrap();
+        let genealogy = Genealogy::builder(&mapfile, &binary)
+            .symbols(true)
+            .build()
+            .unwrap();
+
+        // Nothing is hot in the fixture, so the hot region is the front of `.text`, where
+        // `_start` is and `main` is not
+        let hot = parse_function_list("# from perf\n_start\n\nmain\nnot_a_function\n");
+        let report = genealogy.hot_placement(hot).unwrap();
+        assert_eq!(report.missing, ["not_a_function"]);
+        let placed: Vec<_> = report
+            .functions
+            .iter()
+            .map(|function| (function.name.as_str(), function.placed))
+            .collect();
+        assert_eq!(placed, [("_start", true), ("main", false)]);
+
+        let main = report.misplaced().next().unwrap();
+        assert_eq!(main.filename.as_deref(), Some("simple.o"));
+        assert_eq!(main.subsection.as_deref(), Some(".text"));
+        assert_eq!(report.entries[0].filename, "simple.o");
+        assert_eq!(report.entries[0].misplaced, 1);
+    }
+}
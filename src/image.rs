@@ -0,0 +1,332 @@
+//! Attribution of combined flash images, such as a bootloader and an application written to one
+//! artifact at their flash offsets, to the component and then the object file of every byte.
+
+use std::{fmt::Display, ops::Range};
+
+use intervaltree::{Element, IntervalTree};
+
+use crate::{
+    format::{hex, human_size, Align, Table},
+    Genealogy, Section,
+};
+
+/// How the bytes of a component binary are laid out in the image.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Placement {
+    /// The binary is copied as is, its file offsets are offsets from the component.
+    File,
+    /// The binary was converted to a raw image (`objcopy -O binary`): the lowest virtual address
+    /// of its loaded sections is at the offset of the component, and sections without contents
+    /// such as `.bss` are not in the image.
+    Raw,
+}
+
+/// A binary and its map, written to the image at `offset`.
+pub struct Component<'a> {
+    pub name: String,
+    pub offset: u64,
+    pub placement: Placement,
+    pub genealogy: Genealogy,
+    pub binary: &'a [u8],
+}
+
+impl Component<'_> {
+    /// The ranges of the component attributed to a file, from the start of the component.
+    fn hits(&self) -> Vec<(Range<u64>, &str)> {
+        match self.placement {
+            Placement::File => self
+                .genealogy
+                .iter()
+                .map(|hit| (hit.range, hit.filename))
+                .collect(),
+            Placement::Raw => {
+                // `objcopy -O binary` leaves out the sections without contents, e.g. `.bss`
+                let sections: Vec<&Section> = self
+                    .genealogy
+                    .sections()
+                    .iter()
+                    .filter(|section| section.loaded && section.start_file_offset.is_some())
+                    .collect();
+                let base = sections
+                    .iter()
+                    .filter(|section| section.size > 0)
+                    .map(|section| section.start_vaddr)
+                    .min()
+                    .unwrap_or(0);
+                sections
+                    .iter()
+                    .flat_map(|section| &section.subsections)
+                    .filter(|subsection| subsection.start_vaddr >= base && subsection.size > 0)
+                    .map(|subsection| {
+                        let start = subsection.start_vaddr - base;
+                        (
+                            start..start.saturating_add(subsection.size),
+                            subsection.filename.as_str(),
+                        )
+                    })
+                    .collect()
+            }
+        }
+    }
+}
+
+/// A combined image and the single index of its components, see [`CombinedImage::new`].
+pub struct CombinedImage<'a> {
+    /// In offset order.
+    pub components: Vec<Component<'a>>,
+    /// The range of the image covered by every component.
+    extents: Vec<Range<u64>>,
+    /// Whether the image holds the bytes of every component, `None` for [`Placement::Raw`] ones.
+    matches: Vec<Option<bool>>,
+    intervals: IntervalTree<u64, (usize, String)>,
+}
+
+/// A range of the image attributed to a file of a component.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ImageHit<'a> {
+    /// The range in the image.
+    pub range: Range<u64>,
+    pub component: &'a str,
+    pub filename: &'a str,
+}
+
+/// The attribution of a component, see [`CombinedImage::summary`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ComponentEntry {
+    pub name: String,
+    pub offset: u64,
+    /// Bytes of the image up to the next component or the end of the image, at most the size
+    /// of the binary for [`Placement::File`].
+    pub size: u64,
+    pub attributed: u64,
+    pub matches: Option<bool>,
+}
+
+impl<'a> CombinedImage<'a> {
+    /// Places `components` in `image`. A component extends to the next one or to the end of the
+    /// image, the content of a component past it is left out. [`Placement::File`] components are
+    /// checked against the bytes of the image, to catch an image built from other binaries.
+    ///
+    /// Building takes O(n log n) for n attributed ranges over all components.
+    pub fn new(image: &[u8], mut components: Vec<Component<'a>>) -> Self {
+        components.sort_by(|a, b| a.offset.cmp(&b.offset).then_with(|| a.name.cmp(&b.name)));
+        let image_len = image.len() as u64;
+        let extents: Vec<Range<u64>> = components
+            .iter()
+            .enumerate()
+            .map(|(i, component)| {
+                let mut end = components
+                    .get(i + 1)
+                    .map_or(image_len, |next| next.offset)
+                    .min(image_len);
+                if component.placement == Placement::File {
+                    end = end.min(
+                        component
+                            .offset
+                            .saturating_add(component.binary.len() as u64),
+                    );
+                }
+                component.offset..end.max(component.offset)
+            })
+            .collect();
+
+        let matches = components
+            .iter()
+            .zip(&extents)
+            .map(|(component, extent)| {
+                (component.placement == Placement::File).then(|| {
+                    let expected = &component.binary[..(extent.end - extent.start) as usize];
+                    extent.end - extent.start == component.binary.len() as u64
+                        && image.get(extent.start as usize..extent.end as usize) == Some(expected)
+                })
+            })
+            .collect();
+
+        let mut elements = vec![];
+        for (i, (component, extent)) in components.iter().zip(&extents).enumerate() {
+            for (range, filename) in component.hits() {
+                let start = extent.start.saturating_add(range.start);
+                let end = extent.start.saturating_add(range.end).min(extent.end);
+                if start < end {
+                    elements.push(Element {
+                        range: start..end,
+                        value: (i, filename.to_string()),
+                    });
+                }
+            }
+        }
+
+        Self {
+            components,
+            extents,
+            matches,
+            intervals: elements.into_iter().collect(),
+        }
+    }
+
+    /// The files of the image containing the image offset `point`.
+    pub fn query_point(&self, point: u64) -> impl Iterator<Item = ImageHit<'_>> {
+        self.intervals
+            .query_point(point)
+            .map(|element| self.hit(element))
+    }
+
+    /// Iterates over every attributed range of the image, ordered by start offset.
+    pub fn iter(&self) -> impl Iterator<Item = ImageHit<'_>> {
+        self.intervals
+            .iter_sorted()
+            .map(|element| self.hit(element))
+    }
+
+    fn hit<'s>(&'s self, element: &'s Element<u64, (usize, String)>) -> ImageHit<'s> {
+        let (component, filename) = &element.value;
+        ImageHit {
+            range: element.range.clone(),
+            component: &self.components[*component].name,
+            filename,
+        }
+    }
+
+    /// The component covering the image offset `point`.
+    pub fn component_at(&self, point: u64) -> Option<&Component<'a>> {
+        self.extents
+            .iter()
+            .position(|extent| extent.contains(&point))
+            .map(|i| &self.components[i])
+    }
+
+    /// The placement and attribution of every component, in offset order.
+    pub fn summary(&self) -> Vec<ComponentEntry> {
+        let mut attributed = vec![0; self.components.len()];
+        for element in self.intervals.iter_sorted() {
+            attributed[element.value.0] += element.range.end - element.range.start;
+        }
+        self.components
+            .iter()
+            .zip(&self.extents)
+            .zip(&self.matches)
+            .zip(attributed)
+            .map(
+                |(((component, extent), &matches), attributed)| ComponentEntry {
+                    name: component.name.clone(),
+                    offset: component.offset,
+                    size: extent.end - extent.start,
+                    attributed,
+                    matches,
+                },
+            )
+            .collect()
+    }
+}
+
+/// The table of a [`CombinedImage::summary`].
+pub fn summary_table(entries: &[ComponentEntry]) -> Table {
+    let mut table = Table::new(&[
+        Align::Left,
+        Align::Left,
+        Align::Right,
+        Align::Right,
+        Align::Left,
+    ])
+    .with_header(["component", "offset", "size", "attributed", "matches"]);
+    for entry in entries {
+        table.push_row([
+            entry.name.clone(),
+            hex(entry.offset),
+            human_size(entry.size),
+            human_size(entry.attributed),
+            match entry.matches {
+                Some(true) => "yes",
+                Some(false) => "no",
+                None => "-",
+            }
+            .to_string(),
+        ]);
+    }
+    table
+}
+
+impl Display for ImageHit<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}..{} {:>8} {} {}",
+            hex(self.range.start),
+            hex(self.range.end),
+            human_size(self.range.end - self.range.start),
+            self.component,
+            self.filename
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Genealogy;
+
+    use super::{CombinedImage, Component, Placement};
+
+    #[test]
+    fn test_combined_image() {
+        let gcc_map = std::fs::read_to_string("tests/gcc/out.map").unwrap();
+        let gcc = std::fs::read("tests/gcc/a.out").unwrap();
+        let clang_map = std::fs::read_to_string("tests/clang/output.map").unwrap();
+        let clang = std::fs::read("tests/clang/a.out").unwrap();
+
+        // A bootloader at 0x1000 and an application on the next 64K boundary
+        let app_offset = (0x1000 + gcc.len()).next_multiple_of(0x10000);
+        let mut image = vec![0xff; app_offset + clang.len()];
+        image[0x1000..0x1000 + gcc.len()].copy_from_slice(&gcc);
+        image[app_offset..].copy_from_slice(&clang);
+        let components = vec![
+            Component {
+                name: "app".to_string(),
+                offset: app_offset as u64,
+                placement: Placement::File,
+                genealogy: Genealogy::new(&clang_map, &clang).unwrap(),
+                binary: &clang,
+            },
+            Component {
+                name: "bootloader".to_string(),
+                offset: 0x1000,
+                placement: Placement::File,
+                genealogy: Genealogy::new(&gcc_map, &gcc).unwrap(),
+                binary: &gcc,
+            },
+        ];
+        let combined = CombinedImage::new(&image, components);
+
+        let hit = combined.query_point(0x1000 + 0x1151).next().unwrap();
+        assert_eq!((hit.component, hit.filename), ("bootloader", "simple.o"));
+        assert!(combined.query_point(0x800).next().is_none());
+        assert_eq!(
+            combined.component_at(app_offset as u64).unwrap().name,
+            "app"
+        );
+        let summary = combined.summary();
+        assert!(summary.iter().all(|entry| entry.matches == Some(true)));
+        assert!(combined
+            .iter()
+            .all(|hit| hit.range.start >= 0x1000 && hit.range.end <= image.len() as u64));
+
+        // A stale image no longer matches the bootloader
+        image[0x1000 + 0x1151] ^= 0xff;
+        let bootloader = |placement| {
+            vec![Component {
+                name: "bootloader".to_string(),
+                offset: 0x1000,
+                placement,
+                genealogy: Genealogy::new(&gcc_map, &gcc).unwrap(),
+                binary: &gcc,
+            }]
+        };
+        let stale = CombinedImage::new(&image, bootloader(Placement::File));
+        assert_eq!(stale.summary()[0].matches, Some(false));
+
+        // Raw images start at the first loaded section, `.interp` at 0x318
+        let raw = CombinedImage::new(&image, bootloader(Placement::Raw));
+        assert_eq!(raw.summary()[0].matches, None);
+        let hit = raw.query_point(0x1000 + 0x1151 - 0x318).next().unwrap();
+        assert_eq!(hit.filename, "simple.o");
+    }
+}
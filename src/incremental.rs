@@ -0,0 +1,284 @@
+//! Incremental rebuilds for large maps that change little between builds: the output sections
+//! whose text is unchanged since the previous build are not parsed again.
+
+use std::{
+    collections::HashMap,
+    hash::{DefaultHasher, Hash, Hasher},
+};
+
+use crate::{
+    parse::{extract_mapfile, mapfile_format, MapfileFormat},
+    scan,
+    validate::Warning,
+    GenealogyError, Section,
+};
+
+/// The sections parsed by previous builds, keyed by a hash of their text in the map, see
+/// [`GenealogyBuilder::cache`](crate::GenealogyBuilder::cache).
+///
+/// Sections are reused when their text is unchanged, so the sections that moved (e.g. all the
+/// ones placed after a function that grew) are parsed again. Placement in the binary, origin
+/// naming and indexing are always redone. GNU and LLVM maps are split per output section, MSVC
+/// maps are parsed whole every time.
+#[derive(Clone, Debug, Default)]
+pub struct LayoutCache {
+    sections: HashMap<u64, Section>,
+    changed_sections: Vec<String>,
+}
+
+impl LayoutCache {
+    /// The sections parsed by the last build because they are new or their text changed, in map
+    /// order.
+    pub fn changed_sections(&self) -> &[String] {
+        &self.changed_sections
+    }
+
+    /// Parses the sections of `mapfile` that are not cached, and replaces the cache with the
+    /// sections of `mapfile`. Maps with malformed regions are parsed whole and not cached, so that
+    /// their warnings are reported at the right lines by every build.
+    pub(crate) fn extract_mapfile(
+        &mut self,
+        mapfile: &str,
+    ) -> Result<(Vec<Section>, Vec<Warning>), GenealogyError> {
+        let Some((header, chunks)) = section_chunks(mapfile) else {
+            return self.extract_whole(mapfile);
+        };
+        let hashes: Vec<u64> = chunks.iter().map(|chunk| hash(chunk)).collect();
+
+        // Parse all the changed sections at once, after the header of the map if any, noting
+        // the line every one starts at
+        let mut changed = header.to_string();
+        let mut changed_lines = vec![];
+        let mut is_changed = vec![false; chunks.len()];
+        for (i, chunk) in chunks.iter().enumerate() {
+            if !self.sections.contains_key(&hashes[i]) {
+                changed_lines.push(changed.matches('\n').count() + 1);
+                changed.push_str(chunk);
+                if !chunk.ends_with('\n') {
+                    changed.push('\n');
+                }
+                is_changed[i] = true;
+            }
+        }
+        let (parsed, warnings) = extract_mapfile(&changed)?;
+
+        // Every chunk must have given its own section, or the split is not the one of the parser
+        let changed_chunks = chunks.iter().zip(&is_changed).filter(|(_, &c)| c);
+        if !warnings.is_empty()
+            || parsed.len() != changed_chunks.clone().count()
+            || !parsed
+                .iter()
+                .zip(changed_chunks)
+                .all(|(section, (chunk, _))| {
+                    chunk
+                        .lines()
+                        .next()
+                        .is_some_and(|line| line.contains(section.name.as_str()))
+                })
+        {
+            return self.extract_whole(mapfile);
+        }
+
+        // Cached sections have the lines of their chunk, from 1
+        let mut parsed = parsed.into_iter().zip(changed_lines);
+        let mut sections = Vec::with_capacity(chunks.len());
+        let mut cache = HashMap::with_capacity(chunks.len());
+        self.changed_sections.clear();
+        let first_lines = chunk_lines(mapfile, &chunks);
+        for ((hash, is_changed), first_line) in hashes.into_iter().zip(is_changed).zip(first_lines)
+        {
+            let section = if is_changed {
+                let (mut section, line) =
+                    parsed.next().expect("one parsed section per changed chunk");
+                shift_lines(&mut section, 1 - line as isize);
+                self.changed_sections.push(section.name.clone());
+                section
+            } else {
+                // The same text may appear twice, then it has already been moved
+                self.sections
+                    .remove(&hash)
+                    .or_else(|| cache.get(&hash).cloned())
+                    .expect("unchanged chunks are cached")
+            };
+            let mut placed = section.clone();
+            shift_lines(&mut placed, first_line as isize - 1);
+            sections.push(placed);
+            cache.insert(hash, section);
+        }
+        self.sections = cache;
+
+        Ok((sections, vec![]))
+    }
+
+    fn extract_whole(
+        &mut self,
+        mapfile: &str,
+    ) -> Result<(Vec<Section>, Vec<Warning>), GenealogyError> {
+        let (sections, warnings) = extract_mapfile(mapfile)?;
+        self.sections.clear();
+        self.changed_sections = sections.iter().map(|s| s.name.clone()).collect();
+        Ok((sections, warnings))
+    }
+}
+
+/// Splits `mapfile` into the header the parser needs, and the text of every output section.
+pub(crate) fn section_chunks(mapfile: &str) -> Option<(&str, Vec<&str>)> {
+    match mapfile_format(mapfile) {
+        MapfileFormat::Gnu => {
+            let starts: Vec<usize> = scan::gnu_sections(mapfile)
+                .map(|entry| entry.offset)
+                .collect();
+            // Maps without input sections are attributed from the symbols of every section and
+            // the cross reference table at the end, so they are parsed whole
+            let memory_map = starts.first().copied().unwrap_or(mapfile.len());
+            if mapfile.contains("\nCross Reference Table")
+                || scan::gnu_subsections(mapfile).all(|entry| entry.offset < memory_map)
+            {
+                return None;
+            }
+            Some(("", split_at(mapfile, &starts)))
+        }
+        MapfileFormat::Llvm { .. } | MapfileFormat::Wasm => {
+            // Section names start in the `Out` column, subsection and symbol names further
+            let header_len = mapfile.find('\n').map_or(mapfile.len(), |i| i + 1);
+            let (header, body) = mapfile.split_at(header_len);
+            let out_column = header.find("Out")?;
+            let mut starts = vec![];
+            let mut offset = 0;
+            for line in body.split_inclusive('\n') {
+                let bytes = line.as_bytes();
+                if bytes.get(out_column - 1) == Some(&b' ')
+                    && bytes
+                        .get(out_column)
+                        .is_some_and(|b| !b.is_ascii_whitespace())
+                {
+                    starts.push(offset);
+                }
+                offset += line.len();
+            }
+            Some((header, split_at(body, &starts)))
+        }
+        MapfileFormat::Gold
+        | MapfileFormat::Msvc
+        | MapfileFormat::Armlink
+        | MapfileFormat::Iar
+        | MapfileFormat::Ghs
+        | MapfileFormat::Ld64
+        | MapfileFormat::Ti
+        | MapfileFormat::Segger
+        | MapfileFormat::Renesas
+        | MapfileFormat::Microchip => None,
+    }
+}
+
+/// The 1-based line of `mapfile` every one of its `chunks` of [`section_chunks`] starts at.
+pub(crate) fn chunk_lines(mapfile: &str, chunks: &[&str]) -> Vec<usize> {
+    let mut line = 1;
+    let mut offset = 0;
+    chunks
+        .iter()
+        .map(|chunk| {
+            // The chunks are slices of the map, in order
+            let start = chunk.as_ptr() as usize - mapfile.as_ptr() as usize;
+            line += mapfile[offset..start].matches('\n').count();
+            offset = start;
+            line
+        })
+        .collect()
+}
+
+/// Moves the map lines of `section` and its subsections `by` lines down, for sections parsed
+/// from a part of the map.
+pub(crate) fn shift_lines(section: &mut Section, by: isize) {
+    let shift = |line: &mut Option<usize>| {
+        if let Some(line) = line {
+            *line = line.saturating_add_signed(by);
+        }
+    };
+    shift(&mut section.line);
+    for subsection in &mut section.subsections {
+        shift(&mut subsection.line);
+    }
+}
+
+/// The slices of `text` from every start to the next one, the text before the first is dropped.
+fn split_at<'a>(text: &'a str, starts: &[usize]) -> Vec<&'a str> {
+    starts
+        .iter()
+        .zip(starts.iter().skip(1).chain([&text.len()]))
+        .map(|(&start, &end)| &text[start..end])
+        .collect()
+}
+
+fn hash(text: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    text.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Genealogy;
+
+    use super::LayoutCache;
+
+    #[test]
+    fn test_layout_cache() {
+        for (binary, map, text_contribution) in [
+            ("tests/gcc/a.out", "tests/gcc/out.map", "0x8 simple2.o"),
+            (
+                "tests/clang/a.out",
+                "tests/clang/output.map",
+                "simple2.o:(.text)",
+            ),
+        ] {
+            let binary = std::fs::read(binary).unwrap();
+            let mapfile = std::fs::read_to_string(map).unwrap();
+            let mut cache = LayoutCache::default();
+
+            let genealogy = Genealogy::builder(&mapfile, &binary)
+                .cache(&mut cache)
+                .build()
+                .unwrap();
+            let full = Genealogy::new(&mapfile, &binary).unwrap();
+            assert_eq!(genealogy.sections(), full.sections());
+            assert!(cache.changed_sections().contains(&".text".to_string()));
+
+            let genealogy = Genealogy::builder(&mapfile, &binary)
+                .cache(&mut cache)
+                .build()
+                .unwrap();
+            assert_eq!(genealogy.sections(), full.sections());
+            assert!(cache.changed_sections().is_empty());
+
+            // Only the section whose text changed is parsed again
+            let renamed = mapfile.replace(
+                text_contribution,
+                &text_contribution.replace("simple2.o", "renamed.o"),
+            );
+            let genealogy = Genealogy::builder(&renamed, &binary)
+                .cache(&mut cache)
+                .build()
+                .unwrap();
+            assert_eq!(
+                genealogy.sections(),
+                Genealogy::new(&renamed, &binary).unwrap().sections()
+            );
+            assert_eq!(cache.changed_sections(), [".text"]);
+
+            // The sections after a section that grew are reused at their new lines
+            let end = renamed.find("renamed.o").unwrap();
+            let end = end + renamed[end..].find('\n').unwrap();
+            let grown = format!("{}\n{}", &renamed[..end], &renamed[end..]);
+            let genealogy = Genealogy::builder(&grown, &binary)
+                .cache(&mut cache)
+                .build()
+                .unwrap();
+            assert_eq!(
+                genealogy.sections(),
+                Genealogy::new(&grown, &binary).unwrap().sections()
+            );
+            assert_eq!(cache.changed_sections(), [".text"]);
+        }
+    }
+}
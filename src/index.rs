@@ -0,0 +1,381 @@
+//! The layout model and the interval index answering queries on it. Unlike the parsers, they only
+//! need `alloc`: with the `std` feature disabled, an [`Index`] can be built from [`Section`]s and
+//! queried on targets such as monitors or bootloaders, without keeping the sections.
+
+use alloc::{collections::BTreeMap, format, string::String, vec::Vec};
+use core::{fmt::Display, iter, mem::size_of, ops::Range};
+
+use crate::{
+    intervals::{Interval, Intervals},
+    GenealogyError,
+};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Section {
+    pub name: String,
+    pub start_vaddr: u64,
+    pub start_file_offset: Option<u64>,
+    pub size: u64,
+    /// Whether the section occupies memory at runtime (`SHF_ALLOC` for ELF), filled in when
+    /// mapping to the binary.
+    pub loaded: bool,
+    pub subsections: Vec<SubSection>,
+    /// The 1-based line of the map the section was parsed from, `None` for sections the map
+    /// does not list, e.g. those of MSVC maps, which come from the PE headers.
+    pub line: Option<usize>,
+}
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SubSection {
+    pub name: String,
+    pub start_vaddr: u64,
+    pub start_file_offset: Option<u64>,
+    pub size: u64,
+    pub filename: String,
+    /// Other files whose identical sections the linker folded into this one (lld `--icf`, COMDAT
+    /// deduplication), they share its address range.
+    pub folded_with: Vec<String>,
+    /// The 1-based line of the map the subsection was parsed from: its input section line, the
+    /// line of its name when wrapped, or the first symbol of the run it was inferred from.
+    /// `None` for subsections found elsewhere, e.g. PE resources.
+    pub line: Option<usize>,
+}
+
+#[cfg(feature = "std")]
+impl SubSection {
+    /// The file of the subsection, then the files folded into it.
+    pub(crate) fn filenames(&self) -> impl Iterator<Item = &String> {
+        iter::once(&self.filename).chain(&self.folded_with)
+    }
+
+    pub(crate) fn filenames_mut(&mut self) -> impl Iterator<Item = &mut String> {
+        iter::once(&mut self.filename).chain(&mut self.folded_with)
+    }
+}
+
+/// The number of an origin in an [`Index`], to count or group hits in arrays rather than by
+/// comparing file names, see [`Index::file_id`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct FileId(u32);
+
+impl FileId {
+    pub(crate) fn new(id: u32) -> Self {
+        Self(id)
+    }
+
+    /// From 0 to the number of files of the index, excluded.
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+/// The subsections of a layout indexed by file offset and by virtual address. The index does not
+/// keep the layout, only the ranges and files of its subsections.
+pub struct Index {
+    intervals: Intervals,
+    vaddr_intervals: Intervals,
+    /// The file of every [`FileId`], and the reverse.
+    files: Vec<String>,
+    file_ids: BTreeMap<String, FileId>,
+}
+
+impl Index {
+    /// Indexes the subsections that have a file offset by file offset, and those of loaded
+    /// sections by virtual address. Empty subsections cannot contain anything, they stay in the
+    /// sections only.
+    ///
+    /// Building takes O(n log n) for n subsections.
+    pub fn new(sections: &[Section]) -> Result<Self, GenealogyError> {
+        // Files are numbered in the order the sections list them, folded ones included
+        let mut files: Vec<String> = Vec::new();
+        let mut file_ids: BTreeMap<String, FileId> = BTreeMap::new();
+        for filename in sections
+            .iter()
+            .flat_map(|s| s.subsections.iter())
+            .flat_map(|sub_section| {
+                iter::once(&sub_section.filename).chain(&sub_section.folded_with)
+            })
+        {
+            if !file_ids.contains_key(filename) {
+                let id = u32::try_from(files.len()).expect("fewer than 2^32 files");
+                file_ids.insert(filename.clone(), FileId(id));
+                files.push(filename.clone());
+            }
+        }
+        let vaddr_intervals = Intervals::new(
+            sections
+                .iter()
+                .filter(|s| s.loaded)
+                .flat_map(|s| s.subsections.iter())
+                .filter(|sub_section| sub_section.size > 0)
+                .map(|sub_section| {
+                    Ok(interval(
+                        range(
+                            sub_section.start_vaddr,
+                            sub_section.size,
+                            &sub_section.filename,
+                        )?,
+                        sub_section,
+                        &file_ids,
+                    ))
+                })
+                .collect::<Result<Vec<_>, GenealogyError>>()?,
+        );
+
+        let intervals = Intervals::new(
+            sections
+                .iter()
+                .flat_map(|s| s.subsections.iter())
+                .filter(|sub_section| sub_section.size > 0)
+                .filter_map(|sub_section| {
+                    sub_section.start_file_offset.map(|file_offset| {
+                        Ok(interval(
+                            range(file_offset, sub_section.size, &sub_section.filename)?,
+                            sub_section,
+                            &file_ids,
+                        ))
+                    })
+                })
+                .collect::<Result<Vec<_>, GenealogyError>>()?,
+        );
+
+        Ok(Self {
+            intervals,
+            vaddr_intervals,
+            files,
+            file_ids,
+        })
+    }
+
+    /// The subsections overlapping the file offsets in `range`.
+    ///
+    /// Queries take O(log n + k) for n indexed subsections and k hits. The `genealogy`
+    /// benchmarks (`cargo bench --features bench`) track them.
+    pub fn query(&self, range: Range<u64>) -> impl Iterator<Item = QueryHit<'_>> {
+        self.intervals
+            .query(range)
+            .map(|interval| self.hit(interval))
+    }
+
+    /// The subsections containing the file offset `point`, see [`Index::query`].
+    pub fn query_point(&self, point: u64) -> impl Iterator<Item = QueryHit<'_>> {
+        self.intervals
+            .query_point(point)
+            .map(|interval| self.hit(interval))
+    }
+
+    /// Like [`Index::query`], with virtual addresses instead of file offsets.
+    pub fn query_vaddr(&self, range: Range<u64>) -> impl Iterator<Item = QueryHit<'_>> {
+        self.vaddr_intervals
+            .query(range)
+            .map(|interval| self.hit(interval))
+    }
+
+    /// Like [`Index::query_point`], with a virtual address instead of a file offset.
+    pub fn query_vaddr_point(&self, vaddr: u64) -> impl Iterator<Item = QueryHit<'_>> {
+        self.vaddr_intervals
+            .query_point(vaddr)
+            .map(|interval| self.hit(interval))
+    }
+
+    /// Iterates over every range attributed by file offset, ordered by start offset.
+    pub fn iter(&self) -> impl Iterator<Item = QueryHit<'_>> {
+        self.intervals.iter().map(|interval| self.hit(interval))
+    }
+
+    /// Like [`Index::iter`], with virtual addresses instead of file offsets.
+    pub fn iter_vaddr(&self) -> impl Iterator<Item = QueryHit<'_>> {
+        self.vaddr_intervals
+            .iter()
+            .map(|interval| self.hit(interval))
+    }
+
+    /// The number of `filename`, `None` if no subsection comes from it.
+    pub fn file_id(&self, filename: &str) -> Option<FileId> {
+        self.file_ids.get(filename).copied()
+    }
+
+    /// The file numbered `id`.
+    ///
+    /// # Panics
+    ///
+    /// If `id` comes from another index with more files.
+    pub fn file_name(&self, id: FileId) -> &str {
+        &self.files[id.index()]
+    }
+
+    /// The files of the subsections by [`FileId`], in the order the sections list them.
+    pub fn files(&self) -> &[String] {
+        &self.files
+    }
+
+    /// The memory taken by the index, to size it against the map it was built from.
+    pub fn stats(&self) -> IndexStats {
+        let strings = |strings: &[String]| {
+            strings
+                .iter()
+                .map(|string| size_of::<String>() + string.capacity())
+                .sum::<usize>()
+        };
+        IndexStats {
+            intervals: self.intervals.len(),
+            vaddr_intervals: self.vaddr_intervals.len(),
+            files: self.files.len(),
+            interval_bytes: self.intervals.heap_size() + self.vaddr_intervals.heap_size(),
+            file_bytes: strings(&self.files)
+                + self
+                    .file_ids
+                    .keys()
+                    .map(|file| size_of::<String>() + size_of::<FileId>() + file.capacity())
+                    .sum::<usize>(),
+        }
+    }
+
+    fn hit<'a>(&'a self, interval: Interval<'a>) -> QueryHit<'a> {
+        QueryHit {
+            filename: self.file_name(interval.file),
+            range: interval.range,
+            file_id: interval.file,
+            folded_with: interval.folded_with,
+            line: interval.line,
+        }
+    }
+}
+
+/// The memory taken by an [`Index`], see [`Index::stats`]. Sizes are heap bytes, approximated
+/// from the lengths and capacities of the containers, map nodes excluded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct IndexStats {
+    /// The ranges indexed by file offset.
+    pub intervals: usize,
+    /// The ranges indexed by virtual address.
+    pub vaddr_intervals: usize,
+    pub files: usize,
+    /// The two interval trees, about 24 bytes per range.
+    pub interval_bytes: usize,
+    /// The file names and their [`FileId`]s.
+    pub file_bytes: usize,
+}
+
+impl IndexStats {
+    pub fn total_bytes(&self) -> usize {
+        self.interval_bytes + self.file_bytes
+    }
+}
+
+/// A file offset (or virtual address) range and the file it originates from, as returned by
+/// [`Index::query`] (or [`Index::query_vaddr`]).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct QueryHit<'a> {
+    pub range: Range<u64>,
+    pub filename: &'a str,
+    /// The number of `filename` in the index.
+    pub file_id: FileId,
+    /// The files whose sections were folded into the range, see [`SubSection::folded_with`].
+    pub folded_with: &'a [String],
+    /// The map line of the subsection, see [`SubSection::line`].
+    pub line: Option<usize>,
+}
+
+/// The interval of `sub_section` over `range`.
+fn interval<'a>(
+    range: Range<u64>,
+    sub_section: &'a SubSection,
+    file_ids: &BTreeMap<String, FileId>,
+) -> Interval<'a> {
+    Interval {
+        range,
+        file: file_ids[&sub_section.filename],
+        folded_with: &sub_section.folded_with,
+        line: sub_section.line,
+    }
+}
+
+/// `start..start + size`, failing instead of wrapping around at the end of the address space.
+pub(crate) fn range(
+    start: u64,
+    size: u64,
+    what: &impl Display,
+) -> Result<Range<u64>, GenealogyError> {
+    let end = start.checked_add(size).ok_or_else(|| {
+        GenealogyError::AddressOutOfRange(format!(
+            "0x{start:08x} + 0x{size:08x} overflows in {what}"
+        ))
+    })?;
+    Ok(start..end)
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{string::ToString, vec};
+
+    use super::{Index, Section, SubSection};
+
+    #[test]
+    fn test_index() {
+        let subsection = |start, file_offset, size, filename: &str| SubSection {
+            name: ".text".to_string(),
+            start_vaddr: start,
+            start_file_offset: file_offset,
+            size,
+            filename: filename.to_string(),
+            folded_with: vec![],
+            line: None,
+        };
+        let sections = vec![
+            Section {
+                name: ".text".to_string(),
+                start_vaddr: 0x8000_0000,
+                start_file_offset: Some(0x100),
+                size: 0x30,
+                loaded: true,
+                subsections: vec![
+                    subsection(0x8000_0000, Some(0x100), 0x20, "boot.o"),
+                    subsection(0x8000_0020, Some(0x120), 0x10, "uart.o"),
+                    subsection(0x8000_0030, Some(0x130), 0, "empty.o"),
+                ],
+                line: None,
+            },
+            Section {
+                name: ".comment".to_string(),
+                start_vaddr: 0,
+                start_file_offset: Some(0x200),
+                size: 0x10,
+                loaded: false,
+                subsections: vec![subsection(0, Some(0x200), 0x10, "boot.o")],
+                line: None,
+            },
+        ];
+        let index = Index::new(&sections).unwrap();
+
+        assert_eq!(
+            index
+                .query_vaddr_point(0x8000_0024)
+                .next()
+                .unwrap()
+                .filename,
+            "uart.o"
+        );
+        assert_eq!(index.query_point(0x205).next().unwrap().filename, "boot.o");
+        assert!(index.query_vaddr_point(0x5).next().is_none());
+        assert_eq!(index.query_vaddr(0x8000_0000..0x8000_0040).count(), 2);
+        assert_eq!(index.iter().count(), 3);
+        assert_eq!(index.iter_vaddr().count(), 2);
+
+        // Numbered in the order of the sections, the empty file too
+        assert_eq!(index.files(), ["boot.o", "uart.o", "empty.o"]);
+        let uart = index.file_id("uart.o").unwrap();
+        assert_eq!(index.file_name(uart), "uart.o");
+        assert_eq!(uart.index(), 1);
+        assert_eq!(index.query_point(0x125).next().unwrap().file_id, uart);
+        assert_eq!(index.file_id("main.o"), None);
+
+        // 24 bytes per range, 3 by file offset and 2 by virtual address
+        let stats = index.stats();
+        assert_eq!(
+            (stats.intervals, stats.vaddr_intervals, stats.files),
+            (3, 2, 3)
+        );
+        assert_eq!(stats.interval_bytes, 5 * 24);
+        assert!(stats.total_bytes() > stats.interval_bytes);
+    }
+}
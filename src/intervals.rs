@@ -0,0 +1,354 @@
+//! The interval trees of an [`Index`](crate::Index), laid out for indexes of tens of millions of
+//! subsections: every interval takes 24 bytes, its start, its size and the largest end of its
+//! subtree as a delta from the start, its [`FileId`] and its map line, where a generic tree of
+//! ranges and owned payloads takes several times that. Sizes and deltas that do not fit 32
+//! bits, and the files folded into an interval, are kept aside.
+//!
+//! The tree is implicit, as in the `intervaltree` crate: intervals are sorted by start, and the
+//! root of every slice is its middle one.
+
+use alloc::{collections::BTreeMap, string::String, vec::Vec};
+use core::{mem::size_of, ops::Range};
+
+use crate::index::FileId;
+
+/// The 32-bit value standing for a size or a delta kept in the `spilled` maps.
+const SPILLED: u32 = u32::MAX;
+/// The bit of [`Payload::file`] telling that files were folded into the interval.
+const FOLDED: u32 = 1 << 31;
+
+/// The origins of an interval.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Payload {
+    /// The [`FileId`], with the [`FOLDED`] flag.
+    file: u32,
+    /// The 1-based map line, 0 when unknown.
+    line: u32,
+}
+
+/// An interval and its origins, see [`Intervals::new`].
+pub(crate) struct Interval<'a> {
+    pub range: Range<u64>,
+    pub file: FileId,
+    pub folded_with: &'a [String],
+    pub line: Option<usize>,
+}
+
+pub(crate) struct Intervals {
+    starts: Vec<u64>,
+    sizes: Vec<u32>,
+    /// The largest end of the subtree of every interval, minus its start.
+    max_ends: Vec<u32>,
+    payloads: Vec<Payload>,
+    spilled_sizes: BTreeMap<u32, u64>,
+    spilled_max_ends: BTreeMap<u32, u64>,
+    folded: BTreeMap<u32, Vec<String>>,
+}
+
+/// Largest end of the implicit tree of `ends` rooted in its middle, written in `max_ends`.
+fn update_max(ends: &[u64], max_ends: &mut [u64]) -> u64 {
+    let i = ends.len() / 2;
+    let mut max = ends[i];
+    if i > 0 {
+        max = max.max(update_max(&ends[..i], &mut max_ends[..i]));
+    }
+    if i + 1 < ends.len() {
+        max = max.max(update_max(&ends[i + 1..], &mut max_ends[i + 1..]));
+    }
+    max_ends[i] = max;
+    max
+}
+
+impl Intervals {
+    /// Building takes O(n log n) for n intervals, which must be fewer than 2^31.
+    pub fn new<'a>(intervals: impl IntoIterator<Item = Interval<'a>>) -> Self {
+        let mut intervals: Vec<Interval> = intervals.into_iter().collect();
+        // Stable, so that intervals of the same start are returned in the order of the map
+        intervals.sort_by_key(|interval| interval.range.start);
+        assert!(
+            intervals.len() < FOLDED as usize,
+            "fewer than 2^31 intervals"
+        );
+
+        let mut index = Self {
+            starts: Vec::with_capacity(intervals.len()),
+            sizes: Vec::with_capacity(intervals.len()),
+            max_ends: Vec::with_capacity(intervals.len()),
+            payloads: Vec::with_capacity(intervals.len()),
+            spilled_sizes: BTreeMap::new(),
+            spilled_max_ends: BTreeMap::new(),
+            folded: BTreeMap::new(),
+        };
+        for (i, interval) in intervals.iter().enumerate() {
+            let i = i as u32;
+            let size = interval.range.end - interval.range.start;
+            index.starts.push(interval.range.start);
+            index.sizes.push(match u32::try_from(size) {
+                Ok(size) if size != SPILLED => size,
+                _ => {
+                    index.spilled_sizes.insert(i, size);
+                    SPILLED
+                }
+            });
+            let mut file = interval.file.index() as u32;
+            assert_eq!(file & FOLDED, 0, "fewer than 2^31 files");
+            if !interval.folded_with.is_empty() {
+                file |= FOLDED;
+                index.folded.insert(i, interval.folded_with.to_vec());
+            }
+            index.payloads.push(Payload {
+                file,
+                line: interval
+                    .line
+                    .and_then(|line| u32::try_from(line).ok())
+                    .unwrap_or(0),
+            });
+        }
+
+        let ends: Vec<u64> = intervals
+            .iter()
+            .map(|interval| interval.range.end)
+            .collect();
+        let mut max_ends = alloc::vec![0; ends.len()];
+        if !ends.is_empty() {
+            update_max(&ends, &mut max_ends);
+        }
+        for (i, (max_end, start)) in max_ends.into_iter().zip(&index.starts).enumerate() {
+            let delta = max_end - start;
+            index.max_ends.push(match u32::try_from(delta) {
+                Ok(delta) if delta != SPILLED => delta,
+                _ => {
+                    index.spilled_max_ends.insert(i as u32, delta);
+                    SPILLED
+                }
+            });
+        }
+        index
+    }
+
+    pub fn len(&self) -> usize {
+        self.starts.len()
+    }
+
+    /// Heap bytes of the intervals, spilled values and folded files included.
+    pub fn heap_size(&self) -> usize {
+        let spilled = (self.spilled_sizes.len() + self.spilled_max_ends.len())
+            * (size_of::<u32>() + size_of::<u64>());
+        let folded: usize = self
+            .folded
+            .values()
+            .map(|files| {
+                size_of::<u32>()
+                    + size_of::<Vec<String>>()
+                    + files
+                        .iter()
+                        .map(|file| size_of::<String>() + file.capacity())
+                        .sum::<usize>()
+            })
+            .sum();
+        self.starts.capacity() * size_of::<u64>()
+            + (self.sizes.capacity() + self.max_ends.capacity()) * size_of::<u32>()
+            + self.payloads.capacity() * size_of::<Payload>()
+            + spilled
+            + folded
+    }
+
+    fn end(&self, i: usize) -> u64 {
+        let size = match self.sizes[i] {
+            SPILLED => self.spilled_sizes[&(i as u32)],
+            size => u64::from(size),
+        };
+        self.starts[i] + size
+    }
+
+    fn max_end(&self, i: usize) -> u64 {
+        let delta = match self.max_ends[i] {
+            SPILLED => self.spilled_max_ends[&(i as u32)],
+            delta => u64::from(delta),
+        };
+        self.starts[i] + delta
+    }
+
+    /// The interval at position `i`, in start order.
+    pub fn get(&self, i: usize) -> Interval<'_> {
+        let payload = self.payloads[i];
+        Interval {
+            range: self.starts[i]..self.end(i),
+            file: FileId::new(payload.file & !FOLDED),
+            folded_with: match payload.file & FOLDED {
+                0 => &[],
+                _ => &self.folded[&(i as u32)],
+            },
+            line: (payload.line > 0).then_some(payload.line as usize),
+        }
+    }
+
+    /// The intervals overlapping `range`, in O(log n + k) for k of them.
+    pub fn query(&self, range: Range<u64>) -> Query<'_> {
+        Query::new(self, range.start, range.end, false)
+    }
+
+    /// The intervals containing `point`.
+    pub fn query_point(&self, point: u64) -> Query<'_> {
+        Query::new(self, point, point, true)
+    }
+
+    /// Every interval, in start order.
+    pub fn iter(&self) -> impl Iterator<Item = Interval<'_>> {
+        (0..self.len()).map(|i| self.get(i))
+    }
+}
+
+/// The height bound of the implicit trees of fewer than 2^31 intervals, and more than the
+/// slices waiting in a query.
+const MAX_PENDING: usize = 64;
+
+/// The intervals matching a query, by depth-first search.
+pub(crate) struct Query<'a> {
+    intervals: &'a Intervals,
+    start: u64,
+    /// The end of the range, or the point.
+    end: u64,
+    point: bool,
+    /// The slices left to search, as start and length.
+    pending: [(u32, u32); MAX_PENDING],
+    len: usize,
+}
+
+impl<'a> Query<'a> {
+    fn new(intervals: &'a Intervals, start: u64, end: u64, point: bool) -> Self {
+        let mut query = Self {
+            intervals,
+            start,
+            end,
+            point,
+            pending: [(0, 0); MAX_PENDING],
+            len: 0,
+        };
+        if intervals.len() > 0 {
+            query.push(0, intervals.len());
+        }
+        query
+    }
+
+    fn push(&mut self, start: usize, len: usize) {
+        self.pending[self.len] = (start as u32, len as u32);
+        self.len += 1;
+    }
+}
+
+impl<'a> Iterator for Query<'a> {
+    type Item = Interval<'a>;
+
+    fn next(&mut self) -> Option<Interval<'a>> {
+        while self.len > 0 {
+            self.len -= 1;
+            let (s, l) = self.pending[self.len];
+            let (s, l) = (s as usize, l as usize);
+            let i = s + l / 2;
+            if self.start >= self.intervals.max_end(i) {
+                continue;
+            }
+            if i > s {
+                self.push(s, i - s);
+            }
+            let node_start = self.intervals.starts[i];
+            let go_right = match self.point {
+                true => self.end >= node_start,
+                false => self.end > node_start,
+            };
+            if go_right {
+                if s + l > i + 1 {
+                    self.push(i + 1, s + l - i - 1);
+                }
+                if self.start < self.intervals.end(i) {
+                    return Some(self.intervals.get(i));
+                }
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{string::ToString, vec, vec::Vec};
+
+    use super::{Interval, Intervals};
+    use crate::index::FileId;
+
+    #[test]
+    fn test_intervals() {
+        let folded = vec!["b.o".to_string()];
+        // Overlapping, nested and huge ranges, whose sizes and deltas do not fit 32 bits
+        let ranges = [
+            (0x10, 0x20),
+            (0x10, 0x1_0000_0010),
+            (0x18, 0x1c),
+            (0x30, 0x40),
+            (0x40, 0x50),
+            (0x1_0000_0000, 0x1_0000_0004),
+            (u64::MAX - 4, u64::MAX),
+        ];
+        // Out of order, but for those of the same start
+        let intervals = Intervals::new([4, 6, 0, 3, 1, 5, 2].map(|i| {
+            let (start, end) = ranges[i];
+            Interval {
+                range: start..end,
+                file: FileId::new(i as u32),
+                folded_with: if i == 4 { &folded } else { &[] },
+                line: (i > 0).then_some(i),
+            }
+        }));
+        assert_eq!(intervals.len(), ranges.len());
+        let sorted: Vec<_> = intervals.iter().map(|interval| interval.range).collect();
+        assert_eq!(
+            sorted,
+            ranges.map(|(start, end)| start..end),
+            "ranges of the same start keep their order"
+        );
+        let huge = intervals.get(1);
+        assert_eq!((huge.file.index(), huge.line), (1, Some(1)));
+        assert_eq!(intervals.get(4).folded_with, ["b.o"]);
+        assert!(intervals.get(3).folded_with.is_empty());
+        assert_eq!(intervals.get(0).line, None);
+
+        let expected = |hit: &dyn Fn(u64, u64) -> bool| -> Vec<_> {
+            ranges
+                .iter()
+                .filter(|(start, end)| hit(*start, *end))
+                .map(|(start, end)| *start..*end)
+                .collect()
+        };
+        for point in [
+            0,
+            0x10,
+            0x1b,
+            0x1c,
+            0x40,
+            0x4f,
+            0x50,
+            0x1_0000_0001,
+            u64::MAX - 1,
+        ] {
+            let mut hits: Vec<_> = intervals.query_point(point).map(|i| i.range).collect();
+            hits.sort_by_key(|range| (range.start, range.end));
+            let mut expected = expected(&|start, end| start <= point && point < end);
+            expected.sort_by_key(|range| (range.start, range.end));
+            assert_eq!(hits, expected, "point 0x{point:x}");
+        }
+        for (start, end) in [
+            (0, 0x10),
+            (0x1c, 0x31),
+            (0x50, 0x1_0000_0000),
+            (0x48, u64::MAX),
+        ] {
+            let mut hits: Vec<_> = intervals.query(start..end).map(|i| i.range).collect();
+            hits.sort_by_key(|range| (range.start, range.end));
+            let mut expected = expected(&|s, e| s < end && start < e);
+            expected.sort_by_key(|range| (range.start, range.end));
+            assert_eq!(hits, expected, "range 0x{start:x}..0x{end:x}");
+        }
+        assert!(Intervals::new([]).query_point(0).next().is_none());
+    }
+}
@@ -0,0 +1,249 @@
+//! Linux kernel images: the `System.map` of a kernel build, and what every object file of
+//! `vmlinux` or of a module adds to the sections the kernel treats specially: exported symbols in
+//! `__ksymtab`, per-CPU variables, and the code and data freed after boot.
+//!
+//! `vmlinux` is mapped with the `vmlinux.map` written with `CONFIG_VMLINUX_MAP`, modules with
+//! a map of their final link (`ld -r -Map`).
+
+use std::{collections::HashMap, fmt::Display};
+
+use crate::{
+    format::{human_size, Align, Cell, Style, Table},
+    Genealogy, GenealogyError,
+};
+
+/// The prefix of the symbols of the `__ksymtab` entries `EXPORT_SYMBOL` emits.
+pub const KSYMTAB_PREFIX: &str = "__ksymtab_";
+/// The output section of per-CPU variables.
+pub const PERCPU_SECTION: &str = ".data..percpu";
+
+/// A line of a `System.map`, e.g. `ffffffff81000000 T _text`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KernelSymbol {
+    pub vaddr: u64,
+    /// The `nm` type, lowercase for local symbols.
+    pub kind: char,
+    pub name: String,
+}
+
+/// Parses a `System.map`, or the output of `nm -n`, skipping undefined symbols.
+pub fn parse_system_map(text: &str) -> Result<Vec<KernelSymbol>, GenealogyError> {
+    let mut symbols = vec![];
+    for line in text.lines().filter(|line| !line.trim().is_empty()) {
+        let mut fields = line.split_whitespace();
+        let symbol = match (fields.next(), fields.next(), fields.next()) {
+            // `nm` lists undefined symbols without an address
+            (Some("U" | "w" | "v"), Some(_), None) => continue,
+            (Some(vaddr), Some(kind), Some(name)) if kind.len() == 1 => KernelSymbol {
+                vaddr: u64::from_str_radix(vaddr, 16)
+                    .map_err(|_| GenealogyError::WrongSystemMapFormat)?,
+                kind: kind.chars().next().expect("one character"),
+                name: name.to_string(),
+            },
+            _ => return Err(GenealogyError::WrongSystemMapFormat),
+        };
+        symbols.push(symbol);
+    }
+    Ok(symbols)
+}
+
+/// The kernel-specific bytes of every file, see [`Genealogy::kernel_report`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KernelReport {
+    /// Files with the most exports first.
+    pub entries: Vec<KernelEntry>,
+    /// The bytes freed after boot, attributed or not.
+    pub init: u64,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KernelEntry {
+    pub filename: String,
+    /// Symbols exported with `EXPORT_SYMBOL` and its variants.
+    pub exports: u64,
+    /// Bytes of per-CPU variables, replicated for every CPU at runtime.
+    pub percpu: u64,
+    /// Bytes freed after boot, or after the initialization of a module.
+    pub init: u64,
+}
+
+impl Genealogy {
+    /// Attributes the exports, per-CPU variables and init sections of a kernel image or
+    /// module to its files.
+    ///
+    /// Exports are the `__ksymtab_<symbol>` symbols of `system_map`, or of the symbol index
+    /// when `system_map` is empty, e.g. for modules, and the genealogy was built with
+    /// [`GenealogyBuilder::symbols`]. The init bytes lie between the `__init_begin` and
+    /// `__init_end` symbols of `vmlinux`, and in the `.init.*` sections of modules.
+    ///
+    /// [`GenealogyBuilder::symbols`]: crate::GenealogyBuilder::symbols
+    pub fn kernel_report(&self, system_map: &[KernelSymbol]) -> KernelReport {
+        let symbols: Vec<(&str, u64)> = match self.symbols() {
+            Some(index) if system_map.is_empty() => index
+                .iter()
+                .map(|symbol| (symbol.name.as_str(), symbol.vaddr))
+                .collect(),
+            _ => system_map
+                .iter()
+                .map(|symbol| (symbol.name.as_str(), symbol.vaddr))
+                .collect(),
+        };
+        let address = |name: &str| {
+            symbols
+                .iter()
+                .find(|&&(symbol, _)| symbol == name)
+                .map(|&(_, vaddr)| vaddr)
+        };
+        let init_range = address("__init_begin")
+            .zip(address("__init_end"))
+            .map(|(begin, end)| begin..end);
+
+        // Exports, per-CPU and init bytes
+        let mut files: HashMap<&str, (u64, u64, u64)> = HashMap::new();
+        for &(name, vaddr) in &symbols {
+            if !name.starts_with(KSYMTAB_PREFIX) {
+                continue;
+            }
+            if let Some(hit) = self.query_vaddr_point(vaddr).next() {
+                files.entry(hit.filename).or_default().0 += 1;
+            }
+        }
+        let mut init = 0;
+        for section in self.sections() {
+            let percpu = section.name == PERCPU_SECTION;
+            let section_init = init_range.is_none() && section.name.starts_with(".init");
+            for subsection in &section.subsections {
+                let range =
+                    subsection.start_vaddr..subsection.start_vaddr.saturating_add(subsection.size);
+                let init_bytes = match &init_range {
+                    Some(init_range) => range
+                        .end
+                        .min(init_range.end)
+                        .saturating_sub(range.start.max(init_range.start)),
+                    None if section_init => subsection.size,
+                    None => 0,
+                };
+                init += init_bytes;
+                if percpu || init_bytes > 0 {
+                    let entry = files.entry(&subsection.filename).or_default();
+                    entry.2 += init_bytes;
+                    if percpu {
+                        entry.1 += subsection.size;
+                    }
+                }
+            }
+        }
+        if let Some(init_range) = &init_range {
+            // Padding and linker-generated tables are freed too
+            init = init.max(init_range.end.saturating_sub(init_range.start));
+        }
+
+        let mut entries: Vec<KernelEntry> = files
+            .into_iter()
+            .map(|(filename, (exports, percpu, init))| KernelEntry {
+                filename: filename.to_string(),
+                exports,
+                percpu,
+                init,
+            })
+            .collect();
+        entries.sort_by(|a, b| {
+            b.exports
+                .cmp(&a.exports)
+                .then_with(|| b.percpu.cmp(&a.percpu))
+                .then_with(|| b.init.cmp(&a.init))
+                .then_with(|| a.filename.cmp(&b.filename))
+        });
+        KernelReport { entries, init }
+    }
+}
+
+impl KernelReport {
+    /// The table rendered by `Display`.
+    pub fn to_table(&self) -> Table {
+        let mut table = Table::new(&[Align::Right, Align::Right, Align::Right, Align::Left])
+            .with_header(["exports", "percpu", "init", "file"]);
+        for entry in &self.entries {
+            table.push_row([
+                entry.exports.to_string(),
+                human_size(entry.percpu),
+                human_size(entry.init),
+                entry.filename.clone(),
+            ]);
+        }
+        let exports: u64 = self.entries.iter().map(|entry| entry.exports).sum();
+        let percpu: u64 = self.entries.iter().map(|entry| entry.percpu).sum();
+        table.push_row([
+            Cell::styled(exports.to_string(), Some(Style::Bold)),
+            Cell::styled(human_size(percpu), Some(Style::Bold)),
+            Cell::styled(human_size(self.init), Some(Style::Bold)),
+            format!("total in {} files", self.entries.len()).into(),
+        ]);
+        table
+    }
+}
+
+impl Display for KernelReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_table())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Genealogy, GenealogyError};
+
+    use super::{parse_system_map, KernelSymbol};
+
+    #[test]
+    fn test_kernel_report() {
+        let system_map = std::fs::read_to_string("tests/kernel/System.map").unwrap();
+        let symbols = parse_system_map(&system_map).unwrap();
+        assert_eq!(
+            symbols[1],
+            KernelSymbol {
+                vaddr: 0,
+                kind: 'D',
+                name: "process_counts".to_string()
+            }
+        );
+        assert!(matches!(
+            parse_system_map("                 U printk\nffffffff81000000 T"),
+            Err(GenealogyError::WrongSystemMapFormat)
+        ));
+
+        // Per-CPU variables are linked at 0, and `__ksymtab` is not a dot section
+        let mapfile = std::fs::read_to_string("tests/kernel/vmlinux.map").unwrap();
+        let binary = std::fs::read("tests/kernel/vmlinux").unwrap();
+        let genealogy = Genealogy::new(&mapfile, &binary).unwrap();
+        let report = genealogy.kernel_report(&symbols);
+        let entries: Vec<_> = report
+            .entries
+            .iter()
+            .map(|entry| {
+                (
+                    entry.filename.as_str(),
+                    entry.exports,
+                    entry.percpu,
+                    entry.init,
+                )
+            })
+            .collect();
+        assert_eq!(
+            entries,
+            [
+                ("kernel/fork.o", 2, 0x8, 0x7 + 0x4),
+                ("kernel/sched.o", 1, 0x80, 0),
+                ("init/main.o", 0, 0, 0x23 + 0x100),
+            ]
+        );
+        assert_eq!(report.init, 0x1000);
+
+        // The same from the symbol table of the binary
+        let genealogy = Genealogy::builder(&mapfile, &binary)
+            .symbols(true)
+            .build()
+            .unwrap();
+        assert_eq!(genealogy.kernel_report(&[]), report);
+    }
+}
@@ -0,0 +1,334 @@
+//! Classification of origins by programming language, to split a report between the teams of a
+//! mixed-language product. Languages are guessed from file and archive names only.
+
+use std::{collections::HashMap, fmt::Display};
+
+use crate::{
+    format::{human_size, percentage, Align, Cell, Style, Table},
+    origin::{file_name, Origin},
+    Report, ReportEntry,
+};
+
+/// The origin the files of [`Language::Runtime`] are folded into, see [`Report::fold_runtime`].
+pub const RUNTIME: &str = "<runtime>";
+
+/// Objects of the C runtime start files, by file name prefix. `crt0` is the start file of
+/// newlib and of bare-metal toolchains, `crt2` and `dllcrt2` the ones of MinGW.
+const RUNTIME_OBJECTS: [&str; 13] = [
+    "crt0.",
+    "crt1.",
+    "Scrt1.",
+    "rcrt1.",
+    "gcrt1.",
+    "Mcrt1.",
+    "crti.",
+    "crtn.",
+    "crtbegin",
+    "crtend",
+    "crtfastmath.",
+    "crt2.",
+    "dllcrt2.",
+];
+
+/// Archives of compiler support libraries and of the MSVC runtime, by file name prefix.
+/// `compiler_builtins` is Rust's port of compiler-rt.
+const RUNTIME_ARCHIVES: [&str; 11] = [
+    "libgcc.",
+    "libgcc_eh.",
+    "libgcc_s.",
+    "libclang_rt.",
+    "libcompiler_builtins-",
+    "msvcrt",
+    "libcmt",
+    "ucrt",
+    "libucrt",
+    "vcruntime",
+    "libvcruntime",
+];
+
+const CPP_ARCHIVES: [&str; 4] = ["libstdc++.", "libc++.", "libc++abi.", "libsupc++."];
+
+const C_ARCHIVES: [&str; 2] = ["libc.", "libm."];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Language {
+    Rust,
+    C,
+    Cpp,
+    Assembly,
+    /// Compiler support libraries and C runtime start files.
+    Runtime,
+    /// Contents synthesized by the linker, e.g. lld's `<internal>` or armlink's `anon$$obj.o`,
+    /// which holds its veneers and scatter-loading tables.
+    Linker,
+    Unknown,
+}
+
+impl Language {
+    /// Guesses the language of an origin as found in the map, e.g. `foo.cpp.o` as written by
+    /// CMake, a `.rlib` member or `libgcc.a(_udivdi3.o)`. Objects named after their source
+    /// without its extension, such as `foo.o`, are [`Language::Unknown`].
+    pub fn of(origin: &str) -> Self {
+        if origin == RUNTIME {
+            return Language::Runtime;
+        }
+        if origin.starts_with('<') || origin == "linker stubs" || origin == "anon$$obj.o" {
+            return Language::Linker;
+        }
+
+        let origin = Origin::parse(origin);
+        let object = file_name(origin.object);
+        let archive = origin.archive.map(file_name);
+        let starts_with_any =
+            |name: &str, prefixes: &[&str]| prefixes.iter().any(|prefix| name.starts_with(prefix));
+
+        if starts_with_any(object, &RUNTIME_OBJECTS) {
+            return Language::Runtime;
+        }
+        // MSVC maps name archive members after their archive, without extension
+        if let Some(library) = archive.or(Some(object).filter(|o| !o.contains('.'))) {
+            if starts_with_any(library, &RUNTIME_ARCHIVES) {
+                return Language::Runtime;
+            }
+            if starts_with_any(library, &CPP_ARCHIVES) {
+                return Language::Cpp;
+            }
+            if starts_with_any(library, &C_ARCHIVES) {
+                return Language::C;
+            }
+            if library.ends_with(".rlib") || is_rust_library_stem(library) {
+                return Language::Rust;
+            }
+        }
+
+        let source = object
+            .strip_suffix(".o")
+            .or_else(|| object.strip_suffix(".obj"))
+            .unwrap_or(object);
+        match source.rsplit_once('.').map(|(_, extension)| extension) {
+            Some("rcgu") => Language::Rust,
+            Some("c") => Language::C,
+            Some("cpp" | "cc" | "cxx" | "c++" | "C") => Language::Cpp,
+            Some("s" | "S" | "asm") => Language::Assembly,
+            _ => Language::Unknown,
+        }
+    }
+}
+
+/// Whether `name` looks like `liballoc-a743693af38ef10c`, the stem of an rlib.
+pub(crate) fn is_rust_library_stem(name: &str) -> bool {
+    name.starts_with("lib")
+        && name.rsplit_once('-').is_some_and(|(_, hash)| {
+            hash.len() == 16 && hash.bytes().all(|b| b.is_ascii_hexdigit())
+        })
+}
+
+impl Display for Language {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Language::Rust => "Rust",
+            Language::C => "C",
+            Language::Cpp => "C++",
+            Language::Assembly => "assembly",
+            Language::Runtime => "runtime",
+            Language::Linker => "linker",
+            Language::Unknown => "unknown",
+        })
+    }
+}
+
+/// Size per language, see [`Report::by_language`].
+#[derive(Clone, Debug)]
+pub struct LanguageReport {
+    /// Biggest first.
+    pub entries: Vec<LanguageEntry>,
+    pub total: u64,
+}
+
+#[derive(Clone, Debug)]
+pub struct LanguageEntry {
+    pub language: Language,
+    pub size: u64,
+    /// Number of files classified as the language.
+    pub files: u64,
+}
+
+impl Report {
+    /// Sums the entries of the report per [`Language::of`] their file.
+    pub fn by_language(&self) -> LanguageReport {
+        let mut languages: HashMap<Language, (u64, u64)> = HashMap::new();
+        for entry in &self.entries {
+            let (size, files) = languages.entry(Language::of(&entry.filename)).or_default();
+            *size += entry.size;
+            *files += 1;
+        }
+
+        let mut entries: Vec<LanguageEntry> = languages
+            .into_iter()
+            .map(|(language, (size, files))| LanguageEntry {
+                language,
+                size,
+                files,
+            })
+            .collect();
+        entries.sort_by(|a, b| b.size.cmp(&a.size).then(a.language.cmp(&b.language)));
+
+        LanguageReport {
+            entries,
+            total: self.total,
+        }
+    }
+
+    /// Folds the entries of [`Language::Runtime`] (start files, compiler support libraries and
+    /// the MSVC runtime) into a single [`RUNTIME`] entry, leaving the files of the application.
+    pub fn fold_runtime(&self) -> Report {
+        let (runtime, mut entries): (Vec<&ReportEntry>, Vec<&ReportEntry>) = self
+            .entries
+            .iter()
+            .partition(|entry| Language::of(&entry.filename) == Language::Runtime);
+        let folded = ReportEntry {
+            filename: RUNTIME.to_string(),
+            size: runtime.iter().map(|entry| entry.size).sum(),
+            source: None,
+        };
+        if !runtime.is_empty() {
+            // Biggest first, like the other entries
+            let position = entries
+                .iter()
+                .position(|entry| {
+                    entry.size < folded.size
+                        || (entry.size == folded.size && entry.filename > folded.filename)
+                })
+                .unwrap_or(entries.len());
+            entries.insert(position, &folded);
+        }
+        Report {
+            entries: entries.into_iter().cloned().collect(),
+            total: self.total,
+        }
+    }
+}
+
+impl LanguageReport {
+    /// The table rendered by `Display`.
+    pub fn to_table(&self) -> Table {
+        let mut table = Table::new(&[Align::Right, Align::Right, Align::Right, Align::Left])
+            .with_header(["size", "share", "files", "language"]);
+        for entry in &self.entries {
+            table.push_row([
+                human_size(entry.size),
+                percentage(entry.size, self.total),
+                entry.files.to_string(),
+                entry.language.to_string(),
+            ]);
+        }
+        table.push_row([
+            Cell::styled(human_size(self.total), Some(Style::Bold)),
+            percentage(self.total, self.total).into(),
+            self.entries
+                .iter()
+                .map(|e| e.files)
+                .sum::<u64>()
+                .to_string()
+                .into(),
+            "total".into(),
+        ]);
+        table
+    }
+}
+
+impl Display for LanguageReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_table())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Genealogy;
+
+    use super::{Language, RUNTIME};
+
+    #[test]
+    fn test_language_report() {
+        for (origin, language) in [
+            ("CMakeFiles/app.dir/src/main.cpp.o", Language::Cpp),
+            ("CMakeFiles/app.dir/src/util.c.o", Language::C),
+            ("start.S.o", Language::Assembly),
+            (
+                "/usr/lib/gcc/x86_64-linux-gnu/11/libgcc.a(_udivdi3.o)",
+                Language::Runtime,
+            ),
+            (
+                "libclang_rt.builtins-x86_64.a(divti3.c.o)",
+                Language::Runtime,
+            ),
+            ("/usr/lib/x86_64-linux-gnu/Scrt1.o", Language::Runtime),
+            (
+                "/usr/lib/gcc/x86_64-linux-gnu/11/crtendS.o",
+                Language::Runtime,
+            ),
+            ("/usr/lib/libstdc++.a(string-inst.o)", Language::Cpp),
+            (
+                "libcore-a241e0394ca8cc1d.rlib(core-a241e0394ca8cc1d.core.5a61-cgu.0.rcgu.o)",
+                Language::Rust,
+            ),
+            (
+                "genealogy.genealogy.21e9e755dfa74b4b-cgu.0.rcgu.o",
+                Language::Rust,
+            ),
+            ("libstd-4ee9ee8805e6ac55", Language::Rust),
+            ("vcruntime", Language::Runtime),
+            (
+                "/opt/arm-none-eabi/lib/thumb/v7e-m/crt0.o",
+                Language::Runtime,
+            ),
+            (
+                "/usr/lib/gcc/x86_64-w64-mingw32/10/libgcc_s.a(d000012.o)",
+                Language::Runtime,
+            ),
+            (RUNTIME, Language::Runtime),
+            ("<internal>", Language::Linker),
+            ("anon$$obj.o", Language::Linker),
+            ("simple.o", Language::Unknown),
+        ] {
+            assert_eq!(Language::of(origin), language, "{origin}");
+        }
+
+        let mapfile = std::fs::read_to_string("tests/cargo/out.map").unwrap();
+        let binary = std::fs::read("tests/cargo/genealogy").unwrap();
+        let report = Genealogy::new(&mapfile, &binary).unwrap().report();
+        let languages = report.by_language();
+
+        assert_eq!(languages.entries[0].language, Language::Rust);
+        assert_eq!(languages.total, report.total);
+        assert_eq!(
+            languages.entries.iter().map(|e| e.files).sum::<u64>(),
+            report.entries.len() as u64
+        );
+
+        let folded = report.fold_runtime();
+        assert_eq!(folded.total, report.total);
+        let runtime: u64 = report
+            .entries
+            .iter()
+            .filter(|entry| Language::of(&entry.filename) == Language::Runtime)
+            .map(|entry| entry.size)
+            .sum();
+        assert!(runtime > 0);
+        let entry = folded
+            .entries
+            .iter()
+            .find(|entry| entry.filename == RUNTIME)
+            .unwrap();
+        assert_eq!(entry.size, runtime);
+        assert!(folded
+            .entries
+            .windows(2)
+            .all(|pair| pair[0].size >= pair[1].size));
+        assert_eq!(
+            folded.entries.iter().map(|e| e.size).sum::<u64>(),
+            report.total
+        );
+    }
+}
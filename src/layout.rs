@@ -0,0 +1,299 @@
+//! A picture of the composition of a binary, for slides and reviews: its file offsets laid out
+//! left to right in bands, colored by component (see [`Granularities::component`]), with the
+//! biggest regions labelled. Rendered as SVG, which converters such as `rsvg-convert` or a
+//! browser turn into PNG.
+
+use std::{
+    collections::{HashMap, HashSet},
+    ops::Range,
+};
+
+use crate::{
+    format::{hex, human_size},
+    origin::{file_name, Granularities},
+    Genealogy,
+};
+
+/// The number of bands the file is split into by default.
+pub const DEFAULT_BANDS: u32 = 16;
+
+/// Width of the bands, in pixels.
+const BAND_WIDTH: u64 = 1200;
+const BAND_HEIGHT: u64 = 32;
+const BAND_GAP: u64 = 6;
+/// Room left of the bands for their start offset.
+const MARGIN: u64 = 90;
+const LEGEND_LINE: u64 = 18;
+/// Approximate width of a character of the labels, in pixels.
+const CHAR_WIDTH: u64 = 7;
+
+/// Colors of the biggest components, the others share [`OTHER`].
+const PALETTE: [&str; 12] = [
+    "#4e79a7", "#f28e2b", "#e15759", "#76b7b2", "#59a14f", "#edc948", "#b07aa1", "#ff9da7",
+    "#9c755f", "#86bcb6", "#d37295", "#a0cbe8",
+];
+const OTHER: &str = "#bab0ac";
+const UNATTRIBUTED: &str = "#eeeeee";
+
+/// The number of components labelled on their biggest region, biggest first.
+const LABELS: usize = 12;
+
+/// The attributed regions of a file, see [`Genealogy::layout`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Layout {
+    pub file_size: u64,
+    pub bands: u32,
+    /// In offset order.
+    pub regions: Vec<Region>,
+    /// The bytes of every component, biggest first.
+    pub components: Vec<(String, u64)>,
+}
+
+/// File offsets of a single component, possibly with gaps too small to be seen.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Region {
+    pub component: String,
+    pub range: Range<u64>,
+    /// Bytes attributed to the component in the range.
+    pub size: u64,
+}
+
+impl Genealogy {
+    /// Lays out the file offsets of a binary of `file_size` bytes in `bands` bands. Consecutive
+    /// ranges of the same component are merged into a region when the gap between them would be
+    /// less than a pixel wide.
+    ///
+    /// # Panics
+    ///
+    /// If `bands` is 0.
+    pub fn layout(&self, file_size: u64, bands: u32) -> Layout {
+        assert!(bands > 0, "a layout needs a band");
+        let pixel = file_size.div_ceil(u64::from(bands) * BAND_WIDTH).max(1);
+        let mut regions: Vec<Region> = vec![];
+        let mut components: HashMap<&str, u64> = HashMap::new();
+        for hit in self.iter() {
+            let component = Granularities::of(hit.filename).component;
+            let size = hit.range.end - hit.range.start;
+            *components.entry(component).or_default() += size;
+            match regions.last_mut() {
+                Some(region)
+                    if region.component == component
+                        && hit.range.start <= region.range.end + pixel =>
+                {
+                    region.range.end = region.range.end.max(hit.range.end);
+                    region.size += size;
+                }
+                _ => regions.push(Region {
+                    component: component.to_string(),
+                    range: hit.range.clone(),
+                    size,
+                }),
+            }
+        }
+
+        let mut components: Vec<(String, u64)> = components
+            .into_iter()
+            .map(|(component, size)| (component.to_string(), size))
+            .collect();
+        components.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        Layout {
+            file_size,
+            bands,
+            regions,
+            components,
+        }
+    }
+}
+
+impl Layout {
+    /// Renders the bands, a legend of the colored components and a tooltip for every region.
+    pub fn to_svg(&self) -> String {
+        let band_bytes = self.file_size.div_ceil(u64::from(self.bands)).max(1);
+        let colors: HashMap<&str, &str> = self
+            .components
+            .iter()
+            .zip(PALETTE)
+            .map(|((component, _), color)| (component.as_str(), color))
+            .collect();
+        let mut legend: Vec<(String, u64, &str)> = self
+            .components
+            .iter()
+            .zip(PALETTE)
+            .map(|((component, size), color)| (escape(component), *size, color))
+            .collect();
+        if let Some(others) = self.components.get(PALETTE.len()..) {
+            if !others.is_empty() {
+                legend.push((
+                    format!("{} other components", others.len()),
+                    others.iter().map(|(_, size)| size).sum(),
+                    OTHER,
+                ));
+            }
+        }
+        let attributed: u64 = self.components.iter().map(|(_, size)| size).sum();
+        legend.push((
+            "unattributed".to_string(),
+            self.file_size.saturating_sub(attributed),
+            UNATTRIBUTED,
+        ));
+        let legend_lines = legend.len() as u64;
+        let bands_height = u64::from(self.bands) * (BAND_HEIGHT + BAND_GAP);
+        let width = MARGIN + BAND_WIDTH + BAND_GAP;
+        let height = bands_height + BAND_GAP + legend_lines * LEGEND_LINE + BAND_GAP;
+        let band_y = |band: u64| band * (BAND_HEIGHT + BAND_GAP) + BAND_GAP;
+        let x = |offset: u64| MARGIN + offset * BAND_WIDTH / band_bytes;
+
+        let mut svg = String::new();
+        let mut line = |text: String| {
+            svg.push_str(&text);
+            svg.push('\n');
+        };
+        line(format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{width}\" height=\"{height}\" \
+             viewBox=\"0 0 {width} {height}\" font-family=\"sans-serif\" font-size=\"11\">"
+        ));
+        line(format!(
+            "<rect width=\"{width}\" height=\"{height}\" fill=\"white\"/>"
+        ));
+
+        // The bands, filled with the color of unattributed bytes up to the end of the file
+        for band in 0..u64::from(self.bands) {
+            let start = band * band_bytes;
+            if start >= self.file_size {
+                break;
+            }
+            let end = (start + band_bytes).min(self.file_size);
+            line(format!(
+                "<text x=\"{}\" y=\"{}\" text-anchor=\"end\">{}</text>",
+                MARGIN - BAND_GAP,
+                band_y(band) + BAND_HEIGHT / 2 + 4,
+                hex(start)
+            ));
+            line(format!(
+                "<rect x=\"{MARGIN}\" y=\"{}\" width=\"{}\" height=\"{BAND_HEIGHT}\" fill=\"{UNATTRIBUTED}\"/>",
+                band_y(band),
+                x(end - start) - MARGIN
+            ));
+        }
+
+        // The regions, split at the end of bands, and the widest piece of every region
+        let mut widest = Vec::with_capacity(self.regions.len());
+        for region in &self.regions {
+            let color = colors.get(region.component.as_str()).unwrap_or(&OTHER);
+            line(format!(
+                "<g fill=\"{color}\"><title>{} {}..{} {}</title>",
+                escape(&region.component),
+                hex(region.range.start),
+                hex(region.range.end),
+                human_size(region.size)
+            ));
+            let mut piece_widest = (0, 0, 0);
+            let mut start = region.range.start;
+            while start < region.range.end.min(self.file_size) {
+                let band = start / band_bytes;
+                let end = region.range.end.min((band + 1) * band_bytes);
+                let left = x(start - band * band_bytes);
+                // At least a pixel, so that small regions stay visible
+                let right = (MARGIN
+                    + ((end - band * band_bytes) * BAND_WIDTH).div_ceil(band_bytes))
+                .max(left + 1);
+                line(format!(
+                    "<rect x=\"{left}\" y=\"{}\" width=\"{}\" height=\"{BAND_HEIGHT}\"/>",
+                    band_y(band),
+                    right - left
+                ));
+                if right - left > piece_widest.2 {
+                    piece_widest = (band, left, right - left);
+                }
+                start = end;
+            }
+            line("</g>".to_string());
+            widest.push(piece_widest);
+        }
+
+        // Labels of the biggest region of the biggest components, when they fit
+        let mut biggest: Vec<usize> = (0..self.regions.len()).collect();
+        biggest.sort_by_key(|&i| std::cmp::Reverse(self.regions[i].size));
+        let mut labelled = HashSet::new();
+        biggest.retain(|&i| labelled.insert(self.regions[i].component.as_str()));
+        for &i in biggest.iter().take(LABELS) {
+            let (band, left, piece_width) = widest[i];
+            let name = file_name(&self.regions[i].component);
+            let fitting = (piece_width.saturating_sub(6) / CHAR_WIDTH) as usize;
+            let label = if name.chars().count() <= fitting {
+                name.to_string()
+            } else if fitting >= 4 {
+                let mut truncated: String = name.chars().take(fitting - 1).collect();
+                truncated.push('…');
+                truncated
+            } else {
+                continue;
+            };
+            line(format!(
+                "<text x=\"{}\" y=\"{}\" fill=\"black\">{}</text>",
+                left + 3,
+                band_y(band) + BAND_HEIGHT / 2 + 4,
+                escape(&label)
+            ));
+        }
+
+        // The legend
+        for (i, (name, size, color)) in legend.iter().enumerate() {
+            let y = bands_height + BAND_GAP + i as u64 * LEGEND_LINE;
+            line(format!(
+                "<rect x=\"{MARGIN}\" y=\"{y}\" width=\"12\" height=\"12\" fill=\"{color}\" stroke=\"#999\"/>"
+            ));
+            line(format!(
+                "<text x=\"{}\" y=\"{}\">{} {name}</text>",
+                MARGIN + 18,
+                y + 10,
+                human_size(*size)
+            ));
+        }
+        line("</svg>".to_string());
+        svg
+    }
+}
+
+/// Escapes text for XML content and attributes.
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Genealogy;
+
+    use super::escape;
+
+    #[test]
+    fn test_layout() {
+        let mapfile = std::fs::read_to_string("tests/gcc/out.map").unwrap();
+        let binary = std::fs::read("tests/gcc/a.out").unwrap();
+        let genealogy = Genealogy::new(&mapfile, &binary).unwrap();
+        let layout = genealogy.layout(binary.len() as u64, 4);
+
+        assert!(layout
+            .regions
+            .windows(2)
+            .all(|pair| pair[0].range.start <= pair[1].range.start));
+        assert_eq!(
+            layout.regions.iter().map(|region| region.size).sum::<u64>(),
+            layout.components.iter().map(|(_, size)| size).sum::<u64>()
+        );
+        assert!(layout
+            .components
+            .iter()
+            .any(|(component, _)| component == "<runtime>"));
+
+        let svg = layout.to_svg();
+        assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\""));
+        assert!(svg.trim_end().ends_with("</svg>"));
+        assert!(svg.contains(">simple.o</text>"));
+        assert!(svg.contains("&lt;runtime&gt;"));
+        assert_eq!(escape("a<b>&\"c\""), "a&lt;b&gt;&amp;&quot;c&quot;");
+    }
+}
@@ -1,34 +1,292 @@
-use std::{collections::HashMap, error::Error, fmt::Display, ops::Range};
+#![cfg_attr(not(feature = "std"), no_std)]
 
-use goblin::{elf::Elf, pe::PE, Object};
-use intervaltree::{Element, IntervalTree};
-use regex::{Captures, Regex, RegexBuilder};
+extern crate alloc;
 
-#[derive(Clone, Debug)]
+#[cfg(all(feature = "std", not(any(feature = "regex", feature = "no-regex"))))]
+compile_error!("map files are parsed with the `regex` feature or the `no-regex` feature");
+
+#[cfg(feature = "std")]
+pub mod android;
+#[cfg(feature = "std")]
+pub mod archives;
+#[cfg(feature = "bench")]
+pub mod bench;
+#[cfg(feature = "std")]
+pub mod blobs;
+#[cfg(feature = "std")]
+pub mod commits;
+#[cfg(feature = "std")]
+pub mod compile_commands;
+#[cfg(feature = "compression")]
+pub mod compression;
+#[cfg(feature = "std")]
+pub mod ctors;
+#[cfg(feature = "std")]
+pub mod deadstrip;
+#[cfg(feature = "std")]
+pub mod debuginfo;
+#[cfg(feature = "std")]
+pub mod dependencies;
+#[cfg(feature = "std")]
+pub mod diff;
+#[cfg(feature = "std")]
+pub mod disasm;
+#[cfg(feature = "std")]
+pub mod explain;
+#[cfg(feature = "std")]
+pub mod exports;
+#[cfg(feature = "std")]
+pub mod format;
+#[cfg(feature = "std")]
+pub mod got;
+#[cfg(feature = "std")]
+pub mod hierarchy;
+#[cfg(feature = "std")]
+pub mod history;
+#[cfg(feature = "std")]
+pub mod hotcold;
+#[cfg(feature = "std")]
+pub mod image;
+#[cfg(feature = "std")]
+pub mod incremental;
+pub mod index;
+mod intervals;
+#[cfg(feature = "std")]
+pub mod kernel;
+#[cfg(feature = "std")]
+pub mod language;
+#[cfg(feature = "std")]
+pub mod layout;
+#[cfg(feature = "std")]
+pub mod locality;
+#[cfg(feature = "std")]
+pub mod matrix;
+#[cfg(feature = "std")]
+pub mod memdump;
+#[cfg(feature = "std")]
+pub mod metrics;
+#[cfg(feature = "mmap")]
+pub mod mmap;
+#[cfg(feature = "std")]
+pub mod namespaces;
+#[cfg(feature = "std")]
+pub mod origin;
+#[cfg(feature = "std")]
+pub mod output;
+#[cfg(feature = "std")]
+pub mod ownership;
+#[cfg(feature = "std")]
+pub mod padding;
+#[cfg(feature = "std")]
+pub mod pages;
+#[cfg(feature = "std")]
+mod parse;
+#[cfg(feature = "std")]
+pub mod partitions;
+#[cfg(feature = "std")]
+mod pipeline;
+#[cfg(feature = "std")]
+pub mod profile;
+#[cfg(feature = "std")]
+pub mod quality;
+#[cfg(feature = "std")]
+pub mod render;
+#[cfg(feature = "std")]
+pub mod reorder;
+#[cfg(feature = "std")]
+pub mod resolution;
+#[cfg(feature = "std")]
+pub mod resources;
+#[cfg(feature = "std")]
+mod scan;
+#[cfg(feature = "std")]
+pub mod server;
+#[cfg(feature = "std")]
+pub mod snapshot;
+#[cfg(feature = "std")]
+pub mod stack;
+#[cfg(feature = "std")]
+pub mod statics;
+#[cfg(feature = "std")]
+pub mod strip;
+#[cfg(feature = "std")]
+pub mod symbols;
+pub mod table;
+#[cfg(feature = "regex")]
+pub mod triage;
+#[cfg(feature = "std")]
+pub mod uboot;
+#[cfg(feature = "std")]
+pub mod unwind;
+#[cfg(feature = "std")]
+pub mod validate;
+#[cfg(feature = "std")]
+pub mod vectors;
+#[cfg(feature = "std")]
+pub mod wasm;
+
+use alloc::string::String;
+use core::{error::Error, fmt::Display};
+#[cfg(feature = "std")]
+use std::{borrow::Cow, collections::HashMap, ops::Range, thread};
+
+#[cfg(feature = "std")]
+use goblin::{
+    elf::Elf,
+    mach::{Mach, MachO, SingleArch},
+    pe::PE,
+    Object,
+};
+#[cfg(feature = "regex")]
+use regex::Regex;
+
+pub use crate::index::{FileId, Index, IndexStats, QueryHit, Section, SubSection};
+#[cfg(feature = "std")]
+pub use crate::parse::normalize_line_endings;
+#[cfg(feature = "std")]
+use crate::{
+    format::{hex, human_size, percentage, Align, Cell, Style, Table},
+    incremental::LayoutCache,
+    origin::{resolve_sysroot, Granularities, OriginNaming, PathFilter, PathSeparators, RustUnits},
+    parse::{
+        extract_mapfile, group_sections_by_address, ld64_arch, map_msvc_sections_to_pe,
+        map_sections_to_elf, map_sections_to_macho, map_sections_to_wasm, mapfile_format,
+        MapfileFormat,
+    },
+    pipeline::{parse_in_background, Unsplittable},
+    symbols::{Symbol, SymbolIndex},
+    validate::Warning,
+};
+
+#[derive(Debug)]
 pub enum GenealogyError {
     UnsupportedBinaryFormat,
+    /// The binary looks like a supported format but goblin failed to parse it, e.g. because it
+    /// is truncated or its headers are corrupt. The goblin error is the [`Error::source`].
+    #[cfg(feature = "std")]
+    BinaryParse(goblin::error::Error),
     WrongMapfileFormat,
+    /// A symbol query was made on a [`Genealogy`] built without [`GenealogyBuilder::symbols`].
+    NoSymbolIndex,
+    /// A profiler export could not be parsed.
+    WrongProfileFormat,
+    /// A compilation database (`compile_commands.json`) could not be parsed.
+    WrongCompileCommandsFormat,
+    /// A Ninja dependency log (`.ninja_deps`) could not be parsed.
+    WrongNinjaDepsFormat,
+    /// A stack usage file (`.su`) could not be parsed.
+    WrongStackUsageFormat,
+    /// A Linux `System.map` could not be parsed.
+    WrongSystemMapFormat,
+    /// No Cortex-M vector table was found at the start of the image.
+    NoVectorTable,
+    /// A flash partition table could not be parsed.
+    WrongPartitionTableFormat,
+    /// A FIT image, such as `u-boot.itb`, could not be parsed.
+    WrongFitFormat,
+    /// An APK, an Android App Bundle or another ZIP archive could not be parsed.
+    WrongArchiveFormat,
+    /// A file of an archive is deflated, and the `compression` feature is disabled.
+    CompressedArchiveEntry(String),
+    /// A layout snapshot could not be parsed, or was written by another version of the format.
+    WrongSnapshotFormat,
+    /// A line of a size history could not be parsed, or was written by another version of the
+    /// format.
+    WrongHistoryFormat,
+    /// A universal Mach-O binary has no slice of the architecture its map was linked for.
+    NoArchSlice(String),
+    /// An [`Address`] was queried in a key space the binary has no index for, e.g. an RVA in an
+    /// ELF file.
+    NoKeySpace(&'static str),
+    /// An address, offset or size of the map does not fit in 64 bits once computed, e.g. a
+    /// subsection placed before its section.
+    AddressOutOfRange(String),
 }
 
 impl Display for GenealogyError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         match self {
             GenealogyError::UnsupportedBinaryFormat => {
                 write!(
                     f,
-                    "Binary format not supported. Only ELF and PE supported for now.",
+                    "Binary format not supported. Only ELF, PE, Mach-O and WebAssembly supported for now.",
                 )
             }
+            #[cfg(feature = "std")]
+            GenealogyError::BinaryParse(error) => {
+                write!(f, "Binary could not be parsed: {error}")
+            }
             GenealogyError::WrongMapfileFormat => {
                 write!(f, "Mapfile not conforming to the expected format")
             }
+            GenealogyError::NoSymbolIndex => {
+                write!(f, "Symbol index not built, enable it on the builder")
+            }
+            GenealogyError::WrongProfileFormat => {
+                write!(f, "Profile not conforming to the expected format")
+            }
+            GenealogyError::WrongCompileCommandsFormat => {
+                write!(
+                    f,
+                    "Compilation database not conforming to the expected format"
+                )
+            }
+            GenealogyError::WrongNinjaDepsFormat => {
+                write!(
+                    f,
+                    "Ninja dependency log not conforming to the expected format"
+                )
+            }
+            GenealogyError::WrongStackUsageFormat => {
+                write!(f, "Stack usage not conforming to the expected format")
+            }
+            GenealogyError::WrongSystemMapFormat => {
+                write!(f, "System.map not conforming to the expected format")
+            }
+            GenealogyError::NoVectorTable => {
+                write!(f, "No vector table at the start of the image")
+            }
+            GenealogyError::WrongPartitionTableFormat => {
+                write!(f, "Partition table not conforming to the expected format")
+            }
+            GenealogyError::WrongFitFormat => {
+                write!(f, "FIT image not conforming to the expected format")
+            }
+            GenealogyError::WrongArchiveFormat => {
+                write!(f, "Archive not conforming to the ZIP format")
+            }
+            GenealogyError::CompressedArchiveEntry(name) => {
+                write!(
+                    f,
+                    "{name} is compressed in the archive, enable the `compression` feature"
+                )
+            }
+            GenealogyError::WrongSnapshotFormat => {
+                write!(f, "Layout snapshot not conforming to the expected format")
+            }
+            GenealogyError::WrongHistoryFormat => {
+                write!(f, "Size history not conforming to the expected format")
+            }
+            GenealogyError::NoArchSlice(arch) => {
+                write!(f, "Universal binary without a {arch} slice")
+            }
+            GenealogyError::NoKeySpace(key_space) => {
+                write!(f, "No {key_space} index for this binary")
+            }
+            GenealogyError::AddressOutOfRange(what) => {
+                write!(f, "Address out of range: {what}")
+            }
         }
     }
 }
 
 impl Error for GenealogyError {
     fn source(&self) -> Option<&(dyn Error + 'static)> {
-        None
+        match self {
+            #[cfg(feature = "std")]
+            GenealogyError::BinaryParse(error) => Some(error),
+            _ => None,
+        }
     }
 
     fn description(&self) -> &str {
@@ -48,385 +306,1118 @@ pub struct MapfileInformation {
     pub filename: String,
 }
 
-#[derive(Debug)]
-pub struct Section {
-    pub name: String,
-    pub start_vaddr: u64,
-    pub start_file_offset: Option<u64>,
-    pub size: u64,
-    pub subsections: Vec<SubSection>,
+#[cfg(feature = "std")]
+pub struct Genealogy {
+    /// The map sections, placed in the binary.
+    sections: Vec<Section>,
+    index: Index,
+    symbols: Option<SymbolIndex>,
+    addressing: Addressing,
+    /// The [`GenealogyBuilder::vaddr_bias`] the map addresses were shifted by.
+    vaddr_bias: i64,
+    warnings: Vec<Warning>,
 }
-#[derive(Debug)]
-pub struct SubSection {
-    pub name: String,
-    pub start_vaddr: u64,
-    pub start_file_offset: Option<u64>,
-    pub size: u64,
-    pub filename: String,
+
+/// The key spaces of a [`Genealogy`] besides file offsets and virtual addresses, which depend on
+/// the format of the binary.
+#[cfg(feature = "std")]
+enum Addressing {
+    /// The load address ranges of the `PT_LOAD` segments, with the virtual address each starts
+    /// at. Empty for ELF files without program headers.
+    Elf { segments: Vec<(Range<u64>, u64)> },
+    /// The preferred load address, which RVAs are relative to.
+    Pe { image_base: u64 },
+    /// Mach-O images have neither load addresses distinct from their virtual ones nor RVAs.
+    MachO,
+    /// Function bodies are addressed by their offset in the code section, data by its address in
+    /// linear memory.
+    Wasm,
 }
 
-pub struct Genealogy {
-    intervals: IntervalTree<u64, String>,
+/// An address in one of the key spaces a [`Genealogy`] can be queried in, see
+/// [`Genealogy::query_address`].
+#[cfg(feature = "std")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Address {
+    FileOffset(u64),
+    /// Virtual memory address, where the byte is at runtime.
+    Vma(u64),
+    /// Load memory address, where the byte is stored before the startup code copies it, e.g.
+    /// the flash copy of `.data`. Only for ELF files with program headers.
+    Lma(u64),
+    /// Relative virtual address, from the image base. Only for PE files.
+    Rva(u64),
 }
 
-impl Genealogy {
-    pub fn new(mapfile: &str, binary: &[u8]) -> Result<Self, GenealogyError> {
-        let mut sections = extract_mapfile(mapfile)?;
+#[cfg(feature = "std")]
+impl Address {
+    pub fn value(self) -> u64 {
+        match self {
+            Address::FileOffset(value)
+            | Address::Vma(value)
+            | Address::Lma(value)
+            | Address::Rva(value) => value,
+        }
+    }
+
+    /// The name of the key space, as in [`GenealogyError::NoKeySpace`].
+    pub fn key_space(self) -> &'static str {
+        match self {
+            Address::FileOffset(_) => "file offset",
+            Address::Vma(_) => "VMA",
+            Address::Lma(_) => "LMA",
+            Address::Rva(_) => "RVA",
+        }
+    }
+}
+
+/// Configures how a [`Genealogy`] is built, see [`Genealogy::builder`].
+#[cfg(feature = "std")]
+pub struct GenealogyBuilder<'a> {
+    /// With its line endings normalized, see [`normalize_line_endings`].
+    mapfile: Cow<'a, str>,
+    binary: Binary<'a>,
+    symbols: bool,
+    path_separators: PathSeparators,
+    sysroot: Option<&'a str>,
+    origin_naming: OriginNaming,
+    rust_units: RustUnits,
+    vaddr_bias: i64,
+    cache: Option<&'a mut LayoutCache>,
+    threads: usize,
+    memory_limit: usize,
+}
+
+/// The binary a [`Genealogy`] is built against, parsed by the builder or by the caller.
+#[cfg(feature = "std")]
+enum Binary<'a> {
+    Bytes(&'a [u8]),
+    Elf(&'a Elf<'a>),
+    Pe { pe: &'a PE<'a>, file_size: u64 },
+}
+
+#[cfg(feature = "std")]
+impl<'a> GenealogyBuilder<'a> {
+    /// Also index the symbols of the binary, needed for lookups by symbol name.
+    pub fn symbols(mut self, enabled: bool) -> Self {
+        self.symbols = enabled;
+        self
+    }
+
+    /// Added to the addresses of the map when mapping it onto an ELF file, for maps produced
+    /// against another base than the ELF headers (e.g. rebased console images). Sections whose
+    /// address still differs from the headers are reported by [`Genealogy::warnings`].
+    pub fn vaddr_bias(mut self, bias: i64) -> Self {
+        self.vaddr_bias = bias;
+        self
+    }
+
+    /// How path separators in origins are normalized, kept as is by default.
+    pub fn path_separators(mut self, separators: PathSeparators) -> Self {
+        self.path_separators = separators;
+        self
+    }
+
+    /// The sysroot GNU ld resolved the `=` and `$SYSROOT` prefixes of origins against
+    /// (`--sysroot`), e.g. `=/usr/lib/crt1.o`. Without one the prefix is dropped, see
+    /// [`resolve_sysroot`].
+    pub fn sysroot(mut self, sysroot: &'a str) -> Self {
+        self.sysroot = Some(sysroot);
+        self
+    }
+
+    /// How origins are named, full paths by default. Applied after
+    /// [`GenealogyBuilder::path_separators`].
+    pub fn origin_naming(mut self, naming: OriginNaming) -> Self {
+        self.origin_naming = naming;
+        self
+    }
+
+    /// How the codegen units of Rust crates are named, kept by default. Applied before
+    /// [`GenealogyBuilder::origin_naming`].
+    pub fn rust_units(mut self, units: RustUnits) -> Self {
+        self.rust_units = units;
+        self
+    }
+
+    /// Reuses the sections parsed by a previous build with the same `cache` when their text did
+    /// not change, and keeps the sections of this build in it for the next one. For large maps
+    /// rebuilt after small changes, see [`LayoutCache`].
+    pub fn cache(mut self, cache: &'a mut LayoutCache) -> Self {
+        self.cache = Some(cache);
+        self
+    }
 
-        match Object::parse(binary).map_err(|_| GenealogyError::UnsupportedBinaryFormat)? {
-            Object::Elf(elf) => {
-                map_sections_to_elf(&mut sections, &elf);
+    /// Parses the output sections of the map on `threads` worker threads, while the calling
+    /// thread parses the binary. 1 by default, which parses everything on the calling thread.
+    /// Ignored when building with a [`GenealogyBuilder::cache`].
+    pub fn threads(mut self, threads: usize) -> Self {
+        self.threads = threads;
+        self
+    }
+
+    /// Bounds the map text the workers of [`GenealogyBuilder::threads`] parse ahead of the
+    /// calling thread, in bytes, unbounded by default. Output sections bigger than the limit are
+    /// parsed one at a time.
+    pub fn memory_limit(mut self, bytes: usize) -> Self {
+        self.memory_limit = bytes;
+        self
+    }
+
+    pub fn build(mut self) -> Result<Genealogy, GenealogyError> {
+        let ((mut sections, mut warnings), object) = match self.cache.take() {
+            Some(cache) => (cache.extract_mapfile(&self.mapfile)?, self.parse_binary()),
+            None if self.threads > 1 => self.extract_pipelined()?,
+            None => (extract_mapfile(&self.mapfile)?, self.parse_binary()),
+        };
+
+        let (symbols, addressing) = match self.binary {
+            Binary::Bytes(binary) => {
+                let object = object.expect("bytes are parsed");
+                match object.map_err(GenealogyError::BinaryParse)? {
+                    Object::Elf(elf) => self.place_in_elf(&mut sections, &elf, &mut warnings)?,
+                    Object::PE(pe) => self.place_in_pe(
+                        &mut sections,
+                        &pe,
+                        binary.len() as u64,
+                        Some(binary),
+                        &mut warnings,
+                    )?,
+                    Object::Mach(Mach::Binary(macho)) => {
+                        warnings.extend(map_sections_to_macho(&mut sections, &macho, 0)?);
+                        (None, Addressing::MachO)
+                    }
+                    Object::Mach(Mach::Fat(fat)) => {
+                        let (macho, offset) = self.macho_slice(&fat)?;
+                        warnings.extend(map_sections_to_macho(&mut sections, &macho, offset)?);
+                        (None, Addressing::MachO)
+                    }
+                    Object::Unknown(_) if wasm::is_wasm(binary) => {
+                        warnings.extend(map_sections_to_wasm(&mut sections, binary)?);
+                        (None, Addressing::Wasm)
+                    }
+                    _ => {
+                        return Err(GenealogyError::UnsupportedBinaryFormat);
+                    }
+                }
+            }
+            Binary::Elf(elf) => self.place_in_elf(&mut sections, elf, &mut warnings)?,
+            Binary::Pe { pe, file_size } => {
+                self.place_in_pe(&mut sections, pe, file_size, None, &mut warnings)?
+            }
+        };
+
+        warnings.extend(validate::remove_invalid_subsections(&mut sections));
+
+        for filename in sections
+            .iter_mut()
+            .flat_map(|s| s.subsections.iter_mut())
+            .flat_map(SubSection::filenames_mut)
+        {
+            let resolved = resolve_sysroot(filename, self.sysroot);
+            if resolved != filename.as_str() {
+                *filename = resolved.into_owned();
             }
-            Object::PE(pe) => map_msvc_sections_to_pe(&mut sections, &pe),
-            _ => {
-                return Err(GenealogyError::UnsupportedBinaryFormat);
+        }
+
+        if self.path_separators != PathSeparators::Keep {
+            for filename in sections
+                .iter_mut()
+                .flat_map(|s| s.subsections.iter_mut())
+                .flat_map(SubSection::filenames_mut)
+            {
+                if let Cow::Owned(normalized) = self.path_separators.normalize(filename) {
+                    *filename = normalized;
+                }
+            }
+        }
+
+        if self.rust_units != RustUnits::Keep {
+            for subsection in sections.iter_mut().flat_map(|s| s.subsections.iter_mut()) {
+                if let Some(unit) = self
+                    .rust_units
+                    .rename(&subsection.filename, &subsection.name)
+                {
+                    subsection.filename = unit;
+                }
+                // Folded sections have the name of the one kept
+                for filename in &mut subsection.folded_with {
+                    if let Some(unit) = self.rust_units.rename(filename, &subsection.name) {
+                        *filename = unit;
+                    }
+                }
             }
         }
 
-        // Build interval tree
-        let intervals: IntervalTree<u64, String> = IntervalTree::from_iter(
-            sections
+        if self.origin_naming != OriginNaming::FullPath {
+            let names: HashMap<String, String> = self
+                .origin_naming
+                .names(
+                    sections
+                        .iter()
+                        .flat_map(|s| s.subsections.iter())
+                        .flat_map(SubSection::filenames)
+                        .map(String::as_str),
+                )
                 .into_iter()
-                .flat_map(|s| s.subsections.into_iter())
-                .filter_map(|sub_section| {
-                    sub_section.start_file_offset.map(|file_offset| {
-                        (
-                            file_offset..file_offset + sub_section.size,
-                            sub_section.filename,
-                        )
-                    })
-                }),
-        );
+                .map(|(origin, name)| (origin.to_string(), name))
+                .collect();
+            for filename in sections
+                .iter_mut()
+                .flat_map(|s| s.subsections.iter_mut())
+                .flat_map(SubSection::filenames_mut)
+            {
+                if let Some(name) = names.get(filename) {
+                    *filename = name.clone();
+                }
+            }
+        }
 
-        Ok(Self { intervals })
+        Ok(Genealogy {
+            index: Index::new(&sections)?,
+            sections,
+            symbols,
+            addressing,
+            vaddr_bias: self.vaddr_bias,
+            warnings,
+        })
     }
 
-    pub fn query(&self, range: Range<u64>) -> impl Iterator<Item = &Element<u64, String>> {
-        self.intervals.query(range)
+    /// The parsed binary, when given as bytes.
+    fn parse_binary(&self) -> Option<goblin::error::Result<Object<'a>>> {
+        match self.binary {
+            Binary::Bytes(binary) => Some(Object::parse(binary)),
+            Binary::Elf(_) | Binary::Pe { .. } => None,
+        }
     }
 
-    pub fn query_point(&self, point: u64) -> impl Iterator<Item = &Element<u64, String>> {
-        self.intervals.query_point(point)
+    /// Like [`extract_mapfile`] followed by [`GenealogyBuilder::parse_binary`], overlapping
+    /// both, see [`GenealogyBuilder::threads`].
+    #[allow(clippy::type_complexity)]
+    fn extract_pipelined(
+        &self,
+    ) -> Result<
+        (
+            (Vec<Section>, Vec<Warning>),
+            Option<goblin::error::Result<Object<'a>>>,
+        ),
+        GenealogyError,
+    > {
+        thread::scope(|scope| {
+            let mut stream =
+                parse_in_background(scope, &self.mapfile, self.threads, self.memory_limit);
+            let object = self.parse_binary();
+            let mut sections = vec![];
+            loop {
+                match stream.next_sections() {
+                    Ok(Some(chunk)) => sections.extend(chunk),
+                    Ok(None) => break,
+                    Err(Unsplittable) => {
+                        drop(stream);
+                        return Ok((extract_mapfile(&self.mapfile)?, object));
+                    }
+                }
+            }
+            Ok(((sections, vec![]), object))
+        })
     }
-}
 
-fn extract_mapfile(mapfile: &str) -> Result<Vec<Section>, GenealogyError> {
-    let header_regex = Regex::new(
-        r"VMA(?:\s+)LMA(?:\s+)Size(?:\s+)Align(?:\s+)Out(?<out_in_space>\s+)In(?:\s+)Symbol",
-    )
-    .expect("I know how to write regexes");
+    /// Places the sections in the ELF file, returning the symbol index and load segments.
+    fn place_in_elf(
+        &self,
+        sections: &mut Vec<Section>,
+        elf: &Elf,
+        warnings: &mut Vec<Warning>,
+    ) -> Result<(Option<SymbolIndex>, Addressing), GenealogyError> {
+        if matches!(
+            mapfile_format(&self.mapfile),
+            MapfileFormat::Iar | MapfileFormat::Segger
+        ) {
+            group_sections_by_address(sections, elf, self.vaddr_bias);
+        }
+        warnings.extend(map_sections_to_elf(sections, elf, self.vaddr_bias)?);
+        let segments = elf
+            .program_headers
+            .iter()
+            .filter(|phdr| phdr.p_type == goblin::elf::program_header::PT_LOAD)
+            .filter_map(|phdr| {
+                let end = phdr.p_paddr.checked_add(phdr.p_memsz)?;
+                (phdr.p_memsz > 0).then_some((phdr.p_paddr..end, phdr.p_vaddr))
+            })
+            .collect();
+        Ok((
+            self.symbols.then(|| SymbolIndex::from_elf(elf)),
+            Addressing::Elf { segments },
+        ))
+    }
 
-    if let Some(header_match) = header_regex.captures(mapfile) {
-        Ok(extract_llvm_mapfile(
-            mapfile,
-            header_match["out_in_space"].len(),
+    /// The slice of the universal binary `fat` for the architecture of the ld64 map, with its
+    /// offset in the binary. The only slice when the map names no architecture.
+    fn macho_slice<'b>(
+        &self,
+        fat: &goblin::mach::MultiArch<'b>,
+    ) -> Result<(MachO<'b>, u64), GenealogyError> {
+        let arches = fat.arches().map_err(GenealogyError::BinaryParse)?;
+        let arch = ld64_arch(&self.mapfile);
+        let index = match arch {
+            Some(arch) => arches.iter().position(|fat_arch| {
+                goblin::mach::constants::cputype::get_arch_name_from_types(
+                    fat_arch.cputype,
+                    fat_arch.cpusubtype,
+                ) == Some(arch)
+            }),
+            None => (arches.len() == 1).then_some(0),
+        }
+        .ok_or_else(|| GenealogyError::NoArchSlice(arch.unwrap_or("single").into()))?;
+        match fat.get(index).map_err(GenealogyError::BinaryParse)? {
+            SingleArch::MachO(macho) => Ok((macho, u64::from(arches[index].offset))),
+            SingleArch::Archive(_) => Err(GenealogyError::UnsupportedBinaryFormat),
+        }
+    }
+
+    /// Places the sections in the PE file, returning the symbol index and image base.
+    fn place_in_pe(
+        &self,
+        sections: &mut [Section],
+        pe: &PE,
+        file_size: u64,
+        binary: Option<&[u8]>,
+        warnings: &mut Vec<Warning>,
+    ) -> Result<(Option<SymbolIndex>, Addressing), GenealogyError> {
+        warnings.extend(map_msvc_sections_to_pe(sections, pe, file_size)?);
+        if let Some(binary) = binary {
+            let resources = resources::pe_resources(pe, binary);
+            resources::attribute_resources(sections, &resources, pe.image_base as u64);
+        }
+        let symbols = self
+            .symbols
+            .then(|| SymbolIndex::from_msvc_mapfile(&self.mapfile, pe))
+            .transpose()?;
+        Ok((
+            symbols,
+            Addressing::Pe {
+                image_base: pe.image_base as u64,
+            },
         ))
-    } else if mapfile.contains("Preferred load address is ") {
-        extract_msvc_mapfile(mapfile)
-    } else {
-        Ok(extract_gnu_mapfile(mapfile))
     }
 }
 
-fn extract_gnu_mapfile(mapfile: &str) -> Vec<Section> {
-    let regex_subsections = RegexBuilder::new(r"^ (?P<name>\.[^\s]+)\s+0x(?P<vrom>[0-9a-fA-F]+)[[:blank:]]+0x(?P<size>[0-9a-fA-F]+)[[:blank:]]+(?P<file>[^\r\n]+)")
-    .multi_line(true)
-    .build()
-    .unwrap();
-
-    let regex_sections = RegexBuilder::new(
-        r"^(?P<name>\.[^\s]+)\s+0x(?P<vrom>[0-9a-fA-F]+)[[:blank:]]+0x(?P<size>[0-9a-fA-F]+)",
-    )
-    .multi_line(true)
-    .build()
-    .unwrap();
-
-    // Extract all sections, don't fill subsections in yet
-    let (section_offsets, mut sections): (Vec<usize>, Vec<Section>) = regex_sections
-        .captures_iter(mapfile)
-        .map(|c| {
-            (
-                c.get(0).unwrap().start(),
-                Section {
-                    name: c["name"].into(),
-                    start_vaddr: u64::from_str_radix(&c["vrom"], 16).unwrap(),
-                    size: u64::from_str_radix(&c["size"], 16).unwrap(),
-                    subsections: vec![],
-                    start_file_offset: None,
-                },
-            )
-        })
-        .unzip();
-
-    // Assign each subsection to the closest section
-    regex_subsections.captures_iter(mapfile).for_each(|c| {
-        let subsection = SubSection {
-            name: c["name"].to_string(),
-            start_vaddr: u64::from_str_radix(&c["vrom"], 16).unwrap(),
-            size: u64::from_str_radix(&c["size"], 16).unwrap(),
-            filename: c["file"].to_string(),
-            start_file_offset: None,
-        };
-        let ss_offset = c.get(0).unwrap().start();
-        // Find closest section
-        let section_index = section_offsets
-            .iter()
-            .enumerate()
-            .find_map(|(i, &s_offset)| Some(i).filter(|_| s_offset > ss_offset))
-            .unwrap_or(sections.len());
-        if section_index > 0 {
-            sections[section_index - 1].subsections.push(subsection);
+#[cfg(feature = "std")]
+impl Genealogy {
+    pub fn new(mapfile: &str, binary: &[u8]) -> Result<Self, GenealogyError> {
+        Self::builder(mapfile, binary).build()
+    }
+
+    pub fn builder<'a>(mapfile: &'a str, binary: &'a [u8]) -> GenealogyBuilder<'a> {
+        Self::builder_for(mapfile, Binary::Bytes(binary))
+    }
+
+    /// Like [`Genealogy::new`], for an ELF file the caller already parsed with goblin.
+    pub fn with_elf(mapfile: &str, elf: &Elf) -> Result<Self, GenealogyError> {
+        Self::elf_builder(mapfile, elf).build()
+    }
+
+    /// Like [`Genealogy::builder`], for an ELF file the caller already parsed with goblin.
+    pub fn elf_builder<'a>(mapfile: &'a str, elf: &'a Elf<'a>) -> GenealogyBuilder<'a> {
+        Self::builder_for(mapfile, Binary::Elf(elf))
+    }
+
+    /// Like [`Genealogy::new`], for a PE file the caller already parsed with goblin.
+    /// `file_size` is the size of the file, the raw data of sections is checked against it.
+    /// Resources are not attributed, their directory is read from the bytes of the file, see
+    /// [`resources`].
+    pub fn with_pe(mapfile: &str, pe: &PE, file_size: u64) -> Result<Self, GenealogyError> {
+        Self::pe_builder(mapfile, pe, file_size).build()
+    }
+
+    /// Like [`Genealogy::builder`], for a PE file the caller already parsed with goblin, see
+    /// [`Genealogy::with_pe`].
+    pub fn pe_builder<'a>(
+        mapfile: &'a str,
+        pe: &'a PE<'a>,
+        file_size: u64,
+    ) -> GenealogyBuilder<'a> {
+        Self::builder_for(mapfile, Binary::Pe { pe, file_size })
+    }
+
+    fn builder_for<'a>(mapfile: &'a str, binary: Binary<'a>) -> GenealogyBuilder<'a> {
+        GenealogyBuilder {
+            mapfile: normalize_line_endings(mapfile),
+            binary,
+            symbols: false,
+            path_separators: PathSeparators::Keep,
+            sysroot: None,
+            origin_naming: OriginNaming::FullPath,
+            rust_units: RustUnits::Keep,
+            vaddr_bias: 0,
+            cache: None,
+            threads: 1,
+            memory_limit: usize::MAX,
         }
-    });
+    }
 
-    sections
-}
+    /// The preferred load address of PE images, which RVAs are relative to. 0 for ELF, Mach-O
+    /// and WebAssembly.
+    pub fn image_base(&self) -> u64 {
+        match self.addressing {
+            Addressing::Elf { .. } | Addressing::MachO | Addressing::Wasm => 0,
+            Addressing::Pe { image_base } => image_base,
+        }
+    }
 
-fn extract_llvm_mapfile(mapfile: &str, out_in_len: usize) -> Vec<Section> {
-    enum EntryType {
-        Section(Section),
-        SubSection(SubSection),
-    }
-    fn capture_to_entry_type(m: Captures<'_>, out_in_space: usize) -> Option<EntryType> {
-        let start_vaddr = u64::from_str_radix(&m["vma"], 16).unwrap();
-        let size = u64::from_str_radix(&m["size"], 16).unwrap();
-        if m["spaces"].len() == 1 {
-            // Section header
-            Some(EntryType::Section(Section {
-                name: m["name"].to_string(),
-                start_vaddr,
-                start_file_offset: None,
-                size,
-                subsections: vec![],
-            }))
-        } else if m["spaces"].len() == 1 + 3 + out_in_space {
-            // A subsection
-            let (filename, mut name) = m["name"][..m["name"].len() - 1].split_once(":(").unwrap();
-            // Remove a potential +0xXXX substring for the subsection name, where XXX are hex digits
-            if let Some(plus_pos) = name.rfind("+0x") {
-                if name[plus_pos + 3..]
-                    .chars()
-                    .all(|c| matches!(c, '0'..='9' | 'a'..='f' | 'A'..='F'))
-                {
-                    // If it is indeed a +0xXXX suffix indicating the offset which we ignore
-                    name = &name[..plus_pos];
-                }
+    /// The sections of the map file, with their file offsets and virtual addresses resolved
+    /// against the binary.
+    pub fn sections(&self) -> &[Section] {
+        &self.sections
+    }
+
+    /// The index answering the queries, which can be used without `std`.
+    pub fn index(&self) -> &Index {
+        &self.index
+    }
+
+    /// See [`Index::file_id`].
+    pub fn file_id(&self, filename: &str) -> Option<FileId> {
+        self.index.file_id(filename)
+    }
+
+    /// See [`Index::file_name`].
+    pub fn file_name(&self, id: FileId) -> &str {
+        self.index.file_name(id)
+    }
+
+    /// Inconsistencies of the map found while building, whose entries were left out.
+    pub fn warnings(&self) -> &[Warning] {
+        &self.warnings
+    }
+
+    /// The symbol index, if it was enabled with [`GenealogyBuilder::symbols`].
+    pub fn symbols(&self) -> Option<&SymbolIndex> {
+        self.symbols.as_ref()
+    }
+
+    /// Finds symbols by (mangled or demangled) name, along with the file they originate from.
+    pub fn lookup_symbol(&self, name: &str) -> Result<Vec<SymbolHit<'_>>, GenealogyError> {
+        let symbols = self.symbols().ok_or(GenealogyError::NoSymbolIndex)?;
+        Ok(symbols
+            .lookup(name)
+            .map(|symbol| self.symbol_hit(symbol))
+            .collect())
+    }
+
+    /// Finds the symbols whose mangled or demangled name matches `pattern`, ordered by address.
+    /// Aliases are found once, as their canonical symbol, see [`SymbolIndex::canonical`].
+    #[cfg(feature = "regex")]
+    pub fn find_symbols(&self, pattern: &Regex) -> Result<Vec<SymbolHit<'_>>, GenealogyError> {
+        let symbols = self.symbols().ok_or(GenealogyError::NoSymbolIndex)?;
+        let mut hits: Vec<SymbolHit> = vec![];
+        for symbol in symbols.iter().filter(|symbol| {
+            pattern.is_match(&symbol.name)
+                || symbol
+                    .demangled
+                    .as_deref()
+                    .is_some_and(|demangled| pattern.is_match(demangled))
+        }) {
+            let canonical = symbols.canonical(symbol);
+            if hits
+                .last()
+                .is_none_or(|hit| !std::ptr::eq(hit.symbol, canonical))
+            {
+                hits.push(self.symbol_hit(canonical));
             }
-            Some(EntryType::SubSection(SubSection {
-                name: name.to_string(),
-                start_vaddr,
-                start_file_offset: None,
-                size,
-                filename: filename.to_string(),
-            }))
-        } else {
-            // A symbol, ignore for now
-            None
         }
+        Ok(hits)
     }
 
-    let line_regex = Regex::new(
-        r"^(?:\s)*(?<vma>[0-9a-fA-F]+)(?:\s)*(?<lma>[0-9a-fA-F]+)(?:\s)*(?<size>[0-9a-fA-F]+)(?:\s)*(?<align>[0-9]+)(?<spaces>\s+)(?<name>.+)$",
-    ).unwrap();
+    pub(crate) fn symbol_hit<'a>(&'a self, symbol: &'a Symbol) -> SymbolHit<'a> {
+        SymbolHit {
+            symbol,
+            filename: symbol.file_offset.and_then(|offset| {
+                self.index
+                    .query_point(offset)
+                    .next()
+                    .map(|hit| hit.filename)
+            }),
+        }
+    }
 
-    let mut lines = mapfile.lines();
-    lines.next(); // skip header, handled by regex
+    /// The subsections overlapping the file offsets in `range`, see [`Index::query`].
+    pub fn query(&self, range: Range<u64>) -> impl Iterator<Item = QueryHit<'_>> {
+        self.index.query(range)
+    }
 
-    let mut res = vec![];
+    /// The subsections containing the file offset `point`.
+    pub fn query_point(&self, point: u64) -> impl Iterator<Item = QueryHit<'_>> {
+        self.index.query_point(point)
+    }
 
-    let Some(next_line) = lines.next() else {
-        return res;
-    };
-    let Some(regex_capture) = line_regex.captures(next_line) else {
-        return res;
-    };
-    let Some(EntryType::Section(mut cur_section)) =
-        capture_to_entry_type(regex_capture, out_in_len)
-    else {
-        return res;
-    };
+    /// Like [`Genealogy::query`], with virtual addresses instead of file offsets.
+    pub fn query_vaddr(&self, range: Range<u64>) -> impl Iterator<Item = QueryHit<'_>> {
+        self.index.query_vaddr(range)
+    }
+
+    /// Like [`Genealogy::query_point`], with a virtual address instead of a file offset.
+    pub fn query_vaddr_point(&self, vaddr: u64) -> impl Iterator<Item = QueryHit<'_>> {
+        self.index.query_vaddr_point(vaddr)
+    }
 
-    for line in lines {
-        let Some(capture) = line_regex.captures(line) else {
-            continue;
+    /// The subsections containing `address`, in whichever key space it is given. Load and
+    /// relative addresses are turned into virtual addresses, fails with
+    /// [`GenealogyError::NoKeySpace`] when the binary has no such addresses.
+    pub fn query_address(&self, address: Address) -> Result<Vec<QueryHit<'_>>, GenealogyError> {
+        let vaddr = match (address, &self.addressing) {
+            (Address::FileOffset(offset), _) => return Ok(self.query_point(offset).collect()),
+            (Address::Vma(vaddr), _) => Some(vaddr),
+            (Address::Lma(lma), Addressing::Elf { segments }) if !segments.is_empty() => segments
+                .iter()
+                .find(|(range, _)| range.contains(&lma))
+                .and_then(|(range, vaddr)| vaddr.checked_add(lma - range.start)),
+            (Address::Rva(rva), Addressing::Pe { image_base }) => image_base.checked_add(rva),
+            _ => return Err(GenealogyError::NoKeySpace(address.key_space())),
         };
-        match capture_to_entry_type(capture, out_in_len) {
-            Some(EntryType::Section(section)) => {
-                res.push(cur_section);
-                cur_section = section;
-            }
-            Some(EntryType::SubSection(subsection)) => {
-                cur_section.subsections.push(subsection);
-            }
-            None => {
-                continue;
-            }
-        }
+        Ok(vaddr
+            .map(|vaddr| self.query_vaddr_point(vaddr).collect())
+            .unwrap_or_default())
     }
 
-    res.push(cur_section);
+    /// The ranges of the files matching `filter` that overlap the file offsets in `window`,
+    /// clipped to it and ordered by start offset. Focused views and partial exports use it
+    /// instead of filtering [`Genealogy::iter`].
+    pub fn contributions(
+        &self,
+        filter: &PathFilter,
+        window: Range<u64>,
+    ) -> impl Iterator<Item = QueryHit<'_>> {
+        let mut hits: Vec<QueryHit> = self
+            .query(window.clone())
+            .filter(|hit| filter.matches(hit.filename))
+            .map(|mut hit| {
+                hit.range = hit.range.start.max(window.start)..hit.range.end.min(window.end);
+                hit
+            })
+            .collect();
+        hits.sort_by(|a, b| {
+            a.range
+                .start
+                .cmp(&b.range.start)
+                .then_with(|| a.filename.cmp(b.filename))
+        });
+        hits.into_iter()
+    }
+
+    /// Iterates over every attributed range, ordered by start offset.
+    pub fn iter(&self) -> impl Iterator<Item = QueryHit<'_>> {
+        self.index.iter()
+    }
 
-    res
+    /// Sums the attributed bytes per originating file, biggest contributors first.
+    pub fn report(&self) -> Report {
+        Report::from_sizes(
+            self.iter()
+                .map(|hit| (hit.filename, hit.range.end - hit.range.start)),
+        )
+    }
 }
 
-fn extract_msvc_mapfile(mapfile: &str) -> Result<Vec<Section>, GenealogyError> {
-    // We don't have the same information for msvc mapfiles as we havec for other kinds
-    // However, msvc mapfiles will (should ?) only ever be associated with PE binaries.
-    // The PE reader provides the size and file pointer for each section, while the mapfile
-    // provides information about "subsections" relative to the start of the section.
-    // Thus, we will not really use the names of the (sub-)sections, and instead refer to them by
-    // index, and the virtual addresses are not absolute (too cumbersome) but relative to the
-    // vaddr of the section start.
-    // Furtermore, because msvc mapfiles do not really contain the same information as other
-    // types of mapfiles (subsection with origin), we will have to "cheat" a little bit and instead
-    // try to find subsection boundaries with origins by looking at the static symbol offsets and supposing
-    // that in a contiguous section of symbols from the same origin, everything in between has also the same origin
-    let line_regex =
-        Regex::new(r"^ (?<section>[0-9a-zA-Z]{4}):(?<section_offset>[0-9a-zA-Z]{8})\s+(?<name>[^ ]+)\s+(?<vaddr>[0-9a-zA-Z]{16})(?: \w)?\s+(?<origin>.+)$").unwrap();
-
-    // Find the offset of the static symbols section
-    let offset = mapfile
-        .find(" Static symbols")
-        .ok_or(GenealogyError::WrongMapfileFormat)?;
-
-    let mut lines = mapfile[offset..].lines();
-    lines.next(); // skip " Static symbols" line
-    lines.next(); // skip the following newline
-
-    // Let's go
-    let mut res = vec![];
-    let mut current_filename = None;
-    let mut current_start_offset = 0;
-    let mut current_section_nb = 0;
-
-    let mut prev_section_offset = 0;
-    for line in lines {
-        let Some(capture) = line_regex.captures(line) else {
-            break;
-        };
-        let section_nb = u64::from_str_radix(&capture["section"], 16)
-            .map_err(|_| GenealogyError::WrongMapfileFormat)?;
-        let section_offset = u64::from_str_radix(&capture["section_offset"], 16)
-            .map_err(|_| GenealogyError::WrongMapfileFormat)?;
-        // let vaddr = u64::from_str_radix(&capture["vaddr"], 16)
-        //    .map_err(|_| GenealogyError::WrongMapfileFormat)?;
-        while res.len() <= section_nb as usize {
-            res.push(Section {
-                name: "".into(),
-                start_vaddr: 0,
-                start_file_offset: None,
-                size: 0,
-                subsections: vec![],
-            });
+#[cfg(feature = "std")]
+impl Report {
+    /// Sums `(filename, size)` pairs into a report.
+    pub(crate) fn from_sizes<'a>(contributions: impl IntoIterator<Item = (&'a str, u64)>) -> Self {
+        let mut sizes: HashMap<&str, u64> = HashMap::new();
+        for (filename, size) in contributions {
+            *sizes.entry(filename).or_default() += size;
         }
-        let filename = capture["origin"]
-            .split(':')
-            .next()
-            .expect("at least one element in split iterator")
-            .to_string();
-        if current_filename.is_none() {
-            current_filename = Some(filename);
-            current_start_offset = section_offset;
-        } else if let Some(current_filename_value) = &current_filename {
-            // Change current values and push subsection if needed
-            if current_filename_value != &filename || current_section_nb != section_nb {
-                res[current_section_nb as usize]
-                    .subsections
-                    .push(SubSection {
-                        name: String::new(),
-                        start_vaddr: current_start_offset, // /!\ not actually the vaddr but it's easier to do so
-                        start_file_offset: None,
-                        size: prev_section_offset - current_start_offset + 1, // an underestimation but what can we do ?
-                        filename: current_filename_value.clone(),
-                    });
-                current_filename = Some(filename);
-                current_start_offset = section_offset;
-                current_section_nb = section_nb;
-            }
+
+        let mut entries: Vec<ReportEntry> = sizes
+            .into_iter()
+            .map(|(filename, size)| ReportEntry {
+                filename: filename.to_string(),
+                size,
+                source: None,
+            })
+            .collect();
+        entries.sort_by(|a, b| {
+            b.size
+                .cmp(&a.size)
+                .then_with(|| a.filename.cmp(&b.filename))
+        });
+
+        Report {
+            total: entries.iter().map(|e| e.size).sum(),
+            entries,
         }
+    }
+}
 
-        prev_section_offset = section_offset;
+#[cfg(feature = "std")]
+impl<'a> QueryHit<'a> {
+    /// The origin of the hit at every granularity, see [`Granularities::of`].
+    pub fn granularities(&self) -> Granularities<'a> {
+        Granularities::of(self.filename)
     }
+}
 
-    // The last one
-    if let Some(filename) = current_filename {
-        res[current_section_nb as usize]
-            .subsections
-            .push(SubSection {
-                name: String::new(),
-                start_vaddr: current_start_offset, // /!\ not actually the vaddr but it's easier to do so
-                start_file_offset: None,
-                size: prev_section_offset - current_start_offset + 1, // an underestimation but what can we do ?
-                filename,
-            });
+#[cfg(feature = "std")]
+impl Display for QueryHit<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}..{} {:>10} {}",
+            hex(self.range.start),
+            hex(self.range.end),
+            human_size(self.range.end - self.range.start),
+            self.filename
+        )?;
+        if !self.folded_with.is_empty() {
+            write!(f, " (folded with {})", self.folded_with.join(", "))?;
+        }
+        Ok(())
     }
+}
 
-    Ok(res)
+/// A symbol and the file it originates from, if the map attributes its address.
+#[cfg(feature = "std")]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SymbolHit<'a> {
+    pub symbol: &'a Symbol,
+    pub filename: Option<&'a str>,
 }
 
-fn map_msvc_sections_to_pe(sections: &mut [Section], pe: &PE) {
-    for (section_nb, section) in sections.iter_mut().enumerate() {
-        if section_nb == 0 {
-            continue;
-        }
-        let Some(pe_section) = pe.sections.get(section_nb - 1) else {
-            // No info about this section :(
-            continue;
+/// Attributed size per originating file, see [`Genealogy::report`].
+#[cfg(feature = "std")]
+#[derive(Clone, Debug)]
+pub struct Report {
+    pub entries: Vec<ReportEntry>,
+    pub total: u64,
+}
+
+#[cfg(feature = "std")]
+#[derive(Clone, Debug)]
+pub struct ReportEntry {
+    pub filename: String,
+    pub size: u64,
+    /// The source file compiled into the file, see [`Report::annotate_sources`].
+    pub source: Option<String>,
+}
+
+#[cfg(feature = "std")]
+impl Report {
+    /// The table rendered by `Display`, with a source column when some entry has a source,
+    /// exposed so callers can enable color on it.
+    pub fn to_table(&self) -> Table {
+        let sources = self.entries.iter().any(|entry| entry.source.is_some());
+        let mut table = if sources {
+            Table::new(&[Align::Right, Align::Right, Align::Left, Align::Left])
+                .with_header(["size", "share", "file", "source"])
+        } else {
+            Table::new(&[Align::Right, Align::Right, Align::Left])
+                .with_header(["size", "share", "file"])
         };
-        let pointer_offset = pe_section.pointer_to_raw_data;
+        for entry in &self.entries {
+            let mut row = vec![
+                human_size(entry.size),
+                percentage(entry.size, self.total),
+                entry.filename.clone(),
+            ];
+            if sources {
+                row.push(entry.source.clone().unwrap_or_default());
+            }
+            table.push_row(row);
+        }
+        let mut total: Vec<Cell> = vec![
+            Cell::styled(human_size(self.total), Some(Style::Bold)),
+            percentage(self.total, self.total).into(),
+            format!("total in {} files", self.entries.len()).into(),
+        ];
+        if sources {
+            total.push(String::new().into());
+        }
+        table.push_row(total);
+        table
+    }
+}
+
+#[cfg(feature = "std")]
+impl Display for Report {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_table())
+    }
+}
 
-        for subsection in &mut section.subsections {
-            subsection.start_file_offset = Some(pointer_offset as u64 + subsection.start_vaddr);
+#[cfg(feature = "std")]
+impl Display for Section {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} vaddr {} offset {} size {}",
+            self.name,
+            hex(self.start_vaddr),
+            display_file_offset(self.start_file_offset),
+            human_size(self.size)
+        )?;
+        if self.subsections.is_empty() {
+            return Ok(());
+        }
+
+        let mut table = Table::new(&[
+            Align::Left,
+            Align::Left,
+            Align::Left,
+            Align::Right,
+            Align::Left,
+        ]);
+        for subsection in &self.subsections {
+            table.push_row(subsection_cells(subsection));
+        }
+        for line in table.to_string().lines() {
+            write!(f, "\n  {line}")?;
         }
+        Ok(())
     }
 }
 
-fn map_sections_to_elf(sections: &mut [Section], elf: &Elf) {
-    /*
-        For each section:
-        - Find the named section in the Elf file
-        - Find the file offset and fill it in
-        - Do the same for all subsections
-    */
-
-    // Maps a section header name to its offset in the file
-    let elf_section_hm: HashMap<&str, _> = elf
-        .section_headers
-        .iter()
-        .map(|shdr| {
-            (
-                elf.shdr_strtab.get_at(shdr.sh_name).unwrap(),
-                shdr.sh_offset,
+#[cfg(feature = "std")]
+impl Display for SubSection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", subsection_cells(self).join(" "))
+    }
+}
+
+#[cfg(feature = "std")]
+fn subsection_cells(subsection: &SubSection) -> [String; 5] {
+    [
+        subsection.name.clone(),
+        hex(subsection.start_vaddr),
+        display_file_offset(subsection.start_file_offset),
+        human_size(subsection.size),
+        if subsection.folded_with.is_empty() {
+            subsection.filename.clone()
+        } else {
+            format!(
+                "{} (folded with {})",
+                subsection.filename,
+                subsection.folded_with.join(", ")
             )
-        })
-        .collect();
-
-    sections.iter_mut().for_each(|section| {
-        section.start_file_offset = elf_section_hm.get(section.name.as_str()).copied();
-        if let Some(file_offset) = section.start_file_offset {
-            section.subsections.iter_mut().for_each(|ssection| {
-                ssection.start_file_offset =
-                    Some(ssection.start_vaddr - section.start_vaddr + file_offset);
-            });
-        }
-    })
+        },
+    ]
 }
 
-#[cfg(test)]
+#[cfg(feature = "std")]
+fn display_file_offset(offset: Option<u64>) -> String {
+    offset.map(hex).unwrap_or_else(|| "-".to_string())
+}
+
+#[cfg(all(test, feature = "std"))]
 mod tests {
+    use std::{borrow::Cow, error::Error, mem::size_of, ops::Range};
+
     use goblin::Object;
 
-    use crate::{extract_mapfile, map_sections_to_elf};
+    use crate::{
+        normalize_line_endings,
+        origin::{OriginNaming, PathFilter},
+        validate::Warning,
+        Address, Genealogy, GenealogyError,
+    };
+
+    #[test]
+    fn test_binary_parse_error() {
+        let mapfile = std::fs::read_to_string("tests/gcc/out.map").unwrap();
+        let binary = std::fs::read("tests/gcc/a.out").unwrap();
+
+        // A truncated ELF file is reported with the cause, not as an unsupported format
+        let error = Genealogy::new(&mapfile, &binary[..0x100]).err().unwrap();
+        assert!(matches!(error, GenealogyError::BinaryParse(_)));
+        assert!(error.source().is_some());
+        assert!(matches!(
+            Genealogy::new(&mapfile, mapfile.as_bytes()),
+            Err(GenealogyError::UnsupportedBinaryFormat)
+        ));
+    }
+
+    #[test]
+    fn test_line_endings() {
+        for (map, binary) in [
+            ("tests/gcc/out.map", "tests/gcc/a.out"),
+            ("tests/clang/output.map", "tests/clang/a.out"),
+            (
+                "tests/cargo-windows/out.map",
+                "tests/cargo-windows/genealogy.exe",
+            ),
+        ] {
+            // The MSVC map has the line endings of Windows already
+            let mapfile = std::fs::read_to_string(map).unwrap();
+            let mapfile = normalize_line_endings(&mapfile).into_owned();
+            assert!(matches!(normalize_line_endings(&mapfile), Cow::Borrowed(_)));
+            let binary = std::fs::read(binary).unwrap();
+            let expected = Genealogy::new(&mapfile, &binary).unwrap();
+            // The maps are those of clean builds
+            assert!(expected.warnings().is_empty(), "{map}");
+
+            // Windows line endings, on every line or some, doubled by a conversion
+            let crlf = mapfile.replace('\n', "\r\n");
+            let mixed: String = mapfile
+                .split_inclusive('\n')
+                .enumerate()
+                .map(|(i, line)| match i % 3 {
+                    0 => line.replace('\n', "\r\n"),
+                    1 => line.replace('\n', "\r\r\n"),
+                    _ => line.to_string(),
+                })
+                .collect();
+            for windows in [crlf, mixed] {
+                assert_eq!(normalize_line_endings(&windows), mapfile);
+                let genealogy = Genealogy::new(&windows, &binary).unwrap();
+                assert_eq!(genealogy.sections(), expected.sections(), "{map}");
+                assert_eq!(genealogy.warnings(), expected.warnings(), "{map}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_index_memory() {
+        let mapfile = std::fs::read_to_string("tests/cargo/out.map").unwrap();
+        let binary = std::fs::read("tests/cargo/genealogy").unwrap();
+        let genealogy = Genealogy::new(&mapfile, &binary).unwrap();
+        let index = genealogy.index();
+        // A tree of ranges owning their file names, as `IntervalTree<u64, String>` stores them:
+        // the range, the name and the largest end of the subtree per node
+        let tree_bytes: usize = index
+            .iter()
+            .chain(index.iter_vaddr())
+            .map(|hit| size_of::<(Range<u64>, String, u64)>() + hit.filename.len())
+            .sum();
+        let stats = index.stats();
+        assert_eq!(
+            stats.interval_bytes,
+            24 * (stats.intervals + stats.vaddr_intervals)
+        );
+        assert!(tree_bytes > 5 * stats.total_bytes());
+    }
+
+    #[test]
+    fn test_pre_parsed_binary() {
+        let mapfile = std::fs::read_to_string("tests/gcc/out.map").unwrap();
+        let binary = std::fs::read("tests/gcc/a.out").unwrap();
+        let Object::Elf(elf) = Object::parse(&binary).unwrap() else {
+            panic!("not an ELF file");
+        };
+        let genealogy = Genealogy::elf_builder(&mapfile, &elf)
+            .symbols(true)
+            .build()
+            .unwrap();
+        assert_eq!(
+            genealogy.sections(),
+            Genealogy::new(&mapfile, &binary).unwrap().sections()
+        );
+        assert_eq!(genealogy.lookup_symbol("main").unwrap().len(), 1);
+
+        let mapfile = std::fs::read_to_string("tests/cargo-windows/out.map").unwrap();
+        let binary = std::fs::read("tests/cargo-windows/genealogy.exe").unwrap();
+        let Object::PE(pe) = Object::parse(&binary).unwrap() else {
+            panic!("not a PE file");
+        };
+        let genealogy = Genealogy::with_pe(&mapfile, &pe, binary.len() as u64).unwrap();
+        assert_eq!(genealogy.image_base(), pe.image_base as u64);
+        assert_eq!(
+            genealogy.report().total,
+            Genealogy::new(&mapfile, &binary).unwrap().report().total
+        );
+    }
 
     #[test]
-    fn test_llvm_mapfile() {
-        let file = std::fs::read_to_string("tests/clang/output.map").unwrap();
-        let mut sections = extract_mapfile(&file).unwrap();
-
-        let binary = std::fs::read("tests/clang/a.out").unwrap();
-        let object = Object::parse(&binary).expect("Open test1");
-        if let Object::Elf(elf) = object {
-            map_sections_to_elf(&mut sections, &elf)
+    fn test_vaddr_bias() {
+        let mapfile = std::fs::read_to_string("tests/gcc/out.map").unwrap();
+        let binary = std::fs::read("tests/gcc/a.out").unwrap();
+        let genealogy = Genealogy::builder(&mapfile, &binary)
+            .vaddr_bias(0x1000)
+            .build()
+            .unwrap();
+
+        // Pretending the map was linked 0x1000 too low moves the addresses, not the file offsets
+        let hit = genealogy.query_vaddr_point(0x2151).next().unwrap();
+        assert_eq!(hit.filename, "simple.o");
+        assert_eq!(
+            genealogy.query_point(0x1151).next().unwrap().filename,
+            "simple.o"
+        );
+        assert!(genealogy.warnings().iter().any(|warning| matches!(
+            warning,
+            Warning::SectionAddressMismatch { section, map_vaddr: 0x2060, .. } if section == ".text"
+        )));
+    }
+
+    #[test]
+    fn test_query_address() {
+        let mapfile = std::fs::read_to_string("tests/gcc/out.map").unwrap();
+        let binary = std::fs::read("tests/gcc/a.out").unwrap();
+        let genealogy = Genealogy::new(&mapfile, &binary).unwrap();
+
+        for address in [
+            Address::FileOffset(0x1151),
+            Address::Vma(0x1151),
+            Address::Lma(0x1151),
+        ] {
+            let hits = genealogy.query_address(address).unwrap();
+            assert_eq!(hits.len(), 1);
+            assert_eq!(hits[0].filename, "simple.o");
+        }
+        assert!(genealogy
+            .query_address(Address::Lma(0x10_0000))
+            .unwrap()
+            .is_empty());
+        assert!(matches!(
+            genealogy.query_address(Address::Rva(0x1151)),
+            Err(GenealogyError::NoKeySpace("RVA"))
+        ));
+
+        let mapfile = std::fs::read_to_string("tests/cargo-windows/out.map").unwrap();
+        let binary = std::fs::read("tests/cargo-windows/genealogy.exe").unwrap();
+        let genealogy = Genealogy::new(&mapfile, &binary).unwrap();
+        let vaddr = genealogy.index().iter_vaddr().next().unwrap().range.start;
+        let rva = vaddr - genealogy.image_base();
+        assert_eq!(
+            genealogy.query_address(Address::Rva(rva)).unwrap(),
+            genealogy.query_vaddr_point(vaddr).collect::<Vec<_>>()
+        );
+        assert!(matches!(
+            genealogy.query_address(Address::Lma(vaddr)),
+            Err(GenealogyError::NoKeySpace("LMA"))
+        ));
+    }
+
+    #[test]
+    fn test_contributions() {
+        let mapfile = std::fs::read_to_string("tests/gcc/out.map").unwrap();
+        let binary = std::fs::read("tests/gcc/a.out").unwrap();
+        let genealogy = Genealogy::new(&mapfile, &binary).unwrap();
+
+        let filter = PathFilter::new().include("simple*.o").exclude("simple2.o");
+        assert!(filter.matches("/tmp/build/simple.o"));
+        assert!(!filter.matches("simple2.o"));
+        assert!(PathFilter::new()
+            .include("libc.a")
+            .matches("C:\\lib\\libc.a(printf.o)"));
+
+        let window = 0x1150..0x1160;
+        let hits: Vec<_> = genealogy.contributions(&filter, window.clone()).collect();
+        assert!(!hits.is_empty());
+        assert!(hits.iter().all(|hit| hit.filename == "simple.o"
+            && hit.range.start >= window.start
+            && hit.range.end <= window.end));
+        assert!(genealogy
+            .contributions(&filter, 0x1198..0x11a0)
+            .next()
+            .is_none());
+        assert_eq!(
+            genealogy
+                .contributions(&PathFilter::new(), 0..u64::MAX)
+                .count(),
+            genealogy.iter().count()
+        );
+    }
+
+    #[test]
+    fn test_report() {
+        let mapfile = std::fs::read_to_string("tests/gcc/out.map").unwrap();
+        let binary = std::fs::read("tests/gcc/a.out").unwrap();
+        let genealogy = Genealogy::new(&mapfile, &binary).unwrap();
+
+        let report = genealogy.report();
+        assert_eq!(report.total, report.entries.iter().map(|e| e.size).sum());
+        let simple = report
+            .entries
+            .iter()
+            .find(|e| e.filename == "simple.o")
+            .unwrap();
+        assert!(simple.size >= 0x4f);
+        assert!(report.to_string().contains("simple.o"));
+    }
+
+    #[test]
+    #[cfg(feature = "regex")]
+    fn test_find_symbols() {
+        let mapfile = std::fs::read_to_string("tests/gcc/out.map").unwrap();
+        let binary = std::fs::read("tests/gcc/a.out").unwrap();
+        let genealogy = Genealogy::builder(&mapfile, &binary)
+            .symbols(true)
+            .build()
+            .unwrap();
+
+        let pattern = regex::Regex::new("^(main|f2)$").unwrap();
+        let hits = genealogy.find_symbols(&pattern).unwrap();
+        let found: Vec<_> = hits
+            .iter()
+            .map(|hit| (hit.symbol.name.as_str(), hit.filename))
+            .collect();
+        assert_eq!(
+            found,
+            [("main", Some("simple.o")), ("f2", Some("simple2.o"))]
+        );
+        assert!(Genealogy::new(&mapfile, &binary)
+            .unwrap()
+            .find_symbols(&pattern)
+            .is_err());
+    }
+
+    #[test]
+    fn test_query_vaddr() {
+        let mapfile = std::fs::read_to_string("tests/gcc/out.map").unwrap();
+        let binary = std::fs::read("tests/gcc/a.out").unwrap();
+        let genealogy = Genealogy::new(&mapfile, &binary).unwrap();
+
+        let hits: Vec<_> = genealogy.query_vaddr_point(0x1151).collect();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].filename, "simple.o");
+        // Non-alloc sections such as .comment all start at 0 and must not be indexed
+        assert!(genealogy.query_vaddr_point(0).next().is_none());
+
+        // Empty subsections are kept in the layout but not indexed
+        assert!(genealogy
+            .sections()
+            .iter()
+            .flat_map(|s| s.subsections.iter())
+            .any(|sub_section| sub_section.size == 0));
+        assert!(genealogy.iter().all(|hit| !hit.range.is_empty()));
+        assert!(genealogy
+            .query_vaddr(0..u64::MAX)
+            .all(|hit| !hit.range.is_empty()));
+    }
+
+    #[test]
+    fn test_deterministic_output() {
+        use crate::output::ToValue;
+
+        // Every build hashes with other keys, so iteration orders leaking into the output would
+        // show up as differences between builds
+        let render = |map: &str, binary: &str| {
+            let mapfile = std::fs::read_to_string(map).unwrap();
+            let binary = std::fs::read(binary).unwrap();
+            let genealogy = Genealogy::builder(&mapfile, &binary)
+                .symbols(true)
+                .origin_naming(OriginNaming::ShortestUniqueSuffix)
+                .build()
+                .unwrap();
+            let report = genealogy.report();
+            [
+                report.to_value(),
+                report.by_language().to_value(),
+                genealogy.section_matrix().to_value(),
+                genealogy.padding_report().to_value(),
+                genealogy.page_report(4096).to_value(),
+                genealogy.locality_report().unwrap().to_value(),
+            ]
+            .map(|value| value.to_json())
+        };
+        for (map, binary) in [
+            ("tests/gcc/out.map", "tests/gcc/a.out"),
+            ("tests/cargo/out.map", "tests/cargo/genealogy"),
+            (
+                "tests/cargo-windows/out.map",
+                "tests/cargo-windows/genealogy.exe",
+            ),
+        ] {
+            assert_eq!(render(map, binary), render(map, binary), "{map}");
         }
     }
 }
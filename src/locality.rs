@@ -0,0 +1,203 @@
+//! How interleaved the symbols of every file are in the binary, and symbol ordering files
+//! (`--symbol-ordering-file` for lld, `-order_file` for ld64) that group them back, so that the
+//! code run at startup or in hot loops touches fewer pages.
+//!
+//! Linkers only reorder sections, so the order only applies to objects compiled with
+//! `-ffunction-sections` (and `-fdata-sections` for data).
+
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Display,
+};
+
+use crate::{
+    format::{human_size, Align, Table},
+    symbols::Symbol,
+    Genealogy, GenealogyError,
+};
+
+/// The symbols of every file, see [`Genealogy::locality_report`].
+#[derive(Clone, Debug)]
+pub struct LocalityReport {
+    /// Most fragmented first.
+    pub entries: Vec<LocalityEntry>,
+}
+
+#[derive(Clone, Debug)]
+pub struct LocalityEntry {
+    pub filename: String,
+    pub symbols: u64,
+    /// Number of runs of consecutive symbols of the file, in address order and per section.
+    pub runs: u64,
+    /// Sum of the sizes of the symbols.
+    pub size: u64,
+    /// Virtual address of the first symbol.
+    pub start: u64,
+    /// Bytes between the start of the first symbol and the end of the last one, across sections.
+    pub span: u64,
+}
+
+impl LocalityEntry {
+    /// From 0 when the symbols of the file are all contiguous, to 1 when no two of them are.
+    pub fn fragmentation(&self) -> f64 {
+        if self.symbols <= 1 {
+            0.0
+        } else {
+            (self.runs - 1) as f64 / (self.symbols - 1) as f64
+        }
+    }
+}
+
+impl Genealogy {
+    /// Measures the locality of the symbols of every file. Symbols without a size or a file,
+    /// and aliases, are left out.
+    pub fn locality_report(&self) -> Result<LocalityReport, GenealogyError> {
+        let mut entries: HashMap<&str, LocalityEntry> = HashMap::new();
+        let mut previous: Option<(&str, Option<&str>)> = None;
+        for (symbol, filename) in self.placed_symbols()? {
+            let section = symbol.section.as_deref();
+            let entry = entries.entry(filename).or_insert_with(|| LocalityEntry {
+                filename: filename.to_string(),
+                symbols: 0,
+                runs: 0,
+                size: 0,
+                start: symbol.vaddr,
+                span: 0,
+            });
+            entry.symbols += 1;
+            entry.size += symbol.size;
+            if previous != Some((filename, section)) {
+                entry.runs += 1;
+            }
+            previous = Some((filename, section));
+
+            let end = symbol.vaddr.saturating_add(symbol.size);
+            entry.span = entry.span.max(end - entry.start);
+        }
+
+        let mut entries: Vec<LocalityEntry> = entries.into_values().collect();
+        entries.sort_by(|a, b| {
+            b.fragmentation()
+                .total_cmp(&a.fragmentation())
+                .then_with(|| b.runs.cmp(&a.runs))
+                .then_with(|| a.filename.cmp(&b.filename))
+        });
+        Ok(LocalityReport { entries })
+    }
+
+    /// A symbol ordering file placing the symbols of `files` first and together, in the order of
+    /// `files` (e.g. the heaviest files of a [`ProfileReport`](crate::profile::ProfileReport))
+    /// and in their current order within a file. Files not in the genealogy are ignored.
+    pub fn symbol_ordering<'a>(
+        &self,
+        files: impl IntoIterator<Item = &'a str>,
+    ) -> Result<Vec<String>, GenealogyError> {
+        let mut symbols: HashMap<&str, Vec<&Symbol>> = HashMap::new();
+        for (symbol, filename) in self.placed_symbols()? {
+            symbols.entry(filename).or_default().push(symbol);
+        }
+
+        let mut seen = HashSet::new();
+        Ok(files
+            .into_iter()
+            .flat_map(|file| symbols.get(file).into_iter().flatten())
+            .filter(|symbol| seen.insert(symbol.name.as_str()))
+            .map(|symbol| symbol.name.clone())
+            .collect())
+    }
+
+    /// The symbols occupying file space, in address order, without aliases, and their file.
+    fn placed_symbols(&self) -> Result<Vec<(&Symbol, &str)>, GenealogyError> {
+        let symbols = self.symbols().ok_or(GenealogyError::NoSymbolIndex)?;
+        Ok(symbols
+            .deduplicated()
+            .filter(|symbol| symbol.size > 0)
+            .filter_map(|symbol| Some((symbol, self.symbol_hit(symbol).filename?)))
+            .collect())
+    }
+}
+
+impl LocalityReport {
+    /// The files in the order of their first symbol, to defragment all of them.
+    pub fn files_by_address(&self) -> Vec<&str> {
+        let mut entries: Vec<&LocalityEntry> = self.entries.iter().collect();
+        entries.sort_by(|a, b| {
+            a.start
+                .cmp(&b.start)
+                .then_with(|| a.filename.cmp(&b.filename))
+        });
+        entries
+            .into_iter()
+            .map(|entry| entry.filename.as_str())
+            .collect()
+    }
+
+    /// The table rendered by `Display`.
+    pub fn to_table(&self) -> Table {
+        let mut table = Table::new(&[
+            Align::Right,
+            Align::Right,
+            Align::Right,
+            Align::Right,
+            Align::Right,
+            Align::Left,
+        ])
+        .with_header(["fragmentation", "runs", "symbols", "size", "span", "file"]);
+        for entry in &self.entries {
+            table.push_row([
+                format!("{:.2}", entry.fragmentation()),
+                entry.runs.to_string(),
+                entry.symbols.to_string(),
+                human_size(entry.size),
+                human_size(entry.span),
+                entry.filename.clone(),
+            ]);
+        }
+        table
+    }
+}
+
+impl Display for LocalityReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_table())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Genealogy;
+
+    #[test]
+    fn test_locality_report() {
+        let mapfile = std::fs::read_to_string("tests/gcc/out.map").unwrap();
+        let binary = std::fs::read("tests/gcc/a.out").unwrap();
+        let genealogy = Genealogy::builder(&mapfile, &binary)
+            .symbols(true)
+            .build()
+            .unwrap();
+
+        let report = genealogy.locality_report().unwrap();
+        let simple = report
+            .entries
+            .iter()
+            .find(|entry| entry.filename == "simple.o")
+            .unwrap();
+        // f and main are next to each other
+        assert_eq!(simple.symbols, 2);
+        assert_eq!(simple.runs, 1);
+        assert_eq!(simple.fragmentation(), 0.0);
+        assert!(simple.span >= simple.size);
+
+        let ordering = genealogy
+            .symbol_ordering(["simple2.o", "simple.o", "missing.o"])
+            .unwrap();
+        assert_eq!(ordering, ["f2", "f", "main"]);
+
+        let files = report.files_by_address();
+        let position = |file| files.iter().position(|&f| f == file).unwrap();
+        assert!(position("simple.o") < position("simple2.o"));
+
+        let genealogy = Genealogy::new(&mapfile, &binary).unwrap();
+        assert!(genealogy.locality_report().is_err());
+    }
+}
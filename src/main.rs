@@ -1,12 +1,2677 @@
-use genealogy::Genealogy;
+use std::{
+    borrow::Cow,
+    io::{BufWriter, IsTerminal, Write},
+    ops::Range,
+    path::Path,
+    process::{Command, ExitCode},
+};
 
-fn main() {
-    let binary_path = "tests/cargo-windows/genealogy.exe";
-    let map_path = "tests/cargo-windows/out.map";
+#[cfg(feature = "compression")]
+use genealogy::compression::{Codec, Compression};
+use genealogy::{
+    android::{app_report, extract, native_libraries},
+    archives::ArchiveMembers,
+    blobs::DEFAULT_MIN_BLOB_SIZE,
+    commits::{parse_git_log, GIT_LOG_ARGUMENTS},
+    compile_commands::CompileCommands,
+    debuginfo::is_debug_section,
+    dependencies::Dependencies,
+    format::{hex, human_size, percentage, Align, Table},
+    history::{Build, History},
+    hotcold::parse_function_list,
+    image::{summary_table, CombinedImage, Component, Placement},
+    kernel::parse_system_map,
+    layout::DEFAULT_BANDS,
+    normalize_line_endings,
+    origin::{Origin, OriginNaming, PathFilter, PathSeparators, RustUnits},
+    output::{ToValue, Value},
+    ownership::CodeOwners,
+    pages::DEFAULT_PAGE_SIZE,
+    partitions::PartitionTable,
+    profile::{parse_callgrind, parse_wpa_csv, Sample},
+    render::{
+        HtmlRenderer, JsonRenderer, MarkdownRenderer, Renderable, ReportRenderer, TextRenderer,
+        YamlRenderer,
+    },
+    server::Server,
+    snapshot::{Snapshot, Thresholds},
+    stack::StackUsage,
+    table::ProvenanceTable,
+    triage::{parse_sanitizer_frames, Frame},
+    uboot::{fit_images, fit_table, locate_stage, stage_report, Stage},
+    Address, Genealogy, GenealogyError, QueryHit, Report, SymbolHit,
+};
+use regex::Regex;
 
-    let binary = std::fs::read(binary_path).unwrap();
-    let mapfile = std::fs::read_to_string(map_path).unwrap();
+const USAGE: &str = "\
+Usage:
+    genealogy query [--key <offset|vma|lma|rva>] [--granularities]
+                    <binary> <map> <address>...
+    genealogy explain <binary> <map> <offset>...
+    genealogy annotate-disasm [--disassembly <listing>] [--objdump <tool>]
+                              <binary> <map>
+    genealogy serve <binary> <map>
+    genealogy dump [--include <pattern>]... [--exclude <pattern>]...
+                   [--window <start>..<end>] [--granularities] <binary> <map>
+    genealogy report [--budget <size>] [--compress <codec>[:<level>]] [--by-language]
+                     [--runtime-files] [--compile-commands <json>] [--deps <file>]...
+                     [--codeowners <file> [--repository <dir>]] <binary> <map>
+    genealogy diff <old-binary> <old-map> <new-binary> <new-map>
+    genealogy diff --git <old-rev>..<new-rev> [--repository <dir>]
+                   [--compile-commands <json>] <binary-path> <map-path>
+    genealogy deadstrip <binary> <map> <stripped-binary> <stripped-map>
+    genealogy reorder <old-binary> <old-map> <new-binary> <new-map>
+    genealogy why <archive(member)> <binary> <map>
+    genealogy whois <symbol> <binary> <map>
+    genealogy find <regex> <binary> <map>
+    genealogy triage --asan-log <log> <binary> <map>
+    genealogy ctors <binary> <map>
+    genealogy got <binary> <map>
+    genealogy exports <binary> <map>
+    genealogy unwind <binary> <map>
+    genealogy debuginfo <binary> <map>
+    genealogy strip [--by-component] <binary> <map>
+    genealogy quality [--min-quality <percent>] <binary> <map>
+    genealogy padding <binary> <map>
+    genealogy pages [--page-size <size>] <binary> <map>
+    genealogy partitions --partitions <table> [--flash-base <addr>]
+                         [--flash-size <size>] <binary> <map>
+    genealogy resolution <binary> <map>
+    genealogy matrix <binary> <map>
+    genealogy table <binary> <map> <output>
+    genealogy lookup <table> <address>...            (with the `mmap` feature)
+    genealogy snapshot <binary> <map> <output>
+    genealogy hierarchy <binary> <map> <output>
+    genealogy check --against <snapshot> [--max-move <size>] [--max-growth <size>]
+                    <binary> <map>
+    genealogy history --record [--label <label>] <history> <binary> <map>
+    genealogy history [--component <name>] [--last <n>] <history>
+    genealogy memdump --dump-base <addr> [--load-bias <addr>] <dump> <binary> <map>
+    genealogy hotcold --hot <list> <binary> <map>
+    genealogy vectors <binary> <map>
+    genealogy stack --su <file>... <binary> <map>
+    genealogy kernel [--system-map <file>] <binary> <map>
+    genealogy statics <binary> <map>
+    genealogy namespaces [--depth <n>] <binary> <map>
+    genealogy blobs [--min-size <size>] <binary> <map>
+    genealogy layout [--bands <n>] <binary> <map> <svg>
+    genealogy image --component <name>,<offset>,<binary>,<map>... [--raw]
+                    <image> [<offset>...]
+    genealogy uboot <build-dir> [<image>]
+    genealogy android [--abi <abi>] <apk|aab> <map>...
+    genealogy locality [--order-file <output>] [--wpa-csv <csv> | --callgrind <out>]
+                       <binary> <map>
+    genealogy profile --wpa-csv <csv> [--module <name>] <binary> <map>
+    genealogy profile --callgrind <out> [--event <name>] [--load-bias <addr>]
+                      [--module <name>] <binary> <map>
 
-    let genealogy = Genealogy::new(&mapfile, &binary).unwrap();
-    println!("{:?}", genealogy.query_point(0x135900).collect::<Vec<_>>());
+Options:
+    --output <text|json|jsonl|yaml|csv|openmetrics|markdown|html>
+        Output format (default: text). `jsonl` streams one JSON record per
+        line: one per hit, explained offset, interval, file, changed, stripped or
+        reordered file, archive member pulling a file in, constructor, GOT
+        slot, exported symbol, symbol, dump region, page, segment, partition
+        offender, layout drift, recorded or historical build, image component,
+        vector, stack frame, static, namespace, blob, or object of a native
+        library.
+        `csv` is only supported by `matrix`. `openmetrics` is only supported
+        by `report`: Prometheus/OpenMetrics gauges of the total and of every
+        file (or language, owners or header) for monitoring. `markdown` and
+        `html` render the main table of a report, e.g. for pull request
+        comments, and are not supported by query, explain, dump, whois, find,
+        triage, image, uboot with an image, lookup and check.
+    --key <offset|vma|lma|rva>
+        What the addresses of `query` are (default: offset): file offsets,
+        virtual addresses, load addresses (ELF files with program headers)
+        or addresses relative to the image base (PE files).
+    --include <pattern>, --exclude <pattern>
+        Only `dump` the files matching an included pattern (or any file when
+        none is given) and no excluded one, using the CODEOWNERS syntax on
+        the object, its archive and its full path. Repeatable.
+    --window <start>..<end>
+        Only `dump` the file offsets from <start> to <end>, clipping the
+        ranges overlapping them.
+    --granularities
+        Give the file of every hit of `query` and `dump` at every granularity:
+        object, archive, Rust crate, component (<runtime>, the crate, the
+        archive or the directory) and directory. Text output of `dump` is
+        left as is.
+    --origin-naming <full|suffix|hash>
+        How object files are named (default: full): their full path, their
+        shortest path suffix no other file shares, or their file name with a
+        hash of the path when another file has the same name.
+    --rust-units <keep|crate|module>
+        How the codegen units of Rust crates are named (default: keep): as
+        written, one per crate, or after the module of the Rust symbol each
+        input section is named after, as in `.text._ZN...`.
+    --vaddr-bias <offset>
+        Added to the addresses of the map when mapping it onto an ELF file,
+        for maps linked against another base than the ELF headers. May be
+        negative, e.g. -0x80000000.
+    --path-separators <keep|slash|backslash>
+        Rewrite the path separators of object files (default: keep), also
+        resolving `.` and `..` components, so that a file written with mixed
+        separators is counted once.
+    --sysroot <dir>
+        Sysroot the linker resolved `=` and `$SYSROOT` paths against, e.g.
+        =/usr/lib/crt1.o. Without it the prefix is dropped.
+    --no-color
+        Never color text output. Color is otherwise used when stdout is a
+        terminal and NO_COLOR is not set.
+    --error-format <text|json>
+        How errors and warnings are written to stderr (default: text).
+    --warnings
+        List the issues found while mapping, e.g. a subsection outside of its
+        section or a PE section with unaligned raw data. Otherwise only their
+        count is written, and nothing with --error-format json.
+    --budget <size>
+        Fail `report` when the attributed total exceeds <size> bytes. Accepts
+        K, M and G (binary) suffixes.
+    --by-language
+        Make `report` sum the sizes per language (Rust, C, C++, assembly,
+        runtime, linker), guessed from file and archive names.
+    --runtime-files
+        List the start files (crt1.o, crtbegin.o...) and compiler support
+        libraries (libgcc, compiler-rt, the MSVC runtime) in `report` one by
+        one. By default they are summed as a single <runtime> file.
+    --min-quality <percent>
+        Fail `quality` when the score is below <percent>, e.g. 90 or 90%:
+        the percentage of the bytes of the sections attributed, the ones
+        estimated from symbols counting half.
+    --by-component
+        Make `strip` sum the sizes per component (<runtime>, the crate, the
+        archive or the directory) instead of per file.
+    --codeowners <file>
+        Make `report` sum the sizes per owners of the source files, read from
+        a CODEOWNERS file. Sources are found with --compile-commands.
+    --compile-commands <json>
+        Compilation database (compile_commands.json) giving the source file
+        of every object file, shown by `report` next to the object.
+    --deps <file>
+        Make `report` sum the sizes per header, of the objects including it,
+        from Make depfiles (*.d) or Ninja's .ninja_deps. Repeatable.
+    --repository <dir>
+        Root of the paths of --codeowners (default: the directory of the
+        CODEOWNERS file, or its parent for .github, .gitlab and docs), and
+        the git repository of `diff --git` (default: the current directory).
+    --git <old-rev>..<new-rev>
+        Make `diff` compare the artifacts of two revisions and attribute the
+        size changes to the commits in between that touched the source of
+        the changed objects (from --compile-commands, or by name). The paths
+        of the binary and the map are patterns in which {rev} is replaced by
+        the full commit hash and {short} by its first 7 characters, e.g.
+        artifacts/{short}/app.elf. Artifacts are not built nor fetched.
+    --compress <gzip|zstd>[:<level>]
+        Make `report` attribute the compressed size of every file instead of
+        its raw size, e.g. zstd:19 (default level: 6 for gzip, 3 for zstd).
+        The budget then applies to the compressed total. Requires the
+        `compression` feature.
+    --asan-log <log>
+        AddressSanitizer/UBSan report whose stack frames `triage` attributes.
+    --wpa-csv <csv>
+        Windows Performance Analyzer export with an RVA column (and optionally
+        Weight and Module columns) whose samples `profile` attributes, or
+        `locality` orders the heaviest files with.
+    --callgrind <out>
+        callgrind output recorded with --dump-instr=yes whose instruction
+        costs `profile` attributes, or `locality` orders the heaviest files
+        with.
+    --event <name>
+        callgrind event to weigh samples by (default: the first one).
+    --load-bias <addr>
+        Subtracted from callgrind addresses, e.g. 0x108000 for position
+        independent executables run by Valgrind on amd64, and from the
+        runtime addresses of `memdump`: the load address of position
+        independent ELF files, or the load address minus the image base of
+        PE files.
+    --dump-base <addr>
+        Runtime address of the first byte of the `memdump` dump.
+    --module <name>
+        Only keep the profile rows of this module.
+    --threads <n>
+        Parse the map on <n> threads while the binary is parsed (default: 1).
+    --memory-limit <size>
+        Bound the map text parsed ahead by --threads, e.g. 256M (default:
+        unbounded).
+    --page-size <size>
+        Size of the pages `pages` splits the image into (default: 4K).
+    --partitions <table>
+        Flash partition table `partitions` checks the content against: an
+        ESP-IDF partitions.csv, the bootloader.conf of MCUboot's Espressif
+        port, or [[partition]] TOML tables of name, type, offset and size.
+        Content must be in an app partition, or in any partition when the
+        table has no app ones.
+    --flash-base <addr>
+        Address the flash is mapped at, where partition offsets start
+        (default: 0).
+    --flash-size <size>
+        Size of the flash, content past it is taken for RAM (default: the
+        end of the last partition).
+    --component <name>,<offset>,<binary>,<map>
+        Component of the combined flash image of `image`, written at
+        <offset> of it, e.g. bootloader,0x1000,boot.elf,boot.map.
+        Repeatable. Without offsets to query, `image` lists the components.
+    --component <name>
+        Component whose size `history` shows across builds instead of the
+        total, as named by --granularities, e.g. libcrypto.a.
+    --raw
+        The components of `image` were converted to raw images (objcopy -O
+        binary) starting at their lowest loaded address, instead of being
+        copied as is.
+    --abi <abi>
+        Only attribute the native libraries `android` finds for this ABI,
+        e.g. arm64-v8a. Maps are paired with libraries by name
+        (libfoo.so.map or libfoo.map), and by ABI when in a directory named
+        after one.
+    --su <file>
+        Stack usage file written by -fstack-usage next to an object file
+        (foo.su for foo.o), whose functions `stack` attributes. Repeatable.
+    --system-map <file>
+        System.map of the kernel build, whose __ksymtab_ and __init_ symbols
+        `kernel` reads (default: the symbol table of the binary).
+    --depth <n>
+        Number of namespace or module path components `namespaces` sums the
+        symbol sizes by (default: 1, the top-level namespace or crate).
+    --min-size <size>
+        Smallest blob `blobs` reports (default: 64K): input sections starting
+        with the magic bytes of an archive, image or model format, or runs of
+        compressed-looking data.
+    --bands <n>
+        Number of bands `layout` splits the file offsets of the binary into
+        (default: 16). The SVG it writes can be turned into a PNG with e.g.
+        rsvg-convert.
+    --hot <list>
+        Functions `hotcold` checks were placed in .text.hot or at the front
+        of .text, one name per line (e.g. a symbol ordering file).
+    --disassembly <listing>
+        Output of `objdump -d` or `llvm-objdump -d` of the binary that
+        `annotate-disasm` annotates with a comment naming the file of the
+        instructions that follow, whenever it changes. By default the
+        binary is disassembled with --objdump.
+    --objdump <tool>
+        Disassembler `annotate-disasm` runs as `<tool> -d <binary>` without
+        --disassembly (default: objdump).
+    --order-file <output>
+        Make `locality` write a symbol ordering file (lld's
+        --symbol-ordering-file, ld64's -order_file) grouping the symbols of
+        every file, heaviest files first with a profile, or in address order.
+    --against <snapshot>
+        Layout snapshot written by `snapshot` that `check` compares the
+        loaded sections with.
+    --max-move <size>
+        Bytes a section may move by before `check` fails (default: 0).
+    --max-growth <size>
+        Bytes a section may grow by before `check` fails (default: 0).
+    --record
+        Make `history` append the size of every component of the binary to
+        the <history> file, a JSON record per line, instead of showing it.
+    --label <label>
+        What the build recorded by `history` was made from, e.g. a commit
+        hash or a CI job number.
+    --last <n>
+        Number of the latest builds `history` shows (default: all).
+
+Exit codes:
+    0  success
+    1  I/O error
+    2  invalid command line
+    3  map file or input could not be parsed
+    4  binary format not supported
+    5  size budget exceeded
+    6  queried offset or symbol not found
+    7  hot functions placed outside of the hot region
+    8  layout drifted from the snapshot
+    9  content outside of its flash partition
+    10 vector table entry in no file or without the Thumb bit
+    11 attribution quality below --min-quality";
+
+/// Number of statics listed by the text output of `statics`.
+const BIGGEST_STATICS: usize = 10;
+
+/// Failure causes, each with its own exit code so scripts can branch on them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ErrorKind {
+    Io,
+    Usage,
+    Parse,
+    UnsupportedFormat,
+    BudgetExceeded,
+    NotFound,
+    Misplaced,
+    LayoutDrift,
+    OutOfPartition,
+    InvalidVectors,
+    Degraded,
+}
+
+impl ErrorKind {
+    fn exit_code(self) -> u8 {
+        match self {
+            ErrorKind::Io => 1,
+            ErrorKind::Usage => 2,
+            ErrorKind::Parse => 3,
+            ErrorKind::UnsupportedFormat => 4,
+            ErrorKind::BudgetExceeded => 5,
+            ErrorKind::NotFound => 6,
+            ErrorKind::Misplaced => 7,
+            ErrorKind::LayoutDrift => 8,
+            ErrorKind::OutOfPartition => 9,
+            ErrorKind::InvalidVectors => 10,
+            ErrorKind::Degraded => 11,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            ErrorKind::Io => "io",
+            ErrorKind::Usage => "usage",
+            ErrorKind::Parse => "parse",
+            ErrorKind::UnsupportedFormat => "unsupported_format",
+            ErrorKind::BudgetExceeded => "budget_exceeded",
+            ErrorKind::NotFound => "not_found",
+            ErrorKind::Misplaced => "misplaced",
+            ErrorKind::LayoutDrift => "layout_drift",
+            ErrorKind::OutOfPartition => "out_of_partition",
+            ErrorKind::InvalidVectors => "invalid_vectors",
+            ErrorKind::Degraded => "degraded",
+        }
+    }
+}
+
+#[derive(Debug)]
+struct CliError {
+    kind: ErrorKind,
+    message: String,
+}
+
+impl CliError {
+    fn new(kind: ErrorKind, message: impl Into<String>) -> Self {
+        Self {
+            kind,
+            message: message.into(),
+        }
+    }
+
+    fn usage() -> Self {
+        Self::new(ErrorKind::Usage, USAGE)
+    }
+
+    fn to_value(&self) -> Value {
+        Value::object([(
+            "error",
+            Value::object([
+                ("kind", self.kind.name().into()),
+                ("code", u64::from(self.kind.exit_code()).into()),
+                ("message", self.message.as_str().into()),
+            ]),
+        )])
+    }
+}
+
+impl From<GenealogyError> for CliError {
+    fn from(error: GenealogyError) -> Self {
+        let kind = match error {
+            GenealogyError::UnsupportedBinaryFormat => ErrorKind::UnsupportedFormat,
+            GenealogyError::BinaryParse(_)
+            | GenealogyError::WrongMapfileFormat
+            | GenealogyError::WrongProfileFormat
+            | GenealogyError::WrongCompileCommandsFormat
+            | GenealogyError::WrongNinjaDepsFormat
+            | GenealogyError::WrongPartitionTableFormat
+            | GenealogyError::WrongFitFormat
+            | GenealogyError::WrongArchiveFormat
+            | GenealogyError::WrongStackUsageFormat
+            | GenealogyError::WrongSystemMapFormat
+            | GenealogyError::WrongSnapshotFormat
+            | GenealogyError::WrongHistoryFormat
+            | GenealogyError::NoArchSlice(_)
+            | GenealogyError::AddressOutOfRange(_) => ErrorKind::Parse,
+            GenealogyError::NoSymbolIndex | GenealogyError::NoKeySpace(_) => ErrorKind::Usage,
+            GenealogyError::NoVectorTable => ErrorKind::NotFound,
+            GenealogyError::CompressedArchiveEntry(_) => ErrorKind::UnsupportedFormat,
+        };
+        Self::new(kind, error.to_string())
+    }
+}
+
+impl From<std::io::Error> for CliError {
+    fn from(error: std::io::Error) -> Self {
+        Self::new(ErrorKind::Io, error.to_string())
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum OutputFormat {
+    Text,
+    Json,
+    JsonLines,
+    Yaml,
+    Csv,
+    OpenMetrics,
+    Markdown,
+    Html,
+}
+
+/// A `--component` of `image`.
+struct ComponentSpec {
+    name: String,
+    offset: u64,
+    binary: String,
+    map: String,
+}
+
+struct Options {
+    output: OutputFormat,
+    color: bool,
+    budget: Option<u64>,
+    min_quality: Option<f64>,
+    #[cfg(feature = "compression")]
+    compression: Option<Compression>,
+    asan_log: Option<String>,
+    wpa_csv: Option<String>,
+    callgrind: Option<String>,
+    event: Option<String>,
+    order_file: Option<String>,
+    hot: Option<String>,
+    disassembly: Option<String>,
+    objdump: Option<String>,
+    components: Vec<ComponentSpec>,
+    raw: bool,
+    component: Option<String>,
+    record: bool,
+    label: Option<String>,
+    last: Option<usize>,
+    key: fn(u64) -> Address,
+    page_size: u64,
+    depth: usize,
+    min_blob_size: u64,
+    bands: u32,
+    against: Option<String>,
+    partitions: Option<String>,
+    flash_base: u64,
+    flash_size: Option<u64>,
+    thresholds: Thresholds,
+    threads: usize,
+    memory_limit: usize,
+    load_bias: u64,
+    dump_base: Option<u64>,
+    module: Option<String>,
+    path_separators: PathSeparators,
+    sysroot: Option<String>,
+    origin_naming: OriginNaming,
+    rust_units: RustUnits,
+    json_errors: bool,
+    warnings: bool,
+    granularities: bool,
+    by_language: bool,
+    runtime_files: bool,
+    by_component: bool,
+    codeowners: Option<String>,
+    compile_commands: Option<String>,
+    repository: Option<String>,
+    git_range: Option<(String, String)>,
+    deps: Vec<String>,
+    su: Vec<String>,
+    system_map: Option<String>,
+    abi: Option<String>,
+    filter: PathFilter,
+    window: Option<Range<u64>>,
+    vaddr_bias: i64,
+    positionals: Vec<String>,
+    /// `--help` was given, the usage is printed instead of running a command.
+    help: bool,
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    // Looked up before anything else so that option parsing errors honor it too
+    let json_errors = args
+        .windows(2)
+        .any(|pair| pair[0] == "--error-format" && pair[1] == "json");
+
+    let result = parse_options(&args).and_then(|options| match options.help {
+        true => {
+            println!("{USAGE}");
+            Ok(())
+        }
+        false => run(&options),
+    });
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            if json_errors {
+                eprintln!("{}", error.to_value().to_json());
+            } else {
+                eprintln!("error: {}", error.message);
+            }
+            ExitCode::from(error.kind.exit_code())
+        }
+    }
+}
+
+fn parse_options(args: &[String]) -> Result<Options, CliError> {
+    let mut options = Options {
+        output: OutputFormat::Text,
+        color: std::io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none(),
+        budget: None,
+        min_quality: None,
+        #[cfg(feature = "compression")]
+        compression: None,
+        asan_log: None,
+        wpa_csv: None,
+        callgrind: None,
+        event: None,
+        order_file: None,
+        hot: None,
+        disassembly: None,
+        objdump: None,
+        components: vec![],
+        raw: false,
+        component: None,
+        record: false,
+        label: None,
+        last: None,
+        key: Address::FileOffset,
+        page_size: DEFAULT_PAGE_SIZE,
+        depth: 1,
+        min_blob_size: DEFAULT_MIN_BLOB_SIZE,
+        bands: DEFAULT_BANDS,
+        against: None,
+        partitions: None,
+        flash_base: 0,
+        flash_size: None,
+        thresholds: Thresholds::default(),
+        threads: 1,
+        memory_limit: usize::MAX,
+        load_bias: 0,
+        dump_base: None,
+        module: None,
+        path_separators: PathSeparators::Keep,
+        sysroot: None,
+        origin_naming: OriginNaming::FullPath,
+        rust_units: RustUnits::Keep,
+        json_errors: false,
+        warnings: false,
+        granularities: false,
+        by_language: false,
+        runtime_files: false,
+        by_component: false,
+        codeowners: None,
+        compile_commands: None,
+        repository: None,
+        git_range: None,
+        deps: vec![],
+        su: vec![],
+        system_map: None,
+        abi: None,
+        filter: PathFilter::new(),
+        window: None,
+        vaddr_bias: 0,
+        positionals: vec![],
+        help: false,
+    };
+    // Read once the subcommand is known, wherever it is among the arguments
+    let mut components = vec![];
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--output" => {
+                options.output = match args.next().map(String::as_str) {
+                    Some("text") => OutputFormat::Text,
+                    Some("json") => OutputFormat::Json,
+                    Some("jsonl") => OutputFormat::JsonLines,
+                    Some("yaml") => OutputFormat::Yaml,
+                    Some("csv") => OutputFormat::Csv,
+                    Some("openmetrics") => OutputFormat::OpenMetrics,
+                    Some("markdown") => OutputFormat::Markdown,
+                    Some("html") => OutputFormat::Html,
+                    Some(other) => {
+                        return Err(CliError::new(
+                            ErrorKind::Usage,
+                            format!("unknown output format: {other}"),
+                        ))
+                    }
+                    None => return Err(missing_value("--output")),
+                }
+            }
+            "--error-format" => match args.next().map(String::as_str) {
+                Some("text") => options.json_errors = false,
+                Some("json") => options.json_errors = true,
+                Some(other) => {
+                    return Err(CliError::new(
+                        ErrorKind::Usage,
+                        format!("unknown error format: {other}"),
+                    ))
+                }
+                None => return Err(missing_value("--error-format")),
+            },
+            "--budget" => {
+                let value = args.next().ok_or_else(|| missing_value("--budget"))?;
+                options.budget = Some(parse_size(value)?);
+            }
+            "--min-quality" => {
+                let value = args.next().ok_or_else(|| missing_value("--min-quality"))?;
+                options.min_quality = Some(parse_percentage(value)?);
+            }
+            #[cfg(feature = "compression")]
+            "--compress" => {
+                let value = args.next().ok_or_else(|| missing_value("--compress"))?;
+                options.compression = Some(parse_compression(value)?);
+            }
+            "--asan-log" => {
+                let value = args.next().ok_or_else(|| missing_value("--asan-log"))?;
+                options.asan_log = Some(value.clone());
+            }
+            "--wpa-csv" => {
+                let value = args.next().ok_or_else(|| missing_value("--wpa-csv"))?;
+                options.wpa_csv = Some(value.clone());
+            }
+            "--callgrind" => {
+                let value = args.next().ok_or_else(|| missing_value("--callgrind"))?;
+                options.callgrind = Some(value.clone());
+            }
+            "--event" => {
+                let value = args.next().ok_or_else(|| missing_value("--event"))?;
+                options.event = Some(value.clone());
+            }
+            "--threads" => {
+                let value = args.next().ok_or_else(|| missing_value("--threads"))?;
+                options.threads = value.parse().map_err(|_| {
+                    CliError::new(ErrorKind::Usage, format!("invalid thread count: {value}"))
+                })?;
+            }
+            "--memory-limit" => {
+                let value = args.next().ok_or_else(|| missing_value("--memory-limit"))?;
+                options.memory_limit = usize::try_from(parse_size(value)?).unwrap_or(usize::MAX);
+            }
+            "--page-size" => {
+                let value = args.next().ok_or_else(|| missing_value("--page-size"))?;
+                options.page_size = match parse_size(value)? {
+                    0 => return Err(CliError::new(ErrorKind::Usage, "pages cannot be empty")),
+                    size => size,
+                };
+            }
+            "--depth" => {
+                let value = args.next().ok_or_else(|| missing_value("--depth"))?;
+                options.depth = match value.parse() {
+                    Ok(0) | Err(_) => {
+                        return Err(CliError::new(
+                            ErrorKind::Usage,
+                            format!("invalid namespace depth: {value}"),
+                        ))
+                    }
+                    Ok(depth) => depth,
+                };
+            }
+            "--bands" => {
+                let value = args.next().ok_or_else(|| missing_value("--bands"))?;
+                options.bands = match value.parse() {
+                    Ok(0) | Err(_) => {
+                        return Err(CliError::new(
+                            ErrorKind::Usage,
+                            format!("invalid number of bands: {value}"),
+                        ))
+                    }
+                    Ok(bands) => bands,
+                };
+            }
+            "--min-size" => {
+                let value = args.next().ok_or_else(|| missing_value("--min-size"))?;
+                options.min_blob_size = parse_size(value)?;
+            }
+            "--against" => {
+                let value = args.next().ok_or_else(|| missing_value("--against"))?;
+                options.against = Some(value.clone());
+            }
+            "--max-move" => {
+                let value = args.next().ok_or_else(|| missing_value("--max-move"))?;
+                options.thresholds.max_move = parse_size(value)?;
+            }
+            "--max-growth" => {
+                let value = args.next().ok_or_else(|| missing_value("--max-growth"))?;
+                options.thresholds.max_growth = parse_size(value)?;
+            }
+            "--partitions" => {
+                let value = args.next().ok_or_else(|| missing_value("--partitions"))?;
+                options.partitions = Some(value.clone());
+            }
+            "--flash-base" => {
+                let value = args.next().ok_or_else(|| missing_value("--flash-base"))?;
+                options.flash_base = parse_u64(value)?;
+            }
+            "--flash-size" => {
+                let value = args.next().ok_or_else(|| missing_value("--flash-size"))?;
+                options.flash_size = Some(parse_size(value)?);
+            }
+            "--component" => {
+                let value = args.next().ok_or_else(|| missing_value("--component"))?;
+                components.push(value);
+            }
+            "--label" => {
+                let value = args.next().ok_or_else(|| missing_value("--label"))?;
+                options.label = Some(value.clone());
+            }
+            "--last" => {
+                let value = args.next().ok_or_else(|| missing_value("--last"))?;
+                options.last = match value.parse() {
+                    Ok(0) | Err(_) => {
+                        return Err(CliError::new(
+                            ErrorKind::Usage,
+                            format!("invalid number of builds: {value}"),
+                        ))
+                    }
+                    Ok(last) => Some(last),
+                };
+            }
+            "--su" => {
+                let value = args.next().ok_or_else(|| missing_value("--su"))?;
+                options.su.push(value.clone());
+            }
+            "--system-map" => {
+                let value = args.next().ok_or_else(|| missing_value("--system-map"))?;
+                options.system_map = Some(value.clone());
+            }
+            "--abi" => {
+                let value = args.next().ok_or_else(|| missing_value("--abi"))?;
+                options.abi = Some(value.clone());
+            }
+            "--include" => {
+                let value = args.next().ok_or_else(|| missing_value("--include"))?;
+                options.filter = std::mem::take(&mut options.filter).include(value);
+            }
+            "--exclude" => {
+                let value = args.next().ok_or_else(|| missing_value("--exclude"))?;
+                options.filter = std::mem::take(&mut options.filter).exclude(value);
+            }
+            "--window" => {
+                let value = args.next().ok_or_else(|| missing_value("--window"))?;
+                let (start, end) = value.split_once("..").ok_or_else(|| {
+                    CliError::new(ErrorKind::Usage, format!("invalid window: {value}"))
+                })?;
+                options.window = Some(parse_u64(start)?..parse_u64(end)?);
+            }
+            "--disassembly" => {
+                let value = args.next().ok_or_else(|| missing_value("--disassembly"))?;
+                options.disassembly = Some(value.clone());
+            }
+            "--objdump" => {
+                let value = args.next().ok_or_else(|| missing_value("--objdump"))?;
+                options.objdump = Some(value.clone());
+            }
+            "--hot" => {
+                let value = args.next().ok_or_else(|| missing_value("--hot"))?;
+                options.hot = Some(value.clone());
+            }
+            "--order-file" => {
+                let value = args.next().ok_or_else(|| missing_value("--order-file"))?;
+                options.order_file = Some(value.clone());
+            }
+            "--load-bias" => {
+                let value = args.next().ok_or_else(|| missing_value("--load-bias"))?;
+                options.load_bias = parse_u64(value)?;
+            }
+            "--codeowners" => {
+                let value = args.next().ok_or_else(|| missing_value("--codeowners"))?;
+                options.codeowners = Some(value.clone());
+            }
+            "--compile-commands" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| missing_value("--compile-commands"))?;
+                options.compile_commands = Some(value.clone());
+            }
+            "--git" => {
+                let value = args.next().ok_or_else(|| missing_value("--git"))?;
+                let Some((old, new)) = value
+                    .split_once("..")
+                    .filter(|(old, new)| !old.is_empty() && !new.is_empty())
+                else {
+                    return Err(CliError::new(
+                        ErrorKind::Usage,
+                        format!("invalid revision range: {value}"),
+                    ));
+                };
+                options.git_range = Some((old.to_string(), new.to_string()));
+            }
+            "--repository" => {
+                let value = args.next().ok_or_else(|| missing_value("--repository"))?;
+                options.repository = Some(value.clone());
+            }
+            "--deps" => {
+                let value = args.next().ok_or_else(|| missing_value("--deps"))?;
+                options.deps.push(value.clone());
+            }
+            "--dump-base" => {
+                let value = args.next().ok_or_else(|| missing_value("--dump-base"))?;
+                options.dump_base = Some(parse_u64(value)?);
+            }
+            "--module" => {
+                let value = args.next().ok_or_else(|| missing_value("--module"))?;
+                options.module = Some(value.clone());
+            }
+            "--path-separators" => {
+                options.path_separators = match args.next().map(String::as_str) {
+                    Some("keep") => PathSeparators::Keep,
+                    Some("slash") => PathSeparators::Slash,
+                    Some("backslash") => PathSeparators::Backslash,
+                    Some(other) => {
+                        return Err(CliError::new(
+                            ErrorKind::Usage,
+                            format!("unknown path separators: {other}"),
+                        ))
+                    }
+                    None => return Err(missing_value("--path-separators")),
+                }
+            }
+            "--sysroot" => {
+                let value = args.next().ok_or_else(|| missing_value("--sysroot"))?;
+                options.sysroot = Some(value.clone());
+            }
+            "--origin-naming" => {
+                options.origin_naming = match args.next().map(String::as_str) {
+                    Some("full") => OriginNaming::FullPath,
+                    Some("suffix") => OriginNaming::ShortestUniqueSuffix,
+                    Some("hash") => OriginNaming::HashSuffix,
+                    Some(other) => {
+                        return Err(CliError::new(
+                            ErrorKind::Usage,
+                            format!("unknown origin naming: {other}"),
+                        ))
+                    }
+                    None => return Err(missing_value("--origin-naming")),
+                }
+            }
+            "--rust-units" => {
+                options.rust_units = match args.next().map(String::as_str) {
+                    Some("keep") => RustUnits::Keep,
+                    Some("crate") => RustUnits::Crate,
+                    Some("module") => RustUnits::Module,
+                    Some(other) => {
+                        return Err(CliError::new(
+                            ErrorKind::Usage,
+                            format!("unknown Rust unit naming: {other}"),
+                        ))
+                    }
+                    None => return Err(missing_value("--rust-units")),
+                }
+            }
+            "--key" => {
+                options.key = match args.next().map(String::as_str) {
+                    Some("offset") => Address::FileOffset,
+                    Some("vma") => Address::Vma,
+                    Some("lma") => Address::Lma,
+                    Some("rva") => Address::Rva,
+                    Some(other) => {
+                        return Err(CliError::new(
+                            ErrorKind::Usage,
+                            format!("unknown key: {other}"),
+                        ))
+                    }
+                    None => return Err(missing_value("--key")),
+                }
+            }
+            "--vaddr-bias" => {
+                let value = args.next().ok_or_else(|| missing_value("--vaddr-bias"))?;
+                options.vaddr_bias = parse_i64(value)?;
+            }
+            "--no-color" => options.color = false,
+            "--warnings" => options.warnings = true,
+            "--granularities" => options.granularities = true,
+            "--by-language" => options.by_language = true,
+            "--runtime-files" => options.runtime_files = true,
+            "--by-component" => options.by_component = true,
+            "--raw" => options.raw = true,
+            "--record" => options.record = true,
+            "--help" | "-h" => {
+                options.help = true;
+                return Ok(options);
+            }
+            _ => options.positionals.push(arg.clone()),
+        }
+    }
+    // The component `history` shows, or the components of the image of `image`
+    if options.positionals.first().map(String::as_str) == Some("history") {
+        options.component = components.pop().cloned();
+    } else {
+        options.components = components
+            .into_iter()
+            .map(|value| parse_component(value))
+            .collect::<Result<_, _>>()?;
+    }
+    Ok(options)
+}
+
+/// The value of a hit, with its origin at every granularity for `--granularities`.
+fn hit_value(hit: &QueryHit, options: &Options) -> Value {
+    let mut value = hit.to_value();
+    if let (true, Value::Object(entries)) = (options.granularities, &mut value) {
+        entries.push(("granularities".to_string(), hit.granularities().to_value()));
+    }
+    value
+}
+
+fn missing_value(option: &str) -> CliError {
+    CliError::new(ErrorKind::Usage, format!("{option} expects a value"))
+}
+
+fn run(options: &Options) -> Result<(), CliError> {
+    let Some((command, args)) = options.positionals.split_first() else {
+        return Err(CliError::usage());
+    };
+    let unsupported = match options.output {
+        OutputFormat::Csv => (command != "matrix").then_some("csv"),
+        OutputFormat::OpenMetrics => (command != "report").then_some("openmetrics"),
+        // Renderers of tables only render reports
+        OutputFormat::Markdown | OutputFormat::Html
+            if matches!(
+                command.as_str(),
+                "query"
+                    | "explain"
+                    | "dump"
+                    | "whois"
+                    | "find"
+                    | "triage"
+                    | "image"
+                    | "lookup"
+                    | "check"
+            ) || (command == "uboot" && args.len() > 1)
+                || (command == "history" && options.record) =>
+        {
+            Some(if options.output == OutputFormat::Html {
+                "html"
+            } else {
+                "markdown"
+            })
+        }
+        _ => None,
+    };
+    if let Some(format) = unsupported {
+        return Err(CliError::new(
+            ErrorKind::Usage,
+            format!("{format} output is not supported by {command}"),
+        ));
+    }
+    match (command.as_str(), args) {
+        ("query", [binary, map, offsets @ ..]) if !offsets.is_empty() => {
+            let genealogy = load(binary, map, options)?;
+            let mut hits = vec![];
+            let mut missing = vec![];
+            for offset in offsets {
+                let offset = parse_u64(offset)?;
+                let hit_count = hits.len();
+                let found = genealogy.query_address((options.key)(offset))?;
+                hits.extend(found.into_iter().map(|hit| (offset, hit)));
+                if hits.len() == hit_count {
+                    missing.push(hex(offset));
+                }
+            }
+
+            if options.output == OutputFormat::Text {
+                let mut header = vec![(options.key)(0).key_space(), "range", "size", "file"];
+                let mut aligns = vec![Align::Left, Align::Left, Align::Right, Align::Left];
+                if options.granularities {
+                    header.extend(["crate", "component", "directory"]);
+                    aligns.extend([Align::Left; 3]);
+                }
+                let mut table = Table::new(&aligns).with_header(header);
+                table.set_color(options.color);
+                for (offset, hit) in &hits {
+                    let mut row = vec![
+                        hex(*offset),
+                        format!("{}..{}", hex(hit.range.start), hex(hit.range.end)),
+                        human_size(hit.range.end - hit.range.start),
+                        hit.filename.to_string(),
+                    ];
+                    if options.granularities {
+                        let granularities = hit.granularities();
+                        row.extend([
+                            granularities.crate_name.unwrap_or("-").to_string(),
+                            granularities.component.to_string(),
+                            granularities.directory.unwrap_or("-").to_string(),
+                        ]);
+                    }
+                    table.push_row(row);
+                }
+                if !table.is_empty() {
+                    println!("{table}");
+                }
+            } else {
+                let hits = hits.iter().map(|(offset, hit)| {
+                    Value::object([
+                        ("offset", (*offset).into()),
+                        ("hit", hit_value(hit, options)),
+                    ])
+                });
+                if options.output == OutputFormat::JsonLines {
+                    print_records(hits)?;
+                } else {
+                    let document = Value::object([("hits", Value::Array(hits.collect()))]);
+                    print_value(options.output, &document);
+                }
+            }
+
+            if missing.is_empty() {
+                Ok(())
+            } else {
+                Err(CliError::new(
+                    ErrorKind::NotFound,
+                    format!("no attribution for {}", missing.join(", ")),
+                ))
+            }
+        }
+        ("explain", [binary, map, offsets @ ..]) if !offsets.is_empty() => {
+            let binary = read(binary)?;
+            let mapfile = read_map(map)?;
+            let genealogy = build(&binary, &mapfile, options, false)?;
+            let mut explanations = vec![];
+            for offset in offsets {
+                explanations.push(genealogy.explain(&mapfile, parse_u64(offset)?));
+            }
+            match options.output {
+                OutputFormat::Text => {
+                    for (i, explanation) in explanations.iter().enumerate() {
+                        if i > 0 {
+                            println!();
+                        }
+                        print!("{explanation}");
+                    }
+                }
+                OutputFormat::JsonLines => {
+                    print_records(explanations.iter().map(ToValue::to_value))?
+                }
+                format => print_value(
+                    format,
+                    &Value::object([(
+                        "explanations",
+                        Value::Array(explanations.iter().map(ToValue::to_value).collect()),
+                    )]),
+                ),
+            }
+
+            let missing: Vec<String> = explanations
+                .iter()
+                .filter(|explanation| explanation.derivations.is_empty())
+                .map(|explanation| hex(explanation.point))
+                .collect();
+            if missing.is_empty() {
+                Ok(())
+            } else {
+                Err(CliError::new(
+                    ErrorKind::NotFound,
+                    format!("no attribution for {}", missing.join(", ")),
+                ))
+            }
+        }
+        ("serve", [binary, map]) => {
+            let binary = read(binary)?;
+            let mapfile = read_map(map)?;
+            let genealogy = build(&binary, &mapfile, options, false)?;
+            // JSON-RPC on stdin and stdout, whatever --output is
+            Server::new(&genealogy, &mapfile)
+                .serve(std::io::stdin().lock(), std::io::stdout().lock())?;
+            Ok(())
+        }
+        ("dump", [binary, map]) => {
+            let genealogy = load(binary, map, options)?;
+            let hits: Box<dyn Iterator<Item = QueryHit>> =
+                if options.filter == PathFilter::new() && options.window.is_none() {
+                    Box::new(genealogy.iter())
+                } else {
+                    let window = options.window.clone().unwrap_or(0..u64::MAX);
+                    Box::new(genealogy.contributions(&options.filter, window))
+                };
+            match options.output {
+                OutputFormat::Text => {
+                    for hit in hits {
+                        println!("{hit}");
+                    }
+                }
+                // Streamed straight from the index, without building the whole document
+                OutputFormat::JsonLines => {
+                    return print_records(hits.map(|hit| hit_value(&hit, options)))
+                }
+                format => {
+                    let intervals = hits.map(|hit| hit_value(&hit, options)).collect();
+                    print_value(
+                        format,
+                        &Value::object([("intervals", Value::Array(intervals))]),
+                    );
+                }
+            }
+            Ok(())
+        }
+        ("report", [binary_path, map]) => {
+            let binary = read(binary_path)?;
+            let binary_name = Path::new(binary_path)
+                .file_name()
+                .map_or(binary_path.into(), |name| name.to_string_lossy());
+            let genealogy = build(&binary, &read_map(map)?, options, false)?;
+            let mut report = size_report(&genealogy, &binary, options)?;
+            let commands = match &options.compile_commands {
+                Some(path) => Some(CompileCommands::parse(&read_to_string(path)?)?),
+                None => None,
+            };
+            if let Some(commands) = &commands {
+                report.annotate_sources(commands);
+            }
+            if let Some(codeowners) = &options.codeowners {
+                let commands = commands
+                    .as_ref()
+                    .ok_or_else(|| missing_value("--compile-commands"))?;
+                let owners = report.by_owner(
+                    commands,
+                    &CodeOwners::parse(&read_to_string(codeowners)?),
+                    &options
+                        .repository
+                        .clone()
+                        .unwrap_or_else(|| repository_of(codeowners)),
+                );
+                match options.output {
+                    OutputFormat::OpenMetrics => print!("{}", owners.to_openmetrics(&binary_name)),
+                    OutputFormat::JsonLines => {
+                        print_records(owners.entries.iter().map(ToValue::to_value))?
+                    }
+                    _ => print_report(&owners, options),
+                }
+            } else if !options.deps.is_empty() {
+                let mut dependencies = Dependencies::default();
+                for path in &options.deps {
+                    dependencies.extend(load_dependencies(path)?);
+                }
+                let headers = report.by_header(&dependencies);
+                match options.output {
+                    OutputFormat::OpenMetrics => {
+                        print!("{}", headers.to_openmetrics(&binary_name))
+                    }
+                    OutputFormat::JsonLines => {
+                        print_records(headers.entries.iter().map(ToValue::to_value))?
+                    }
+                    _ => print_report(&headers, options),
+                }
+            } else if options.by_language {
+                let languages = report.by_language();
+                match options.output {
+                    OutputFormat::OpenMetrics => {
+                        print!("{}", languages.to_openmetrics(&binary_name))
+                    }
+                    OutputFormat::JsonLines => {
+                        print_records(languages.entries.iter().map(ToValue::to_value))?
+                    }
+                    _ => print_report(&languages, options),
+                }
+            } else {
+                let files = if options.runtime_files {
+                    report.clone()
+                } else {
+                    report.fold_runtime()
+                };
+                match options.output {
+                    OutputFormat::OpenMetrics => print!("{}", files.to_openmetrics(&binary_name)),
+                    OutputFormat::JsonLines => {
+                        print_records(files.entries.iter().map(ToValue::to_value))?
+                    }
+                    _ => print_report(&files, options),
+                }
+            }
+
+            match options.budget {
+                Some(budget) if report.total > budget => Err(CliError::new(
+                    ErrorKind::BudgetExceeded,
+                    format!(
+                        "{} attributed, over the budget of {}",
+                        human_size(report.total),
+                        human_size(budget)
+                    ),
+                )),
+                _ => Ok(()),
+            }
+        }
+        ("diff", [binary_path, map_path]) if options.git_range.is_some() => {
+            let (old, new) = options.git_range.as_ref().expect("checked by the guard");
+            let repository = options.repository.as_deref().unwrap_or(".");
+            let revisions = git(repository, &["rev-parse", old, new])?;
+            let [old_id, new_id] = [0, 1].map(|i| revisions.lines().nth(i).unwrap_or_default());
+            let report = |id: &str| {
+                let path = |pattern: &str| {
+                    pattern
+                        .replace("{rev}", id)
+                        .replace("{short}", id.get(..7).unwrap_or(id))
+                };
+                load(&path(binary_path), &path(map_path), options)
+                    .map(|genealogy| genealogy.report())
+            };
+            let diff = report(old_id)?.diff(&report(new_id)?);
+            let mut arguments = GIT_LOG_ARGUMENTS.to_vec();
+            let range = format!("{old_id}..{new_id}");
+            arguments.push(&range);
+            let commits = parse_git_log(&git(repository, &arguments)?);
+            let commands = match &options.compile_commands {
+                Some(path) => Some(CompileCommands::parse(&read_to_string(path)?)?),
+                None => None,
+            };
+            let absolute = std::path::absolute(repository).unwrap_or_else(|_| repository.into());
+            let report = diff.by_commit(&commits, commands.as_ref(), &absolute.to_string_lossy());
+            match options.output {
+                OutputFormat::Text => print!("{report}"),
+                OutputFormat::JsonLines => {
+                    return print_records(report.entries.iter().map(ToValue::to_value))
+                }
+                _ => print_report(&report, options),
+            }
+            Ok(())
+        }
+        ("diff", [old_binary, old_map, new_binary, new_map]) => {
+            let old = load(old_binary, old_map, options)?.report();
+            let new = load(new_binary, new_map, options)?.report();
+            let diff = old.diff(&new);
+            match options.output {
+                OutputFormat::JsonLines => {
+                    return print_records(diff.entries.iter().map(ToValue::to_value))
+                }
+                _ => print_report(&diff, options),
+            }
+            Ok(())
+        }
+        ("deadstrip", [binary, map, stripped_binary, stripped_map]) => {
+            let genealogy = load(binary, map, options)?;
+            let stripped = load(stripped_binary, stripped_map, options)?;
+            let report = genealogy.dead_strip(&stripped);
+            match options.output {
+                OutputFormat::JsonLines => {
+                    return print_records(report.entries.iter().map(ToValue::to_value))
+                }
+                _ => print_report(&report, options),
+            }
+            Ok(())
+        }
+        ("reorder", [old_binary, old_map, new_binary, new_map]) => {
+            let old = load(old_binary, old_map, options)?;
+            let new = load(new_binary, new_map, options)?;
+            let report = old.reorder_report(&new);
+            match options.output {
+                OutputFormat::JsonLines => {
+                    return print_records(report.entries.iter().map(ToValue::to_value))
+                }
+                _ => print_report(&report, options),
+            }
+            Ok(())
+        }
+        ("why", [origin, binary, map]) => {
+            let mapfile = read_map(map)?;
+            let genealogy = build(&read(binary)?, &mapfile, options, false)?;
+            let members = ArchiveMembers::from_mapfile(&mapfile);
+            let report = genealogy.inclusion_report(&members, origin);
+            if report.steps.is_empty() {
+                return Err(CliError::new(
+                    ErrorKind::NotFound,
+                    format!("{origin} is not an archive member the map says was included"),
+                ));
+            }
+            match options.output {
+                OutputFormat::JsonLines => {
+                    return print_records(report.steps.iter().map(ToValue::to_value))
+                }
+                _ => print_report(&report, options),
+            }
+            Ok(())
+        }
+        ("whois", [symbol, binary, map]) => {
+            let binary = read(binary)?;
+            let mapfile = read_map(map)?;
+            let genealogy = build(&binary, &mapfile, options, true)?;
+            let hits = genealogy.lookup_symbol(symbol)?;
+            if hits.is_empty() {
+                return Err(CliError::new(
+                    ErrorKind::NotFound,
+                    format!("no symbol named {symbol}"),
+                ));
+            }
+
+            match options.output {
+                OutputFormat::Text => {
+                    for (i, hit) in hits.iter().enumerate() {
+                        if i > 0 {
+                            println!();
+                        }
+                        print_table(whois_table(hit), options);
+                    }
+                }
+                OutputFormat::JsonLines => print_records(hits.iter().map(ToValue::to_value))?,
+                format => print_value(
+                    format,
+                    &Value::object([("symbols", hits.as_slice().to_value())]),
+                ),
+            }
+            Ok(())
+        }
+        ("find", [pattern, binary, map]) => {
+            let pattern = Regex::new(pattern).map_err(|e| {
+                CliError::new(ErrorKind::Usage, format!("invalid regex {pattern}: {e}"))
+            })?;
+            let binary = read(binary)?;
+            let mapfile = read_map(map)?;
+            let genealogy = build(&binary, &mapfile, options, true)?;
+            let hits = genealogy.find_symbols(&pattern)?;
+            if hits.is_empty() {
+                return Err(CliError::new(
+                    ErrorKind::NotFound,
+                    format!("no symbol matches {pattern}"),
+                ));
+            }
+
+            match options.output {
+                OutputFormat::Text => {
+                    let mut table =
+                        Table::new(&[Align::Left, Align::Right, Align::Left, Align::Left])
+                            .with_header(["address", "size", "file", "symbol"]);
+                    for hit in &hits {
+                        table.push_row([
+                            hex(hit.symbol.vaddr),
+                            human_size(hit.symbol.size),
+                            hit.filename.unwrap_or("-").to_string(),
+                            match hit.symbol.aliases.len() {
+                                0 => hit.symbol.display_name().to_string(),
+                                1 => format!("{} (+1 alias)", hit.symbol.display_name()),
+                                aliases => {
+                                    format!("{} (+{aliases} aliases)", hit.symbol.display_name())
+                                }
+                            },
+                        ]);
+                    }
+                    print_table(table, options);
+                }
+                OutputFormat::JsonLines => print_records(hits.iter().map(ToValue::to_value))?,
+                format => print_value(
+                    format,
+                    &Value::object([("symbols", hits.as_slice().to_value())]),
+                ),
+            }
+            Ok(())
+        }
+        ("triage", [binary_path, map]) => {
+            let log_path = options.asan_log.as_ref().ok_or_else(CliError::usage)?;
+            let frames = parse_sanitizer_frames(&read_to_string(log_path)?);
+            let genealogy = load(binary_path, map, options)?;
+            let file_of = |frame: &Frame| {
+                frame
+                    .is_in_module(binary_path)
+                    .then(|| genealogy.query_vaddr_point(frame.vaddr()).next())
+                    .flatten()
+                    .map(|hit| hit.filename)
+            };
+
+            match options.output {
+                OutputFormat::Text => {
+                    let mut table =
+                        Table::new(&[Align::Right, Align::Left, Align::Left, Align::Left])
+                            .with_header(["frame", "pc", "function", "file"]);
+                    for frame in &frames {
+                        let file = match (file_of(frame), &frame.module) {
+                            (Some(file), _) => file.to_string(),
+                            (None, Some(module)) if !frame.is_in_module(binary_path) => {
+                                format!("({module})")
+                            }
+                            (None, _) => "-".to_string(),
+                        };
+                        table.push_row([
+                            format!("#{}", frame.index),
+                            hex(frame.pc),
+                            frame.function.clone().unwrap_or_else(|| "-".to_string()),
+                            file,
+                        ]);
+                    }
+                    print_table(table, options);
+                }
+                format => {
+                    let records = frames.iter().map(|frame| {
+                        Value::object([
+                            ("frame", frame.to_value()),
+                            ("file", file_of(frame).into()),
+                        ])
+                    });
+                    if format == OutputFormat::JsonLines {
+                        print_records(records)?;
+                    } else {
+                        let document = Value::object([("frames", Value::Array(records.collect()))]);
+                        print_value(format, &document);
+                    }
+                }
+            }
+            Ok(())
+        }
+        ("profile", [binary, map]) => {
+            let genealogy = load(binary, map, options)?;
+            let samples = samples(&genealogy, options)?.ok_or_else(CliError::usage)?;
+            let report = genealogy.attribute_samples(samples);
+            match options.output {
+                OutputFormat::JsonLines => {
+                    print_records(report.entries.iter().map(ToValue::to_value))?
+                }
+                _ => print_report(&report, options),
+            }
+            Ok(())
+        }
+        ("pages", [binary, map]) => {
+            let genealogy = load(binary, map, options)?;
+            let report = genealogy.page_report(options.page_size);
+            match options.output {
+                OutputFormat::Text => {
+                    print_table(report.to_table(), options);
+                    println!(
+                        "{} of {} pages are shared by several files",
+                        report.mixed,
+                        report.pages.len()
+                    );
+                }
+                OutputFormat::JsonLines => {
+                    print_records(report.pages.iter().map(ToValue::to_value))?
+                }
+                _ => print_report(&report, options),
+            }
+            Ok(())
+        }
+        ("partitions", [binary, map]) => {
+            let table = options.partitions.as_deref().ok_or_else(CliError::usage)?;
+            let table = PartitionTable::parse(&read_to_string(table)?)?;
+            let flash_size = options.flash_size.unwrap_or_else(|| table.end());
+            let flash = options.flash_base..options.flash_base.saturating_add(flash_size);
+            let report = load(binary, map, options)?.partition_usage(&table, flash);
+            match options.output {
+                OutputFormat::Text => {
+                    print_table(report.to_table(), options);
+                    if !report.offenders.is_empty() {
+                        println!();
+                        print_table(report.offenders_table(), options);
+                    }
+                }
+                OutputFormat::JsonLines => {
+                    print_records(report.offenders.iter().map(ToValue::to_value))?
+                }
+                _ => print_report(&report, options),
+            }
+
+            match report.offenders.len() {
+                0 => Ok(()),
+                offenders => Err(CliError::new(
+                    ErrorKind::OutOfPartition,
+                    format!("{offenders} ranges of content outside of their partition"),
+                )),
+            }
+        }
+        ("uboot", [dir, image @ ..]) if image.len() <= 1 => {
+            let path = |relative: &str| format!("{}/{relative}", dir.trim_end_matches('/'));
+            let mut stages = vec![];
+            let mut binaries = vec![];
+            for stage in Stage::ALL {
+                let (elf, map) = (path(stage.elf_path()), path(&stage.map_path()));
+                if Path::new(&elf).exists() && Path::new(&map).exists() {
+                    let binary = read(&elf)?;
+                    stages.push((stage, build(&binary, &read_map(&map)?, options, false)?));
+                    binaries.push(binary);
+                }
+            }
+            if stages.is_empty() {
+                return Err(CliError::new(
+                    ErrorKind::NotFound,
+                    format!("no U-Boot stage with its map in {dir}"),
+                ));
+            }
+
+            let [image] = image else {
+                let genealogies: Vec<(Stage, &Genealogy)> = stages
+                    .iter()
+                    .map(|(stage, genealogy)| (*stage, genealogy))
+                    .collect();
+                let report = stage_report(&genealogies);
+                match options.output {
+                    OutputFormat::JsonLines => print_records(report.file_values())?,
+                    _ => print_report(&report, options),
+                }
+                return Ok(());
+            };
+            let image_bytes = read(image)?;
+            let mut components = vec![];
+            for ((stage, genealogy), binary) in stages.into_iter().zip(&binaries) {
+                let raw = path(&stage.raw_path());
+                let offset = match Path::new(&raw).exists() {
+                    true => locate_stage(&image_bytes, &read(&raw)?),
+                    false => None,
+                };
+                let Some(offset) = offset else {
+                    let message = format!("{} ({raw}) is not in {image}", stage.name());
+                    print_warning(cli_warning("stage_not_in_image", message), options);
+                    continue;
+                };
+                components.push(Component {
+                    name: stage.name().to_string(),
+                    offset,
+                    placement: Placement::Raw,
+                    genealogy,
+                    binary,
+                });
+            }
+            let combined = CombinedImage::new(&image_bytes, components);
+            let summary = combined.summary();
+            let fit = fit_images(&image_bytes).unwrap_or_default();
+            match options.output {
+                OutputFormat::Text => {
+                    print_table(summary_table(&summary), options);
+                    if !fit.is_empty() {
+                        println!();
+                        print_table(fit_table(&fit), options);
+                    }
+                }
+                OutputFormat::JsonLines => print_records(summary.iter().map(ToValue::to_value))?,
+                format => print_value(
+                    format,
+                    &Value::object([
+                        ("components", summary.as_slice().to_value()),
+                        ("fit_images", fit.as_slice().to_value()),
+                    ]),
+                ),
+            }
+            Ok(())
+        }
+        ("android", [archive, maps @ ..]) => {
+            let bytes = read(archive)?;
+            let libraries: Vec<_> = native_libraries(&bytes)?
+                .into_iter()
+                .filter(|library| options.abi.as_ref().is_none_or(|abi| *abi == library.abi))
+                .collect();
+            if libraries.is_empty() {
+                return Err(CliError::new(
+                    ErrorKind::NotFound,
+                    format!("no native library in {archive}"),
+                ));
+            }
+            let mut genealogies: Vec<Option<Genealogy>> = libraries.iter().map(|_| None).collect();
+            for map in maps {
+                let matching: Vec<usize> = (0..libraries.len())
+                    .filter(|&index| libraries[index].matches_map(map))
+                    .collect();
+                match matching.as_slice() {
+                    [] => {
+                        let message = format!("{map} is not the map of a library of {archive}");
+                        print_warning(cli_warning("map_without_library", message), options);
+                    }
+                    &[index] => {
+                        let binary = extract(&bytes, &libraries[index].entry)?;
+                        genealogies[index] = Some(build(&binary, &read_map(map)?, options, false)?);
+                    }
+                    _ => {
+                        return Err(CliError::new(
+                            ErrorKind::Usage,
+                            format!(
+                                "{map} matches the libraries of several ABIs, pick one with --abi"
+                            ),
+                        ))
+                    }
+                }
+            }
+
+            let pairs: Vec<_> = libraries
+                .iter()
+                .zip(genealogies.iter().map(Option::as_ref))
+                .collect();
+            let report = app_report(&pairs);
+            match options.output {
+                OutputFormat::Text => {
+                    print_table(report.libraries_table(), options);
+                    println!();
+                    print_table(report.to_table(), options);
+                }
+                OutputFormat::JsonLines => {
+                    print_records(report.rows.iter().map(ToValue::to_value))?
+                }
+                _ => print_report(&report, options),
+            }
+            Ok(())
+        }
+        ("image", [image, offsets @ ..]) if !options.components.is_empty() => {
+            let binaries = options
+                .components
+                .iter()
+                .map(|component| read(&component.binary))
+                .collect::<Result<Vec<_>, CliError>>()?;
+            let placement = if options.raw {
+                Placement::Raw
+            } else {
+                Placement::File
+            };
+            let mut components = vec![];
+            for (spec, binary) in options.components.iter().zip(&binaries) {
+                components.push(Component {
+                    name: spec.name.clone(),
+                    offset: spec.offset,
+                    placement,
+                    genealogy: build(binary, &read_map(&spec.map)?, options, false)?,
+                    binary,
+                });
+            }
+            let image = read(image)?;
+            let combined = CombinedImage::new(&image, components);
+
+            if offsets.is_empty() {
+                let summary = combined.summary();
+                match options.output {
+                    OutputFormat::Text => print_table(summary_table(&summary), options),
+                    OutputFormat::JsonLines => {
+                        print_records(summary.iter().map(ToValue::to_value))?
+                    }
+                    format => print_value(
+                        format,
+                        &Value::object([("components", summary.as_slice().to_value())]),
+                    ),
+                }
+                return Ok(());
+            }
+
+            let mut hits = vec![];
+            let mut missing = vec![];
+            for offset in offsets {
+                let offset = parse_u64(offset)?;
+                let hit_count = hits.len();
+                hits.extend(combined.query_point(offset).map(|hit| (offset, hit)));
+                if hits.len() == hit_count {
+                    missing.push(hex(offset));
+                }
+            }
+            if options.output == OutputFormat::Text {
+                let mut table = Table::new(&[
+                    Align::Left,
+                    Align::Left,
+                    Align::Right,
+                    Align::Left,
+                    Align::Left,
+                ])
+                .with_header(["offset", "range", "size", "component", "file"]);
+                for (offset, hit) in &hits {
+                    table.push_row([
+                        hex(*offset),
+                        format!("{}..{}", hex(hit.range.start), hex(hit.range.end)),
+                        human_size(hit.range.end - hit.range.start),
+                        hit.component.to_string(),
+                        hit.filename.to_string(),
+                    ]);
+                }
+                if !table.is_empty() {
+                    print_table(table, options);
+                }
+            } else {
+                let hits = hits.iter().map(|(offset, hit)| {
+                    Value::object([("offset", (*offset).into()), ("hit", hit.to_value())])
+                });
+                if options.output == OutputFormat::JsonLines {
+                    print_records(hits)?;
+                } else {
+                    let document = Value::object([("hits", Value::Array(hits.collect()))]);
+                    print_value(options.output, &document);
+                }
+            }
+
+            if missing.is_empty() {
+                Ok(())
+            } else {
+                Err(CliError::new(
+                    ErrorKind::NotFound,
+                    format!("no attribution for {}", missing.join(", ")),
+                ))
+            }
+        }
+        ("vectors", [binary, map]) => {
+            let binary = read(binary)?;
+            let genealogy = build(&binary, &read_map(map)?, options, true)?;
+            let table = genealogy.vector_table(&binary)?;
+            match options.output {
+                OutputFormat::Text => {
+                    print_table(table.to_table(), options);
+                    let defaults = table.vectors.iter().filter(|v| v.default).count();
+                    println!(
+                        "{defaults} of {} vectors use a default handler",
+                        table.vectors.len()
+                    );
+                }
+                OutputFormat::JsonLines => {
+                    print_records(table.vectors.iter().map(ToValue::to_value))?
+                }
+                _ => print_report(&table, options),
+            }
+
+            match table.vectors.iter().filter(|v| v.is_invalid()).count() {
+                0 => Ok(()),
+                invalid => Err(CliError::new(
+                    ErrorKind::InvalidVectors,
+                    format!("{invalid} vectors in no file or without the Thumb bit"),
+                )),
+            }
+        }
+        ("stack", [binary, map]) if !options.su.is_empty() => {
+            let mut usage = StackUsage::default();
+            for path in &options.su {
+                usage.add_su(path, &read_to_string(path)?)?;
+            }
+            let genealogy = build(&read(binary)?, &read_map(map)?, options, true)?;
+            let report = genealogy.stack_report(&usage);
+            match options.output {
+                OutputFormat::Text => {
+                    print_table(report.to_table(), options);
+                    if !report.sections.is_empty() {
+                        println!();
+                        print_table(report.sections_table(), options);
+                    }
+                }
+                OutputFormat::JsonLines => {
+                    print_records(report.functions.iter().map(ToValue::to_value))?
+                }
+                _ => print_report(&report, options),
+            }
+            Ok(())
+        }
+        ("kernel", [binary, map]) => {
+            let system_map = match &options.system_map {
+                Some(path) => parse_system_map(&read_to_string(path)?)?,
+                None => vec![],
+            };
+            let genealogy = build(
+                &read(binary)?,
+                &read_map(map)?,
+                options,
+                system_map.is_empty(),
+            )?;
+            let report = genealogy.kernel_report(&system_map);
+            match options.output {
+                OutputFormat::JsonLines => {
+                    print_records(report.entries.iter().map(ToValue::to_value))?
+                }
+                _ => print_report(&report, options),
+            }
+            Ok(())
+        }
+        ("statics", [binary, map]) => {
+            let genealogy = build(&read(binary)?, &read_map(map)?, options, true)?;
+            let report = genealogy.static_report();
+            match options.output {
+                OutputFormat::Text => {
+                    print_table(report.to_table(), options);
+                    if !report.buffers.is_empty() {
+                        println!();
+                        print_table(report.buffers_table(BIGGEST_STATICS), options);
+                    }
+                }
+                OutputFormat::JsonLines => {
+                    print_records(report.buffers.iter().map(ToValue::to_value))?
+                }
+                _ => print_report(&report, options),
+            }
+            Ok(())
+        }
+        ("namespaces", [binary, map]) => {
+            let genealogy = build(&read(binary)?, &read_map(map)?, options, true)?;
+            let report = genealogy.namespace_report(options.depth)?;
+            match options.output {
+                OutputFormat::JsonLines => {
+                    print_records(report.entries.iter().map(ToValue::to_value))?
+                }
+                _ => print_report(&report, options),
+            }
+            Ok(())
+        }
+        ("annotate-disasm", [binary_path, map]) => {
+            let disassembly = match &options.disassembly {
+                Some(listing) => read_to_string(listing)?,
+                None => objdump(options.objdump.as_deref().unwrap_or("objdump"), binary_path)?,
+            };
+            let genealogy = build(&read(binary_path)?, &read_map(map)?, options, false)?;
+            let mut out = BufWriter::new(std::io::stdout().lock());
+            out.write_all(genealogy.annotate_disassembly(&disassembly).as_bytes())?;
+            Ok(out.flush()?)
+        }
+        ("layout", [binary, map, output]) => {
+            let binary = read(binary)?;
+            let genealogy = build(&binary, &read_map(map)?, options, false)?;
+            let layout = genealogy.layout(binary.len() as u64, options.bands);
+            std::fs::write(output, layout.to_svg())
+                .map_err(|error| CliError::new(ErrorKind::Io, format!("{output}: {error}")))?;
+            println!(
+                "{} regions of {} components, written to {output}",
+                layout.regions.len(),
+                layout.components.len()
+            );
+            Ok(())
+        }
+        ("blobs", [binary, map]) => {
+            let binary = read(binary)?;
+            let genealogy = build(&binary, &read_map(map)?, options, false)?;
+            let report = genealogy.blob_report(&binary, options.min_blob_size);
+            match options.output {
+                OutputFormat::JsonLines => {
+                    print_records(report.blobs.iter().map(ToValue::to_value))?
+                }
+                _ => print_report(&report, options),
+            }
+            Ok(())
+        }
+        ("hotcold", [binary, map]) => {
+            let hot = options.hot.as_deref().ok_or_else(CliError::usage)?;
+            let hot = read_to_string(hot)?;
+            let genealogy = build(&read(binary)?, &read_map(map)?, options, true)?;
+            let report = genealogy.hot_placement(parse_function_list(&hot))?;
+            match options.output {
+                OutputFormat::Text => {
+                    print_table(report.to_table(), options);
+                    if report.misplaced().next().is_some() {
+                        println!();
+                        print_table(report.misplaced_table(), options);
+                    }
+                    for name in &report.missing {
+                        let message = format!("no symbol named {name}");
+                        print_warning(cli_warning("missing_symbol", message), options);
+                    }
+                }
+                OutputFormat::JsonLines => {
+                    print_records(report.functions.iter().map(ToValue::to_value))?
+                }
+                _ => print_report(&report, options),
+            }
+
+            match report.misplaced().count() {
+                0 => Ok(()),
+                misplaced => Err(CliError::new(
+                    ErrorKind::Misplaced,
+                    format!(
+                        "{misplaced} of {} hot functions outside of the hot region",
+                        report.functions.len()
+                    ),
+                )),
+            }
+        }
+        ("locality", [binary, map]) => {
+            let genealogy = build(&read(binary)?, &read_map(map)?, options, true)?;
+            let report = genealogy.locality_report()?;
+            if let Some(path) = &options.order_file {
+                // The heaviest files of the profile when there is one, all of them otherwise
+                let files: Vec<String> = match samples(&genealogy, options)? {
+                    Some(samples) => genealogy
+                        .attribute_samples(samples)
+                        .entries
+                        .into_iter()
+                        .map(|entry| entry.filename)
+                        .collect(),
+                    None => report
+                        .files_by_address()
+                        .into_iter()
+                        .map(str::to_string)
+                        .collect(),
+                };
+                let ordering = genealogy.symbol_ordering(files.iter().map(String::as_str))?;
+                let mut contents = ordering.join("\n");
+                contents.push('\n');
+                std::fs::write(path, contents)
+                    .map_err(|error| CliError::new(ErrorKind::Io, format!("{path}: {error}")))?;
+            }
+            match options.output {
+                OutputFormat::JsonLines => {
+                    print_records(report.entries.iter().map(ToValue::to_value))?
+                }
+                _ => print_report(&report, options),
+            }
+            Ok(())
+        }
+        ("debuginfo", [binary_path, map]) => {
+            let binary = read(binary_path)?;
+            let genealogy = build(&binary, &read_map(map)?, options, false)?;
+            let report = genealogy.debug_report(&binary);
+            match options.output {
+                OutputFormat::Text => {
+                    print_table(report.to_table(), options);
+                    let debug_size: u64 = genealogy
+                        .sections()
+                        .iter()
+                        .filter(|section| is_debug_section(&section.name))
+                        .map(|section| section.size)
+                        .sum();
+                    println!(
+                        "{} of the {} of debug sections ({}) attributed",
+                        human_size(report.total),
+                        human_size(debug_size),
+                        percentage(report.total, debug_size)
+                    );
+                }
+                OutputFormat::JsonLines => {
+                    print_records(report.entries.iter().map(ToValue::to_value))?
+                }
+                _ => print_report(&report, options),
+            }
+            Ok(())
+        }
+        ("strip", [binary_path, map]) => {
+            let binary = read(binary_path)?;
+            let genealogy = build(&binary, &read_map(map)?, options, false)?;
+            let mut report = genealogy.strip_report(&binary)?;
+            if options.by_component {
+                report = report.by_component();
+            }
+            match options.output {
+                OutputFormat::Text => {
+                    print_table(report.to_table(), options);
+                    println!(
+                        "stripped: {} of {}, {} ({}) removed",
+                        human_size(report.stripped_size()),
+                        human_size(report.file_size),
+                        human_size(report.strippable),
+                        percentage(report.strippable, report.file_size)
+                    );
+                }
+                OutputFormat::JsonLines => {
+                    print_records(report.entries.iter().map(ToValue::to_value))?
+                }
+                _ => print_report(&report, options),
+            }
+            Ok(())
+        }
+        ("quality", [binary_path, map]) => {
+            let binary = read(binary_path)?;
+            let genealogy = build(&binary, &read_map(map)?, options, false)?;
+            let quality = genealogy.attribution_quality(&binary)?;
+            match options.output {
+                OutputFormat::Text => {
+                    print_table(quality.to_table(), options);
+                    println!("quality: {:.1}%", quality.score());
+                }
+                OutputFormat::JsonLines => print_records([quality.to_value()])?,
+                _ => print_report(&quality, options),
+            }
+            match options.min_quality {
+                Some(min_quality) if quality.score() < min_quality => Err(CliError::new(
+                    ErrorKind::Degraded,
+                    format!(
+                        "attribution quality of {:.1}%, below the minimum of {min_quality}%",
+                        quality.score()
+                    ),
+                )),
+                _ => Ok(()),
+            }
+        }
+        ("unwind", [binary, map]) => {
+            let genealogy = load(binary, map, options)?;
+            let report = genealogy.unwind_report();
+            match options.output {
+                OutputFormat::Text => {
+                    print_table(report.to_table(), options);
+                    let total = genealogy.report().total;
+                    println!(
+                        "{} of {} attributed bytes ({}) are unwind tables",
+                        human_size(report.total),
+                        human_size(total),
+                        percentage(report.total, total)
+                    );
+                }
+                OutputFormat::JsonLines => {
+                    print_records(report.entries.iter().map(ToValue::to_value))?
+                }
+                _ => print_report(&report, options),
+            }
+            Ok(())
+        }
+        ("padding", [binary, map]) => {
+            let report = load(binary, map, options)?.padding_report();
+            match options.output {
+                OutputFormat::Text => {
+                    print_table(report.sections_table(), options);
+                    print_table(report.to_table(), options);
+                }
+                OutputFormat::JsonLines => {
+                    print_records(report.entries.iter().map(ToValue::to_value))?
+                }
+                _ => print_report(&report, options),
+            }
+            Ok(())
+        }
+        ("table", [binary, map, output]) => {
+            let table = ProvenanceTable::build(&load(binary, map, options)?);
+            std::fs::write(output, &table)
+                .map_err(|error| CliError::new(ErrorKind::Io, format!("{output}: {error}")))?;
+            let ranges = ProvenanceTable::parse(&table).map_or(0, |table| table.len());
+            println!(
+                "{ranges} ranges, {} written to {output}",
+                human_size(table.len() as u64)
+            );
+            Ok(())
+        }
+        #[cfg(feature = "mmap")]
+        ("lookup", [table, addresses @ ..]) if !addresses.is_empty() => {
+            let mapped = Genealogy::open_mmap(table)
+                .map_err(|error| CliError::new(ErrorKind::Io, format!("{table}: {error}")))?;
+            let mut hits = vec![];
+            let mut missing = vec![];
+            for address in addresses {
+                let address = parse_u64(address)?;
+                match mapped.lookup(address) {
+                    Some(filename) => hits.push((address, filename)),
+                    None => missing.push(hex(address)),
+                }
+            }
+            match options.output {
+                OutputFormat::Text => {
+                    let mut table =
+                        Table::new(&[Align::Left, Align::Left]).with_header(["address", "file"]);
+                    for (address, filename) in &hits {
+                        table.push_row([hex(*address), filename.to_string()]);
+                    }
+                    if !table.is_empty() {
+                        print_table(table, options);
+                    }
+                }
+                format => {
+                    let hits = hits.iter().map(|(address, filename)| {
+                        Value::object([
+                            ("address", (*address).into()),
+                            ("file", (*filename).into()),
+                        ])
+                    });
+                    if format == OutputFormat::JsonLines {
+                        print_records(hits)?;
+                    } else {
+                        print_value(
+                            format,
+                            &Value::object([("hits", Value::Array(hits.collect()))]),
+                        );
+                    }
+                }
+            }
+            if missing.is_empty() {
+                Ok(())
+            } else {
+                Err(CliError::new(
+                    ErrorKind::NotFound,
+                    format!("no attribution for {}", missing.join(", ")),
+                ))
+            }
+        }
+        ("snapshot", [binary, map, output]) => {
+            let snapshot = load(binary, map, options)?.snapshot();
+            std::fs::write(output, snapshot.to_json())
+                .map_err(|error| CliError::new(ErrorKind::Io, format!("{output}: {error}")))?;
+            match options.output {
+                OutputFormat::JsonLines => {
+                    print_records(snapshot.sections.iter().map(ToValue::to_value))?
+                }
+                _ => print_report(&snapshot, options),
+            }
+            Ok(())
+        }
+        ("hierarchy", [binary_path, map, output]) => {
+            let binary = read(binary_path)?;
+            let genealogy = build(&binary, &read_map(map)?, options, false)?;
+            let hierarchy = genealogy.hierarchy(&binary)?;
+            std::fs::write(output, hierarchy.to_json())
+                .map_err(|error| CliError::new(ErrorKind::Io, format!("{output}: {error}")))?;
+            match options.output {
+                OutputFormat::JsonLines => {
+                    print_records(hierarchy.segments.iter().map(ToValue::to_value))?
+                }
+                _ => print_report(&hierarchy, options),
+            }
+            Ok(())
+        }
+        ("check", [binary, map]) => {
+            let against = options.against.as_deref().ok_or_else(CliError::usage)?;
+            let snapshot = Snapshot::from_json(&read_to_string(against)?)?;
+            let current = load(binary, map, options)?.snapshot();
+            let drifts = snapshot.drifts(&current, options.thresholds);
+            match options.output {
+                OutputFormat::Text => {
+                    for drift in &drifts {
+                        println!("{drift}");
+                    }
+                }
+                OutputFormat::JsonLines => print_records(drifts.iter().map(ToValue::to_value))?,
+                format => print_value(
+                    format,
+                    &Value::object([("drifts", drifts.as_slice().to_value())]),
+                ),
+            }
+
+            match drifts.len() {
+                0 => Ok(()),
+                drifted => Err(CliError::new(
+                    ErrorKind::LayoutDrift,
+                    format!("{drifted} changes from the layout of {against}"),
+                )),
+            }
+        }
+        ("history", [history, binary_path, map]) if options.record => {
+            let binary = read(binary_path)?;
+            let genealogy = build(&binary, &read_map(map)?, options, false)?;
+            let timestamp = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map_or(0, |elapsed| elapsed.as_secs());
+            let recorded = Build::from_report(
+                &size_report(&genealogy, &binary, options)?,
+                timestamp,
+                options.label.clone(),
+            );
+            std::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(history)
+                .and_then(|mut file| file.write_all(recorded.to_json().as_bytes()))
+                .map_err(|error| CliError::new(ErrorKind::Io, format!("{history}: {error}")))?;
+            match options.output {
+                OutputFormat::Text => println!(
+                    "{} in {} components recorded in {history}",
+                    human_size(recorded.total),
+                    recorded.components.len()
+                ),
+                OutputFormat::JsonLines => print_records([recorded.to_value()])?,
+                format => print_value(format, &recorded.to_value()),
+            }
+            Ok(())
+        }
+        ("history", [history]) if !options.record => {
+            let history = History::parse(&read_to_string(history)?)?;
+            let trend = history.trend(options.component.as_deref(), options.last);
+            match options.output {
+                OutputFormat::JsonLines => {
+                    print_records(trend.entries.iter().map(ToValue::to_value))?
+                }
+                _ => print_report(&trend, options),
+            }
+            Ok(())
+        }
+        ("memdump", [dump, binary, map]) => {
+            let dump_base = options.dump_base.ok_or_else(CliError::usage)?;
+            let dump_len = std::fs::metadata(dump)
+                .map_err(|e| CliError::new(ErrorKind::Io, format!("cannot read {dump}: {e}")))?
+                .len();
+            let genealogy = load(binary, map, options)?;
+            let report = genealogy.attribute_dump(
+                dump_base..dump_base.saturating_add(dump_len),
+                options.load_bias,
+            );
+            match options.output {
+                OutputFormat::Text => {
+                    print_table(report.to_table(), options);
+                    println!(
+                        "{} of {} attributed",
+                        human_size(report.report.total),
+                        human_size(dump_len)
+                    );
+                }
+                OutputFormat::JsonLines => {
+                    print_records(report.regions.iter().map(ToValue::to_value))?
+                }
+                _ => print_report(&report, options),
+            }
+            Ok(())
+        }
+        ("matrix", [binary, map]) => {
+            let matrix = load(binary, map, options)?.section_matrix();
+            match options.output {
+                OutputFormat::Csv => print!("{}", matrix.to_csv()),
+                OutputFormat::JsonLines => print_records(matrix.file_values())?,
+                _ => print_report(&matrix, options),
+            }
+            Ok(())
+        }
+        ("resolution", [binary, map]) => {
+            let mapfile = read_map(map)?;
+            let genealogy = build(&read(binary)?, &mapfile, options, true)?;
+            let report = genealogy.symbol_resolutions(&mapfile)?;
+            match options.output {
+                OutputFormat::JsonLines => {
+                    print_records(report.resolutions.iter().map(ToValue::to_value))?
+                }
+                _ => print_report(&report, options),
+            }
+            Ok(())
+        }
+        ("ctors", [binary_path, map]) => {
+            let binary = read(binary_path)?;
+            let genealogy = build(&binary, &read_map(map)?, options, false)?;
+            let report = genealogy.static_constructors(&binary)?;
+            match options.output {
+                OutputFormat::JsonLines => {
+                    print_records(report.constructors.iter().map(ToValue::to_value))?
+                }
+                _ => print_report(&report, options),
+            }
+            Ok(())
+        }
+        ("got", [binary_path, map]) => {
+            let binary = read(binary_path)?;
+            let mapfile = read_map(map)?;
+            let genealogy = build(&binary, &mapfile, options, false)?;
+            let report = genealogy.got_report(&binary, &mapfile)?;
+            match options.output {
+                OutputFormat::JsonLines => {
+                    print_records(report.slots.iter().map(ToValue::to_value))?
+                }
+                _ => print_report(&report, options),
+            }
+            Ok(())
+        }
+        ("exports", [binary_path, map]) => {
+            let binary = read(binary_path)?;
+            let genealogy = build(&binary, &read_map(map)?, options, false)?;
+            let report = genealogy.export_report(&binary)?;
+            match options.output {
+                OutputFormat::JsonLines => {
+                    print_records(report.symbols.iter().map(ToValue::to_value))?
+                }
+                _ => print_report(&report, options),
+            }
+            Ok(())
+        }
+        _ => Err(CliError::usage()),
+    }
+}
+
+/// The raw size report, or the compressed one with `--compress`.
+#[cfg_attr(not(feature = "compression"), allow(unused_variables))]
+fn size_report(
+    genealogy: &Genealogy,
+    binary: &[u8],
+    options: &Options,
+) -> Result<Report, CliError> {
+    #[cfg(feature = "compression")]
+    if let Some(compression) = options.compression {
+        return Ok(genealogy.compressed_report(binary, compression)?);
+    }
+    Ok(genealogy.report())
+}
+
+/// The samples of the profile given with `--wpa-csv` or `--callgrind`, if any.
+fn samples(genealogy: &Genealogy, options: &Options) -> Result<Option<Vec<Sample>>, CliError> {
+    let samples = match (&options.wpa_csv, &options.callgrind) {
+        (Some(csv_path), None) => parse_wpa_csv(
+            &read_to_string(csv_path)?,
+            options.module.as_deref(),
+            genealogy.image_base(),
+        )?,
+        (None, Some(callgrind_path)) => parse_callgrind(
+            &read_to_string(callgrind_path)?,
+            options.event.as_deref(),
+            options.module.as_deref(),
+            options.load_bias,
+        )?,
+        (None, None) => return Ok(None),
+        (Some(_), Some(_)) => return Err(CliError::usage()),
+    };
+    Ok(Some(samples))
+}
+
+/// A Ninja dependency log or a Make depfile, told apart by the signature of the former.
+fn load_dependencies(path: &str) -> Result<Dependencies, CliError> {
+    let bytes = read(path)?;
+    if bytes.starts_with(b"# ninjadeps\n") {
+        Ok(Dependencies::parse_ninja_deps(&bytes)?)
+    } else {
+        Ok(Dependencies::parse_depfile(&String::from_utf8_lossy(
+            &bytes,
+        )))
+    }
+}
+
+/// The repository a CODEOWNERS file applies to, from where GitHub and GitLab look for it.
+fn repository_of(codeowners: &str) -> String {
+    let path = std::path::absolute(codeowners).unwrap_or_else(|_| codeowners.into());
+    let directory = path.parent().unwrap_or(&path);
+    let repository = match directory.file_name().and_then(|name| name.to_str()) {
+        Some(".github" | ".gitlab" | "docs") => directory.parent().unwrap_or(directory),
+        _ => directory,
+    };
+    repository.to_string_lossy().into_owned()
+}
+
+fn whois_table(hit: &SymbolHit) -> Table {
+    let symbol = hit.symbol;
+    let range = |start: u64| format!("{}..{}", hex(start), hex(start.saturating_add(symbol.size)));
+    let origin = hit.filename.map(Origin::parse);
+
+    let mut table = Table::new(&[Align::Left, Align::Left]);
+    table.push_row(["symbol", symbol.display_name()]);
+    if symbol.demangled.is_some() {
+        table.push_row(["mangled", symbol.name.as_str()]);
+    }
+    if !symbol.aliases.is_empty() {
+        table.push_row(["aliases".to_string(), symbol.aliases.join(", ")]);
+    }
+    table.push_row([
+        "address".to_string(),
+        format!("{} ({})", range(symbol.vaddr), human_size(symbol.size)),
+    ]);
+    table.push_row([
+        "offset".to_string(),
+        symbol.file_offset.map_or("-".to_string(), range),
+    ]);
+    table.push_row(["section", symbol.section.as_deref().unwrap_or("-")]);
+    table.push_row(["file", hit.filename.unwrap_or("-")]);
+    table.push_row(["archive", origin.and_then(|o| o.archive).unwrap_or("-")]);
+    table
+}
+
+fn print_table(mut table: Table, options: &Options) {
+    table.set_color(options.color);
+    println!("{table}");
+}
+
+fn print_value(format: OutputFormat, value: &Value) {
+    match format {
+        OutputFormat::Json => println!("{}", value.to_json()),
+        OutputFormat::Yaml => print!("{}", value.to_yaml()),
+        OutputFormat::Markdown | OutputFormat::Html => {
+            unreachable!("markdown and html output are only supported by reports")
+        }
+        OutputFormat::Text
+        | OutputFormat::JsonLines
+        | OutputFormat::Csv
+        | OutputFormat::OpenMetrics => {
+            unreachable!("text, jsonl, csv and openmetrics output are rendered by each command")
+        }
+    }
+}
+
+/// Prints a report with the renderer of `--output`.
+fn print_report(report: &dyn Renderable, options: &Options) {
+    let renderer: Box<dyn ReportRenderer> = match options.output {
+        OutputFormat::Text => Box::new(TextRenderer {
+            color: options.color,
+        }),
+        OutputFormat::Json => Box::new(JsonRenderer),
+        OutputFormat::Yaml => Box::new(YamlRenderer),
+        OutputFormat::Markdown => Box::new(MarkdownRenderer),
+        OutputFormat::Html => Box::new(HtmlRenderer),
+        OutputFormat::JsonLines | OutputFormat::Csv | OutputFormat::OpenMetrics => {
+            unreachable!("jsonl, csv and openmetrics output are rendered by each command")
+        }
+    };
+    print!("{}", renderer.render(report));
+}
+
+/// Writes one compact JSON document per line, as they are produced.
+fn print_records(records: impl IntoIterator<Item = Value>) -> Result<(), CliError> {
+    let mut out = BufWriter::new(std::io::stdout().lock());
+    for record in records {
+        writeln!(out, "{}", record.to_json())?;
+    }
+    Ok(out.flush()?)
+}
+
+/// Disassembles `binary` with `tool -d`, failing with its error message.
+fn objdump(tool: &str, binary: &str) -> Result<String, CliError> {
+    let output = Command::new(tool)
+        .arg("-d")
+        .arg(binary)
+        .output()
+        .map_err(|e| CliError::new(ErrorKind::Io, format!("cannot run {tool}: {e}")))?;
+    if !output.status.success() {
+        return Err(CliError::new(
+            ErrorKind::Io,
+            format!(
+                "{tool} -d {binary}: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ),
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Runs git in `repository`, failing with its error message.
+fn git(repository: &str, arguments: &[&str]) -> Result<String, CliError> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(repository)
+        .args(arguments)
+        .output()
+        .map_err(|e| CliError::new(ErrorKind::Io, format!("cannot run git: {e}")))?;
+    if !output.status.success() {
+        return Err(CliError::new(
+            ErrorKind::NotFound,
+            format!(
+                "git {}: {}",
+                arguments.join(" "),
+                String::from_utf8_lossy(&output.stderr).trim()
+            ),
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+fn read(path: &str) -> Result<Vec<u8>, CliError> {
+    std::fs::read(path)
+        .map_err(|e| CliError::new(ErrorKind::Io, format!("cannot read {path}: {e}")))
+}
+
+fn read_to_string(path: &str) -> Result<String, CliError> {
+    std::fs::read_to_string(path)
+        .map_err(|e| CliError::new(ErrorKind::Io, format!("cannot read {path}: {e}")))
+}
+
+/// Reads a map file, with its line endings normalized for the parsers and the commands reading
+/// it besides the genealogy.
+fn read_map(path: &str) -> Result<String, CliError> {
+    let mapfile = read_to_string(path)?;
+    Ok(match normalize_line_endings(&mapfile) {
+        Cow::Owned(normalized) => normalized,
+        Cow::Borrowed(_) => mapfile,
+    })
+}
+
+fn load(binary_path: &str, map_path: &str, options: &Options) -> Result<Genealogy, CliError> {
+    let binary = read(binary_path)?;
+    let mapfile = read_map(map_path)?;
+    build(&binary, &mapfile, options, false)
+}
+
+fn build(
+    binary: &[u8],
+    mapfile: &str,
+    options: &Options,
+    symbols: bool,
+) -> Result<Genealogy, CliError> {
+    let mut builder = Genealogy::builder(mapfile, binary)
+        .symbols(symbols)
+        .path_separators(options.path_separators)
+        .origin_naming(options.origin_naming)
+        .rust_units(options.rust_units)
+        .vaddr_bias(options.vaddr_bias)
+        .threads(options.threads)
+        .memory_limit(options.memory_limit);
+    if let Some(sysroot) = &options.sysroot {
+        builder = builder.sysroot(sysroot);
+    }
+    let genealogy = builder.build()?;
+    let warnings = genealogy.warnings();
+    if options.warnings {
+        for warning in warnings {
+            print_warning(warning.to_value(), options);
+        }
+    } else if !warnings.is_empty() && !options.json_errors {
+        eprintln!(
+            "warning: {} issues found while mapping, see --warnings",
+            warnings.len()
+        );
+    }
+    Ok(genealogy)
+}
+
+/// Prints `warning`, a value with a `message`, on stderr: as a `{"warning": …}` object with
+/// `--error-format json`, else its message.
+fn print_warning(warning: Value, options: &Options) {
+    if options.json_errors {
+        eprintln!("{}", Value::object([("warning", warning)]).to_json());
+    } else if let Some(message) = warning.get("message").and_then(Value::as_str) {
+        eprintln!("warning: {message}");
+    }
+}
+
+/// A warning of the command line tool rather than of a build, see [`print_warning`].
+fn cli_warning(kind: &str, message: String) -> Value {
+    Value::object([("kind", kind.into()), ("message", message.into())])
+}
+
+/// Parses a decimal or `0x`-prefixed hexadecimal number.
+fn parse_u64(value: &str) -> Result<u64, CliError> {
+    let parsed = match value
+        .strip_prefix("0x")
+        .or_else(|| value.strip_prefix("0X"))
+    {
+        Some(hex) => u64::from_str_radix(hex, 16),
+        None => value.parse(),
+    };
+    parsed.map_err(|_| CliError::new(ErrorKind::Usage, format!("invalid number: {value}")))
+}
+
+/// Parses a [`parse_u64`] number with an optional `-` sign.
+fn parse_i64(value: &str) -> Result<i64, CliError> {
+    let (negative, magnitude) = match value.strip_prefix('-') {
+        Some(magnitude) => (true, magnitude),
+        None => (false, value),
+    };
+    let magnitude = parse_u64(magnitude)?;
+    let parsed = if negative {
+        0i64.checked_sub_unsigned(magnitude)
+    } else {
+        i64::try_from(magnitude).ok()
+    };
+    parsed.ok_or_else(|| CliError::new(ErrorKind::Usage, format!("number out of range: {value}")))
+}
+
+/// Parses a number of bytes with an optional binary `K`, `M` or `G` suffix.
+fn parse_size(value: &str) -> Result<u64, CliError> {
+    let (number, multiplier) = match value.char_indices().last() {
+        Some((i, 'k' | 'K')) => (&value[..i], 1 << 10),
+        Some((i, 'm' | 'M')) => (&value[..i], 1 << 20),
+        Some((i, 'g' | 'G')) => (&value[..i], 1 << 30),
+        _ => (value, 1),
+    };
+    parse_u64(number)?
+        .checked_mul(multiplier)
+        .ok_or_else(|| CliError::new(ErrorKind::Usage, format!("size too large: {value}")))
+}
+
+/// Parses a percentage from 0 to 100, with an optional `%` suffix.
+fn parse_percentage(value: &str) -> Result<f64, CliError> {
+    value
+        .strip_suffix('%')
+        .unwrap_or(value)
+        .parse::<f64>()
+        .ok()
+        .filter(|percentage| (0.0..=100.0).contains(percentage))
+        .ok_or_else(|| CliError::new(ErrorKind::Usage, format!("invalid percentage: {value}")))
+}
+
+/// Parses `<name>,<offset>,<binary>,<map>`, the map path may contain commas.
+fn parse_component(value: &str) -> Result<ComponentSpec, CliError> {
+    let invalid = || CliError::new(ErrorKind::Usage, format!("invalid component: {value}"));
+    let mut fields = value.splitn(4, ',');
+    let mut next = || {
+        fields
+            .next()
+            .filter(|field| !field.is_empty())
+            .ok_or_else(invalid)
+    };
+    Ok(ComponentSpec {
+        name: next()?.to_string(),
+        offset: parse_u64(next()?)?,
+        binary: next()?.to_string(),
+        map: next()?.to_string(),
+    })
+}
+
+/// Parses `<codec>[:<level>]`.
+#[cfg(feature = "compression")]
+fn parse_compression(value: &str) -> Result<Compression, CliError> {
+    let (codec, level) = value
+        .split_once(':')
+        .map_or((value, None), |(c, l)| (c, Some(l)));
+    let codec: Codec = codec
+        .parse()
+        .map_err(|()| CliError::new(ErrorKind::Usage, format!("unknown codec: {codec}")))?;
+    let level = match level {
+        Some(level) => level
+            .parse()
+            .map_err(|_| CliError::new(ErrorKind::Usage, format!("invalid level: {level}")))?,
+        None => codec.default_level(),
+    };
+    Compression::new(codec, level).ok_or_else(|| {
+        let levels = codec.levels();
+        CliError::new(
+            ErrorKind::Usage,
+            format!(
+                "{codec} levels go from {} to {}",
+                levels.start(),
+                levels.end()
+            ),
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{parse_i64, parse_options, parse_size, run, ErrorKind};
+
+    fn arguments(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size("4096").unwrap(), 4096);
+        assert_eq!(parse_size("0x10K").unwrap(), 16 * 1024);
+        assert_eq!(parse_size("2M").unwrap(), 2 * 1024 * 1024);
+        assert_eq!(parse_size("1.5M").unwrap_err().kind, ErrorKind::Usage);
+    }
+
+    #[test]
+    fn test_parse_i64() {
+        assert_eq!(parse_i64("-0x1000").unwrap(), -0x1000);
+        assert_eq!(parse_i64("-0x8000000000000000").unwrap(), i64::MIN);
+        assert!(parse_i64("0x8000000000000000").is_err());
+    }
+
+    #[test]
+    fn test_report_budget_jsonl() {
+        let args = arguments(&[
+            "report",
+            "tests/gcc/a.out",
+            "tests/gcc/out.map",
+            "--budget",
+            "10",
+            "--output",
+            "jsonl",
+        ]);
+        let error = run(&parse_options(&args).unwrap()).unwrap_err();
+        assert_eq!(error.kind, ErrorKind::BudgetExceeded);
+        assert_eq!(error.kind.exit_code(), 5);
+    }
+
+    #[test]
+    fn test_component_by_subcommand() {
+        let history = ["history", "--component", "libcrypto.a,v2", "builds.jsonl"];
+        let options = parse_options(&arguments(&history)).unwrap();
+        assert_eq!(options.component.as_deref(), Some("libcrypto.a,v2"));
+        assert!(options.components.is_empty());
+
+        let image = ["--component", "app", "image", "flash.bin"];
+        let error = parse_options(&arguments(&image)).err().unwrap();
+        assert_eq!(error.kind, ErrorKind::Usage);
+        let image = [
+            "image",
+            "--component",
+            "app,0x1000,app.elf,app.map",
+            "flash.bin",
+        ];
+        let options = parse_options(&arguments(&image)).unwrap();
+        assert_eq!(options.components[0].offset, 0x1000);
+        assert_eq!(options.component, None);
+    }
+
+    #[test]
+    fn test_help() {
+        // Not an error, whatever follows it
+        let options = parse_options(&arguments(&["--budget", "10", "--help", "--last", "0"]));
+        assert!(options.ok().unwrap().help);
+    }
 }
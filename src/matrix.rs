@@ -0,0 +1,182 @@
+//! Bytes per file and per output section, to pivot on (e.g. `.rodata` per library) without
+//! querying the genealogy again.
+
+use std::{collections::HashMap, fmt::Display};
+
+use crate::{
+    format::{human_size, Align, Table},
+    Genealogy,
+};
+
+/// A files × sections table of byte counts, see [`Genealogy::section_matrix`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SectionMatrix {
+    /// Output sections in map order, including empty ones.
+    pub sections: Vec<String>,
+    /// Biggest files first.
+    pub rows: Vec<MatrixRow>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MatrixRow {
+    pub filename: String,
+    /// One cell per entry of [`SectionMatrix::sections`].
+    pub sizes: Vec<u64>,
+}
+
+impl MatrixRow {
+    pub fn total(&self) -> u64 {
+        self.sizes.iter().sum()
+    }
+}
+
+impl Genealogy {
+    /// Sums the size of every subsection of the map per file and output section.
+    ///
+    /// Unlike [`Genealogy::report`], sections without file contents such as `.bss` are counted
+    /// too, so that memory usage can be broken down as well.
+    pub fn section_matrix(&self) -> SectionMatrix {
+        let sections: Vec<String> = self
+            .sections()
+            .iter()
+            .filter(|section| !section.name.is_empty())
+            .map(|section| section.name.clone())
+            .fold(vec![], |mut names, name| {
+                if !names.contains(&name) {
+                    names.push(name);
+                }
+                names
+            });
+        let columns: HashMap<&str, usize> = sections
+            .iter()
+            .enumerate()
+            .map(|(i, name)| (name.as_str(), i))
+            .collect();
+
+        let mut rows: HashMap<&str, Vec<u64>> = HashMap::new();
+        for section in self.sections() {
+            let Some(&column) = columns.get(section.name.as_str()) else {
+                continue;
+            };
+            for subsection in &section.subsections {
+                let sizes = rows
+                    .entry(subsection.filename.as_str())
+                    .or_insert_with(|| vec![0; sections.len()]);
+                sizes[column] += subsection.size;
+            }
+        }
+
+        let mut rows: Vec<MatrixRow> = rows
+            .into_iter()
+            .map(|(filename, sizes)| MatrixRow {
+                filename: filename.to_string(),
+                sizes,
+            })
+            .collect();
+        rows.sort_by(|a, b| {
+            b.total()
+                .cmp(&a.total())
+                .then_with(|| a.filename.cmp(&b.filename))
+        });
+
+        SectionMatrix { sections, rows }
+    }
+}
+
+impl SectionMatrix {
+    /// Renders the matrix as CSV with a `file` column, one column per section, and a `total`
+    /// column. Sizes are in bytes.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::new();
+        let header = std::iter::once("file")
+            .chain(self.sections.iter().map(String::as_str))
+            .chain(std::iter::once("total"))
+            .map(csv_field)
+            .collect::<Vec<_>>()
+            .join(",");
+        csv.push_str(&header);
+        csv.push('\n');
+        for row in &self.rows {
+            csv.push_str(&csv_field(&row.filename));
+            for size in row.sizes.iter().chain(std::iter::once(&row.total())) {
+                csv.push(',');
+                csv.push_str(&size.to_string());
+            }
+            csv.push('\n');
+        }
+        csv
+    }
+
+    /// The table rendered by `Display`, empty cells are left blank.
+    pub fn to_table(&self) -> Table {
+        let mut aligns = vec![Align::Right; self.sections.len() + 1];
+        aligns.push(Align::Left);
+        let mut table = Table::new(&aligns).with_header(
+            self.sections
+                .iter()
+                .map(String::as_str)
+                .chain(["total", "file"]),
+        );
+        for row in &self.rows {
+            table.push_row(
+                row.sizes
+                    .iter()
+                    .map(|&size| {
+                        if size == 0 {
+                            String::new()
+                        } else {
+                            human_size(size)
+                        }
+                    })
+                    .chain([human_size(row.total()), row.filename.clone()]),
+            );
+        }
+        table
+    }
+}
+
+impl Display for SectionMatrix {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_table())
+    }
+}
+
+/// Quotes a CSV field when it contains a separator, a quote or a line break.
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Genealogy;
+
+    use super::csv_field;
+
+    #[test]
+    fn test_section_matrix() {
+        assert_eq!(csv_field("simple.o"), "simple.o");
+        assert_eq!(csv_field("a,\"b\""), "\"a,\"\"b\"\"\"");
+
+        let mapfile = std::fs::read_to_string("tests/gcc/out.map").unwrap();
+        let binary = std::fs::read("tests/gcc/a.out").unwrap();
+        let matrix = Genealogy::new(&mapfile, &binary).unwrap().section_matrix();
+
+        let text = matrix.sections.iter().position(|s| s == ".text").unwrap();
+        let simple = matrix
+            .rows
+            .iter()
+            .find(|row| row.filename == "simple.o")
+            .unwrap();
+        assert!(simple.sizes[text] >= 71);
+
+        let csv = matrix.to_csv();
+        let mut lines = csv.lines();
+        let header = lines.next().unwrap();
+        assert!(header.starts_with("file,") && header.ends_with(",total"));
+        assert_eq!(lines.count(), matrix.rows.len());
+    }
+}
@@ -0,0 +1,150 @@
+//! Attribution of memory dumps of a running process, whose image was loaded at another address
+//! than the one it was linked at (ASLR).
+
+use std::{fmt::Display, ops::Range};
+
+use crate::{
+    format::{hex, human_size, Align, Table},
+    Genealogy, Report,
+};
+
+/// A run of dump bytes that come from the same file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DumpRegion {
+    /// Offsets in the dump.
+    pub dump_range: Range<u64>,
+    /// Link-time virtual addresses.
+    pub vaddr_range: Range<u64>,
+    pub filename: String,
+}
+
+/// The regions of a dump and their size per file, see [`Genealogy::attribute_dump`].
+#[derive(Clone, Debug)]
+pub struct DumpReport {
+    /// In dump order.
+    pub regions: Vec<DumpRegion>,
+    pub report: Report,
+    /// Dump bytes not attributed by the map, e.g. heap, stacks or other modules.
+    pub unattributed: u64,
+}
+
+impl Genealogy {
+    /// Attributes the bytes of a dump covering the runtime addresses `dump`, for an image loaded
+    /// `load_bias` bytes after its link-time addresses. The bias is the load address for position
+    /// independent ELF files, and the load address minus [`Genealogy::image_base`] for PE files.
+    ///
+    /// Overlapping subsections give overlapping regions, which are counted once per file.
+    pub fn attribute_dump(&self, dump: Range<u64>, load_bias: u64) -> DumpReport {
+        let start = dump.start.wrapping_sub(load_bias);
+        let len = dump.end.saturating_sub(dump.start);
+        let vaddrs = start..start.saturating_add(len);
+
+        let mut hits: Vec<_> = self.query_vaddr(vaddrs.clone()).collect();
+        hits.sort_by_key(|hit| hit.range.start);
+
+        let mut regions: Vec<DumpRegion> = vec![];
+        for hit in hits {
+            let vaddr_range = hit.range.start.max(vaddrs.start)..hit.range.end.min(vaddrs.end);
+            let dump_range = vaddr_range.start - vaddrs.start..vaddr_range.end - vaddrs.start;
+            match regions.last_mut() {
+                Some(last)
+                    if last.filename == hit.filename
+                        && last.vaddr_range.end == vaddr_range.start =>
+                {
+                    last.vaddr_range.end = vaddr_range.end;
+                    last.dump_range.end = dump_range.end;
+                }
+                _ => regions.push(DumpRegion {
+                    dump_range,
+                    vaddr_range,
+                    filename: hit.filename.to_string(),
+                }),
+            }
+        }
+
+        let mut attributed = 0;
+        let mut end = 0;
+        for region in &regions {
+            let start = region.dump_range.start.max(end);
+            attributed += region.dump_range.end.saturating_sub(start);
+            end = end.max(region.dump_range.end);
+        }
+
+        DumpReport {
+            report: Report::from_sizes(regions.iter().map(|region| {
+                (
+                    region.filename.as_str(),
+                    region.dump_range.end - region.dump_range.start,
+                )
+            })),
+            regions,
+            unattributed: len - attributed,
+        }
+    }
+}
+
+impl DumpReport {
+    /// The regions table rendered by `Display`.
+    pub fn to_table(&self) -> Table {
+        let mut table = Table::new(&[Align::Left, Align::Left, Align::Right, Align::Left])
+            .with_header(["dump offset", "vaddr", "size", "file"]);
+        for region in &self.regions {
+            table.push_row([
+                format!(
+                    "{}..{}",
+                    hex(region.dump_range.start),
+                    hex(region.dump_range.end)
+                ),
+                format!(
+                    "{}..{}",
+                    hex(region.vaddr_range.start),
+                    hex(region.vaddr_range.end)
+                ),
+                human_size(region.dump_range.end - region.dump_range.start),
+                region.filename.clone(),
+            ]);
+        }
+        table
+    }
+}
+
+impl Display for DumpReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_table())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Genealogy;
+
+    #[test]
+    fn test_attribute_dump() {
+        let mapfile = std::fs::read_to_string("tests/gcc/out.map").unwrap();
+        let binary = std::fs::read("tests/gcc/a.out").unwrap();
+        let genealogy = Genealogy::new(&mapfile, &binary).unwrap();
+
+        // A page of `.text` captured from a process whose PIE image was loaded at 0x5555_5555_4000
+        let load_bias = 0x5555_5555_4000;
+        let dump = genealogy.attribute_dump(load_bias + 0x1000..load_bias + 0x2000, load_bias);
+
+        let main = dump
+            .regions
+            .iter()
+            .find(|region| region.vaddr_range.contains(&0x1151))
+            .unwrap();
+        assert_eq!(main.filename, "simple.o");
+        assert_eq!(main.dump_range.start, main.vaddr_range.start - 0x1000);
+        assert!(dump.unattributed > 0);
+        assert_eq!(
+            dump.unattributed + dump.report.total,
+            0x1000,
+            "no overlapping regions in .text"
+        );
+
+        // Nothing of the image in a dump of the wrong module
+        let dump = genealogy.attribute_dump(0x1000..0x2000, load_bias);
+        assert!(dump.regions.is_empty());
+        assert_eq!(dump.unattributed, 0x1000);
+    }
+}
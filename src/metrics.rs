@@ -0,0 +1,148 @@
+//! Size reports as OpenMetrics text, which Prometheus reads too, for CI jobs pushing binary sizes
+//! to existing monitoring, e.g. through a Pushgateway or the textfile collector of the node
+//! exporter.
+
+use std::fmt::Write;
+
+use crate::{dependencies::HeaderReport, language::LanguageReport, ownership::OwnerReport, Report};
+
+/// Renders the `total` size of `binary` and the size of every component as gauges, the
+/// components labelled with what they are, `by` (e.g. `file` or `language`).
+pub fn size_metrics<S: AsRef<str>>(
+    binary: &str,
+    by: &str,
+    total: u64,
+    components: impl IntoIterator<Item = (S, u64)>,
+) -> String {
+    let binary = escape(binary);
+    let mut text = String::new();
+    gauge(
+        &mut text,
+        "genealogy_size_bytes",
+        "Bytes attributed to the files of the binary.",
+    );
+    writeln!(text, "genealogy_size_bytes{{binary=\"{binary}\"}} {total}")
+        .expect("writing to a String");
+    gauge(
+        &mut text,
+        "genealogy_component_size_bytes",
+        "Bytes attributed to a component of the binary.",
+    );
+    let by = escape(by);
+    for (component, size) in components {
+        writeln!(
+            text,
+            "genealogy_component_size_bytes{{binary=\"{binary}\",by=\"{by}\",component=\"{}\"}} {size}",
+            escape(component.as_ref())
+        )
+        .expect("writing to a String");
+    }
+    text.push_str("# EOF\n");
+    text
+}
+
+/// The metadata of a gauge family in bytes.
+fn gauge(text: &mut String, name: &str, help: &str) {
+    writeln!(text, "# TYPE {name} gauge").expect("writing to a String");
+    writeln!(text, "# UNIT {name} bytes").expect("writing to a String");
+    writeln!(text, "# HELP {name} {help}").expect("writing to a String");
+}
+
+/// Escapes a label value.
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+impl Report {
+    /// The total and the size of every file as OpenMetrics text, see [`size_metrics`].
+    pub fn to_openmetrics(&self, binary: &str) -> String {
+        size_metrics(
+            binary,
+            "file",
+            self.total,
+            self.entries
+                .iter()
+                .map(|entry| (&entry.filename, entry.size)),
+        )
+    }
+}
+
+impl LanguageReport {
+    /// The total and the size of every language as OpenMetrics text, see [`size_metrics`].
+    pub fn to_openmetrics(&self, binary: &str) -> String {
+        size_metrics(
+            binary,
+            "language",
+            self.total,
+            self.entries
+                .iter()
+                .map(|entry| (entry.language.to_string(), entry.size)),
+        )
+    }
+}
+
+impl OwnerReport {
+    /// The total and the size of the files of every owners as OpenMetrics text, see
+    /// [`size_metrics`].
+    pub fn to_openmetrics(&self, binary: &str) -> String {
+        size_metrics(
+            binary,
+            "owners",
+            self.total,
+            self.entries.iter().map(|entry| (&entry.owners, entry.size)),
+        )
+    }
+}
+
+impl HeaderReport {
+    /// The total and the size of the objects depending on every header as OpenMetrics text,
+    /// see [`size_metrics`].
+    pub fn to_openmetrics(&self, binary: &str) -> String {
+        size_metrics(
+            binary,
+            "header",
+            self.total,
+            self.entries.iter().map(|entry| (&entry.header, entry.size)),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Report, ReportEntry};
+
+    #[test]
+    fn test_to_openmetrics() {
+        let report = Report {
+            entries: [("libfoo.a(model.o)", 3 << 20), ("C:\\src\\\"main\".o", 12)]
+                .into_iter()
+                .map(|(filename, size)| ReportEntry {
+                    filename: filename.to_string(),
+                    size,
+                    source: None,
+                })
+                .collect(),
+            total: (3 << 20) + 12,
+        };
+        let text = report.to_openmetrics("app.elf");
+        let lines: Vec<_> = text.lines().filter(|line| !line.starts_with('#')).collect();
+        assert_eq!(
+            lines,
+            [
+                "genealogy_size_bytes{binary=\"app.elf\"} 3145740",
+                "genealogy_component_size_bytes{binary=\"app.elf\",by=\"file\",component=\"libfoo.a(model.o)\"} 3145728",
+                "genealogy_component_size_bytes{binary=\"app.elf\",by=\"file\",component=\"C:\\\\src\\\\\\\"main\\\".o\"} 12",
+            ]
+        );
+        assert!(
+            text.contains("# TYPE genealogy_size_bytes gauge\n# UNIT genealogy_size_bytes bytes\n")
+        );
+        assert!(text.ends_with("# EOF\n"));
+
+        let languages = report.by_language().to_openmetrics("app.elf");
+        assert!(languages.contains("by=\"language\",component=\"unknown\"} 3145740"));
+    }
+}
@@ -0,0 +1,167 @@
+//! Provenance tables opened in place from disk, for servers attributing addresses of many
+//! builds: the table of every build is mapped on demand and queried without being read or
+//! deserialized, see [`Genealogy::open_mmap`].
+
+use std::{fs::File, io, ops::Deref, path::Path};
+
+use crate::{table::ProvenanceTable, Genealogy};
+
+/// A table file written by [`ProvenanceTable::build`], mapped read-only.
+pub struct MappedTable {
+    bytes: Mapping,
+}
+
+impl MappedTable {
+    /// The table, checked when opened.
+    pub fn table(&self) -> ProvenanceTable<'_> {
+        ProvenanceTable::parse(&self.bytes).expect("checked when opened")
+    }
+
+    /// See [`ProvenanceTable::lookup`].
+    pub fn lookup(&self, address: u64) -> Option<&str> {
+        self.table().lookup(address)
+    }
+}
+
+impl Genealogy {
+    /// Maps the table file at `path`, failing with [`io::ErrorKind::InvalidData`] and a
+    /// [`TableError`](crate::table::TableError) when it is not a table of a supported version.
+    /// Only the pages lookups touch are read, in O(log n) for n ranges. Platforms without `mmap`
+    /// read the whole file instead.
+    ///
+    /// The file must not be modified while mapped.
+    pub fn open_mmap(path: impl AsRef<Path>) -> io::Result<MappedTable> {
+        let bytes = Mapping::new(&File::open(path)?)?;
+        if let Err(error) = ProvenanceTable::read(&bytes) {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, error));
+        }
+        Ok(MappedTable { bytes })
+    }
+}
+
+#[cfg(unix)]
+struct Mapping {
+    address: *mut libc::c_void,
+    len: usize,
+}
+
+// The mapping is read-only and owned
+#[cfg(unix)]
+unsafe impl Send for Mapping {}
+#[cfg(unix)]
+unsafe impl Sync for Mapping {}
+
+#[cfg(unix)]
+impl Mapping {
+    fn new(file: &File) -> io::Result<Self> {
+        use std::os::unix::io::AsRawFd;
+
+        let len = usize::try_from(file.metadata()?.len())
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "file too large to map"))?;
+        // Empty mappings are rejected by mmap, an empty file is not a table anyway
+        if len == 0 {
+            return Ok(Self {
+                address: std::ptr::null_mut(),
+                len,
+            });
+        }
+        // SAFETY: a private read-only mapping of a file we keep open for the call, the bytes are
+        // only read through `Deref`, while the mapping lives
+        let address = unsafe {
+            libc::mmap(
+                std::ptr::null_mut(),
+                len,
+                libc::PROT_READ,
+                libc::MAP_PRIVATE,
+                file.as_raw_fd(),
+                0,
+            )
+        };
+        if address == libc::MAP_FAILED {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(Self { address, len })
+    }
+}
+
+#[cfg(unix)]
+impl Deref for Mapping {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        if self.len == 0 {
+            return &[];
+        }
+        // SAFETY: `address` maps `len` readable bytes until dropped
+        unsafe { std::slice::from_raw_parts(self.address.cast(), self.len) }
+    }
+}
+
+#[cfg(unix)]
+impl Drop for Mapping {
+    fn drop(&mut self) {
+        if self.len > 0 {
+            // SAFETY: unmaps the mapping made by `new`, no slice of it outlives `self`
+            unsafe {
+                libc::munmap(self.address, self.len);
+            }
+        }
+    }
+}
+
+#[cfg(not(unix))]
+struct Mapping(Vec<u8>);
+
+#[cfg(not(unix))]
+impl Mapping {
+    fn new(mut file: &File) -> io::Result<Self> {
+        use std::io::Read;
+
+        let mut bytes = vec![];
+        file.read_to_end(&mut bytes)?;
+        Ok(Self(bytes))
+    }
+}
+
+#[cfg(not(unix))]
+impl Deref for Mapping {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{table::ProvenanceTable, Genealogy};
+
+    #[test]
+    fn test_open_mmap() {
+        let mapfile = std::fs::read_to_string("tests/gcc/out.map").unwrap();
+        let binary = std::fs::read("tests/gcc/a.out").unwrap();
+        let bytes = ProvenanceTable::build(&Genealogy::new(&mapfile, &binary).unwrap());
+        let directory = std::env::temp_dir().join(format!("genealogy-mmap-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+
+        let path = directory.join("a.out.gnlg");
+        std::fs::write(&path, &bytes).unwrap();
+        let mapped = Genealogy::open_mmap(&path).unwrap();
+        assert_eq!(mapped.lookup(0x1151), Some("simple.o"));
+        assert_eq!(mapped.lookup(0), None);
+        assert_eq!(
+            mapped.table().len(),
+            ProvenanceTable::parse(&bytes).unwrap().len()
+        );
+
+        // Not a table, or empty
+        let path = directory.join("out.map");
+        std::fs::write(&path, &mapfile).unwrap();
+        let error = Genealogy::open_mmap(&path).err().unwrap();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+        assert_eq!(error.to_string(), "not a provenance table");
+        std::fs::write(&path, "").unwrap();
+        assert!(Genealogy::open_mmap(&path).is_err());
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+}
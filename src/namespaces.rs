@@ -0,0 +1,354 @@
+//! Sizes per C++ namespace or Rust module path, from the demangled names of the symbols: a view
+//! by code structure, complementary to the one by object file when a namespace spans many files
+//! or a file many namespaces.
+
+use std::{collections::HashMap, fmt::Display};
+
+use crate::{
+    format::{human_size, percentage, Align, Cell, Style, Table},
+    Genealogy, GenealogyError,
+};
+
+/// The namespace of symbols outside of any, e.g. C functions.
+pub const GLOBAL: &str = "<global>";
+
+/// What the C++ demangler writes before the entity of special names, e.g. `vtable for ns::Foo`.
+const SPECIAL_PREFIXES: &[&str] = &[
+    "vtable for ",
+    "VTT for ",
+    "construction vtable for ",
+    "typeinfo for ",
+    "typeinfo name for ",
+    "guard variable for ",
+    "non-virtual thunk to ",
+    "virtual thunk to ",
+    "covariant return thunk to ",
+    "transaction clone for ",
+    "TLS init function for ",
+    "TLS wrapper function for ",
+];
+
+/// The first `depth` components of the namespace or module path of a demangled name, `None` for
+/// names at the top level. Template and generic arguments are left out, and trait methods are
+/// placed in the path of their self type, e.g. `alloc::vec` for
+/// `<alloc::vec::Vec<T> as core::ops::drop::Drop>::drop` with a depth of 2, or of the trait
+/// for generic and primitive self types.
+pub fn namespace_of(demangled: &str, depth: usize) -> Option<String> {
+    let mut name = demangled;
+    while let Some(rest) = SPECIAL_PREFIXES
+        .iter()
+        .find_map(|prefix| name.strip_prefix(prefix))
+    {
+        name = rest;
+    }
+    let mut components = components(name);
+    // The last component is the function, variable or class itself
+    components.pop();
+    components.truncate(depth);
+    (!components.is_empty()).then(|| components.join("::"))
+}
+
+/// The `::` separated components of `name`, without arguments nor return type.
+fn components(name: &str) -> Vec<String> {
+    if name.starts_with('<') {
+        if let Some(close) = matching_close(name) {
+            // `<Type as Trait>::method`, or `<Type>::method`
+            let inner = &name[1..close];
+            let (self_type, trait_path) = split_top_level(inner, " as ");
+            let self_type = self_type
+                .trim_start_matches(['&', '*'])
+                .trim_start_matches("const ")
+                .trim_start_matches("mut ")
+                .trim_start_matches("dyn ");
+            let mut components = components(self_type);
+            // Generic parameters and primitive types have no path, the trait has one
+            if components.len() < 2 {
+                if let Some(trait_path) = trait_path {
+                    components = self::components(trait_path);
+                }
+            }
+            if let Some(rest) = name[close + 1..].strip_prefix("::") {
+                components.extend(self::components(rest));
+            }
+            return components;
+        }
+    }
+
+    let mut components = vec![];
+    let mut current = String::new();
+    let mut depth = 0usize;
+    let mut i = 0;
+    while let Some(c) = name[i..].chars().next() {
+        let rest = &name[i..];
+        if depth == 0 && rest.starts_with("(anonymous namespace)") {
+            current.push_str("(anonymous namespace)");
+            i += "(anonymous namespace)".len();
+            continue;
+        }
+        if depth == 0 && current.is_empty() && rest.starts_with("operator") {
+            // Operators may contain `<`, `(` or spaces, they are always the last component
+            current.push_str("operator");
+            break;
+        }
+        match c {
+            '<' | '{' | '[' => depth += 1,
+            '>' | '}' | ']' => depth = depth.saturating_sub(1),
+            '(' if depth == 0 => break,
+            ':' if depth == 0 && rest.starts_with("::") => {
+                components.push(std::mem::take(&mut current));
+                i += 2;
+                continue;
+            }
+            // What came before is the return type of a template function
+            ' ' if depth == 0 => {
+                components.clear();
+                current.clear();
+            }
+            _ if depth == 0 => current.push(c),
+            _ => {}
+        }
+        i += c.len_utf8();
+    }
+    if !current.is_empty() {
+        components.push(current);
+    }
+    components
+}
+
+/// The index of the `>` closing the `<` `text` starts with.
+fn matching_close(text: &str) -> Option<usize> {
+    let mut depth = 0;
+    for (i, c) in text.char_indices() {
+        match c {
+            '<' => depth += 1,
+            '>' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Splits `text` at the first `separator` outside of angle brackets.
+fn split_top_level<'a>(text: &'a str, separator: &str) -> (&'a str, Option<&'a str>) {
+    let mut depth = 0usize;
+    for (i, c) in text.char_indices() {
+        match c {
+            '<' => depth += 1,
+            '>' => depth = depth.saturating_sub(1),
+            _ if depth == 0 && text[i..].starts_with(separator) => {
+                return (&text[..i], Some(&text[i + separator.len()..]));
+            }
+            _ => {}
+        }
+    }
+    (text, None)
+}
+
+/// Symbol sizes per namespace, see [`Genealogy::namespace_report`].
+#[derive(Clone, Debug)]
+pub struct NamespaceReport {
+    /// Biggest first.
+    pub entries: Vec<NamespaceEntry>,
+    pub total: u64,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NamespaceEntry {
+    /// The namespace, or [`GLOBAL`].
+    pub namespace: String,
+    pub size: u64,
+    pub symbols: u64,
+    /// Number of files the symbols of the namespace come from.
+    pub files: u64,
+    /// The file with the most bytes of the namespace.
+    pub top_file: Option<String>,
+}
+
+impl Genealogy {
+    /// Sums the sizes of the symbols per namespace, of `depth` components (1 for top-level
+    /// namespaces and crates). Needs [`GenealogyBuilder::symbols`], aliases are counted once,
+    /// in the namespace of their canonical symbol.
+    ///
+    /// [`GenealogyBuilder::symbols`]: crate::GenealogyBuilder::symbols
+    pub fn namespace_report(&self, depth: usize) -> Result<NamespaceReport, GenealogyError> {
+        let symbols = self.symbols().ok_or(GenealogyError::NoSymbolIndex)?;
+        let mut namespaces: HashMap<String, (u64, u64, HashMap<&str, u64>)> = HashMap::new();
+        for symbol in symbols.deduplicated().filter(|symbol| symbol.size > 0) {
+            let namespace = symbol
+                .demangled
+                .as_deref()
+                .and_then(|demangled| namespace_of(demangled, depth))
+                .unwrap_or_else(|| GLOBAL.to_string());
+            let (size, count, files) = namespaces.entry(namespace).or_default();
+            *size += symbol.size;
+            *count += 1;
+            if let Some(hit) = self.query_vaddr_point(symbol.vaddr).next() {
+                *files.entry(hit.filename).or_default() += symbol.size;
+            }
+        }
+
+        let mut entries: Vec<NamespaceEntry> = namespaces
+            .into_iter()
+            .map(|(namespace, (size, symbols, files))| NamespaceEntry {
+                namespace,
+                size,
+                symbols,
+                files: files.len() as u64,
+                top_file: files
+                    .into_iter()
+                    .max_by(|a, b| a.1.cmp(&b.1).then_with(|| b.0.cmp(a.0)))
+                    .map(|(filename, _)| filename.to_string()),
+            })
+            .collect();
+        entries.sort_by(|a, b| {
+            b.size
+                .cmp(&a.size)
+                .then_with(|| a.namespace.cmp(&b.namespace))
+        });
+
+        Ok(NamespaceReport {
+            total: entries.iter().map(|entry| entry.size).sum(),
+            entries,
+        })
+    }
+}
+
+impl NamespaceReport {
+    /// The table rendered by `Display`.
+    pub fn to_table(&self) -> Table {
+        let mut table = Table::new(&[
+            Align::Right,
+            Align::Right,
+            Align::Right,
+            Align::Right,
+            Align::Left,
+            Align::Left,
+        ])
+        .with_header(["size", "share", "symbols", "files", "namespace", "top file"]);
+        for entry in &self.entries {
+            table.push_row([
+                human_size(entry.size),
+                percentage(entry.size, self.total),
+                entry.symbols.to_string(),
+                entry.files.to_string(),
+                entry.namespace.clone(),
+                entry.top_file.clone().unwrap_or_else(|| "-".to_string()),
+            ]);
+        }
+        table.push_row([
+            Cell::styled(human_size(self.total), Some(Style::Bold)),
+            percentage(self.total, self.total).into(),
+            self.entries
+                .iter()
+                .map(|entry| entry.symbols)
+                .sum::<u64>()
+                .to_string()
+                .into(),
+            "".into(),
+            Cell::styled("total", Some(Style::Bold)),
+            "".into(),
+        ]);
+        table
+    }
+}
+
+impl Display for NamespaceReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_table())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Genealogy;
+
+    use super::{namespace_of, GLOBAL};
+
+    #[test]
+    fn test_namespace_report() {
+        for (demangled, depth, namespace) in [
+            ("core::fmt::write", 1, Some("core")),
+            ("core::fmt::write", 5, Some("core::fmt")),
+            (
+                "core::ptr::drop_in_place<alloc::string::String>",
+                2,
+                Some("core::ptr"),
+            ),
+            (
+                "<alloc::vec::Vec<T> as core::ops::drop::Drop>::drop",
+                2,
+                Some("alloc::vec"),
+            ),
+            ("<&T as core::fmt::Debug>::fmt", 2, Some("core::fmt")),
+            (
+                "<[u8] as scroll::pread::Pread<Ctx,E>>::gread_with",
+                1,
+                Some("scroll"),
+            ),
+            ("<[T]>::len", 1, None),
+            ("ns::Foo::bar(int) const", 2, Some("ns::Foo")),
+            (
+                "void ns::f<int>(std::vector<int, std::allocator<int> >&)",
+                1,
+                Some("ns"),
+            ),
+            (
+                "std::vector<int, std::allocator<int> >::push_back(int const&)",
+                2,
+                Some("std::vector"),
+            ),
+            (
+                "ns::operator<<(std::ostream&, ns::Foo const&)",
+                3,
+                Some("ns"),
+            ),
+            (
+                "(anonymous namespace)::helper()",
+                1,
+                Some("(anonymous namespace)"),
+            ),
+            ("vtable for ns::Foo", 1, Some("ns")),
+            ("foo(int)", 1, None),
+            ("operator new(unsigned long)", 1, None),
+        ] {
+            assert_eq!(
+                namespace_of(demangled, depth).as_deref(),
+                namespace,
+                "{demangled}"
+            );
+        }
+
+        let mapfile = std::fs::read_to_string("tests/cargo/out.map").unwrap();
+        let binary = std::fs::read("tests/cargo/genealogy").unwrap();
+        let genealogy = Genealogy::builder(&mapfile, &binary)
+            .symbols(true)
+            .build()
+            .unwrap();
+        let report = genealogy.namespace_report(1).unwrap();
+        let entry = |namespace: &str| {
+            report
+                .entries
+                .iter()
+                .find(|entry| entry.namespace == namespace)
+                .unwrap()
+        };
+        assert!(entry("genealogy")
+            .top_file
+            .as_deref()
+            .unwrap()
+            .contains("genealogy"));
+        assert!(entry("core").files > 1);
+        assert!(entry(GLOBAL).size > 0);
+        assert_eq!(report.total, report.entries.iter().map(|e| e.size).sum());
+
+        assert!(Genealogy::new(&mapfile, &binary)
+            .unwrap()
+            .namespace_report(1)
+            .is_err());
+    }
+}
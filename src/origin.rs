@@ -0,0 +1,565 @@
+//! Helpers to interpret the origin strings found in map files, such as
+//! `/usr/lib/libc.a(printf.o)`.
+
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
+};
+
+use crate::{
+    language::{is_rust_library_stem, Language, RUNTIME},
+    ownership::pattern_matches,
+};
+
+/// An origin split into its archive and object file parts.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Origin<'a> {
+    /// The static archive the object was pulled from, if any.
+    pub archive: Option<&'a str>,
+    /// The object file, or the whole origin if it is not an archive member.
+    pub object: &'a str,
+}
+
+impl<'a> Origin<'a> {
+    /// Splits `libfoo.a(bar.o)` into `libfoo.a` and `bar.o`. Anything else is a plain object.
+    pub fn parse(origin: &'a str) -> Self {
+        if let Some(without_paren) = origin.strip_suffix(')') {
+            if let Some((archive, object)) = without_paren.split_once('(') {
+                if !archive.is_empty() && !object.is_empty() {
+                    return Self {
+                        archive: Some(archive),
+                        object,
+                    };
+                }
+            }
+        }
+        Self {
+            archive: None,
+            object: origin,
+        }
+    }
+}
+
+/// An origin at every granularity sizes are grouped by, so that a single pass over the hits can
+/// feed reports by object, archive, crate, component and directory.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Granularities<'a> {
+    /// The object file, see [`Origin::object`].
+    pub object: &'a str,
+    /// The static archive the object was pulled from, if any.
+    pub archive: Option<&'a str>,
+    /// The Rust crate of rlib members and of the codegen units of the crate being linked.
+    pub crate_name: Option<&'a str>,
+    /// The coarsest unit the origin belongs to: [`RUNTIME`] for start files and compiler
+    /// support libraries, otherwise the crate, the file name of the archive, the directory or
+    /// the object.
+    pub component: &'a str,
+    /// The directory of the archive, or of the object outside of archives.
+    pub directory: Option<&'a str>,
+}
+
+impl<'a> Granularities<'a> {
+    /// Splits `origin` as found in the map, e.g. `/usr/lib/libfoo.a(bar.o)`.
+    pub fn of(origin: &'a str) -> Self {
+        let parsed = Origin::parse(origin);
+        let crate_name = crate_name(parsed);
+        let directory = parent(parsed.archive.unwrap_or(parsed.object));
+        let component = if Language::of(origin) == Language::Runtime {
+            RUNTIME
+        } else {
+            crate_name
+                .or(parsed.archive.map(file_name))
+                .or(directory)
+                .unwrap_or(parsed.object)
+        };
+        Self {
+            object: parsed.object,
+            archive: parsed.archive,
+            crate_name,
+            component,
+            directory,
+        }
+    }
+}
+
+/// The crate of `libfoo-0123456789abcdef.rlib(...)`, of MSVC's `libfoo-0123456789abcdef` archive
+/// members, or of `foo-0123456789abcdef.foo.0123456789abcdef-cgu.0.rcgu.o`.
+fn crate_name(origin: Origin<'_>) -> Option<&str> {
+    let library = file_name(origin.archive.unwrap_or(origin.object));
+    if let Some(stem) = library
+        .strip_suffix(".rlib")
+        .or(Some(library).filter(|library| is_rust_library_stem(library)))
+    {
+        let stem = stem.strip_prefix("lib").unwrap_or(stem);
+        return Some(stem.rsplit_once('-').map_or(stem, |(name, _)| name));
+    }
+    let object = file_name(origin.object);
+    if origin.archive.is_none() && object.ends_with(".rcgu.o") {
+        let unit = object.split('.').next().unwrap_or(object);
+        return Some(unit.rsplit_once('-').map_or(unit, |(name, _)| name));
+    }
+    None
+}
+
+/// The path without its last component, `None` for a bare file name.
+fn parent(path: &str) -> Option<&str> {
+    path.rfind(['/', '\\']).map(|i| &path[..i.max(1)])
+}
+
+/// The last component of a path, accepting both `/` and `\\` as separators.
+pub fn file_name(path: &str) -> &str {
+    path.rsplit(['/', '\\']).next().unwrap_or(path)
+}
+
+/// Selects origins by path, for the contributions of some files only, see
+/// [`Genealogy::contributions`](crate::Genealogy::contributions).
+///
+/// Patterns follow the `CODEOWNERS` syntax and are matched against the whole origin, its archive
+/// and its object, with `\\` read as `/`. An origin matches when it matches an included pattern,
+/// or when there is none, and no excluded one.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PathFilter {
+    include: Vec<String>,
+    exclude: Vec<String>,
+}
+
+impl PathFilter {
+    /// A filter matching every origin.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn include(mut self, pattern: &str) -> Self {
+        self.include.push(pattern.to_string());
+        self
+    }
+
+    pub fn exclude(mut self, pattern: &str) -> Self {
+        self.exclude.push(pattern.to_string());
+        self
+    }
+
+    pub fn matches(&self, origin: &str) -> bool {
+        let Origin { archive, object } = Origin::parse(origin);
+        let candidates: Vec<String> = [
+            Some(origin),
+            archive,
+            Some(object).filter(|_| archive.is_some()),
+        ]
+        .into_iter()
+        .flatten()
+        .map(|path| path.replace('\\', "/").trim_start_matches('/').to_string())
+        .collect();
+        let any_matches = |patterns: &[String]| {
+            patterns.iter().any(|pattern| {
+                candidates
+                    .iter()
+                    .any(|candidate| pattern_matches(pattern, candidate))
+            })
+        };
+        (self.include.is_empty() || any_matches(&self.include)) && !any_matches(&self.exclude)
+    }
+}
+
+/// Path separator normalization applied to origins when parsing the map, selected with
+/// [`GenealogyBuilder::path_separators`](crate::GenealogyBuilder::path_separators).
+///
+/// Cross-compiled builds often mix `/` and `\\` for the same file, which would otherwise count
+/// as two origins.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PathSeparators {
+    /// Origins are kept as written in the map.
+    #[default]
+    Keep,
+    /// Separators are turned into `/`.
+    Slash,
+    /// Separators are turned into `\\`.
+    Backslash,
+}
+
+impl PathSeparators {
+    /// Rewrites the separators of `path`, also dropping repeated separators and resolving `.`
+    /// and `..` components lexically.
+    pub fn normalize(self, path: &str) -> Cow<'_, str> {
+        let separator = match self {
+            PathSeparators::Keep => return Cow::Borrowed(path),
+            PathSeparators::Slash => "/",
+            PathSeparators::Backslash => "\\",
+        };
+
+        let is_separator = |c: char| c == '/' || c == '\\';
+        // Keep the root, including the double separator of UNC paths
+        let root_len = path.chars().take_while(|&c| is_separator(c)).count().min(2);
+        let mut components: Vec<&str> = vec![];
+        for component in path[root_len..].split(is_separator) {
+            match component {
+                "" | "." => {}
+                ".." if components.last().is_some_and(|&last| last != "..") => {
+                    components.pop();
+                }
+                ".." if root_len > 0 => {}
+                component => components.push(component),
+            }
+        }
+
+        let normalized = separator.repeat(root_len) + &components.join(separator);
+        if normalized == path {
+            Cow::Borrowed(path)
+        } else {
+            Cow::Owned(normalized)
+        }
+    }
+}
+
+/// Resolves the sysroot prefix of GNU ld paths, `=` or `$SYSROOT`, as in `=/usr/lib/crt1.o`:
+/// against `sysroot` when given, otherwise by dropping the prefix, so that the same file is
+/// named the same whether it was found through the sysroot or not.
+pub fn resolve_sysroot<'a>(path: &'a str, sysroot: Option<&str>) -> Cow<'a, str> {
+    let Some(rest) = path
+        .strip_prefix('=')
+        .or_else(|| path.strip_prefix("$SYSROOT"))
+    else {
+        return Cow::Borrowed(path);
+    };
+    match sysroot {
+        Some(sysroot) => {
+            let sysroot = sysroot.trim_end_matches(['/', '\\']);
+            let separator = if rest.starts_with(['/', '\\']) {
+                ""
+            } else {
+                "/"
+            };
+            Cow::Owned(format!("{sysroot}{separator}{rest}"))
+        }
+        None => Cow::Borrowed(rest),
+    }
+}
+
+/// How origins are named in a [`Genealogy`](crate::Genealogy), selected with
+/// [`GenealogyBuilder::origin_naming`](crate::GenealogyBuilder::origin_naming).
+///
+/// Full paths are unambiguous but long. The other policies shorten them to their file name
+/// while keeping files with the same name in different directories apart.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OriginNaming {
+    /// Origins are kept as written in the map.
+    #[default]
+    FullPath,
+    /// The fewest trailing path components that no other origin ends with, e.g. `util.o`, or
+    /// `net/util.o` and `fs/util.o` when two directories both have a `util.o`.
+    ShortestUniqueSuffix,
+    /// The file name, followed by `#` and a hash of the full path when it is not unique, e.g.
+    /// `util.o#1b2c3d4e`.
+    HashSuffix,
+}
+
+impl OriginNaming {
+    /// The name of every distinct origin under this policy.
+    pub fn names<'a>(self, origins: impl IntoIterator<Item = &'a str>) -> HashMap<&'a str, String> {
+        let origins: Vec<&str> = origins
+            .into_iter()
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect();
+        match self {
+            OriginNaming::FullPath => origins.iter().map(|&o| (o, o.to_string())).collect(),
+            OriginNaming::ShortestUniqueSuffix => shortest_unique_suffixes(&origins),
+            OriginNaming::HashSuffix => {
+                let mut counts: HashMap<&str, usize> = HashMap::new();
+                for origin in &origins {
+                    *counts.entry(file_name(origin)).or_default() += 1;
+                }
+                origins
+                    .iter()
+                    .map(|&origin| {
+                        let name = file_name(origin);
+                        let name = if counts[name] == 1 {
+                            name.to_string()
+                        } else {
+                            format!("{name}#{:08x}", fnv1a(origin))
+                        };
+                        (origin, name)
+                    })
+                    .collect()
+            }
+        }
+    }
+}
+
+fn shortest_unique_suffixes<'a>(origins: &[&'a str]) -> HashMap<&'a str, String> {
+    let mut names = HashMap::new();
+    let mut pending: Vec<&str> = origins.to_vec();
+    let mut depth = 1;
+    while !pending.is_empty() {
+        let mut groups: HashMap<&str, Vec<&str>> = HashMap::new();
+        for &origin in &pending {
+            groups
+                .entry(path_suffix(origin, depth))
+                .or_default()
+                .push(origin);
+        }
+        pending.clear();
+        for (suffix, group) in groups {
+            for origin in &group {
+                // An origin with no components left is named by its full path
+                if group.len() == 1 || *origin == suffix {
+                    names.insert(*origin, suffix.to_string());
+                } else {
+                    pending.push(origin);
+                }
+            }
+        }
+        depth += 1;
+    }
+    names
+}
+
+/// The last `depth` components of `path`, or the whole path if it has fewer.
+fn path_suffix(path: &str, depth: usize) -> &str {
+    path.rmatch_indices(['/', '\\'])
+        .nth(depth - 1)
+        .map_or(path, |(i, _)| &path[i + 1..])
+}
+
+/// 32-bit FNV-1a, a hash that is stable across platforms and releases.
+fn fnv1a(text: &str) -> u32 {
+    text.bytes().fold(0x811c_9dc5, |hash, byte| {
+        (hash ^ u32::from(byte)).wrapping_mul(0x0100_0193)
+    })
+}
+
+/// How the codegen units of Rust crates are named, selected with
+/// [`GenealogyBuilder::rust_units`](crate::GenealogyBuilder::rust_units).
+///
+/// rustc splits every crate into codegen units named after hashes, e.g.
+/// `regex-1a2b3c4d5e6f7a8b.regex.9c0d1e2f3a4b5c6d-cgu.07.rcgu.o`, which say nothing of what
+/// they hold and change from one build to the next.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RustUnits {
+    /// Units are kept as written in the map.
+    #[default]
+    Keep,
+    /// The units of a crate are counted as one, e.g. `regex-1a2b3c4d5e6f7a8b.regex.rcgu.o`.
+    Crate,
+    /// Subsections are attributed to the module of the Rust symbol their input section is named
+    /// after, e.g. `regex-1a2b3c4d5e6f7a8b.regex::meta::strategy.rcgu.o` for
+    /// `.text._ZN5regex4meta8strategy3new17h…E`, otherwise to their crate as with
+    /// [`RustUnits::Crate`].
+    Module,
+}
+
+impl RustUnits {
+    /// The name of the codegen unit `origin` under this policy, for its input section
+    /// `section`. `None` when it is kept, or is not a codegen unit.
+    pub fn rename(self, origin: &str, section: &str) -> Option<String> {
+        if self == RustUnits::Keep {
+            return None;
+        }
+        let parsed = Origin::parse(origin);
+        let unit = parsed.object.strip_suffix(".rcgu.o")?;
+        let (before_cgu, _) = unit.rsplit_once("-cgu.")?;
+        let (stem, _) = before_cgu.rsplit_once('.')?;
+        let stem = match self {
+            RustUnits::Module => match section_module(section) {
+                Some(module) => {
+                    let (artifact, _) = stem.rsplit_once('.').unwrap_or((stem, ""));
+                    Cow::Owned(format!("{artifact}.{module}"))
+                }
+                None => Cow::Borrowed(stem),
+            },
+            _ => Cow::Borrowed(stem),
+        };
+        Some(match parsed.archive {
+            Some(archive) => format!("{archive}({stem}.rcgu.o)"),
+            None => format!("{stem}.rcgu.o"),
+        })
+    }
+}
+
+/// The module of the Rust symbol an input section is named after, e.g. `regex::meta` for
+/// `.text._ZN5regex4meta5Regex3new17h0123456789abcdefE`: the leading path components, up to the
+/// first type, generic argument or closure. For trait implementations, that of the type.
+fn section_module(section: &str) -> Option<String> {
+    let start = section.find("._ZN").or_else(|| section.find("._R"))? + 1;
+    let symbol = rustc_demangle::try_demangle(&section[start..]).ok()?;
+    let demangled = format!("{symbol:#}");
+    // `<regex::meta::Regex as core::clone::Clone>::clone`
+    let path = match demangled.strip_prefix('<') {
+        Some(implementation) => implementation.split([' ', '>', '<']).next()?,
+        None => demangled.split('<').next()?,
+    };
+    let components: Vec<&str> = path.split("::").collect();
+    let modules: Vec<&str> = components[..components.len() - 1]
+        .iter()
+        .copied()
+        .take_while(|component| {
+            component
+                .chars()
+                .next()
+                .is_some_and(|c| c.is_lowercase() || c == '_')
+        })
+        .collect();
+    (!modules.is_empty()).then(|| modules.join("::"))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::language::RUNTIME;
+
+    use super::{
+        file_name, resolve_sysroot, Granularities, Origin, OriginNaming, PathSeparators, RustUnits,
+    };
+
+    #[test]
+    fn test_parse_origin() {
+        let origin = Origin::parse("/usr/lib/libc.a(printf.o)");
+        assert_eq!(origin.archive, Some("/usr/lib/libc.a"));
+        assert_eq!(origin.object, "printf.o");
+
+        assert_eq!(Origin::parse("simple.o").archive, None);
+        assert_eq!(Origin::parse("<internal>").object, "<internal>");
+        assert_eq!(Origin::parse("(weird)").archive, None);
+
+        assert_eq!(file_name("C:\\build\\app.exe"), "app.exe");
+        assert_eq!(file_name("/x/a.out"), "a.out");
+    }
+
+    #[test]
+    fn test_origin_naming() {
+        let origins = [
+            "build/net/util.o",
+            "build/fs/util.o",
+            "build/main.o",
+            "util.o",
+            "/usr/lib/libc.a(printf.o)",
+        ];
+
+        let names = OriginNaming::ShortestUniqueSuffix.names(origins);
+        assert_eq!(names["build/net/util.o"], "net/util.o");
+        assert_eq!(names["build/fs/util.o"], "fs/util.o");
+        assert_eq!(names["build/main.o"], "main.o");
+        assert_eq!(names["util.o"], "util.o");
+        assert_eq!(names["/usr/lib/libc.a(printf.o)"], "libc.a(printf.o)");
+
+        let names = OriginNaming::HashSuffix.names(origins);
+        assert_eq!(names["build/main.o"], "main.o");
+        assert!(names["build/net/util.o"].starts_with("util.o#"));
+        assert_ne!(names["build/net/util.o"], names["build/fs/util.o"]);
+
+        let names = OriginNaming::FullPath.names(origins);
+        assert_eq!(names["build/net/util.o"], "build/net/util.o");
+    }
+
+    #[test]
+    fn test_normalize_separators() {
+        let slash = PathSeparators::Slash;
+        assert_eq!(
+            slash.normalize("C:\\build\\obj/util.o"),
+            "C:/build/obj/util.o"
+        );
+        assert_eq!(slash.normalize("./build//a/../util.o"), "build/util.o");
+        assert_eq!(slash.normalize("../src/util.o"), "../src/util.o");
+        assert_eq!(
+            slash.normalize("/usr/lib/../lib/libc.a(printf.o)"),
+            "/usr/lib/libc.a(printf.o)"
+        );
+        assert_eq!(
+            PathSeparators::Backslash.normalize("\\\\server/share/x.obj"),
+            "\\\\server\\share\\x.obj"
+        );
+        assert_eq!(PathSeparators::Keep.normalize("a\\b/c.o"), "a\\b/c.o");
+    }
+
+    #[test]
+    fn test_resolve_sysroot() {
+        assert_eq!(resolve_sysroot("=/usr/lib/crt1.o", None), "/usr/lib/crt1.o");
+        assert_eq!(
+            resolve_sysroot("$SYSROOT/usr/lib/libc.a(printf.o)", Some("/opt/sdk/")),
+            "/opt/sdk/usr/lib/libc.a(printf.o)"
+        );
+        assert_eq!(
+            resolve_sysroot("=usr/lib/crt1.o", Some("/opt/sdk")),
+            "/opt/sdk/usr/lib/crt1.o"
+        );
+        assert_eq!(
+            resolve_sysroot("/usr/lib/crt1.o", Some("/opt/sdk")),
+            "/usr/lib/crt1.o"
+        );
+    }
+
+    #[test]
+    fn test_rust_units() {
+        let member = "/deps/libregex-1a2b3c4d5e6f7a8b.rlib(regex-1a2b3c4d5e6f7a8b.regex.9c0d1e2f3a4b5c6d-cgu.07.rcgu.o)";
+        let method = ".text._ZN5regex4meta5Regex3new17h0123456789abcdefE";
+        assert_eq!(
+            RustUnits::Crate.rename(member, method).unwrap(),
+            "/deps/libregex-1a2b3c4d5e6f7a8b.rlib(regex-1a2b3c4d5e6f7a8b.regex.rcgu.o)"
+        );
+        assert_eq!(
+            RustUnits::Module.rename(member, method).unwrap(),
+            "/deps/libregex-1a2b3c4d5e6f7a8b.rlib(regex-1a2b3c4d5e6f7a8b.regex::meta.rcgu.o)"
+        );
+        assert_eq!(RustUnits::Keep.rename(member, method), None);
+
+        // Generic instances and trait implementations, the unit of the crate being linked
+        let unit = "/deps/app-8279a83a4d1b92a3.app.b8315eae5e9d9f36-cgu.1.rcgu.o";
+        let module = |section| RustUnits::Module.rename(unit, section).unwrap();
+        assert_eq!(
+            module(".text._ZN4core3ptr46drop_in_place$LT$alloc..vec..Vec$LT$u8$GT$$GT$17h0123456789abcdefE"),
+            "/deps/app-8279a83a4d1b92a3.core::ptr.rcgu.o"
+        );
+        assert_eq!(
+            module(".text._ZN58_$LT$app..config..Config$u20$as$u20$core..clone..Clone$GT$5clone17h0123456789abcdefE"),
+            "/deps/app-8279a83a4d1b92a3.app::config.rcgu.o"
+        );
+        assert_eq!(
+            module(".rodata..Lanon.fad58de7366495db4650cfefac2fcd61.3"),
+            "/deps/app-8279a83a4d1b92a3.app.rcgu.o"
+        );
+        assert_eq!(RustUnits::Crate.rename("build/main.o", ".text"), None);
+    }
+
+    #[test]
+    fn test_granularities() {
+        let rlib = Granularities::of(
+            "/target/deps/libregex_automata-98191570a5b0da38.rlib(regex_automata-98191570a5b0da38.regex_automata.e1e79f21987b9640-cgu.06.rcgu.o)",
+        );
+        assert_eq!(rlib.crate_name, Some("regex_automata"));
+        assert_eq!(rlib.component, "regex_automata");
+        assert_eq!(rlib.directory, Some("/target/deps"));
+        assert_eq!(
+            rlib.archive,
+            Some("/target/deps/libregex_automata-98191570a5b0da38.rlib")
+        );
+
+        let unit = Granularities::of(
+            "/target/deps/genealogy-8279a83a4d1b92a3.genealogy.b8315eae5e9d9f36-cgu.0.rcgu.o",
+        );
+        assert_eq!(unit.crate_name, Some("genealogy"));
+        assert_eq!(
+            Granularities::of("libstd-4ee9ee8805e6ac55").crate_name,
+            Some("std")
+        );
+
+        let member = Granularities::of("build/lib/libnet.a(socket.o)");
+        assert_eq!(
+            (
+                member.object,
+                member.crate_name,
+                member.component,
+                member.directory
+            ),
+            ("socket.o", None, "libnet.a", Some("build/lib"))
+        );
+        let object = Granularities::of("src/app/main.o");
+        assert_eq!(
+            (object.component, object.directory),
+            ("src/app", Some("src/app"))
+        );
+        assert_eq!(Granularities::of("main.o").component, "main.o");
+        assert_eq!(
+            Granularities::of("/usr/lib/gcc/x86_64-linux-gnu/11/libgcc.a(_udivdi3.o)").component,
+            RUNTIME
+        );
+    }
+}
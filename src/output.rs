@@ -0,0 +1,1724 @@
+//! A minimal document model used to export results as JSON or YAML with one shared schema.
+//!
+//! Results implement [`ToValue`] once and every serializer renders the same tree, so the formats
+//! cannot drift apart.
+
+use std::fmt::Write;
+
+use crate::{
+    android::{AppReport, AppRow, LibraryEntry},
+    archives::{ArchiveMember, InclusionReport, InclusionStep},
+    blobs::{Blob, BlobReport},
+    commits::{Commit, CommitChange, CommitReport},
+    ctors::{ConstructorEntry, ConstructorReport, StaticConstructor},
+    deadstrip::{DeadStripEntry, DeadStripReport, StrippedSection},
+    dependencies::{HeaderEntry, HeaderReport},
+    diff::{DiffEntry, ReportDiff},
+    explain::Explanation,
+    exports::{ExportEntry, ExportReport, ExportedSymbol},
+    got::{GotEntry, GotReport, GotSlot},
+    hierarchy::{Contribution, Hierarchy, HierarchySection, Segment, HIERARCHY_VERSION},
+    history::{Build, TrendEntry, TrendReport, HISTORY_VERSION},
+    hotcold::{HotColdEntry, HotColdReport, HotFunction},
+    image::{ComponentEntry, ImageHit},
+    kernel::{KernelEntry, KernelReport},
+    language::{LanguageEntry, LanguageReport},
+    locality::{LocalityEntry, LocalityReport},
+    matrix::SectionMatrix,
+    memdump::{DumpRegion, DumpReport},
+    namespaces::{NamespaceEntry, NamespaceReport},
+    origin::{Granularities, Origin},
+    ownership::{OwnerEntry, OwnerReport},
+    padding::{PaddingEntry, PaddingReport, SectionPadding},
+    pages::{PageEntry, PageReport},
+    partitions::{Offender, PartitionEntry, PartitionReport},
+    profile::{ProfileEntry, ProfileReport},
+    quality::AttributionQuality,
+    reorder::{FileLayout, ReorderEntry, ReorderReport},
+    resolution::{ResolutionReport, SymbolResolution},
+    snapshot::{Drift, Snapshot, SnapshotSection, SNAPSHOT_VERSION},
+    stack::{SectionStackEntry, StackEntry, StackReport, StackUser},
+    statics::{StaticBuffer, StaticEntry, StaticReport},
+    strip::{StripEntry, StripReport},
+    symbols::Symbol,
+    uboot::{FitImage, StageReport},
+    validate::Warning,
+    vectors::{Vector, VectorTable},
+    QueryHit, Report, ReportEntry, SymbolHit,
+};
+
+#[cfg(feature = "regex")]
+use crate::triage::Frame;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    UInt(u64),
+    Int(i64),
+    /// Non-finite values are written as null.
+    Float(f64),
+    String(String),
+    Array(Vec<Value>),
+    /// Keys keep their insertion order so the output is stable.
+    Object(Vec<(String, Value)>),
+}
+
+impl Value {
+    /// Builds an object from `(key, value)` pairs.
+    pub fn object<K: Into<String>>(entries: impl IntoIterator<Item = (K, Value)>) -> Self {
+        Value::Object(entries.into_iter().map(|(k, v)| (k.into(), v)).collect())
+    }
+
+    pub fn to_json(&self) -> String {
+        let mut out = String::new();
+        write_json(self, &mut out);
+        out
+    }
+
+    pub fn to_yaml(&self) -> String {
+        let mut out = String::new();
+        if self.is_inline_yaml() {
+            write_yaml_scalar(self, &mut out);
+            out.push('\n');
+        } else {
+            write_yaml(self, 0, &mut out);
+        }
+        out
+    }
+
+    /// Parses a JSON document, `None` if it is not valid JSON. Integers are [`Value::UInt`] or
+    /// [`Value::Int`] when they fit, other numbers [`Value::Float`].
+    pub fn from_json(json: &str) -> Option<Self> {
+        let mut parser = JsonParser { json, position: 0 };
+        let value = parser.value()?;
+        parser.skip_whitespace();
+        (parser.position == json.len()).then_some(value)
+    }
+
+    /// The value of `key`, if this is an object that has it.
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        match self {
+            Value::Object(entries) => entries.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_u64(&self) -> Option<u64> {
+        match self {
+            Value::UInt(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[Value]> {
+        match self {
+            Value::Array(items) => Some(items),
+            _ => None,
+        }
+    }
+
+    fn is_inline_yaml(&self) -> bool {
+        match self {
+            Value::Array(items) => items.is_empty(),
+            Value::Object(entries) => entries.is_empty(),
+            _ => true,
+        }
+    }
+}
+
+impl From<u64> for Value {
+    fn from(value: u64) -> Self {
+        Value::UInt(value)
+    }
+}
+
+impl From<i64> for Value {
+    fn from(value: i64) -> Self {
+        Value::Int(value)
+    }
+}
+
+impl From<f64> for Value {
+    fn from(value: f64) -> Self {
+        Value::Float(value)
+    }
+}
+
+impl From<bool> for Value {
+    fn from(value: bool) -> Self {
+        Value::Bool(value)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(value: &str) -> Self {
+        Value::String(value.to_string())
+    }
+}
+
+impl From<String> for Value {
+    fn from(value: String) -> Self {
+        Value::String(value)
+    }
+}
+
+impl<T: Into<Value>> From<Option<T>> for Value {
+    fn from(value: Option<T>) -> Self {
+        value.map(Into::into).unwrap_or(Value::Null)
+    }
+}
+
+/// Conversion of a result into the shared export schema.
+pub trait ToValue {
+    fn to_value(&self) -> Value;
+}
+
+impl<T: ToValue> ToValue for [T] {
+    fn to_value(&self) -> Value {
+        Value::Array(self.iter().map(ToValue::to_value).collect())
+    }
+}
+
+impl ToValue for QueryHit<'_> {
+    fn to_value(&self) -> Value {
+        Value::object([
+            ("start", self.range.start.into()),
+            ("end", self.range.end.into()),
+            ("size", (self.range.end - self.range.start).into()),
+            ("file", self.filename.into()),
+            (
+                "folded_with",
+                Value::Array(self.folded_with.iter().map(|f| f.as_str().into()).collect()),
+            ),
+            ("map_line", self.line.map(|line| line as u64).into()),
+        ])
+    }
+}
+
+impl ToValue for Explanation<'_> {
+    fn to_value(&self) -> Value {
+        let derivations = self.derivations.iter().map(|derivation| {
+            let subsection = derivation.subsection;
+            Value::object([
+                ("section", derivation.section.name.as_str().into()),
+                ("subsection", subsection.name.as_str().into()),
+                ("file", subsection.filename.as_str().into()),
+                (
+                    "folded_with",
+                    Value::Array(
+                        subsection
+                            .folded_with
+                            .iter()
+                            .map(|f| f.as_str().into())
+                            .collect(),
+                    ),
+                ),
+                (
+                    "map_line",
+                    derivation.map_line.map_or(Value::Null, |(number, text)| {
+                        Value::object([
+                            ("number", (number as u64).into()),
+                            ("text", text.trim().into()),
+                        ])
+                    }),
+                ),
+                ("map_address", derivation.map_vaddr.into()),
+                ("file_offset", subsection.start_file_offset.into()),
+                ("vaddr", subsection.start_vaddr.into()),
+                ("size", subsection.size.into()),
+                (
+                    "steps",
+                    Value::Array(
+                        derivation
+                            .steps(self.placement, self.point)
+                            .into_iter()
+                            .map(Value::from)
+                            .collect(),
+                    ),
+                ),
+            ])
+        });
+        Value::object([
+            ("offset", self.point.into()),
+            ("derivations", Value::Array(derivations.collect())),
+        ])
+    }
+}
+
+impl ToValue for Granularities<'_> {
+    fn to_value(&self) -> Value {
+        Value::object([
+            ("object", self.object.into()),
+            ("archive", self.archive.into()),
+            ("crate", self.crate_name.into()),
+            ("component", self.component.into()),
+            ("directory", self.directory.into()),
+        ])
+    }
+}
+
+impl ToValue for ReportEntry {
+    fn to_value(&self) -> Value {
+        Value::object([
+            ("file", self.filename.as_str().into()),
+            ("size", self.size.into()),
+            ("source", self.source.as_deref().into()),
+        ])
+    }
+}
+
+impl ToValue for Report {
+    fn to_value(&self) -> Value {
+        Value::object([
+            ("total", self.total.into()),
+            ("files", self.entries.to_value()),
+        ])
+    }
+}
+
+impl ToValue for DiffEntry {
+    fn to_value(&self) -> Value {
+        Value::object([
+            ("file", self.filename.as_str().into()),
+            ("old_size", self.old_size.into()),
+            ("new_size", self.new_size.into()),
+            ("delta", self.delta().into()),
+        ])
+    }
+}
+
+impl ToValue for ReportDiff {
+    fn to_value(&self) -> Value {
+        Value::object([
+            ("old_total", self.old_total.into()),
+            ("new_total", self.new_total.into()),
+            ("delta", self.delta().into()),
+            ("files", self.entries.to_value()),
+        ])
+    }
+}
+
+impl ToValue for Commit {
+    fn to_value(&self) -> Value {
+        Value::object([
+            ("id", self.id.as_str().into()),
+            ("summary", self.summary.as_str().into()),
+        ])
+    }
+}
+
+impl ToValue for CommitChange {
+    fn to_value(&self) -> Value {
+        Value::object([
+            ("commits", self.commits.to_value()),
+            ("file", self.change.filename.as_str().into()),
+            ("old_size", self.change.old_size.into()),
+            ("new_size", self.change.new_size.into()),
+            ("delta", self.change.delta().into()),
+        ])
+    }
+}
+
+impl ToValue for CommitReport {
+    fn to_value(&self) -> Value {
+        Value::object([
+            ("old_total", self.old_total.into()),
+            ("new_total", self.new_total.into()),
+            ("changes", self.entries.to_value()),
+            ("unattributed_delta", self.unattributed_delta().into()),
+            ("unattributed", self.unattributed.to_value()),
+        ])
+    }
+}
+
+impl ToValue for Blob {
+    fn to_value(&self) -> Value {
+        Value::object([
+            ("file", self.filename.as_str().into()),
+            ("section", self.section.as_str().into()),
+            ("file_offset", self.file_offset.into()),
+            ("size", self.size.into()),
+            ("kind", self.kind_name().into()),
+            ("entropy", self.entropy.into()),
+        ])
+    }
+}
+
+impl ToValue for BlobReport {
+    fn to_value(&self) -> Value {
+        Value::object([
+            ("min_size", self.min_size.into()),
+            ("blobs", self.blobs.to_value()),
+        ])
+    }
+}
+
+impl ToValue for StrippedSection {
+    fn to_value(&self) -> Value {
+        Value::object([
+            ("section", self.name.as_str().into()),
+            ("stripped", self.stripped.into()),
+        ])
+    }
+}
+
+impl ToValue for DeadStripEntry {
+    fn to_value(&self) -> Value {
+        Value::object([
+            ("file", self.filename.as_str().into()),
+            ("size", self.size.into()),
+            ("stripped", self.stripped.into()),
+            ("removed", self.is_removed().into()),
+            ("sections", self.sections.to_value()),
+        ])
+    }
+}
+
+impl ToValue for DeadStripReport {
+    fn to_value(&self) -> Value {
+        Value::object([
+            ("size", self.size.into()),
+            ("stripped", self.stripped.into()),
+            ("files", self.entries.to_value()),
+        ])
+    }
+}
+
+impl ToValue for Symbol {
+    fn to_value(&self) -> Value {
+        Value::object([
+            ("name", self.name.as_str().into()),
+            ("demangled", self.demangled.as_deref().into()),
+            ("vaddr", self.vaddr.into()),
+            ("size", self.size.into()),
+            ("file_offset", self.file_offset.into()),
+            ("section", self.section.as_deref().into()),
+            (
+                "aliases",
+                Value::Array(self.aliases.iter().map(|a| a.as_str().into()).collect()),
+            ),
+        ])
+    }
+}
+
+impl ToValue for SymbolHit<'_> {
+    fn to_value(&self) -> Value {
+        let origin = self.filename.map(Origin::parse);
+        Value::object([
+            ("symbol", self.symbol.to_value()),
+            ("file", self.filename.into()),
+            ("archive", origin.and_then(|o| o.archive).into()),
+            ("object", origin.map(|o| o.object).into()),
+        ])
+    }
+}
+
+#[cfg(feature = "regex")]
+impl ToValue for Frame {
+    fn to_value(&self) -> Value {
+        Value::object([
+            ("index", u64::from(self.index).into()),
+            ("pc", self.pc.into()),
+            ("function", self.function.as_deref().into()),
+            ("location", self.location.as_deref().into()),
+            ("module", self.module.as_deref().into()),
+            ("module_offset", self.module_offset.into()),
+        ])
+    }
+}
+
+impl ToValue for ProfileEntry {
+    fn to_value(&self) -> Value {
+        Value::object([
+            ("file", self.filename.as_str().into()),
+            ("weight", self.weight.into()),
+            ("size", self.size.into()),
+        ])
+    }
+}
+
+impl ToValue for ProfileReport {
+    fn to_value(&self) -> Value {
+        Value::object([
+            ("total_weight", self.total_weight.into()),
+            ("unattributed_weight", self.unattributed_weight.into()),
+            ("files", self.entries.to_value()),
+        ])
+    }
+}
+
+impl ToValue for AttributionQuality {
+    fn to_value(&self) -> Value {
+        Value::object([
+            ("exact", self.exact.into()),
+            ("estimated", self.estimated.into()),
+            ("unmapped", self.unmapped.into()),
+            ("score", self.score().into()),
+        ])
+    }
+}
+
+impl ToValue for StaticConstructor {
+    fn to_value(&self) -> Value {
+        Value::object([
+            ("section", self.section.as_str().into()),
+            ("slot_vaddr", self.slot_vaddr.into()),
+            ("target_vaddr", self.target_vaddr.into()),
+            ("file", self.filename.as_deref().into()),
+        ])
+    }
+}
+
+impl ToValue for ConstructorEntry {
+    fn to_value(&self) -> Value {
+        Value::object([
+            ("file", self.filename.as_str().into()),
+            ("count", self.count.into()),
+        ])
+    }
+}
+
+impl ToValue for ConstructorReport {
+    fn to_value(&self) -> Value {
+        Value::object([
+            ("constructors", self.constructors.to_value()),
+            ("files", self.entries.to_value()),
+        ])
+    }
+}
+
+impl ToValue for ExportedSymbol {
+    fn to_value(&self) -> Value {
+        Value::object([
+            ("name", self.name.as_str().into()),
+            ("demangled", self.demangled.as_deref().into()),
+            ("vaddr", self.vaddr.into()),
+            ("size", self.size.into()),
+            ("weak", self.weak.into()),
+            ("file", self.filename.as_deref().into()),
+        ])
+    }
+}
+
+impl ToValue for ExportEntry {
+    fn to_value(&self) -> Value {
+        Value::object([
+            ("file", self.filename.as_str().into()),
+            ("exports", self.exports.into()),
+            ("size", self.size.into()),
+        ])
+    }
+}
+
+impl ToValue for ExportReport {
+    fn to_value(&self) -> Value {
+        Value::object([
+            ("symbols", self.symbols.to_value()),
+            ("files", self.entries.to_value()),
+            ("total", self.total().into()),
+        ])
+    }
+}
+
+impl ToValue for GotSlot {
+    fn to_value(&self) -> Value {
+        Value::object([
+            ("vaddr", self.vaddr.into()),
+            ("kind", self.kind.as_str().into()),
+            ("symbol", self.symbol.as_str().into()),
+            ("size", self.size.into()),
+            (
+                "files",
+                Value::Array(self.files.iter().map(|f| f.as_str().into()).collect()),
+            ),
+        ])
+    }
+}
+
+impl ToValue for GotEntry {
+    fn to_value(&self) -> Value {
+        Value::object([
+            ("file", self.filename.as_str().into()),
+            ("slots", self.slots.into()),
+            ("size", self.size.into()),
+        ])
+    }
+}
+
+impl ToValue for GotReport {
+    fn to_value(&self) -> Value {
+        Value::object([
+            ("slots", self.slots.to_value()),
+            ("files", self.entries.to_value()),
+            ("total", self.total().into()),
+        ])
+    }
+}
+
+impl ToValue for HotFunction {
+    fn to_value(&self) -> Value {
+        Value::object([
+            ("function", self.name.as_str().into()),
+            ("vaddr", self.vaddr.into()),
+            ("size", self.size.into()),
+            ("file", self.filename.as_deref().into()),
+            ("section", self.subsection.as_deref().into()),
+            ("placed", self.placed.into()),
+        ])
+    }
+}
+
+impl ToValue for HotColdEntry {
+    fn to_value(&self) -> Value {
+        Value::object([
+            ("file", self.filename.as_str().into()),
+            ("functions", self.functions.into()),
+            ("misplaced", self.misplaced.into()),
+            ("misplaced_size", self.misplaced_size.into()),
+        ])
+    }
+}
+
+impl ToValue for HotColdReport {
+    fn to_value(&self) -> Value {
+        Value::object([
+            ("files", self.entries.to_value()),
+            ("functions", self.functions.to_value()),
+            (
+                "missing",
+                Value::Array(
+                    self.missing
+                        .iter()
+                        .map(|name| name.as_str().into())
+                        .collect(),
+                ),
+            ),
+        ])
+    }
+}
+
+impl ToValue for ImageHit<'_> {
+    fn to_value(&self) -> Value {
+        Value::object([
+            ("start", self.range.start.into()),
+            ("end", self.range.end.into()),
+            ("component", self.component.into()),
+            ("file", self.filename.into()),
+        ])
+    }
+}
+
+impl ToValue for ComponentEntry {
+    fn to_value(&self) -> Value {
+        Value::object([
+            ("component", self.name.as_str().into()),
+            ("offset", self.offset.into()),
+            ("size", self.size.into()),
+            ("attributed", self.attributed.into()),
+            ("matches", self.matches.into()),
+        ])
+    }
+}
+
+impl ToValue for KernelEntry {
+    fn to_value(&self) -> Value {
+        Value::object([
+            ("file", self.filename.as_str().into()),
+            ("exports", self.exports.into()),
+            ("percpu", self.percpu.into()),
+            ("init", self.init.into()),
+        ])
+    }
+}
+
+impl ToValue for KernelReport {
+    fn to_value(&self) -> Value {
+        Value::object([
+            ("files", self.entries.to_value()),
+            ("init", self.init.into()),
+        ])
+    }
+}
+
+impl ToValue for LanguageEntry {
+    fn to_value(&self) -> Value {
+        Value::object([
+            ("language", self.language.to_string().into()),
+            ("size", self.size.into()),
+            ("files", self.files.into()),
+        ])
+    }
+}
+
+impl ToValue for LanguageReport {
+    fn to_value(&self) -> Value {
+        Value::object([
+            ("total", self.total.into()),
+            ("languages", self.entries.to_value()),
+        ])
+    }
+}
+
+impl ToValue for LocalityEntry {
+    fn to_value(&self) -> Value {
+        Value::object([
+            ("file", self.filename.as_str().into()),
+            ("fragmentation", self.fragmentation().into()),
+            ("runs", self.runs.into()),
+            ("symbols", self.symbols.into()),
+            ("size", self.size.into()),
+            ("start", self.start.into()),
+            ("span", self.span.into()),
+        ])
+    }
+}
+
+impl ToValue for LocalityReport {
+    fn to_value(&self) -> Value {
+        Value::object([("files", self.entries.to_value())])
+    }
+}
+
+impl ToValue for FileLayout {
+    fn to_value(&self) -> Value {
+        Value::object([
+            ("sections", self.sections.into()),
+            ("runs", self.runs.into()),
+            ("fragmentation", self.fragmentation().into()),
+            ("size", self.size.into()),
+            ("start", self.start.into()),
+            ("span", self.span.into()),
+        ])
+    }
+}
+
+impl ToValue for ReorderEntry {
+    fn to_value(&self) -> Value {
+        Value::object([
+            ("file", self.filename.as_str().into()),
+            ("old", self.old.to_value()),
+            ("new", self.new.to_value()),
+            ("moved", self.moved.into()),
+        ])
+    }
+}
+
+impl ToValue for ReorderReport {
+    fn to_value(&self) -> Value {
+        Value::object([
+            ("moved", self.moved().into()),
+            ("files", self.entries.to_value()),
+        ])
+    }
+}
+
+impl ToValue for ArchiveMember {
+    fn to_value(&self) -> Value {
+        Value::object([
+            ("archive", self.archive.as_str().into()),
+            ("member", self.member.as_str().into()),
+            ("referenced_by", self.referenced_by.as_deref().into()),
+            ("symbol", self.symbol.as_deref().into()),
+            ("line", (self.line as u64).into()),
+        ])
+    }
+}
+
+impl ToValue for InclusionStep {
+    fn to_value(&self) -> Value {
+        Value::object([
+            ("member", self.member.to_value()),
+            ("size", self.size.into()),
+        ])
+    }
+}
+
+impl ToValue for InclusionReport {
+    fn to_value(&self) -> Value {
+        Value::object([
+            ("origin", self.origin.as_str().into()),
+            ("chain", self.steps.to_value()),
+        ])
+    }
+}
+
+impl ToValue for OwnerEntry {
+    fn to_value(&self) -> Value {
+        Value::object([
+            ("owners", self.owners.as_str().into()),
+            ("size", self.size.into()),
+            ("files", self.files.into()),
+        ])
+    }
+}
+
+impl ToValue for OwnerReport {
+    fn to_value(&self) -> Value {
+        Value::object([
+            ("total", self.total.into()),
+            ("owners", self.entries.to_value()),
+        ])
+    }
+}
+
+impl ToValue for HeaderEntry {
+    fn to_value(&self) -> Value {
+        Value::object([
+            ("header", self.header.as_str().into()),
+            ("size", self.size.into()),
+            ("files", self.files.into()),
+        ])
+    }
+}
+
+impl ToValue for HeaderReport {
+    fn to_value(&self) -> Value {
+        Value::object([
+            ("total", self.total.into()),
+            ("headers", self.entries.to_value()),
+        ])
+    }
+}
+
+impl ToValue for DumpRegion {
+    fn to_value(&self) -> Value {
+        Value::object([
+            ("dump_start", self.dump_range.start.into()),
+            ("dump_end", self.dump_range.end.into()),
+            ("vaddr_start", self.vaddr_range.start.into()),
+            ("vaddr_end", self.vaddr_range.end.into()),
+            ("file", self.filename.as_str().into()),
+        ])
+    }
+}
+
+impl ToValue for DumpReport {
+    fn to_value(&self) -> Value {
+        Value::object([
+            ("regions", self.regions.to_value()),
+            ("files", self.report.entries.to_value()),
+            ("attributed", self.report.total.into()),
+            ("unattributed", self.unattributed.into()),
+        ])
+    }
+}
+
+impl ToValue for SymbolResolution {
+    fn to_value(&self) -> Value {
+        Value::object([
+            ("symbol", self.symbol.as_str().into()),
+            ("demangled", self.demangled.as_deref().into()),
+            ("kept", self.kept.as_deref().into()),
+            (
+                "discarded",
+                Value::Array(self.discarded.iter().map(|f| f.as_str().into()).collect()),
+            ),
+        ])
+    }
+}
+
+impl ToValue for ResolutionReport {
+    fn to_value(&self) -> Value {
+        Value::object([("symbols", self.resolutions.to_value())])
+    }
+}
+
+impl ToValue for SectionPadding {
+    fn to_value(&self) -> Value {
+        Value::object([
+            ("section", self.section.as_str().into()),
+            ("size", self.size.into()),
+            ("padding", self.padding.into()),
+        ])
+    }
+}
+
+impl ToValue for PageEntry {
+    fn to_value(&self) -> Value {
+        Value::object([
+            ("vaddr", self.vaddr.into()),
+            ("attributed", self.attributed.into()),
+            ("files", self.files.into()),
+            ("dominant", self.dominant.as_str().into()),
+            ("dominant_size", self.dominant_size.into()),
+        ])
+    }
+}
+
+impl ToValue for PageReport {
+    fn to_value(&self) -> Value {
+        Value::object([
+            ("page_size", self.page_size.into()),
+            ("mixed", self.mixed.into()),
+            ("pages", self.pages.to_value()),
+        ])
+    }
+}
+
+impl ToValue for PartitionEntry {
+    fn to_value(&self) -> Value {
+        Value::object([
+            ("partition", self.partition.name.as_str().into()),
+            ("type", self.partition.kind.as_deref().into()),
+            ("offset", self.partition.offset.into()),
+            ("size", self.partition.size.into()),
+            ("used", self.used.into()),
+            ("files", self.files.into()),
+        ])
+    }
+}
+
+impl ToValue for Offender {
+    fn to_value(&self) -> Value {
+        Value::object([
+            ("file", self.filename.as_str().into()),
+            ("offset", self.offset.into()),
+            ("size", self.size.into()),
+            ("partition", self.partition.as_deref().into()),
+            ("violation", self.violation.name().into()),
+        ])
+    }
+}
+
+impl ToValue for PartitionReport {
+    fn to_value(&self) -> Value {
+        Value::object([
+            ("outside_flash", self.outside_flash.into()),
+            ("partitions", self.entries.to_value()),
+            ("offenders", self.offenders.to_value()),
+        ])
+    }
+}
+
+impl ToValue for PaddingEntry {
+    fn to_value(&self) -> Value {
+        Value::object([
+            ("file", self.filename.as_str().into()),
+            ("padding", self.padding.into()),
+            ("gaps", self.gaps.into()),
+        ])
+    }
+}
+
+impl ToValue for PaddingReport {
+    fn to_value(&self) -> Value {
+        Value::object([
+            ("total", self.total.into()),
+            ("sections", self.sections.to_value()),
+            ("files", self.entries.to_value()),
+        ])
+    }
+}
+
+impl SectionMatrix {
+    /// The `files` records of the exported matrix, one per file.
+    pub fn file_values(&self) -> Vec<Value> {
+        self.rows
+            .iter()
+            .map(|row| {
+                let sizes = self
+                    .sections
+                    .iter()
+                    .zip(&row.sizes)
+                    .map(|(section, &size)| (section.clone(), size.into()));
+                Value::object([
+                    ("file", row.filename.as_str().into()),
+                    ("total", row.total().into()),
+                    ("sections", Value::object(sizes)),
+                ])
+            })
+            .collect()
+    }
+}
+
+impl ToValue for SectionMatrix {
+    fn to_value(&self) -> Value {
+        Value::object([
+            (
+                "sections",
+                Value::Array(self.sections.iter().map(|s| s.as_str().into()).collect()),
+            ),
+            ("files", Value::Array(self.file_values())),
+        ])
+    }
+}
+
+impl StageReport {
+    /// The `files` records of the exported report, one per file.
+    pub fn file_values(&self) -> Vec<Value> {
+        self.rows
+            .iter()
+            .map(|row| {
+                let sizes = self
+                    .stages
+                    .iter()
+                    .zip(&row.sizes)
+                    .map(|(stage, &size)| (stage.name().to_string(), size.into()));
+                Value::object([
+                    ("file", row.filename.as_str().into()),
+                    ("total", row.total().into()),
+                    ("stages", Value::object(sizes)),
+                ])
+            })
+            .collect()
+    }
+}
+
+impl ToValue for StageReport {
+    fn to_value(&self) -> Value {
+        Value::object([
+            (
+                "stages",
+                Value::Array(self.stages.iter().map(|s| s.name().into()).collect()),
+            ),
+            ("files", Value::Array(self.file_values())),
+        ])
+    }
+}
+
+impl ToValue for FitImage {
+    fn to_value(&self) -> Value {
+        Value::object([
+            ("image", self.name.as_str().into()),
+            ("type", self.kind.as_str().into()),
+            ("description", self.description.as_str().into()),
+            ("start", self.data.start.into()),
+            ("end", self.data.end.into()),
+        ])
+    }
+}
+
+impl ToValue for LibraryEntry {
+    fn to_value(&self) -> Value {
+        Value::object([
+            ("library", self.library.as_str().into()),
+            ("abi", self.abi.as_str().into()),
+            ("size", self.size.into()),
+            ("stored", self.stored.into()),
+            ("attributed", self.attributed.into()),
+        ])
+    }
+}
+
+impl ToValue for AppRow {
+    fn to_value(&self) -> Value {
+        Value::object([
+            ("library", self.library.as_str().into()),
+            ("file", self.filename.as_str().into()),
+            ("size", self.size.into()),
+        ])
+    }
+}
+
+impl ToValue for AppReport {
+    fn to_value(&self) -> Value {
+        Value::object([
+            ("libraries", self.libraries.to_value()),
+            ("files", self.rows.to_value()),
+        ])
+    }
+}
+
+impl ToValue for StaticBuffer {
+    fn to_value(&self) -> Value {
+        Value::object([
+            ("static", self.name.as_str().into()),
+            (
+                "aliases",
+                Value::Array(self.aliases.iter().map(|a| a.as_str().into()).collect()),
+            ),
+            ("size", self.size.into()),
+            ("section", self.section.as_str().into()),
+            ("file", self.filename.as_deref().into()),
+        ])
+    }
+}
+
+impl ToValue for StaticEntry {
+    fn to_value(&self) -> Value {
+        Value::object([
+            ("file", self.filename.as_str().into()),
+            ("data", self.data.into()),
+            ("bss", self.bss.into()),
+            (
+                "biggest",
+                self.biggest.as_ref().map_or(Value::Null, ToValue::to_value),
+            ),
+        ])
+    }
+}
+
+impl ToValue for StaticReport {
+    fn to_value(&self) -> Value {
+        Value::object([
+            ("data", self.data.into()),
+            ("bss", self.bss.into()),
+            ("files", self.entries.to_value()),
+            ("statics", self.buffers.to_value()),
+        ])
+    }
+}
+
+impl ToValue for StripEntry {
+    fn to_value(&self) -> Value {
+        Value::object([
+            ("file", self.filename.as_str().into()),
+            ("shipped", self.shipped.into()),
+            ("strippable", self.strippable.into()),
+        ])
+    }
+}
+
+impl ToValue for StripReport {
+    fn to_value(&self) -> Value {
+        Value::object([
+            ("file_size", self.file_size.into()),
+            ("strippable", self.strippable.into()),
+            ("stripped_size", self.stripped_size().into()),
+            ("unattributed", self.unattributed.into()),
+            ("files", self.entries.to_value()),
+        ])
+    }
+}
+
+impl ToValue for NamespaceEntry {
+    fn to_value(&self) -> Value {
+        Value::object([
+            ("namespace", self.namespace.as_str().into()),
+            ("size", self.size.into()),
+            ("symbols", self.symbols.into()),
+            ("files", self.files.into()),
+            ("top_file", self.top_file.as_deref().into()),
+        ])
+    }
+}
+
+impl ToValue for NamespaceReport {
+    fn to_value(&self) -> Value {
+        Value::object([
+            ("total", self.total.into()),
+            ("namespaces", self.entries.to_value()),
+        ])
+    }
+}
+
+impl ToValue for StackUser {
+    fn to_value(&self) -> Value {
+        Value::object([
+            ("function", self.function.name.as_str().into()),
+            ("location", self.function.location.as_str().into()),
+            ("bytes", self.function.bytes.into()),
+            ("qualifier", self.function.qualifier.name().into()),
+            ("file", self.filename.as_str().into()),
+            ("section", self.section.as_deref().into()),
+        ])
+    }
+}
+
+impl ToValue for StackEntry {
+    fn to_value(&self) -> Value {
+        Value::object([
+            ("file", self.filename.as_str().into()),
+            ("functions", self.functions.into()),
+            ("worst", self.worst.as_str().into()),
+            ("worst_bytes", self.worst_bytes.into()),
+            ("unbounded", self.unbounded.into()),
+        ])
+    }
+}
+
+impl ToValue for SectionStackEntry {
+    fn to_value(&self) -> Value {
+        Value::object([
+            ("section", self.section.as_str().into()),
+            ("function", self.function.as_str().into()),
+            ("bytes", self.bytes.into()),
+            ("file", self.filename.as_str().into()),
+        ])
+    }
+}
+
+impl ToValue for StackReport {
+    fn to_value(&self) -> Value {
+        Value::object([
+            ("files", self.entries.to_value()),
+            ("sections", self.sections.to_value()),
+            ("functions", self.functions.to_value()),
+        ])
+    }
+}
+
+impl ToValue for SnapshotSection {
+    fn to_value(&self) -> Value {
+        Value::object([
+            ("name", self.name.as_str().into()),
+            ("vaddr", self.vaddr.into()),
+            ("size", self.size.into()),
+        ])
+    }
+}
+
+impl ToValue for Contribution {
+    fn to_value(&self) -> Value {
+        Value::object([
+            ("file", self.filename.as_str().into()),
+            ("name", self.name.as_str().into()),
+            ("vaddr", self.vaddr.into()),
+            ("size", self.size.into()),
+            ("file_offset", self.file_offset.into()),
+            (
+                "folded_with",
+                Value::Array(self.folded_with.iter().map(|f| f.as_str().into()).collect()),
+            ),
+        ])
+    }
+}
+
+impl ToValue for HierarchySection {
+    fn to_value(&self) -> Value {
+        Value::object([
+            ("name", self.name.as_str().into()),
+            ("vaddr", self.vaddr.into()),
+            ("size", self.size.into()),
+            ("file_offset", self.file_offset.into()),
+            ("attributed", self.attributed().into()),
+            ("contributions", self.contributions.to_value()),
+        ])
+    }
+}
+
+impl ToValue for Segment {
+    fn to_value(&self) -> Value {
+        Value::object([
+            ("name", self.name.as_str().into()),
+            ("vaddr", self.vaddr.into()),
+            ("size", self.size.into()),
+            ("file_offset", self.file_offset.into()),
+            ("file_size", self.file_size.into()),
+            ("permissions", self.permissions.as_str().into()),
+            ("sections", self.sections.to_value()),
+        ])
+    }
+}
+
+impl ToValue for Hierarchy {
+    fn to_value(&self) -> Value {
+        Value::object([
+            ("version", HIERARCHY_VERSION.into()),
+            (
+                "binary",
+                Value::object([("format", self.format.into()), ("size", self.size.into())]),
+            ),
+            ("segments", self.segments.to_value()),
+            ("sections", self.sections.to_value()),
+        ])
+    }
+}
+
+impl ToValue for Snapshot {
+    fn to_value(&self) -> Value {
+        Value::object([
+            ("version", SNAPSHOT_VERSION.into()),
+            ("sections", self.sections.to_value()),
+        ])
+    }
+}
+
+impl ToValue for Build {
+    fn to_value(&self) -> Value {
+        Value::object([
+            ("version", HISTORY_VERSION.into()),
+            ("timestamp", self.timestamp.into()),
+            ("label", self.label.as_deref().into()),
+            ("total", self.total.into()),
+            (
+                "components",
+                Value::object(
+                    self.components
+                        .iter()
+                        .map(|(name, size)| (name.as_str(), (*size).into())),
+                ),
+            ),
+        ])
+    }
+}
+
+impl ToValue for TrendEntry {
+    fn to_value(&self) -> Value {
+        Value::object([
+            ("timestamp", self.timestamp.into()),
+            ("label", self.label.as_deref().into()),
+            ("size", self.size.into()),
+            ("delta", self.delta.into()),
+        ])
+    }
+}
+
+impl ToValue for TrendReport {
+    fn to_value(&self) -> Value {
+        Value::object([
+            ("component", self.component.as_deref().into()),
+            ("builds", self.entries.to_value()),
+            ("delta", self.delta().into()),
+        ])
+    }
+}
+
+impl ToValue for Drift {
+    fn to_value(&self) -> Value {
+        let (kind, section, change) = match self {
+            Drift::Moved { section, from, to } => ("moved", section, Some((from, to))),
+            Drift::Grew { section, from, to } => ("grew", section, Some((from, to))),
+            Drift::Added { section } => ("added", section, None),
+            Drift::Removed { section } => ("removed", section, None),
+        };
+        Value::object([
+            ("kind", kind.into()),
+            ("section", section.as_str().into()),
+            ("from", change.map(|(&from, _)| from).into()),
+            ("to", change.map(|(_, &to)| to).into()),
+        ])
+    }
+}
+
+impl ToValue for Vector {
+    fn to_value(&self) -> Value {
+        Value::object([
+            ("index", (self.index as u64).into()),
+            ("vector", self.name.as_str().into()),
+            ("handler", self.handler.into()),
+            ("thumb", self.thumb.into()),
+            ("symbol", self.symbol.as_deref().into()),
+            ("file", self.filename.as_deref().into()),
+            ("default", self.default.into()),
+            ("weak", self.weak.into()),
+        ])
+    }
+}
+
+impl ToValue for VectorTable {
+    fn to_value(&self) -> Value {
+        Value::object([
+            ("vaddr", self.vaddr.into()),
+            ("initial_sp", self.initial_sp.into()),
+            ("vectors", self.vectors.to_value()),
+        ])
+    }
+}
+
+impl ToValue for Warning {
+    fn to_value(&self) -> Value {
+        let (kind, details): (&str, Vec<(&str, Value)>) = match self {
+            Warning::SubsectionOutOfBounds {
+                section,
+                section_range: (start, end),
+                subsection,
+                filename,
+                start_vaddr,
+                size,
+            } => (
+                "subsection_out_of_bounds",
+                vec![
+                    ("section", section.as_str().into()),
+                    ("section_start", (*start).into()),
+                    ("section_end", (*end).into()),
+                    ("subsection", subsection.as_str().into()),
+                    ("file", filename.as_str().into()),
+                    ("start_vaddr", (*start_vaddr).into()),
+                    ("size", (*size).into()),
+                ],
+            ),
+            Warning::SectionNotInBinary { section } => (
+                "section_not_in_binary",
+                vec![("section", section.as_str().into())],
+            ),
+            Warning::SectionAddressMismatch {
+                section,
+                map_vaddr,
+                binary_vaddr,
+            } => (
+                "section_address_mismatch",
+                vec![
+                    ("section", section.as_str().into()),
+                    ("map_vaddr", (*map_vaddr).into()),
+                    ("binary_vaddr", (*binary_vaddr).into()),
+                ],
+            ),
+            Warning::UnalignedRawData {
+                section,
+                pointer_to_raw_data,
+                file_alignment,
+            } => (
+                "unaligned_raw_data",
+                vec![
+                    ("section", section.as_str().into()),
+                    ("pointer_to_raw_data", (*pointer_to_raw_data).into()),
+                    ("file_alignment", (*file_alignment).into()),
+                ],
+            ),
+            Warning::RawDataOutOfFile {
+                section,
+                raw_size,
+                available,
+            } => (
+                "raw_data_out_of_file",
+                vec![
+                    ("section", section.as_str().into()),
+                    ("raw_size", (*raw_size).into()),
+                    ("available", (*available).into()),
+                ],
+            ),
+            Warning::MalformedMapRegion { line, lines } => (
+                "malformed_map_region",
+                vec![
+                    ("line", (*line as u64).into()),
+                    ("lines", (*lines as u64).into()),
+                ],
+            ),
+            Warning::SymbolRuns { section, symbols } => (
+                "symbol_runs",
+                vec![
+                    ("section", section.as_str().into()),
+                    ("symbols", (*symbols as u64).into()),
+                ],
+            ),
+        };
+        Value::object(
+            [("kind", kind.into()), ("message", self.to_string().into())]
+                .into_iter()
+                .chain(details),
+        )
+    }
+}
+
+fn write_json(value: &Value, out: &mut String) {
+    match value {
+        Value::Null => out.push_str("null"),
+        Value::Bool(b) => out.push_str(if *b { "true" } else { "false" }),
+        Value::UInt(n) => write!(out, "{n}").expect("writing to a String"),
+        Value::Int(n) => write!(out, "{n}").expect("writing to a String"),
+        Value::Float(x) if x.is_finite() => write!(out, "{x}").expect("writing to a String"),
+        Value::Float(_) => out.push_str("null"),
+        Value::String(s) => write_quoted(s, out),
+        Value::Array(items) => {
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_json(item, out);
+            }
+            out.push(']');
+        }
+        Value::Object(entries) => {
+            out.push('{');
+            for (i, (key, item)) in entries.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_quoted(key, out);
+                out.push(':');
+                write_json(item, out);
+            }
+            out.push('}');
+        }
+    }
+}
+
+struct JsonParser<'a> {
+    json: &'a str,
+    position: usize,
+}
+
+impl JsonParser<'_> {
+    fn skip_whitespace(&mut self) {
+        let rest = &self.json[self.position..];
+        self.position += rest.len() - rest.trim_start_matches([' ', '\t', '\n', '\r']).len();
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.json.as_bytes().get(self.position).copied()
+    }
+
+    fn eat(&mut self, token: &str) -> bool {
+        let found = self.json[self.position..].starts_with(token);
+        if found {
+            self.position += token.len();
+        }
+        found
+    }
+
+    fn value(&mut self) -> Option<Value> {
+        self.skip_whitespace();
+        match self.peek()? {
+            b'{' => {
+                self.position += 1;
+                let mut entries = vec![];
+                self.skip_whitespace();
+                if !self.eat("}") {
+                    loop {
+                        self.skip_whitespace();
+                        let key = self.string()?;
+                        self.skip_whitespace();
+                        if !self.eat(":") {
+                            return None;
+                        }
+                        entries.push((key, self.value()?));
+                        self.skip_whitespace();
+                        if self.eat("}") {
+                            break;
+                        }
+                        if !self.eat(",") {
+                            return None;
+                        }
+                    }
+                }
+                Some(Value::Object(entries))
+            }
+            b'[' => {
+                self.position += 1;
+                let mut items = vec![];
+                self.skip_whitespace();
+                if !self.eat("]") {
+                    loop {
+                        items.push(self.value()?);
+                        self.skip_whitespace();
+                        if self.eat("]") {
+                            break;
+                        }
+                        if !self.eat(",") {
+                            return None;
+                        }
+                    }
+                }
+                Some(Value::Array(items))
+            }
+            b'"' => self.string().map(Value::String),
+            b't' => self.eat("true").then_some(Value::Bool(true)),
+            b'f' => self.eat("false").then_some(Value::Bool(false)),
+            b'n' => self.eat("null").then_some(Value::Null),
+            _ => self.number(),
+        }
+    }
+
+    fn number(&mut self) -> Option<Value> {
+        let rest = &self.json[self.position..];
+        let len = rest
+            .find(|c: char| !(c.is_ascii_digit() || matches!(c, '-' | '+' | '.' | 'e' | 'E')))
+            .unwrap_or(rest.len());
+        let number = &rest[..len];
+        self.position += len;
+        if let Ok(value) = number.parse() {
+            Some(Value::UInt(value))
+        } else if let Ok(value) = number.parse() {
+            Some(Value::Int(value))
+        } else {
+            number.parse().ok().map(Value::Float)
+        }
+    }
+
+    fn string(&mut self) -> Option<String> {
+        if !self.eat("\"") {
+            return None;
+        }
+        let mut out = String::new();
+        loop {
+            let rest = &self.json[self.position..];
+            let end = rest.find(['"', '\\'])?;
+            out.push_str(&rest[..end]);
+            self.position += end + 1;
+            if rest.as_bytes()[end] == b'"' {
+                return Some(out);
+            }
+            let escape = self.peek()?;
+            self.position += 1;
+            out.push(match escape {
+                b'"' => '"',
+                b'\\' => '\\',
+                b'/' => '/',
+                b'b' => '\u{8}',
+                b'f' => '\u{c}',
+                b'n' => '\n',
+                b'r' => '\r',
+                b't' => '\t',
+                b'u' => {
+                    let high = self.hex4()?;
+                    if (0xd800..0xdc00).contains(&high) && self.eat("\\u") {
+                        let low = self.hex4()?;
+                        char::from_u32(
+                            0x10000 + ((high - 0xd800) << 10) + low.checked_sub(0xdc00)?,
+                        )?
+                    } else {
+                        char::from_u32(high)?
+                    }
+                }
+                _ => return None,
+            });
+        }
+    }
+
+    fn hex4(&mut self) -> Option<u32> {
+        let digits = self.json.get(self.position..self.position + 4)?;
+        self.position += 4;
+        // `from_str_radix` alone would take a sign
+        if !digits.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return None;
+        }
+        u32::from_str_radix(digits, 16).ok()
+    }
+}
+
+/// Writes a double-quoted string, escaped so that it is valid both in JSON and in YAML.
+fn write_quoted(s: &str, out: &mut String) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                write!(out, "\\u{:04x}", c as u32).expect("writing to a String")
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+fn write_yaml_scalar(value: &Value, out: &mut String) {
+    match value {
+        Value::Null => out.push('~'),
+        Value::Array(_) => out.push_str("[]"),
+        Value::Object(_) => out.push_str("{}"),
+        _ => write_json(value, out),
+    }
+}
+
+/// Writes `key` plain when it reads back as the same string, quoted otherwise: keys that would
+/// read as null, booleans or numbers, or start a sequence item, are quoted.
+fn write_yaml_key(key: &str, out: &mut String) {
+    const RESERVED: [&str; 9] = ["null", "true", "false", "yes", "no", "on", "off", "y", "n"];
+    if key
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+        && !RESERVED
+            .iter()
+            .any(|reserved| key.eq_ignore_ascii_case(reserved))
+    {
+        out.push_str(key);
+    } else {
+        write_quoted(key, out);
+    }
+}
+
+/// Writes a non-empty collection in block style, every line indented by `indent` spaces.
+fn write_yaml(value: &Value, indent: usize, out: &mut String) {
+    match value {
+        Value::Object(entries) => {
+            for (key, item) in entries {
+                out.push_str(&" ".repeat(indent));
+                write_yaml_key(key, out);
+                out.push(':');
+                if item.is_inline_yaml() {
+                    out.push(' ');
+                    write_yaml_scalar(item, out);
+                    out.push('\n');
+                } else {
+                    out.push('\n');
+                    write_yaml(item, indent + 2, out);
+                }
+            }
+        }
+        Value::Array(items) => {
+            for item in items {
+                if item.is_inline_yaml() {
+                    out.push_str(&" ".repeat(indent));
+                    out.push_str("- ");
+                    write_yaml_scalar(item, out);
+                    out.push('\n');
+                } else {
+                    // Render the item one level deeper and turn its first indentation into the
+                    // sequence marker, which gives the usual compact `- key: value` layout.
+                    let mut nested = String::new();
+                    write_yaml(item, indent + 2, &mut nested);
+                    out.push_str(&" ".repeat(indent));
+                    out.push_str("- ");
+                    out.push_str(&nested[indent + 2..]);
+                }
+            }
+        }
+        scalar => {
+            write_yaml_scalar(scalar, out);
+            out.push('\n');
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Value;
+
+    #[test]
+    fn test_json_and_yaml() {
+        let value = Value::object([
+            ("total", Value::UInt(3)),
+            (
+                "files",
+                Value::Array(vec![
+                    Value::object([("file", "a \"b\".o".into()), ("size", Value::UInt(3))]),
+                    Value::Array(vec![]),
+                ]),
+            ),
+            ("empty", Value::object::<&str>([])),
+        ]);
+
+        assert_eq!(
+            value.to_json(),
+            r#"{"total":3,"files":[{"file":"a \"b\".o","size":3},[]],"empty":{}}"#
+        );
+        assert_eq!(
+            value.to_yaml(),
+            "total: 3\nfiles:\n  - file: \"a \\\"b\\\".o\"\n    size: 3\n  - []\nempty: {}\n"
+        );
+
+        assert_eq!(Value::from_json(&value.to_json()), Some(value));
+        let parsed = Value::from_json(r#" [ -1 , 2.5e1, true, null, "\u00e9\ud83d\ude00\n" ] "#);
+        assert_eq!(
+            parsed,
+            Some(Value::Array(vec![
+                Value::Int(-1),
+                Value::Float(25.0),
+                Value::Bool(true),
+                Value::Null,
+                "é😀\n".into(),
+            ]))
+        );
+        assert_eq!(Value::from_json("[1,]"), None);
+        assert_eq!(Value::from_json("{\"a\": 1} x"), None);
+        assert_eq!(Value::from_json(r#""\u+abc""#), None);
+
+        // Keys from data that would not read back as strings are quoted
+        let keys = Value::object(
+            ["null", "No", "123", "-", "-x", "libc.a", "main_o", "x-1"]
+                .map(|key| (key, Value::Null)),
+        );
+        assert_eq!(
+            keys.to_yaml(),
+            "\"null\": ~\n\"No\": ~\n\"123\": ~\n\"-\": ~\n\"-x\": ~\n\"libc.a\": ~\nmain_o: ~\nx-1: ~\n"
+        );
+    }
+}
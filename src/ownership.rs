@@ -0,0 +1,240 @@
+//! Sizes per owning team, joining the object files of a report with the sources they were
+//! compiled from (see [`CompileCommands`]) and the owners of those sources (`CODEOWNERS`).
+
+use std::{collections::HashMap, fmt::Display};
+
+use crate::{
+    compile_commands::{normalize, CompileCommands},
+    format::{human_size, percentage, Align, Cell, Style, Table},
+    Report,
+};
+
+/// Shown instead of the owners of files without a source or an owner.
+pub const UNOWNED: &str = "<unowned>";
+
+/// Owners of paths, from a `CODEOWNERS` file as used by GitHub and GitLab.
+#[derive(Clone, Debug, Default)]
+pub struct CodeOwners {
+    /// In file order, the last matching rule wins.
+    rules: Vec<(String, Vec<String>)>,
+}
+
+impl CodeOwners {
+    pub fn parse(text: &str) -> Self {
+        let rules = text
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#') && !line.starts_with('['))
+            .filter_map(|line| {
+                let mut fields = line.split_whitespace();
+                let pattern = fields.next()?.to_string();
+                Some((pattern, fields.map(str::to_string).collect()))
+            })
+            .collect();
+        Self { rules }
+    }
+
+    /// The owners of `path`, relative to the repository root.
+    pub fn owners_of(&self, path: &str) -> Option<&[String]> {
+        let path = path.trim_start_matches('/');
+        self.rules
+            .iter()
+            .rev()
+            .find(|(pattern, _)| pattern_matches(pattern, path))
+            .map(|(_, owners)| owners.as_slice())
+            .filter(|owners| !owners.is_empty())
+    }
+}
+
+/// Size per owner, see [`Report::by_owner`].
+#[derive(Clone, Debug)]
+pub struct OwnerReport {
+    /// Biggest first.
+    pub entries: Vec<OwnerEntry>,
+    pub total: u64,
+}
+
+#[derive(Clone, Debug)]
+pub struct OwnerEntry {
+    /// The owners of the files sharing them, space separated, or [`UNOWNED`].
+    pub owners: String,
+    pub size: u64,
+    pub files: u64,
+}
+
+impl Report {
+    /// Sums the entries of the report per owners of their source file. Sources outside of
+    /// `repository`, the root of the paths of `owners`, are [`UNOWNED`].
+    pub fn by_owner(
+        &self,
+        sources: &CompileCommands,
+        owners: &CodeOwners,
+        repository: &str,
+    ) -> OwnerReport {
+        let repository = normalize(repository);
+        let mut sizes: HashMap<String, (u64, u64)> = HashMap::new();
+        for entry in &self.entries {
+            let owner = sources
+                .source_for_object(&entry.filename)
+                .and_then(|source| source.strip_prefix(repository.as_str()))
+                .filter(|relative| relative.is_empty() || relative.starts_with('/'))
+                .and_then(|relative| owners.owners_of(relative))
+                .map_or_else(|| UNOWNED.to_string(), |owners| owners.join(" "));
+            let (size, files) = sizes.entry(owner).or_default();
+            *size += entry.size;
+            *files += 1;
+        }
+
+        let mut entries: Vec<OwnerEntry> = sizes
+            .into_iter()
+            .map(|(owners, (size, files))| OwnerEntry {
+                owners,
+                size,
+                files,
+            })
+            .collect();
+        entries.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.owners.cmp(&b.owners)));
+
+        OwnerReport {
+            entries,
+            total: self.total,
+        }
+    }
+}
+
+impl OwnerReport {
+    /// The table rendered by `Display`.
+    pub fn to_table(&self) -> Table {
+        let mut table = Table::new(&[Align::Right, Align::Right, Align::Right, Align::Left])
+            .with_header(["size", "share", "files", "owners"]);
+        for entry in &self.entries {
+            table.push_row([
+                human_size(entry.size),
+                percentage(entry.size, self.total),
+                entry.files.to_string(),
+                entry.owners.clone(),
+            ]);
+        }
+        table.push_row([
+            Cell::styled(human_size(self.total), Some(Style::Bold)),
+            percentage(self.total, self.total).into(),
+            self.entries
+                .iter()
+                .map(|e| e.files)
+                .sum::<u64>()
+                .to_string()
+                .into(),
+            "total".into(),
+        ]);
+        table
+    }
+}
+
+impl Display for OwnerReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_table())
+    }
+}
+
+/// Matches `path` against a `CODEOWNERS` pattern, which follows the `.gitignore` syntax: a
+/// pattern without a slash (other than a trailing one) matches at any depth, `*` does not match
+/// slashes and `**` does, and a pattern matching a directory matches everything in it.
+pub(crate) fn pattern_matches(pattern: &str, path: &str) -> bool {
+    let anchored = pattern.trim_end_matches('/').contains('/');
+    let pattern = pattern.trim_start_matches('/').trim_end_matches('/');
+    let components: Vec<&str> = path.split('/').collect();
+    let starts: Vec<usize> = if anchored {
+        vec![0]
+    } else {
+        (0..components.len()).collect()
+    };
+    // Every prefix of the path is a candidate, to match the directories it is in
+    starts.into_iter().any(|start| {
+        (start + 1..=components.len()).any(|end| {
+            glob_matches(
+                pattern.as_bytes(),
+                components[start..end].join("/").as_bytes(),
+            )
+        })
+    })
+}
+
+fn glob_matches(pattern: &[u8], text: &[u8]) -> bool {
+    match pattern {
+        [] => text.is_empty(),
+        [b'*', b'*', rest @ ..] => {
+            let rest = rest.strip_prefix(b"/").unwrap_or(rest);
+            (0..=text.len()).any(|i| glob_matches(rest, &text[i..]))
+        }
+        [b'*', rest @ ..] => (0..=text.len())
+            .take_while(|&i| i == 0 || text[i - 1] != b'/')
+            .any(|i| glob_matches(rest, &text[i..])),
+        [b'?', rest @ ..] => {
+            matches!(text, [c, text @ ..] if *c != b'/' && glob_matches(rest, text))
+        }
+        [c, rest @ ..] => matches!(text, [t, text @ ..] if t == c && glob_matches(rest, text)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Report, ReportEntry};
+
+    use crate::compile_commands::CompileCommands;
+
+    use super::{pattern_matches, CodeOwners, UNOWNED};
+
+    #[test]
+    fn test_by_owner() {
+        assert!(pattern_matches("*.c", "src/motor/control.c"));
+        assert!(pattern_matches("/src/motor/", "src/motor/control.c"));
+        assert!(pattern_matches("motor", "src/motor/control.c"));
+        assert!(pattern_matches("src/**/control.c", "src/motor/control.c"));
+        assert!(!pattern_matches("/motor/", "src/motor/control.c"));
+        assert!(!pattern_matches("src/*.c", "src/motor/control.c"));
+
+        let commands = CompileCommands::parse(
+            r#"[
+                {"directory": "/repo/build", "file": "../src/motor/control.c",
+                 "arguments": ["cc", "-c", "-o", "CMakeFiles/fw.dir/src/motor/control.c.o",
+                               "../src/motor/control.c"]},
+                {"directory": "/repo/build", "file": "/repo/src/net/tcp.c",
+                 "command": "cc -c -o net/tcp.o /repo/src/net/tcp.c"},
+                {"directory": "/repo/build", "file": "/repo/vendor/lz4.c",
+                 "output": "vendor/lz4.o", "command": "cc -c /repo/vendor/lz4.c"}
+            ]"#,
+        )
+        .unwrap();
+
+        let owners =
+            CodeOwners::parse("# Firmware\n* @firmware\n/src/motor/ @motion @safety\n/vendor/ \n");
+        let entry = |filename: &str, size| ReportEntry {
+            filename: filename.to_string(),
+            size,
+            source: None,
+        };
+        let report = Report {
+            entries: vec![
+                entry("CMakeFiles/fw.dir/src/motor/control.c.o", 300),
+                entry("net/tcp.o", 200),
+                entry("vendor/lz4.o", 50),
+                entry("libgcc.a(_udivdi3.o)", 10),
+            ],
+            total: 560,
+        };
+        let owners: Vec<_> = report
+            .by_owner(&commands, &owners, "/repo")
+            .entries
+            .into_iter()
+            .map(|e| (e.owners, e.size))
+            .collect();
+        assert_eq!(
+            owners,
+            [
+                ("@motion @safety".to_string(), 300),
+                ("@firmware".to_string(), 200),
+                (UNOWNED.to_string(), 60)
+            ]
+        );
+    }
+}
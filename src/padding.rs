@@ -0,0 +1,162 @@
+//! Alignment padding between the input sections of every output section. Not every toolchain
+//! writes `*fill*` lines for it, so it is computed from the subsection boundaries instead.
+
+use std::{collections::HashMap, fmt::Display};
+
+use crate::{
+    format::{human_size, percentage, Align, Cell, Style, Table},
+    Genealogy,
+};
+
+/// Padding per output section and per file, see [`Genealogy::padding_report`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PaddingReport {
+    /// Output sections in map order, including the ones without padding.
+    pub sections: Vec<SectionPadding>,
+    /// Files causing the most padding first.
+    pub entries: Vec<PaddingEntry>,
+    pub total: u64,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SectionPadding {
+    pub section: String,
+    pub size: u64,
+    pub padding: u64,
+}
+
+/// The padding inserted before the subsections of a file to align them.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PaddingEntry {
+    pub filename: String,
+    pub padding: u64,
+    /// Number of gaps the padding is made of.
+    pub gaps: u64,
+}
+
+impl Genealogy {
+    /// Sums the gaps between consecutive subsections of every output section. A gap is
+    /// attributed to the file of the subsection after it, whose alignment required it.
+    ///
+    /// Space before the first subsection and after the last one is not counted: it depends on
+    /// the neighbouring sections and on the linker script rather than on the input files.
+    pub fn padding_report(&self) -> PaddingReport {
+        let mut sections = vec![];
+        let mut files: HashMap<&str, (u64, u64)> = HashMap::new();
+        for section in self.sections() {
+            let mut subsections: Vec<_> = section
+                .subsections
+                .iter()
+                .filter(|subsection| subsection.size > 0)
+                .collect();
+            subsections.sort_by_key(|subsection| subsection.start_vaddr);
+
+            let mut padding = 0;
+            let mut end = None;
+            for subsection in subsections {
+                if let Some(end) = end.filter(|&end| subsection.start_vaddr > end) {
+                    let gap = subsection.start_vaddr - end;
+                    let (size, gaps) = files.entry(subsection.filename.as_str()).or_default();
+                    *size += gap;
+                    *gaps += 1;
+                    padding += gap;
+                }
+                let subsection_end = subsection.start_vaddr.saturating_add(subsection.size);
+                end = end.max(Some(subsection_end));
+            }
+            sections.push(SectionPadding {
+                section: section.name.clone(),
+                size: section.size,
+                padding,
+            });
+        }
+
+        let mut entries: Vec<PaddingEntry> = files
+            .into_iter()
+            .map(|(filename, (padding, gaps))| PaddingEntry {
+                filename: filename.to_string(),
+                padding,
+                gaps,
+            })
+            .collect();
+        entries.sort_by(|a, b| {
+            b.padding
+                .cmp(&a.padding)
+                .then_with(|| a.filename.cmp(&b.filename))
+        });
+
+        PaddingReport {
+            total: sections.iter().map(|s| s.padding).sum(),
+            sections,
+            entries,
+        }
+    }
+}
+
+impl PaddingReport {
+    /// The per-section table, sections without padding are left out.
+    pub fn sections_table(&self) -> Table {
+        let mut table = Table::new(&[Align::Right, Align::Right, Align::Left])
+            .with_header(["padding", "share", "section"]);
+        for section in self.sections.iter().filter(|s| s.padding > 0) {
+            table.push_row([
+                human_size(section.padding),
+                percentage(section.padding, section.size),
+                section.section.clone(),
+            ]);
+        }
+        table
+    }
+
+    /// The per-file table rendered by `Display`.
+    pub fn to_table(&self) -> Table {
+        let mut table = Table::new(&[Align::Right, Align::Right, Align::Left])
+            .with_header(["padding", "gaps", "file"]);
+        for entry in &self.entries {
+            table.push_row([
+                human_size(entry.padding),
+                entry.gaps.to_string(),
+                entry.filename.clone(),
+            ]);
+        }
+        table.push_row([
+            Cell::styled(human_size(self.total), Some(Style::Bold)),
+            self.entries
+                .iter()
+                .map(|e| e.gaps)
+                .sum::<u64>()
+                .to_string()
+                .into(),
+            format!("total in {} files", self.entries.len()).into(),
+        ]);
+        table
+    }
+}
+
+impl Display for PaddingReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_table())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Genealogy;
+
+    #[test]
+    fn test_padding_report() {
+        let mapfile = std::fs::read_to_string("tests/gcc/out.map").unwrap();
+        let binary = std::fs::read("tests/gcc/a.out").unwrap();
+        let report = Genealogy::new(&mapfile, &binary).unwrap().padding_report();
+
+        // `*fill*         0x0000000000001086        0xa` in `.text`
+        let text = report
+            .sections
+            .iter()
+            .find(|section| section.section == ".text")
+            .unwrap();
+        assert_eq!(text.padding, 0xa);
+        assert!(report.entries[0].filename.ends_with("crtbeginS.o"));
+        assert_eq!(report.total, report.entries.iter().map(|e| e.padding).sum());
+    }
+}
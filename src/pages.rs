@@ -0,0 +1,167 @@
+//! Attribution of the memory pages of the loaded image, to estimate how much of it processes can
+//! share and how the page cache sees it: a page is only as cold as the hottest file in it.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Display,
+};
+
+use crate::{
+    format::{hex, human_size, percentage, Align, Table},
+    Genealogy,
+};
+
+/// The page size of most targets.
+pub const DEFAULT_PAGE_SIZE: u64 = 4096;
+
+/// The pages holding attributed bytes, see [`Genealogy::page_report`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PageReport {
+    pub page_size: u64,
+    /// In address order.
+    pub pages: Vec<PageEntry>,
+    /// Number of pages with bytes of several files.
+    pub mixed: u64,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PageEntry {
+    /// Virtual address of the start of the page.
+    pub vaddr: u64,
+    /// The file with the most bytes in the page, the first in name order on ties.
+    pub dominant: String,
+    pub dominant_size: u64,
+    /// Bytes attributed to any file, at most the page size unless subsections overlap.
+    pub attributed: u64,
+    /// Number of files with bytes in the page.
+    pub files: u64,
+}
+
+impl PageEntry {
+    pub fn is_mixed(&self) -> bool {
+        self.files > 1
+    }
+}
+
+impl Genealogy {
+    /// Splits the loaded sections into pages of `page_size` bytes, aligned on virtual addresses,
+    /// and sums what each file contributes to every page. Pages without attributed bytes, such
+    /// as the ones of `.bss`, are left out.
+    ///
+    /// # Panics
+    ///
+    /// If `page_size` is 0.
+    pub fn page_report(&self, page_size: u64) -> PageReport {
+        assert!(page_size > 0, "pages cannot be empty");
+        let mut pages: BTreeMap<u64, HashMap<&str, u64>> = BTreeMap::new();
+        for hit in self.index().iter_vaddr() {
+            let mut start = hit.range.start;
+            while start < hit.range.end {
+                let page = start - start % page_size;
+                let end = hit.range.end.min(page.saturating_add(page_size));
+                *pages
+                    .entry(page)
+                    .or_default()
+                    .entry(hit.filename)
+                    .or_default() += end - start;
+                start = end;
+            }
+        }
+
+        let pages: Vec<PageEntry> = pages
+            .into_iter()
+            .map(|(vaddr, files)| {
+                let (dominant, dominant_size) = files
+                    .iter()
+                    .max_by(|a, b| a.1.cmp(b.1).then_with(|| b.0.cmp(a.0)))
+                    .map(|(&file, &size)| (file, size))
+                    .expect("pages are created with a file");
+                PageEntry {
+                    vaddr,
+                    dominant: dominant.to_string(),
+                    dominant_size,
+                    attributed: files.values().sum(),
+                    files: files.len() as u64,
+                }
+            })
+            .collect();
+        PageReport {
+            page_size,
+            mixed: pages.iter().filter(|page| page.is_mixed()).count() as u64,
+            pages,
+        }
+    }
+}
+
+impl PageReport {
+    /// The table rendered by `Display`.
+    pub fn to_table(&self) -> Table {
+        let mut table = Table::new(&[
+            Align::Left,
+            Align::Right,
+            Align::Right,
+            Align::Right,
+            Align::Left,
+        ])
+        .with_header(["page", "attributed", "files", "dominant", "dominant file"]);
+        for page in &self.pages {
+            table.push_row([
+                hex(page.vaddr),
+                human_size(page.attributed),
+                page.files.to_string(),
+                percentage(page.dominant_size, self.page_size),
+                page.dominant.clone(),
+            ]);
+        }
+        table
+    }
+}
+
+impl Display for PageReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_table())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Genealogy;
+
+    use super::DEFAULT_PAGE_SIZE;
+
+    #[test]
+    fn test_page_report() {
+        let mapfile = std::fs::read_to_string("tests/gcc/out.map").unwrap();
+        let binary = std::fs::read("tests/gcc/a.out").unwrap();
+        let genealogy = Genealogy::new(&mapfile, &binary).unwrap();
+
+        let report = genealogy.page_report(DEFAULT_PAGE_SIZE);
+        assert!(report.pages.windows(2).all(|w| w[0].vaddr < w[1].vaddr));
+        assert!(report
+            .pages
+            .iter()
+            .all(|page| page.vaddr % DEFAULT_PAGE_SIZE == 0));
+        assert_eq!(
+            report.pages.iter().map(|page| page.attributed).sum::<u64>(),
+            genealogy
+                .index()
+                .iter_vaddr()
+                .map(|hit| hit.range.end - hit.range.start)
+                .sum()
+        );
+
+        // `.text` is in the second page, shared by the start files and both objects
+        let text = report
+            .pages
+            .iter()
+            .find(|page| page.vaddr == 0x1000)
+            .unwrap();
+        assert!(text.is_mixed());
+        assert!(report.mixed >= 1);
+        assert!(text.files > 3);
+        assert!(text.dominant.ends_with("crtbeginS.o"));
+
+        // Pages of a single byte are never mixed
+        assert_eq!(genealogy.page_report(1).mixed, 0);
+    }
+}
@@ -0,0 +1,3537 @@
+//! Parsing of the GNU ld, LLVM lld, MSVC link, ld64, armlink, IAR ILINK, Green Hills, TI,
+//! SEGGER, Renesas and Microchip map files, and placement of their sections in the ELF, PE, Mach-O or
+//! WebAssembly binary they describe. Needs `std`, unlike the [`Index`](crate::Index) built from
+//! the result.
+
+use std::{borrow::Cow, collections::HashMap, fmt::Display, ops::Range};
+
+use goblin::{
+    elf::{
+        section_header::{SectionHeader, SHT_NOBITS},
+        Elf,
+    },
+    mach::{
+        constants::{
+            SECTION_TYPE, S_ATTR_DEBUG, S_GB_ZEROFILL, S_THREAD_LOCAL_ZEROFILL, S_ZEROFILL,
+        },
+        MachO,
+    },
+    pe::PE,
+};
+
+use crate::{
+    format::hex, index::range, scan, validate::Warning, wasm, GenealogyError, Section, SubSection,
+};
+
+fn parse_hex(text: &str) -> Result<u64, GenealogyError> {
+    u64::from_str_radix(text, 16)
+        .map_err(|_| GenealogyError::AddressOutOfRange(format!("0x{text} does not fit in 64 bits")))
+}
+
+/// The sections `.gnu.linkonce.<kind>.<symbol>` older toolchains emit vague linkage definitions
+/// in, and the `<section>.<symbol>` their COMDAT groups are named today.
+const LINKONCE_SECTIONS: [(&str, &str); 14] = [
+    (".gnu.linkonce.armexidx.", ".ARM.exidx."),
+    (".gnu.linkonce.armextab.", ".ARM.extab."),
+    (".gnu.linkonce.sb2.", ".sbss2."),
+    (".gnu.linkonce.s2.", ".sdata2."),
+    (".gnu.linkonce.sb.", ".sbss."),
+    (".gnu.linkonce.tb.", ".tbss."),
+    (".gnu.linkonce.td.", ".tdata."),
+    (".gnu.linkonce.lb.", ".lbss."),
+    (".gnu.linkonce.lr.", ".lrodata."),
+    (".gnu.linkonce.b.", ".bss."),
+    (".gnu.linkonce.d.", ".data."),
+    (".gnu.linkonce.l.", ".ldata."),
+    (".gnu.linkonce.r.", ".rodata."),
+    (".gnu.linkonce.t.", ".text."),
+];
+
+/// The name of an input section as a COMDAT section, e.g. `.text._ZN3FooC2Ev` for
+/// `.gnu.linkonce.t._ZN3FooC2Ev`, so that the definitions of legacy and current builds are
+/// named alike.
+pub(crate) fn normalize_section_name(name: &str) -> Cow<'_, str> {
+    LINKONCE_SECTIONS
+        .iter()
+        .find_map(|(linkonce, section)| {
+            let symbol = name.strip_prefix(linkonce)?;
+            Some(Cow::Owned(format!("{section}{symbol}")))
+        })
+        .unwrap_or(Cow::Borrowed(name))
+}
+
+/// `text` without the carriage returns ending its lines, which maps written or edited on Windows
+/// have on all or some of them: the parsers, their `$` anchors and the origins they extract
+/// then see the same lines on every platform. Line numbers are unchanged, and `text` is borrowed
+/// when it has no such line.
+pub fn normalize_line_endings(text: &str) -> Cow<'_, str> {
+    if !text.contains("\r\n") && !text.ends_with('\r') {
+        return Cow::Borrowed(text);
+    }
+    let mut normalized = String::with_capacity(text.len());
+    for line in text.split_inclusive('\n') {
+        let (line, line_break) = match line.strip_suffix('\n') {
+            Some(line) => (line, "\n"),
+            None => (line, ""),
+        };
+        normalized.push_str(line.trim_end_matches('\r'));
+        normalized.push_str(line_break);
+    }
+    Cow::Owned(normalized)
+}
+
+/// The 1-based line number of byte offsets of `text`, in O(log n) for n lines.
+fn line_numbers(text: &str) -> impl Fn(usize) -> usize {
+    let line_breaks: Vec<usize> = text.match_indices('\n').map(|(i, _)| i).collect();
+    move |offset| line_breaks.partition_point(|&i| i < offset) + 1
+}
+
+/// The linkers whose maps are understood, told apart by their headers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum MapfileFormat {
+    Gnu,
+    /// gold, whose maps are GNU ones listing the contents the linker generates, and the padding
+    /// between input sections, as `** <kind>` entries without a file.
+    Gold,
+    /// `out_in_space` is the width of the space between the `Out` and `In` columns.
+    Llvm {
+        out_in_space: usize,
+    },
+    Msvc,
+    /// `wasm-ld`, which lists file offsets instead of load addresses.
+    Wasm,
+    /// armlink of ARM Compiler 5 and 6, whose execution regions are the sections of the image.
+    Armlink,
+    /// IAR ILINK, whose placement directives hold the sections of the image.
+    Iar,
+    /// Green Hills `elxr`, the linker of MULTI.
+    Ghs,
+    /// The Apple linker and `ld64.lld`, whose maps list symbols rather than input sections.
+    Ld64,
+    /// The TI linkers of Code Composer Studio, `armcl`, `cl2000` and the others.
+    Ti,
+    /// The SEGGER linker of Embedded Studio, whose section detail lists the input sections.
+    Segger,
+    /// `rlink`, the optimizing linker of the Renesas CC-RX and CC-RL compilers.
+    Renesas,
+    /// The GNU ld of Microchip XC32 and XC16, whose maps have a memory-usage report listing the
+    /// output sections before the memory map.
+    Microchip,
+}
+
+/// The title of the part of armlink maps listing the execution regions, written with `--map`.
+const ARMLINK_MEMORY_MAP: &str = "Memory Map of the image";
+
+/// The titles of the parts of IAR ILINK maps listing the placement of the sections and the
+/// contribution of every module.
+const IAR_PLACEMENT_SUMMARY: &str = "*** PLACEMENT SUMMARY";
+const IAR_MODULE_SUMMARY: &str = "*** MODULE SUMMARY";
+
+/// The titles of the parts of Green Hills maps listing the sections of the image and the
+/// contribution of every module to them.
+const GHS_IMAGE_SUMMARY: &str = "Image Summary";
+const GHS_MODULE_SUMMARY: &str = "Module Summary";
+
+/// The title of the part of TI linker maps listing the output sections and their input
+/// sections.
+const TI_SECTION_ALLOCATION_MAP: &str = "SECTION ALLOCATION MAP";
+
+/// The title of the part of SEGGER linker maps listing the input sections by address, and the
+/// name of the linker in their link information.
+const SEGGER_SECTION_DETAIL: &str = "SECTION DETAIL";
+const SEGGER_LINKER: &str = "SEGGER";
+
+/// The titles of the parts of Renesas rlink maps listing the output sections, and the
+/// contribution of every file to them.
+const RENESAS_MAPPING_LIST: &str = "*** Mapping List ***";
+const RENESAS_SYMBOL_LIST: &str = "*** Symbol List ***";
+
+/// The titles of the memory-usage reports of Microchip XC32 and XC16 maps, whose tables have
+/// a line per output section laid out as in the memory map, `.text  0x9d000000  0x1a8  424`.
+const MICROCHIP_PIC32_REPORT: &str = "Microchip PIC32 Memory-Usage Report";
+const MICROCHIP_XC16_REPORT: &str = "Program Memory  [Origin = ";
+
+/// The title of the memory map of GNU ld maps.
+const GNU_MEMORY_MAP: &str = "Linker script and memory map";
+
+/// The title of the memory map of gold maps, `Linker script and memory map` in GNU ld ones.
+const GOLD_MEMORY_MAP: &str = "Memory map";
+
+/// The header of `wasm-ld` maps.
+const WASM_HEADER: [&str; 6] = ["Addr", "Off", "Size", "Out", "In", "Symbol"];
+
+pub(crate) fn mapfile_format(mapfile: &str) -> MapfileFormat {
+    let first_line = mapfile.lines().next().unwrap_or_default();
+    if first_line.split_whitespace().eq(WASM_HEADER) {
+        MapfileFormat::Wasm
+    } else if first_line.starts_with("# Path: ") {
+        MapfileFormat::Ld64
+    } else if let Some(out_in_space) = scan::llvm_header(mapfile) {
+        MapfileFormat::Llvm { out_in_space }
+    } else if mapfile.contains("Preferred load address is ") {
+        MapfileFormat::Msvc
+    } else if mapfile.contains(ARMLINK_MEMORY_MAP) || mapfile.contains("Image Symbol Table") {
+        MapfileFormat::Armlink
+    } else if mapfile.contains(SEGGER_SECTION_DETAIL) && mapfile.contains(SEGGER_LINKER) {
+        MapfileFormat::Segger
+    } else if mapfile.contains(IAR_PLACEMENT_SUMMARY) {
+        MapfileFormat::Iar
+    } else if mapfile.contains(GHS_IMAGE_SUMMARY) {
+        MapfileFormat::Ghs
+    } else if mapfile.contains(TI_SECTION_ALLOCATION_MAP) {
+        MapfileFormat::Ti
+    } else if mapfile.contains(RENESAS_MAPPING_LIST) {
+        MapfileFormat::Renesas
+    } else if mapfile.contains(MICROCHIP_PIC32_REPORT) || mapfile.contains(MICROCHIP_XC16_REPORT) {
+        MapfileFormat::Microchip
+    } else if mapfile
+        .lines()
+        .any(|line| line.trim_end() == GOLD_MEMORY_MAP)
+    {
+        MapfileFormat::Gold
+    } else {
+        MapfileFormat::Gnu
+    }
+}
+
+/// Parses the sections of `mapfile`. Malformed regions, e.g. in hand-edited or concatenated
+/// maps, are skipped up to the next line the parser can resume at and reported as warnings: the
+/// next output section header in LLVM maps, the next symbol in MSVC maps. The GNU matchers look
+/// for entries anywhere, so they resume at the next entry by construction.
+pub(crate) fn extract_mapfile(
+    mapfile: &str,
+) -> Result<(Vec<Section>, Vec<Warning>), GenealogyError> {
+    match mapfile_format(mapfile) {
+        MapfileFormat::Gnu => extract_gnu_mapfile(mapfile, 0),
+        MapfileFormat::Gold => extract_gold_mapfile(mapfile),
+        MapfileFormat::Llvm { out_in_space } => extract_llvm_mapfile(mapfile, out_in_space),
+        MapfileFormat::Msvc => extract_msvc_mapfile(mapfile),
+        MapfileFormat::Wasm => extract_wasm_mapfile(mapfile),
+        MapfileFormat::Armlink => extract_armlink_mapfile(mapfile),
+        MapfileFormat::Iar => extract_iar_mapfile(mapfile),
+        MapfileFormat::Ghs => extract_ghs_mapfile(mapfile),
+        MapfileFormat::Ld64 => extract_ld64_mapfile(mapfile),
+        MapfileFormat::Ti => extract_ti_mapfile(mapfile),
+        MapfileFormat::Segger => extract_segger_mapfile(mapfile),
+        MapfileFormat::Renesas => extract_renesas_mapfile(mapfile),
+        MapfileFormat::Microchip => extract_microchip_mapfile(mapfile),
+    }
+}
+
+/// The byte offsets of the `/DISCARD/` output sections of a GNU map, which list the input
+/// sections a linker script discards without an address nor a size of their own.
+fn gnu_discard_offsets(mapfile: &str) -> Vec<usize> {
+    let mut offsets: Vec<usize> = mapfile
+        .match_indices("\n/DISCARD/")
+        .map(|(i, _)| i + 1)
+        .collect();
+    if mapfile.starts_with("/DISCARD/") {
+        offsets.insert(0, 0);
+    }
+    offsets
+}
+
+/// Whether the map line at `offset` belongs to a `/DISCARD/` output section rather than to the
+/// section starting before it, both given as sorted byte offsets.
+fn is_discarded(discard_offsets: &[usize], section_offsets: &[usize], offset: usize) -> bool {
+    let discard = discard_offsets.partition_point(|&discard| discard < offset);
+    let section = section_offsets.partition_point(|&section| section < offset);
+    discard
+        .checked_sub(1)
+        .is_some_and(|i| section == 0 || discard_offsets[i] > section_offsets[section - 1])
+}
+
+/// The input sections GNU ld lists as discarded: before the memory map, e.g. COMDAT duplicates
+/// or sections removed by `--gc-sections`, and in the `/DISCARD/` output sections of the linker
+/// script. The symbols of ld64 maps dead stripping removed, without an address. Other formats
+/// do not list them.
+pub(crate) fn discarded_subsections(mapfile: &str) -> Result<Vec<SubSection>, GenealogyError> {
+    let memory_map = match mapfile_format(mapfile) {
+        MapfileFormat::Gnu | MapfileFormat::Gold => 0,
+        MapfileFormat::Microchip => microchip_memory_map(mapfile),
+        MapfileFormat::Ld64 => return Ok(ld64_dead_symbols(mapfile)),
+        _ => return Ok(vec![]),
+    };
+    let section_offsets: Vec<usize> = scan::gnu_sections(mapfile)
+        .map(|entry| entry.offset)
+        .filter(|&offset| offset >= memory_map)
+        .collect();
+    let discard_offsets = gnu_discard_offsets(mapfile);
+    let memory_map = section_offsets.first().copied().unwrap_or(mapfile.len());
+    let line = line_numbers(mapfile);
+    scan::gnu_subsections(mapfile)
+        .filter(|entry| {
+            entry.offset < memory_map
+                || is_discarded(&discard_offsets, &section_offsets, entry.offset)
+        })
+        .map(|entry| {
+            Ok(SubSection {
+                name: normalize_section_name(entry.name).into_owned(),
+                start_vaddr: parse_hex(entry.vrom)?,
+                start_file_offset: None,
+                size: parse_hex(entry.size)?,
+                filename: entry.file.to_string(),
+                folded_with: vec![],
+                line: Some(line(entry.offset)),
+            })
+        })
+        .collect()
+}
+
+/// Parses a GNU map whose output sections are listed from the byte offset `memory_map` on.
+fn extract_gnu_mapfile(
+    mapfile: &str,
+    memory_map: usize,
+) -> Result<(Vec<Section>, Vec<Warning>), GenealogyError> {
+    let line = line_numbers(mapfile);
+    // Extract all sections, don't fill subsections in yet
+    let (section_offsets, mut sections): (Vec<usize>, Vec<Section>) = scan::gnu_sections(mapfile)
+        .filter(|entry| entry.offset >= memory_map)
+        .map(|entry| {
+            Ok((
+                entry.offset,
+                Section {
+                    name: entry.name.into(),
+                    start_vaddr: parse_hex(entry.vrom)?,
+                    size: parse_hex(entry.size)?,
+                    subsections: vec![],
+                    start_file_offset: None,
+                    loaded: false,
+                    line: Some(line(entry.offset)),
+                },
+            ))
+        })
+        .collect::<Result<Vec<_>, GenealogyError>>()?
+        .into_iter()
+        .unzip();
+
+    // Assign each subsection to the closest section, unless a `/DISCARD/` is closer
+    let discard_offsets = gnu_discard_offsets(mapfile);
+    for entry in scan::gnu_subsections(mapfile) {
+        if is_discarded(&discard_offsets, &section_offsets, entry.offset) {
+            continue;
+        }
+        let subsection = SubSection {
+            name: normalize_section_name(entry.name).into_owned(),
+            start_vaddr: parse_hex(entry.vrom)?,
+            size: parse_hex(entry.size)?,
+            filename: entry.file.to_string(),
+            start_file_offset: None,
+            folded_with: vec![],
+            line: Some(line(entry.offset)),
+        };
+        // Find closest section
+        let section_index = section_offsets.partition_point(|&offset| offset <= entry.offset);
+        let Some(section) = section_index
+            .checked_sub(1)
+            .and_then(|i| sections.get_mut(i))
+        else {
+            continue;
+        };
+        // Deduplicated COMDAT sections may be listed for every object defining them, at the
+        // address of the kept one: their bytes are not counted again
+        match folded_into(section, &subsection).filter(|kept| kept.name == subsection.name) {
+            Some(kept) => {
+                if kept.filenames().all(|f| *f != subsection.filename) {
+                    kept.folded_with.push(subsection.filename);
+                }
+            }
+            None => section.subsections.push(subsection),
+        }
+    }
+
+    // Stripped-down maps only list the symbols of the sections
+    if sections
+        .iter()
+        .all(|section| section.subsections.is_empty())
+    {
+        let warnings = attribute_symbol_runs(mapfile, &section_offsets, &mut sections, &line)?;
+        return Ok((sections, warnings));
+    }
+    Ok((sections, vec![]))
+}
+
+/// Fills the subsections of GNU map sections without input sections from their symbols, the way
+/// MSVC maps are read: a run of symbols of one origin is taken to span from its first symbol to
+/// the next symbol of another origin, or to the end of the section. The origins come from the
+/// cross reference table of `--cref`; symbols it does not define are left unattributed, and so
+/// are local symbols.
+fn attribute_symbol_runs(
+    mapfile: &str,
+    section_offsets: &[usize],
+    sections: &mut [Section],
+    line: impl Fn(usize) -> usize,
+) -> Result<Vec<Warning>, GenealogyError> {
+    let cross_references = mapfile.find("\nCross Reference Table");
+    let definitions: HashMap<&str, &str> = cross_references.map_or_else(HashMap::new, |start| {
+        cross_reference_table(&mapfile[start..])
+            .into_iter()
+            .map(|(symbol, files)| (symbol, files.definition))
+            .collect()
+    });
+    let memory_map_end = cross_references.unwrap_or(mapfile.len());
+
+    let mut warnings = vec![];
+    for (i, section) in sections.iter_mut().enumerate() {
+        let text = section_offsets[i]
+            ..section_offsets
+                .get(i + 1)
+                .map_or(memory_map_end, |&next| next.min(memory_map_end));
+        let section_end = section.start_vaddr.saturating_add(section.size);
+        let first_line = line(text.start);
+        let mut symbols = vec![];
+        for (j, text) in mapfile.get(text).unwrap_or_default().lines().enumerate() {
+            let Some(symbol) = scan::gnu_symbol(text) else {
+                continue;
+            };
+            let vaddr = parse_hex(symbol.vrom)?;
+            if (section.start_vaddr..section_end).contains(&vaddr) {
+                symbols.push((vaddr, definitions.get(symbol.name).copied(), first_line + j));
+            }
+        }
+        symbols.sort_by_key(|&(vaddr, _, _)| vaddr);
+
+        let mut runs: Vec<(Range<u64>, &str, usize)> = vec![];
+        for (j, &(start, origin, line)) in symbols.iter().enumerate() {
+            let end = symbols.get(j + 1).map_or(section_end, |&(next, _, _)| next);
+            let Some(origin) = origin.filter(|_| start < end) else {
+                continue;
+            };
+            match runs.last_mut() {
+                Some((run, run_origin, _)) if *run_origin == origin && run.end == start => {
+                    run.end = end;
+                }
+                _ => runs.push((start..end, origin, line)),
+            }
+        }
+        if runs.is_empty() {
+            continue;
+        }
+        warnings.push(Warning::SymbolRuns {
+            section: section.name.clone(),
+            symbols: symbols.len(),
+        });
+        section.subsections = runs
+            .into_iter()
+            .map(|(run, origin, line)| SubSection {
+                name: section.name.clone(),
+                start_vaddr: run.start,
+                start_file_offset: None,
+                size: run.end - run.start,
+                filename: origin.to_string(),
+                folded_with: vec![],
+                line: Some(line),
+            })
+            .collect();
+    }
+    Ok(warnings)
+}
+
+/// A `** <kind>` entry of a gold map, ` ** fill        0x00000000000005a2        0xe`: the kind,
+/// the address and the size. Kinds too long for their column, such as `string table`, have the
+/// address and size on `next`, the line after.
+fn gold_entry<'a>(line: &'a str, next: Option<&str>) -> Option<(&'a str, u64, u64, bool)> {
+    let rest = line.strip_prefix(" ** ")?;
+    let (kind, fields, wrapped) = match rest.find(" 0x") {
+        Some(i) => (rest[..i].trim_end(), &rest[i..], false),
+        None => (rest.trim_end(), next?, true),
+    };
+    let mut fields = fields.split_whitespace();
+    let (Some(vaddr), Some(size), None) = (fields.next(), fields.next(), fields.next()) else {
+        return None;
+    };
+    let vaddr = parse_hex(vaddr.strip_prefix("0x")?).ok()?;
+    let size = parse_hex(size.strip_prefix("0x")?).ok()?;
+    (!kind.is_empty()).then_some((kind, vaddr, size, wrapped))
+}
+
+/// Parses a gold map: its input sections as a GNU map, and the `** <kind>` entries, e.g. the PLT,
+/// the merged `.eh_frame` or merged strings, as subsections of the linker named after their
+/// kind. Fills are padding. The file and segment headers before the first section are left out.
+fn extract_gold_mapfile(mapfile: &str) -> Result<(Vec<Section>, Vec<Warning>), GenealogyError> {
+    let (mut sections, mut warnings) = extract_gnu_mapfile(mapfile, 0)?;
+    let section_lines: Vec<usize> = sections
+        .iter()
+        .map(|section| section.line.unwrap_or_default())
+        .collect();
+
+    let mut lines = mapfile.lines().enumerate().peekable();
+    while let Some((i, line)) = lines.next() {
+        if !line.starts_with(" ** ") {
+            continue;
+        }
+        let line_number = i + 1;
+        let Some((kind, start_vaddr, size, wrapped)) =
+            gold_entry(line, lines.peek().map(|&(_, next)| next))
+        else {
+            warnings.push(Warning::MalformedMapRegion {
+                line: line_number,
+                lines: 1,
+            });
+            continue;
+        };
+        if wrapped {
+            lines.next();
+        }
+        let section_index = section_lines.partition_point(|&line| line <= line_number);
+        let Some(section) = section_index
+            .checked_sub(1)
+            .and_then(|i| sections.get_mut(i))
+        else {
+            continue;
+        };
+        let filename = match kind {
+            "fill" | "zero fill" => PADDING,
+            _ => "<linker>",
+        };
+        section.subsections.push(SubSection {
+            name: kind.to_string(),
+            start_vaddr,
+            start_file_offset: None,
+            size,
+            filename: filename.to_string(),
+            folded_with: vec![],
+            line: Some(line_number),
+        });
+    }
+    for section in &mut sections {
+        section
+            .subsections
+            .sort_by_key(|subsection| subsection.start_vaddr);
+    }
+    Ok((sections, warnings))
+}
+
+/// The byte offset of the memory map of a Microchip map, 0 without one.
+fn microchip_memory_map(mapfile: &str) -> usize {
+    mapfile.find(GNU_MEMORY_MAP).unwrap_or_default()
+}
+
+/// The address and size of the fields of a `*fill*` line, `0x9d00004d  0x3  ffffffff`.
+fn microchip_fill(fields: &str) -> Option<(u64, u64)> {
+    let mut fields = fields.split_whitespace();
+    let (Some(vaddr), Some(size), _, None) =
+        (fields.next(), fields.next(), fields.next(), fields.next())
+    else {
+        return None;
+    };
+    let vaddr = parse_hex(vaddr.strip_prefix("0x")?).ok()?;
+    let size = parse_hex(size.strip_prefix("0x")?).ok()?;
+    Some((vaddr, size))
+}
+
+/// Parses a Microchip XC32 or XC16 map as a GNU one from its memory map on, since the tables of
+/// the memory-usage report before it would read as output sections. The `*fill*` lines of the
+/// memory map, with the pattern of `--fill` after their size, are padding.
+fn extract_microchip_mapfile(
+    mapfile: &str,
+) -> Result<(Vec<Section>, Vec<Warning>), GenealogyError> {
+    let memory_map = microchip_memory_map(mapfile);
+    let (mut sections, mut warnings) = extract_gnu_mapfile(mapfile, memory_map)?;
+    let section_lines: Vec<usize> = sections
+        .iter()
+        .map(|section| section.line.unwrap_or_default())
+        .collect();
+
+    let first_line = line_numbers(mapfile)(memory_map);
+    for (i, line) in mapfile[memory_map..].lines().enumerate() {
+        let Some(fields) = line.strip_prefix(" *fill*") else {
+            continue;
+        };
+        let line_number = first_line + i;
+        let Some((start_vaddr, size)) = microchip_fill(fields) else {
+            warnings.push(Warning::MalformedMapRegion {
+                line: line_number,
+                lines: 1,
+            });
+            continue;
+        };
+        let section_index = section_lines.partition_point(|&line| line <= line_number);
+        let Some(section) = section_index
+            .checked_sub(1)
+            .and_then(|i| sections.get_mut(i))
+            .filter(|_| size > 0)
+        else {
+            continue;
+        };
+        section.subsections.push(SubSection {
+            name: "*fill*".to_string(),
+            start_vaddr,
+            start_file_offset: None,
+            size,
+            filename: PADDING.to_string(),
+            folded_with: vec![],
+            line: Some(line_number),
+        });
+    }
+    for section in &mut sections {
+        section
+            .subsections
+            .sort_by_key(|subsection| subsection.start_vaddr);
+    }
+    Ok((sections, warnings))
+}
+
+/// The files listing a symbol in the cross reference table of a GNU map.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct CrossReference<'a> {
+    /// The file defining the symbol, or the first one referencing it for undefined weak symbols.
+    pub definition: &'a str,
+    pub references: Vec<&'a str>,
+}
+
+/// The cross reference table GNU ld writes with `--cref`, by symbol. Empty for other maps.
+pub(crate) fn gnu_cross_references(mapfile: &str) -> HashMap<&str, CrossReference<'_>> {
+    mapfile
+        .find("\nCross Reference Table")
+        .map_or_else(HashMap::new, |start| {
+            cross_reference_table(&mapfile[start..])
+        })
+}
+
+/// Parses a cross reference table: the first file listed for a symbol defines it, the others
+/// reference it. Names too long for the symbol column are followed by a line break.
+fn cross_reference_table(table: &str) -> HashMap<&str, CrossReference<'_>> {
+    let mut lines = table.lines().skip_while(|line| !line.starts_with("Symbol"));
+    let Some(column) = lines.next().and_then(|header| header.find("File")) else {
+        return HashMap::new();
+    };
+    let mut symbols = HashMap::new();
+    // The symbol of the last definition, whose references follow
+    let mut current = None;
+    let mut wrapped = None;
+    for line in lines {
+        let line = line.trim_end();
+        if line.is_empty() {
+            continue;
+        }
+        if line.starts_with(char::is_whitespace) {
+            // The references, or the definition of a wrapped name
+            let file = line.trim_start();
+            if let Some(symbol) = wrapped.take() {
+                symbols.insert(
+                    symbol,
+                    CrossReference {
+                        definition: file,
+                        references: vec![],
+                    },
+                );
+                current = Some(symbol);
+            } else if let Some(entry) = current.and_then(|symbol| symbols.get_mut(symbol)) {
+                entry.references.push(file);
+            }
+            continue;
+        }
+        match (line.get(..column), line.get(column..)) {
+            (Some(symbol), Some(file)) if symbol.ends_with(' ') => {
+                let symbol = symbol.trim_end();
+                symbols.insert(
+                    symbol,
+                    CrossReference {
+                        definition: file.trim_start(),
+                        references: vec![],
+                    },
+                );
+                current = Some(symbol);
+            }
+            _ => {
+                wrapped = Some(line);
+                current = None;
+            }
+        }
+    }
+    symbols
+}
+
+fn extract_llvm_mapfile(
+    mapfile: &str,
+    out_in_len: usize,
+) -> Result<(Vec<Section>, Vec<Warning>), GenealogyError> {
+    enum EntryType {
+        Section(Section),
+        SubSection(SubSection),
+        Symbol,
+        /// An input section line without an origin.
+        Malformed,
+    }
+    fn capture_to_entry_type(
+        m: scan::LlvmEntry<'_>,
+        out_in_space: usize,
+        line: usize,
+    ) -> Result<EntryType, GenealogyError> {
+        let start_vaddr = parse_hex(m.vma)?;
+        let size = parse_hex(m.size)?;
+        Ok(if m.spaces == 1 {
+            // Section header
+            EntryType::Section(Section {
+                name: m.name.to_string(),
+                start_vaddr,
+                start_file_offset: None,
+                size,
+                loaded: false,
+                subsections: vec![],
+                line: Some(line),
+            })
+        } else if m.spaces == 1 + 3 + out_in_space {
+            // A subsection
+            let Some((filename, mut name)) = m
+                .name
+                .strip_suffix(')')
+                .and_then(|name| name.split_once(":("))
+            else {
+                return Ok(EntryType::Malformed);
+            };
+            // Remove a potential +0xXXX substring for the subsection name, where XXX are hex digits
+            if let Some(plus_pos) = name.rfind("+0x") {
+                if name[plus_pos + 3..].chars().all(|c| c.is_ascii_hexdigit()) {
+                    // If it is indeed a +0xXXX suffix indicating the offset which we ignore
+                    name = &name[..plus_pos];
+                }
+            }
+            EntryType::SubSection(SubSection {
+                name: name.to_string(),
+                start_vaddr,
+                start_file_offset: None,
+                size,
+                filename: filename.to_string(),
+                folded_with: vec![],
+                line: Some(line),
+            })
+        } else {
+            // A symbol, ignore for now
+            EntryType::Symbol
+        })
+    }
+
+    let mut res = vec![];
+    let mut warnings = vec![];
+    let mut cur_section: Option<Section> = None;
+    // The first line and number of lines of the malformed region being skipped, which ends at
+    // the next section header
+    let mut skipped: Option<(usize, usize)> = None;
+
+    // Skip the header, already recognized
+    for (i, line) in mapfile.lines().enumerate().skip(1) {
+        let entry = match scan::llvm_entry(line) {
+            Some(entry) => capture_to_entry_type(entry, out_in_len, i + 1)?,
+            None if line.trim().is_empty() => continue,
+            None => EntryType::Malformed,
+        };
+        match (entry, &mut cur_section) {
+            (EntryType::Section(section), _) => {
+                if let Some((line, lines)) = skipped.take() {
+                    warnings.push(Warning::MalformedMapRegion { line, lines });
+                }
+                res.extend(cur_section.replace(section));
+            }
+            (_, _) if skipped.is_some() => {
+                skipped.as_mut().expect("skipping").1 += 1;
+            }
+            (EntryType::SubSection(subsection), Some(cur_section)) => {
+                // With `--icf`, lld may list the sections folded together at the same address
+                if let Some(kept) = folded_into(cur_section, &subsection) {
+                    if kept.filenames().all(|f| *f != subsection.filename) {
+                        kept.folded_with.push(subsection.filename);
+                    }
+                } else {
+                    cur_section.subsections.push(subsection);
+                }
+            }
+            (EntryType::Symbol, _) => {}
+            (EntryType::SubSection(_) | EntryType::Malformed, _) => skipped = Some((i + 1, 1)),
+        }
+    }
+
+    if let Some((line, lines)) = skipped {
+        warnings.push(Warning::MalformedMapRegion { line, lines });
+    }
+    res.extend(cur_section);
+
+    Ok((res, warnings))
+}
+
+/// The address, offset, size, indentation and name of a line of a `wasm-ld` map:
+/// `{addr:>8} {off:>8} {size:>8} ` then the name, indented by 8 spaces per level.
+fn wasm_entry(line: &str) -> Option<(&str, &str, &str, usize, &str)> {
+    let mut fields = [""; 3];
+    let mut rest = line;
+    for field in &mut fields {
+        let trimmed = rest.trim_start();
+        (*field, rest) = trimmed.split_at(trimmed.find(' ')?);
+    }
+    let rest = rest.strip_prefix(' ')?;
+    let name = rest.trim_start();
+    let [addr, off, size] = fields;
+    (!name.is_empty()).then_some((addr, off, size, rest.len() - name.len(), name))
+}
+
+/// Parses a `wasm-ld` map. Its sections are those of the module, at their file offset, and its
+/// data segments, at their address in linear memory. Function bodies have no address: they get
+/// their offset in the code section, as in DWARF for WebAssembly, and are placed in the file by
+/// [`map_sections_to_wasm`].
+fn extract_wasm_mapfile(mapfile: &str) -> Result<(Vec<Section>, Vec<Warning>), GenealogyError> {
+    let mut res = vec![];
+    let mut warnings = vec![];
+    // The section being filled and its file offset in the map
+    let mut cur_section: Option<(Section, u64)> = None;
+    let mut skipped: Option<(usize, usize)> = None;
+
+    for (i, line) in mapfile.lines().enumerate().skip(1) {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let Some((addr, off, size, indent, name)) = wasm_entry(line) else {
+            match &mut skipped {
+                Some((_, lines)) => *lines += 1,
+                None => skipped = Some((i + 1, 1)),
+            }
+            continue;
+        };
+        if let Some((line, lines)) = skipped.take() {
+            warnings.push(Warning::MalformedMapRegion { line, lines });
+        }
+        let vaddr = match addr {
+            "-" => None,
+            addr => Some(parse_hex(addr)?),
+        };
+        let (off, size) = (parse_hex(off)?, parse_hex(size)?);
+        match indent {
+            // A section or a data segment
+            0 => res.extend(
+                cur_section
+                    .replace((
+                        Section {
+                            name: name.to_string(),
+                            start_vaddr: vaddr.unwrap_or(0),
+                            start_file_offset: None,
+                            size,
+                            loaded: false,
+                            subsections: vec![],
+                            line: Some(i + 1),
+                        },
+                        off,
+                    ))
+                    .map(|(section, _)| section),
+            ),
+            // An input chunk: a function, a data segment of an object file or a custom section
+            8 => {
+                // Synthetic chunks, e.g. the globals of the linker, come from no file
+                let Some((filename, chunk)) = name
+                    .strip_suffix(')')
+                    .and_then(|name| name.split_once(":("))
+                else {
+                    continue;
+                };
+                let Some((section, section_off)) = &mut cur_section else {
+                    warnings.push(Warning::MalformedMapRegion {
+                        line: i + 1,
+                        lines: 1,
+                    });
+                    continue;
+                };
+                let start_vaddr = match vaddr {
+                    Some(vaddr) => vaddr,
+                    None => off.checked_sub(*section_off).ok_or_else(|| {
+                        GenealogyError::AddressOutOfRange(format!(
+                            "{chunk} at {} before its section at {}",
+                            hex(off),
+                            hex(*section_off)
+                        ))
+                    })?,
+                };
+                section.subsections.push(SubSection {
+                    name: chunk.to_string(),
+                    start_vaddr,
+                    start_file_offset: None,
+                    size,
+                    filename: filename.to_string(),
+                    folded_with: vec![],
+                    line: Some(i + 1),
+                });
+            }
+            // A symbol, ignore for now
+            _ => {}
+        }
+    }
+
+    if let Some((line, lines)) = skipped {
+        warnings.push(Warning::MalformedMapRegion { line, lines });
+    }
+    res.extend(cur_section.map(|(section, _)| section));
+
+    Ok((res, warnings))
+}
+
+/// The origin of the padding between two input sections: the `PAD` entries of armlink maps and
+/// the `** fill` ones of gold.
+const PADDING: &str = "<padding>";
+
+/// The value of `field` in the attributes of an armlink region, e.g. `0x08000000` for `Exec base`
+/// in `(Exec base: 0x08000000, Load base: 0x08000000, Size: 0x00000d18, ...)`.
+fn armlink_attribute(line: &str, field: &str) -> Option<u64> {
+    let (_, rest) = line.split_once(&format!("{field}: 0x"))?;
+    let hex_len = rest
+        .find(|c: char| !c.is_ascii_hexdigit())
+        .unwrap_or(rest.len());
+    u64::from_str_radix(&rest[..hex_len], 16).ok()
+}
+
+/// Parses the memory map of an armlink map. Every execution region is a section of the ELF
+/// image, named after the region, its zero-initialized tail a section of its own: the module
+/// contributions of every region are the subsections, along with the padding between them. Lines of the listing that do not parse are
+/// skipped up to the next execution region.
+fn extract_armlink_mapfile(mapfile: &str) -> Result<(Vec<Section>, Vec<Warning>), GenealogyError> {
+    // Written with `--symbols` only, the map has nothing to attribute
+    let offset = mapfile
+        .find(ARMLINK_MEMORY_MAP)
+        .ok_or(GenealogyError::WrongMapfileFormat)?;
+    let first_line = mapfile[..offset].matches('\n').count() + 1;
+
+    let mut res = vec![];
+    let mut warnings = vec![];
+    let mut cur_section: Option<Section> = None;
+    // Whether the listing of the region has a `Load Addr` column, as in ARM Compiler 6 maps, and
+    // whether its ZI data was reached
+    let mut load_column = false;
+    let mut zero_initialized = false;
+    let mut skipped: Option<(usize, usize)> = None;
+    for (i, line) in mapfile[offset..].lines().enumerate().skip(1) {
+        let line_number = first_line + i;
+        let trimmed = line.trim_start();
+        if let Some(region) = trimmed.strip_prefix("Execution Region ") {
+            if let Some((line, lines)) = skipped.take() {
+                warnings.push(Warning::MalformedMapRegion { line, lines });
+            }
+            let name = region.split_whitespace().next().unwrap_or_default();
+            let start_vaddr = armlink_attribute(trimmed, "Exec base")
+                .or_else(|| armlink_attribute(trimmed, "Base"))
+                .ok_or(GenealogyError::WrongMapfileFormat)?;
+            res.extend(cur_section.replace(Section {
+                name: name.to_string(),
+                start_vaddr,
+                start_file_offset: None,
+                size: armlink_attribute(trimmed, "Size").unwrap_or(0),
+                loaded: false,
+                subsections: vec![],
+                line: Some(line_number),
+            }));
+            zero_initialized = false;
+            continue;
+        }
+        if trimmed.starts_with("Exec Addr") || trimmed.starts_with("Base Addr") {
+            load_column = trimmed.contains("Load Addr");
+            continue;
+        }
+        // The component sizes follow the memory map
+        if trimmed.starts_with("Image component sizes") || trimmed.starts_with("=====") {
+            break;
+        }
+        if !trimmed.starts_with("0x") {
+            continue;
+        }
+        if let Some((_, lines)) = &mut skipped {
+            *lines += 1;
+            continue;
+        }
+
+        // `0x08000130   0x08000130   0x00000004   Code   RO    1    .text    main.o`, `*` in
+        // the `E` column marking the section of the entry point. Veneers, of type `Ven`, and
+        // the `Region$$Table` of scatter-loading come from `anon$$obj.o`
+        let mut fields = trimmed.split_whitespace();
+        let exec = fields.next();
+        if load_column {
+            fields.next();
+        }
+        let entry = exec
+            .zip(fields.next())
+            .zip(fields.next())
+            .and_then(|((exec, size), kind)| {
+                let (name, object) = if kind == "PAD" {
+                    // The alignment padding between two sections has no other column
+                    (kind, PADDING.to_string())
+                } else {
+                    fields.next()?; // attributes
+                    fields.next()?.parse::<u64>().ok()?;
+                    let mut name = fields.next()?;
+                    if name == "*" {
+                        name = fields.next()?;
+                    }
+                    (name, fields.collect::<Vec<_>>().join(" "))
+                };
+                (!object.is_empty()).then_some((
+                    parse_hex(exec.strip_prefix("0x")?).ok()?,
+                    parse_hex(size.strip_prefix("0x")?).ok()?,
+                    kind,
+                    name,
+                    object,
+                ))
+            });
+        let (Some((start_vaddr, size, kind, name, object)), Some(section)) =
+            (entry, &mut cur_section)
+        else {
+            skipped = Some((line_number, 1));
+            continue;
+        };
+        if kind == "Zero" && !zero_initialized {
+            zero_initialized = true;
+            // The ZI data after the RW data of a region is an `SHT_NOBITS` section of its name
+            if start_vaddr > section.start_vaddr {
+                let region_end = section.start_vaddr.saturating_add(section.size);
+                let zi = Section {
+                    name: section.name.clone(),
+                    start_vaddr,
+                    start_file_offset: None,
+                    size: region_end.saturating_sub(start_vaddr),
+                    loaded: false,
+                    subsections: vec![],
+                    line: section.line,
+                };
+                section.size = start_vaddr - section.start_vaddr;
+                res.extend(cur_section.replace(zi));
+            }
+        }
+        let section = cur_section.as_mut().expect("a current section");
+        section.subsections.push(SubSection {
+            name: name.to_string(),
+            start_vaddr,
+            start_file_offset: None,
+            size,
+            filename: object,
+            folded_with: vec![],
+            line: Some(line_number),
+        });
+    }
+
+    if let Some((line, lines)) = skipped {
+        warnings.push(Warning::MalformedMapRegion { line, lines });
+    }
+    res.extend(cur_section);
+    Ok((res, warnings))
+}
+
+/// A number of an IAR map, whose digits ILINK 8.20 and later group with `'`, e.g. `0x800'0040`
+/// or `8'192`.
+fn iar_number(text: &str) -> Option<u64> {
+    let digits = text.replace('\'', "");
+    match digits.strip_prefix("0x") {
+        Some(hex) => u64::from_str_radix(hex, 16).ok(),
+        None => digits.parse().ok(),
+    }
+}
+
+/// The directories and archives of the module summary of an IAR map by their number, e.g.
+/// `dl7M_tln.a` for `[2]` in `dl7M_tln.a: [2]`.
+fn iar_modules(mapfile: &str) -> HashMap<&str, &str> {
+    let Some(offset) = mapfile.find(IAR_MODULE_SUMMARY) else {
+        return HashMap::new();
+    };
+    mapfile[offset..]
+        .lines()
+        .filter_map(|line| {
+            let (container, number) = line.trim_end().rsplit_once(": ")?;
+            (number.starts_with('[') && number.ends_with(']')).then_some((number, container))
+        })
+        .collect()
+}
+
+/// The file of the object column of an IAR map, `main.o [1]` naming the object `main.o` of the
+/// first directory or archive of the module summary.
+fn iar_filename(object: &str, modules: &HashMap<&str, &str>) -> String {
+    let Some((member, number)) = object
+        .rsplit_once(' ')
+        .filter(|(_, number)| number.starts_with('['))
+    else {
+        return object.to_string();
+    };
+    match modules.get(number) {
+        Some(archive) if archive.ends_with(".a") || archive.ends_with(".lib") => {
+            format!("{archive}({member})")
+        }
+        Some(dir) => {
+            let separator = if dir.contains('\\') { '\\' } else { '/' };
+            format!("{}{separator}{member}", dir.trim_end_matches(separator))
+        }
+        None => member.to_string(),
+    }
+}
+
+/// The kind of an entry of an IAR placement summary, and the section of its placement it goes
+/// to: code and constants, initialized data, zero-initialized data and data left uninitialized.
+fn iar_kind(fields: &[&str]) -> Option<(usize, &'static str)> {
+    match fields {
+        [.., "rw", "code" | "data"] => Some((2, "rw")),
+        [.., "ro", "code" | "data"] => Some((2, "ro")),
+        [.., "const"] => Some((1, "ro")),
+        [.., "inited"] => Some((1, "rw")),
+        [.., "zero"] => Some((1, "zi")),
+        [.., "uninit" | "noinit"] => Some((1, "ui")),
+        _ => None,
+    }
+}
+
+/// Parses the placement summary of an IAR ILINK map. Every kind of content of a placement
+/// directive is a section, e.g. `P2 zi` for the zero-initialized data placed by `"P2"`, the
+/// entries the subsections; [`group_sections_by_address`] gives them the ELF sections holding them.
+/// The initializers of the data initialized by copy are attributed to the objects of the data
+/// when stored unpacked, and the entries of stacks and heaps to their block, e.g. `<CSTACK>`.
+/// Lines of the summary that do not parse are skipped up to the next placement directive.
+fn extract_iar_mapfile(mapfile: &str) -> Result<(Vec<Section>, Vec<Warning>), GenealogyError> {
+    let offset = mapfile
+        .find(IAR_PLACEMENT_SUMMARY)
+        .ok_or(GenealogyError::WrongMapfileFormat)?;
+    let first_line = mapfile[..offset].matches('\n').count() + 1;
+    let modules = iar_modules(mapfile);
+
+    let mut res = vec![];
+    let mut warnings = vec![];
+    // The label of the placement directive, and its sections by kind
+    let mut label = String::new();
+    let mut part: Vec<Section> = vec![];
+    // The block the entries indented past its indentation are in
+    let mut block: Option<(usize, String)> = None;
+    // The entries of the blocks of initialized data, by block
+    let mut init_blocks: HashMap<String, Vec<SubSection>> = HashMap::new();
+    // The name of an entry wrapped on the next line, as long section names are
+    let mut wrapped_name: Option<&str> = None;
+    let mut skipped: Option<(usize, usize)> = None;
+    // The placement directives of the configuration come before the table
+    let mut in_table = false;
+    for (i, line) in mapfile[offset..].lines().enumerate().skip(1) {
+        let line_number = first_line + i;
+        let trimmed = line.trim();
+        if !in_table {
+            in_table = trimmed.starts_with("Section") && trimmed.contains("Kind");
+            continue;
+        }
+        // The unused ranges or the next chapter of the map
+        if trimmed.starts_with("Unused ranges:") || trimmed.starts_with("*****") {
+            break;
+        }
+        if let Some(quoted) = trimmed.strip_prefix('"') {
+            if let Some((line, lines)) = skipped.take() {
+                warnings.push(Warning::MalformedMapRegion { line, lines });
+            }
+            res.append(&mut part);
+            label = quoted.split('"').next().unwrap_or_default().to_string();
+            block = None;
+            continue;
+        }
+        // The end of the range of the directive, `- 0x800'01e4  0x1a4`
+        if trimmed.starts_with('-') {
+            block = None;
+            continue;
+        }
+        if !trimmed.contains("0x") {
+            // Long section names are alone on their line, unlike the column titles
+            wrapped_name = match trimmed.split_whitespace().count() {
+                1 if !label.is_empty() => Some(trimmed),
+                _ => None,
+            };
+            continue;
+        }
+        if let Some((_, lines)) = &mut skipped {
+            *lines += 1;
+            continue;
+        }
+
+        // `.text  ro code  0x800'0040  0x8c  main.o [1]`, without a kind for blocks
+        let fields: Vec<&str> = trimmed.split_whitespace().collect();
+        let entry = fields
+            .iter()
+            .position(|field| field.starts_with("0x"))
+            .and_then(|address| {
+                let vaddr = iar_number(fields[address])?;
+                let size = iar_number(fields.get(address + 1)?)?;
+                let object = fields[address + 2..].join(" ");
+                let (kind_len, kind) = match iar_kind(&fields[..address]) {
+                    Some((len, kind)) => (len, Some(kind)),
+                    None => (0, None),
+                };
+                let name = match fields[..address - kind_len].join(" ") {
+                    name if name.is_empty() => wrapped_name?.to_string(),
+                    name => name,
+                };
+                (!object.is_empty() && !label.is_empty())
+                    .then_some((name, kind, vaddr, size, object))
+            });
+        wrapped_name = None;
+        let Some((name, kind, start_vaddr, size, object)) = entry else {
+            skipped = Some((line_number, 1));
+            continue;
+        };
+        let indent = line.len() - line.trim_start().len();
+        if block
+            .as_ref()
+            .is_some_and(|(block_indent, _)| indent <= *block_indent)
+        {
+            block = None;
+        }
+        let Some(kind) = kind else {
+            if object == "<Init block>" {
+                init_blocks.insert(name.clone(), vec![]);
+            }
+            block = Some((indent, name));
+            continue;
+        };
+
+        let filename = match (&block, object.as_str()) {
+            (Some((_, block)), "<Block tail>") => format!("<{block}>"),
+            _ => iar_filename(&object, &modules),
+        };
+        let subsection = SubSection {
+            name,
+            start_vaddr,
+            start_file_offset: None,
+            size,
+            filename,
+            folded_with: vec![],
+            line: Some(line_number),
+        };
+        if let Some(entries) = block
+            .as_ref()
+            .and_then(|(_, block)| init_blocks.get_mut(block))
+        {
+            entries.push(subsection.clone());
+        }
+        let section_name = format!("{label} {kind}");
+        let section = match part.iter().position(|section| section.name == section_name) {
+            Some(index) => &mut part[index],
+            None => {
+                part.push(Section {
+                    name: section_name,
+                    start_vaddr,
+                    start_file_offset: None,
+                    size: 0,
+                    loaded: false,
+                    subsections: vec![],
+                    line: Some(line_number),
+                });
+                part.last_mut().expect("a section")
+            }
+        };
+        let end = start_vaddr.saturating_add(size);
+        section.start_vaddr = section.start_vaddr.min(start_vaddr);
+        section.size = section.size.max(end.saturating_sub(section.start_vaddr));
+        section.subsections.push(subsection);
+    }
+    if let Some((line, lines)) = skipped {
+        warnings.push(Warning::MalformedMapRegion { line, lines });
+    }
+    res.append(&mut part);
+
+    // `Initializer bytes  const  0x800'0144  0xc  <for P2-1>`, a copy of the block unless packed
+    for section in &mut res {
+        section.subsections = std::mem::take(&mut section.subsections)
+            .into_iter()
+            .flat_map(|subsection| {
+                let entries = subsection
+                    .filename
+                    .strip_prefix("<for ")
+                    .and_then(|block| init_blocks.get(block.strip_suffix('>')?))
+                    .filter(|entries| {
+                        let start = entries.first().map_or(0, |entry| entry.start_vaddr);
+                        let end = entries
+                            .last()
+                            .map_or(0, |entry| entry.start_vaddr + entry.size);
+                        end - start == subsection.size
+                    });
+                match entries {
+                    Some(entries) => {
+                        let start = entries[0].start_vaddr;
+                        entries
+                            .iter()
+                            .map(|entry| SubSection {
+                                start_vaddr: subsection.start_vaddr + (entry.start_vaddr - start),
+                                ..entry.clone()
+                            })
+                            .collect()
+                    }
+                    None => vec![subsection],
+                }
+            })
+            .collect();
+    }
+    Ok((res, warnings))
+}
+
+/// The lines of the summary of a Green Hills map titled `title` and their 1-based numbers, up
+/// to the `Load Map <date>` title of the next one, without the blank ones and the column titles.
+fn ghs_summary<'a>(mapfile: &'a str, title: &str) -> Option<Vec<(usize, &'a str)>> {
+    let offset = mapfile.find(title)?;
+    let first_line = mapfile[..offset].matches('\n').count() + 1;
+    let lines = mapfile[offset..]
+        .lines()
+        .enumerate()
+        .skip(1)
+        .take_while(|(_, line)| !line.starts_with("Load Map"))
+        .filter(|(_, line)| !line.trim().is_empty())
+        .skip(1)
+        .map(|(i, line)| (first_line + i, line))
+        .collect();
+    Some(lines)
+}
+
+/// Parses the image and module summaries of a Green Hills `elxr` map: the sections of the
+/// image summary are those of the ELF file, the modules of the module summary their
+/// subsections. Runs of lines that do not parse are reported as one malformed region.
+fn extract_ghs_mapfile(mapfile: &str) -> Result<(Vec<Section>, Vec<Warning>), GenealogyError> {
+    let (image, modules) = ghs_summary(mapfile, GHS_IMAGE_SUMMARY)
+        .zip(ghs_summary(mapfile, GHS_MODULE_SUMMARY))
+        .ok_or(GenealogyError::WrongMapfileFormat)?;
+
+    let mut warnings = vec![];
+    let mut skipped: Option<(usize, usize)> = None;
+    let mut res = vec![];
+    // `  .text   00000100   00001230   4656   0000200`
+    for (line_number, line) in image {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let section = match fields.as_slice() {
+            [name, base, size, ..] => u64::from_str_radix(base, 16)
+                .ok()
+                .zip(u64::from_str_radix(size, 16).ok())
+                .map(|(start_vaddr, size)| Section {
+                    name: name.to_string(),
+                    start_vaddr,
+                    start_file_offset: None,
+                    size,
+                    loaded: false,
+                    subsections: vec![],
+                    line: Some(line_number),
+                }),
+            _ => None,
+        };
+        match section {
+            Some(section) => {
+                if let Some((line, lines)) = skipped.take() {
+                    warnings.push(Warning::MalformedMapRegion { line, lines });
+                }
+                res.push(section);
+            }
+            None => match &mut skipped {
+                Some((_, lines)) => *lines += 1,
+                None => skipped = Some((line_number, 1)),
+            },
+        }
+    }
+    if let Some((line, lines)) = skipped.take() {
+        warnings.push(Warning::MalformedMapRegion { line, lines });
+    }
+
+    // `00000100+000080  .text  main.o`, in the section of its name at its address, as sections
+    // may share a name
+    for (line_number, line) in modules {
+        let mut fields = line.split_whitespace();
+        let entry = fields
+            .next()
+            .and_then(|origin| origin.split_once('+'))
+            .and_then(|(start, size)| {
+                let start_vaddr = u64::from_str_radix(start, 16).ok()?;
+                let size = u64::from_str_radix(size, 16).ok()?;
+                let name = fields.next()?;
+                let module = fields.collect::<Vec<_>>().join(" ");
+                let index = res.iter().rposition(|section| {
+                    section.name == name
+                        && (section.start_vaddr..=section.start_vaddr.saturating_add(section.size))
+                            .contains(&start_vaddr)
+                })?;
+                (!module.is_empty()).then_some((index, name, start_vaddr, size, module))
+            });
+        let Some((index, name, start_vaddr, size, module)) = entry else {
+            match &mut skipped {
+                Some((_, lines)) => *lines += 1,
+                None => skipped = Some((line_number, 1)),
+            }
+            continue;
+        };
+        if let Some((line, lines)) = skipped.take() {
+            warnings.push(Warning::MalformedMapRegion { line, lines });
+        }
+        res[index].subsections.push(SubSection {
+            name: name.to_string(),
+            start_vaddr,
+            start_file_offset: None,
+            size,
+            filename: module,
+            folded_with: vec![],
+            line: Some(line_number),
+        });
+    }
+    if let Some((line, lines)) = skipped {
+        warnings.push(Warning::MalformedMapRegion { line, lines });
+    }
+    Ok((res, warnings))
+}
+
+/// An output section header of a TI linker map, `.text  0  0000026c  00000150`, as its name,
+/// origin and length. The page column, which tells program from data memory on C2000 parts, is
+/// optional.
+fn ti_output_section(line: &str) -> Option<(&str, u64, u64)> {
+    let mut fields = line.split_whitespace();
+    let name = fields.next()?;
+    let mut field = fields.next()?;
+    if field.len() < 4 && field.bytes().all(|b| b.is_ascii_digit()) {
+        field = fields.next()?;
+    }
+    let origin = u64::from_str_radix(field, 16).ok()?;
+    let length = u64::from_str_radix(fields.next()?, 16).ok()?;
+    Some((name, origin, length))
+}
+
+/// An input section of a TI linker map, `00000308  0000007a  main.obj (.text:main)`, as its
+/// address, size, file and name, `None` for `--HOLE--` fill. Library members,
+/// `rts.lib : memcpy_t2.asm.obj (.text)`, are named `rts.lib(memcpy_t2.asm.obj)`, the tables
+/// the linker generates, `(__TI_handler_table)`, `<linker>`. Notes such as
+/// `[fill = 0]` are dropped.
+fn ti_input_section(line: &str) -> Option<Option<(u64, u64, String, &str)>> {
+    let (origin, rest) = line.trim_start().split_once(char::is_whitespace)?;
+    let (length, rest) = rest.trim_start().split_once(char::is_whitespace)?;
+    let origin = u64::from_str_radix(origin, 16).ok()?;
+    let length = u64::from_str_radix(length, 16).ok()?;
+    let mut rest = rest.trim();
+    if rest.starts_with("--HOLE--") {
+        return Some(None);
+    }
+    if rest.ends_with(']') {
+        rest = rest[..rest.rfind('[')?].trim_end();
+    }
+    let open = rest.rfind('(')?;
+    let name = rest[open + 1..].strip_suffix(')')?;
+    let filename = match rest[..open].trim() {
+        "" => "<linker>".to_string(),
+        file => match file.split_once(" : ") {
+            Some((library, member)) => format!("{library}({member})"),
+            None => file.to_string(),
+        },
+    };
+    Some(Some((origin, length, filename, name)))
+}
+
+/// Whether a line of a TI linker map is the title of the part after the section allocation
+/// map, e.g. `MODULE SUMMARY` or `GLOBAL SYMBOLS: SORTED ALPHABETICALLY BY Name`.
+fn is_ti_title(line: &str) -> bool {
+    let words: Vec<&str> = line.split_whitespace().take(2).collect();
+    words.len() == 2
+        && words.iter().all(|word| {
+            let word = word.trim_end_matches(':');
+            word.len() > 1 && word.bytes().all(|b| b.is_ascii_uppercase())
+        })
+}
+
+/// Parses the section allocation map of a TI linker map, written by `armcl`, `cl2000` and the
+/// other Code Composer linkers with `--map_file`: its output sections are those of the ELF
+/// file, with their input sections as subsections. Output section names too long for their
+/// column are written on a line of their own, the header continuing on the next one after a
+/// `*`. Runs of lines that do not parse are reported as one malformed region.
+fn extract_ti_mapfile(mapfile: &str) -> Result<(Vec<Section>, Vec<Warning>), GenealogyError> {
+    let offset = mapfile
+        .find(TI_SECTION_ALLOCATION_MAP)
+        .ok_or(GenealogyError::WrongMapfileFormat)?;
+    let first_line = mapfile[..offset].matches('\n').count() + 1;
+    let mut lines = mapfile[offset..]
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (first_line + i, line));
+    // The column titles end with a line of dashes
+    lines
+        .by_ref()
+        .find(|(_, line)| line.starts_with("--"))
+        .ok_or(GenealogyError::WrongMapfileFormat)?;
+
+    let mut res: Vec<Section> = vec![];
+    let mut warnings = vec![];
+    let mut skipped: Option<(usize, usize)> = None;
+    let mut wrapped_name: Option<&str> = None;
+    for (line_number, line) in lines {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let indented = line.starts_with(char::is_whitespace);
+        if !indented && is_ti_title(line) {
+            break;
+        }
+        let parsed = if indented {
+            match ti_input_section(line) {
+                Some(Some((start_vaddr, size, filename, name))) if !res.is_empty() => {
+                    let section = res.last_mut().expect("not empty");
+                    section.subsections.push(SubSection {
+                        name: name.to_string(),
+                        start_vaddr,
+                        start_file_offset: None,
+                        size,
+                        filename,
+                        folded_with: vec![],
+                        line: Some(line_number),
+                    });
+                    true
+                }
+                Some(None) => true,
+                _ => false,
+            }
+        } else if let Some(header) = line.strip_prefix('*') {
+            let header = wrapped_name.take().map(|name| format!("{name} {header}"));
+            match header.as_deref().and_then(ti_output_section) {
+                Some((name, start_vaddr, size)) => {
+                    res.push(Section {
+                        name: name.to_string(),
+                        start_vaddr,
+                        start_file_offset: None,
+                        size,
+                        loaded: false,
+                        subsections: vec![],
+                        line: Some(line_number - 1),
+                    });
+                    true
+                }
+                None => false,
+            }
+        } else if line.split_whitespace().count() == 1 {
+            wrapped_name = Some(line.trim());
+            true
+        } else if let Some((name, start_vaddr, size)) = ti_output_section(line) {
+            res.push(Section {
+                name: name.to_string(),
+                start_vaddr,
+                start_file_offset: None,
+                size,
+                loaded: false,
+                subsections: vec![],
+                line: Some(line_number),
+            });
+            true
+        } else {
+            false
+        };
+        if !parsed {
+            match &mut skipped {
+                Some((_, lines)) => *lines += 1,
+                None => skipped = Some((line_number, 1)),
+            }
+            continue;
+        }
+        if let Some((line, lines)) = skipped.take() {
+            warnings.push(Warning::MalformedMapRegion { line, lines });
+        }
+    }
+    if let Some((line, lines)) = skipped {
+        warnings.push(Warning::MalformedMapRegion { line, lines });
+    }
+    Ok((res, warnings))
+}
+
+/// An entry of the symbols of an ld64 map, `0x1000003D0  0x00000020  [  1] _main`, its address
+/// `None` for the `<<dead>>` ones dead stripping removed: the address, the size, the number of
+/// the object file and the name, which may contain spaces, e.g. `literal string: Hello`.
+fn ld64_entry(line: &str) -> Option<(Option<u64>, u64, usize, &str)> {
+    let (address, rest) = line.split_once(char::is_whitespace)?;
+    let vaddr = match address {
+        "<<dead>>" => None,
+        address => Some(parse_hex(address.strip_prefix("0x")?).ok()?),
+    };
+    let rest = rest.trim_start();
+    let (size, rest) = rest.split_once(char::is_whitespace)?;
+    let size = parse_hex(size.strip_prefix("0x")?).ok()?;
+    let (index, name) = rest.trim_start().strip_prefix('[')?.split_once(']')?;
+    Some((vaddr, size, index.trim().parse().ok()?, name.trim()))
+}
+
+/// The architecture of an ld64 map, `# Arch: arm64`.
+pub(crate) fn ld64_arch(mapfile: &str) -> Option<&str> {
+    mapfile
+        .lines()
+        .take_while(|line| line.starts_with('#'))
+        .find_map(|line| line.strip_prefix("# Arch: "))
+        .map(str::trim)
+}
+
+/// The object files of an ld64 map by number, `[  2] /build/libemit.a(emit.o)`.
+fn ld64_objects(mapfile: &str) -> HashMap<usize, &str> {
+    mapfile
+        .lines()
+        .skip_while(|line| !line.starts_with("# Object files:"))
+        .skip(1)
+        .take_while(|line| !line.starts_with('#'))
+        .filter_map(|line| {
+            let (index, path) = line.strip_prefix('[')?.split_once(']')?;
+            Some((index.trim().parse().ok()?, path.trim()))
+        })
+        .collect()
+}
+
+/// The symbols ld64 lists as dead stripped, as subsections without an address.
+fn ld64_dead_symbols(mapfile: &str) -> Vec<SubSection> {
+    let objects = ld64_objects(mapfile);
+    mapfile
+        .lines()
+        .enumerate()
+        .filter_map(|(i, line)| {
+            let (None, size, index, name) = ld64_entry(line)? else {
+                return None;
+            };
+            Some(SubSection {
+                name: name.to_string(),
+                start_vaddr: 0,
+                start_file_offset: None,
+                size,
+                filename: objects.get(&index)?.to_string(),
+                folded_with: vec![],
+                line: Some(i + 1),
+            })
+        })
+        .collect()
+}
+
+/// An entry of the section detail of a SEGGER linker map, from its fields after the range:
+/// `main  42  16  Code  rx  main.o`, as its name without brackets, kind and object, or
+/// `( ALIGN .=.+6 )` for the alignment gaps, without either.
+fn segger_entry<'a>(fields: &[&'a str]) -> Option<(&'a str, Option<(&'a str, String)>)> {
+    if fields.first()? == &"(" {
+        return fields.contains(&")").then_some(("", None));
+    }
+    let [name, size, _align, kind, _access, object @ ..] = fields else {
+        return None;
+    };
+    if object.is_empty() || !size.bytes().all(|b| b.is_ascii_digit() || b == b',') {
+        return None;
+    }
+    let name = name
+        .strip_prefix('[')
+        .and_then(|name| name.strip_suffix(']'))
+        .unwrap_or(name);
+    Some((name, Some((*kind, object.join(" ")))))
+}
+
+/// Parses the section detail of a SEGGER linker map, written by the linker of Embedded Studio
+/// with `--map-file`. Its entries are the input sections by address, named after the symbol
+/// they define, or in brackets after the section when they define none or several. The runs of
+/// entries of the same kind, `Code`, `Cnst`, `Init` or `Zero`, are the sections, which
+/// [`group_sections_by_address`] splits into the ELF sections holding them, and the alignment
+/// gaps go to [`PADDING`]. Sizes are taken from the inclusive ranges, `00000020-00000049`.
+/// Names too long for their column are alone on their line. Runs of lines that do not parse are
+/// reported as one malformed region.
+fn extract_segger_mapfile(mapfile: &str) -> Result<(Vec<Section>, Vec<Warning>), GenealogyError> {
+    let offset = mapfile
+        .find(SEGGER_SECTION_DETAIL)
+        .ok_or(GenealogyError::WrongMapfileFormat)?;
+    let first_line = mapfile[..offset].matches('\n').count() + 1;
+    let mut res: Vec<Section> = vec![];
+    let mut warnings = vec![];
+    // The line, range and name of an entry whose columns are on the next line
+    let mut wrapped: Option<(usize, &str, &str)> = None;
+    let mut skipped: Option<(usize, usize)> = None;
+    // The section of the last entry, which the alignment gap after it belongs to
+    let mut previous: Option<usize> = None;
+    // The column titles come before the table
+    let mut in_table = false;
+    for (i, line) in mapfile[offset..].lines().enumerate().skip(1) {
+        let line_number = first_line + i;
+        let trimmed = line.trim();
+        if !in_table {
+            in_table = trimmed.starts_with("-----");
+            continue;
+        }
+        if trimmed.is_empty() || trimmed.starts_with("*****") {
+            break;
+        }
+        let mut fields: Vec<&str> = trimmed.split_whitespace().collect();
+        let (entry_line, range) = match wrapped.take() {
+            Some((entry_line, range, name)) if line.starts_with("   ") => {
+                fields.insert(0, name);
+                (entry_line, range)
+            }
+            stale => {
+                // A name alone whose columns are not on the next line
+                if let Some((stale_line, ..)) = stale {
+                    match &mut skipped {
+                        Some((_, lines)) => *lines += 1,
+                        None => skipped = Some((stale_line, 1)),
+                    }
+                    previous = None;
+                }
+                let range = fields.remove(0);
+                if let [name] = fields[..] {
+                    wrapped = Some((line_number, range, name));
+                    continue;
+                }
+                (line_number, range)
+            }
+        };
+        let entry = range
+            .split_once('-')
+            .and_then(|(start, end)| {
+                let start = u64::from_str_radix(start, 16).ok()?;
+                let end = u64::from_str_radix(end, 16).ok()?;
+                Some((start, end.checked_add(1)?.checked_sub(start)?))
+            })
+            .zip(segger_entry(&fields));
+        let Some(((start_vaddr, size), (name, kind_and_object))) = entry else {
+            match &mut skipped {
+                Some((_, lines)) => *lines += 1,
+                None => skipped = Some((line_number, 1)),
+            }
+            previous = None;
+            continue;
+        };
+        if let Some((line, lines)) = skipped.take() {
+            warnings.push(Warning::MalformedMapRegion { line, lines });
+        }
+
+        let (index, name, filename) = match kind_and_object {
+            Some((kind, object)) => {
+                let index = match res.iter().rposition(|section| section.name == kind) {
+                    Some(index) => index,
+                    None => {
+                        res.push(Section {
+                            name: kind.to_string(),
+                            start_vaddr,
+                            start_file_offset: None,
+                            size: 0,
+                            loaded: false,
+                            subsections: vec![],
+                            line: Some(entry_line),
+                        });
+                        res.len() - 1
+                    }
+                };
+                (index, name.to_string(), object)
+            }
+            // The gap belongs to the section of the entry it follows
+            None => match previous {
+                Some(index) if size > 0 => (index, "ALIGN".to_string(), PADDING.to_string()),
+                _ => continue,
+            },
+        };
+        previous = Some(index);
+        let section = &mut res[index];
+        let end = start_vaddr.saturating_add(size);
+        section.start_vaddr = section.start_vaddr.min(start_vaddr);
+        section.size = section.size.max(end.saturating_sub(section.start_vaddr));
+        section.subsections.push(SubSection {
+            name,
+            start_vaddr,
+            start_file_offset: None,
+            size,
+            filename,
+            folded_with: vec![],
+            line: Some(entry_line),
+        });
+    }
+    if let Some((line, lines)) = skipped {
+        warnings.push(Warning::MalformedMapRegion { line, lines });
+    }
+    Ok((res, warnings))
+}
+
+/// The start and size of a range of a Renesas rlink map, `00000100  00000166  67`, in hex.
+fn renesas_range(fields: &[&str]) -> Option<(u64, u64)> {
+    let [start, end, size, ..] = fields else {
+        return None;
+    };
+    let start = u64::from_str_radix(start, 16).ok()?;
+    u64::from_str_radix(end, 16).ok()?;
+    Some((start, u64::from_str_radix(size, 16).ok()?))
+}
+
+/// Parses a map of the Renesas optimizing linker, `rlink`, of CC-RX and CC-RL: the sections of
+/// its mapping list are those of the ELF file, the files of its symbol list, written with
+/// `-show=symbol`, their subsections. Both list the names on their line and the range on the
+/// next one, short section names may share it. Runs of lines that do not parse are reported as
+/// one malformed region.
+fn extract_renesas_mapfile(mapfile: &str) -> Result<(Vec<Section>, Vec<Warning>), GenealogyError> {
+    let offset = mapfile
+        .find(RENESAS_MAPPING_LIST)
+        .ok_or(GenealogyError::WrongMapfileFormat)?;
+    let line = line_numbers(mapfile);
+    let mut res: Vec<Section> = vec![];
+    let mut warnings = vec![];
+    let mut skipped: Option<(usize, usize)> = None;
+    let skip = |skipped: &mut Option<(usize, usize)>, line_number| match skipped {
+        Some((_, lines)) => *lines += 1,
+        None => *skipped = Some((line_number, 1)),
+    };
+
+    // `P`, then `00000100  00000166  67  1`
+    let first_line = line(offset);
+    let mut name: Option<(usize, &str)> = None;
+    for (i, text) in mapfile[offset..].lines().enumerate().skip(1) {
+        let line_number = first_line + i;
+        let trimmed = text.trim();
+        if trimmed.starts_with("***") {
+            break;
+        }
+        if trimmed.is_empty() || trimmed.starts_with("SECTION ") {
+            continue;
+        }
+        let fields: Vec<&str> = trimmed.split_whitespace().collect();
+        let section = match (name.take(), renesas_range(&fields)) {
+            (Some((name_line, name)), Some(range)) => Some((name_line, name, range)),
+            (pending, _) => {
+                if let Some((name_line, _)) = pending {
+                    skip(&mut skipped, name_line);
+                }
+                if let [field] = fields[..] {
+                    name = Some((line_number, field));
+                    continue;
+                }
+                renesas_range(&fields[1..]).map(|range| (line_number, fields[0], range))
+            }
+        };
+        let Some((name_line, name, (start_vaddr, size))) = section else {
+            skip(&mut skipped, line_number);
+            continue;
+        };
+        if let Some((line, lines)) = skipped.take() {
+            warnings.push(Warning::MalformedMapRegion { line, lines });
+        }
+        res.push(Section {
+            name: name.to_string(),
+            start_vaddr,
+            start_file_offset: None,
+            size,
+            loaded: false,
+            subsections: vec![],
+            line: Some(name_line),
+        });
+    }
+    if let Some((line, lines)) = skipped.take() {
+        warnings.push(Warning::MalformedMapRegion { line, lines });
+    }
+
+    // `SECTION=P`, then `FILE=main.obj` and its range for every file, then its symbols
+    let Some(offset) = mapfile.find(RENESAS_SYMBOL_LIST) else {
+        return Ok((res, warnings));
+    };
+    let first_line = line(offset);
+    let mut section_name = "";
+    let mut file: Option<(usize, &str)> = None;
+    for (i, text) in mapfile[offset..].lines().enumerate().skip(1) {
+        let line_number = first_line + i;
+        let trimmed = text.trim();
+        if trimmed.starts_with("***") {
+            break;
+        }
+        if let Some(name) = trimmed.strip_prefix("SECTION=") {
+            section_name = name.trim();
+            continue;
+        }
+        if let Some(filename) = trimmed.strip_prefix("FILE=") {
+            // The column titles come first, without a section
+            if !section_name.is_empty() {
+                file = Some((line_number, filename.trim()));
+            }
+            continue;
+        }
+        // The symbols of the file and their ranges
+        let Some((file_line, filename)) = file.take() else {
+            continue;
+        };
+        let fields: Vec<&str> = trimmed.split_whitespace().collect();
+        let entry = renesas_range(&fields).and_then(|(start_vaddr, size)| {
+            let index = res.iter().rposition(|section| {
+                section.name == section_name
+                    && (section.start_vaddr..=section.start_vaddr.saturating_add(section.size))
+                        .contains(&start_vaddr)
+            })?;
+            Some((index, start_vaddr, size))
+        });
+        let Some((index, start_vaddr, size)) = entry else {
+            skip(&mut skipped, file_line);
+            continue;
+        };
+        if let Some((line, lines)) = skipped.take() {
+            warnings.push(Warning::MalformedMapRegion { line, lines });
+        }
+        res[index].subsections.push(SubSection {
+            name: section_name.to_string(),
+            start_vaddr,
+            start_file_offset: None,
+            size,
+            filename: filename.to_string(),
+            folded_with: vec![],
+            line: Some(file_line),
+        });
+    }
+    if let Some((line, lines)) = skipped {
+        warnings.push(Warning::MalformedMapRegion { line, lines });
+    }
+    Ok((res, warnings))
+}
+
+/// Parses an ld64 map, written by the Apple linker and `ld64.lld` with `-map`. Its sections,
+/// named `<segment>,<section>` after the Mach-O sections, e.g. `__TEXT,__text`, have the
+/// symbols of the objects as subsections, the `<<dead>>` ones being left out. Runs of symbols
+/// that do not parse or lie out of the sections are reported as one malformed region.
+fn extract_ld64_mapfile(mapfile: &str) -> Result<(Vec<Section>, Vec<Warning>), GenealogyError> {
+    let objects = ld64_objects(mapfile);
+    let mut res: Vec<Section> = vec![];
+    let mut warnings = vec![];
+    let mut skipped: Option<(usize, usize)> = None;
+    // The part of the map being read, after its `# Sections:` or `# Symbols:` title
+    let mut part = "";
+    for (i, line) in mapfile.lines().enumerate() {
+        let line_number = i + 1;
+        // Titles end with a colon, unlike the column titles and `# Path: <binary>`
+        if let Some(title) = line.strip_prefix("# ") {
+            if title.ends_with(':') {
+                part = title;
+            }
+            continue;
+        }
+        match part {
+            // `0x1000003D0  0x00000030  __TEXT  __text`
+            "Sections:" => {
+                let fields: Vec<&str> = line.split_whitespace().collect();
+                let [address, size, segment, section] = fields.as_slice() else {
+                    return Err(GenealogyError::WrongMapfileFormat);
+                };
+                let parse = |field: &str| {
+                    parse_hex(
+                        field
+                            .strip_prefix("0x")
+                            .ok_or(GenealogyError::WrongMapfileFormat)?,
+                    )
+                };
+                res.push(Section {
+                    name: format!("{segment},{section}"),
+                    start_vaddr: parse(address)?,
+                    start_file_offset: None,
+                    size: parse(size)?,
+                    loaded: false,
+                    subsections: vec![],
+                    line: Some(line_number),
+                });
+            }
+            "Symbols:" => {
+                let entry = ld64_entry(line).and_then(|(vaddr, size, index, name)| {
+                    let Some(vaddr) = vaddr else {
+                        return Some(None);
+                    };
+                    let section = res.iter().rposition(|section| {
+                        (section.start_vaddr..=section.start_vaddr.saturating_add(section.size))
+                            .contains(&vaddr)
+                    })?;
+                    Some(Some((section, vaddr, size, objects.get(&index)?, name)))
+                });
+                let Some(entry) = entry else {
+                    match &mut skipped {
+                        Some((_, lines)) => *lines += 1,
+                        None => skipped = Some((line_number, 1)),
+                    }
+                    continue;
+                };
+                if let Some((line, lines)) = skipped.take() {
+                    warnings.push(Warning::MalformedMapRegion { line, lines });
+                }
+                // Dead stripped, see `ld64_dead_symbols`
+                let Some((section, start_vaddr, size, filename, name)) = entry else {
+                    continue;
+                };
+                res[section].subsections.push(SubSection {
+                    name: name.to_string(),
+                    start_vaddr,
+                    start_file_offset: None,
+                    size,
+                    filename: filename.to_string(),
+                    folded_with: vec![],
+                    line: Some(line_number),
+                });
+            }
+            _ => {}
+        }
+    }
+    if let Some((line, lines)) = skipped {
+        warnings.push(Warning::MalformedMapRegion { line, lines });
+    }
+    Ok((res, warnings))
+}
+
+/// Moves the subsections of an IAR or SEGGER map from the sections of their placement
+/// directives, or of their kind, to the ELF sections holding them by address, named after the
+/// directives by ILINK but split and merged on its own terms. Subsections out of the alloc
+/// sections are left where they are.
+pub(crate) fn group_sections_by_address(sections: &mut Vec<Section>, elf: &Elf, vaddr_bias: i64) {
+    // The alloc section headers, with their address range in the address space of the map
+    let headers: Vec<(&str, Range<u64>)> = elf
+        .section_headers
+        .iter()
+        .filter(|shdr| shdr.is_alloc() && shdr.sh_size > 0)
+        .filter_map(|shdr| {
+            let name = elf.shdr_strtab.get_at(shdr.sh_name)?;
+            let start = shdr.sh_addr.checked_add_signed(vaddr_bias.checked_neg()?)?;
+            Some((name, start..start.checked_add(shdr.sh_size)?))
+        })
+        .collect();
+    let mut grouped: Vec<Option<Section>> = headers.iter().map(|_| None).collect();
+    let mut rest = vec![];
+    for mut section in sections.drain(..) {
+        let mut kept = vec![];
+        for subsection in std::mem::take(&mut section.subsections) {
+            let Some(index) = headers
+                .iter()
+                .position(|(_, range)| range.contains(&subsection.start_vaddr))
+            else {
+                kept.push(subsection);
+                continue;
+            };
+            let (name, range) = &headers[index];
+            grouped[index]
+                .get_or_insert_with(|| Section {
+                    name: name.to_string(),
+                    start_vaddr: range.start,
+                    start_file_offset: None,
+                    size: range.end - range.start,
+                    loaded: false,
+                    subsections: vec![],
+                    line: section.line,
+                })
+                .subsections
+                .push(subsection);
+        }
+        if !kept.is_empty() {
+            section.subsections = kept;
+            rest.push(section);
+        }
+    }
+    sections.extend(grouped.into_iter().flatten());
+    sections.extend(rest);
+}
+
+/// The subsection of `section` that `subsection` was folded into: the last non-empty one, when it
+/// has the same address and size.
+fn folded_into<'a>(
+    section: &'a mut Section,
+    subsection: &SubSection,
+) -> Option<&'a mut SubSection> {
+    if subsection.size == 0 {
+        return None;
+    }
+    section
+        .subsections
+        .iter_mut()
+        .rev()
+        .find(|previous| previous.size > 0)
+        .filter(|previous| {
+            previous.start_vaddr == subsection.start_vaddr && previous.size == subsection.size
+        })
+}
+
+fn extract_msvc_mapfile(mapfile: &str) -> Result<(Vec<Section>, Vec<Warning>), GenealogyError> {
+    // We don't have the same information for msvc mapfiles as we havec for other kinds
+    // However, msvc mapfiles will (should ?) only ever be associated with PE binaries.
+    // The PE reader provides the size and file pointer for each section, while the mapfile
+    // provides information about "subsections" relative to the start of the section.
+    // Thus, we will not really use the names of the (sub-)sections, and instead refer to them by
+    // index, and the virtual addresses are not absolute (too cumbersome) but relative to the
+    // vaddr of the section start.
+    // Furtermore, because msvc mapfiles do not really contain the same information as other
+    // types of mapfiles (subsection with origin), we will have to "cheat" a little bit and instead
+    // try to find subsection boundaries with origins by looking at the static symbol offsets and supposing
+    // that in a contiguous section of symbols from the same origin, everything in between has also the same origin
+    // The section contributions (`.text$mn`, `.xdata`...) listed at the top name the subsections
+    let groups: Vec<(u64, Range<u64>, &str)> = scan::msvc_groups(mapfile)
+        .filter_map(|group| {
+            let section_nb = u64::from_str_radix(group.section, 16).ok()?;
+            let start = u64::from_str_radix(group.start, 16).ok()?;
+            let length = u64::from_str_radix(group.length, 16).ok()?;
+            Some((section_nb, start..start.checked_add(length)?, group.name))
+        })
+        .collect();
+    let group_name = |section_nb: u64, section_offset: u64| {
+        groups
+            .iter()
+            .find(|(nb, range, _)| *nb == section_nb && range.contains(&section_offset))
+            .map_or("", |&(_, _, name)| name)
+    };
+
+    // Find the offset of the static symbols section
+    let offset = mapfile
+        .find(" Static symbols")
+        .ok_or(GenealogyError::WrongMapfileFormat)?;
+
+    let first_line = mapfile[..offset].matches('\n').count() + 1;
+    // skip the " Static symbols" line and the following newline
+    let lines = mapfile[offset..].lines().enumerate().skip(2);
+
+    // Let's go
+    let mut res = vec![];
+    let mut warnings = vec![];
+    // Lines that are not symbols are skipped up to the next symbol, the list ends at the last one
+    let mut skipped: Option<(usize, usize)> = None;
+    let mut current_filename = None;
+    let mut current_start_offset = 0;
+    let mut current_line = 0;
+    let mut current_section_nb = 0;
+    let mut current_group = "";
+
+    let mut prev_section_offset = 0;
+    for (i, line) in lines {
+        let Some((symbol, origin)) =
+            scan::msvc_symbol(line).and_then(|symbol| Some((symbol, symbol.origin?)))
+        else {
+            if !line.trim().is_empty() {
+                skipped.get_or_insert((first_line + i, 0)).1 += 1;
+            }
+            continue;
+        };
+        if let Some((line, lines)) = skipped.take() {
+            warnings.push(Warning::MalformedMapRegion { line, lines });
+        }
+        let section_nb = u64::from_str_radix(symbol.section, 16)
+            .map_err(|_| GenealogyError::WrongMapfileFormat)?;
+        let section_offset = u64::from_str_radix(symbol.section_offset, 16)
+            .map_err(|_| GenealogyError::WrongMapfileFormat)?;
+        // Contributions without content (import placeholders, `.debug$S` of import libraries)
+        // are listed at the image base, at a negative offset from their section
+        if section_offset > i32::MAX as u64 {
+            continue;
+        }
+        // let vaddr = u64::from_str_radix(&capture["vaddr"], 16)
+        //    .map_err(|_| GenealogyError::WrongMapfileFormat)?;
+        while res.len() <= section_nb as usize {
+            res.push(Section {
+                name: "".into(),
+                start_vaddr: 0,
+                start_file_offset: None,
+                size: 0,
+                loaded: false,
+                subsections: vec![],
+                line: None,
+            });
+        }
+        let filename = origin
+            .split(':')
+            .next()
+            .expect("at least one element in split iterator")
+            .to_string();
+        let group = group_name(section_nb, section_offset);
+        if current_filename.is_none() {
+            current_filename = Some(filename);
+            current_start_offset = section_offset;
+            current_line = first_line + i;
+            current_section_nb = section_nb;
+            current_group = group;
+        } else if let Some(current_filename_value) = &current_filename {
+            // Change current values and push subsection if needed
+            if current_filename_value != &filename
+                || current_section_nb != section_nb
+                || current_group != group
+            {
+                res[current_section_nb as usize]
+                    .subsections
+                    .push(SubSection {
+                        name: current_group.to_string(),
+                        start_vaddr: current_start_offset, // /!\ not actually the vaddr but it's easier to do so
+                        start_file_offset: None,
+                        size: run_size(current_start_offset, prev_section_offset)?,
+                        filename: current_filename_value.clone(),
+                        folded_with: vec![],
+                        line: Some(current_line),
+                    });
+                current_filename = Some(filename);
+                current_start_offset = section_offset;
+                current_line = first_line + i;
+                current_section_nb = section_nb;
+                current_group = group;
+            }
+        }
+
+        prev_section_offset = section_offset;
+    }
+
+    // The last one
+    if let Some(filename) = current_filename {
+        res[current_section_nb as usize]
+            .subsections
+            .push(SubSection {
+                name: current_group.to_string(),
+                start_vaddr: current_start_offset, // /!\ not actually the vaddr but it's easier to do so
+                start_file_offset: None,
+                size: run_size(current_start_offset, prev_section_offset)?,
+                filename,
+                folded_with: vec![],
+                line: Some(current_line),
+            });
+    }
+
+    Ok((res, warnings))
+}
+
+/// The size of a run of symbols of one origin, from the first to the last symbol offset: an
+/// underestimation, but the map gives nothing better.
+fn run_size(start_offset: u64, last_offset: u64) -> Result<u64, GenealogyError> {
+    last_offset
+        .checked_sub(start_offset)
+        .and_then(|size| size.checked_add(1))
+        .ok_or_else(|| {
+            GenealogyError::AddressOutOfRange(format!(
+                "static symbols out of order at section offset {}",
+                hex(last_offset)
+            ))
+        })
+}
+
+/// Places the MSVC sections using the PE section headers.
+///
+/// Packed or hand-crafted images may have sections whose raw data is unaligned, does not cover
+/// their virtual size, or lies past the end of the file: only the bytes actually present in the
+/// file get file offsets, and the oddities are reported as warnings.
+pub(crate) fn map_msvc_sections_to_pe(
+    sections: &mut [Section],
+    pe: &PE,
+    binary_len: u64,
+) -> Result<Vec<Warning>, GenealogyError> {
+    let mut warnings = vec![];
+    let file_alignment = pe
+        .header
+        .optional_header
+        .map(|header| u64::from(header.windows_fields.file_alignment))
+        .filter(|&alignment| alignment > 0);
+
+    for (section_nb, section) in sections.iter_mut().enumerate() {
+        if section_nb == 0 {
+            continue;
+        }
+        let Some(pe_section) = pe.sections.get(section_nb - 1) else {
+            if !section.subsections.is_empty() {
+                warnings.push(Warning::SectionNotInBinary {
+                    section: format!("section {section_nb}"),
+                });
+            }
+            continue;
+        };
+        let pointer_offset = u64::from(pe_section.pointer_to_raw_data);
+        let section_vaddr = (pe.image_base as u64)
+            .checked_add(u64::from(pe_section.virtual_address))
+            .ok_or_else(|| {
+                GenealogyError::AddressOutOfRange(format!(
+                    "section {section_nb} above the image base {}",
+                    hex(pe.image_base as u64)
+                ))
+            })?;
+
+        if section.name.is_empty() {
+            section.name = pe_section.name().unwrap_or_default().to_string();
+        }
+        section.start_vaddr = section_vaddr;
+        section.start_file_offset = Some(pointer_offset);
+        section.size = pe_section.virtual_size as u64;
+        section.loaded = true;
+
+        if let Some(alignment) = file_alignment {
+            if pointer_offset % alignment != 0 {
+                warnings.push(Warning::UnalignedRawData {
+                    section: section.name.clone(),
+                    pointer_to_raw_data: pointer_offset,
+                    file_alignment: alignment,
+                });
+            }
+        }
+        let mut raw_size = u64::from(pe_section.size_of_raw_data);
+        let available = binary_len.saturating_sub(pointer_offset);
+        if raw_size > available {
+            warnings.push(Warning::RawDataOutOfFile {
+                section: section.name.clone(),
+                raw_size,
+                available,
+            });
+            raw_size = available;
+        }
+
+        let mut subsections = Vec::with_capacity(section.subsections.len());
+        for mut subsection in std::mem::take(&mut section.subsections) {
+            let section_offset = subsection.start_vaddr;
+            // Now that the section is known, turn the section offset into an actual vaddr
+            subsection.start_vaddr = range(section_vaddr, section_offset, &subsection)?.end;
+            if section_offset >= raw_size {
+                // Entirely in the zero-filled tail of the section
+                subsections.push(subsection);
+                continue;
+            }
+            subsection.start_file_offset =
+                Some(range(pointer_offset, section_offset, &subsection)?.end);
+            let in_file = raw_size - section_offset;
+            if subsection.size > in_file {
+                // Only the beginning is in the file, the rest is zero-filled at load time
+                subsections.push(SubSection {
+                    name: subsection.name.clone(),
+                    start_vaddr: subsection.start_vaddr + in_file,
+                    start_file_offset: None,
+                    size: subsection.size - in_file,
+                    filename: subsection.filename.clone(),
+                    folded_with: subsection.folded_with.clone(),
+                    line: subsection.line,
+                });
+                subsection.size = in_file;
+            }
+            subsections.push(subsection);
+        }
+        subsections.sort_by_key(|subsection| subsection.start_vaddr);
+        section.subsections = subsections;
+    }
+    Ok(warnings)
+}
+
+/// Places the sections in the ELF file, by name. `vaddr_bias` is added to the addresses of the
+/// map to bring them to those of the ELF headers, for maps produced against another base.
+///
+/// `SHT_NOBITS` sections, `.bss` or the `NOLOAD` sections of linker scripts, take no bytes of
+/// the file: they get no file offsets, only their virtual addresses when loaded.
+pub(crate) fn map_sections_to_elf(
+    sections: &mut [Section],
+    elf: &Elf,
+    vaddr_bias: i64,
+) -> Result<Vec<Warning>, GenealogyError> {
+    /*
+        For each section:
+        - Find the named section in the Elf file
+        - Find the file offset and fill it in
+        - Do the same for all subsections
+    */
+
+    // Maps a section header name to its headers, in header order: linker scripts may emit
+    // several sections of the same name. Headers whose name is out of the string table, as
+    // when `e_shstrndx` is 0, are left out
+    let mut elf_section_hm: HashMap<&str, Vec<&SectionHeader>> = HashMap::new();
+    for shdr in &elf.section_headers {
+        if let Some(name) = elf.shdr_strtab.get_at(shdr.sh_name) {
+            elf_section_hm.entry(name).or_default().push(shdr);
+        }
+    }
+    let rebase = |vaddr: u64, what: &dyn Display| {
+        vaddr.checked_add_signed(vaddr_bias).ok_or_else(|| {
+            GenealogyError::AddressOutOfRange(format!(
+                "{what} at {} cannot be biased by {vaddr_bias:#x}",
+                hex(vaddr)
+            ))
+        })
+    };
+
+    let mut warnings = vec![];
+    for section in sections.iter_mut() {
+        if vaddr_bias != 0 {
+            section.start_vaddr = rebase(section.start_vaddr, &section.name)?;
+            for ssection in &mut section.subsections {
+                ssection.start_vaddr = rebase(ssection.start_vaddr, ssection)?;
+            }
+        }
+
+        // The header at the address of the section, or the first of its name
+        let shdr = elf_section_hm.get(section.name.as_str()).and_then(|shdrs| {
+            shdrs
+                .iter()
+                .find(|shdr| shdr.sh_addr == section.start_vaddr)
+                .or(shdrs.first())
+                .copied()
+        });
+        section.start_file_offset = shdr
+            .filter(|shdr| shdr.sh_type != SHT_NOBITS)
+            .map(|shdr| shdr.sh_offset);
+        section.loaded = shdr.is_some_and(|shdr| shdr.is_alloc());
+        if let Some(shdr) =
+            shdr.filter(|shdr| shdr.is_alloc() && shdr.sh_addr != section.start_vaddr)
+        {
+            warnings.push(Warning::SectionAddressMismatch {
+                section: section.name.clone(),
+                map_vaddr: section.start_vaddr,
+                binary_vaddr: shdr.sh_addr,
+            });
+        }
+        if let Some(file_offset) = section.start_file_offset {
+            for ssection in &mut section.subsections {
+                // Subsections starting before their section are reported by the validation
+                let Some(offset_in_section) = ssection.start_vaddr.checked_sub(section.start_vaddr)
+                else {
+                    continue;
+                };
+                ssection.start_file_offset =
+                    Some(range(file_offset, offset_in_section, ssection)?.end);
+            }
+        }
+    }
+    Ok(warnings)
+}
+
+/// Places the sections of an ld64 map in the Mach-O file `macho`, whose contents start at
+/// `slice_offset` of the binary: that of its slice in universal binaries. Sections are matched
+/// by segment and section name, and by address among duplicates. Zero-filled sections have no
+/// file offset, debug sections are not loaded.
+pub(crate) fn map_sections_to_macho(
+    sections: &mut [Section],
+    macho: &MachO,
+    slice_offset: u64,
+) -> Result<Vec<Warning>, GenealogyError> {
+    // `<segment>,<section>` to the address, size, file offset and flags of its headers
+    let mut headers: HashMap<String, Vec<(u64, u64, u64, u32)>> = HashMap::new();
+    for segment in macho.segments.iter() {
+        for (header, _) in segment.sections().map_err(GenealogyError::BinaryParse)? {
+            let name = format!(
+                "{},{}",
+                header.segname().map_err(GenealogyError::BinaryParse)?,
+                header.name().map_err(GenealogyError::BinaryParse)?
+            );
+            headers.entry(name).or_default().push((
+                header.addr,
+                header.size,
+                slice_offset + u64::from(header.offset),
+                header.flags,
+            ));
+        }
+    }
+
+    let mut warnings = vec![];
+    for section in sections.iter_mut() {
+        let header = headers.get(&section.name).and_then(|headers| {
+            headers
+                .iter()
+                .find(|(addr, ..)| *addr == section.start_vaddr)
+                .or(headers.first())
+                .copied()
+        });
+        let Some((addr, _, offset, flags)) = header else {
+            continue;
+        };
+        let zero_filled = matches!(
+            flags & SECTION_TYPE,
+            S_ZEROFILL | S_GB_ZEROFILL | S_THREAD_LOCAL_ZEROFILL
+        );
+        section.start_file_offset = (!zero_filled).then_some(offset);
+        section.loaded = flags & S_ATTR_DEBUG == 0;
+        if addr != section.start_vaddr {
+            warnings.push(Warning::SectionAddressMismatch {
+                section: section.name.clone(),
+                map_vaddr: section.start_vaddr,
+                binary_vaddr: addr,
+            });
+        }
+        if let Some(file_offset) = section.start_file_offset {
+            for ssection in &mut section.subsections {
+                // Subsections starting before their section are reported by the validation
+                let Some(offset_in_section) = ssection.start_vaddr.checked_sub(section.start_vaddr)
+                else {
+                    continue;
+                };
+                ssection.start_file_offset =
+                    Some(range(file_offset, offset_in_section, ssection)?.end);
+            }
+        }
+    }
+    Ok(warnings)
+}
+
+/// Places the sections and data segments of a `wasm-ld` map in the WebAssembly module
+/// `binary`: sections by name, segments by address in linear memory. Only the function bodies
+/// and the data segments are loaded, into the engine and into memory, and only the segments of
+/// the data section have file offsets.
+pub(crate) fn map_sections_to_wasm(
+    sections: &mut [Section],
+    binary: &[u8],
+) -> Result<Vec<Warning>, GenealogyError> {
+    let wasm_sections = wasm::wasm_sections(binary)?;
+    let segments = match wasm_sections
+        .iter()
+        .find(|section| section.id == wasm::DATA_SECTION)
+    {
+        Some(data) => wasm::data_segments(binary, data)?,
+        None => vec![],
+    };
+    // Segments placed relative to a global, or passive, are matched in order
+    let mut unplaced = segments.iter().filter(|segment| segment.vaddr.is_none());
+
+    let mut warnings = vec![];
+    for section in sections.iter_mut() {
+        if wasm::is_section_name(&section.name) {
+            let Some(wasm_section) = wasm_sections.iter().find(|s| s.name == section.name) else {
+                warnings.push(Warning::SectionNotInBinary {
+                    section: section.name.clone(),
+                });
+                continue;
+            };
+            let payload = &wasm_section.payload;
+            section.start_file_offset = Some(payload.start);
+            section.size = payload.end - payload.start;
+            section.loaded = wasm_section.id == wasm::CODE_SECTION;
+            for ssection in &mut section.subsections {
+                ssection.start_file_offset =
+                    Some(range(payload.start, ssection.start_vaddr, ssection)?.end);
+            }
+        } else {
+            let Some(segment) = segments
+                .iter()
+                .find(|segment| segment.vaddr == Some(section.start_vaddr))
+                .or_else(|| unplaced.next())
+            else {
+                // Zero-initialized segments, e.g. `.bss`, are left out of the module
+                section.loaded = true;
+                continue;
+            };
+            section.start_file_offset = Some(segment.data.start);
+            section.size = segment.data.end - segment.data.start;
+            section.loaded = true;
+            for ssection in &mut section.subsections {
+                // Subsections starting before their segment are reported by the validation
+                let Some(offset_in_segment) = ssection.start_vaddr.checked_sub(section.start_vaddr)
+                else {
+                    continue;
+                };
+                ssection.start_file_offset =
+                    Some(range(segment.data.start, offset_in_segment, ssection)?.end);
+            }
+        }
+    }
+    Ok(warnings)
+}
+
+#[cfg(test)]
+mod tests {
+    use goblin::Object;
+
+    use crate::{index::range, validate::Warning, Genealogy, GenealogyError};
+
+    use super::{
+        discarded_subsections, extract_gnu_mapfile, extract_mapfile, iar_filename,
+        map_msvc_sections_to_pe, map_sections_to_elf, mapfile_format, normalize_section_name,
+        MapfileFormat,
+    };
+
+    #[test]
+    fn test_llvm_mapfile() {
+        let file = std::fs::read_to_string("tests/clang/output.map").unwrap();
+        let (mut sections, _) = extract_mapfile(&file).unwrap();
+
+        let binary = std::fs::read("tests/clang/a.out").unwrap();
+        let object = Object::parse(&binary).expect("Open test1");
+        if let Object::Elf(elf) = object {
+            assert!(map_sections_to_elf(&mut sections, &elf, 0)
+                .unwrap()
+                .is_empty());
+        }
+    }
+
+    #[test]
+    fn test_llvm_folded_sections() {
+        let mapfile = "               VMA              LMA     Size Align Out     In      Symbol
+            1000             1000       30    16 .text
+            1000             1000       10    16         a.o:(.text._Z3foov)
+            1000             1000       10     1                 _Z3foov
+            1000             1000       10    16         b.o:(.text._Z3barv)
+            1000             1000       10    16         c.o:(.text._Z3bazv)
+            1010             1010        0     1         d.o:(.text)
+            1010             1010       20    16         d.o:(.text._Z3quxv)
+";
+        let (sections, _) = extract_mapfile(mapfile).unwrap();
+        let subsections = &sections[0].subsections;
+        assert_eq!(subsections.len(), 3);
+        assert_eq!(subsections[0].filename, "a.o");
+        assert_eq!(subsections[0].folded_with, ["b.o", "c.o"]);
+        assert!(subsections[2].folded_with.is_empty());
+    }
+
+    #[test]
+    fn test_armlink_mapfile() {
+        // ARM Compiler 6, abridged
+        let mapfile = "Component: Arm Compiler for Embedded 6.19 Tool: armlink [5e73cb00]
+
+==============================================================================
+
+Image Symbol Table
+
+    Global Symbols
+
+    Symbol Name                              Value     Ov Type        Size  Object(Section)
+
+    main                                     0x08000131   Thumb Code    36  main.o(.text.main)
+
+==============================================================================
+
+Memory Map of the image
+
+  Image Entry point : 0x08000101
+
+  Load Region LR_IROM1 (Base: 0x08000000, Size: 0x00000190, Max: 0x00080000, ABSOLUTE)
+
+    Execution Region ER_IROM1 (Exec base: 0x08000000, Load base: 0x08000000, Size: 0x00000180, Max: 0x00080000, ABSOLUTE)
+
+    Exec Addr    Load Addr    Size         Type   Attr      Idx    E Section Name        Object
+
+    0x08000000   0x08000000   0x00000100   Data   RO            3    RESET               startup_stm32f407xx.o
+    0x08000100   0x08000100   0x00000000   Code   RO          262  * .ARM.Collect$$$$00000000  mc_w.l(entry.o)
+    0x08000100   0x08000100   0x0000002c   Code   RO          4    .text               startup_stm32f407xx.o
+    0x0800012c   0x0800012c   0x00000004   PAD
+    0x08000130   0x08000130   0x00000048   Code   RO          12    .text.main          main.o
+    0x08000178   0x08000178   0x00000008   Ven    RO          384    Veneer$$Code        anon$$obj.o
+    0x08000180   0x08000180   0x000
+    0x08000180   0x08000180   0x00000000   Code   RO          13    .text.idle          main.o
+
+    Execution Region RW_IRAM1 (Exec base: 0x20000000, Load base: 0x08000180, Size: 0x00000410, Max: 0x00020000, ABSOLUTE)
+
+    Exec Addr    Load Addr    Size         Type   Attr      Idx    E Section Name        Object
+
+    0x20000000   0x08000180   0x00000010   Data   RW           14    .data.counters      main.o
+    0x20000010        -       0x00000400   Zero   RW            2    STACK               startup_stm32f407xx.o
+
+==============================================================================
+
+Image component sizes
+";
+        let (sections, warnings) = extract_mapfile(mapfile).unwrap();
+        let summary: Vec<(&str, u64, u64, usize)> = sections
+            .iter()
+            .map(|s| (s.name.as_str(), s.start_vaddr, s.size, s.subsections.len()))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("ER_IROM1", 0x0800_0000, 0x180, 6),
+                ("RW_IRAM1", 0x2000_0000, 0x10, 1),
+                ("RW_IRAM1", 0x2000_0010, 0x400, 1),
+            ]
+        );
+        // A truncated line, skipped up to the next execution region
+        assert_eq!(
+            warnings,
+            [Warning::MalformedMapRegion { line: 31, lines: 2 }]
+        );
+        let entry = &sections[0].subsections[1];
+        assert_eq!(
+            (
+                entry.name.as_str(),
+                entry.start_vaddr,
+                entry.filename.as_str()
+            ),
+            (".ARM.Collect$$$$00000000", 0x0800_0100, "mc_w.l(entry.o)")
+        );
+        // Padding and veneers are attributed too
+        let entries: Vec<(&str, u64, &str)> = sections[0]
+            .subsections
+            .iter()
+            .map(|s| (s.name.as_str(), s.size, s.filename.as_str()))
+            .collect();
+        assert_eq!(entries[3], ("PAD", 4, "<padding>"));
+        assert_eq!(entries[5], ("Veneer$$Code", 8, "anon$$obj.o"));
+        assert_eq!(sections[2].subsections[0].name, "STACK");
+
+        // ARM Compiler 5 lists no load addresses
+        let mapfile = "Memory Map of the image
+
+    Execution Region ER_IROM1 (Base: 0x00000000, Size: 0x00000020, Max: 0xffffffff, ABSOLUTE)
+
+    Base Addr    Size         Type   Attr      Idx    E Section Name        Object
+
+    0x00000000   0x00000020   Code   RO            1    .text               retarget.o
+";
+        let (sections, warnings) = extract_mapfile(mapfile).unwrap();
+        assert!(warnings.is_empty());
+        assert_eq!(sections[0].subsections[0].filename, "retarget.o");
+        assert!(matches!(
+            extract_mapfile("Image Symbol Table\n"),
+            Err(GenealogyError::WrongMapfileFormat)
+        ));
+    }
+
+    #[test]
+    fn test_iar_mapfile() {
+        let mapfile = std::fs::read_to_string("tests/iar/app.map").unwrap();
+        let (sections, warnings) = extract_mapfile(&mapfile).unwrap();
+        assert!(warnings.is_empty());
+        let summary: Vec<(&str, u64, u64, usize)> = sections
+            .iter()
+            .map(|s| (s.name.as_str(), s.start_vaddr, s.size, s.subsections.len()))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("A0 ro", 0x800_0000, 0x10, 1),
+                ("P1 ro", 0x800_0010, 0x90, 6),
+                ("P2 rw", 0x2000_0000, 0x10, 2),
+                ("P2 zi", 0x2000_0010, 0x4, 1),
+                ("P2 ui", 0x2000_0018, 0x200, 1),
+            ]
+        );
+        // Wrapped names, objects of the module summary and initializers copied from their block
+        let objects: Vec<(&str, u64, &str)> = sections[1]
+            .subsections
+            .iter()
+            .map(|s| (s.name.as_str(), s.start_vaddr, s.filename.as_str()))
+            .collect();
+        assert_eq!(
+            objects[1..],
+            [
+                (".text.startup", 0x800_0020, r"C:\iar\app\Debug\Obj\main.o"),
+                (".text", 0x800_0050, r"C:\iar\app\Debug\Obj\uart.o"),
+                (".rodata", 0x800_0080, r"C:\iar\app\Debug\Obj\main.o"),
+                (".data", 0x800_0090, r"C:\iar\app\Debug\Obj\main.o"),
+                (".data", 0x800_0098, r"C:\iar\app\Debug\Obj\uart.o"),
+            ]
+        );
+        assert_eq!(sections[4].subsections[0].filename, "<CSTACK>");
+        let modules = [("[2]", "dl7M_tln.a")].into();
+        assert_eq!(iar_filename("exit.o [2]", &modules), "dl7M_tln.a(exit.o)");
+
+        // ILINK names the sections of the ELF file its own way
+        let binary = std::fs::read("tests/iar/app.out").unwrap();
+        let genealogy = Genealogy::new(&mapfile, &binary).unwrap();
+        assert!(genealogy.warnings().is_empty());
+        let names: Vec<&str> = genealogy
+            .sections()
+            .iter()
+            .map(|s| s.name.as_str())
+            .collect();
+        assert_eq!(names, ["A0 rw", "P1 ro", "P2-1", "P2 zi", "CSTACK"]);
+        let hit = genealogy.query_point(0x1098).next().unwrap();
+        assert_eq!(
+            (hit.filename, hit.range),
+            (r"C:\iar\app\Debug\Obj\uart.o", 0x1098..0x10a0)
+        );
+        // The data is zero-filled in RAM before the initializers are copied
+        assert_eq!(genealogy.sections()[2].start_file_offset, None);
+        let hit = genealogy.query_vaddr_point(0x2000_0008).next().unwrap();
+        assert_eq!(hit.filename, r"C:\iar\app\Debug\Obj\uart.o");
+
+        // Malformed entries are skipped up to the next placement directive
+        let mangled = mapfile.replace("0x2000'0000     0x4  main.o [1]", "0x2000'0000");
+        let (sections, warnings) = extract_mapfile(&mangled).unwrap();
+        assert_eq!(
+            warnings,
+            [Warning::MalformedMapRegion { line: 70, lines: 2 }]
+        );
+        assert_eq!(sections[1].subsections[4].filename, "<for P2-1>");
+    }
+
+    #[test]
+    fn test_ghs_mapfile() {
+        let mapfile = "Green Hills Software, MULTI v7.1.6
+Link Date:  Wed Oct 14 10:12:31 2026
+Host OS:    Linux
+Version:    ELXR 2021.1.5 (c) Green Hills Software    Build: Jan 20 2021
+
+
+Load Map Wed Oct 14 10:12:31 2026
+Image Summary
+
+  Section              Base      Size(hex)    Size(dec)  SecOffs
+  .vectors          00000000     00000100          256   0000100
+  .text             00000100     000001a0          416   0000200
+  .rodata           000002a0     00000020           32   00003a0
+  .data             40000000     00000010           16   00003c0
+  .bss              40000010     00000200          512   0000000
+
+Load Map Wed Oct 14 10:12:31 2026
+Module Summary
+
+  Origin+Size    Section          Module
+00000000+000100  .vectors         startup.o
+00000100+000120  .text            main.o
+00000220+000080  .text            libansi.a(ccvsprnt.o)
+000002a0+000020  .rodata          main.o
+00000320+0000xx  .rodata          libansi.a(ccvsprnt.o)
+00000340+000010  .sdata2          main.o
+40000000+000010  .data            can.o
+40000010+000200  .bss             can.o
+
+Load Map Wed Oct 14 10:12:31 2026
+Global Symbols (sorted alphabetically)
+
+ 00000100+000040 .text            main
+";
+        let (sections, warnings) = extract_mapfile(mapfile).unwrap();
+        let summary: Vec<(&str, u64, u64, usize)> = sections
+            .iter()
+            .map(|s| (s.name.as_str(), s.start_vaddr, s.size, s.subsections.len()))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                (".vectors", 0, 0x100, 1),
+                (".text", 0x100, 0x1a0, 2),
+                (".rodata", 0x2a0, 0x20, 1),
+                (".data", 0x4000_0000, 0x10, 1),
+                (".bss", 0x4000_0010, 0x200, 1),
+            ]
+        );
+        assert_eq!(sections[1].subsections[1].filename, "libansi.a(ccvsprnt.o)");
+        assert_eq!(sections[4].subsections[0].line, Some(28));
+        // A mangled size and a section missing from the image summary
+        assert_eq!(
+            warnings,
+            [Warning::MalformedMapRegion { line: 25, lines: 2 }]
+        );
+        assert!(matches!(
+            extract_mapfile("Load Map Wed Oct 14 10:12:31 2026\nImage Summary\n"),
+            Err(GenealogyError::WrongMapfileFormat)
+        ));
+    }
+
+    #[test]
+    fn test_ld64_mapfile() {
+        let mapfile = std::fs::read_to_string("tests/macho/app.map").unwrap();
+        let (sections, warnings) = extract_mapfile(&mapfile).unwrap();
+        assert!(warnings.is_empty());
+        let summary: Vec<(&str, u64, usize)> = sections
+            .iter()
+            .map(|s| (s.name.as_str(), s.start_vaddr, s.subsections.len()))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("__TEXT,__text", 0x1_0000_03d0, 2),
+                ("__TEXT,__cstring", 0x1_0000_0400, 1),
+                ("__DATA,__data", 0x1_0000_1000, 1),
+                ("__DATA,__bss", 0x1_0000_1004, 1),
+            ]
+        );
+        let emit = &sections[0].subsections[1];
+        assert_eq!(
+            (emit.name.as_str(), emit.filename.as_str()),
+            ("_emit", "libemit.a(emit.o)")
+        );
+        assert_eq!(
+            sections[1].subsections[0].name,
+            r"literal string: linked by ld64\n"
+        );
+        // Dead-stripped symbols have no address, and are only listed as discarded
+        let discarded = discarded_subsections(&mapfile).unwrap();
+        let dead: Vec<(&str, &str)> = discarded
+            .iter()
+            .map(|s| (s.name.as_str(), s.filename.as_str()))
+            .collect();
+        assert_eq!(
+            dead,
+            [
+                ("_unused_in_main", "main.o"),
+                ("_emit_unused", "libemit.a(emit.o)"),
+                ("_emitted", "libemit.a(emit.o)"),
+            ]
+        );
+
+        let binary = std::fs::read("tests/macho/app").unwrap();
+        let genealogy = Genealogy::new(&mapfile, &binary).unwrap();
+        assert!(genealogy.warnings().is_empty());
+        let hit = genealogy.query_point(0x3f4).next().unwrap();
+        assert_eq!(
+            (hit.filename, hit.range),
+            ("libemit.a(emit.o)", 0x3f0..0x400)
+        );
+        assert_eq!(genealogy.sections()[3].start_file_offset, None);
+        let hit = genealogy.query_vaddr_point(0x1_0000_1004).next().unwrap();
+        assert_eq!(hit.filename, "main.o");
+        // The slice of the map's architecture, further in universal binaries
+        let binary = std::fs::read("tests/macho/app.universal").unwrap();
+        let genealogy = Genealogy::new(&mapfile, &binary).unwrap();
+        let hit = genealogy.query_point(0x13f4).next().unwrap();
+        assert_eq!(hit.filename, "libemit.a(emit.o)");
+        let arm64 = mapfile.replace("# Arch: x86_64", "# Arch: arm64");
+        assert!(matches!(
+            Genealogy::new(&arm64, &binary),
+            Err(GenealogyError::NoArchSlice(arch)) if arch == "arm64"
+        ));
+
+        // Symbols out of the sections are skipped
+        let mangled = mapfile.replace("0x1000003F0\t", "0x2000003F0\t");
+        let (sections, warnings) = extract_mapfile(&mangled).unwrap();
+        assert_eq!(sections[0].subsections.len(), 1);
+        assert_eq!(
+            warnings,
+            [Warning::MalformedMapRegion { line: 16, lines: 1 }]
+        );
+    }
+
+    #[test]
+    fn test_gold_mapfile() {
+        let mapfile = std::fs::read_to_string("tests/gold/out.map").unwrap();
+        assert_eq!(mapfile_format(&mapfile), MapfileFormat::Gold);
+        let (sections, warnings) = extract_mapfile(&mapfile).unwrap();
+        assert!(warnings.is_empty());
+        let subsections = |name: &str| -> Vec<(&str, u64, &str)> {
+            sections
+                .iter()
+                .find(|section| section.name == name)
+                .unwrap()
+                .subsections
+                .iter()
+                .map(|s| (s.name.as_str(), s.size, s.filename.as_str()))
+                .collect()
+        };
+        let crt = "/usr/lib/gcc/x86_64-linux-gnu/12/../../../x86_64-linux-gnu/Scrt1.o";
+        assert_eq!(
+            subsections(".text"),
+            [
+                (".text", 0x22, crt),
+                ("fill", 0xe, "<padding>"),
+                (
+                    ".text",
+                    0xb9,
+                    "/usr/lib/gcc/x86_64-linux-gnu/12/crtbeginS.o"
+                ),
+                (".text.main", 0x19, "main.o"),
+                (
+                    ".text.helper_with_a_really_long_function_name_to_wrap",
+                    0x4,
+                    "libhelper.a(helper.o)"
+                ),
+            ]
+        );
+        // gold merges the `.eh_frame` of the objects, and the names wrap without an address
+        assert_eq!(subsections(".eh_frame")[0], ("eh_frame", 0xa0, "<linker>"));
+        assert_eq!(
+            subsections(".got.plt"),
+            [
+                ("GOT PLT", 0x20, "<linker>"),
+                ("GOT IRELATIVE PLT", 0, "<linker>"),
+                ("GOT", 0, "<linker>"),
+            ]
+        );
+        assert_eq!(subsections(".dynstr"), [("string table", 0x88, "<linker>")]);
+
+        let binary = std::fs::read("tests/gold/app").unwrap();
+        let genealogy = Genealogy::new(&mapfile, &binary).unwrap();
+        let hit = genealogy.query_vaddr_point(0x560).next().unwrap();
+        assert_eq!((hit.filename, hit.range), ("<linker>", 0x560..0x580));
+
+        let mangled = mapfile.replace(
+            "0x0000000000000560       0x20",
+            "0x0000000000000560       0x2g",
+        );
+        let (_, warnings) = extract_mapfile(&mangled).unwrap();
+        assert_eq!(
+            warnings,
+            [Warning::MalformedMapRegion {
+                line: 101,
+                lines: 1
+            }]
+        );
+    }
+
+    #[test]
+    fn test_ti_mapfile() {
+        let mapfile = "******************************************************************************
+                  TI ARM Linker PC v20.2.7
+******************************************************************************
+>> Linked Wed Oct 14 10:12:31 2026
+
+OUTPUT FILE NAME:   <app.out>
+ENTRY POINT SYMBOL: \"_c_int00_noargs\"  address: 00000385
+
+
+SECTION ALLOCATION MAP
+
+ output                                  attributes/
+section   page    origin      length       input sections
+--------  ----  ----------  ----------   ----------------
+.intvecs   0    00000000    0000026c
+                  00000000    0000026c     startup_ccs.obj (.intvecs)
+
+.text      0    0000026c    00000154
+                  0000026c    0000009c     rtsv7M4_T_le_v4SPD16_eabi.lib : memcpy_t2.asm.obj (.text)
+                  00000308    0000007a     main.obj (.text:main)
+                  00000382    00000002     --HOLE-- [fill = 0]
+                  00000384    zzzzzzzz     rtsv7M4_T_le_v4SPD16_eabi.lib : boot_cortex_m.c.obj (.text)
+
+.cinit     0    000003c0    00000018
+                  000003c0    00000008     (.cinit..data.load) [load image, compression = lzss]
+                  000003c8    00000010     (__TI_handler_table)
+
+.TI.ramfunc
+*          0    20000000    00000020     RUN ADDR = 20000000
+                  20000000    00000020     flash.obj (.TI.ramfunc:flash_write)
+
+.bss       0    20000020    00000004     UNINITIALIZED
+                  20000020    00000004     (.common:counter)
+
+
+MODULE SUMMARY
+
+       Module             code   ro data   rw data
+       ------             ----   -------   -------
+    .\\
+       main.obj           122    0         4
+";
+        let (sections, warnings) = extract_mapfile(mapfile).unwrap();
+        let summary: Vec<(&str, u64, u64, usize)> = sections
+            .iter()
+            .map(|s| (s.name.as_str(), s.start_vaddr, s.size, s.subsections.len()))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                (".intvecs", 0, 0x26c, 1),
+                (".text", 0x26c, 0x154, 2),
+                (".cinit", 0x3c0, 0x18, 2),
+                (".TI.ramfunc", 0x2000_0000, 0x20, 1),
+                (".bss", 0x2000_0020, 0x4, 1),
+            ]
+        );
+        let entries: Vec<(&str, &str)> = sections
+            .iter()
+            .flat_map(|s| &s.subsections)
+            .map(|s| (s.name.as_str(), s.filename.as_str()))
+            .collect();
+        assert_eq!(
+            entries[1..5],
+            [
+                (".text", "rtsv7M4_T_le_v4SPD16_eabi.lib(memcpy_t2.asm.obj)"),
+                (".text:main", "main.obj"),
+                (".cinit..data.load", "<linker>"),
+                ("__TI_handler_table", "<linker>"),
+            ]
+        );
+        assert_eq!(sections[3].line, Some(28));
+        // A mangled size, then the module summary is left alone
+        assert_eq!(
+            warnings,
+            [Warning::MalformedMapRegion { line: 22, lines: 1 }]
+        );
+
+        // Maps without a page column
+        let (sections, _) = extract_mapfile(&mapfile.replace("  0    ", "       ")).unwrap();
+        assert_eq!(sections.len(), 5);
+        assert_eq!(sections[3].start_vaddr, 0x2000_0000);
+    }
+
+    #[test]
+    fn test_segger_mapfile() {
+        let mapfile = std::fs::read_to_string("tests/segger/app.map").unwrap();
+        assert_eq!(mapfile_format(&mapfile), MapfileFormat::Segger);
+        let (sections, warnings) = extract_mapfile(&mapfile).unwrap();
+        assert!(warnings.is_empty());
+        // Sections by kind, the ELF ones come from the binary
+        let summary: Vec<(&str, u64, u64, usize)> = sections
+            .iter()
+            .map(|s| (s.name.as_str(), s.start_vaddr, s.size, s.subsections.len()))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("Cnst", 0, 0x90, 2),
+                ("Code", 0x10, 0x67, 5),
+                ("Init", 0x2000_0000, 0x10, 3),
+                ("Zero", 0x2000_0010, 0x4, 1),
+            ]
+        );
+        // Names in brackets, wrapped names and alignment gaps
+        let entries: Vec<(&str, u64, u64, &str, Option<usize>)> = sections
+            .iter()
+            .flat_map(|s| &s.subsections)
+            .map(|s| {
+                let entry = (s.name.as_str(), s.start_vaddr, s.size);
+                (entry.0, entry.1, entry.2, s.filename.as_str(), s.line)
+            })
+            .collect();
+        assert_eq!(
+            entries[1..7],
+            [
+                (".rodata.banner", 0x80, 0x10, "main.o", Some(54)),
+                ("Reset_Handler", 0x10, 0xb, "startup.o", Some(48)),
+                ("ALIGN", 0x1b, 0x5, "<padding>", Some(49)),
+                ("main", 0x20, 0x2a, "main.o", Some(50)),
+                ("ALIGN", 0x4a, 0x6, "<padding>", Some(51)),
+                (
+                    "uart_write_polling_until_the_fifo_drains",
+                    0x50,
+                    0x27,
+                    "uart.o",
+                    Some(52)
+                ),
+            ]
+        );
+
+        // The SEGGER linker writes the ELF sections by address
+        let binary = std::fs::read("tests/segger/app.elf").unwrap();
+        let genealogy = Genealogy::new(&mapfile, &binary).unwrap();
+        assert!(genealogy.warnings().is_empty());
+        let names: Vec<(&str, usize)> = genealogy
+            .sections()
+            .iter()
+            .map(|s| (s.name.as_str(), s.subsections.len()))
+            .collect();
+        assert_eq!(
+            names,
+            [
+                (".vectors", 1),
+                (".text", 5),
+                (".rodata", 1),
+                (".data", 3),
+                (".bss", 1)
+            ]
+        );
+        let hit = genealogy.query_point(0x1085).next().unwrap();
+        assert_eq!((hit.filename, hit.range), ("main.o", 0x1080..0x1090));
+        let hit = genealogy.query_vaddr_point(0x2000_000c).next().unwrap();
+        assert_eq!(hit.filename, "uart.o");
+
+        // Runs of lines that do not parse are skipped, with the alignment gap after them
+        let mangled = mapfile.replace("20000000-20000003", "20000000-2000000Z");
+        let (sections, warnings) = extract_mapfile(&mangled).unwrap();
+        assert_eq!(
+            warnings,
+            [Warning::MalformedMapRegion { line: 55, lines: 1 }]
+        );
+        assert_eq!(sections[2].subsections.len(), 1);
+    }
+
+    #[test]
+    fn test_renesas_mapfile() {
+        let mapfile = std::fs::read_to_string("tests/renesas/app.map").unwrap();
+        assert_eq!(mapfile_format(&mapfile), MapfileFormat::Renesas);
+        let (sections, warnings) = extract_mapfile(&mapfile).unwrap();
+        assert!(warnings.is_empty());
+        let summary: Vec<(&str, u64, u64, usize, Option<usize>)> = sections
+            .iter()
+            .map(|s| {
+                let section = (s.name.as_str(), s.start_vaddr, s.size);
+                (section.0, section.1, section.2, s.subsections.len(), s.line)
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("P", 0x100, 0x67, 3, Some(21)),
+                ("C", 0x170, 0x10, 1, Some(23)),
+                ("D", 0x180, 0x10, 2, Some(25)),
+                ("B", 0xf_e000, 0x8, 2, Some(27)),
+            ]
+        );
+        let files: Vec<(&str, u64, u64, Option<usize>)> = sections[2]
+            .subsections
+            .iter()
+            .map(|s| (s.filename.as_str(), s.start_vaddr, s.size, s.line))
+            .collect();
+        assert_eq!(
+            files,
+            [
+                (r"DefaultBuild\main.obj", 0x180, 0x4, Some(63)),
+                (r"DefaultBuild\uart.obj", 0x188, 0x8, Some(67)),
+            ]
+        );
+
+        let binary = std::fs::read("tests/renesas/app.abs").unwrap();
+        let genealogy = Genealogy::new(&mapfile, &binary).unwrap();
+        assert!(genealogy.warnings().is_empty());
+        let hit = genealogy.query_point(0x18a).next().unwrap();
+        assert_eq!(
+            (hit.filename, hit.range),
+            (r"DefaultBuild\uart.obj", 0x188..0x190)
+        );
+        let hit = genealogy.query_vaddr_point(0xf_e005).next().unwrap();
+        assert_eq!(hit.filename, r"DefaultBuild\uart.obj");
+
+        // Short names on the line of their range, and a mangled range skipped with its file
+        let short = mapfile.replacen("P\n                                  ", "P ", 1);
+        let mangled = short.replace(
+            "00000110  00000139        2a",
+            "00000110  0000013z        2a",
+        );
+        let (sections, warnings) = extract_mapfile(&mangled).unwrap();
+        assert_eq!(sections[0].line, Some(21));
+        assert_eq!(sections[1].line, Some(22));
+        assert_eq!(sections[0].subsections.len(), 2);
+        assert_eq!(
+            warnings,
+            [Warning::MalformedMapRegion { line: 46, lines: 1 }]
+        );
+    }
+
+    #[test]
+    fn test_microchip_mapfile() {
+        // A GNU map with a hand-written memory-usage report, not output of xc32-gcc, see
+        // tests/microchip/build_commands.txt
+        let mapfile = std::fs::read_to_string("tests/microchip/app.map").unwrap();
+        assert_eq!(mapfile_format(&mapfile), MapfileFormat::Microchip);
+        // The rows of the memory-usage report read as output sections of a GNU map
+        let (sections, _) = extract_gnu_mapfile(&mapfile, 0).unwrap();
+        assert_eq!(sections[0].line, Some(19));
+
+        let (sections, warnings) = extract_mapfile(&mapfile).unwrap();
+        assert!(warnings.is_empty());
+        let summary: Vec<(&str, u64, Option<usize>)> = sections
+            .iter()
+            .filter(|s| s.size > 0)
+            .map(|s| (s.name.as_str(), s.start_vaddr, s.line))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                (".reset", 0xbfc0_0000, Some(58)),
+                (".text", 0x9d00_0000, Some(63)),
+                (".rodata", 0x9d00_005c, Some(76)),
+                (".data", 0xa000_0000, Some(84)),
+                (".bss", 0xa000_0008, Some(101)),
+            ]
+        );
+        let text: Vec<(&str, u64, u64, &str)> = sections[1]
+            .subsections
+            .iter()
+            .filter(|s| s.size > 0)
+            .map(|s| (s.name.as_str(), s.start_vaddr, s.size, s.filename.as_str()))
+            .collect();
+        assert_eq!(
+            text,
+            [
+                (".text.startup", 0x9d00_0000, 0x4d, "main.o"),
+                ("*fill*", 0x9d00_004d, 0x3, "<padding>"),
+                (".text", 0x9d00_0050, 0xc, "led.o"),
+            ]
+        );
+        assert_eq!(discarded_subsections(&mapfile).unwrap().len(), 6);
+
+        let binary = std::fs::read("tests/microchip/app.elf").unwrap();
+        let genealogy = Genealogy::new(&mapfile, &binary).unwrap();
+        assert!(genealogy.warnings().is_empty());
+        let hit = genealogy.query_vaddr_point(0x9d00_0054).next().unwrap();
+        assert_eq!(hit.filename, "led.o");
+        let hit = genealogy.query_vaddr_point(0xa000_0004).next().unwrap();
+        assert_eq!(hit.filename, "led.o");
+
+        // Fills with the pattern of `--fill`, and fills whose size does not parse
+        let fill = " *fill*         0x9d00004d        0x3 ";
+        let filled = mapfile.replace(fill, &format!("{fill}ffffffff"));
+        let (sections, warnings) = extract_mapfile(&filled).unwrap();
+        assert!(warnings.is_empty());
+        assert_eq!(sections[1].subsections[3].filename, "<padding>");
+        let mangled = mapfile.replace(fill, " *fill*         0x9d00004d        0x3Z");
+        let (_, warnings) = extract_mapfile(&mangled).unwrap();
+        assert_eq!(
+            warnings,
+            [Warning::MalformedMapRegion { line: 69, lines: 1 }]
+        );
+    }
+
+    #[test]
+    fn test_gnu_symbol_runs() {
+        let mapfile = "Memory map
+
+.text           0x0000000000001000      0x100
+                0x0000000000001000                _start
+                0x0000000000001040                parse
+                0x0000000000001080                parse_expression
+                0x0000000000001090                local_helper
+                0x00000000000010c0                main
+                0x0000000000001100                . = ALIGN (0x8)
+
+.data           0x0000000000002000       0x10
+                0x0000000000002000                a_very_long_variable_name_wrapped_by_the_linker
+
+Cross Reference Table
+
+Symbol                                            File
+_start                                            crt1.o
+a_very_long_variable_name_wrapped_by_the_linker
+                                                  data.o
+main                                              main.o
+parse                                             libparse.a(parser.o)
+                                                  main.o
+parse_expression                                  libparse.a(parser.o)
+";
+        let (sections, warnings) = extract_mapfile(mapfile).unwrap();
+        let runs: Vec<_> = sections[0]
+            .subsections
+            .iter()
+            .map(|run| (run.filename.as_str(), run.start_vaddr, run.size))
+            .collect();
+        assert_eq!(
+            runs,
+            [
+                ("crt1.o", 0x1000, 0x40),
+                ("libparse.a(parser.o)", 0x1040, 0x50),
+                ("main.o", 0x10c0, 0x40),
+            ]
+        );
+        assert_eq!(sections[1].subsections[0].filename, "data.o");
+        assert_eq!(sections[1].subsections[0].size, 0x10);
+        assert_eq!(
+            warnings[0],
+            Warning::SymbolRuns {
+                section: ".text".to_string(),
+                symbols: 5
+            }
+        );
+    }
+
+    #[test]
+    fn test_gnu_linkonce_sections() {
+        let mapfile = ".text           0x0000000000001000       0x40
+ .text          0x0000000000001000       0x10 main.o
+ .gnu.linkonce.t._ZN3FooC2Ev
+                0x0000000000001010       0x20 main.o
+ .gnu.linkonce.t._ZN3FooC2Ev
+                0x0000000000001010       0x20 foo.o
+ .text._Z3barv  0x0000000000001030       0x10 foo.o
+ .text._Z3bazv  0x0000000000001030       0x10 baz.o
+";
+        let (sections, _) = extract_mapfile(mapfile).unwrap();
+        let subsections: Vec<_> = sections[0]
+            .subsections
+            .iter()
+            .map(|s| {
+                (
+                    s.name.as_str(),
+                    s.filename.as_str(),
+                    s.folded_with.join(","),
+                )
+            })
+            .collect();
+        assert_eq!(
+            subsections,
+            [
+                (".text", "main.o", String::new()),
+                (".text._ZN3FooC2Ev", "main.o", "foo.o".to_string()),
+                (".text._Z3barv", "foo.o", String::new()),
+                (".text._Z3bazv", "baz.o", String::new()),
+            ]
+        );
+        assert_eq!(normalize_section_name(".gnu.linkonce.tb.tls"), ".tbss.tls");
+        assert_eq!(normalize_section_name(".gnu.linkonce.t"), ".gnu.linkonce.t");
+    }
+
+    #[test]
+    fn test_discarded_and_noload_sections() {
+        let mapfile = std::fs::read_to_string("tests/gcc/out.map")
+            .unwrap()
+            .replace(
+                "/DISCARD/\n *(.note.GNU-stack)\n",
+                "/DISCARD/\n *(.note.GNU-stack)\n \
+                 .note.GNU-stack\n                0x0000000000000000       0x10 simple.o\n",
+            );
+        let binary = std::fs::read("tests/gcc/a.out").unwrap();
+        let (mut sections, _) = extract_mapfile(&mapfile).unwrap();
+        assert!(sections
+            .iter()
+            .flat_map(|section| &section.subsections)
+            .all(|subsection| subsection.name != ".note.GNU-stack"));
+        let discarded = discarded_subsections(&mapfile).unwrap();
+        assert_eq!(
+            discarded
+                .last()
+                .map(|s| (s.name.as_str(), s.filename.as_str())),
+            Some((".note.GNU-stack", "simple.o"))
+        );
+
+        // `.bss` is SHT_NOBITS, it only has virtual addresses
+        let Object::Elf(elf) = Object::parse(&binary).unwrap() else {
+            unreachable!()
+        };
+        map_sections_to_elf(&mut sections, &elf, 0).unwrap();
+        let bss = sections.iter().find(|s| s.name == ".bss").unwrap();
+        assert!(bss.loaded);
+        assert_eq!(bss.start_file_offset, None);
+        assert!(bss
+            .subsections
+            .iter()
+            .all(|subsection| subsection.start_file_offset.is_none()));
+    }
+
+    #[test]
+    fn test_malformed_regions() {
+        // A hand edit broke an input section, and the map got another header appended
+        let mapfile = "               VMA              LMA     Size Align Out     In      Symbol
+            1000             1000       10    16         early.o:(.text)
+            1000             1000       30    16 .text
+            1000             1000       10    16         a.o:(.text)
+            1010             1010       10    16         b.o.text
+            1020             1020       10    16         c.o:(.text)
+            2000             2000       10    16 .data
+            2000             2000       10    16         d.o:(.data)
+               VMA              LMA     Size Align Out     In      Symbol
+            3000             3000       10    16 .bss
+            3000             3000       10    16         e.o:(.bss)
+";
+        let (sections, warnings) = extract_mapfile(mapfile).unwrap();
+        let names: Vec<_> = sections.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, [".text", ".data", ".bss"]);
+        assert_eq!(sections[0].subsections.len(), 1);
+        assert_eq!(sections[2].subsections[0].filename, "e.o");
+        assert_eq!(sections[2].line, Some(10));
+        assert_eq!(sections[2].subsections[0].line, Some(11));
+        assert_eq!(
+            warnings,
+            [
+                Warning::MalformedMapRegion { line: 2, lines: 1 },
+                Warning::MalformedMapRegion { line: 5, lines: 2 },
+                Warning::MalformedMapRegion { line: 9, lines: 1 },
+            ]
+        );
+        assert_eq!(
+            warnings[1].to_string(),
+            "lines 5 to 6 of the map could not be parsed, skipped"
+        );
+
+        // Static symbols after a garbled line are still read
+        let mapfile = std::fs::read_to_string("tests/cargo-windows/out.map").unwrap();
+        let (mut expected, _) = extract_mapfile(&mapfile).unwrap();
+        let statics = mapfile.find(" Static symbols").unwrap();
+        let garbled = statics + mapfile[statics..].find("\n 0003:").unwrap() + 1;
+        let line = mapfile[..garbled].matches('\n').count() + 1;
+        // The symbols after it are a line further
+        for subsection in expected.iter_mut().flat_map(|s| &mut s.subsections) {
+            subsection.line = subsection.line.map(|l| if l >= line { l + 1 } else { l });
+        }
+        let mapfile = format!("{}garbled\n{}", &mapfile[..garbled], &mapfile[garbled..]);
+        let (sections, warnings) = extract_mapfile(&mapfile).unwrap();
+        assert_eq!(sections, expected);
+        assert_eq!(warnings, [Warning::MalformedMapRegion { line, lines: 1 }]);
+    }
+
+    #[test]
+    fn test_address_out_of_range() {
+        // Kernel-style addresses at the top of the address space are fine...
+        let mapfile = "\
+.text           0xffffffff81000000     0x2000
+ .text          0xffffffff81000000     0x1000 head.o
+";
+        let (sections, _) = extract_mapfile(mapfile).unwrap();
+        assert_eq!(sections[0].subsections[0].start_vaddr, 0xffffffff81000000);
+        assert_eq!(
+            range(0xffffffff81000000, 0x1000, &"head.o").unwrap().end,
+            0xffffffff81001000
+        );
+
+        // ...but wrapping around or not fitting in 64 bits is an error, not a panic
+        assert!(matches!(
+            range(0xffffffffffffff00, 0x1000, &"head.o"),
+            Err(GenealogyError::AddressOutOfRange(_))
+        ));
+        let mapfile = ".text 0x1ffffffff81000000 0x10\n";
+        assert!(matches!(
+            extract_mapfile(mapfile),
+            Err(GenealogyError::AddressOutOfRange(_))
+        ));
+    }
+
+    #[test]
+    fn test_pe_raw_data_out_of_file() {
+        let mapfile = std::fs::read_to_string("tests/cargo-windows/out.map").unwrap();
+        let binary = std::fs::read("tests/cargo-windows/genealogy.exe").unwrap();
+        let Object::PE(pe) = Object::parse(&binary).unwrap() else {
+            panic!("not a PE");
+        };
+        let data = pe
+            .sections
+            .iter()
+            .position(|s| s.name().ok() == Some(".data"))
+            .unwrap();
+        let data_end = u64::from(pe.sections[data].pointer_to_raw_data) + 0x100;
+
+        // Pretend the file stops in the middle of .data
+        let (mut sections, _) = extract_mapfile(&mapfile).unwrap();
+        let warnings = map_msvc_sections_to_pe(&mut sections, &pe, data_end).unwrap();
+        assert!(warnings.contains(&Warning::RawDataOutOfFile {
+            section: ".data".to_string(),
+            raw_size: u64::from(pe.sections[data].size_of_raw_data),
+            available: 0x100,
+        }));
+        for subsection in &sections[data + 1].subsections {
+            if let Some(offset) = subsection.start_file_offset {
+                assert!(offset + subsection.size <= data_end);
+            }
+        }
+    }
+
+    /// A big-endian executable for `machine` with its section headers right after the ELF
+    /// header, out of address order, two `.text` sections and a header named out of the string
+    /// table.
+    fn big_endian_elf(is_64: bool, machine: u16) -> Vec<u8> {
+        let word = |elf: &mut Vec<u8>, value: u64| match is_64 {
+            true => elf.extend_from_slice(&value.to_be_bytes()),
+            false => elf.extend_from_slice(&(value as u32).to_be_bytes()),
+        };
+        let (header_size, section_header_size) = if is_64 { (64, 64) } else { (52, 40) };
+        let shstrtab = b"\0.shstrtab\0.text\0.data\0.bss\0";
+        // name, type, flags, address, offset, size
+        let headers: [(u32, u32, u64, u64, u64, u64); 7] = [
+            (0, 0, 0, 0, 0, 0),
+            (
+                1,
+                3,
+                0,
+                0,
+                header_size + 7 * section_header_size,
+                shstrtab.len() as u64,
+            ),
+            (17, 1, 0x3, 0x1001_0000, 0x430, 0x10),
+            (11, 1, 0x6, 0x40_0200, 0x420, 0x10),
+            (23, 8, 0x3, 0x1001_0010, 0x440, 0x20),
+            (11, 1, 0x6, 0x40_0100, 0x400, 0x20),
+            (0xffff, 1, 0, 0, 0, 0),
+        ];
+
+        let mut elf = vec![0x7f, b'E', b'L', b'F', 1 + u8::from(is_64), 2, 1, 0];
+        elf.resize(16, 0);
+        elf.extend_from_slice(&2u16.to_be_bytes()); // ET_EXEC
+        elf.extend_from_slice(&machine.to_be_bytes());
+        elf.extend_from_slice(&1u32.to_be_bytes()); // e_version
+        word(&mut elf, 0x40_0100); // e_entry
+        word(&mut elf, 0); // e_phoff
+        word(&mut elf, header_size); // e_shoff
+        elf.extend_from_slice(&0u32.to_be_bytes()); // e_flags
+        elf.extend_from_slice(&(header_size as u16).to_be_bytes());
+        elf.extend_from_slice(&0u16.to_be_bytes()); // e_phentsize
+        elf.extend_from_slice(&0u16.to_be_bytes()); // e_phnum
+        elf.extend_from_slice(&(section_header_size as u16).to_be_bytes());
+        elf.extend_from_slice(&(headers.len() as u16).to_be_bytes());
+        elf.extend_from_slice(&1u16.to_be_bytes()); // e_shstrndx
+        for (name, kind, flags, addr, offset, size) in headers {
+            elf.extend_from_slice(&name.to_be_bytes());
+            elf.extend_from_slice(&kind.to_be_bytes());
+            word(&mut elf, flags);
+            word(&mut elf, addr);
+            word(&mut elf, offset);
+            word(&mut elf, size);
+            elf.extend_from_slice(&0u32.to_be_bytes()); // sh_link
+            elf.extend_from_slice(&0u32.to_be_bytes()); // sh_info
+            word(&mut elf, 4); // sh_addralign
+            word(&mut elf, 0); // sh_entsize
+        }
+        elf.extend_from_slice(shstrtab);
+        elf.resize(0x440, 0xa5);
+        elf
+    }
+
+    #[test]
+    fn test_big_endian_elf() {
+        let mapfile = "Memory map
+
+.text           0x00400100       0x20
+ .text          0x00400100       0x10 start.o
+ .text          0x00400110       0x10 main.o
+
+.data           0x10010000       0x10
+ .data          0x10010000       0x10 main.o
+
+.bss            0x10010010       0x20
+ .bss           0x10010010       0x20 main.o
+
+.text           0x00400200       0x10
+ .text          0x00400200       0x10 late.o
+";
+        // MIPS and 64-bit PowerPC
+        for (is_64, machine) in [(false, 8), (true, 21)] {
+            let binary = big_endian_elf(is_64, machine);
+            let Object::Elf(elf) = Object::parse(&binary).unwrap() else {
+                unreachable!()
+            };
+            assert!(!elf.little_endian);
+            let (mut sections, _) = extract_mapfile(mapfile).unwrap();
+            let warnings = map_sections_to_elf(&mut sections, &elf, 0).unwrap();
+            assert!(warnings.is_empty(), "{warnings:?}");
+            let offsets: Vec<_> = sections
+                .iter()
+                .flat_map(|section| &section.subsections)
+                .map(|subsection| (subsection.filename.as_str(), subsection.start_file_offset))
+                .collect();
+            assert_eq!(
+                offsets,
+                [
+                    ("start.o", Some(0x400)),
+                    ("main.o", Some(0x410)),
+                    ("main.o", Some(0x430)),
+                    ("main.o", None),
+                    ("late.o", Some(0x420)),
+                ]
+            );
+
+            let genealogy = crate::Genealogy::new(mapfile, &binary).unwrap();
+            let hit = genealogy.query_point(0x425).next().unwrap();
+            assert_eq!((hit.filename, hit.range.start), ("late.o", 0x420));
+            let hit = genealogy.query_vaddr_point(0x1001_0018).next().unwrap();
+            assert_eq!(hit.filename, "main.o");
+        }
+    }
+}
@@ -0,0 +1,529 @@
+//! Flash partition tables, and the check that the content of a firmware image stays within the
+//! partition it is flashed to: the map-level complement of the image size checks of `esptool`
+//! and `imgtool`, which only see the total.
+//!
+//! Three descriptions of the partitions are understood, see [`PartitionTable::parse`]:
+//!
+//! - ESP-IDF partition tables (`partitions.csv`),
+//! - MCUboot slots, from the `bootloader.conf` of its Espressif port,
+//! - a TOML list of `[[partition]]` tables with a `name`, an `offset`, a `size` and optionally a
+//!   `type`, for everything else.
+
+use std::{fmt::Display, ops::Range};
+
+use crate::{
+    format::{hex, human_size, percentage, Align, Cell, Style, Table},
+    Genealogy, GenealogyError,
+};
+
+/// The type of the partitions holding code, the only ones content may be placed in when a table
+/// has some.
+pub const APP_PARTITION: &str = "app";
+
+/// Where ESP-IDF places the first partition without an offset, after the bootloader and the
+/// partition table.
+const ESP_IDF_FIRST_OFFSET: u64 = 0x9000;
+/// ESP-IDF aligns app partitions without an offset on 64K, and the others on 4K.
+const ESP_IDF_APP_ALIGNMENT: u64 = 0x10000;
+const ESP_IDF_DATA_ALIGNMENT: u64 = 0x1000;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Partition {
+    pub name: String,
+    /// [`APP_PARTITION`], `data`, or whatever the table calls it.
+    pub kind: Option<String>,
+    /// Offset from the start of the flash.
+    pub offset: u64,
+    pub size: u64,
+}
+
+impl Partition {
+    pub fn range(&self) -> Range<u64> {
+        self.offset..self.offset.saturating_add(self.size)
+    }
+
+    pub fn is_app(&self) -> bool {
+        self.kind.as_deref() == Some(APP_PARTITION)
+    }
+}
+
+/// The partitions of a flash, in offset order.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PartitionTable {
+    pub partitions: Vec<Partition>,
+}
+
+impl PartitionTable {
+    /// Parses any of the descriptions of the partitions listed in the [module](self)
+    /// documentation, told apart by their keys.
+    pub fn parse(text: &str) -> Result<Self, GenealogyError> {
+        if text.lines().any(|line| line.trim() == "[[partition]]") {
+            Self::parse_toml(text)
+        } else if text
+            .lines()
+            .any(|line| line.trim_start().starts_with("CONFIG_ESP_"))
+        {
+            Self::parse_mcuboot(text)
+        } else {
+            Self::parse_esp_idf(text)
+        }
+    }
+
+    /// Parses an ESP-IDF partition table: `name, type, subtype, offset, size, flags` rows. Missing
+    /// offsets follow the previous partition, aligned as `gen_esp32part.py` does.
+    pub fn parse_esp_idf(csv: &str) -> Result<Self, GenealogyError> {
+        let mut partitions: Vec<Partition> = vec![];
+        for line in csv.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+            let [name, kind, _subtype, offset, size, ..] = fields[..] else {
+                return Err(GenealogyError::WrongPartitionTableFormat);
+            };
+            let alignment = if kind == APP_PARTITION {
+                ESP_IDF_APP_ALIGNMENT
+            } else {
+                ESP_IDF_DATA_ALIGNMENT
+            };
+            let offset = match offset {
+                "" => partitions
+                    .last()
+                    .map_or(ESP_IDF_FIRST_OFFSET, |last| last.range().end)
+                    .next_multiple_of(alignment),
+                offset => parse_size(offset)?,
+            };
+            partitions.push(Partition {
+                name: name.to_string(),
+                kind: Some(kind.to_string()),
+                offset,
+                size: parse_size(size)?,
+            });
+        }
+        Ok(Self::sorted(partitions))
+    }
+
+    /// Parses the slots of MCUboot from the `CONFIG_ESP_*` keys of its `bootloader.conf`: the
+    /// bootloader, the primary (`slot<2n>`, holding the running image `n`) and secondary
+    /// (`slot<2n+1>`, receiving its updates) slots of every image, and the scratch area.
+    pub fn parse_mcuboot(conf: &str) -> Result<Self, GenealogyError> {
+        let mut keys = vec![];
+        for line in conf.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (key, value) = line
+                .split_once('=')
+                .ok_or(GenealogyError::WrongPartitionTableFormat)?;
+            keys.push((key.trim(), value.trim()));
+        }
+        let get = |key: &str| {
+            keys.iter()
+                .rev()
+                .find(|(k, _)| *k == key)
+                .map(|(_, value)| parse_size(value))
+                .transpose()
+        };
+
+        let mut partitions = vec![];
+        let mut push = |name: String, kind: &str, offset, size| {
+            if let (Some(offset), Some(size)) = (offset, size) {
+                partitions.push(Partition {
+                    name,
+                    kind: Some(kind.to_string()),
+                    offset,
+                    size,
+                });
+            }
+        };
+        push(
+            "bootloader".to_string(),
+            "bootloader",
+            get("CONFIG_ESP_BOOTLOADER_OFFSET")?,
+            get("CONFIG_ESP_BOOTLOADER_SIZE")?,
+        );
+        let size = get("CONFIG_ESP_APPLICATION_SIZE")?;
+        for image in 0.. {
+            let primary = get(&format!("CONFIG_ESP_IMAGE{image}_PRIMARY_START_ADDRESS"))?;
+            let secondary = get(&format!("CONFIG_ESP_IMAGE{image}_SECONDARY_START_ADDRESS"))?;
+            if primary.is_none() && secondary.is_none() {
+                break;
+            }
+            push(format!("slot{}", 2 * image), APP_PARTITION, primary, size);
+            push(format!("slot{}", 2 * image + 1), "update", secondary, size);
+        }
+        push(
+            "scratch".to_string(),
+            "scratch",
+            get("CONFIG_ESP_SCRATCH_OFFSET")?,
+            get("CONFIG_ESP_SCRATCH_SIZE")?,
+        );
+        if partitions.is_empty() {
+            return Err(GenealogyError::WrongPartitionTableFormat);
+        }
+        Ok(Self::sorted(partitions))
+    }
+
+    /// Parses `[[partition]]` tables of `name`, `type`, `offset` and `size` keys. Numbers may be
+    /// hexadecimal, sizes may also be strings with a K or M suffix, e.g. `size = "1M"`.
+    pub fn parse_toml(toml: &str) -> Result<Self, GenealogyError> {
+        let invalid = || GenealogyError::WrongPartitionTableFormat;
+        let mut tables: Vec<Vec<(&str, &str)>> = vec![];
+        for line in toml.lines() {
+            // Strings with a `#` are not worth supporting in names and sizes
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+            if line == "[[partition]]" {
+                tables.push(vec![]);
+                continue;
+            }
+            let (key, value) = line.split_once('=').ok_or_else(invalid)?;
+            let value = value.trim();
+            let value = value
+                .strip_prefix('"')
+                .and_then(|value| value.strip_suffix('"'))
+                .unwrap_or(value);
+            tables
+                .last_mut()
+                .ok_or_else(invalid)?
+                .push((key.trim(), value));
+        }
+
+        let partitions = tables
+            .into_iter()
+            .map(|table| {
+                let get = |key| table.iter().find(|(k, _)| *k == key).map(|(_, v)| *v);
+                Ok(Partition {
+                    name: get("name").ok_or_else(invalid)?.to_string(),
+                    kind: get("type").map(str::to_string),
+                    offset: parse_size(get("offset").ok_or_else(invalid)?)?,
+                    size: parse_size(get("size").ok_or_else(invalid)?)?,
+                })
+            })
+            .collect::<Result<_, GenealogyError>>()?;
+        Ok(Self::sorted(partitions))
+    }
+
+    fn sorted(mut partitions: Vec<Partition>) -> Self {
+        partitions.sort_by(|a, b| a.offset.cmp(&b.offset).then_with(|| a.name.cmp(&b.name)));
+        Self { partitions }
+    }
+
+    /// The end of the last partition.
+    pub fn end(&self) -> u64 {
+        self.partitions
+            .iter()
+            .map(|partition| partition.range().end)
+            .max()
+            .unwrap_or(0)
+    }
+
+    fn partition_at(&self, offset: u64) -> Option<&Partition> {
+        self.partitions
+            .iter()
+            .find(|partition| partition.range().contains(&offset))
+    }
+}
+
+/// A number of `parse_esp_idf`, `parse_mcuboot` and `parse_toml`: decimal or hexadecimal, with an
+/// optional K or M suffix.
+fn parse_size(value: &str) -> Result<u64, GenealogyError> {
+    let (number, multiplier) = match value.char_indices().last() {
+        Some((i, 'k' | 'K')) => (&value[..i], 1 << 10),
+        Some((i, 'm' | 'M')) => (&value[..i], 1 << 20),
+        _ => (value, 1),
+    };
+    let number = match number
+        .strip_prefix("0x")
+        .or_else(|| number.strip_prefix("0X"))
+    {
+        Some(hex) => u64::from_str_radix(hex, 16),
+        None => number.parse(),
+    };
+    number
+        .ok()
+        .and_then(|number| number.checked_mul(multiplier))
+        .ok_or(GenealogyError::WrongPartitionTableFormat)
+}
+
+/// The usage of every partition, see [`Genealogy::partition_usage`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PartitionReport {
+    /// In offset order.
+    pub entries: Vec<PartitionEntry>,
+    /// Content outside of its partition, in address order.
+    pub offenders: Vec<Offender>,
+    /// Bytes outside of the flash, e.g. in RAM.
+    pub outside_flash: u64,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PartitionEntry {
+    pub partition: Partition,
+    /// Bytes of content starting in the partition.
+    pub used: u64,
+    /// Number of files with content starting in the partition.
+    pub files: u64,
+}
+
+/// Why content is outside of its partition.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Violation {
+    /// The content runs past the end of the partition it starts in.
+    Overflow,
+    /// The content is between partitions or past the last one.
+    Unpartitioned,
+    /// The content is in a partition that is not an app one, while the table has app ones.
+    NotApp,
+}
+
+impl Violation {
+    pub fn name(self) -> &'static str {
+        match self {
+            Violation::Overflow => "overflow",
+            Violation::Unpartitioned => "unpartitioned",
+            Violation::NotApp => "not_app",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Offender {
+    pub filename: String,
+    /// Offset from the start of the flash.
+    pub offset: u64,
+    pub size: u64,
+    /// The partition the content starts in.
+    pub partition: Option<String>,
+    pub violation: Violation,
+}
+
+impl Genealogy {
+    /// Checks that every attributed byte inside `flash`, the address range the flash is mapped
+    /// at, is within an app partition of `table`, or any partition when it has no app ones.
+    /// Content is attributed to the partition it starts in.
+    pub fn partition_usage(&self, table: &PartitionTable, flash: Range<u64>) -> PartitionReport {
+        let has_apps = table.partitions.iter().any(Partition::is_app);
+        let mut entries: Vec<(PartitionEntry, Vec<&str>)> = table
+            .partitions
+            .iter()
+            .map(|partition| {
+                let entry = PartitionEntry {
+                    partition: partition.clone(),
+                    used: 0,
+                    files: 0,
+                };
+                (entry, vec![])
+            })
+            .collect();
+        let mut offenders = vec![];
+        let mut outside_flash = 0;
+        for hit in self.index().iter_vaddr() {
+            let size = hit.range.end - hit.range.start;
+            if !flash.contains(&hit.range.start) {
+                outside_flash += size;
+                continue;
+            }
+            let offset = hit.range.start - flash.start;
+            let end = offset + size;
+            let offender = |partition: Option<&Partition>, violation| Offender {
+                filename: hit.filename.to_string(),
+                offset,
+                size,
+                partition: partition.map(|partition| partition.name.clone()),
+                violation,
+            };
+            let Some(partition) = table.partition_at(offset) else {
+                offenders.push(offender(None, Violation::Unpartitioned));
+                continue;
+            };
+            if has_apps && !partition.is_app() {
+                offenders.push(offender(Some(partition), Violation::NotApp));
+            } else if end > partition.range().end {
+                offenders.push(offender(Some(partition), Violation::Overflow));
+            }
+            let (entry, files) = entries
+                .iter_mut()
+                .find(|(entry, _)| entry.partition == *partition)
+                .expect("entries are created for every partition");
+            entry.used += size;
+            files.push(hit.filename);
+        }
+
+        let entries = entries
+            .into_iter()
+            .map(|(mut entry, mut files)| {
+                files.sort_unstable();
+                files.dedup();
+                entry.files = files.len() as u64;
+                entry
+            })
+            .collect();
+        PartitionReport {
+            entries,
+            offenders,
+            outside_flash,
+        }
+    }
+}
+
+impl PartitionReport {
+    /// The per partition table rendered by `Display`.
+    pub fn to_table(&self) -> Table {
+        let mut table = Table::new(&[
+            Align::Left,
+            Align::Left,
+            Align::Left,
+            Align::Right,
+            Align::Right,
+            Align::Right,
+            Align::Right,
+        ])
+        .with_header([
+            "partition",
+            "type",
+            "offset",
+            "size",
+            "used",
+            "usage",
+            "files",
+        ]);
+        for entry in &self.entries {
+            let partition = &entry.partition;
+            let usage = percentage(entry.used, partition.size);
+            let usage = if entry.used > partition.size {
+                Cell::styled(usage, Some(Style::Bold))
+            } else {
+                Cell::from(usage)
+            };
+            table.push_row([
+                Cell::from(partition.name.clone()),
+                Cell::from(partition.kind.clone().unwrap_or_default()),
+                Cell::from(hex(partition.offset)),
+                Cell::from(human_size(partition.size)),
+                Cell::from(human_size(entry.used)),
+                usage,
+                Cell::from(entry.files.to_string()),
+            ]);
+        }
+        table
+    }
+
+    /// The table of the [`offenders`](Self::offenders).
+    pub fn offenders_table(&self) -> Table {
+        let mut table = Table::new(&[
+            Align::Left,
+            Align::Right,
+            Align::Left,
+            Align::Left,
+            Align::Left,
+        ])
+        .with_header(["offset", "size", "partition", "violation", "file"]);
+        for offender in &self.offenders {
+            table.push_row([
+                hex(offender.offset),
+                human_size(offender.size),
+                offender
+                    .partition
+                    .clone()
+                    .unwrap_or_else(|| "-".to_string()),
+                offender.violation.name().to_string(),
+                offender.filename.clone(),
+            ]);
+        }
+        table
+    }
+}
+
+impl Display for PartitionReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_table())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Genealogy;
+
+    use super::{PartitionTable, Violation};
+
+    #[test]
+    fn test_partition_tables() {
+        let esp_idf = PartitionTable::parse(
+            "# Name,   Type, SubType, Offset,  Size, Flags\n\
+             nvs,      data, nvs,     ,        0x6000,\n\
+             phy_init, data, phy,     ,        0x1000,\n\
+             factory,  app,  factory, ,        1M,\n\
+             storage,  data, spiffs,  0x200000, 64K,\n",
+        )
+        .unwrap();
+        let layout: Vec<_> = esp_idf
+            .partitions
+            .iter()
+            .map(|p| (p.name.as_str(), p.offset, p.size))
+            .collect();
+        assert_eq!(
+            layout,
+            [
+                ("nvs", 0x9000, 0x6000),
+                ("phy_init", 0xf000, 0x1000),
+                ("factory", 0x10000, 0x100000),
+                ("storage", 0x200000, 0x10000),
+            ]
+        );
+
+        let mcuboot = PartitionTable::parse(
+            "CONFIG_ESP_BOOTLOADER_OFFSET=0x0000\n\
+             CONFIG_ESP_BOOTLOADER_SIZE=0xF000\n\
+             CONFIG_ESP_IMAGE0_PRIMARY_START_ADDRESS=0x10000\n\
+             CONFIG_ESP_APPLICATION_SIZE=0x100000\n\
+             CONFIG_ESP_IMAGE0_SECONDARY_START_ADDRESS=0x110000\n\
+             CONFIG_ESP_SCRATCH_OFFSET=0x210000\n\
+             CONFIG_ESP_SCRATCH_SIZE=0x40000\n",
+        )
+        .unwrap();
+        let names: Vec<_> = mcuboot.partitions.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, ["bootloader", "slot0", "slot1", "scratch"]);
+        assert!(mcuboot.partitions[1].is_app());
+
+        let toml = PartitionTable::parse(
+            "[[partition]]\nname = \"boot\" # first\noffset = 0\nsize = \"4K\"\n\n\
+             [[partition]]\nname = \"app\"\ntype = \"app\"\noffset = 0x1000\nsize = 0x1000\n",
+        )
+        .unwrap();
+        assert_eq!(toml.end(), 0x2000);
+        assert!(PartitionTable::parse("nvs, data\n").is_err());
+    }
+
+    #[test]
+    fn test_partition_usage() {
+        let mapfile = std::fs::read_to_string("tests/gcc/out.map").unwrap();
+        let binary = std::fs::read("tests/gcc/a.out").unwrap();
+        let genealogy = Genealogy::new(&mapfile, &binary).unwrap();
+
+        // `.text` starts in `app` and `main` runs past its end, the data pages are not partitioned
+        let table = PartitionTable::parse_toml(
+            "[[partition]]\nname = \"header\"\noffset = 0\nsize = 0x1000\n\
+             [[partition]]\nname = \"app\"\ntype = \"app\"\noffset = 0x1000\nsize = 0x160\n",
+        )
+        .unwrap();
+        let report = genealogy.partition_usage(&table, 0..0x3000);
+        assert!(report.entries[1].used > 0);
+        assert!(report.outside_flash > 0);
+        let violation_of = |file: &str| {
+            report
+                .offenders
+                .iter()
+                .find(|offender| offender.filename == file)
+                .map(|offender| offender.violation)
+        };
+        assert_eq!(violation_of("simple.o"), Some(Violation::Overflow));
+        assert_eq!(violation_of("simple2.o"), Some(Violation::Unpartitioned));
+        assert!(report
+            .offenders
+            .iter()
+            .any(|offender| offender.violation == Violation::NotApp));
+    }
+}
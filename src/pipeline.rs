@@ -0,0 +1,258 @@
+//! Parsing of the output sections of a map on worker threads, streamed in map order to the
+//! thread placing them in the binary, see [`GenealogyBuilder::threads`].
+//!
+//! A dispatcher hands the text of every output section to the workers, as split by
+//! [`LayoutCache`](crate::LayoutCache), and stops while the text of the sections parsed but not
+//! consumed yet exceeds the memory limit. The sections are then consumed in map order, so the
+//! result is the one of a single-threaded build. MSVC maps are not split, they are parsed whole
+//! by one worker, which still overlaps with the parsing of the binary.
+//!
+//! [`GenealogyBuilder::threads`]: crate::GenealogyBuilder::threads
+
+use std::{
+    borrow::Cow,
+    collections::BTreeMap,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{channel, Receiver},
+        Arc, Condvar, Mutex,
+    },
+    thread::Scope,
+};
+
+use crate::{
+    incremental::{chunk_lines, section_chunks, shift_lines},
+    parse::extract_mapfile,
+    Section,
+};
+
+/// A chunk of the map did not parse on its own: the map has to be parsed whole, which gives the
+/// error if there is one.
+#[derive(Debug)]
+pub(crate) struct Unsplittable;
+
+/// The bytes of map text parsed ahead of the consumer.
+#[derive(Default)]
+struct Budget {
+    used: Mutex<usize>,
+    freed: Condvar,
+    cancelled: AtomicBool,
+}
+
+impl Budget {
+    /// Waits for `len` bytes to be available, false once cancelled. A chunk bigger than the
+    /// limit is let through when nothing else is in flight.
+    fn acquire(&self, len: usize, limit: usize) -> bool {
+        let mut used = self.used.lock().expect("no panics while holding the lock");
+        while *used > 0 && *used + len > limit && !self.cancelled.load(Ordering::Relaxed) {
+            used = self
+                .freed
+                .wait(used)
+                .expect("no panics while holding the lock");
+        }
+        *used += len;
+        !self.cancelled.load(Ordering::Relaxed)
+    }
+
+    fn release(&self, len: usize) {
+        *self.used.lock().expect("no panics while holding the lock") -= len;
+        self.freed.notify_all();
+    }
+
+    fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+        self.freed.notify_all();
+    }
+}
+
+/// The sections of a map parsed in the background, see [`parse_in_background`].
+pub(crate) struct SectionStream {
+    results: Receiver<(usize, Option<Vec<Section>>)>,
+    /// Sections received before the ones preceding them.
+    pending: BTreeMap<usize, Option<Vec<Section>>>,
+    lens: Vec<usize>,
+    next: usize,
+    budget: Arc<Budget>,
+}
+
+impl SectionStream {
+    /// The sections of the next chunk of the map, `None` after the last one.
+    pub(crate) fn next_sections(&mut self) -> Result<Option<Vec<Section>>, Unsplittable> {
+        if self.next == self.lens.len() {
+            return Ok(None);
+        }
+        let sections = loop {
+            if let Some(sections) = self.pending.remove(&self.next) {
+                break sections;
+            }
+            let (i, sections) = self.results.recv().map_err(|_| Unsplittable)?;
+            self.pending.insert(i, sections);
+        };
+        self.budget.release(self.lens[self.next]);
+        self.next += 1;
+        sections.map(Some).ok_or(Unsplittable)
+    }
+}
+
+impl Drop for SectionStream {
+    fn drop(&mut self) {
+        // Unblocks the dispatcher when the stream is dropped before its end
+        self.budget.cancel();
+    }
+}
+
+/// Starts parsing the output sections of `mapfile` on `threads` workers, with at most about
+/// `memory_limit` bytes of map text parsed ahead of the consumer.
+pub(crate) fn parse_in_background<'scope>(
+    scope: &'scope Scope<'scope, '_>,
+    mapfile: &'scope str,
+    threads: usize,
+    memory_limit: usize,
+) -> SectionStream {
+    let (header, chunks, whole) = match section_chunks(mapfile) {
+        Some((header, chunks)) => (header, chunks, false),
+        None => ("", vec![mapfile], true),
+    };
+    let lens: Vec<usize> = chunks.iter().map(|chunk| chunk.len()).collect();
+    let first_lines = chunk_lines(mapfile, &chunks);
+    let budget = Arc::new(Budget::default());
+
+    let (work_sender, work) = channel::<(usize, &str, usize)>();
+    let work = Arc::new(Mutex::new(work));
+    let (result_sender, results) = channel();
+    for _ in 0..threads.max(1) {
+        let work = Arc::clone(&work);
+        let results = result_sender.clone();
+        scope.spawn(move || loop {
+            let next = work
+                .lock()
+                .expect("no panics while holding the lock")
+                .recv();
+            let Ok((i, chunk, first_line)) = next else {
+                return;
+            };
+            let sections = if whole {
+                extract_mapfile(chunk)
+                    .ok()
+                    .filter(|(_, warnings)| warnings.is_empty())
+                    .map(|(sections, _)| sections)
+            } else {
+                parse_chunk(header, chunk, first_line).map(|section| vec![section])
+            };
+            if results.send((i, sections)).is_err() {
+                return;
+            }
+        });
+    }
+
+    let dispatcher_budget = Arc::clone(&budget);
+    scope.spawn(move || {
+        for (i, (chunk, first_line)) in chunks.into_iter().zip(first_lines).enumerate() {
+            if !dispatcher_budget.acquire(chunk.len(), memory_limit)
+                || work_sender.send((i, chunk, first_line)).is_err()
+            {
+                return;
+            }
+        }
+    });
+
+    SectionStream {
+        results,
+        pending: BTreeMap::new(),
+        lens,
+        next: 0,
+        budget,
+    }
+}
+
+/// The one section of `chunk`, starting at `first_line` of the map, `None` if the chunk does not
+/// parse into exactly the section it starts with. Malformed chunks are parsed with the whole map
+/// instead, for the line numbers of their warnings.
+fn parse_chunk(header: &str, chunk: &str, first_line: usize) -> Option<Section> {
+    let text = if header.is_empty() {
+        Cow::Borrowed(chunk)
+    } else {
+        Cow::Owned(format!("{header}{chunk}"))
+    };
+    let (mut sections, warnings) = extract_mapfile(&text).ok()?;
+    if !warnings.is_empty() {
+        return None;
+    }
+    let mut section = sections.pop().filter(|_| sections.is_empty())?;
+    shift_lines(
+        &mut section,
+        (first_line - header.lines().count()) as isize - 1,
+    );
+    chunk
+        .lines()
+        .next()
+        .is_some_and(|line| line.contains(section.name.as_str()))
+        .then_some(section)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Genealogy;
+
+    #[test]
+    fn test_pipelined_build() {
+        let mut maps: Vec<(&str, String, &str)> = [
+            ("tests/gcc/out.map", "tests/gcc/a.out"),
+            ("tests/clang/output.map", "tests/clang/a.out"),
+            ("tests/wasm/out.map", "tests/wasm/out.wasm"),
+            ("tests/iar/app.map", "tests/iar/app.out"),
+            (
+                "tests/cargo-windows/out.map",
+                "tests/cargo-windows/genealogy.exe",
+            ),
+        ]
+        .into_iter()
+        .map(|(map, binary)| (map, std::fs::read_to_string(map).unwrap(), binary))
+        .collect();
+        // A stripped map, attributed with the cross reference table after its last section
+        let stripped = "Linker script and memory map
+
+.text           0x0000000000001060      0x140
+                0x0000000000001060                _start
+                0x0000000000001151                main
+
+.data           0x0000000000004000       0x10
+                0x0000000000004000                data_start
+
+.comment        0x0000000000000000       0x2b
+
+Cross Reference Table
+
+Symbol                                            File
+_start                                            crt1.o
+data_start                                        data.o
+main                                              main.o
+";
+        maps.push(("stripped", stripped.to_string(), "tests/gcc/a.out"));
+
+        for (map, mapfile, binary) in &maps {
+            let binary = std::fs::read(binary).unwrap();
+            let sequential = Genealogy::builder(mapfile, &binary).build().unwrap();
+            // A limit smaller than any section, so that they are parsed one at a time
+            for (threads, memory_limit) in [(4, usize::MAX), (2, 1)] {
+                let pipelined = Genealogy::builder(mapfile, &binary)
+                    .threads(threads)
+                    .memory_limit(memory_limit)
+                    .build()
+                    .unwrap();
+                assert_eq!(pipelined.sections(), sequential.sections(), "{map}");
+                assert_eq!(pipelined.warnings(), sequential.warnings(), "{map}");
+            }
+        }
+
+        let binary = std::fs::read("tests/gcc/a.out").unwrap();
+        let genealogy = Genealogy::builder(stripped, &binary)
+            .threads(4)
+            .build()
+            .unwrap();
+        let hit = genealogy.query_vaddr_point(0x1151).next().unwrap();
+        assert_eq!(hit.filename, "main.o");
+        let hit = genealogy.query_vaddr_point(0x4008).next().unwrap();
+        assert_eq!(hit.filename, "data.o");
+    }
+}
@@ -0,0 +1,392 @@
+//! Attribution of profiler samples to the object files their addresses come from.
+//!
+//! Profilers export sampled addresses in many formats; each parser turns them into [`Sample`]s
+//! holding link-time virtual addresses, which [`Genealogy::attribute_samples`] then aggregates.
+
+use std::{collections::HashMap, fmt::Display};
+
+use crate::{
+    format::{hex, human_size, Align, Table},
+    origin::file_name,
+    Genealogy, GenealogyError,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Sample {
+    /// Link-time virtual address.
+    pub vaddr: u64,
+    /// Sample count, time, or any other cost.
+    pub weight: f64,
+}
+
+/// Sample weight per object file, see [`Genealogy::attribute_samples`].
+#[derive(Clone, Debug)]
+pub struct ProfileReport {
+    /// Heaviest files first.
+    pub entries: Vec<ProfileEntry>,
+    pub total_weight: f64,
+    /// Weight of the samples whose address is not attributed by the map.
+    pub unattributed_weight: f64,
+}
+
+#[derive(Clone, Debug)]
+pub struct ProfileEntry {
+    pub filename: String,
+    pub weight: f64,
+    /// Bytes the file contributes to the binary, to relate cost with size.
+    pub size: u64,
+}
+
+impl Genealogy {
+    /// Sums the weight of `samples` per object file.
+    pub fn attribute_samples(&self, samples: impl IntoIterator<Item = Sample>) -> ProfileReport {
+        let mut weights: HashMap<&str, f64> = HashMap::new();
+        let mut total_weight = 0.0;
+        let mut unattributed_weight = 0.0;
+        for sample in samples {
+            total_weight += sample.weight;
+            match self.query_vaddr_point(sample.vaddr).next() {
+                Some(hit) => *weights.entry(hit.filename).or_default() += sample.weight,
+                None => unattributed_weight += sample.weight,
+            }
+        }
+
+        let sizes: HashMap<String, u64> = self
+            .report()
+            .entries
+            .into_iter()
+            .map(|e| (e.filename, e.size))
+            .collect();
+        let mut entries: Vec<ProfileEntry> = weights
+            .into_iter()
+            .map(|(filename, weight)| ProfileEntry {
+                filename: filename.to_string(),
+                weight,
+                size: sizes.get(filename).copied().unwrap_or(0),
+            })
+            .collect();
+        entries.sort_by(|a, b| {
+            b.weight
+                .total_cmp(&a.weight)
+                .then_with(|| a.filename.cmp(&b.filename))
+        });
+
+        ProfileReport {
+            entries,
+            total_weight,
+            unattributed_weight,
+        }
+    }
+}
+
+impl ProfileReport {
+    /// The table rendered by `Display`.
+    pub fn to_table(&self) -> Table {
+        let mut table = Table::new(&[Align::Right, Align::Right, Align::Right, Align::Left])
+            .with_header(["weight", "share", "size", "file"]);
+        let share = |weight: f64| {
+            let value = if self.total_weight > 0.0 {
+                weight * 100.0 / self.total_weight
+            } else {
+                0.0
+            };
+            format!("{value:.2}%")
+        };
+        for entry in &self.entries {
+            table.push_row([
+                format_weight(entry.weight),
+                share(entry.weight),
+                human_size(entry.size),
+                entry.filename.clone(),
+            ]);
+        }
+        if self.unattributed_weight > 0.0 {
+            table.push_row([
+                format_weight(self.unattributed_weight),
+                share(self.unattributed_weight),
+                "-".to_string(),
+                "<unattributed>".to_string(),
+            ]);
+        }
+        table.push_row([
+            format_weight(self.total_weight),
+            share(self.total_weight),
+            "-".to_string(),
+            "total".to_string(),
+        ]);
+        table
+    }
+}
+
+impl Display for ProfileReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_table())
+    }
+}
+
+/// Integral weights (sample counts) are printed as such, others with two decimals.
+fn format_weight(weight: f64) -> String {
+    if weight.fract() == 0.0 {
+        format!("{weight:.0}")
+    } else {
+        format!("{weight:.2}")
+    }
+}
+
+/// Parses a CSV export of sampled RVAs from Windows Performance Analyzer.
+///
+/// The header must contain an `RVA` column. An optional `Weight` (or `Count`) column weighs each
+/// row, and rows whose `Module` column does not match `module`, when given, are skipped. The
+/// RVAs are turned into virtual addresses by adding `image_base`.
+pub fn parse_wpa_csv(
+    csv: &str,
+    module: Option<&str>,
+    image_base: u64,
+) -> Result<Vec<Sample>, GenealogyError> {
+    let mut lines = csv.lines().filter(|line| !line.trim().is_empty());
+    let header = split_csv_line(lines.next().ok_or(GenealogyError::WrongProfileFormat)?);
+    let column = |names: &[&str]| {
+        header.iter().position(|title| {
+            let title = title.to_ascii_lowercase();
+            names.iter().any(|name| title.starts_with(name))
+        })
+    };
+    let rva_column = column(&["rva"]).ok_or(GenealogyError::WrongProfileFormat)?;
+    let weight_column = column(&["weight", "count", "samples"]);
+    let module_column = column(&["module"]);
+
+    let mut samples = vec![];
+    for line in lines {
+        let fields = split_csv_line(line);
+        if let (Some(wanted), Some(column)) = (module, module_column) {
+            let row_module = fields.get(column).map(String::as_str).unwrap_or_default();
+            if !row_module.eq_ignore_ascii_case(wanted) {
+                continue;
+            }
+        }
+        let rva = fields
+            .get(rva_column)
+            .and_then(|field| parse_number(field))
+            .ok_or(GenealogyError::WrongProfileFormat)?;
+        let weight = match weight_column {
+            Some(column) => fields
+                .get(column)
+                .and_then(|field| field.replace(',', "").trim().parse().ok())
+                .ok_or(GenealogyError::WrongProfileFormat)?,
+            None => 1.0,
+        };
+        let vaddr = image_base.checked_add(rva).ok_or_else(|| {
+            GenealogyError::AddressOutOfRange(format!(
+                "RVA {} above the image base {}",
+                hex(rva),
+                hex(image_base)
+            ))
+        })?;
+        samples.push(Sample { vaddr, weight });
+    }
+    Ok(samples)
+}
+
+/// Parses a callgrind profile recorded with `--dump-instr=yes`, so that cost lines carry
+/// instruction addresses.
+///
+/// Each sample is weighted by the `event` column (the first event by default). Inclusive costs of
+/// calls are skipped since the callee lines already account for them. When `module` is given,
+/// only the costs of the `ob=` object with that file name are kept. Callgrind reports runtime
+/// addresses: `load_bias` is subtracted from them, e.g. `0x108000` for position independent
+/// executables run by Valgrind on amd64.
+pub fn parse_callgrind(
+    profile: &str,
+    event: Option<&str>,
+    module: Option<&str>,
+    load_bias: u64,
+) -> Result<Vec<Sample>, GenealogyError> {
+    let mut positions: Vec<&str> = vec!["line"];
+    let mut event_column = None;
+    let mut previous: Vec<u64> = vec![];
+    // `ob=(id) name` defines a compressed name that later lines refer to as `ob=(id)`
+    let mut objects: HashMap<&str, &str> = HashMap::new();
+    let mut in_module = true;
+    let mut skip_next_cost = false;
+
+    let mut samples = vec![];
+    for line in profile.lines().map(str::trim) {
+        if let Some(names) = line.strip_prefix("positions:") {
+            positions = names.split_whitespace().collect();
+            previous = vec![0; positions.len()];
+        } else if let Some(names) = line.strip_prefix("events:") {
+            let names: Vec<&str> = names.split_whitespace().collect();
+            event_column = match event {
+                Some(event) => names.iter().position(|&name| name == event),
+                None => (!names.is_empty()).then_some(0),
+            };
+        } else if let Some(object) = line.strip_prefix("ob=") {
+            let object = match object.strip_prefix('(') {
+                Some(compressed) => {
+                    let (id, name) = compressed
+                        .split_once(')')
+                        .ok_or(GenealogyError::WrongProfileFormat)?;
+                    let name = name.trim();
+                    if name.is_empty() {
+                        objects.get(id).copied().unwrap_or_default()
+                    } else {
+                        objects.insert(id, name);
+                        name
+                    }
+                }
+                None => object,
+            };
+            in_module = module.is_none_or(|module| file_name(object) == file_name(module));
+        } else if line.starts_with("calls=")
+            || line.starts_with("jump=")
+            || line.starts_with("jcnd=")
+        {
+            skip_next_cost = true;
+        } else if line.starts_with(|c: char| c.is_ascii_digit() || matches!(c, '+' | '-' | '*')) {
+            let address_column = positions
+                .iter()
+                .position(|&name| name == "instr")
+                .ok_or(GenealogyError::WrongProfileFormat)?;
+            let event_column = event_column.ok_or(GenealogyError::WrongProfileFormat)?;
+
+            let mut fields = line.split_whitespace();
+            for (position, field) in previous.iter_mut().zip(fields.by_ref()) {
+                *position = parse_position(field, *position)?;
+            }
+            if std::mem::take(&mut skip_next_cost) || !in_module {
+                continue;
+            }
+            // Trailing zero costs may be omitted
+            let cost = match fields.nth(event_column) {
+                Some(field) => parse_number(field).ok_or(GenealogyError::WrongProfileFormat)?,
+                None => 0,
+            };
+            if cost > 0 {
+                samples.push(Sample {
+                    // Addresses below the bias are not in the binary and stay unattributed
+                    vaddr: previous[address_column].wrapping_sub(load_bias),
+                    weight: cost as f64,
+                });
+            }
+        }
+    }
+    Ok(samples)
+}
+
+/// A callgrind position: absolute, relative to the previous one (`+n`, `-n`), or unchanged (`*`).
+fn parse_position(field: &str, previous: u64) -> Result<u64, GenealogyError> {
+    let position = if field == "*" {
+        Some(previous)
+    } else if let Some(delta) = field.strip_prefix('+') {
+        parse_number(delta).and_then(|delta| previous.checked_add(delta))
+    } else if let Some(delta) = field.strip_prefix('-') {
+        parse_number(delta).and_then(|delta| previous.checked_sub(delta))
+    } else {
+        parse_number(field)
+    };
+    position.ok_or(GenealogyError::WrongProfileFormat)
+}
+
+fn parse_number(field: &str) -> Option<u64> {
+    let field = field.trim();
+    match field
+        .strip_prefix("0x")
+        .or_else(|| field.strip_prefix("0X"))
+    {
+        Some(hex) => u64::from_str_radix(hex, 16).ok(),
+        None => field.replace(',', "").parse().ok(),
+    }
+}
+
+/// Splits a CSV line, honoring double quotes (WPA quotes numbers with thousands separators).
+fn split_csv_line(line: &str) -> Vec<String> {
+    let mut fields = vec![];
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => in_quotes = !in_quotes,
+            ',' if !in_quotes => fields.push(std::mem::take(&mut field)),
+            c => field.push(c),
+        }
+    }
+    fields.push(field);
+    fields
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_callgrind, parse_wpa_csv, split_csv_line, Sample};
+
+    #[test]
+    fn test_parse_wpa_csv() {
+        assert_eq!(
+            split_csv_line(r#"a,"1,234","x""y""#),
+            ["a", "1,234", "x\"y"]
+        );
+
+        let csv = "\
+Module,Function,RVA,Weight (in view) (ms)
+app.exe,main,0x1010,\"1,000.5\"
+kernel32.dll,BaseThreadInitThunk,0x2000,3
+app.exe,f,4144,2
+";
+        let samples = parse_wpa_csv(csv, Some("APP.EXE"), 0x1_4000_0000).unwrap();
+        assert_eq!(samples.len(), 2);
+        assert_eq!(samples[0].vaddr, 0x1_4000_1010);
+        assert_eq!(samples[0].weight, 1000.5);
+        assert_eq!(samples[1].vaddr, 0x1_4000_1030);
+
+        assert!(parse_wpa_csv("Module,Address\n", None, 0).is_err());
+    }
+
+    #[test]
+    fn test_parse_callgrind() {
+        let profile = "\
+# callgrind format
+version: 1
+positions: instr line
+events: Ir Dr
+
+ob=(1) /build/a.out
+fl=(1) simple.c
+fn=(1) main
+0x109151 3 5 2
++4 * 7
+calls=1 0x109198 8
++3 4 40 10
+-3 * 1 1
+ob=(2) /lib/libc.so.6
+fn=(2) printf
+0x4a0000 0 100
+ob=(1)
+fn=(3) f2
+0x109198 9 0 6
+";
+        let sample = |vaddr, weight| Sample { vaddr, weight };
+        assert_eq!(
+            parse_callgrind(profile, None, Some("a.out"), 0x108000).unwrap(),
+            [
+                sample(0x1151, 5.0),
+                sample(0x1155, 7.0),
+                sample(0x1155, 1.0)
+            ]
+        );
+        assert_eq!(
+            parse_callgrind(profile, Some("Dr"), None, 0).unwrap(),
+            [
+                sample(0x109151, 2.0),
+                sample(0x109155, 1.0),
+                sample(0x109198, 6.0)
+            ]
+        );
+
+        let no_instr = "positions: line\nevents: Ir\n3 5\n";
+        assert!(parse_callgrind(no_instr, None, None, 0).is_err());
+    }
+}
@@ -0,0 +1,216 @@
+//! How trustworthy an attribution is: the share of the bytes of the binary whose file is given
+//! by the map, the share inferred from symbols, and the share no file is known for, for
+//! pipelines to reject builds whose maps are too degraded to report on.
+
+use std::{fmt::Display, ops::Range};
+
+use goblin::{elf::section_header::SHT_NOBITS, Object};
+
+use crate::{
+    format::{human_size, percentage, Align, Cell, Style, Table},
+    validate::Warning,
+    Genealogy, GenealogyError,
+};
+
+/// The weight of estimated bytes in [`AttributionQuality::score`]: their file is likely right,
+/// their boundaries are not.
+pub const ESTIMATED_WEIGHT: f64 = 0.5;
+
+/// The bytes of the sections of a binary by how they were attributed, see
+/// [`Genealogy::attribution_quality`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AttributionQuality {
+    /// Bytes of input sections listed by the map.
+    pub exact: u64,
+    /// Bytes inferred from runs of symbols (MSVC maps, GNU maps without input sections), or in
+    /// PE sections whose raw data is unaligned.
+    pub estimated: u64,
+    /// Bytes of no subsection: sections the map does not list, gaps and alignment padding.
+    pub unmapped: u64,
+}
+
+impl Genealogy {
+    /// Measures how much of `binary`, the binary the genealogy was built from, is attributed
+    /// exactly, approximately or not at all.
+    ///
+    /// The bytes counted are those the sections of the binary occupy in the file: the allocated
+    /// ELF sections and the ones the map lists, or the raw data of every PE section. File
+    /// headers, and ELF sections such as `.symtab` that maps never list, are left out.
+    pub fn attribution_quality(&self, binary: &[u8]) -> Result<AttributionQuality, GenealogyError> {
+        let len = binary.len() as u64;
+        let listed = |name: &str| self.sections().iter().any(|section| section.name == name);
+        let extents: Vec<Range<u64>> =
+            match Object::parse(binary).map_err(GenealogyError::BinaryParse)? {
+                Object::Elf(elf) => elf
+                    .section_headers
+                    .iter()
+                    .filter(|shdr| shdr.sh_type != SHT_NOBITS)
+                    .filter(|shdr| {
+                        shdr.is_alloc()
+                            || elf
+                                .shdr_strtab
+                                .get_at(shdr.sh_name)
+                                .is_some_and(|name| !name.is_empty() && listed(name))
+                    })
+                    .map(|shdr| shdr.sh_offset..shdr.sh_offset.saturating_add(shdr.sh_size))
+                    .collect(),
+                Object::PE(pe) => pe
+                    .sections
+                    .iter()
+                    .map(|section| {
+                        let start = u64::from(section.pointer_to_raw_data);
+                        let size = section.size_of_raw_data.min(section.virtual_size);
+                        start..start.saturating_add(u64::from(size))
+                    })
+                    .collect(),
+                _ => return Err(GenealogyError::UnsupportedBinaryFormat),
+            };
+        let extents = union(
+            extents
+                .into_iter()
+                .map(|extent| extent.start.min(len)..extent.end.min(len))
+                .collect(),
+        );
+
+        let estimated_sections: Vec<&str> =
+            self.warnings()
+                .iter()
+                .filter_map(|warning| match warning {
+                    Warning::SymbolRuns { section, .. }
+                    | Warning::UnalignedRawData { section, .. } => Some(section.as_str()),
+                    _ => None,
+                })
+                .collect();
+        let mut exact = vec![];
+        let mut attributed = vec![];
+        for section in self.sections() {
+            let section_estimated = estimated_sections.contains(&section.name.as_str());
+            for subsection in &section.subsections {
+                let Some(start) = subsection.start_file_offset else {
+                    continue;
+                };
+                let range = start..start.saturating_add(subsection.size);
+                // Only MSVC maps do not list their sections, their subsections are symbol runs
+                // unless read from the binary, like resources
+                let symbol_run = section.line.is_none() && subsection.line.is_some();
+                if !section_estimated && !symbol_run {
+                    exact.push(range.clone());
+                }
+                attributed.push(range);
+            }
+        }
+        let exact = overlap(&union(exact), &extents);
+        let attributed = overlap(&union(attributed), &extents);
+        Ok(AttributionQuality {
+            exact,
+            estimated: attributed - exact,
+            unmapped: extents
+                .iter()
+                .map(|extent| extent.end - extent.start)
+                .sum::<u64>()
+                - attributed,
+        })
+    }
+}
+
+/// The disjoint ranges covering `ranges`, in order.
+fn union(mut ranges: Vec<Range<u64>>) -> Vec<Range<u64>> {
+    ranges.sort_by_key(|range| range.start);
+    let mut union: Vec<Range<u64>> = vec![];
+    for range in ranges.into_iter().filter(|range| !range.is_empty()) {
+        match union.last_mut() {
+            Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+            _ => union.push(range),
+        }
+    }
+    union
+}
+
+/// The number of values in both `a` and `b`, disjoint ranges in order.
+fn overlap(a: &[Range<u64>], b: &[Range<u64>]) -> u64 {
+    let (mut i, mut j, mut overlap) = (0, 0, 0);
+    while i < a.len() && j < b.len() {
+        let start = a[i].start.max(b[j].start);
+        let end = a[i].end.min(b[j].end);
+        overlap += end.saturating_sub(start);
+        if a[i].end < b[j].end {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    overlap
+}
+
+impl AttributionQuality {
+    /// The bytes measured.
+    pub fn total(&self) -> u64 {
+        self.exact + self.estimated + self.unmapped
+    }
+
+    /// The percentage of the bytes attributed, the estimated ones weighing
+    /// [`ESTIMATED_WEIGHT`]. 0 when there is nothing to attribute.
+    pub fn score(&self) -> f64 {
+        match self.total() {
+            0 => 0.0,
+            total => {
+                (self.exact as f64 + self.estimated as f64 * ESTIMATED_WEIGHT) * 100.0
+                    / total as f64
+            }
+        }
+    }
+
+    /// The table rendered by `Display`, without the score.
+    pub fn to_table(&self) -> Table {
+        let total = self.total();
+        let mut table = Table::new(&[Align::Left, Align::Right, Align::Right])
+            .with_header(["bytes", "size", "share"]);
+        for (kind, size) in [
+            ("exact", self.exact),
+            ("estimated", self.estimated),
+            ("unmapped", self.unmapped),
+        ] {
+            table.push_row([kind.to_string(), human_size(size), percentage(size, total)]);
+        }
+        table.push_row([
+            Cell::from("total"),
+            Cell::styled(human_size(total), Some(Style::Bold)),
+            "".into(),
+        ]);
+        table
+    }
+}
+
+impl Display for AttributionQuality {
+    /// The table, then e.g. `quality: 97.5%`.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{}", self.to_table())?;
+        write!(f, "quality: {:.1}%", self.score())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Genealogy;
+
+    #[test]
+    fn test_attribution_quality() {
+        let mapfile = std::fs::read_to_string("tests/gcc/out.map").unwrap();
+        let binary = std::fs::read("tests/gcc/a.out").unwrap();
+        let genealogy = Genealogy::new(&mapfile, &binary).unwrap();
+        let quality = genealogy.attribution_quality(&binary).unwrap();
+        assert_eq!(quality.estimated, 0);
+        assert_eq!(quality.exact, genealogy.report().total);
+        assert!(quality.unmapped > 0);
+        assert!(quality.score() > 50.0);
+
+        // The sections of the MSVC map are inferred from symbols
+        let mapfile = std::fs::read_to_string("tests/cargo-windows/out.map").unwrap();
+        let binary = std::fs::read("tests/cargo-windows/genealogy.exe").unwrap();
+        let genealogy = Genealogy::new(&mapfile, &binary).unwrap();
+        let quality = genealogy.attribution_quality(&binary).unwrap();
+        assert!(quality.estimated > quality.exact);
+        let attributed = quality.exact + quality.estimated;
+        assert!(quality.score() < attributed as f64 * 100.0 / quality.total() as f64);
+    }
+}
@@ -0,0 +1,282 @@
+//! Renderers of reports, so that every report can be written in every format, including the
+//! ones of downstream crates, e.g. a Confluence page or a Slack message.
+//!
+//! A report gives its [`Table`], the view of its text output, and its
+//! [`Value`](crate::output::Value), the document of its structured outputs; a [`ReportRenderer`]
+//! picks the one it renders.
+
+use std::fmt::Write;
+
+use crate::{
+    android::AppReport,
+    archives::InclusionReport,
+    blobs::BlobReport,
+    commits::CommitReport,
+    ctors::ConstructorReport,
+    deadstrip::DeadStripReport,
+    dependencies::HeaderReport,
+    diff::ReportDiff,
+    exports::ExportReport,
+    format::{Align, Cell, Style, Table},
+    got::GotReport,
+    hierarchy::Hierarchy,
+    history::TrendReport,
+    hotcold::HotColdReport,
+    kernel::KernelReport,
+    language::LanguageReport,
+    locality::LocalityReport,
+    matrix::SectionMatrix,
+    memdump::DumpReport,
+    namespaces::NamespaceReport,
+    output::ToValue,
+    ownership::OwnerReport,
+    padding::PaddingReport,
+    pages::PageReport,
+    partitions::PartitionReport,
+    profile::ProfileReport,
+    quality::AttributionQuality,
+    reorder::ReorderReport,
+    resolution::ResolutionReport,
+    snapshot::Snapshot,
+    stack::StackReport,
+    statics::StaticReport,
+    strip::StripReport,
+    uboot::StageReport,
+    vectors::VectorTable,
+    Report,
+};
+
+/// A report any [`ReportRenderer`] can render.
+pub trait Renderable: ToValue {
+    fn to_table(&self) -> Table;
+}
+
+/// Renders reports to text in a format of its own.
+pub trait ReportRenderer {
+    fn render(&self, report: &dyn Renderable) -> String;
+}
+
+/// The aligned table of the terminal, with ANSI styles when `color` is set.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TextRenderer {
+    pub color: bool,
+}
+
+impl ReportRenderer for TextRenderer {
+    fn render(&self, report: &dyn Renderable) -> String {
+        let mut table = report.to_table();
+        table.set_color(self.color);
+        format!("{table}\n")
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct JsonRenderer;
+
+impl ReportRenderer for JsonRenderer {
+    fn render(&self, report: &dyn Renderable) -> String {
+        format!("{}\n", report.to_value().to_json())
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct YamlRenderer;
+
+impl ReportRenderer for YamlRenderer {
+    fn render(&self, report: &dyn Renderable) -> String {
+        report.to_value().to_yaml()
+    }
+}
+
+/// A GitHub flavored Markdown table, e.g. for pull request comments. Bold cells are emphasized,
+/// the colors of size changes are dropped.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MarkdownRenderer;
+
+impl ReportRenderer for MarkdownRenderer {
+    fn render(&self, report: &dyn Renderable) -> String {
+        let table = report.to_table();
+        let row = |cells: &mut dyn Iterator<Item = String>| {
+            let cells: Vec<String> = cells.collect();
+            format!("| {} |\n", cells.join(" | "))
+        };
+        let mut markdown = String::new();
+        // Tables need a header, possibly empty
+        let header = table.header().map_or_else(
+            || vec![String::new(); table.alignments().len()],
+            |header| {
+                header
+                    .iter()
+                    .map(|cell| escape_markdown(&cell.text))
+                    .collect()
+            },
+        );
+        markdown.push_str(&row(&mut header.into_iter()));
+        markdown.push_str(&row(&mut table.alignments().iter().map(
+            |align| match align {
+                Align::Left => ":---".to_string(),
+                Align::Right => "---:".to_string(),
+            },
+        )));
+        for cells in table.rows() {
+            markdown.push_str(&row(&mut cells.iter().map(|cell| {
+                let text = escape_markdown(&cell.text);
+                match cell.style {
+                    Some(Style::Bold) if !text.is_empty() => format!("**{text}**"),
+                    _ => text,
+                }
+            })));
+        }
+        markdown
+    }
+}
+
+fn escape_markdown(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('|', "\\|")
+        .replace('*', "\\*")
+        .replace('_', "\\_")
+        .replace('<', "&lt;")
+}
+
+/// A standalone HTML `<table>`, with size changes in red and green as in the terminal.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct HtmlRenderer;
+
+impl ReportRenderer for HtmlRenderer {
+    fn render(&self, report: &dyn Renderable) -> String {
+        let table = report.to_table();
+        let mut html = String::from("<table>\n");
+        let mut row = |cells: &[Cell], tag: &str| {
+            html.push_str("<tr>");
+            for (column, cell) in cells.iter().enumerate() {
+                html.push('<');
+                html.push_str(tag);
+                if table.alignments().get(column) == Some(&Align::Right) {
+                    html.push_str(" style=\"text-align: right\"");
+                }
+                html.push('>');
+                let text = escape_html(&cell.text);
+                match cell.style {
+                    Some(Style::Bold) if tag == "td" => write!(html, "<strong>{text}</strong>"),
+                    Some(Style::Dim) => write!(html, "<span style=\"opacity: 0.6\">{text}</span>"),
+                    Some(Style::Red) => {
+                        write!(html, "<span style=\"color: #c62828\">{text}</span>")
+                    }
+                    Some(Style::Green) => {
+                        write!(html, "<span style=\"color: #2e7d32\">{text}</span>")
+                    }
+                    _ => write!(html, "{text}"),
+                }
+                .expect("writing to a String");
+                write!(html, "</{tag}>").expect("writing to a String");
+            }
+            html.push_str("</tr>\n");
+        };
+        if let Some(header) = table.header() {
+            row(header, "th");
+        }
+        for cells in table.rows() {
+            row(cells, "td");
+        }
+        html.push_str("</table>\n");
+        html
+    }
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+macro_rules! renderable {
+    ($($report:ty),* $(,)?) => {
+        $(
+            impl Renderable for $report {
+                fn to_table(&self) -> Table {
+                    <$report>::to_table(self)
+                }
+            }
+        )*
+    };
+}
+
+renderable!(
+    AppReport,
+    AttributionQuality,
+    BlobReport,
+    CommitReport,
+    ConstructorReport,
+    DeadStripReport,
+    DumpReport,
+    ExportReport,
+    GotReport,
+    HeaderReport,
+    Hierarchy,
+    HotColdReport,
+    InclusionReport,
+    KernelReport,
+    LanguageReport,
+    LocalityReport,
+    NamespaceReport,
+    OwnerReport,
+    PaddingReport,
+    PageReport,
+    PartitionReport,
+    ProfileReport,
+    ReorderReport,
+    Report,
+    ReportDiff,
+    ResolutionReport,
+    SectionMatrix,
+    Snapshot,
+    StackReport,
+    StageReport,
+    StaticReport,
+    StripReport,
+    TrendReport,
+    VectorTable,
+);
+
+#[cfg(test)]
+mod tests {
+    use crate::{Report, ReportEntry};
+
+    use super::{HtmlRenderer, JsonRenderer, MarkdownRenderer, ReportRenderer, TextRenderer};
+
+    #[test]
+    fn test_renderers() {
+        let report = Report {
+            entries: [("libfoo.a(a_b.o)", 1024), ("<x|y>.o", 12)]
+                .into_iter()
+                .map(|(filename, size)| ReportEntry {
+                    filename: filename.to_string(),
+                    size,
+                    source: None,
+                })
+                .collect(),
+            total: 1036,
+        };
+        assert_eq!(
+            TextRenderer { color: false }.render(&report),
+            format!("{}\n", report.to_table())
+        );
+        assert!(JsonRenderer
+            .render(&report)
+            .starts_with("{\"total\":1036,\"files\":[{\"file\":\"libfoo.a(a_b.o)\""));
+        assert_eq!(
+            MarkdownRenderer.render(&report),
+            "| size | share | file |\n\
+             | ---: | ---: | :--- |\n\
+             | 1.00 KiB | 98.84% | libfoo.a(a\\_b.o) |\n\
+             | 12 B | 1.16% | &lt;x\\|y>.o |\n\
+             | **1.01 KiB** | 100.00% | total in 2 files |\n"
+        );
+        let html = HtmlRenderer.render(&report);
+        assert!(html.starts_with("<table>\n<tr><th style=\"text-align: right\">size</th>"));
+        assert!(html.contains("<td>&lt;x|y&gt;.o</td>"));
+        assert!(html.contains("<strong>1.01 KiB</strong>"));
+    }
+}
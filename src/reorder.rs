@@ -0,0 +1,259 @@
+//! How reordering the input sections of a program moved the bytes of every file: two builds of
+//! the same program, e.g. before and after linking with a symbol ordering file from a profile
+//! (Propeller, PGO, [`Genealogy::symbol_ordering`]), compared per file, to check that the
+//! layout tool grouped what it was meant to and how much it scattered the rest.
+//!
+//! BOLT rewrites the binary after linking and writes no map, so only reorderings done by the
+//! linker can be compared.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Display,
+};
+
+use crate::{
+    format::{human_size, Align, Cell, Style, Table},
+    Genealogy,
+};
+
+/// The layouts of every file in two builds, see [`Genealogy::reorder_report`].
+#[derive(Clone, Debug)]
+pub struct ReorderReport {
+    /// Only files whose layout changed, biggest change in runs first.
+    pub entries: Vec<ReorderEntry>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ReorderEntry {
+    pub filename: String,
+    /// Empty if the file is new.
+    pub old: FileLayout,
+    /// Empty if the file disappeared.
+    pub new: FileLayout,
+    /// Bytes of the input sections found in both builds whose address changed.
+    pub moved: u64,
+}
+
+impl ReorderEntry {
+    /// `new - old` runs, negative when the file was defragmented.
+    pub fn runs_delta(&self) -> i64 {
+        self.new.runs as i64 - self.old.runs as i64
+    }
+}
+
+/// Where the loaded input sections of a file lie in one build, empty ones left out.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FileLayout {
+    pub sections: u64,
+    /// Number of runs of consecutive input sections of the file, in address order and per
+    /// output section.
+    pub runs: u64,
+    pub size: u64,
+    /// Virtual address of the first input section.
+    pub start: u64,
+    /// Bytes between the start of the first input section and the end of the last one, across
+    /// output sections.
+    pub span: u64,
+}
+
+impl FileLayout {
+    /// From 0 when the input sections of the file are all contiguous, to 1 when no two of them
+    /// are, as [`LocalityEntry::fragmentation`](crate::locality::LocalityEntry::fragmentation).
+    pub fn fragmentation(&self) -> f64 {
+        if self.sections <= 1 {
+            0.0
+        } else {
+            (self.runs - 1) as f64 / (self.sections - 1) as f64
+        }
+    }
+}
+
+/// The layout of one file, and the address and size of each of its input sections by name and
+/// occurrence, for input sections of the same name such as `.text`.
+type Layout<'a> = (FileLayout, HashMap<(&'a str, usize), (u64, u64)>);
+
+/// The layout of every file of `genealogy`.
+fn layouts(genealogy: &Genealogy) -> HashMap<&str, Layout<'_>> {
+    let mut sections: Vec<_> = genealogy
+        .sections()
+        .iter()
+        .filter(|section| section.loaded)
+        .collect();
+    sections.sort_by_key(|section| section.start_vaddr);
+
+    let mut layouts: HashMap<&str, Layout> = HashMap::new();
+    let mut previous: Option<(&str, usize)> = None;
+    for (index, section) in sections.into_iter().enumerate() {
+        let mut subsections: Vec<_> = section
+            .subsections
+            .iter()
+            .filter(|subsection| subsection.size > 0)
+            .collect();
+        subsections.sort_by_key(|subsection| subsection.start_vaddr);
+        for subsection in subsections {
+            let filename = subsection.filename.as_str();
+            let (layout, addresses) = layouts.entry(filename).or_insert_with(|| {
+                let layout = FileLayout {
+                    start: subsection.start_vaddr,
+                    ..FileLayout::default()
+                };
+                (layout, HashMap::new())
+            });
+            layout.sections += 1;
+            layout.size += subsection.size;
+            if previous != Some((filename, index)) {
+                layout.runs += 1;
+            }
+            previous = Some((filename, index));
+            let end = subsection.start_vaddr.saturating_add(subsection.size);
+            layout.span = layout.span.max(end - layout.start);
+
+            let occurrence = (0..)
+                .find(|&i| !addresses.contains_key(&(subsection.name.as_str(), i)))
+                .expect("a free occurrence");
+            addresses.insert(
+                (&subsection.name, occurrence),
+                (subsection.start_vaddr, subsection.size),
+            );
+        }
+    }
+    layouts
+}
+
+impl Genealogy {
+    /// Compares the layout of this (old) build with that of a `new` build of the same program.
+    /// Input sections are matched by file, name and order among those of the same name.
+    pub fn reorder_report(&self, new: &Genealogy) -> ReorderReport {
+        let mut files: BTreeMap<&str, (Option<Layout>, Option<Layout>)> = BTreeMap::new();
+        for (filename, layout) in layouts(self) {
+            files.entry(filename).or_default().0 = Some(layout);
+        }
+        for (filename, layout) in layouts(new) {
+            files.entry(filename).or_default().1 = Some(layout);
+        }
+
+        let mut entries: Vec<ReorderEntry> = files
+            .into_iter()
+            .map(|(filename, (old, new))| {
+                let moved = match (&old, &new) {
+                    (Some((_, old)), Some((_, new))) => new
+                        .iter()
+                        .filter(|(key, (vaddr, _))| {
+                            old.get(key)
+                                .is_some_and(|(old_vaddr, _)| old_vaddr != vaddr)
+                        })
+                        .map(|(_, (_, size))| size)
+                        .sum(),
+                    _ => 0,
+                };
+                ReorderEntry {
+                    filename: filename.to_string(),
+                    old: old.map(|(layout, _)| layout).unwrap_or_default(),
+                    new: new.map(|(layout, _)| layout).unwrap_or_default(),
+                    moved,
+                }
+            })
+            .filter(|entry| entry.moved > 0 || entry.old != entry.new)
+            .collect();
+        // Stable sort, ties stay ordered by file name
+        entries.sort_by_key(|entry| {
+            std::cmp::Reverse((entry.runs_delta().unsigned_abs(), entry.moved))
+        });
+        ReorderReport { entries }
+    }
+}
+
+impl ReorderReport {
+    /// Bytes of the input sections found in both builds whose address changed.
+    pub fn moved(&self) -> u64 {
+        self.entries.iter().map(|entry| entry.moved).sum()
+    }
+
+    /// The table rendered by `Display`, runs styled by [`Style::for_delta`].
+    pub fn to_table(&self) -> Table {
+        let mut table = Table::new(&[
+            Align::Right,
+            Align::Right,
+            Align::Right,
+            Align::Right,
+            Align::Right,
+            Align::Left,
+        ])
+        .with_header([
+            "old runs", "new runs", "old span", "new span", "moved", "file",
+        ]);
+        for entry in &self.entries {
+            table.push_row([
+                entry.old.runs.to_string().into(),
+                Cell::styled(
+                    entry.new.runs.to_string(),
+                    Style::for_delta(entry.runs_delta()),
+                ),
+                human_size(entry.old.span).into(),
+                human_size(entry.new.span).into(),
+                human_size(entry.moved).into(),
+                entry.filename.as_str().into(),
+            ]);
+        }
+        let old_runs: u64 = self.entries.iter().map(|entry| entry.old.runs).sum();
+        let new_runs: u64 = self.entries.iter().map(|entry| entry.new.runs).sum();
+        table.push_row([
+            old_runs.to_string().into(),
+            Cell::styled(
+                new_runs.to_string(),
+                Style::for_delta(new_runs as i64 - old_runs as i64),
+            ),
+            "".into(),
+            "".into(),
+            human_size(self.moved()).into(),
+            format!("total, {} files changed", self.entries.len()).into(),
+        ]);
+        table
+    }
+}
+
+impl Display for ReorderReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_table())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Genealogy;
+
+    #[test]
+    fn test_reorder_report() {
+        let genealogy = |name: &str| {
+            let mapfile = std::fs::read_to_string(format!("tests/reorder/{name}.map")).unwrap();
+            let binary = std::fs::read(format!("tests/reorder/{name}")).unwrap();
+            Genealogy::new(&mapfile, &binary).unwrap()
+        };
+        let (before, after) = (genealogy("before"), genealogy("after"));
+
+        // The hot functions come first, leaving the cold ones of both files behind
+        let report = before.reorder_report(&after);
+        let changes: Vec<(&str, u64, u64, u64)> = report
+            .entries
+            .iter()
+            .map(|entry| {
+                (
+                    entry.filename.as_str(),
+                    entry.old.runs,
+                    entry.new.runs,
+                    entry.moved,
+                )
+            })
+            .collect();
+        assert_eq!(changes, [("parse.o", 1, 2, 0x71), ("render.o", 1, 2, 0x18)]);
+        let parse = &report.entries[0];
+        assert_eq!(
+            (parse.old.fragmentation(), parse.new.fragmentation()),
+            (0.0, 0.5)
+        );
+        assert_eq!(parse.new.span, 0x252 - 0x1d1);
+        assert_eq!(report.moved(), 0x89);
+
+        assert!(after.reorder_report(&after).entries.is_empty());
+    }
+}
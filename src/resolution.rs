@@ -0,0 +1,172 @@
+//! Which definition the linker kept when several objects define a symbol, e.g. inline functions
+//! and templates emitted in COMDAT groups or weak definitions overridden by a strong one.
+
+use std::{collections::BTreeMap, fmt::Display};
+
+use crate::{
+    format::{Align, Table},
+    parse::{discarded_subsections, normalize_line_endings},
+    symbols::demangle,
+    Genealogy, GenealogyError,
+};
+
+/// Prefixes of the sections compilers emit per symbol (`-ffunction-sections`,
+/// `-fdata-sections`, COMDAT groups), longest first.
+const SYMBOL_SECTION_PREFIXES: [&str; 11] = [
+    ".text.unlikely.",
+    ".text.startup.",
+    ".text.exit.",
+    ".text.hot.",
+    ".data.rel.ro.",
+    ".rodata.",
+    ".text.",
+    ".data.",
+    ".tdata.",
+    ".tbss.",
+    ".bss.",
+];
+
+/// A symbol defined by several objects, see [`Genealogy::symbol_resolutions`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SymbolResolution {
+    pub symbol: String,
+    pub demangled: Option<String>,
+    /// The file whose definition is in the binary, if it is attributed.
+    pub kept: Option<String>,
+    /// The files whose definitions the linker discarded, as named in the map.
+    pub discarded: Vec<String>,
+}
+
+impl SymbolResolution {
+    /// The demangled name if any, the symbol name otherwise.
+    pub fn display_name(&self) -> &str {
+        self.demangled.as_deref().unwrap_or(&self.symbol)
+    }
+}
+
+/// The symbols with discarded definitions, by name, see [`Genealogy::symbol_resolutions`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ResolutionReport {
+    pub resolutions: Vec<SymbolResolution>,
+}
+
+impl Genealogy {
+    /// Lists the symbols whose definition was discarded from some objects, and the file whose
+    /// definition was kept.
+    ///
+    /// Discarded definitions are read from the discarded input sections of GNU maps, so only
+    /// symbols with their own section (COMDAT groups, `.gnu.linkonce` sections,
+    /// `-ffunction-sections`) are found. The kept definition is located with the symbol table
+    /// when the genealogy was built with
+    /// [`GenealogyBuilder::symbols`](crate::GenealogyBuilder::symbols), and by section name
+    /// otherwise. Other map formats do not list discarded sections, the report is then empty.
+    pub fn symbol_resolutions(&self, mapfile: &str) -> Result<ResolutionReport, GenealogyError> {
+        let mut discarded: BTreeMap<&str, (&str, Vec<String>)> = BTreeMap::new();
+        let discarded_subsections = discarded_subsections(&normalize_line_endings(mapfile))?;
+        for subsection in &discarded_subsections {
+            let Some(symbol) = SYMBOL_SECTION_PREFIXES
+                .iter()
+                .find_map(|prefix| subsection.name.strip_prefix(prefix))
+            else {
+                continue;
+            };
+            let (_, files) = discarded
+                .entry(symbol)
+                .or_insert((subsection.name.as_str(), vec![]));
+            if !files.contains(&subsection.filename) {
+                files.push(subsection.filename.clone());
+            }
+        }
+
+        let resolutions = discarded
+            .into_iter()
+            .map(|(symbol, (section, discarded))| {
+                let kept = match self.lookup_symbol(symbol) {
+                    Ok(hits) => hits.iter().find_map(|hit| hit.filename).map(str::to_string),
+                    Err(_) => self
+                        .sections()
+                        .iter()
+                        .flat_map(|s| s.subsections.iter())
+                        .find(|subsection| subsection.name == section)
+                        .map(|subsection| subsection.filename.clone()),
+                };
+                SymbolResolution {
+                    symbol: symbol.to_string(),
+                    demangled: demangle(symbol),
+                    kept,
+                    discarded,
+                }
+            })
+            .collect();
+
+        Ok(ResolutionReport { resolutions })
+    }
+}
+
+impl ResolutionReport {
+    /// The table rendered by `Display`.
+    pub fn to_table(&self) -> Table {
+        let mut table = Table::new(&[Align::Left, Align::Left, Align::Left]).with_header([
+            "symbol",
+            "kept",
+            "discarded",
+        ]);
+        for resolution in &self.resolutions {
+            table.push_row([
+                resolution.display_name().to_string(),
+                resolution.kept.as_deref().unwrap_or("-").to_string(),
+                resolution.discarded.join(", "),
+            ]);
+        }
+        table
+    }
+}
+
+impl Display for ResolutionReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_table())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Genealogy;
+
+    #[test]
+    fn test_symbol_resolutions() {
+        // Another definition of `f` and of `main`, discarded in favor of the ones of simple.o
+        let mapfile = std::fs::read_to_string("tests/gcc/out.map")
+            .unwrap()
+            .replacen(
+                " .note.GNU-stack\n",
+                " .text.main\n                0x0000000000000000       0x4f other.o\n \
+                 .text.f        0x0000000000000000        0x8 other.o\n \
+                 .note.GNU-stack\n",
+                1,
+            );
+        let binary = std::fs::read("tests/gcc/a.out").unwrap();
+
+        for symbols in [true, false] {
+            let genealogy = Genealogy::builder(&mapfile, &binary)
+                .symbols(symbols)
+                .build()
+                .unwrap();
+            let report = genealogy.symbol_resolutions(&mapfile).unwrap();
+            let resolutions: Vec<_> = report
+                .resolutions
+                .iter()
+                .map(|r| (r.symbol.as_str(), r.kept.as_deref(), r.discarded.join(",")))
+                .collect();
+            // Without symbols, kept definitions are matched by section name, and simple.o did
+            // not put its functions in sections of their own
+            let kept = symbols.then_some("simple.o");
+            assert_eq!(
+                resolutions,
+                [
+                    ("f", kept, "other.o".to_string()),
+                    ("main", kept, "other.o".to_string())
+                ]
+            );
+        }
+    }
+}
@@ -0,0 +1,312 @@
+//! The resources of PE images (icons, manifests, version information, embedded files), read
+//! from the resource directory of `.rsrc`. The map only lists `.rsrc` as a whole, so every
+//! resource is attributed to a synthetic origin named after it, e.g. `<resource>/ICON/1/1033`.
+
+use goblin::pe::PE;
+
+use crate::{Section, SubSection};
+
+/// The first component of the origins of resources.
+pub const ORIGIN_PREFIX: &str = "<resource>";
+
+/// The predefined resource types, by ID.
+const TYPES: &[(u32, &str)] = &[
+    (1, "CURSOR"),
+    (2, "BITMAP"),
+    (3, "ICON"),
+    (4, "MENU"),
+    (5, "DIALOG"),
+    (6, "STRING"),
+    (7, "FONTDIR"),
+    (8, "FONT"),
+    (9, "ACCELERATOR"),
+    (10, "RCDATA"),
+    (11, "MESSAGETABLE"),
+    (12, "GROUP_CURSOR"),
+    (14, "GROUP_ICON"),
+    (16, "VERSION"),
+    (17, "DLGINCLUDE"),
+    (19, "PLUGPLAY"),
+    (20, "VXD"),
+    (21, "ANICURSOR"),
+    (22, "ANIICON"),
+    (23, "HTML"),
+    (24, "MANIFEST"),
+];
+
+/// Set in directory entries naming their entry with a string, or pointing to a subdirectory.
+const HIGH_BIT: u32 = 0x8000_0000;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Resource {
+    /// The predefined type, e.g. `ICON`, or the name or ID of a custom one.
+    pub kind: String,
+    /// The name, or the ID for resources without one.
+    pub name: String,
+    pub language: u32,
+    pub rva: u32,
+    pub size: u32,
+    /// `None` when the data is not in the raw data of a section.
+    pub file_offset: Option<u64>,
+}
+
+impl Resource {
+    /// The synthetic origin of the resource, `<resource>/<type>/<name>/<language>`.
+    pub fn origin(&self) -> String {
+        format!(
+            "{ORIGIN_PREFIX}/{}/{}/{}",
+            self.kind, self.name, self.language
+        )
+    }
+}
+
+/// The resources of `pe`, parsed from `binary`, in directory order. Empty when the image has no
+/// resource directory or a malformed one.
+pub fn pe_resources(pe: &PE, binary: &[u8]) -> Vec<Resource> {
+    let file_offset = |rva: u32| {
+        pe.sections.iter().find_map(|section| {
+            let offset = rva.checked_sub(section.virtual_address)?;
+            (offset < section.size_of_raw_data)
+                .then(|| u64::from(section.pointer_to_raw_data) + u64::from(offset))
+        })
+    };
+    let Some(directory) = pe
+        .header
+        .optional_header
+        .and_then(|header| *header.data_directories.get_resource_table())
+        .filter(|directory| directory.virtual_address != 0)
+    else {
+        return vec![];
+    };
+    let bytes = file_offset(directory.virtual_address)
+        .and_then(|start| binary.get(usize::try_from(start).ok()?..));
+    let Some(mut resources) = bytes.and_then(parse_directory) else {
+        return vec![];
+    };
+    for resource in &mut resources {
+        resource.file_offset = file_offset(resource.rva);
+    }
+    resources
+}
+
+/// Parses the three levels (type, name, language) of the resource directory `bytes` start with.
+/// Offsets in the directory are relative to its start, the data of the leaves is found by RVA.
+fn parse_directory(bytes: &[u8]) -> Option<Vec<Resource>> {
+    let mut resources = vec![];
+    for (kind, types) in entries(bytes, 0)? {
+        let kind = match kind {
+            Id::Number(id) => TYPES
+                .iter()
+                .find(|(type_id, _)| *type_id == id)
+                .map_or_else(|| format!("#{id}"), |(_, name)| name.to_string()),
+            Id::Name(name) => name,
+        };
+        for (name, names) in entries(bytes, types.checked_sub(HIGH_BIT)?)? {
+            let name = match name {
+                Id::Number(id) => id.to_string(),
+                Id::Name(name) => name,
+            };
+            for (language, data) in entries(bytes, names.checked_sub(HIGH_BIT)?)? {
+                let Id::Number(language) = language else {
+                    return None;
+                };
+                // Leaves point to data entries, not to subdirectories
+                if data & HIGH_BIT != 0 {
+                    return None;
+                }
+                resources.push(Resource {
+                    kind: kind.clone(),
+                    name: name.clone(),
+                    language,
+                    rva: read_u32(bytes, data)?,
+                    size: read_u32(bytes, data.checked_add(4)?)?,
+                    file_offset: None,
+                });
+            }
+        }
+    }
+    Some(resources)
+}
+
+enum Id {
+    Number(u32),
+    Name(String),
+}
+
+/// The entries of the directory at `offset`: their ID or name, and their offset.
+fn entries(bytes: &[u8], offset: u32) -> Option<Vec<(Id, u32)>> {
+    let count = u32::from(read_u16(bytes, offset.checked_add(12)?)?)
+        + u32::from(read_u16(bytes, offset.checked_add(14)?)?);
+    (0..count)
+        .map(|i| {
+            let entry = offset.checked_add(16 + 8 * i)?;
+            let id = read_u32(bytes, entry)?;
+            let id = if id & HIGH_BIT == 0 {
+                Id::Number(id)
+            } else {
+                // A length in UTF-16 code units, then the name
+                let name = id - HIGH_BIT;
+                let length = read_u16(bytes, name)?;
+                let units = (0..u32::from(length))
+                    .map(|unit| read_u16(bytes, name.checked_add(2 + 2 * unit)?))
+                    .collect::<Option<Vec<_>>>()?;
+                Id::Name(String::from_utf16_lossy(&units))
+            };
+            Some((id, read_u32(bytes, entry.checked_add(4)?)?))
+        })
+        .collect()
+}
+
+fn read_u16(bytes: &[u8], offset: u32) -> Option<u16> {
+    let start = usize::try_from(offset).ok()?;
+    Some(u16::from_le_bytes(
+        bytes.get(start..start + 2)?.try_into().ok()?,
+    ))
+}
+
+fn read_u32(bytes: &[u8], offset: u32) -> Option<u32> {
+    let start = usize::try_from(offset).ok()?;
+    Some(u32::from_le_bytes(
+        bytes.get(start..start + 4)?.try_into().ok()?,
+    ))
+}
+
+/// Adds a subsection of its synthetic origin for every resource to the section holding it,
+/// unless the map already attributes some of its bytes.
+pub(crate) fn attribute_resources(
+    sections: &mut [Section],
+    resources: &[Resource],
+    image_base: u64,
+) {
+    for resource in resources.iter().filter(|resource| resource.size > 0) {
+        let start = image_base + u64::from(resource.rva);
+        let end = start + u64::from(resource.size);
+        let Some(section) = sections.iter_mut().find(|section| {
+            section.loaded
+                && section.start_vaddr <= start
+                && end <= section.start_vaddr + section.size
+        }) else {
+            continue;
+        };
+        let attributed = section.subsections.iter().any(|subsection| {
+            subsection.start_vaddr < end && start < subsection.start_vaddr + subsection.size
+        });
+        if attributed {
+            continue;
+        }
+        section.subsections.push(SubSection {
+            name: section.name.clone(),
+            start_vaddr: start,
+            start_file_offset: resource.file_offset,
+            size: u64::from(resource.size),
+            filename: resource.origin(),
+            folded_with: vec![],
+            line: None,
+        });
+    }
+    for section in sections {
+        section
+            .subsections
+            .sort_by_key(|subsection| subsection.start_vaddr);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Section, SubSection};
+
+    use super::{attribute_resources, parse_directory, Resource};
+
+    /// A directory of an `ICON` named 1 and a custom `CONFIG` type named `DEFAULTS`, both in
+    /// language 1033, with their data at RVA 0x2100 and 0x2200.
+    fn directory() -> Vec<u8> {
+        let mut bytes = vec![0u8; 0x110];
+        let mut put = |offset: usize, values: &[u32]| {
+            for (i, value) in values.iter().enumerate() {
+                bytes[offset + 4 * i..offset + 4 * i + 4].copy_from_slice(&value.to_le_bytes());
+            }
+        };
+        let directory = |named: u32, ids: u32| [0, 0, 0, named | (ids << 16)];
+        // Types: one named, one ID
+        put(0x00, &directory(1, 1));
+        put(0x10, &[0x8000_00e0, 0x8000_0028, 3, 0x8000_0040]);
+        // Names and languages of `CONFIG`
+        put(0x28, &[0, 0, 0, 1]);
+        put(0x38, &[0x8000_00f0, 0x8000_0058]);
+        put(0x58, &[0, 0, 0, 1 << 16]);
+        put(0x68, &[1033, 0xc0]);
+        // Names and languages of the icon
+        put(0x40, &[0, 0, 0, 1 << 16]);
+        put(0x50, &[1, 0x8000_0070]);
+        put(0x70, &[0, 0, 0, 1 << 16]);
+        put(0x80, &[1033, 0xb0]);
+        // Data entries
+        put(0xb0, &[0x2100, 0xe8, 0, 0]);
+        put(0xc0, &[0x2200, 0x40, 0, 0]);
+        // Names
+        for (offset, name) in [(0xe0, "CONFIG"), (0xf0, "DEFAULTS")] {
+            let units: Vec<u16> = name.encode_utf16().collect();
+            bytes[offset..offset + 2].copy_from_slice(&(units.len() as u16).to_le_bytes());
+            for (i, unit) in units.iter().enumerate() {
+                bytes[offset + 2 + 2 * i..offset + 4 + 2 * i].copy_from_slice(&unit.to_le_bytes());
+            }
+        }
+        bytes
+    }
+
+    #[test]
+    fn test_resources() {
+        let resources = parse_directory(&directory()).unwrap();
+        let summary: Vec<_> = resources
+            .iter()
+            .map(|resource| (resource.origin(), resource.rva, resource.size))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("<resource>/CONFIG/DEFAULTS/1033".to_string(), 0x2200, 0x40),
+                ("<resource>/ICON/1/1033".to_string(), 0x2100, 0xe8),
+            ]
+        );
+        assert!(parse_directory(&directory()[..0x60]).is_none());
+
+        // The icon fills unattributed bytes, the other resource is already attributed
+        let mut sections = [Section {
+            name: ".rsrc".to_string(),
+            start_vaddr: 0x1_4000_2000,
+            start_file_offset: Some(0x1000),
+            size: 0x1000,
+            loaded: true,
+            subsections: vec![SubSection {
+                name: ".rsrc$02".to_string(),
+                start_vaddr: 0x1_4000_2200,
+                start_file_offset: Some(0x1200),
+                size: 0x40,
+                filename: "app.res".to_string(),
+                folded_with: vec![],
+                line: None,
+            }],
+            line: None,
+        }];
+        let resources: Vec<Resource> = resources
+            .into_iter()
+            .map(|resource| Resource {
+                file_offset: Some(u64::from(resource.rva) - 0x1000),
+                ..resource
+            })
+            .collect();
+        attribute_resources(&mut sections, &resources, 0x1_4000_0000);
+        let subsections: Vec<_> = sections[0]
+            .subsections
+            .iter()
+            .map(|subsection| (subsection.filename.as_str(), subsection.start_file_offset))
+            .collect();
+        assert_eq!(
+            subsections,
+            [
+                ("<resource>/ICON/1/1033", Some(0x1100)),
+                ("app.res", Some(0x1200))
+            ]
+        );
+    }
+}
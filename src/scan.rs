@@ -0,0 +1,481 @@
+//! The line matchers of the map parsers.
+//!
+//! They are implemented with regexes by default, and with hand-written scanners when the
+//! `no-regex` feature is enabled: those avoid compiling the regexes and backtracking on every
+//! line, and do not need the `regex` dependency.
+
+#[cfg(all(feature = "regex", not(feature = "no-regex")))]
+pub(crate) use self::regexes::*;
+#[cfg(feature = "no-regex")]
+pub(crate) use self::scanners::*;
+
+/// An output section (`file` empty) or input section line of a GNU map, e.g.
+/// ` .text          0x0000000000001149       0x4f simple.o`. Long names may be followed by a
+/// line break. Names start with a dot, or with an underscore like the `__ksymtab` sections of
+/// Linux.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct GnuEntry<'a> {
+    /// Byte offset of the line in the map.
+    pub offset: usize,
+    pub name: &'a str,
+    pub vrom: &'a str,
+    pub size: &'a str,
+    pub file: &'a str,
+}
+
+/// A symbol line of a GNU map, e.g. `                0x0000000000001151                main`.
+/// Assignments such as `. = ALIGN (0x8)` are not symbols.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct GnuSymbol<'a> {
+    pub vrom: &'a str,
+    pub name: &'a str,
+}
+
+/// Whether the text after the address of a GNU map line names a symbol, rather than being the
+/// size and file of a wrapped input section line or an assignment.
+fn is_gnu_symbol_name(name: &str) -> bool {
+    !name.starts_with("0x") && !name.contains(" = ")
+}
+
+/// A section, input section or symbol line of an LLVM map, told apart by the indentation of
+/// their name.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct LlvmEntry<'a> {
+    pub vma: &'a str,
+    pub size: &'a str,
+    /// The width of the space before the name.
+    pub spaces: usize,
+    pub name: &'a str,
+}
+
+/// A line of the `Publics by Value` or `Static symbols` lists of an MSVC map, e.g.
+/// ` 0001:00000010       main       0000000140001010 f   main.obj`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct MsvcSymbol<'a> {
+    pub section: &'a str,
+    pub section_offset: &'a str,
+    pub name: &'a str,
+    pub vaddr: &'a str,
+    pub origin: Option<&'a str>,
+}
+
+/// A section contribution line of the table at the top of MSVC maps, e.g.
+/// ` 0001:00000000 000010a0H .text$mn                CODE`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct MsvcGroup<'a> {
+    pub section: &'a str,
+    pub start: &'a str,
+    pub length: &'a str,
+    pub name: &'a str,
+}
+
+#[cfg(feature = "regex")]
+#[cfg_attr(feature = "no-regex", allow(dead_code))]
+mod regexes {
+    use std::sync::OnceLock;
+
+    use regex::{Regex, RegexBuilder};
+
+    use super::{is_gnu_symbol_name, GnuEntry, GnuSymbol, LlvmEntry, MsvcGroup, MsvcSymbol};
+
+    fn compiled(cell: &'static OnceLock<Regex>, pattern: &str, multi_line: bool) -> &'static Regex {
+        cell.get_or_init(|| {
+            RegexBuilder::new(pattern)
+                .multi_line(multi_line)
+                .build()
+                .expect("I know how to write regexes")
+        })
+    }
+
+    /// The width of the space between the `Out` and `In` columns of an LLVM map header.
+    pub(crate) fn llvm_header(mapfile: &str) -> Option<usize> {
+        static REGEX: OnceLock<Regex> = OnceLock::new();
+        let regex = compiled(
+            &REGEX,
+            r"VMA(?:\s+)LMA(?:\s+)Size(?:\s+)Align(?:\s+)Out(?<out_in_space>\s+)In(?:\s+)Symbol",
+            false,
+        );
+        regex.captures(mapfile).map(|c| c["out_in_space"].len())
+    }
+
+    pub(crate) fn gnu_sections(mapfile: &str) -> impl Iterator<Item = GnuEntry<'_>> {
+        static REGEX: OnceLock<Regex> = OnceLock::new();
+        let regex = compiled(
+            &REGEX,
+            r"^(?P<name>[._][^\s]+)\s+0x(?P<vrom>[0-9a-fA-F]+)[[:blank:]]+0x(?P<size>[0-9a-fA-F]+)",
+            true,
+        );
+        regex.captures_iter(mapfile).map(|c| GnuEntry {
+            offset: c.get(0).unwrap().start(),
+            name: c.name("name").unwrap().as_str(),
+            vrom: c.name("vrom").unwrap().as_str(),
+            size: c.name("size").unwrap().as_str(),
+            file: "",
+        })
+    }
+
+    pub(crate) fn gnu_subsections(mapfile: &str) -> impl Iterator<Item = GnuEntry<'_>> {
+        static REGEX: OnceLock<Regex> = OnceLock::new();
+        let regex = compiled(
+            &REGEX,
+            r"^ (?P<name>[._][^\s]+)\s+0x(?P<vrom>[0-9a-fA-F]+)[[:blank:]]+0x(?P<size>[0-9a-fA-F]+)[[:blank:]]+(?P<file>[^\s][^\r\n]*)",
+            true,
+        );
+        regex.captures_iter(mapfile).map(|c| GnuEntry {
+            offset: c.get(0).unwrap().start(),
+            name: c.name("name").unwrap().as_str(),
+            vrom: c.name("vrom").unwrap().as_str(),
+            size: c.name("size").unwrap().as_str(),
+            file: c.name("file").unwrap().as_str(),
+        })
+    }
+
+    pub(crate) fn gnu_symbol(line: &str) -> Option<GnuSymbol<'_>> {
+        static REGEX: OnceLock<Regex> = OnceLock::new();
+        let regex = compiled(
+            &REGEX,
+            r"^[[:blank:]]+0x(?<vrom>[0-9a-fA-F]+)[[:blank:]]+(?<name>[^[:space:]](?:.*[^[:space:]])?)[[:space:]]*$",
+            false,
+        );
+        let c = regex.captures(line)?;
+        Some(GnuSymbol {
+            vrom: c.name("vrom")?.as_str(),
+            name: c.name("name")?.as_str(),
+        })
+        .filter(|symbol| is_gnu_symbol_name(symbol.name))
+    }
+
+    pub(crate) fn llvm_entry(line: &str) -> Option<LlvmEntry<'_>> {
+        static REGEX: OnceLock<Regex> = OnceLock::new();
+        let regex = compiled(
+            &REGEX,
+            r"^(?:\s)*(?<vma>[0-9a-fA-F]+)(?:\s)*(?<lma>[0-9a-fA-F]+)(?:\s)*(?<size>[0-9a-fA-F]+)(?:\s)*(?<align>[0-9]+)(?<spaces>\s+)(?<name>.+)$",
+            false,
+        );
+        let c = regex.captures(line)?;
+        Some(LlvmEntry {
+            vma: c.name("vma")?.as_str(),
+            size: c.name("size")?.as_str(),
+            spaces: c.name("spaces")?.len(),
+            name: c.name("name")?.as_str(),
+        })
+    }
+
+    pub(crate) fn msvc_symbol(line: &str) -> Option<MsvcSymbol<'_>> {
+        static REGEX: OnceLock<Regex> = OnceLock::new();
+        let regex = compiled(
+            &REGEX,
+            r"^ (?<section>[0-9a-fA-F]{4}):(?<section_offset>[0-9a-fA-F]{8})\s+(?<name>[^ ]+)\s+(?<vaddr>[0-9a-fA-F]{16})(?:(?: \w)?\s+(?<origin>\S.*))?$",
+            false,
+        );
+        let c = regex.captures(line)?;
+        Some(MsvcSymbol {
+            section: c.name("section")?.as_str(),
+            section_offset: c.name("section_offset")?.as_str(),
+            name: c.name("name")?.as_str(),
+            vaddr: c.name("vaddr")?.as_str(),
+            origin: c.name("origin").map(|m| m.as_str()),
+        })
+    }
+
+    pub(crate) fn msvc_groups(mapfile: &str) -> impl Iterator<Item = MsvcGroup<'_>> {
+        static REGEX: OnceLock<Regex> = OnceLock::new();
+        let regex = compiled(
+            &REGEX,
+            r"^ (?<section>[0-9a-fA-F]{4}):(?<start>[0-9a-fA-F]{8}) (?<length>[0-9a-fA-F]{8})H[[:blank:]]+(?<name>\S+)[[:blank:]]+\w+[[:blank:]]*\r?$",
+            true,
+        );
+        regex.captures_iter(mapfile).map(|c| MsvcGroup {
+            section: c.name("section").unwrap().as_str(),
+            start: c.name("start").unwrap().as_str(),
+            length: c.name("length").unwrap().as_str(),
+            name: c.name("name").unwrap().as_str(),
+        })
+    }
+}
+
+#[cfg(any(feature = "no-regex", test))]
+#[cfg_attr(not(feature = "no-regex"), allow(dead_code))]
+mod scanners {
+    use memchr::{memchr2, memchr_iter, memmem};
+
+    use super::{is_gnu_symbol_name, GnuEntry, GnuSymbol, LlvmEntry, MsvcGroup, MsvcSymbol};
+
+    /// A cursor over the bytes of an ASCII line format.
+    struct Cursor<'a> {
+        text: &'a str,
+        position: usize,
+    }
+
+    impl<'a> Cursor<'a> {
+        fn new(text: &'a str, position: usize) -> Self {
+            Self { text, position }
+        }
+
+        fn peek(&self) -> Option<u8> {
+            self.text.as_bytes().get(self.position).copied()
+        }
+
+        fn literal(&mut self, literal: &str) -> Option<()> {
+            self.text[self.position..].starts_with(literal).then(|| {
+                self.position += literal.len();
+            })
+        }
+
+        /// The longest run of bytes matching `predicate`, `None` if shorter than `min`.
+        fn take_while(&mut self, min: usize, predicate: impl Fn(u8) -> bool) -> Option<&'a str> {
+            let start = self.position;
+            let len = self.text.as_bytes()[start..]
+                .iter()
+                .take_while(|&&b| predicate(b))
+                .count();
+            (len >= min).then(|| {
+                self.position += len;
+                &self.text[start..self.position]
+            })
+        }
+
+        /// Exactly `len` bytes matching `predicate`.
+        fn take_exactly(&mut self, len: usize, predicate: impl Fn(u8) -> bool) -> Option<&'a str> {
+            let field = self.text.get(self.position..self.position + len)?;
+            field.bytes().all(predicate).then(|| {
+                self.position += len;
+                field
+            })
+        }
+
+        /// The rest of the line, without the line break.
+        fn rest_of_line(&mut self) -> &'a str {
+            let start = self.position;
+            let len = memchr2(b'\r', b'\n', &self.text.as_bytes()[start..])
+                .unwrap_or(self.text.len() - start);
+            self.position += len;
+            &self.text[start..self.position]
+        }
+    }
+
+    fn is_blank(b: u8) -> bool {
+        b == b' ' || b == b'\t'
+    }
+
+    fn is_word(b: u8) -> bool {
+        b.is_ascii_alphanumeric() || b == b'_'
+    }
+
+    /// The offsets of the lines of `text`.
+    fn line_starts(text: &str) -> impl Iterator<Item = usize> + '_ {
+        std::iter::once(0).chain(
+            memchr_iter(b'\n', text.as_bytes())
+                .map(|i| i + 1)
+                .filter(move |&i| i < text.len()),
+        )
+    }
+
+    /// Calls `entry` at every line start, skipping the lines its matches span.
+    fn entries<'a, T>(
+        text: &'a str,
+        entry: impl Fn(&mut Cursor<'a>) -> Option<T> + 'a,
+    ) -> impl Iterator<Item = T> + 'a {
+        let mut end = 0;
+        line_starts(text).filter_map(move |start| {
+            if start < end {
+                return None;
+            }
+            let mut cursor = Cursor::new(text, start);
+            let found = entry(&mut cursor)?;
+            end = cursor.position;
+            Some(found)
+        })
+    }
+
+    pub(crate) fn llvm_header(mapfile: &str) -> Option<usize> {
+        memmem::find_iter(mapfile.as_bytes(), "VMA").find_map(|start| {
+            let mut cursor = Cursor::new(mapfile, start);
+            cursor.literal("VMA")?;
+            for column in ["LMA", "Size", "Align", "Out"] {
+                cursor.take_while(1, |b| b.is_ascii_whitespace())?;
+                cursor.literal(column)?;
+            }
+            let out_in_space = cursor.take_while(1, |b| b.is_ascii_whitespace())?.len();
+            cursor.literal("In")?;
+            cursor.take_while(1, |b| b.is_ascii_whitespace())?;
+            cursor.literal("Symbol")?;
+            Some(out_in_space)
+        })
+    }
+
+    fn gnu_entry<'a>(cursor: &mut Cursor<'a>, subsection: bool) -> Option<GnuEntry<'a>> {
+        let offset = cursor.position;
+        if subsection {
+            cursor.literal(" ")?;
+        }
+        let name_start = cursor.position;
+        cursor.take_exactly(1, |b| b == b'.' || b == b'_')?;
+        cursor.take_while(1, |b| !b.is_ascii_whitespace())?;
+        let name = &cursor.text[name_start..cursor.position];
+        cursor.take_while(1, |b| b.is_ascii_whitespace())?;
+        cursor.literal("0x")?;
+        let vrom = cursor.take_while(1, |b| b.is_ascii_hexdigit())?;
+        cursor.take_while(1, is_blank)?;
+        cursor.literal("0x")?;
+        let size = cursor.take_while(1, |b| b.is_ascii_hexdigit())?;
+        let file = if subsection {
+            cursor.take_while(1, is_blank)?;
+            Some(cursor.rest_of_line()).filter(|file| !file.is_empty())?
+        } else {
+            ""
+        };
+        Some(GnuEntry {
+            offset,
+            name,
+            vrom,
+            size,
+            file,
+        })
+    }
+
+    pub(crate) fn gnu_sections(mapfile: &str) -> impl Iterator<Item = GnuEntry<'_>> {
+        entries(mapfile, |cursor| gnu_entry(cursor, false))
+    }
+
+    pub(crate) fn gnu_subsections(mapfile: &str) -> impl Iterator<Item = GnuEntry<'_>> {
+        entries(mapfile, |cursor| gnu_entry(cursor, true))
+    }
+
+    pub(crate) fn gnu_symbol(line: &str) -> Option<GnuSymbol<'_>> {
+        let mut cursor = Cursor::new(line, 0);
+        cursor.take_while(1, is_blank)?;
+        cursor.literal("0x")?;
+        let vrom = cursor.take_while(1, |b| b.is_ascii_hexdigit())?;
+        cursor.take_while(1, is_blank)?;
+        let name = line[cursor.position..].trim_end_matches(|c: char| c.is_ascii_whitespace());
+        (!name.is_empty() && !name.contains('\n') && is_gnu_symbol_name(name))
+            .then_some(GnuSymbol { vrom, name })
+    }
+
+    pub(crate) fn llvm_entry(line: &str) -> Option<LlvmEntry<'_>> {
+        let mut cursor = Cursor::new(line, 0);
+        cursor.take_while(0, |b| b.is_ascii_whitespace());
+        let vma = cursor.take_while(1, |b| b.is_ascii_hexdigit())?;
+        cursor.take_while(0, |b| b.is_ascii_whitespace());
+        cursor.take_while(1, |b| b.is_ascii_hexdigit())?;
+        cursor.take_while(0, |b| b.is_ascii_whitespace());
+        let size = cursor.take_while(1, |b| b.is_ascii_hexdigit())?;
+        cursor.take_while(0, |b| b.is_ascii_whitespace());
+        cursor.take_while(1, |b| b.is_ascii_digit())?;
+        let spaces = cursor.take_while(1, |b| b.is_ascii_whitespace())?.len();
+        let name = &line[cursor.position..];
+        (!name.is_empty() && !name.contains('\n')).then_some(LlvmEntry {
+            vma,
+            size,
+            spaces,
+            name,
+        })
+    }
+
+    pub(crate) fn msvc_symbol(line: &str) -> Option<MsvcSymbol<'_>> {
+        let mut cursor = Cursor::new(line, 0);
+        cursor.literal(" ")?;
+        let section = cursor.take_exactly(4, |b| b.is_ascii_hexdigit())?;
+        cursor.literal(":")?;
+        let section_offset = cursor.take_exactly(8, |b| b.is_ascii_hexdigit())?;
+        cursor.take_while(1, |b| b.is_ascii_whitespace())?;
+        let name = cursor.take_while(1, |b| b != b' ')?;
+        cursor.take_while(1, |b| b.is_ascii_whitespace())?;
+        let vaddr = cursor.take_exactly(16, |b| b.is_ascii_hexdigit())?;
+
+        // An optional one letter flag such as `f` for functions, then the origin
+        let rest = &line.as_bytes()[cursor.position..];
+        let origin_after = |skip: usize| {
+            let mut cursor = Cursor::new(line, cursor.position + skip);
+            cursor.take_while(1, |b| b.is_ascii_whitespace())?;
+            Some(&line[cursor.position..]).filter(|origin| !origin.is_empty())
+        };
+        let origin = if rest.is_empty() {
+            None
+        } else {
+            let flagged = rest.len() > 1 && rest[0] == b' ' && is_word(rest[1]);
+            Some(
+                flagged
+                    .then(|| origin_after(2))
+                    .flatten()
+                    .or_else(|| origin_after(0))?,
+            )
+        };
+        Some(MsvcSymbol {
+            section,
+            section_offset,
+            name,
+            vaddr,
+            origin,
+        })
+    }
+
+    pub(crate) fn msvc_groups(mapfile: &str) -> impl Iterator<Item = MsvcGroup<'_>> {
+        entries(mapfile, |cursor| {
+            cursor.literal(" ")?;
+            let section = cursor.take_exactly(4, |b| b.is_ascii_hexdigit())?;
+            cursor.literal(":")?;
+            let start = cursor.take_exactly(8, |b| b.is_ascii_hexdigit())?;
+            cursor.literal(" ")?;
+            let length = cursor.take_exactly(8, |b| b.is_ascii_hexdigit())?;
+            cursor.literal("H")?;
+            cursor.take_while(1, is_blank)?;
+            let name = cursor.take_while(1, |b| !b.is_ascii_whitespace())?;
+            cursor.take_while(1, is_blank)?;
+            cursor.take_while(1, is_word)?;
+            cursor.take_while(0, is_blank);
+            cursor.literal("\r");
+            matches!(cursor.peek(), None | Some(b'\n')).then_some(MsvcGroup {
+                section,
+                start,
+                length,
+                name,
+            })
+        })
+    }
+}
+
+#[cfg(all(test, feature = "regex"))]
+mod tests {
+    use super::{regexes, scanners};
+
+    #[test]
+    fn test_scanners_match_regexes() {
+        let mut mapfiles = vec![
+            // Wrapped names, CRLF line breaks, trailing blanks and lookalikes
+            ".text.very_long_function_name\n                0x0000000000001000       0x20\r\n \
+             .text.very_long_function_name\n                0x0000000000001000       0x20 a.o\r\n \
+             .text          0x0000000000001020        0x0 \n \
+             *fill*         0x0000000000001020        0x10\n\
+             __ksymtab      0xffffffff82000000       0x18\n \
+             ___ksymtab+fork_init\n                0xffffffff82000000        0xc kernel/fork.o\n\
+             .data          0x0000000000002000\n"
+                .to_string(),
+        ];
+        for map in [
+            "tests/gcc/out.map",
+            "tests/clang/output.map",
+            "tests/cargo/out.map",
+            "tests/cargo-windows/out.map",
+        ] {
+            mapfiles.push(std::fs::read_to_string(map).unwrap());
+        }
+
+        for mapfile in &mapfiles {
+            assert_eq!(
+                regexes::llvm_header(mapfile),
+                scanners::llvm_header(mapfile)
+            );
+            assert!(regexes::gnu_sections(mapfile).eq(scanners::gnu_sections(mapfile)));
+            assert!(regexes::gnu_subsections(mapfile).eq(scanners::gnu_subsections(mapfile)));
+            assert!(regexes::msvc_groups(mapfile).eq(scanners::msvc_groups(mapfile)));
+            for line in mapfile.lines() {
+                assert_eq!(regexes::gnu_symbol(line), scanners::gnu_symbol(line));
+                assert_eq!(regexes::llvm_entry(line), scanners::llvm_entry(line));
+                assert_eq!(regexes::msvc_symbol(line), scanners::msvc_symbol(line));
+            }
+        }
+        assert_eq!(scanners::gnu_subsections(&mapfiles[0]).count(), 2);
+    }
+}
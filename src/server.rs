@@ -0,0 +1,384 @@
+//! A JSON-RPC 2.0 server answering provenance queries about one binary, for editor extensions
+//! showing where the bytes under the cursor of a hex dump or a disassembly come from.
+//!
+//! Messages are framed as in the Language Server Protocol, after a `Content-Length` header, so
+//! that the JSON-RPC libraries of editors can talk to it; a message may also be a single line of
+//! JSON, for scripts. Responses are framed like the request they answer, and batches of
+//! requests are answered with the array of their responses. The methods are:
+//!
+//! - `initialize`: the server name and version, and the methods below.
+//! - `genealogy/hover`: the ranges containing the address given as `offset`, `vma`, `lma` or
+//!   `rva`, see [`Genealogy::query_address`].
+//! - `genealogy/ranges`: the ranges of the files matching the `file` pattern (see
+//!   [`PathFilter`]), by file offset, or by virtual address when `key` is `vma`.
+//! - `genealogy/explain`: how the attribution of `offset` was derived, see
+//!   [`Genealogy::explain`].
+//! - `shutdown`, then the `exit` notification, which stops [`Server::serve`].
+//!
+//! Addresses are numbers, or strings of decimal or `0x`-prefixed hexadecimal numbers since
+//! JavaScript numbers cannot hold every 64-bit address.
+
+use std::io::{self, BufRead, Read, Write};
+
+use crate::{
+    origin::PathFilter,
+    output::{ToValue, Value},
+    Address, Genealogy,
+};
+
+/// The methods answered, as listed by `initialize`.
+pub const METHODS: [&str; 5] = [
+    "initialize",
+    "genealogy/hover",
+    "genealogy/ranges",
+    "genealogy/explain",
+    "shutdown",
+];
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+/// Answers the requests about a genealogy, see the [module documentation](self).
+pub struct Server<'a> {
+    genealogy: &'a Genealogy,
+    mapfile: &'a str,
+    shut_down: bool,
+    exited: bool,
+}
+
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+impl RpcError {
+    fn invalid_params(message: impl Into<String>) -> Self {
+        Self {
+            code: INVALID_PARAMS,
+            message: message.into(),
+        }
+    }
+}
+
+impl<'a> Server<'a> {
+    /// A server for `genealogy`, built from `mapfile`.
+    pub fn new(genealogy: &'a Genealogy, mapfile: &'a str) -> Self {
+        Self {
+            genealogy,
+            mapfile,
+            shut_down: false,
+            exited: false,
+        }
+    }
+
+    /// Answers the messages of `input` on `output` until the `exit` notification or the end of
+    /// `input`. Fails on I/O errors and on headers without a `Content-Length`.
+    pub fn serve(&mut self, mut input: impl BufRead, mut output: impl Write) -> io::Result<()> {
+        let mut line = String::new();
+        while !self.exited {
+            line.clear();
+            if input.read_line(&mut line)? == 0 {
+                break;
+            }
+            let trimmed = line.trim();
+            if trimmed.is_empty() {
+                continue;
+            }
+            if trimmed.starts_with(['{', '[']) {
+                if let Some(response) = self.handle_text(trimmed) {
+                    writeln!(output, "{}", response.to_json())?;
+                    output.flush()?;
+                }
+                continue;
+            }
+
+            // Headers, up to an empty line
+            let mut length = None;
+            loop {
+                if let Some((name, value)) = line.trim().split_once(':') {
+                    if name.trim().eq_ignore_ascii_case("content-length") {
+                        length = value.trim().parse::<usize>().ok();
+                    }
+                }
+                line.clear();
+                if input.read_line(&mut line)? == 0 || line.trim().is_empty() {
+                    break;
+                }
+            }
+            let Some(length) = length else {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "message without a Content-Length header",
+                ));
+            };
+            // Read as it arrives rather than allocated up front, whatever the header claims
+            let mut body = vec![];
+            if input.by_ref().take(length as u64).read_to_end(&mut body)? < length {
+                return Err(io::ErrorKind::UnexpectedEof.into());
+            }
+            if let Some(response) = self.handle_text(&String::from_utf8_lossy(&body)) {
+                let response = response.to_json();
+                write!(
+                    output,
+                    "Content-Length: {}\r\n\r\n{response}",
+                    response.len()
+                )?;
+                output.flush()?;
+            }
+        }
+        Ok(())
+    }
+
+    fn handle_text(&mut self, text: &str) -> Option<Value> {
+        match Value::from_json(text) {
+            Some(Value::Array(messages)) if !messages.is_empty() => {
+                let responses: Vec<Value> = messages
+                    .iter()
+                    .filter_map(|message| self.handle(message))
+                    .collect();
+                // A batch of notifications is not answered either
+                (!responses.is_empty()).then_some(Value::Array(responses))
+            }
+            Some(message) => self.handle(&message),
+            None => Some(error_response(Value::Null, PARSE_ERROR, "invalid JSON")),
+        }
+    }
+
+    /// The response to a JSON-RPC message, `None` for notifications.
+    pub fn handle(&mut self, message: &Value) -> Option<Value> {
+        let id = message.get("id").cloned();
+        let Some(method) = message.get("method").and_then(Value::as_str) else {
+            return Some(error_response(
+                id.unwrap_or(Value::Null),
+                INVALID_REQUEST,
+                "not a JSON-RPC request",
+            ));
+        };
+        let params = message.get("params").unwrap_or(&Value::Null);
+        let result = self.call(method, params);
+        // Notifications are not answered, not even their errors
+        let id = id?;
+        Some(match result {
+            Ok(result) => {
+                Value::object([("jsonrpc", "2.0".into()), ("id", id), ("result", result)])
+            }
+            Err(error) => error_response(id, error.code, &error.message),
+        })
+    }
+
+    fn call(&mut self, method: &str, params: &Value) -> Result<Value, RpcError> {
+        if method == "exit" {
+            self.exited = true;
+            return Ok(Value::Null);
+        }
+        if self.shut_down {
+            return Err(RpcError {
+                code: INVALID_REQUEST,
+                message: "the server is shut down".to_string(),
+            });
+        }
+        match method {
+            "initialize" => Ok(Value::object([
+                (
+                    "serverInfo",
+                    Value::object([
+                        ("name", "genealogy".into()),
+                        ("version", env!("CARGO_PKG_VERSION").into()),
+                    ]),
+                ),
+                (
+                    "methods",
+                    Value::Array(METHODS.iter().map(|&method| method.into()).collect()),
+                ),
+            ])),
+            "genealogy/hover" => {
+                let address = address(params)?;
+                let hits = self
+                    .genealogy
+                    .query_address(address)
+                    .map_err(|error| RpcError::invalid_params(error.to_string()))?;
+                Ok(Value::object([("hits", hits.to_value())]))
+            }
+            "genealogy/ranges" => {
+                let pattern = params
+                    .get("file")
+                    .and_then(Value::as_str)
+                    .ok_or_else(|| RpcError::invalid_params("expected a file pattern"))?;
+                let filter = PathFilter::new().include(pattern);
+                let ranges: Vec<Value> = match params.get("key").and_then(Value::as_str) {
+                    None | Some("offset") => self
+                        .genealogy
+                        .contributions(&filter, 0..u64::MAX)
+                        .map(|hit| hit.to_value())
+                        .collect(),
+                    Some("vma") => self
+                        .genealogy
+                        .index()
+                        .iter_vaddr()
+                        .filter(|hit| filter.matches(hit.filename))
+                        .map(|hit| hit.to_value())
+                        .collect(),
+                    Some(key) => {
+                        return Err(RpcError::invalid_params(format!(
+                            "unknown key {key}, expected offset or vma"
+                        )))
+                    }
+                };
+                Ok(Value::object([("ranges", Value::Array(ranges))]))
+            }
+            "genealogy/explain" => {
+                let offset = params
+                    .get("offset")
+                    .and_then(number)
+                    .ok_or_else(|| RpcError::invalid_params("expected an offset"))?;
+                Ok(self.genealogy.explain(self.mapfile, offset).to_value())
+            }
+            "shutdown" => {
+                self.shut_down = true;
+                Ok(Value::Null)
+            }
+            _ => Err(RpcError {
+                code: METHOD_NOT_FOUND,
+                message: format!("unknown method {method}"),
+            }),
+        }
+    }
+}
+
+fn error_response(id: Value, code: i64, message: &str) -> Value {
+    Value::object([
+        ("jsonrpc", "2.0".into()),
+        ("id", id),
+        (
+            "error",
+            Value::object([("code", code.into()), ("message", message.into())]),
+        ),
+    ])
+}
+
+/// The address of the parameters of `genealogy/hover`.
+fn address(params: &Value) -> Result<Address, RpcError> {
+    let (key, value) = ["offset", "vma", "lma", "rva"]
+        .into_iter()
+        .find_map(|key| Some((key, number(params.get(key)?)?)))
+        .ok_or_else(|| RpcError::invalid_params("expected an offset, vma, lma or rva"))?;
+    Ok(match key {
+        "offset" => Address::FileOffset(value),
+        "vma" => Address::Vma(value),
+        "lma" => Address::Lma(value),
+        _ => Address::Rva(value),
+    })
+}
+
+/// A number, or a string of a decimal or `0x`-prefixed hexadecimal number.
+fn number(value: &Value) -> Option<u64> {
+    if let Some(number) = value.as_u64() {
+        return Some(number);
+    }
+    let text = value.as_str()?;
+    match text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")) {
+        Some(hex) => u64::from_str_radix(hex, 16).ok(),
+        None => text.parse().ok(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{output::Value, Genealogy};
+
+    use super::Server;
+
+    #[test]
+    fn test_server() {
+        let mapfile = std::fs::read_to_string("tests/gcc/out.map").unwrap();
+        let binary = std::fs::read("tests/gcc/a.out").unwrap();
+        let genealogy = Genealogy::new(&mapfile, &binary).unwrap();
+        let mut server = Server::new(&genealogy, &mapfile);
+
+        let hover =
+            r#"{"jsonrpc":"2.0","id":1,"method":"genealogy/hover","params":{"offset":"0x1150"}}"#;
+        let input = format!(
+            "Content-Length: {}\r\nContent-Type: application/vscode-jsonrpc\r\n\r\n{hover}\
+             {{\"jsonrpc\":\"2.0\",\"id\":2,\"method\":\"genealogy/ranges\",\"params\":{{\"file\":\"simple.o\"}}}}\n\
+             {{\"jsonrpc\":\"2.0\",\"method\":\"initialized\"}}\n\
+             {{\"jsonrpc\":\"2.0\",\"id\":3,\"method\":\"genealogy/ranges\",\"params\":{{}}}}\n\
+             {{\"jsonrpc\":\"2.0\",\"method\":\"exit\"}}\n\
+             {{\"jsonrpc\":\"2.0\",\"id\":4,\"method\":\"shutdown\"}}\n",
+            hover.len()
+        );
+        let mut output = vec![];
+        server.serve(input.as_bytes(), &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+
+        // The framed request is answered framed, the lines with lines
+        let (header, rest) = output.split_once("\r\n\r\n").unwrap();
+        let length: usize = header
+            .strip_prefix("Content-Length: ")
+            .unwrap()
+            .parse()
+            .unwrap();
+        let hover = Value::from_json(&rest[..length]).unwrap();
+        let hits = hover.get("result").and_then(|r| r.get("hits")).unwrap();
+        assert_eq!(
+            hits.as_array().unwrap()[0]
+                .get("file")
+                .and_then(Value::as_str),
+            Some("simple.o")
+        );
+        assert_eq!(
+            hits.as_array().unwrap()[0].get("map_line"),
+            Some(&Value::UInt(190))
+        );
+
+        // The notification is not answered, and nothing is read after `exit`
+        let lines: Vec<Value> = rest[length..]
+            .lines()
+            .map(|line| Value::from_json(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        let ranges = lines[0]
+            .get("result")
+            .and_then(|r| r.get("ranges"))
+            .unwrap();
+        assert!(ranges
+            .as_array()
+            .unwrap()
+            .iter()
+            .all(|range| range.get("file").and_then(Value::as_str) == Some("simple.o")));
+        assert_eq!(
+            lines[1].get("error").and_then(|e| e.get("code")),
+            Some(&Value::Int(-32602))
+        );
+
+        // Batches are answered with the responses to their requests
+        let batch = r#"[{"jsonrpc":"2.0","id":5,"method":"initialize"},
+            {"jsonrpc":"2.0","method":"initialized"},{"jsonrpc":"2.0","id":6}]"#;
+        let mut output = vec![];
+        let mut server = Server::new(&genealogy, &mapfile);
+        server
+            .serve(batch.replace('\n', "").as_bytes(), &mut output)
+            .unwrap();
+        let responses = Value::from_json(std::str::from_utf8(&output).unwrap()).unwrap();
+        let responses = responses.as_array().unwrap();
+        assert_eq!(responses.len(), 2);
+        assert_eq!(responses[0].get("id"), Some(&Value::UInt(5)));
+        assert_eq!(
+            responses[1].get("error").and_then(|e| e.get("code")),
+            Some(&Value::Int(-32600))
+        );
+
+        // Bodies are read as they arrive, not allocated from their header
+        let truncated = "Content-Length: 18446744073709551615\r\n\r\n{}";
+        let error = server.serve(truncated.as_bytes(), vec![]).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::UnexpectedEof);
+
+        let unknown = Value::from_json(r#"{"jsonrpc":"2.0","id":"a","method":"hover"}"#).unwrap();
+        let response = server.handle(&unknown).unwrap();
+        assert_eq!(
+            response.get("error").and_then(|e| e.get("code")),
+            Some(&Value::Int(-32601))
+        );
+    }
+}
@@ -0,0 +1,232 @@
+//! Layout snapshots, committed next to firmware whose sections must fit fixed flash partitions,
+//! and the check of a new build against them.
+
+use std::fmt::Display;
+
+use crate::{
+    format::{hex, human_size, Align, Table},
+    output::{ToValue, Value},
+    Genealogy, GenealogyError,
+};
+
+/// The version of the snapshot format written by [`Snapshot::to_json`].
+pub const SNAPSHOT_VERSION: u64 = 1;
+
+/// The loaded sections of a binary, in address order.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Snapshot {
+    pub sections: Vec<SnapshotSection>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SnapshotSection {
+    pub name: String,
+    pub vaddr: u64,
+    pub size: u64,
+}
+
+/// How much sections may change before [`Snapshot::drifts`] reports them, 0 by default.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Thresholds {
+    /// Bytes a section may move by, either way.
+    pub max_move: u64,
+    /// Bytes a section may grow by.
+    pub max_growth: u64,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Drift {
+    Moved { section: String, from: u64, to: u64 },
+    Grew { section: String, from: u64, to: u64 },
+    Added { section: String },
+    Removed { section: String },
+}
+
+impl Display for Drift {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Drift::Moved { section, from, to } => {
+                write!(f, "{section} moved from {} to {}", hex(*from), hex(*to))
+            }
+            Drift::Grew { section, from, to } => write!(
+                f,
+                "{section} grew by {}, from {} to {}",
+                human_size(to - from),
+                human_size(*from),
+                human_size(*to)
+            ),
+            Drift::Added { section } => write!(f, "{section} is new"),
+            Drift::Removed { section } => write!(f, "{section} was removed"),
+        }
+    }
+}
+
+impl Genealogy {
+    /// The layout of the loaded sections, independent of the paths and tools of the build.
+    pub fn snapshot(&self) -> Snapshot {
+        let mut sections: Vec<SnapshotSection> = self
+            .sections()
+            .iter()
+            .filter(|section| section.loaded)
+            .map(|section| SnapshotSection {
+                name: section.name.clone(),
+                vaddr: section.start_vaddr,
+                size: section.size,
+            })
+            .collect();
+        sections.sort_by(|a, b| a.vaddr.cmp(&b.vaddr).then_with(|| a.name.cmp(&b.name)));
+        Snapshot { sections }
+    }
+}
+
+impl Snapshot {
+    /// Parses a snapshot written by [`Snapshot::to_json`].
+    pub fn from_json(json: &str) -> Result<Self, GenealogyError> {
+        let invalid = || GenealogyError::WrongSnapshotFormat;
+        let snapshot = Value::from_json(json).ok_or_else(invalid)?;
+        if snapshot.get("version").and_then(Value::as_u64) != Some(SNAPSHOT_VERSION) {
+            return Err(invalid());
+        }
+        let sections = snapshot
+            .get("sections")
+            .and_then(Value::as_array)
+            .ok_or_else(invalid)?
+            .iter()
+            .map(|section| {
+                let number = |key| section.get(key).and_then(Value::as_u64).ok_or_else(invalid);
+                Ok(SnapshotSection {
+                    name: section
+                        .get("name")
+                        .and_then(Value::as_str)
+                        .ok_or_else(invalid)?
+                        .to_string(),
+                    vaddr: number("vaddr")?,
+                    size: number("size")?,
+                })
+            })
+            .collect::<Result<_, GenealogyError>>()?;
+        Ok(Self { sections })
+    }
+
+    /// JSON with one section per line, to be committed and diffed.
+    pub fn to_json(&self) -> String {
+        let mut json = format!("{{\"version\":{SNAPSHOT_VERSION},\"sections\":[");
+        for (i, section) in self.sections.iter().enumerate() {
+            json.push_str(if i == 0 { "\n  " } else { ",\n  " });
+            json.push_str(&section.to_value().to_json());
+        }
+        json.push_str("\n]}\n");
+        json
+    }
+
+    /// The changes from this snapshot to `current` beyond `thresholds`, in the order of the
+    /// sections of this snapshot then of the new ones. Sections are matched by name, shrinking
+    /// ones are not reported.
+    pub fn drifts(&self, current: &Snapshot, thresholds: Thresholds) -> Vec<Drift> {
+        let mut drifts = vec![];
+        for old in &self.sections {
+            let Some(new) = current.sections.iter().find(|new| new.name == old.name) else {
+                drifts.push(Drift::Removed {
+                    section: old.name.clone(),
+                });
+                continue;
+            };
+            if old.vaddr.abs_diff(new.vaddr) > thresholds.max_move {
+                drifts.push(Drift::Moved {
+                    section: old.name.clone(),
+                    from: old.vaddr,
+                    to: new.vaddr,
+                });
+            }
+            if new.size.saturating_sub(old.size) > thresholds.max_growth {
+                drifts.push(Drift::Grew {
+                    section: old.name.clone(),
+                    from: old.size,
+                    to: new.size,
+                });
+            }
+        }
+        for new in &current.sections {
+            if !self.sections.iter().any(|old| old.name == new.name) {
+                drifts.push(Drift::Added {
+                    section: new.name.clone(),
+                });
+            }
+        }
+        drifts
+    }
+
+    /// The table rendered by `Display`.
+    pub fn to_table(&self) -> Table {
+        let mut table = Table::new(&[Align::Left, Align::Left, Align::Right])
+            .with_header(["vaddr", "section", "size"]);
+        for section in &self.sections {
+            table.push_row([
+                hex(section.vaddr),
+                section.name.clone(),
+                human_size(section.size),
+            ]);
+        }
+        table
+    }
+}
+
+impl Display for Snapshot {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_table())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Genealogy;
+
+    use super::{Drift, Snapshot, Thresholds};
+
+    #[test]
+    fn test_snapshot_drifts() {
+        let mapfile = std::fs::read_to_string("tests/gcc/out.map").unwrap();
+        let binary = std::fs::read("tests/gcc/a.out").unwrap();
+        let snapshot = Genealogy::new(&mapfile, &binary).unwrap().snapshot();
+        assert!(snapshot.sections.iter().any(|s| s.name == ".text"));
+        assert!(!snapshot.sections.iter().any(|s| s.name == ".comment"));
+
+        let json = snapshot.to_json();
+        assert_eq!(json.lines().count(), snapshot.sections.len() + 2);
+        assert_eq!(Snapshot::from_json(&json).unwrap(), snapshot);
+        assert!(Snapshot::from_json(&json.replace("\"version\":1", "\"version\":2")).is_err());
+        assert!(snapshot.drifts(&snapshot, Thresholds::default()).is_empty());
+
+        let mut current = snapshot.clone();
+        let text = current
+            .sections
+            .iter_mut()
+            .find(|s| s.name == ".text")
+            .unwrap();
+        let (vaddr, size) = (text.vaddr, text.size);
+        text.vaddr += 0x10;
+        text.size += 0x100;
+        current.sections.retain(|s| s.name != ".fini");
+        let thresholds = Thresholds {
+            max_move: 0x10,
+            max_growth: 0xff,
+        };
+        assert_eq!(
+            snapshot.drifts(&current, thresholds),
+            [
+                Drift::Grew {
+                    section: ".text".to_string(),
+                    from: size,
+                    to: size + 0x100
+                },
+                Drift::Removed {
+                    section: ".fini".to_string()
+                },
+            ]
+        );
+        assert!(matches!(
+            current.drifts(&snapshot, Thresholds::default())[..],
+            [Drift::Moved { from, .. }, Drift::Added { .. }] if from == vaddr + 0x10
+        ));
+    }
+}
@@ -0,0 +1,329 @@
+//! Stack usage, from the `.su` files written by GCC and Clang with `-fstack-usage`, joined with
+//! the objects of the map: the worst stack users of every object file and output section, next
+//! to their flash usage.
+//!
+//! The usage is per function frame, the worst case of a call chain needs a call graph.
+
+use std::{collections::HashMap, fmt::Display};
+
+use crate::{
+    compile_commands::{lookup_object, normalize},
+    format::{human_size, Align, Table},
+    Genealogy, GenealogyError,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StackQualifier {
+    /// The frame has a fixed size.
+    Static,
+    /// The frame grows at runtime (`alloca`, variable length arrays), up to the given size.
+    Bounded,
+    /// The frame grows at runtime without a known bound, the size is a minimum.
+    Dynamic,
+}
+
+impl StackQualifier {
+    pub fn name(self) -> &'static str {
+        match self {
+            StackQualifier::Static => "static",
+            StackQualifier::Bounded => "bounded",
+            StackQualifier::Dynamic => "dynamic",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StackFunction {
+    /// As printed by the compiler, e.g. `int foo(int)` for C++.
+    pub name: String,
+    /// `file:line:column` of the definition.
+    pub location: String,
+    pub bytes: u64,
+    pub qualifier: StackQualifier,
+}
+
+/// The functions listed by `.su` files, per object file.
+#[derive(Clone, Debug, Default)]
+pub struct StackUsage {
+    /// Keyed by normalized object path.
+    objects: HashMap<String, Vec<StackFunction>>,
+}
+
+impl StackUsage {
+    /// Adds the functions of the `.su` file at `path`, written next to the object of the same
+    /// name (`foo.su` for `foo.o`, `foo.c.su` for CMake's `foo.c.o`).
+    pub fn add_su(&mut self, path: &str, text: &str) -> Result<(), GenealogyError> {
+        let functions = text
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(parse_su_line)
+            .collect::<Option<Vec<_>>>()
+            .ok_or(GenealogyError::WrongStackUsageFormat)?;
+        let object = format!("{}.o", path.strip_suffix(".su").unwrap_or(path));
+        self.objects
+            .entry(normalize(&object))
+            .or_default()
+            .extend(functions);
+        Ok(())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.objects.is_empty()
+    }
+
+    /// The functions of `origin`, looked up as [`Dependencies::inputs_of`] does.
+    ///
+    /// [`Dependencies::inputs_of`]: crate::dependencies::Dependencies::inputs_of
+    pub fn functions_of(&self, origin: &str) -> Option<&[StackFunction]> {
+        lookup_object(&self.objects, origin).map(Vec::as_slice)
+    }
+}
+
+/// Parses `<file>:<line>:<column>:<function>\t<bytes>\t<qualifiers>`. The file may contain `:`
+/// on Windows, and the function for C++, so the location ends at the first `:<line>:<column>:`.
+fn parse_su_line(line: &str) -> Option<StackFunction> {
+    let mut fields = line.split('\t');
+    let (location, bytes, qualifiers) = (fields.next()?, fields.next()?, fields.next()?);
+    let is_number = |text: &str| !text.is_empty() && text.bytes().all(|b| b.is_ascii_digit());
+    let (location, name) = location.match_indices(':').find_map(|(i, _)| {
+        let mut parts = location[i + 1..].splitn(3, ':');
+        let (line, column, name) = (parts.next()?, parts.next()?, parts.next()?);
+        (is_number(line) && is_number(column)).then(|| {
+            let end = location.len() - name.len() - 1;
+            (&location[..end], name)
+        })
+    })?;
+    let qualifier = match qualifiers.trim() {
+        "static" => StackQualifier::Static,
+        "dynamic,bounded" => StackQualifier::Bounded,
+        "dynamic" => StackQualifier::Dynamic,
+        _ => return None,
+    };
+    Some(StackFunction {
+        name: name.to_string(),
+        location: location.to_string(),
+        bytes: bytes.trim().parse().ok()?,
+        qualifier,
+    })
+}
+
+/// The stack usage of the attributed files, see [`Genealogy::stack_report`].
+#[derive(Clone, Debug)]
+pub struct StackReport {
+    /// Per file, worst user first.
+    pub entries: Vec<StackEntry>,
+    /// Per output section, worst user first.
+    pub sections: Vec<SectionStackEntry>,
+    /// Every function, biggest frame first.
+    pub functions: Vec<StackUser>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StackEntry {
+    pub filename: String,
+    pub functions: u64,
+    pub worst: String,
+    pub worst_bytes: u64,
+    /// Number of functions of [`StackQualifier::Dynamic`] size.
+    pub unbounded: u64,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SectionStackEntry {
+    pub section: String,
+    pub function: String,
+    pub bytes: u64,
+    pub filename: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StackUser {
+    pub function: StackFunction,
+    pub filename: String,
+    /// The output section of the function, found with the symbol index.
+    pub section: Option<String>,
+}
+
+impl Genealogy {
+    /// Joins `usage` with the attributed files. Functions are placed in their output section
+    /// with [`GenealogyBuilder::symbols`], files without a `.su` file are left out.
+    ///
+    /// [`GenealogyBuilder::symbols`]: crate::GenealogyBuilder::symbols
+    pub fn stack_report(&self, usage: &StackUsage) -> StackReport {
+        let mut functions = vec![];
+        for entry in self.report().entries {
+            for function in usage.functions_of(&entry.filename).unwrap_or_default() {
+                functions.push(StackUser {
+                    section: self.function_section(&function.name, &entry.filename),
+                    function: function.clone(),
+                    filename: entry.filename.clone(),
+                });
+            }
+        }
+        functions.sort_by(|a, b| {
+            b.function
+                .bytes
+                .cmp(&a.function.bytes)
+                .then_with(|| a.filename.cmp(&b.filename))
+                .then_with(|| a.function.name.cmp(&b.function.name))
+        });
+
+        // The functions are sorted, so the first of a file or section is its worst user
+        let mut entries: Vec<StackEntry> = vec![];
+        let mut positions = HashMap::new();
+        for user in &functions {
+            let i = *positions.entry(user.filename.as_str()).or_insert_with(|| {
+                entries.push(StackEntry {
+                    filename: user.filename.clone(),
+                    functions: 0,
+                    worst: user.function.name.clone(),
+                    worst_bytes: user.function.bytes,
+                    unbounded: 0,
+                });
+                entries.len() - 1
+            });
+            entries[i].functions += 1;
+            if user.function.qualifier == StackQualifier::Dynamic {
+                entries[i].unbounded += 1;
+            }
+        }
+        let mut sections: Vec<SectionStackEntry> = vec![];
+        for user in &functions {
+            let Some(section) = &user.section else {
+                continue;
+            };
+            if !sections.iter().any(|entry| entry.section == *section) {
+                sections.push(SectionStackEntry {
+                    section: section.clone(),
+                    function: user.function.name.clone(),
+                    bytes: user.function.bytes,
+                    filename: user.filename.clone(),
+                });
+            }
+        }
+
+        StackReport {
+            entries,
+            sections,
+            functions,
+        }
+    }
+
+    /// The section of the symbol of `function` in `filename`, or of any file if none matches.
+    /// C++ names are printed with their return type, which symbols are not looked up by.
+    fn function_section(&self, function: &str, filename: &str) -> Option<String> {
+        let without_return_type = function
+            .find('(')
+            .and_then(|params| function[..params].rfind(' '))
+            .map(|space| &function[space + 1..]);
+        let hits: Vec<_> = [Some(function), without_return_type]
+            .into_iter()
+            .flatten()
+            .flat_map(|name| self.lookup_symbol(name).unwrap_or_default())
+            .collect();
+        hits.iter()
+            .find(|hit| hit.filename == Some(filename))
+            .or(hits.first())
+            .and_then(|hit| hit.symbol.section.clone())
+    }
+}
+
+impl StackReport {
+    /// The per file table rendered by `Display`.
+    pub fn to_table(&self) -> Table {
+        let mut table = Table::new(&[
+            Align::Right,
+            Align::Left,
+            Align::Right,
+            Align::Right,
+            Align::Left,
+        ])
+        .with_header(["worst", "function", "functions", "unbounded", "file"]);
+        for entry in &self.entries {
+            table.push_row([
+                human_size(entry.worst_bytes),
+                entry.worst.clone(),
+                entry.functions.to_string(),
+                entry.unbounded.to_string(),
+                entry.filename.clone(),
+            ]);
+        }
+        table
+    }
+
+    /// The table of the worst user of every section.
+    pub fn sections_table(&self) -> Table {
+        let mut table = Table::new(&[Align::Left, Align::Right, Align::Left, Align::Left])
+            .with_header(["section", "worst", "function", "file"]);
+        for entry in &self.sections {
+            table.push_row([
+                entry.section.clone(),
+                human_size(entry.bytes),
+                entry.function.clone(),
+                entry.filename.clone(),
+            ]);
+        }
+        table
+    }
+}
+
+impl Display for StackReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_table())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Genealogy;
+
+    use super::{parse_su_line, StackQualifier, StackUsage};
+
+    #[test]
+    fn test_stack_report() {
+        let function =
+            parse_su_line("C:\\src\\a.cpp:3:7:int ns::f(int)\t48\tdynamic,bounded").unwrap();
+        assert_eq!(function.location, "C:\\src\\a.cpp:3:7");
+        assert_eq!(function.name, "int ns::f(int)");
+        assert_eq!(function.qualifier, StackQualifier::Bounded);
+        assert!(parse_su_line("a.c:3:f\t16\tstatic").is_none());
+
+        let mut usage = StackUsage::default();
+        for su in ["tests/gcc/simple.su", "tests/gcc/simple2.su"] {
+            usage
+                .add_su(su, &std::fs::read_to_string(su).unwrap())
+                .unwrap();
+        }
+        assert!(usage.add_su("bad.su", "not a stack usage\n").is_err());
+
+        let mapfile = std::fs::read_to_string("tests/gcc/out.map").unwrap();
+        let binary = std::fs::read("tests/gcc/a.out").unwrap();
+        let genealogy = Genealogy::builder(&mapfile, &binary)
+            .symbols(true)
+            .build()
+            .unwrap();
+        let report = genealogy.stack_report(&usage);
+        let worst: Vec<_> = report
+            .entries
+            .iter()
+            .map(|entry| {
+                (
+                    entry.filename.as_str(),
+                    entry.worst.as_str(),
+                    entry.functions,
+                )
+            })
+            .collect();
+        assert_eq!(worst, [("simple.o", "main", 2), ("simple2.o", "f2", 1)]);
+        assert_eq!(report.sections.len(), 1);
+        assert_eq!(report.sections[0].section, ".text");
+        assert_eq!(report.sections[0].function, "main");
+
+        // Without symbols, functions are still attributed to their file
+        let report = Genealogy::new(&mapfile, &binary)
+            .unwrap()
+            .stack_report(&usage);
+        assert_eq!(report.functions.len(), 3);
+        assert!(report.sections.is_empty());
+    }
+}
@@ -0,0 +1,221 @@
+//! RAM taken by static variables: the `.data` and `.bss` bytes of every file, and the biggest
+//! static buffers behind them.
+
+use std::{collections::HashMap, fmt::Display};
+
+use crate::{
+    format::{human_size, Align, Cell, Style, Table},
+    symbols::demangle,
+    Genealogy,
+};
+
+/// The output sections of initialized statics, also stored in the image for the startup code
+/// to copy.
+const DATA_SECTIONS: &[&str] = &[".data", ".sdata", ".tdata"];
+/// The output sections of zeroed or uninitialized statics, which only take RAM.
+const BSS_SECTIONS: &[&str] = &[".bss", ".sbss", ".tbss", ".noinit"];
+
+/// RAM statics per file, see [`Genealogy::static_report`].
+#[derive(Clone, Debug)]
+pub struct StaticReport {
+    /// Biggest first.
+    pub entries: Vec<StaticEntry>,
+    /// The statics found in the symbol index, biggest first.
+    pub buffers: Vec<StaticBuffer>,
+    pub data: u64,
+    pub bss: u64,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StaticEntry {
+    pub filename: String,
+    pub data: u64,
+    pub bss: u64,
+    /// The biggest static of the file, with the symbol index.
+    pub biggest: Option<StaticBuffer>,
+}
+
+impl StaticEntry {
+    pub fn total(&self) -> u64 {
+        self.data + self.bss
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StaticBuffer {
+    pub name: String,
+    /// The other names of the static, see [`Symbol::aliases`](crate::symbols::Symbol::aliases).
+    pub aliases: Vec<String>,
+    pub size: u64,
+    pub section: String,
+    pub filename: Option<String>,
+}
+
+/// Whether `name` is one of `sections` or an input section of it, e.g. `.bss.buffer`.
+fn is_in(sections: &[&str], name: &str) -> bool {
+    sections.iter().any(|section| {
+        name.strip_prefix(section)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with('.'))
+    })
+}
+
+impl Genealogy {
+    /// Sums the `.data` and `.bss` bytes of every file. The statics are listed with
+    /// [`GenealogyBuilder::symbols`], their aliases with them.
+    ///
+    /// [`GenealogyBuilder::symbols`]: crate::GenealogyBuilder::symbols
+    pub fn static_report(&self) -> StaticReport {
+        let mut sizes: HashMap<&str, (u64, u64)> = HashMap::new();
+        for section in self.sections().iter().filter(|section| section.loaded) {
+            let bss = is_in(BSS_SECTIONS, &section.name);
+            if !bss && !is_in(DATA_SECTIONS, &section.name) {
+                continue;
+            }
+            for subsection in &section.subsections {
+                let (data_size, bss_size) = sizes.entry(&subsection.filename).or_default();
+                *if bss { bss_size } else { data_size } += subsection.size;
+            }
+        }
+
+        let mut buffers = vec![];
+        for symbol in self
+            .symbols()
+            .into_iter()
+            .flat_map(|symbols| symbols.deduplicated())
+        {
+            let Some(section) = symbol.section.as_deref() else {
+                continue;
+            };
+            if symbol.size == 0 || !(is_in(BSS_SECTIONS, section) || is_in(DATA_SECTIONS, section))
+            {
+                continue;
+            }
+            buffers.push(StaticBuffer {
+                name: symbol.display_name().to_string(),
+                aliases: symbol
+                    .aliases
+                    .iter()
+                    .map(|alias| demangle(alias).unwrap_or_else(|| alias.clone()))
+                    .collect(),
+                size: symbol.size,
+                section: section.to_string(),
+                // `.bss` symbols have no file offset to look them up by
+                filename: self
+                    .query_vaddr_point(symbol.vaddr)
+                    .next()
+                    .map(|hit| hit.filename.to_string()),
+            });
+        }
+        buffers.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.name.cmp(&b.name)));
+
+        let mut entries: Vec<StaticEntry> = sizes
+            .into_iter()
+            .filter(|(_, (data, bss))| data + bss > 0)
+            .map(|(filename, (data, bss))| StaticEntry {
+                filename: filename.to_string(),
+                data,
+                bss,
+                biggest: buffers
+                    .iter()
+                    .find(|buffer| buffer.filename.as_deref() == Some(filename))
+                    .cloned(),
+            })
+            .collect();
+        entries.sort_by(|a, b| {
+            b.total()
+                .cmp(&a.total())
+                .then_with(|| a.filename.cmp(&b.filename))
+        });
+
+        StaticReport {
+            data: entries.iter().map(|entry| entry.data).sum(),
+            bss: entries.iter().map(|entry| entry.bss).sum(),
+            entries,
+            buffers,
+        }
+    }
+}
+
+impl StaticReport {
+    /// The per file table rendered by `Display`.
+    pub fn to_table(&self) -> Table {
+        let mut table = Table::new(&[
+            Align::Right,
+            Align::Right,
+            Align::Right,
+            Align::Left,
+            Align::Left,
+        ])
+        .with_header(["data", "bss", "biggest", "static", "file"]);
+        for entry in &self.entries {
+            let biggest = entry.biggest.as_ref();
+            table.push_row([
+                human_size(entry.data),
+                human_size(entry.bss),
+                biggest.map_or_else(|| "-".to_string(), |buffer| human_size(buffer.size)),
+                biggest.map_or_else(|| "-".to_string(), |buffer| buffer.name.clone()),
+                entry.filename.clone(),
+            ]);
+        }
+        table.push_row([
+            Cell::styled(human_size(self.data), Some(Style::Bold)),
+            Cell::styled(human_size(self.bss), Some(Style::Bold)),
+            Cell::from(""),
+            Cell::from(""),
+            Cell::styled("total", Some(Style::Bold)),
+        ]);
+        table
+    }
+
+    /// The table of the `n` biggest statics.
+    pub fn buffers_table(&self, n: usize) -> Table {
+        let mut table = Table::new(&[Align::Right, Align::Left, Align::Left, Align::Left])
+            .with_header(["size", "section", "static", "file"]);
+        for buffer in self.buffers.iter().take(n) {
+            table.push_row([
+                human_size(buffer.size),
+                buffer.section.clone(),
+                buffer.name.clone(),
+                buffer.filename.clone().unwrap_or_else(|| "-".to_string()),
+            ]);
+        }
+        table
+    }
+}
+
+impl Display for StaticReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_table())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Genealogy;
+
+    #[test]
+    fn test_static_report() {
+        let mapfile = std::fs::read_to_string("tests/cortex-m/out.map").unwrap();
+        let binary = std::fs::read("tests/cortex-m/firmware").unwrap();
+        let genealogy = Genealogy::builder(&mapfile, &binary)
+            .symbols(true)
+            .build()
+            .unwrap();
+
+        // The tick counter of the firmware is its only static
+        let report = genealogy.static_report();
+        assert_eq!((report.data, report.bss), (0, 4));
+        assert_eq!(report.entries.len(), 1);
+        let entry = &report.entries[0];
+        assert!(entry.filename.starts_with("firmware."));
+        let biggest = entry.biggest.as_ref().unwrap();
+        assert_eq!(biggest.name, "firmware::TICKS");
+        assert_eq!(biggest.section, ".bss");
+        assert_eq!(report.buffers, std::slice::from_ref(biggest));
+
+        let genealogy = Genealogy::new(&mapfile, &binary).unwrap();
+        let report = genealogy.static_report();
+        assert_eq!(report.bss, 4);
+        assert!(report.buffers.is_empty());
+    }
+}
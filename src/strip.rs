@@ -0,0 +1,251 @@
+//! What stripping would remove from every file: its bytes in the debug sections and symbol
+//! tables next to the bytes it ships, predicting the size of the stripped binary before running
+//! `strip`.
+
+use std::{collections::HashMap, fmt::Display};
+
+use goblin::{
+    elf::{
+        section_header::SHT_NOBITS,
+        sym::{STT_FILE, STT_SECTION},
+    },
+    Object,
+};
+
+use crate::{
+    debuginfo::is_debug_section,
+    format::{human_size, percentage, Align, Cell, Style, Table},
+    origin::Granularities,
+    Genealogy, GenealogyError,
+};
+
+/// The symbol tables `strip` removes besides the debug sections, stabs included.
+pub const SYMBOL_TABLE_SECTIONS: [&str; 4] = [".symtab", ".strtab", ".stab", ".stabstr"];
+
+/// Whether `strip` removes the section `name`.
+pub fn is_strippable_section(name: &str) -> bool {
+    is_debug_section(name) || SYMBOL_TABLE_SECTIONS.contains(&name)
+}
+
+/// The shipped and strippable bytes of every file, see [`Genealogy::strip_report`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StripReport {
+    /// Files with the most strippable bytes first.
+    pub entries: Vec<StripEntry>,
+    /// Strippable bytes of no file, e.g. the symbols of the linker and of absolute values.
+    pub unattributed: u64,
+    pub file_size: u64,
+    /// The bytes `strip` removes: the strippable sections, their headers and their names.
+    pub strippable: u64,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StripEntry {
+    pub filename: String,
+    /// Bytes kept by `strip`.
+    pub shipped: u64,
+    /// Bytes of debug sections, and the estimated size of the symbol table entries and names
+    /// of its symbols.
+    pub strippable: u64,
+}
+
+impl Genealogy {
+    /// Splits the bytes every file contributes to the ELF `binary` the genealogy was built
+    /// from into the ones `strip` keeps and the ones it removes.
+    ///
+    /// Debug sections are attributed as by [`Genealogy::debug_report`]. Symbol tables are
+    /// not in maps, a symbol is estimated to cost its `.symtab` entry and its name in `.strtab`
+    /// to the file owning its address.
+    pub fn strip_report(&self, binary: &[u8]) -> Result<StripReport, GenealogyError> {
+        let Object::Elf(elf) = Object::parse(binary).map_err(GenealogyError::BinaryParse)? else {
+            return Err(GenealogyError::UnsupportedBinaryFormat);
+        };
+
+        let debug = self.debug_report(binary);
+        let mut files: HashMap<&str, (u64, u64)> = HashMap::new();
+        for section in self.sections() {
+            if is_strippable_section(&section.name) {
+                continue;
+            }
+            for subsection in &section.subsections {
+                if subsection.start_file_offset.is_some() {
+                    files.entry(&subsection.filename).or_default().0 += subsection.size;
+                }
+            }
+        }
+        for entry in &debug.entries {
+            files.entry(&entry.filename).or_default().1 += entry.size;
+        }
+        let symbol_size = if elf.is_64 { 24 } else { 16 };
+        for sym in elf.syms.iter() {
+            if sym.st_shndx == 0 || sym.st_type() == STT_SECTION || sym.st_type() == STT_FILE {
+                continue;
+            }
+            let Some(hit) = self.query_vaddr_point(sym.st_value).next() else {
+                continue;
+            };
+            let name = elf.strtab.get_at(sym.st_name).unwrap_or_default();
+            files.entry(hit.filename).or_default().1 += symbol_size + name.len() as u64 + 1;
+        }
+
+        let mut sections_size = 0;
+        let mut strippable = 0;
+        for shdr in &elf.section_headers {
+            let Some(name) = elf.shdr_strtab.get_at(shdr.sh_name) else {
+                continue;
+            };
+            if !is_strippable_section(name) {
+                continue;
+            }
+            let size = if shdr.sh_type == SHT_NOBITS {
+                0
+            } else {
+                shdr.sh_size
+            };
+            sections_size += size;
+            strippable += size + u64::from(elf.header.e_shentsize) + name.len() as u64 + 1;
+        }
+
+        let attributed: u64 = files.values().map(|&(_, strippable)| strippable).sum();
+        let entries = files
+            .into_iter()
+            .map(|(filename, (shipped, strippable))| StripEntry {
+                filename: filename.to_string(),
+                shipped,
+                strippable,
+            })
+            .collect();
+        Ok(StripReport {
+            entries: sorted(entries),
+            unattributed: sections_size.saturating_sub(attributed),
+            file_size: binary.len() as u64,
+            strippable,
+        })
+    }
+}
+
+/// Most strippable bytes first.
+fn sorted(mut entries: Vec<StripEntry>) -> Vec<StripEntry> {
+    entries.sort_by(|a, b| {
+        b.strippable
+            .cmp(&a.strippable)
+            .then_with(|| b.shipped.cmp(&a.shipped))
+            .then_with(|| a.filename.cmp(&b.filename))
+    });
+    entries
+}
+
+impl StripReport {
+    /// The predicted size of the binary once stripped.
+    pub fn stripped_size(&self) -> u64 {
+        self.file_size.saturating_sub(self.strippable)
+    }
+
+    /// The same report with the files summed per component, see
+    /// [`Granularities::component`].
+    pub fn by_component(&self) -> StripReport {
+        let mut components: HashMap<&str, (u64, u64)> = HashMap::new();
+        for entry in &self.entries {
+            let sizes = components
+                .entry(Granularities::of(&entry.filename).component)
+                .or_default();
+            sizes.0 += entry.shipped;
+            sizes.1 += entry.strippable;
+        }
+        let entries = components
+            .into_iter()
+            .map(|(component, (shipped, strippable))| StripEntry {
+                filename: component.to_string(),
+                shipped,
+                strippable,
+            })
+            .collect();
+        StripReport {
+            entries: sorted(entries),
+            ..self.clone()
+        }
+    }
+
+    /// The table rendered by `Display`, without the predicted size.
+    pub fn to_table(&self) -> Table {
+        let mut table = Table::new(&[Align::Right, Align::Right, Align::Right, Align::Left])
+            .with_header(["shipped", "strippable", "share", "file"]);
+        for entry in &self.entries {
+            table.push_row([
+                human_size(entry.shipped),
+                human_size(entry.strippable),
+                percentage(entry.strippable, entry.shipped + entry.strippable),
+                entry.filename.clone(),
+            ]);
+        }
+        if self.unattributed > 0 {
+            table.push_row([
+                "".into(),
+                human_size(self.unattributed),
+                "".into(),
+                "unattributed".into(),
+            ]);
+        }
+        let shipped: u64 = self.entries.iter().map(|entry| entry.shipped).sum();
+        let strippable = self.unattributed
+            + self
+                .entries
+                .iter()
+                .map(|entry| entry.strippable)
+                .sum::<u64>();
+        table.push_row([
+            Cell::styled(human_size(shipped), Some(Style::Bold)),
+            Cell::styled(human_size(strippable), Some(Style::Bold)),
+            percentage(strippable, shipped + strippable).into(),
+            format!("total in {} files", self.entries.len()).into(),
+        ]);
+        table
+    }
+}
+
+impl Display for StripReport {
+    /// The table, then e.g. `stripped: 14.13 KiB of 18.36 KiB, 4.23 KiB (23.02%) removed`.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{}", self.to_table())?;
+        write!(
+            f,
+            "stripped: {} of {}, {} ({}) removed",
+            human_size(self.stripped_size()),
+            human_size(self.file_size),
+            human_size(self.strippable),
+            percentage(self.strippable, self.file_size)
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Genealogy;
+
+    use super::is_strippable_section;
+
+    #[test]
+    fn test_strip_report() {
+        assert!(is_strippable_section(".symtab"));
+        assert!(is_strippable_section(".debug_line_str"));
+        assert!(!is_strippable_section(".comment"));
+
+        let mapfile = std::fs::read_to_string("tests/gcc/out.map").unwrap();
+        let binary = std::fs::read("tests/gcc/a.out").unwrap();
+        let genealogy = Genealogy::new(&mapfile, &binary).unwrap();
+        let report = genealogy.strip_report(&binary).unwrap();
+
+        // `strip` leaves 14472 bytes, the alignment of the remaining sections aside
+        assert!(report.stripped_size().abs_diff(14472) < 64);
+        assert_eq!(report.entries[0].filename, "simple.o");
+        assert!(report.entries[0].shipped > 0);
+        assert!(report.entries[0].strippable > 1024);
+
+        let components = report.by_component();
+        assert!(components.entries.len() < report.entries.len());
+        assert!(components
+            .entries
+            .iter()
+            .any(|entry| entry.filename == "<runtime>"));
+    }
+}
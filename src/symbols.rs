@@ -0,0 +1,328 @@
+//! An optional index of the symbols of the binary, used to answer questions by symbol name.
+//!
+//! ELF binaries provide their own symbol table (`.symtab`, or `.dynsym` once stripped). PE
+//! binaries rarely do, so the public and static symbols listed in the MSVC mapfile are used
+//! instead.
+
+use std::collections::HashMap;
+
+use goblin::{
+    elf::{
+        section_header::SHT_NOBITS,
+        sym::{STB_WEAK, STT_FILE, STT_SECTION},
+        Elf,
+    },
+    pe::PE,
+};
+
+use crate::{scan, GenealogyError};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Symbol {
+    /// The name as found in the binary or mapfile, usually mangled.
+    pub name: String,
+    pub demangled: Option<String>,
+    pub vaddr: u64,
+    /// 0 when unknown.
+    pub size: u64,
+    /// `None` for symbols that do not occupy file space, e.g. in `.bss`.
+    pub file_offset: Option<u64>,
+    pub section: Option<String>,
+    /// Whether the definition is weak (`STB_WEAK`), e.g. a default interrupt handler meant to be
+    /// overridden. Always false for PE.
+    pub weak: bool,
+    /// The other names of the symbol: the symbols at the same address with the same size, e.g.
+    /// functions folded by ICF, `strong_alias` or weak aliases.
+    pub aliases: Vec<String>,
+}
+
+impl Symbol {
+    fn new(
+        name: &str,
+        vaddr: u64,
+        size: u64,
+        file_offset: Option<u64>,
+        section: Option<&str>,
+        weak: bool,
+    ) -> Self {
+        Self {
+            name: name.to_string(),
+            demangled: demangle(name),
+            vaddr,
+            size,
+            file_offset,
+            section: section.map(str::to_string),
+            weak,
+            aliases: vec![],
+        }
+    }
+
+    /// The demangled name if there is one, the raw name otherwise.
+    pub fn display_name(&self) -> &str {
+        self.demangled.as_deref().unwrap_or(&self.name)
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct SymbolIndex {
+    /// Sorted by virtual address.
+    symbols: Vec<Symbol>,
+    /// Every name a symbol can be looked up by, see [`SymbolIndex::lookup`].
+    by_name: HashMap<String, Vec<usize>>,
+}
+
+impl SymbolIndex {
+    fn from_symbols(mut symbols: Vec<Symbol>) -> Self {
+        // Aliases are adjacent, the canonical one first
+        symbols.sort_by(|a, b| {
+            a.vaddr
+                .cmp(&b.vaddr)
+                .then_with(|| b.size.cmp(&a.size))
+                .then_with(|| a.weak.cmp(&b.weak))
+                .then_with(|| a.name.cmp(&b.name))
+        });
+        symbols.dedup();
+        for aliases in symbols.chunk_by_mut(|a, b| (a.vaddr, a.size) == (b.vaddr, b.size)) {
+            if aliases.len() == 1 {
+                continue;
+            }
+            let mut names: Vec<String> = vec![];
+            for symbol in aliases.iter() {
+                if !names.contains(&symbol.name) {
+                    names.push(symbol.name.clone());
+                }
+            }
+            for symbol in aliases {
+                symbol.aliases = names
+                    .iter()
+                    .filter(|name| **name != symbol.name)
+                    .cloned()
+                    .collect();
+            }
+        }
+
+        let mut by_name: HashMap<String, Vec<usize>> = HashMap::new();
+        for (i, symbol) in symbols.iter().enumerate() {
+            for key in lookup_keys(symbol) {
+                let indices = by_name.entry(key).or_default();
+                if indices.last() != Some(&i) {
+                    indices.push(i);
+                }
+            }
+        }
+        Self { symbols, by_name }
+    }
+
+    /// Indexes the defined symbols of `.symtab`, or of `.dynsym` if the binary is stripped.
+    pub fn from_elf(elf: &Elf) -> Self {
+        let (syms, strtab) = if elf.syms.is_empty() {
+            (&elf.dynsyms, &elf.dynstrtab)
+        } else {
+            (&elf.syms, &elf.strtab)
+        };
+
+        let symbols = syms
+            .iter()
+            .filter(|sym| {
+                sym.st_shndx != 0 && sym.st_type() != STT_SECTION && sym.st_type() != STT_FILE
+            })
+            .filter_map(|sym| {
+                let name = strtab.get_at(sym.st_name).filter(|name| !name.is_empty())?;
+                let shdr = elf.section_headers.get(sym.st_shndx);
+                let section = shdr.and_then(|shdr| elf.shdr_strtab.get_at(shdr.sh_name));
+                let file_offset = shdr
+                    .filter(|shdr| shdr.sh_type != SHT_NOBITS && sym.st_value >= shdr.sh_addr)
+                    .and_then(|shdr| (sym.st_value - shdr.sh_addr).checked_add(shdr.sh_offset));
+                Some(Symbol::new(
+                    name,
+                    sym.st_value,
+                    sym.st_size,
+                    file_offset,
+                    section,
+                    sym.st_bind() == STB_WEAK,
+                ))
+            })
+            .collect();
+
+        Self::from_symbols(symbols)
+    }
+
+    /// Indexes the public and static symbols of an MSVC mapfile, placed using the PE sections.
+    ///
+    /// MSVC mapfiles do not give symbol sizes, so each symbol is assumed to extend up to the next
+    /// one in its section.
+    pub fn from_msvc_mapfile(mapfile: &str, pe: &PE) -> Result<Self, GenealogyError> {
+        if !mapfile.contains("Publics by Value") {
+            return Err(GenealogyError::WrongMapfileFormat);
+        }
+
+        // (section number, offset in section, name, vaddr)
+        let mut entries: Vec<(usize, u64, &str, u64)> = mapfile
+            .lines()
+            .filter_map(scan::msvc_symbol)
+            .filter_map(|symbol| {
+                let section_nb = usize::from_str_radix(symbol.section, 16).ok()?;
+                let section_offset = u64::from_str_radix(symbol.section_offset, 16).ok()?;
+                let vaddr = u64::from_str_radix(symbol.vaddr, 16).ok()?;
+                // Section 0 holds absolute symbols
+                (section_nb > 0).then_some((section_nb, section_offset, symbol.name, vaddr))
+            })
+            .collect();
+        entries.sort_by_key(|&(section_nb, section_offset, _, _)| (section_nb, section_offset));
+
+        let symbols = entries
+            .iter()
+            .enumerate()
+            .filter_map(|(i, &(section_nb, section_offset, name, vaddr))| {
+                let pe_section = pe.sections.get(section_nb - 1)?;
+                let section_end = u64::from(pe_section.virtual_size);
+                let end = entries[i + 1..]
+                    .iter()
+                    .find(|next| next.0 == section_nb && next.1 > section_offset)
+                    .map_or(section_end, |next| next.1);
+                let file_offset = (section_offset < u64::from(pe_section.size_of_raw_data))
+                    .then(|| u64::from(pe_section.pointer_to_raw_data) + section_offset);
+                Some(Symbol::new(
+                    name,
+                    vaddr,
+                    end.saturating_sub(section_offset),
+                    file_offset,
+                    pe_section.name().ok(),
+                    false,
+                ))
+            })
+            .collect();
+
+        Ok(Self::from_symbols(symbols))
+    }
+
+    /// Finds symbols by mangled name, demangled name, or demangled name without the Rust hash
+    /// suffix or C++ parameter list.
+    pub fn lookup(&self, name: &str) -> impl Iterator<Item = &Symbol> {
+        self.by_name
+            .get(name)
+            .into_iter()
+            .flatten()
+            .map(|&i| &self.symbols[i])
+    }
+
+    /// The symbols at `vaddr`, strong ones first.
+    pub fn at(&self, vaddr: u64) -> impl Iterator<Item = &Symbol> {
+        let start = self.symbols.partition_point(|symbol| symbol.vaddr < vaddr);
+        let end = self.symbols.partition_point(|symbol| symbol.vaddr <= vaddr);
+        let mut symbols: Vec<&Symbol> = self.symbols[start..end].iter().collect();
+        symbols.sort_by_key(|symbol| symbol.weak);
+        symbols.into_iter()
+    }
+
+    /// All symbols, ordered by virtual address.
+    pub fn iter(&self) -> impl Iterator<Item = &Symbol> {
+        self.symbols.iter()
+    }
+
+    /// One symbol per address and size, ordered by virtual address: the canonical one of its
+    /// [`Symbol::aliases`], see [`SymbolIndex::canonical`]. Sums over them count aliased bytes
+    /// once.
+    pub fn deduplicated(&self) -> impl Iterator<Item = &Symbol> {
+        self.symbols
+            .chunk_by(|a, b| (a.vaddr, a.size) == (b.vaddr, b.size))
+            .map(|aliases| &aliases[0])
+    }
+
+    /// The symbol `symbol` is an alias of, itself if it has no aliases: the strong one, then the
+    /// first by name.
+    pub fn canonical<'a>(&'a self, symbol: &'a Symbol) -> &'a Symbol {
+        let start = self.symbols.partition_point(|other| {
+            other.vaddr < symbol.vaddr || (other.vaddr == symbol.vaddr && other.size > symbol.size)
+        });
+        self.symbols
+            .get(start)
+            .filter(|other| (other.vaddr, other.size) == (symbol.vaddr, symbol.size))
+            .unwrap_or(symbol)
+    }
+
+    pub fn len(&self) -> usize {
+        self.symbols.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.symbols.is_empty()
+    }
+}
+
+fn lookup_keys(symbol: &Symbol) -> Vec<String> {
+    let mut keys = vec![symbol.name.clone()];
+    if let Ok(rust) = rustc_demangle::try_demangle(&symbol.name) {
+        keys.push(rust.to_string());
+        keys.push(format!("{rust:#}"));
+    } else if let Some(cpp) = parse_cpp_symbol(&symbol.name) {
+        keys.extend(cpp.demangle());
+        let no_params = cpp_demangle::DemangleOptions::new().no_params();
+        keys.extend(cpp.demangle_with_options(&no_params));
+    }
+    keys
+}
+
+/// Demangles a Rust or Itanium C++ symbol name. Rust names are returned without their hash.
+pub fn demangle(name: &str) -> Option<String> {
+    if let Ok(rust) = rustc_demangle::try_demangle(name) {
+        return Some(format!("{rust:#}"));
+    }
+    parse_cpp_symbol(name)?.demangle().ok()
+}
+
+fn parse_cpp_symbol(name: &str) -> Option<cpp_demangle::Symbol<&[u8]>> {
+    // Plain C names like `main` parse as C++ types, only accept actual mangled names
+    if !name.starts_with("_Z") {
+        return None;
+    }
+    cpp_demangle::Symbol::new(name.as_bytes()).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use goblin::Object;
+
+    use super::{demangle, SymbolIndex};
+
+    #[test]
+    fn test_demangle() {
+        assert_eq!(
+            demangle("_ZN4core3fmt5write17h01234567890abcdeE").as_deref(),
+            Some("core::fmt::write")
+        );
+        assert_eq!(demangle("_Z3fooi").as_deref(), Some("foo(int)"));
+        assert_eq!(demangle("main"), None);
+    }
+
+    #[test]
+    fn test_elf_symbols() {
+        let binary = std::fs::read("tests/gcc/a.out").unwrap();
+        let Object::Elf(elf) = Object::parse(&binary).unwrap() else {
+            panic!("not an ELF");
+        };
+        let index = SymbolIndex::from_elf(&elf);
+
+        let main = index.lookup("main").next().unwrap();
+        assert_eq!(main.vaddr, 0x1151);
+        assert_eq!(main.section.as_deref(), Some(".text"));
+        assert_eq!(main.file_offset, Some(0x1151));
+        assert!(index.lookup("no_such_symbol").next().is_none());
+
+        // `data_start` is a weak alias of `__data_start`
+        let alias = index.lookup("data_start").next().unwrap();
+        assert_eq!(alias.aliases, ["__data_start"]);
+        let canonical = index.canonical(alias);
+        assert_eq!(canonical.name, "__data_start");
+        assert_eq!(canonical.aliases, ["data_start"]);
+        assert_eq!(
+            index
+                .deduplicated()
+                .filter(|symbol| symbol.vaddr == alias.vaddr)
+                .map(|symbol| symbol.name.as_str())
+                .collect::<Vec<_>>(),
+            ["__data_start"]
+        );
+        assert!(index.deduplicated().count() < index.len());
+    }
+}
@@ -0,0 +1,307 @@
+//! A compact table of the address ranges of a binary and the files they come from, generated at
+//! build time and embedded in firmware, so that e.g. a fault handler can log which file owns the
+//! faulting PC. Reading it needs neither `std` nor `alloc`.
+//!
+//! The table is little-endian and unaligned:
+//!
+//! | offset | size   | contents                                              |
+//! |--------|--------|-------------------------------------------------------|
+//! | 0      | 4      | magic, `GNLG`                                         |
+//! | 4      | 2      | version, 1                                            |
+//! | 6      | 2      | flags, bit 0 set when addresses are 64-bit wide       |
+//! | 8      | 4      | number of ranges                                      |
+//! | 12     | 4      | size of the string pool                               |
+//! | 16     | n × e  | ranges: start, end (exclusive), offset of the name    |
+//! | …      | …      | string pool: NUL-terminated UTF-8 names               |
+//!
+//! Ranges are sorted by start address and do not overlap. Addresses take 4 or 8 bytes and name
+//! offsets 4, so an entry `e` is 12 or 20 bytes long.
+//!
+//! Tables outlive the crate that wrote them, e.g. in the symbol stores of servers running
+//! several releases. Any change to the layout gets a new version, and a release reads the
+//! tables of every version from [`MIN_VERSION`] to its own [`VERSION`]. Tables of a newer
+//! version, or using flags a release does not know, are rejected with
+//! [`TableError::RebuildNeeded`] rather than misread: rebuild them with the release reading
+//! them.
+
+use core::{fmt::Display, str};
+#[cfg(feature = "std")]
+use std::collections::HashMap;
+
+#[cfg(feature = "std")]
+use crate::Genealogy;
+
+/// The first bytes of a table.
+pub const MAGIC: [u8; 4] = *b"GNLG";
+/// The version of the format written by [`ProvenanceTable::build`].
+pub const VERSION: u16 = 1;
+/// The oldest version read.
+pub const MIN_VERSION: u16 = 1;
+
+const HEADER_LEN: usize = 16;
+const WIDE_ADDRESSES: u16 = 1;
+/// The flags known to this version.
+const KNOWN_FLAGS: u16 = WIDE_ADDRESSES;
+
+/// Why bytes are not a table this release can read, see [`ProvenanceTable::read`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TableError {
+    /// The bytes do not start with [`MAGIC`].
+    NotATable,
+    /// The table was written with another version of the format, or with flags this release
+    /// does not know, and must be rebuilt.
+    RebuildNeeded { version: u16, flags: u16 },
+    /// The header announces more ranges or strings than there are bytes.
+    Truncated,
+}
+
+impl Display for TableError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            TableError::NotATable => write!(f, "not a provenance table"),
+            TableError::RebuildNeeded { version, flags }
+                if (MIN_VERSION..=VERSION).contains(version) =>
+            {
+                write!(
+                    f,
+                    "provenance table of version {version} with unknown flags {flags:#06x}: \
+                     rebuild it"
+                )
+            }
+            TableError::RebuildNeeded { version, .. } => write!(
+                f,
+                "provenance table of version {version}, this version reads versions \
+                 {MIN_VERSION} to {VERSION}: rebuild it"
+            ),
+            TableError::Truncated => write!(f, "truncated provenance table"),
+        }
+    }
+}
+
+impl core::error::Error for TableError {}
+
+/// A serialized table, looked up in place.
+#[derive(Clone, Copy, Debug)]
+pub struct ProvenanceTable<'a> {
+    address_len: usize,
+    len: usize,
+    ranges: &'a [u8],
+    strings: &'a [u8],
+}
+
+impl<'a> ProvenanceTable<'a> {
+    /// Checks the header and the size of `bytes`, `None` when it is not a table of a supported
+    /// version. See [`ProvenanceTable::read`] for why.
+    pub fn parse(bytes: &'a [u8]) -> Option<Self> {
+        Self::read(bytes).ok()
+    }
+
+    /// Checks the header and the size of `bytes`, failing with
+    /// [`TableError::RebuildNeeded`] for tables this release cannot read.
+    pub fn read(bytes: &'a [u8]) -> Result<Self, TableError> {
+        if !bytes.starts_with(&MAGIC) {
+            return Err(TableError::NotATable);
+        }
+        let header = bytes.get(..HEADER_LEN).ok_or(TableError::Truncated)?;
+        let version = u16::from_le_bytes([header[4], header[5]]);
+        let flags = u16::from_le_bytes([header[6], header[7]]);
+        if !(MIN_VERSION..=VERSION).contains(&version) || flags & !KNOWN_FLAGS != 0 {
+            return Err(TableError::RebuildNeeded { version, flags });
+        }
+        let address_len = if flags & WIDE_ADDRESSES != 0 { 8 } else { 4 };
+        let len = read_u32(&header[8..12]) as usize;
+        let strings_len = read_u32(&header[12..16]) as usize;
+
+        let ranges_len = len
+            .checked_mul(2 * address_len + 4)
+            .ok_or(TableError::Truncated)?;
+        let rest = &bytes[HEADER_LEN..];
+        let ranges = rest.get(..ranges_len).ok_or(TableError::Truncated)?;
+        let strings = ranges_len
+            .checked_add(strings_len)
+            .and_then(|end| rest.get(ranges_len..end))
+            .ok_or(TableError::Truncated)?;
+        Ok(Self {
+            address_len,
+            len,
+            ranges,
+            strings,
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The name of the file owning `address`, in O(log n) for n ranges.
+    pub fn lookup(&self, address: u64) -> Option<&'a str> {
+        // The first range starting after the address, the one before may contain it
+        let (mut low, mut high) = (0, self.len);
+        while low < high {
+            let middle = low + (high - low) / 2;
+            if self.range(middle).0 <= address {
+                low = middle + 1;
+            } else {
+                high = middle;
+            }
+        }
+        let (_, end, name) = self.range(low.checked_sub(1)?);
+        (address < end).then_some(name).flatten()
+    }
+
+    /// Iterates over the `(start, end, name)` of every range, in address order. Names that are
+    /// not in the string pool are `None`.
+    pub fn iter(&self) -> impl Iterator<Item = (u64, u64, Option<&'a str>)> + '_ {
+        (0..self.len).map(|i| self.range(i))
+    }
+
+    fn range(&self, i: usize) -> (u64, u64, Option<&'a str>) {
+        let entry_len = 2 * self.address_len + 4;
+        let entry = &self.ranges[i * entry_len..(i + 1) * entry_len];
+        let (start, rest) = entry.split_at(self.address_len);
+        let (end, name) = rest.split_at(self.address_len);
+        (
+            self.read_address(start),
+            self.read_address(end),
+            self.name(read_u32(name) as usize),
+        )
+    }
+
+    fn read_address(&self, bytes: &[u8]) -> u64 {
+        if self.address_len == 8 {
+            u64::from_le_bytes(bytes.try_into().expect("8 bytes"))
+        } else {
+            u64::from(read_u32(bytes))
+        }
+    }
+
+    fn name(&self, offset: usize) -> Option<&'a str> {
+        let bytes = self.strings.get(offset..)?;
+        let len = bytes.iter().position(|&b| b == 0)?;
+        str::from_utf8(&bytes[..len]).ok()
+    }
+}
+
+fn read_u32(bytes: &[u8]) -> u32 {
+    u32::from_le_bytes(bytes.try_into().expect("4 bytes"))
+}
+
+#[cfg(feature = "std")]
+impl ProvenanceTable<'_> {
+    /// Serializes the ranges [`Genealogy::query_vaddr`](crate::Genealogy::query_vaddr) answers
+    /// from, i.e. the ones of loaded sections. Names are the ones of the genealogy, so a shorter
+    /// [`OriginNaming`](crate::origin::OriginNaming) makes a smaller table.
+    ///
+    /// Contiguous ranges of the same file are merged, and ranges overlapping a previous one are
+    /// left out.
+    pub fn build(genealogy: &Genealogy) -> Vec<u8> {
+        let mut ranges: Vec<(u64, u64, &str)> = vec![];
+        for hit in genealogy.index().iter_vaddr() {
+            match ranges.last_mut() {
+                Some((_, end, name)) if *end == hit.range.start && *name == hit.filename => {
+                    *end = hit.range.end;
+                }
+                Some((_, end, _)) if hit.range.start < *end => {}
+                _ => ranges.push((hit.range.start, hit.range.end, hit.filename)),
+            }
+        }
+
+        let mut strings = vec![];
+        let mut offsets: HashMap<&str, u32> = HashMap::new();
+        for &(_, _, name) in &ranges {
+            offsets.entry(name).or_insert_with(|| {
+                let offset = strings.len() as u32;
+                strings.extend_from_slice(name.as_bytes());
+                strings.push(0);
+                offset
+            });
+        }
+
+        let wide = ranges
+            .last()
+            .is_some_and(|&(_, end, _)| end > u64::from(u32::MAX));
+        let mut table = Vec::with_capacity(HEADER_LEN + ranges.len() * 20 + strings.len());
+        table.extend_from_slice(&MAGIC);
+        table.extend_from_slice(&VERSION.to_le_bytes());
+        table.extend_from_slice(&(if wide { WIDE_ADDRESSES } else { 0 }).to_le_bytes());
+        table.extend_from_slice(&(ranges.len() as u32).to_le_bytes());
+        table.extend_from_slice(&(strings.len() as u32).to_le_bytes());
+        for (start, end, name) in &ranges {
+            for address in [start, end] {
+                if wide {
+                    table.extend_from_slice(&address.to_le_bytes());
+                } else {
+                    table.extend_from_slice(&(*address as u32).to_le_bytes());
+                }
+            }
+            table.extend_from_slice(&offsets[name].to_le_bytes());
+        }
+        table.extend_from_slice(&strings);
+        table
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use crate::Genealogy;
+
+    use super::{ProvenanceTable, TableError};
+
+    #[test]
+    fn test_provenance_table() {
+        let mapfile = std::fs::read_to_string("tests/gcc/out.map").unwrap();
+        let binary = std::fs::read("tests/gcc/a.out").unwrap();
+        let genealogy = Genealogy::new(&mapfile, &binary).unwrap();
+
+        let bytes = ProvenanceTable::build(&genealogy);
+        let table = ProvenanceTable::parse(&bytes).unwrap();
+        assert!(!table.is_empty());
+        assert!(table.len() <= genealogy.index().iter_vaddr().count());
+        assert_eq!(table.lookup(0x1151), Some("simple.o"));
+        assert_eq!(table.lookup(0x1198), Some("simple2.o"));
+        assert_eq!(table.lookup(0), None);
+        assert_eq!(table.lookup(u64::MAX), None);
+        for hit in genealogy.index().iter_vaddr() {
+            assert!(table.lookup(hit.range.start).is_some());
+        }
+        assert!(table
+            .iter()
+            .zip(table.iter().skip(1))
+            .all(|((_, end, _), (start, _, _))| end <= start));
+
+        // Truncated, of another version or with flags of another version
+        assert!(ProvenanceTable::parse(&bytes[..bytes.len() - 1]).is_none());
+        assert_eq!(
+            ProvenanceTable::read(&bytes[..8]).err(),
+            Some(TableError::Truncated)
+        );
+        assert_eq!(
+            ProvenanceTable::read(b"not a table").err(),
+            Some(TableError::NotATable)
+        );
+        let mut other_version = bytes.clone();
+        other_version[4] = 2;
+        let error = ProvenanceTable::read(&other_version).err().unwrap();
+        assert_eq!(
+            error,
+            TableError::RebuildNeeded {
+                version: 2,
+                flags: 0
+            }
+        );
+        assert_eq!(
+            error.to_string(),
+            "provenance table of version 2, this version reads versions 1 to 1: rebuild it"
+        );
+        let mut other_flags = bytes.clone();
+        other_flags[6] |= 0x80;
+        assert!(matches!(
+            ProvenanceTable::read(&other_flags),
+            Err(TableError::RebuildNeeded { version: 1, .. })
+        ));
+    }
+}
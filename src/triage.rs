@@ -0,0 +1,107 @@
+//! Parsing of AddressSanitizer / UndefinedBehaviorSanitizer stack traces, so that crash frames
+//! can be attributed to the object files they were linked from.
+
+use regex::Regex;
+
+use crate::origin::file_name;
+
+/// One `#N 0x... in function (module+0x...)` line of a sanitizer stack trace.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Frame {
+    /// The frame number, restarting at 0 for every stack trace of the log.
+    pub index: u32,
+    /// The runtime program counter.
+    pub pc: u64,
+    pub function: Option<String>,
+    /// The `file:line:column` source location, when the trace is symbolized.
+    pub location: Option<String>,
+    pub module: Option<String>,
+    /// The program counter relative to the module load address.
+    pub module_offset: Option<u64>,
+}
+
+impl Frame {
+    /// The link-time virtual address of the frame.
+    ///
+    /// The module offset is relative to the load bias, so it is the link-time address both for
+    /// position independent executables and for executables linked at their load address. Without
+    /// it, the binary is assumed not to be relocated.
+    pub fn vaddr(&self) -> u64 {
+        self.module_offset.unwrap_or(self.pc)
+    }
+
+    /// Whether the frame belongs to the module named `binary_name` (compared by file name), or
+    /// possibly does when the trace does not name modules.
+    pub fn is_in_module(&self, binary_name: &str) -> bool {
+        let Some(module) = &self.module else {
+            return true;
+        };
+        file_name(module) == file_name(binary_name)
+    }
+}
+
+/// Extracts all stack frames of a sanitizer log, in order. Other lines are ignored.
+pub fn parse_sanitizer_frames(log: &str) -> Vec<Frame> {
+    let location_regex = Regex::new(r"^(?<function>.+)\s+(?<location>\S+:\d+(?::\d+)?)$").unwrap();
+    let frame_regex = Regex::new(
+        r"^\s*#(?<index>\d+)\s+0x(?<pc>[0-9a-fA-F]+)(?:\s+in\s+(?<function>.+?))?(?:\s+\((?<module>[^()]+)\+0x(?<offset>[0-9a-fA-F]+)\))?(?:\s+\(BuildId:\s*[0-9a-fA-F]+\))?\s*$",
+    )
+    .unwrap();
+
+    log.lines()
+        .filter_map(|line| frame_regex.captures(line))
+        .filter_map(|c| {
+            // Separate a trailing source location from the (possibly demangled) function name
+            let (function, location) = match c.name("function").map(|m| m.as_str()) {
+                Some(text) => match location_regex.captures(text) {
+                    Some(l) => (
+                        Some(l["function"].to_string()),
+                        Some(l["location"].to_string()),
+                    ),
+                    None => (Some(text.to_string()), None),
+                },
+                None => (None, None),
+            };
+            Some(Frame {
+                index: c["index"].parse().ok()?,
+                pc: u64::from_str_radix(&c["pc"], 16).ok()?,
+                function,
+                location,
+                module: c.name("module").map(|m| m.as_str().to_string()),
+                module_offset: c
+                    .name("offset")
+                    .and_then(|m| u64::from_str_radix(m.as_str(), 16).ok()),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_sanitizer_frames;
+
+    #[test]
+    fn test_parse_sanitizer_frames() {
+        let log = "\
+==1234==ERROR: AddressSanitizer: heap-buffer-overflow on address 0x602000000014
+READ of size 4 at 0x602000000014 thread T0
+    #0 0x55d4c3a1b2c3 in f /src/simple.c:5:10
+    #1 0x55d4c3a1b150 in main (/build/a.out+0x1150) (BuildId: 0123abcd)
+    #2 0x7f0a1b2c3d4e in __libc_start_main (/lib/x86_64-linux-gnu/libc.so.6+0x29d8f)
+
+0x602000000014 is located 0 bytes after 4-byte region
+";
+        let frames = parse_sanitizer_frames(log);
+        assert_eq!(frames.len(), 3);
+
+        assert_eq!(frames[0].function.as_deref(), Some("f"));
+        assert_eq!(frames[0].location.as_deref(), Some("/src/simple.c:5:10"));
+        assert_eq!(frames[0].vaddr(), 0x55d4c3a1b2c3);
+
+        assert_eq!(frames[1].index, 1);
+        assert_eq!(frames[1].function.as_deref(), Some("main"));
+        assert_eq!(frames[1].vaddr(), 0x1150);
+        assert!(frames[1].is_in_module("out/a.out"));
+        assert!(!frames[2].is_in_module("a.out"));
+    }
+}
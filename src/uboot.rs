@@ -0,0 +1,420 @@
+//! U-Boot builds: the TPL, VPL, SPL and U-Boot proper stages linked from one source tree, each
+//! with its map, and the images they are flashed in, FIT images (`u-boot.itb`) or concatenations
+//! of several stages, to audit what every stage brings in.
+
+use std::{collections::HashMap, fmt::Display, ops::Range};
+
+use crate::{
+    format::{hex, human_size, Align, Cell, Style, Table},
+    Genealogy, GenealogyError,
+};
+
+/// The magic number of flattened device trees, FIT images included.
+pub const FDT_MAGIC: u32 = 0xd00d_feed;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Stage {
+    Tpl,
+    Vpl,
+    Spl,
+    /// U-Boot proper, `u-boot` at the top of the build directory.
+    Proper,
+}
+
+impl Stage {
+    /// In boot order.
+    pub const ALL: [Stage; 4] = [Stage::Tpl, Stage::Vpl, Stage::Spl, Stage::Proper];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Stage::Tpl => "tpl",
+            Stage::Vpl => "vpl",
+            Stage::Spl => "spl",
+            Stage::Proper => "u-boot",
+        }
+    }
+
+    /// The ELF file of the stage, relative to the build directory. Its map is next to it, with
+    /// a `.map` extension.
+    pub fn elf_path(self) -> &'static str {
+        match self {
+            Stage::Tpl => "tpl/u-boot-tpl",
+            Stage::Vpl => "vpl/u-boot-vpl",
+            Stage::Spl => "spl/u-boot-spl",
+            Stage::Proper => "u-boot",
+        }
+    }
+
+    pub fn map_path(self) -> String {
+        format!("{}.map", self.elf_path())
+    }
+
+    /// The raw binary `objcopy` makes of the ELF file, before the device tree is appended to
+    /// make `u-boot.bin` or `u-boot-spl.bin`.
+    pub fn raw_path(self) -> String {
+        format!("{}-nodtb.bin", self.elf_path())
+    }
+
+    /// The directory the objects of the stage are built in, which prefixes their names in its
+    /// map, e.g. `spl/drivers/serial/ns16550.o`.
+    fn object_dir(self) -> &'static str {
+        match self {
+            Stage::Tpl => "tpl/",
+            Stage::Vpl => "vpl/",
+            Stage::Spl => "spl/",
+            Stage::Proper => "",
+        }
+    }
+}
+
+/// The bytes every file brings into every stage, see [`stage_report`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StageReport {
+    /// In boot order.
+    pub stages: Vec<Stage>,
+    /// Biggest files first.
+    pub rows: Vec<StageRow>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StageRow {
+    /// Without the directory of the stage, so that the builds of a source file for several
+    /// stages share a row.
+    pub filename: String,
+    /// One cell per entry of [`StageReport::stages`].
+    pub sizes: Vec<u64>,
+}
+
+impl StageRow {
+    pub fn total(&self) -> u64 {
+        self.sizes.iter().sum()
+    }
+}
+
+/// Sums the bytes of the loaded sections of every stage per file, `.bss` included, since it
+/// takes the SRAM of early stages too.
+pub fn stage_report(stages: &[(Stage, &Genealogy)]) -> StageReport {
+    let mut stages = stages.to_vec();
+    stages.sort_by_key(|&(stage, _)| Stage::ALL.iter().position(|&s| s == stage));
+    let mut rows: HashMap<&str, Vec<u64>> = HashMap::new();
+    for (column, (stage, genealogy)) in stages.iter().enumerate() {
+        for section in genealogy.sections().iter().filter(|section| section.loaded) {
+            for subsection in &section.subsections {
+                let filename = subsection.filename.as_str();
+                let filename = filename
+                    .strip_prefix(stage.object_dir())
+                    .unwrap_or(filename);
+                rows.entry(filename)
+                    .or_insert_with(|| vec![0; stages.len()])[column] += subsection.size;
+            }
+        }
+    }
+
+    let mut rows: Vec<StageRow> = rows
+        .into_iter()
+        .map(|(filename, sizes)| StageRow {
+            filename: filename.to_string(),
+            sizes,
+        })
+        .filter(|row| row.total() > 0)
+        .collect();
+    rows.sort_by(|a, b| {
+        b.total()
+            .cmp(&a.total())
+            .then_with(|| a.filename.cmp(&b.filename))
+    });
+    StageReport {
+        stages: stages.into_iter().map(|(stage, _)| stage).collect(),
+        rows,
+    }
+}
+
+impl StageReport {
+    /// The table rendered by `Display`, a column per stage.
+    pub fn to_table(&self) -> Table {
+        let mut aligns = vec![Align::Right; self.stages.len()];
+        aligns.push(Align::Left);
+        let mut header: Vec<String> = self
+            .stages
+            .iter()
+            .map(|stage| stage.name().to_string())
+            .collect();
+        header.push("file".to_string());
+        let mut table = Table::new(&aligns).with_header(header);
+        for row in &self.rows {
+            let mut cells: Vec<Cell> = row
+                .sizes
+                .iter()
+                .map(|&size| match size {
+                    0 => Cell::from("-"),
+                    size => human_size(size).into(),
+                })
+                .collect();
+            cells.push(row.filename.clone().into());
+            table.push_row(cells);
+        }
+        let mut totals: Vec<Cell> = (0..self.stages.len())
+            .map(|column| {
+                let total = self.rows.iter().map(|row| row.sizes[column]).sum();
+                Cell::styled(human_size(total), Some(Style::Bold))
+            })
+            .collect();
+        totals.push(format!("total in {} files", self.rows.len()).into());
+        table.push_row(totals);
+        table
+    }
+}
+
+impl Display for StageReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_table())
+    }
+}
+
+/// An image of a FIT image, a node of its `/images`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FitImage {
+    /// The name of the node, e.g. `uboot` or `fdt-1`.
+    pub name: String,
+    /// The `type` property, e.g. `firmware` or `flat_dt`.
+    pub kind: String,
+    pub description: String,
+    /// The offsets of the contents in the FIT image, inside its device tree or after it when
+    /// built with `mkimage -E`.
+    pub data: Range<u64>,
+}
+
+/// The images of the FIT image `itb`, in device tree order.
+pub fn fit_images(itb: &[u8]) -> Result<Vec<FitImage>, GenealogyError> {
+    let word = |offset: usize| -> Result<u32, GenealogyError> {
+        itb.get(offset..offset + 4)
+            .map(|bytes| u32::from_be_bytes(bytes.try_into().expect("4 bytes")))
+            .ok_or(GenealogyError::WrongFitFormat)
+    };
+    if word(0)? != FDT_MAGIC {
+        return Err(GenealogyError::WrongFitFormat);
+    }
+    let total_size = word(4)? as usize;
+    let struct_offset = word(8)? as usize;
+    let strings_offset = word(12)? as usize;
+    let string = |offset: usize| -> Result<&str, GenealogyError> {
+        let bytes = itb.get(offset..).ok_or(GenealogyError::WrongFitFormat)?;
+        let end = bytes
+            .iter()
+            .position(|&b| b == 0)
+            .ok_or(GenealogyError::WrongFitFormat)?;
+        std::str::from_utf8(&bytes[..end]).map_err(|_| GenealogyError::WrongFitFormat)
+    };
+    // External data follows the device tree, 4-byte aligned
+    let external_base = total_size.next_multiple_of(4) as u64;
+
+    let mut images = vec![];
+    let mut path: Vec<&str> = vec![];
+    // The image being read, and its `data-offset`, `data-position` and `data-size`
+    let mut image: Option<(FitImage, Option<u64>, Option<u64>)> = None;
+    let mut offset = struct_offset;
+    loop {
+        let token = word(offset)?;
+        offset += 4;
+        match token {
+            // FDT_BEGIN_NODE
+            1 => {
+                let name = string(offset)?;
+                offset += (name.len() + 1).next_multiple_of(4);
+                path.push(name);
+                if path.len() == 3 && path[1] == "images" {
+                    image = Some((
+                        FitImage {
+                            name: name.to_string(),
+                            kind: String::new(),
+                            description: String::new(),
+                            data: 0..0,
+                        },
+                        None,
+                        None,
+                    ));
+                }
+            }
+            // FDT_END_NODE
+            2 => {
+                if path.len() == 3 {
+                    if let Some((mut fit_image, position, size)) = image.take() {
+                        if let (Some(position), Some(size)) = (position, size) {
+                            fit_image.data = position..position + size;
+                        }
+                        images.push(fit_image);
+                    }
+                }
+                path.pop().ok_or(GenealogyError::WrongFitFormat)?;
+            }
+            // FDT_PROP
+            3 => {
+                let len = word(offset)? as usize;
+                let name = string(strings_offset + word(offset + 4)? as usize)?;
+                let value_offset = offset + 8;
+                let value = itb
+                    .get(value_offset..value_offset + len)
+                    .ok_or(GenealogyError::WrongFitFormat)?;
+                offset = value_offset + len.next_multiple_of(4);
+                let Some((fit_image, position, size)) = image.as_mut().filter(|_| path.len() == 3)
+                else {
+                    continue;
+                };
+                let text = || String::from_utf8_lossy(value.strip_suffix(b"\0").unwrap_or(value));
+                match name {
+                    "type" => fit_image.kind = text().into_owned(),
+                    "description" => fit_image.description = text().into_owned(),
+                    "data" => {
+                        fit_image.data = value_offset as u64..(value_offset + len) as u64;
+                    }
+                    "data-offset" => {
+                        *position = Some(external_base + u64::from(word(value_offset)?))
+                    }
+                    "data-position" => *position = Some(u64::from(word(value_offset)?)),
+                    "data-size" => *size = Some(u64::from(word(value_offset)?)),
+                    _ => {}
+                }
+            }
+            // FDT_NOP
+            4 => {}
+            // FDT_END
+            9 => break,
+            _ => return Err(GenealogyError::WrongFitFormat),
+        }
+    }
+    Ok(images)
+}
+
+/// The table of the images of a FIT image.
+pub fn fit_table(images: &[FitImage]) -> Table {
+    let mut table = Table::new(&[
+        Align::Left,
+        Align::Left,
+        Align::Left,
+        Align::Right,
+        Align::Left,
+    ])
+    .with_header(["image", "type", "offset", "size", "description"]);
+    for image in images {
+        table.push_row([
+            image.name.clone(),
+            image.kind.clone(),
+            hex(image.data.start),
+            human_size(image.data.end - image.data.start),
+            image.description.clone(),
+        ]);
+    }
+    table
+}
+
+/// The offset of the raw binary of a stage, see [`Stage::raw_path`], in `image`: the start of
+/// the image of the FIT image `image` holding it, or else where its first bytes are first found,
+/// e.g. in `u-boot-with-spl.bin`. `None` when the image does not contain it.
+pub fn locate_stage(image: &[u8], raw: &[u8]) -> Option<u64> {
+    let prefix = &raw[..raw.len().min(64)];
+    if prefix.is_empty() {
+        return None;
+    }
+    if let Ok(fit) = fit_images(image) {
+        let found = fit.iter().find(|fit_image| {
+            image
+                .get(fit_image.data.start as usize..fit_image.data.end as usize)
+                .is_some_and(|data| data.starts_with(prefix))
+        });
+        if let Some(fit_image) = found {
+            return Some(fit_image.data.start);
+        }
+    }
+    image
+        .windows(prefix.len())
+        .position(|window| window == prefix)
+        .map(|offset| offset as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        image::{CombinedImage, Component, Placement},
+        Genealogy,
+    };
+
+    use super::{fit_images, locate_stage, stage_report, Stage};
+
+    #[test]
+    fn test_uboot_stages() {
+        let genealogy = |stage: Stage| {
+            let mapfile = std::fs::read_to_string(format!("tests/uboot/{}", stage.map_path()));
+            let binary = std::fs::read(format!("tests/uboot/{}", stage.elf_path())).unwrap();
+            Genealogy::new(&mapfile.unwrap(), &binary).unwrap()
+        };
+        let (spl, proper) = (genealogy(Stage::Spl), genealogy(Stage::Proper));
+
+        // The driver built for both stages shares a row
+        let report = stage_report(&[(Stage::Proper, &proper), (Stage::Spl, &spl)]);
+        assert_eq!(report.stages, [Stage::Spl, Stage::Proper]);
+        let serial = report
+            .rows
+            .iter()
+            .find(|row| row.filename == "drivers/serial/ns16550.o")
+            .unwrap();
+        assert_eq!(serial.sizes, [0x58 + 0x11, 0x58 + 0x11]);
+        let spl_only = report
+            .rows
+            .iter()
+            .find(|row| row.filename == "common/spl/spl.o")
+            .unwrap();
+        assert_eq!(spl_only.sizes, [0x1e + 0xc + 0x8, 0]);
+
+        let itb = std::fs::read("tests/uboot/u-boot.itb").unwrap();
+        let images = fit_images(&itb).unwrap();
+        let names: Vec<(&str, &str)> = images
+            .iter()
+            .map(|image| (image.name.as_str(), image.kind.as_str()))
+            .collect();
+        assert_eq!(names, [("uboot", "firmware"), ("fdt-1", "flat_dt")]);
+        assert_eq!(images[0].data.end - images[0].data.start, 0x110);
+        assert!(fit_images(b"not a device tree").is_err());
+
+        // The SPL, padded to 512 bytes, then the FIT image
+        let image = std::fs::read("tests/uboot/u-boot-with-spl.bin").unwrap();
+        let spl_raw = std::fs::read("tests/uboot/spl/u-boot-spl-nodtb.bin").unwrap();
+        let proper_raw = std::fs::read("tests/uboot/u-boot-nodtb.bin").unwrap();
+        assert_eq!(locate_stage(&image, &spl_raw), Some(0));
+        let proper_offset = locate_stage(&image, &proper_raw).unwrap();
+        assert_eq!(proper_offset, 0x200 + images[0].data.start);
+        assert_eq!(locate_stage(&itb, &proper_raw), Some(images[0].data.start));
+
+        let binaries = [
+            std::fs::read("tests/uboot/spl/u-boot-spl").unwrap(),
+            std::fs::read("tests/uboot/u-boot").unwrap(),
+        ];
+        let combined = CombinedImage::new(
+            &image,
+            vec![
+                Component {
+                    name: Stage::Spl.name().to_string(),
+                    offset: 0,
+                    placement: Placement::Raw,
+                    genealogy: spl,
+                    binary: &binaries[0],
+                },
+                Component {
+                    name: Stage::Proper.name().to_string(),
+                    offset: proper_offset,
+                    placement: Placement::Raw,
+                    genealogy: proper,
+                    binary: &binaries[1],
+                },
+            ],
+        );
+        let hit = combined.query_point(0x30).next().unwrap();
+        assert_eq!(
+            (hit.component, hit.filename),
+            ("spl", "spl/drivers/serial/ns16550.o")
+        );
+        let hit = combined.query_point(proper_offset + 0x50).next().unwrap();
+        assert_eq!((hit.component, hit.filename), ("u-boot", "cmd/version.o"));
+        // `.bss` is not in the raw binaries, the device tree after U-Boot is not attributed
+        assert!(combined.query_point(proper_offset + 0x110).next().is_none());
+    }
+}
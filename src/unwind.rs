@@ -0,0 +1,78 @@
+//! Attribution of unwinding metadata, which often amounts to a hidden 10-20% of a binary.
+
+use crate::{Genealogy, Report};
+
+/// Sections holding unwind tables and language-specific exception data, for the ELF (Itanium and
+/// ARM EHABI) and PE ABIs.
+pub const UNWIND_SECTIONS: [&str; 7] = [
+    ".eh_frame",
+    ".eh_frame_hdr",
+    ".gcc_except_table",
+    ".ARM.exidx",
+    ".ARM.extab",
+    ".pdata",
+    ".xdata",
+];
+
+/// Whether `name` is an unwind section, possibly split per function (`.gcc_except_table.main`).
+pub fn is_unwind_section(name: &str) -> bool {
+    UNWIND_SECTIONS.iter().any(|unwind| {
+        name.strip_prefix(unwind)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with(['.', '$']))
+    })
+}
+
+impl Genealogy {
+    /// Sums the unwind table bytes each file contributes to the binary.
+    ///
+    /// Input sections are matched by name when the map gives them, by output section otherwise
+    /// (MSVC maps only locate contributions within PE sections).
+    pub fn unwind_report(&self) -> Report {
+        Report::from_sizes(
+            self.sections()
+                .iter()
+                .flat_map(|section| {
+                    section.subsections.iter().filter(move |subsection| {
+                        let name = if subsection.name.is_empty() {
+                            &section.name
+                        } else {
+                            &subsection.name
+                        };
+                        is_unwind_section(name)
+                    })
+                })
+                .filter(|subsection| subsection.start_file_offset.is_some())
+                .map(|subsection| (subsection.filename.as_str(), subsection.size)),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Genealogy;
+
+    use super::is_unwind_section;
+
+    #[test]
+    fn test_unwind_report() {
+        assert!(is_unwind_section(".eh_frame"));
+        assert!(is_unwind_section(".gcc_except_table._ZN3foo3barEv"));
+        assert!(is_unwind_section(".xdata$x"));
+        assert!(!is_unwind_section(".eh_framex"));
+        assert!(!is_unwind_section(".text"));
+
+        let mapfile = std::fs::read_to_string("tests/gcc/out.map").unwrap();
+        let binary = std::fs::read("tests/gcc/a.out").unwrap();
+        let genealogy = Genealogy::new(&mapfile, &binary).unwrap();
+
+        let unwind = genealogy.unwind_report();
+        assert!(unwind.entries.iter().any(|e| e.filename == "simple.o"));
+        assert!(unwind.total > 0 && unwind.total < genealogy.report().total);
+
+        // MSVC contributions are named after the `.xdata` / `.pdata` groups they fall in
+        let mapfile = std::fs::read_to_string("tests/cargo-windows/out.map").unwrap();
+        let binary = std::fs::read("tests/cargo-windows/genealogy.exe").unwrap();
+        let genealogy = Genealogy::new(&mapfile, &binary).unwrap();
+        assert!(genealogy.unwind_report().total > 0);
+    }
+}
@@ -0,0 +1,226 @@
+//! Consistency checks run while building a [`Genealogy`](crate::Genealogy). Violations do not
+//! fail the build, they are reported as [`Warning`]s and the offending entries are left out of
+//! the indexes rather than producing wrong intervals.
+
+use std::fmt::Display;
+
+use crate::{format::hex, Section};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Warning {
+    /// A subsection does not lie within `[vaddr, vaddr + size)` of its section.
+    SubsectionOutOfBounds {
+        section: String,
+        section_range: (u64, u64),
+        subsection: String,
+        filename: String,
+        start_vaddr: u64,
+        size: u64,
+    },
+    /// A section of the map has no counterpart in the binary, its subsections are not indexed.
+    SectionNotInBinary { section: String },
+    /// An ELF section is not at the address the map gives, the map was likely produced against
+    /// another base, see [`GenealogyBuilder::vaddr_bias`](crate::GenealogyBuilder::vaddr_bias).
+    SectionAddressMismatch {
+        section: String,
+        map_vaddr: u64,
+        binary_vaddr: u64,
+    },
+    /// The raw data of a PE section does not start on a file alignment boundary, as often in
+    /// packed images. Its file offsets may not be those used by the loader.
+    UnalignedRawData {
+        section: String,
+        pointer_to_raw_data: u64,
+        file_alignment: u64,
+    },
+    /// The raw data of a PE section extends past the end of the file, only the bytes present are
+    /// attributed.
+    RawDataOutOfFile {
+        section: String,
+        raw_size: u64,
+        available: u64,
+    },
+    /// `lines` lines of the map from `line` (1-based) could not be parsed, e.g. because of a
+    /// hand edit or of another map appended to it. Parsing resumed after them.
+    MalformedMapRegion { line: usize, lines: usize },
+    /// A section of a GNU map without input sections was attributed from the runs of its
+    /// `symbols`, their sizes are approximate.
+    SymbolRuns { section: String, symbols: usize },
+}
+
+impl Display for Warning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Warning::SubsectionOutOfBounds {
+                section,
+                section_range: (start, end),
+                subsection,
+                filename,
+                start_vaddr,
+                size,
+            } => write!(
+                f,
+                "{} of {filename} at {} (size {}) is outside of {section} {}..{}, ignored",
+                if subsection.is_empty() {
+                    "contribution"
+                } else {
+                    subsection
+                },
+                hex(*start_vaddr),
+                hex(*size),
+                hex(*start),
+                hex(*end)
+            ),
+            Warning::SectionNotInBinary { section } => {
+                write!(f, "{section} of the map is not in the binary, ignored")
+            }
+            Warning::SectionAddressMismatch {
+                section,
+                map_vaddr,
+                binary_vaddr,
+            } => write!(
+                f,
+                "{section} is at {} in the map but at {} in the binary, consider a vaddr bias",
+                hex(*map_vaddr),
+                hex(*binary_vaddr)
+            ),
+            Warning::UnalignedRawData {
+                section,
+                pointer_to_raw_data,
+                file_alignment,
+            } => write!(
+                f,
+                "raw data of {section} at {} is not aligned to {}, file offsets may be wrong",
+                hex(*pointer_to_raw_data),
+                hex(*file_alignment)
+            ),
+            Warning::RawDataOutOfFile {
+                section,
+                raw_size,
+                available,
+            } => write!(
+                f,
+                "raw data of {section} ({}) extends past the end of the file, only {} attributed",
+                hex(*raw_size),
+                hex(*available)
+            ),
+            Warning::MalformedMapRegion { line, lines: 1 } => {
+                write!(f, "line {line} of the map could not be parsed, skipped")
+            }
+            Warning::MalformedMapRegion { line, lines } => write!(
+                f,
+                "lines {line} to {} of the map could not be parsed, skipped",
+                line + lines - 1
+            ),
+            Warning::SymbolRuns { section, symbols } => write!(
+                f,
+                "{section} lists no input sections, attributed from runs of its {symbols} \
+                 symbols, sizes are approximate"
+            ),
+        }
+    }
+}
+
+/// Removes the subsections that are not contained in their section, returning a warning for
+/// each.
+///
+/// Sections that could not be placed in the binary are not checked, their addresses are not
+/// meaningful. The input sections of sections not loaded at runtime (`.comment`, `.debug_str`)
+/// are listed with their sizes before merging, so they are cut at the end of their section
+/// instead.
+pub(crate) fn remove_invalid_subsections(sections: &mut [Section]) -> Vec<Warning> {
+    let mut warnings = vec![];
+    for section in sections
+        .iter_mut()
+        .filter(|section| section.start_file_offset.is_some() || section.loaded)
+    {
+        let section_start = section.start_vaddr;
+        let section_end = section_start.saturating_add(section.size);
+        if !section.loaded {
+            for subsection in &mut section.subsections {
+                if (section_start..=section_end).contains(&subsection.start_vaddr) {
+                    subsection.size = subsection.size.min(section_end - subsection.start_vaddr);
+                }
+            }
+        }
+        section.subsections.retain(|subsection| {
+            let contained = subsection.start_vaddr >= section_start
+                && subsection
+                    .start_vaddr
+                    .checked_add(subsection.size)
+                    .is_some_and(|end| end <= section_end);
+            if !contained {
+                warnings.push(Warning::SubsectionOutOfBounds {
+                    section: section.name.clone(),
+                    section_range: (section_start, section_end),
+                    subsection: subsection.name.clone(),
+                    filename: subsection.filename.clone(),
+                    start_vaddr: subsection.start_vaddr,
+                    size: subsection.size,
+                });
+            }
+            contained
+        });
+    }
+    warnings
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Section, SubSection};
+
+    use super::{remove_invalid_subsections, Warning};
+
+    #[test]
+    fn test_remove_invalid_subsections() {
+        let subsection = |start_vaddr, size| SubSection {
+            name: ".text".to_string(),
+            start_vaddr,
+            start_file_offset: None,
+            size,
+            filename: "a.o".to_string(),
+            folded_with: vec![],
+            line: None,
+        };
+        let mut sections = [Section {
+            name: ".text".to_string(),
+            start_vaddr: 0x1000,
+            start_file_offset: Some(0x1000),
+            size: 0x100,
+            loaded: true,
+            subsections: vec![
+                subsection(0x1000, 0x80),
+                subsection(0x1080, 0x80),
+                subsection(0x1080, 0x100),
+                subsection(0x800, 0x10),
+                subsection(0x1100, 0),
+            ],
+            line: None,
+        }];
+
+        let warnings = remove_invalid_subsections(&mut sections);
+        assert_eq!(sections[0].subsections.len(), 3);
+        assert_eq!(warnings.len(), 2);
+        assert!(matches!(
+            warnings[1],
+            Warning::SubsectionOutOfBounds {
+                start_vaddr: 0x800,
+                ..
+            }
+        ));
+        assert!(warnings[0].to_string().contains("outside of .text"));
+
+        // Merged sections are smaller than the input sections they list
+        let mut sections = [Section {
+            name: ".comment".to_string(),
+            start_vaddr: 0,
+            start_file_offset: Some(0x3000),
+            size: 0x2b,
+            loaded: false,
+            subsections: vec![subsection(0, 0x2b), subsection(0x2b, 0x2c)],
+            line: None,
+        }];
+        assert!(remove_invalid_subsections(&mut sections).is_empty());
+        assert_eq!(sections[0].subsections[1].size, 0);
+    }
+}
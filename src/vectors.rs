@@ -0,0 +1,278 @@
+//! The vector table of Cortex-M images: which object file provides the handler of every
+//! exception and interrupt, and which ones are still the default handler of the startup code.
+
+use std::{collections::HashMap, fmt::Display};
+
+use crate::{
+    ctors::read_pointer,
+    format::{hex, Align, Cell, Style, Table},
+    index::Section,
+    Genealogy, GenealogyError,
+};
+
+/// The names of the output sections holding the vector table, as set by the linker scripts of
+/// CMSIS, `cortex-m-rt`, Zephyr and TI.
+const VECTOR_SECTIONS: &[&str] = &[".isr_vector", ".vector_table", ".vectors", ".intvecs"];
+
+/// The names of the default handlers of CMSIS and `cortex-m-rt`.
+const DEFAULT_HANDLERS: &[&str] = &["Default_Handler", "DefaultHandler"];
+
+/// The exceptions of the 16 first entries, `None` for the initial stack pointer and the reserved
+/// ones.
+const EXCEPTIONS: [Option<&str>; 16] = [
+    None,
+    Some("Reset"),
+    Some("NMI"),
+    Some("HardFault"),
+    Some("MemManage"),
+    Some("BusFault"),
+    Some("UsageFault"),
+    None,
+    None,
+    None,
+    None,
+    Some("SVCall"),
+    Some("DebugMonitor"),
+    None,
+    Some("PendSV"),
+    Some("SysTick"),
+];
+
+/// The vector table of an image, see [`Genealogy::vector_table`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VectorTable {
+    pub vaddr: u64,
+    pub initial_sp: u64,
+    /// The non-null entries, in table order.
+    pub vectors: Vec<Vector>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Vector {
+    /// Position in the table, IRQ n is at 16 + n.
+    pub index: usize,
+    /// The exception, or `IRQ<n>`.
+    pub name: String,
+    /// Address of the handler, without the Thumb bit.
+    pub handler: u64,
+    /// Whether the Thumb bit is set, as it must be on Cortex-M.
+    pub thumb: bool,
+    /// The strong symbol at the handler if there is one, any symbol otherwise.
+    pub symbol: Option<String>,
+    pub filename: Option<String>,
+    /// The handler is a default one: named as such, or shared with other entries.
+    pub default: bool,
+    /// A symbol at the handler is weak, the handler was not overridden.
+    pub weak: bool,
+}
+
+impl Vector {
+    /// Whether the entry cannot be right: its handler is in no file or would fault in ARM mode.
+    pub fn is_invalid(&self) -> bool {
+        !self.thumb || self.filename.is_none()
+    }
+}
+
+impl Genealogy {
+    /// Reads the vector table at the start of the image in `binary`, the binary the genealogy
+    /// was built from: the first section named as a vector table, or the first input section of
+    /// the lowest loaded section, in the byte order of the binary. Symbols are only resolved with
+    /// [`GenealogyBuilder::symbols`].
+    ///
+    /// [`GenealogyBuilder::symbols`]: crate::GenealogyBuilder::symbols
+    pub fn vector_table(&self, binary: &[u8]) -> Result<VectorTable, GenealogyError> {
+        let (vaddr, file_offset, size) =
+            self.vector_section().ok_or(GenealogyError::NoVectorTable)?;
+        let bytes = usize::try_from(file_offset)
+            .ok()
+            .and_then(|start| binary.get(start..start.checked_add(size as usize)?))
+            .ok_or(GenealogyError::NoVectorTable)?;
+        // EI_DATA of ELF files, PE files are little-endian
+        let little_endian = !(binary.starts_with(b"\x7fELF") && binary.get(5) == Some(&2));
+        let words: Vec<u64> = bytes
+            .chunks_exact(4)
+            .map(|word| read_pointer(word, little_endian))
+            .collect();
+        let Some((&initial_sp, entries)) = words.split_first() else {
+            return Err(GenealogyError::NoVectorTable);
+        };
+
+        let mut shared: HashMap<u64, usize> = HashMap::new();
+        for &entry in entries.iter().filter(|&&entry| entry != 0) {
+            *shared.entry(entry & !1).or_default() += 1;
+        }
+        let vectors = entries
+            .iter()
+            .enumerate()
+            .map(|(i, &entry)| (i + 1, entry))
+            .filter_map(|(index, entry)| {
+                let name = match EXCEPTIONS.get(index) {
+                    Some(name) => (*name)?.to_string(),
+                    None => format!("IRQ{}", index - EXCEPTIONS.len()),
+                };
+                (entry != 0).then(|| self.vector(index, name, entry, shared[&(entry & !1)]))
+            })
+            .collect();
+        Ok(VectorTable {
+            vaddr,
+            initial_sp,
+            vectors,
+        })
+    }
+
+    fn vector(&self, index: usize, name: String, entry: u64, sharing: usize) -> Vector {
+        let handler = entry & !1;
+        // Thumb functions have the Thumb bit in their symbol value too. ARM mapping symbols
+        // (`$t`, `$d`) mark the start of code and data, they are not handlers.
+        let symbols: Vec<_> = self
+            .symbols()
+            .map(|symbols| {
+                symbols
+                    .at(entry)
+                    .chain(symbols.at(handler))
+                    .filter(|symbol| !symbol.name.starts_with('$'))
+                    .collect()
+            })
+            .unwrap_or_default();
+        let symbol = symbols.first().map(|symbol| symbol.name.clone());
+        let default = sharing > 1
+            || symbols
+                .iter()
+                .any(|symbol| DEFAULT_HANDLERS.contains(&symbol.name.as_str()));
+        Vector {
+            index,
+            name,
+            handler,
+            thumb: entry & 1 == 1,
+            symbol,
+            filename: self
+                .query_vaddr_point(handler)
+                .next()
+                .map(|hit| hit.filename.to_string()),
+            default,
+            weak: symbols.iter().any(|symbol| symbol.weak),
+        }
+    }
+
+    /// The address, file offset and size of the vector table.
+    fn vector_section(&self) -> Option<(u64, u64, u64)> {
+        let loaded = || {
+            self.sections()
+                .iter()
+                .filter(|section| section.loaded && section.size > 0)
+        };
+        if let Some(section) = loaded().find(|section| VECTOR_SECTIONS.contains(&&*section.name)) {
+            return Some((
+                section.start_vaddr,
+                section.start_file_offset?,
+                section.size,
+            ));
+        }
+        let first: &Section = loaded().min_by_key(|section| section.start_vaddr)?;
+        let subsection = first
+            .subsections
+            .iter()
+            .filter(|subsection| subsection.size > 0)
+            .min_by_key(|subsection| subsection.start_vaddr)?;
+        Some((
+            subsection.start_vaddr,
+            subsection.start_file_offset?,
+            subsection.size,
+        ))
+    }
+}
+
+impl VectorTable {
+    /// The table rendered by `Display`. Invalid entries are in bold, default ones dimmed.
+    pub fn to_table(&self) -> Table {
+        let mut table = Table::new(&[
+            Align::Right,
+            Align::Left,
+            Align::Left,
+            Align::Left,
+            Align::Left,
+            Align::Left,
+        ])
+        .with_header(["#", "vector", "handler", "symbol", "flags", "file"]);
+        for vector in &self.vectors {
+            let style = if vector.is_invalid() {
+                Some(Style::Bold)
+            } else if vector.default {
+                Some(Style::Dim)
+            } else {
+                None
+            };
+            let flags: Vec<&str> = [
+                (vector.default, "default"),
+                (vector.weak, "weak"),
+                (!vector.thumb, "arm"),
+                (vector.filename.is_none(), "unresolved"),
+            ]
+            .into_iter()
+            .filter_map(|(set, flag)| set.then_some(flag))
+            .collect();
+            table.push_row(
+                [
+                    vector.index.to_string(),
+                    vector.name.clone(),
+                    hex(vector.handler),
+                    vector.symbol.clone().unwrap_or_else(|| "-".to_string()),
+                    flags.join(","),
+                    vector.filename.clone().unwrap_or_else(|| "-".to_string()),
+                ]
+                .map(|text| Cell::styled(text, style)),
+            );
+        }
+        table
+    }
+}
+
+impl Display for VectorTable {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_table())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Genealogy;
+
+    #[test]
+    fn test_vector_table() {
+        let mapfile = std::fs::read_to_string("tests/cortex-m/out.map").unwrap();
+        let binary = std::fs::read("tests/cortex-m/firmware").unwrap();
+        let genealogy = Genealogy::builder(&mapfile, &binary)
+            .symbols(true)
+            .build()
+            .unwrap();
+
+        let table = genealogy.vector_table(&binary).unwrap();
+        assert_eq!(table.vaddr, 0x0800_0000);
+        assert_eq!(table.initial_sp, 0x2000_4000);
+        let vector = |name: &str| table.vectors.iter().find(|v| v.name == name).unwrap();
+
+        let reset = vector("Reset");
+        assert_eq!(reset.symbol.as_deref(), Some("Reset_Handler"));
+        assert!(reset.filename.as_deref().unwrap().starts_with("firmware."));
+        assert!(!reset.default && !reset.weak && !reset.is_invalid());
+
+        // Overridden by the application, left to the startup code
+        assert!(!vector("SysTick").default);
+        assert!(!vector("IRQ0").weak);
+        let nmi = vector("NMI");
+        assert_eq!(nmi.symbol.as_deref(), Some("Default_Handler"));
+        assert!(nmi.filename.as_deref().unwrap().contains("libstartup.rlib"));
+        assert!(nmi.default && nmi.weak);
+        assert!(vector("IRQ3").default);
+
+        // Reserved and null entries are left out
+        let indices: Vec<_> = table.vectors.iter().map(|v| v.index).collect();
+        assert_eq!(indices, [1, 2, 3, 11, 14, 15, 16, 17, 19]);
+
+        // Without symbols, defaults are still found by the entries they share
+        let genealogy = Genealogy::new(&mapfile, &binary).unwrap();
+        let table = genealogy.vector_table(&binary).unwrap();
+        assert!(table.vectors.iter().all(|v| v.symbol.is_none()));
+        assert_eq!(table.vectors.iter().filter(|v| v.default).count(), 6);
+    }
+}
@@ -0,0 +1,265 @@
+//! The layout of WebAssembly modules, which goblin does not read: the file offsets of their
+//! sections and of the bytes of their data segments, where the maps of `wasm-ld` are placed.
+
+use std::ops::Range;
+
+use crate::GenealogyError;
+
+/// The magic number at the start of every module.
+pub const MAGIC: &[u8; 4] = b"\0asm";
+
+/// The ID of the code section, holding the function bodies.
+pub const CODE_SECTION: u8 = 10;
+/// The ID of the data section, holding the data segments.
+pub const DATA_SECTION: u8 = 11;
+
+/// The names of the sections in `wasm-ld` maps, by ID. Custom sections (ID 0) are named
+/// `CUSTOM(<name>)`.
+const SECTION_NAMES: [&str; 14] = [
+    "CUSTOM",
+    "TYPE",
+    "IMPORT",
+    "FUNCTION",
+    "TABLE",
+    "MEMORY",
+    "GLOBAL",
+    "EXPORT",
+    "START",
+    "ELEM",
+    "CODE",
+    "DATA",
+    "DATACOUNT",
+    "TAG",
+];
+
+/// Whether `binary` is a WebAssembly module.
+pub fn is_wasm(binary: &[u8]) -> bool {
+    binary.starts_with(MAGIC)
+}
+
+/// Whether `name` names a section in `wasm-ld` maps, rather than a data segment.
+pub fn is_section_name(name: &str) -> bool {
+    SECTION_NAMES.contains(&name) || name.starts_with("CUSTOM(")
+}
+
+/// A section of a module.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WasmSection {
+    pub id: u8,
+    /// As in `wasm-ld` maps, e.g. `CODE` or `CUSTOM(.debug_info)`.
+    pub name: String,
+    /// File offset of the section ID.
+    pub offset: u64,
+    /// File offsets of the contents, after the ID and the size. Function bodies are at offsets
+    /// relative to its start in maps and DWARF.
+    pub payload: Range<u64>,
+}
+
+/// A data segment of the data section.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DataSegment {
+    /// The linear memory address of active segments placed by a constant, `None` for passive
+    /// segments and those placed relative to a global, as in position independent modules.
+    pub vaddr: Option<u64>,
+    /// File offsets of the bytes copied to memory.
+    pub data: Range<u64>,
+}
+
+/// The sections of the module `binary`, in file order.
+pub fn wasm_sections(binary: &[u8]) -> Result<Vec<WasmSection>, GenealogyError> {
+    if !is_wasm(binary) {
+        return Err(GenealogyError::UnsupportedBinaryFormat);
+    }
+    let mut reader = Reader {
+        bytes: binary,
+        position: 8,
+    };
+    let mut sections = vec![];
+    while reader.position < binary.len() {
+        let offset = reader.position as u64;
+        let id = reader.byte()?;
+        let size = reader.uleb()?;
+        let start = reader.position;
+        let end = start
+            .checked_add(usize::try_from(size).map_err(|_| malformed("section size"))?)
+            .filter(|&end| end <= binary.len())
+            .ok_or_else(|| malformed("section past the end of the file"))?;
+        let name = match SECTION_NAMES.get(usize::from(id)) {
+            Some(_) if id == 0 => {
+                let len = reader.uleb()? as usize;
+                let name = reader.take(len)?;
+                format!("CUSTOM({})", String::from_utf8_lossy(name))
+            }
+            Some(name) => name.to_string(),
+            None => format!("UNKNOWN({id})"),
+        };
+        sections.push(WasmSection {
+            id,
+            name,
+            offset,
+            payload: start as u64..end as u64,
+        });
+        reader.position = end;
+    }
+    Ok(sections)
+}
+
+/// The segments of the data section `data` of `binary`, in order.
+pub fn data_segments(
+    binary: &[u8],
+    data: &WasmSection,
+) -> Result<Vec<DataSegment>, GenealogyError> {
+    let bytes = binary
+        .get(data.payload.start as usize..data.payload.end as usize)
+        .ok_or_else(|| malformed("data section past the end of the file"))?;
+    let mut reader = Reader { bytes, position: 0 };
+    let count = reader.uleb()?;
+    let mut segments = vec![];
+    for _ in 0..count {
+        let vaddr = match reader.uleb()? {
+            0 => reader.constant_expression()?,
+            1 => None,
+            2 => {
+                reader.uleb()?; // memory index
+                reader.constant_expression()?
+            }
+            _ => return Err(malformed("data segment flags")),
+        };
+        let size = reader.uleb()? as usize;
+        let start = data.payload.start + reader.position as u64;
+        reader.take(size)?;
+        segments.push(DataSegment {
+            vaddr,
+            data: start..start + size as u64,
+        });
+    }
+    Ok(segments)
+}
+
+fn malformed(what: &str) -> GenealogyError {
+    GenealogyError::BinaryParse(goblin::error::Error::Malformed(format!(
+        "WebAssembly module: {what}"
+    )))
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn byte(&mut self) -> Result<u8, GenealogyError> {
+        let byte = *self
+            .bytes
+            .get(self.position)
+            .ok_or_else(|| malformed("unexpected end"))?;
+        self.position += 1;
+        Ok(byte)
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], GenealogyError> {
+        let bytes = self
+            .position
+            .checked_add(len)
+            .and_then(|end| self.bytes.get(self.position..end))
+            .ok_or_else(|| malformed("unexpected end"))?;
+        self.position += len;
+        Ok(bytes)
+    }
+
+    /// An unsigned LEB128 number, and the bits of a signed one.
+    fn uleb(&mut self) -> Result<u64, GenealogyError> {
+        let mut value = 0;
+        for shift in (0..64).step_by(7) {
+            let byte = self.byte()?;
+            value |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(malformed("LEB128 number too long"))
+    }
+
+    fn sleb(&mut self) -> Result<i64, GenealogyError> {
+        let mut value = 0;
+        for shift in (0..64).step_by(7) {
+            let byte = self.byte()?;
+            value |= i64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                if shift + 7 < 64 && byte & 0x40 != 0 {
+                    value |= -1 << (shift + 7);
+                }
+                return Ok(value);
+            }
+        }
+        Err(malformed("LEB128 number too long"))
+    }
+
+    /// The value of a constant expression up to its `end`, `None` unless it is a single
+    /// `i32.const` or `i64.const`. Addresses are 32-bit in the default memory.
+    fn constant_expression(&mut self) -> Result<Option<u64>, GenealogyError> {
+        let mut value = None;
+        let mut instructions = 0;
+        loop {
+            instructions += 1;
+            match self.byte()? {
+                0x0b => break,
+                0x41 => value = Some(u64::from(self.sleb()? as u32)),
+                0x42 => value = Some(self.sleb()? as u64),
+                0x23 => {
+                    self.uleb()?; // global.get
+                }
+                // Arithmetic of the extended constant expressions
+                0x6a..=0x6c | 0x7c..=0x7e => {}
+                _ => return Err(malformed("constant expression")),
+            }
+        }
+        Ok(value.filter(|_| instructions == 2))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Address, Genealogy, GenealogyError};
+
+    use super::{data_segments, wasm_sections, DATA_SECTION};
+
+    #[test]
+    fn test_wasm_module() {
+        let binary = std::fs::read("tests/wasm/out.wasm").unwrap();
+        let sections = wasm_sections(&binary).unwrap();
+        let code = sections.iter().find(|s| s.name == "CODE").unwrap();
+        assert_eq!((code.offset, code.payload.start), (0x54, 0x57));
+        assert!(sections.iter().any(|s| s.name == "CUSTOM(producers)"));
+        let data = sections.iter().find(|s| s.id == DATA_SECTION).unwrap();
+        let segments = data_segments(&binary, data).unwrap();
+        assert_eq!(segments.len(), 1);
+        assert_eq!(segments[0].vaddr, Some(0x10000));
+        assert_eq!(segments[0].data, 0x10c..0x118);
+
+        // Map offsets are those of the contents of the sections, without their header
+        let mapfile = std::fs::read_to_string("tests/wasm/out.map").unwrap();
+        let genealogy = Genealogy::new(&mapfile, &binary).unwrap();
+        assert!(genealogy.warnings().is_empty());
+        let filenames = |offset| {
+            genealogy
+                .query_point(offset)
+                .map(|hit| hit.filename)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(filenames(0x58), ["counter.o"]);
+        assert_eq!(filenames(0x8d), ["parser.o"]);
+        assert_eq!(filenames(0x10c), ["counter.o"]);
+        assert!(filenames(0x57).is_empty());
+
+        // Function bodies by their offset in the code section, data by its address
+        let hit = genealogy.query_vaddr_point(0x36).next().unwrap();
+        assert_eq!((hit.filename, hit.range), ("parser.o", 0x36..0x36 + 0x75));
+        let hit = genealogy.query_vaddr_point(0x10004).next().unwrap();
+        assert_eq!((hit.filename, hit.range), ("counter.o", 0x10000..0x1000c));
+        assert!(matches!(
+            genealogy.query_address(Address::Rva(0)),
+            Err(GenealogyError::NoKeySpace(_))
+        ));
+    }
+}
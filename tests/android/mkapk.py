@@ -0,0 +1,14 @@
+"""Packs the libraries in an APK, uncompressed as with `useLegacyPackaging = false`, and in an
+Android App Bundle, where they are deflated. Neither is signed nor has a real manifest."""
+
+import zipfile
+
+with zipfile.ZipFile("app.apk", "w") as apk:
+    apk.writestr("AndroidManifest.xml", b"\x03\x00\x08\x00")
+    apk.write("libcounter.so", "lib/x86_64/libcounter.so", zipfile.ZIP_STORED)
+    apk.write("libparser.so", "lib/x86_64/libparser.so", zipfile.ZIP_STORED)
+
+with zipfile.ZipFile("app.aab", "w") as aab:
+    aab.writestr("base/manifest/AndroidManifest.xml", b"\x03\x00\x08\x00")
+    aab.write("libcounter.so", "base/lib/x86_64/libcounter.so", zipfile.ZIP_DEFLATED)
+    aab.write("libparser.so", "search/lib/x86_64/libparser.so", zipfile.ZIP_DEFLATED)
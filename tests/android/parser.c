@@ -0,0 +1,15 @@
+static const char *const keywords[] = {"if", "else", "while", "for", "return"};
+
+int is_keyword(const char *word)
+{
+    for (unsigned i = 0; i < sizeof(keywords) / sizeof(keywords[0]); i++) {
+        const char *a = keywords[i], *b = word;
+        while (*a && *a == *b) {
+            a++;
+            b++;
+        }
+        if (!*a && !*b)
+            return 1;
+    }
+    return 0;
+}
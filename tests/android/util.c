@@ -0,0 +1,11 @@
+#include "util.h"
+
+unsigned hash(int key)
+{
+    unsigned h = 2166136261u;
+    for (int i = 0; i < 4; i++) {
+        h ^= (key >> (8 * i)) & 0xff;
+        h *= 16777619u;
+    }
+    return h;
+}
@@ -0,0 +1 @@
+unsigned hash(int key);
@@ -0,0 +1 @@
+int deep(void){return 4;}
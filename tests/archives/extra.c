@@ -0,0 +1 @@
+int extra(void){return 5;}
@@ -0,0 +1,2 @@
+int deep(void);
+int helper(void){return deep();}
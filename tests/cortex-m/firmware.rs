@@ -0,0 +1,28 @@
+#![no_std]
+#![no_main]
+
+extern crate startup;
+
+static mut TICKS: u32 = 0;
+
+#[no_mangle]
+pub extern "C" fn Reset_Handler() -> ! {
+    loop {
+        unsafe { core::ptr::read_volatile(&raw const TICKS) };
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn SysTick_Handler() {
+    unsafe { TICKS = TICKS.wrapping_add(1) };
+}
+
+#[no_mangle]
+pub extern "C" fn UART0_Handler() {
+    unsafe { TICKS = 0 };
+}
+
+#[panic_handler]
+fn panic(_: &core::panic::PanicInfo) -> ! {
+    loop {}
+}
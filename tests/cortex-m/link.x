@@ -0,0 +1,15 @@
+MEMORY
+{
+  FLASH : ORIGIN = 0x08000000, LENGTH = 64K
+  RAM : ORIGIN = 0x20000000, LENGTH = 16K
+}
+ENTRY(Reset_Handler)
+SECTIONS
+{
+  .vector_table ORIGIN(FLASH) : { KEEP(*(.vector_table)) } > FLASH
+  .text : { *(.text .text.*) } > FLASH
+  .rodata : { *(.rodata .rodata.*) } > FLASH
+  .bss (NOLOAD) : { *(.bss .bss.*) } > RAM
+  _stack_top = ORIGIN(RAM) + LENGTH(RAM);
+  /DISCARD/ : { *(.ARM.exidx .ARM.exidx.*) }
+}
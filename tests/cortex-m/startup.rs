@@ -0,0 +1,42 @@
+#![no_std]
+
+core::arch::global_asm!(
+    ".section .vector_table,\"a\",%progbits",
+    ".global __vectors",
+    "__vectors:",
+    ".word _stack_top",
+    ".word Reset_Handler",
+    ".word NMI_Handler",
+    ".word HardFault_Handler",
+    ".word 0, 0, 0, 0, 0, 0, 0",
+    ".word SVC_Handler",
+    ".word 0, 0",
+    ".word PendSV_Handler",
+    ".word SysTick_Handler",
+    ".word UART0_Handler",
+    ".word TIMER0_Handler",
+    ".word 0",
+    ".word GPIO_Handler",
+    ".text",
+    ".thumb",
+    ".thumb_func",
+    ".global Default_Handler",
+    "Default_Handler:",
+    "b Default_Handler",
+    ".weak NMI_Handler",
+    ".thumb_set NMI_Handler, Default_Handler",
+    ".weak HardFault_Handler",
+    ".thumb_set HardFault_Handler, Default_Handler",
+    ".weak SVC_Handler",
+    ".thumb_set SVC_Handler, Default_Handler",
+    ".weak PendSV_Handler",
+    ".thumb_set PendSV_Handler, Default_Handler",
+    ".weak SysTick_Handler",
+    ".thumb_set SysTick_Handler, Default_Handler",
+    ".weak UART0_Handler",
+    ".thumb_set UART0_Handler, Default_Handler",
+    ".weak TIMER0_Handler",
+    ".thumb_set TIMER0_Handler, Default_Handler",
+    ".weak GPIO_Handler",
+    ".thumb_set GPIO_Handler, Default_Handler",
+);
@@ -0,0 +1,5 @@
+int api_version = 3;
+
+int api_open(int flags) { return flags + api_version; }
+
+int api_close(int handle) { return handle - 1; }
@@ -0,0 +1,8 @@
+static int table[64];
+
+/* Meant to be internal, exported since the file is not built with -fvisibility=hidden */
+int impl_lookup(int key) { return table[key & 63]; }
+
+void impl_store(int key, int value) { table[key & 63] = value; }
+
+__attribute__((visibility("hidden"))) int impl_hidden(int key) { return key * 2; }
@@ -0,0 +1,3 @@
+int util_internal(int x) { return x * 3; }
+
+__attribute__((visibility("default"))) int util_public(int x) { return util_internal(x) + 1; }
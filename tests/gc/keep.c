@@ -0,0 +1,5 @@
+int used(int a);
+
+int main(int argc, char **argv) {
+	return used(argc);
+}
@@ -0,0 +1,9 @@
+char unused_buffer[256] = {1};
+
+int used(int a) {
+	return a * 3;
+}
+
+int unused(int a) {
+	return unused_buffer[a] * 5 + a;
+}
@@ -0,0 +1,4 @@
+const char message[] = "hello from the helper";
+int helper_with_a_really_long_function_name_to_wrap(int x) { return x + message[0]; }
+static int unused(int x) { return x * 7; }
+int unused_public(int x) { return unused(x) + 1; }
@@ -0,0 +1,4 @@
+extern int helper_with_a_really_long_function_name_to_wrap(int);
+static int counter;
+int values[16] = {1, 2, 3};
+int main(void) { return helper_with_a_really_long_function_name_to_wrap(counter) + values[1]; }
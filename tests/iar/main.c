@@ -0,0 +1,12 @@
+#include "uart.h"
+
+static const char banner[16] = "IAR ILINK test\n";
+int ticks = 3;
+int rx_count;
+
+int main(void)
+{
+    uart_write(banner, sizeof(banner));
+    ticks += rx_count;
+    return ticks;
+}
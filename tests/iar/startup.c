@@ -0,0 +1,14 @@
+extern int main(void);
+extern char CSTACK$$Limit[];
+
+void reset_handler(void)
+{
+    main();
+    for (;;) {
+    }
+}
+
+__attribute__((section(".intvec"), used)) void *const vector_table[] = {
+    CSTACK$$Limit,
+    (void *)reset_handler,
+};
@@ -0,0 +1,9 @@
+#include "uart.h"
+
+volatile unsigned *uart_data = (volatile unsigned *)0x40011004;
+
+void uart_write(const char *text, unsigned len)
+{
+    for (unsigned i = 0; i < len; i++)
+        *uart_data = text[i];
+}
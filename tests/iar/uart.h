@@ -0,0 +1 @@
+void uart_write(const char *text, unsigned len);
@@ -0,0 +1,9 @@
+/* The sections of the kernel's EXPORT_SYMBOL, __percpu and __init, simplified */
+#define EXPORT_SYMBOL(sym) \
+	extern typeof(sym) sym; \
+	__attribute__((section("___ksymtab+" #sym), used)) \
+	const void *__ksymtab_##sym = &sym
+#define DEFINE_PER_CPU(type, name) \
+	__attribute__((section(".data..percpu"))) type name
+#define __init __attribute__((section(".init.text")))
+#define __initdata __attribute__((section(".init.data")))
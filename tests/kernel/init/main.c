@@ -0,0 +1,14 @@
+#include "../export.h"
+
+extern int fork_setup(void);
+extern void fork_init(void);
+extern void schedule(void);
+
+static char command_line[256] __initdata;
+
+int __init start_kernel(void)
+{
+	fork_init();
+	schedule();
+	return fork_setup() + command_line[0];
+}
@@ -0,0 +1,12 @@
+#include "../export.h"
+
+DEFINE_PER_CPU(unsigned long, process_counts);
+static int max_threads __initdata = 32;
+
+int nr_processes(void) { return (int)process_counts; }
+EXPORT_SYMBOL(nr_processes);
+
+void fork_init(void) { process_counts = 1; }
+EXPORT_SYMBOL(fork_init);
+
+int __init fork_setup(void) { return max_threads; }
@@ -0,0 +1,6 @@
+#include "../export.h"
+
+DEFINE_PER_CPU(long, runqueues[16]);
+
+void schedule(void) { runqueues[0]++; }
+EXPORT_SYMBOL(schedule);
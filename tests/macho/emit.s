@@ -0,0 +1,26 @@
+	.section	__TEXT,__text,regular,pure_instructions
+	.globl	_emit
+	.p2align	4, 0x90
+_emit:
+	xorl	%eax, %eax
+1:
+	cmpb	$0, (%rdi,%rax)
+	je	2f
+	incq	%rax
+	jmp	1b
+2:
+	retq
+
+	.globl	_emit_unused
+	.p2align	4, 0x90
+_emit_unused:
+	xorl	%eax, %eax
+	retq
+
+	.section	__DATA,__data
+	.globl	_emitted
+	.p2align	3
+_emitted:
+	.quad	0
+
+.subsections_via_symbols
@@ -0,0 +1,32 @@
+	.section	__TEXT,__text,regular,pure_instructions
+	.globl	_main
+	.p2align	4, 0x90
+_main:
+	pushq	%rbp
+	movq	%rsp, %rbp
+	leaq	L_.greeting(%rip), %rdi
+	callq	_emit
+	movl	_counter(%rip), %eax
+	addl	_ticks(%rip), %eax
+	popq	%rbp
+	retq
+
+	.globl	_unused_in_main
+	.p2align	4, 0x90
+_unused_in_main:
+	movl	$42, %eax
+	retq
+
+	.section	__TEXT,__cstring,cstring_literals
+L_.greeting:
+	.asciz	"linked by ld64\n"
+
+	.section	__DATA,__data
+	.globl	_counter
+	.p2align	2
+_counter:
+	.long	7
+
+	.zerofill	__DATA,__bss,_ticks,4,2
+
+.subsections_via_symbols
@@ -0,0 +1,8 @@
+#include "led.h"
+
+volatile unsigned *latb = (volatile unsigned *)0xbf886130;
+
+void led_set(unsigned mask)
+{
+    *latb = mask;
+}
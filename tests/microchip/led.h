@@ -0,0 +1 @@
+void led_set(unsigned mask);
@@ -0,0 +1,13 @@
+#include "led.h"
+
+static const char pattern[8] = {1, 3, 7, 15, 7, 3, 1, 0};
+int ticks = 3;
+int blinks;
+
+int main(void)
+{
+    for (unsigned i = 0; i < sizeof(pattern); i++)
+        led_set(pattern[i]);
+    blinks += ticks;
+    return blinks;
+}
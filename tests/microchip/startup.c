@@ -0,0 +1,9 @@
+extern int main(void);
+
+/* The boot flash jumps to the startup code, as crt0.S does for PIC32 */
+__attribute__((section(".reset"), used)) void _reset(void)
+{
+    main();
+    for (;;) {
+    }
+}
@@ -0,0 +1,8 @@
+extern int main(void);
+
+void PowerON_Reset(void)
+{
+    main();
+    for (;;) {
+    }
+}
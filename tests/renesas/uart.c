@@ -0,0 +1,10 @@
+#include "uart.h"
+
+volatile unsigned *uart_data = (volatile unsigned *)0x40002000;
+unsigned uart_errors;
+
+void uart_write(const char *text, unsigned len)
+{
+    for (unsigned i = 0; i < len; i++)
+        *uart_data = text[i];
+}
@@ -0,0 +1,6 @@
+int parse(const char *text);
+int render(int value);
+
+int main(int argc, char **argv) {
+	return render(parse(argv[argc - 1]));
+}
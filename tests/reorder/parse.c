@@ -0,0 +1,17 @@
+int parse_error(const char *text) {
+	return -(text[0] + text[1]);
+}
+
+int parse(const char *text) {
+	int value = 0;
+	for (; *text >= '0' && *text <= '9'; text++)
+		value = value * 10 + *text - '0';
+	return *text ? parse_error(text) : value;
+}
+
+int parse_hex(const char *text) {
+	int value = 0;
+	for (; *text; text++)
+		value = value * 16 + (*text & 0xf);
+	return value;
+}
@@ -0,0 +1,7 @@
+int render_debug(int value) {
+	return value ^ 0x5a5a;
+}
+
+int render(int value) {
+	return value < 0 ? render_debug(value) : value * 3;
+}
@@ -0,0 +1,12 @@
+#include "uart.h"
+
+static const char banner[16] = "SEGGER Linker\n";
+int ticks = 3;
+int rx_count;
+
+int main(void)
+{
+    uart_write_polling_until_the_fifo_drains(banner, sizeof(banner));
+    ticks += rx_count;
+    return ticks;
+}
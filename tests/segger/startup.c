@@ -0,0 +1,13 @@
+extern int main(void);
+
+void Reset_Handler(void)
+{
+    main();
+    for (;;) {
+    }
+}
+
+__attribute__((section(".vectors"), used)) void *const _vectors[] = {
+    (void *)0x20010000,
+    (void *)Reset_Handler,
+};
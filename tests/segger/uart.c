@@ -0,0 +1,9 @@
+#include "uart.h"
+
+volatile unsigned *uart_data = (volatile unsigned *)0x40002000;
+
+void uart_write_polling_until_the_fifo_drains(const char *text, unsigned len)
+{
+    for (unsigned i = 0; i < len; i++)
+        *uart_data = text[i];
+}
@@ -0,0 +1 @@
+void uart_write_polling_until_the_fifo_drains(const char *text, unsigned len);
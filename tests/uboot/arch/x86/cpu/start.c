@@ -0,0 +1,8 @@
+void board_init_f(void);
+
+void _start(void)
+{
+	board_init_f();
+	for (;;)
+		;
+}
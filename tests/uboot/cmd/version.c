@@ -0,0 +1,8 @@
+void serial_puts(const char *s);
+
+static const char version_string[] = "U-Boot 2024.01 (Oct 14 2026)\n";
+
+void do_version(void)
+{
+	serial_puts(version_string);
+}
@@ -0,0 +1,15 @@
+void serial_puts(const char *s);
+void serial_puthex(unsigned long value);
+void do_version(void);
+
+static const char banner[] = "U-Boot 2024.01\n";
+unsigned long gd_relocaddr = 0x1000000;
+unsigned long malloc_base;
+
+void board_init_f(void)
+{
+	serial_puts(banner);
+	serial_puthex(gd_relocaddr);
+	malloc_base = gd_relocaddr - 0x100000;
+	do_version();
+}
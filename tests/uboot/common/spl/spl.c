@@ -0,0 +1,10 @@
+void serial_puts(const char *s);
+
+static char spl_banner[] = "U-Boot SPL\n";
+unsigned long spl_boot_device;
+
+void board_init_f(void)
+{
+	serial_puts(spl_banner);
+	spl_boot_device = 1;
+}
@@ -0,0 +1,20 @@
+#define UART_BASE ((volatile unsigned char *)0x3f8)
+
+static const char hex_digits[] = "0123456789abcdef";
+
+void serial_putc(char c)
+{
+	*UART_BASE = c;
+}
+
+void serial_puts(const char *s)
+{
+	while (*s)
+		serial_putc(*s++);
+}
+
+void serial_puthex(unsigned long value)
+{
+	for (int shift = 60; shift >= 0; shift -= 4)
+		serial_putc(hex_digits[(value >> shift) & 0xf]);
+}
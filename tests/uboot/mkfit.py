@@ -0,0 +1,79 @@
+# Writes u-boot.itb as `mkimage -f u-boot.its u-boot.itb` does, for hosts without mkimage,
+# and u-boot-with-spl.bin, the SPL padded to 512 bytes followed by the FIT image.
+import struct
+
+
+class Fdt:
+    def __init__(self):
+        self.struct = b""
+        self.strings = b""
+
+    def begin(self, name):
+        self.struct += struct.pack(">I", 1) + pad(name.encode() + b"\0")
+
+    def end(self):
+        self.struct += struct.pack(">I", 2)
+
+    def prop(self, name, value):
+        if isinstance(value, str):
+            value = value.encode() + b"\0"
+        elif isinstance(value, int):
+            value = struct.pack(">I", value)
+        name = name.encode() + b"\0"
+        if name not in self.strings:
+            self.strings += name
+        offset = self.strings.index(name)
+        self.struct += struct.pack(">III", 3, len(value), offset) + pad(value)
+
+    def blob(self):
+        self.struct += struct.pack(">I", 9)
+        off_rsvmap = 40
+        off_struct = off_rsvmap + 16
+        off_strings = off_struct + len(self.struct)
+        total = off_strings + len(self.strings)
+        header = struct.pack(
+            ">10I", 0xD00DFEED, total, off_struct, off_strings, off_rsvmap, 17, 16, 0,
+            len(self.strings), len(self.struct),
+        )
+        return header + bytes(16) + self.struct + self.strings
+
+
+def pad(data):
+    return data + bytes(-len(data) % 4)
+
+
+dtb = Fdt()
+dtb.begin("")
+dtb.prop("model", "genealogy test board")
+dtb.end()
+dtb = dtb.blob()
+
+fit = Fdt()
+fit.begin("")
+fit.prop("description", "U-Boot FIT image")
+fit.begin("images")
+fit.begin("uboot")
+fit.prop("description", "U-Boot")
+fit.prop("type", "firmware")
+fit.prop("load", 0x1000000)
+fit.prop("data", open("u-boot-nodtb.bin", "rb").read())
+fit.end()
+fit.begin("fdt-1")
+fit.prop("description", "genealogy-test-board")
+fit.prop("type", "flat_dt")
+fit.prop("data", dtb)
+fit.end()
+fit.end()
+fit.begin("configurations")
+fit.prop("default", "config-1")
+fit.begin("config-1")
+fit.prop("firmware", "uboot")
+fit.prop("fdt", "fdt-1")
+fit.end()
+fit.end()
+fit.end()
+itb = fit.blob()
+open("u-boot.itb", "wb").write(itb)
+
+spl = open("spl/u-boot-spl-nodtb.bin", "rb").read()
+open("u-boot-with-spl.bin", "wb").write(spl + bytes(512 - len(spl)) + itb)
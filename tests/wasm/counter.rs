@@ -0,0 +1,19 @@
+#![no_std]
+
+extern "C" {
+    fn is_keyword(word: *const u8, len: usize) -> bool;
+}
+
+#[no_mangle]
+pub static GREETING: [u8; 12] = *b"hello, wasm\0";
+
+#[no_mangle]
+pub extern "C" fn count_keywords(words: *const u8, len: usize) -> u32 {
+    let mut count = 0;
+    for i in 0..len {
+        if unsafe { is_keyword(words.add(i), 1) } {
+            count += 1;
+        }
+    }
+    count
+}
@@ -0,0 +1,12 @@
+#![no_std]
+
+static KEYWORDS: [&[u8]; 4] = [b"let", b"fn", b"if", b"else"];
+
+#[no_mangle]
+pub extern "C" fn is_keyword(word: *const u8, len: usize) -> bool {
+    let word = unsafe { core::slice::from_raw_parts(word, len) };
+    KEYWORDS.iter().any(|keyword| *keyword == word)
+}
+
+#[no_mangle]
+pub static mut TOKENS: u32 = 7;